@@ -100,6 +100,22 @@ pub(crate) fn static_site_spec(name: &str, listen: &str, status: u16, body: &str
 	}
 }
 
+/// Build the [`RuleSpec`] for a `file_server` document-root rule. With
+/// `spa`, unknown paths fall back to the root `index.html`.
+pub(crate) fn file_server_spec(name: &str, listen: &str, root: &str, spa: bool) -> RuleSpec {
+	let mut args = json!({ "root": root });
+	if spa {
+		args["spa"] = Value::Bool(true);
+	}
+	RuleSpec {
+		name: name.to_owned(),
+		preset: "file_server".to_owned(),
+		listen: vec![listen.to_owned()],
+		args,
+		tls: None,
+	}
+}
+
 /// Validate `spec` against the real preset expander, then write it to
 /// `<config_dir>/rules/<name>.json` as the canonical
 /// `{ "rules": [ <invocation> ] }` shape. Returns the path written.
@@ -201,6 +217,20 @@ mod tests {
 		assert!(!tmp.path().join("rules/bad.json").exists(), "no file on validation failure");
 	}
 
+	#[test]
+	fn file_server_spa_flag_is_written_only_when_set() {
+		let tmp = tempfile::tempdir().expect("tempdir");
+		let path =
+			author_rule(tmp.path(), &file_server_spec("app", ":8080", "/srv/app", true)).expect("author");
+		let written: Value =
+			serde_json::from_str(&fs::read_to_string(&path).expect("read")).expect("parse json");
+		assert_eq!(written["rules"][0]["preset"], "file_server");
+		assert_eq!(written["rules"][0]["args"], json!({ "root": "/srv/app", "spa": true }));
+
+		let plain = file_server_spec("docs", ":8080", "/srv/docs", false);
+		assert_eq!(plain.args, json!({ "root": "/srv/docs" }));
+	}
+
 	#[test]
 	fn force_scaffold_clears_prior_rules() {
 		let tmp = tempfile::tempdir().expect("tempdir");
@@ -20,14 +20,17 @@ use owo_colors::{OwoColorize, Stream, Style};
 use vane_banner::print_banner;
 use vane_core::version::BuildInfo;
 use vane_mgmt::verb::{
-	CgiPoolEntry, CompileDryRunArgs, CompileDryRunResult, ConnectionInfo, ForceRenewArgs,
-	ForceRenewResult, GetCertsResult, GetConfigResult, GetConnectionsResult, GetMetricsArgs,
-	GetMetricsResult, GetPoolsResult, GetUpstreamsResult, ListenerStatus, NoArgs, PingResult,
-	PoolDrainArgs, PoolDrainResult, QuicUpstreamEntry, ReloadResult, ShutdownResult, StatsResult,
-	TcpUpstreamEntry, VERB_COMPILE_DRY_RUN, VERB_FORCE_RENEW, VERB_GET_CERTS, VERB_GET_CONFIG,
-	VERB_GET_CONNECTIONS, VERB_GET_METRICS, VERB_GET_POOLS, VERB_GET_UPSTREAMS, VERB_PING,
-	VERB_POOL_DRAIN, VERB_RELOAD, VERB_SHUTDOWN, VERB_STATS, VERB_TAIL_FLOW, VERB_TAIL_LOG,
-	WasmPoolEntry,
+	BanArgs, BanInfo, BanResult, CacheEntryInfo, CachePurgeArgs, CachePurgeResult, CgiPoolEntry,
+	CompileDryRunArgs, CompileDryRunResult, ConnectionInfo, ForceRenewArgs, ForceRenewResult,
+	GetBansResult, GetCacheArgs, GetCacheResult, GetCertsResult, GetConfigResult,
+	GetConnectionsResult, GetHealthResult, GetMetricsArgs, GetMetricsResult, GetPoolsResult,
+	GetUpstreamsResult, HealthTargetEntry, ListenerStatus, NoArgs, PingResult, PoolDrainArgs,
+	PoolDrainResult, QuicUpstreamEntry, ReloadResult, ShutdownResult, StatsResult, TcpUpstreamEntry,
+	UnbanArgs, UnbanResult, UpgradeResult, UpstreamSetEntry, VERB_BAN, VERB_CACHE_PURGE,
+	VERB_COMPILE_DRY_RUN, VERB_FORCE_RENEW, VERB_GET_BANS, VERB_GET_CACHE, VERB_GET_CERTS,
+	VERB_GET_CONFIG, VERB_GET_CONNECTIONS, VERB_GET_HEALTH, VERB_GET_METRICS, VERB_GET_POOLS,
+	VERB_GET_UPSTREAMS, VERB_PING, VERB_POOL_DRAIN, VERB_RELOAD, VERB_SHUTDOWN, VERB_STATS,
+	VERB_TAIL_FLOW, VERB_TAIL_LOG, VERB_UNBAN, VERB_UPGRADE, WasmPoolEntry,
 };
 use vane_mgmt::{HttpMgmtClient, MgmtClientError, UnixMgmtClient};
 
@@ -174,6 +177,10 @@ enum Cmd {
 	Stats,
 	/// Graceful drain + shutdown.
 	Shutdown,
+	/// Zero-downtime binary upgrade: start the `vaned` now installed at
+	/// the daemon's path and hand it every listening socket. The old
+	/// daemon drains once the new one is serving.
+	Upgrade,
 	/// Reload config (compile + swap).
 	Reload,
 	/// Dry-run compile a config directory; emit the symbolic graph as JSON.
@@ -204,6 +211,27 @@ enum Cmd {
 		#[command(subcommand)]
 		what: PoolCmd,
 	},
+	/// Shared response cache operations.
+	Cache {
+		#[command(subcommand)]
+		what: CacheCmd,
+	},
+	/// Ban an IP or CIDR: new connections from it are dropped at accept.
+	Ban {
+		/// IP address or CIDR, e.g. `203.0.113.0/24`.
+		target: String,
+		/// Ban length in seconds (daemon default: `VANE_SEC_BAN_DURATION`).
+		#[arg(long)]
+		duration: Option<u64>,
+		/// Free-form note shown by `vane get bans`.
+		#[arg(long)]
+		reason: Option<String>,
+	},
+	/// Lift a ban, naming the target exactly as `vane get bans` lists it.
+	Unban {
+		/// IP address or CIDR.
+		target: String,
+	},
 	/// Launch the interactive TUI (default action when `vane` is
 	/// invoked with no subcommand).
 	#[cfg(feature = "tui")]
@@ -222,8 +250,21 @@ enum GetCmd {
 	Pools,
 	/// Cached TCP / TLS / QUIC upstream entries.
 	Upstreams,
+	/// Active upstream health-check targets.
+	Health,
+	/// Shared response cache occupancy and keys.
+	Cache {
+		/// Only list keys starting with this prefix.
+		#[arg(long)]
+		prefix: Option<String>,
+		/// Maximum number of keys to list (daemon default: 100).
+		#[arg(long)]
+		limit: Option<usize>,
+	},
 	/// Tracked managed and static certificates.
 	Certs,
+	/// Banned IPs and prefixes, and the never-banned allow list.
+	Bans,
 }
 
 #[derive(Subcommand, Debug)]
@@ -252,6 +293,19 @@ enum PoolCmd {
 	},
 }
 
+#[derive(Subcommand, Debug)]
+enum CacheCmd {
+	/// Drop cached responses by exact key or by key prefix.
+	Purge {
+		/// Exact key from `vane get cache`.
+		#[arg(long, conflicts_with = "prefix", required_unless_present = "prefix")]
+		key: Option<String>,
+		/// Every key starting with this, e.g. `example.com/static/`.
+		#[arg(long)]
+		prefix: Option<String>,
+	},
+}
+
 #[derive(Subcommand, Debug)]
 enum AddCmd {
 	/// L4 TCP/UDP byte forward (no HTTP layer).
@@ -323,6 +377,33 @@ enum AddCmd {
 		#[arg(long)]
 		sni: Option<String>,
 	},
+	/// Serve files from a local directory.
+	FileServer {
+		/// Config directory to write into. Default: ./vane-dev.
+		#[arg(short = 'C', long = "dir", default_value = "vane-dev")]
+		dir: PathBuf,
+		/// Rule name → `rules/<name>.json`.
+		#[arg(long, default_value = "files")]
+		name: String,
+		/// Listen address, e.g. `127.0.0.1:8080`.
+		#[arg(long)]
+		listen: String,
+		/// Document root to serve.
+		#[arg(long)]
+		root: PathBuf,
+		/// Single-page app: unknown paths fall back to `/index.html`.
+		#[arg(long)]
+		spa: bool,
+		/// TLS cert chain PEM path — enables HTTPS termination (needs --key).
+		#[arg(long)]
+		cert: Option<PathBuf>,
+		/// TLS private key PEM path (needs --cert).
+		#[arg(long)]
+		key: Option<PathBuf>,
+		/// SNI host this cert serves (default: the listener's default cert).
+		#[arg(long)]
+		sni: Option<String>,
+	},
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -387,6 +468,7 @@ async fn main() -> std::process::ExitCode {
 		Cmd::Ping => run_ping(&client, cli.json).await,
 		Cmd::Stats => run_stats(&client, cli.json).await,
 		Cmd::Shutdown => run_shutdown(&client, cli.json).await,
+		Cmd::Upgrade => run_upgrade(&client, cli.json).await,
 		Cmd::Reload => run_reload(&client, cli.json).await,
 		Cmd::Compile { config_dir, .. } => run_compile_dry_run(&client, &config_dir).await,
 		Cmd::Get { what: GetCmd::Config } => run_get_config(&client).await,
@@ -394,13 +476,25 @@ async fn main() -> std::process::ExitCode {
 		Cmd::Get { what: GetCmd::Metrics } => run_get_metrics(&client, cli.json).await,
 		Cmd::Get { what: GetCmd::Pools } => run_get_pools(&client, cli.json).await,
 		Cmd::Get { what: GetCmd::Upstreams } => run_get_upstreams(&client, cli.json).await,
+		Cmd::Get { what: GetCmd::Health } => run_get_health(&client, cli.json).await,
+		Cmd::Get { what: GetCmd::Cache { prefix, limit } } => {
+			run_get_cache(&client, GetCacheArgs { prefix, limit }, cli.json).await
+		}
 		Cmd::Get { what: GetCmd::Certs } => run_get_certs(&client, cli.json).await,
+		Cmd::Get { what: GetCmd::Bans } => run_get_bans(&client, cli.json).await,
 		Cmd::Tail { what: TailCmd::Flow } => run_tail_flow(&client, cli.json).await,
 		Cmd::Tail { what: TailCmd::Log } => run_tail_log(&client, cli.json).await,
 		Cmd::Cert { what: CertCmd::Renew { sni } } => run_cert_renew(&client, &sni, cli.json).await,
 		Cmd::Pool { what: PoolCmd::Drain { fingerprint_id } } => {
 			run_pool_drain(&client, &fingerprint_id, cli.json).await
 		}
+		Cmd::Cache { what: CacheCmd::Purge { key, prefix } } => {
+			run_cache_purge(&client, CachePurgeArgs { key, prefix }, cli.json).await
+		}
+		Cmd::Ban { target, duration, reason } => {
+			run_ban(&client, BanArgs { target, duration_secs: duration, reason }, cli.json).await
+		}
+		Cmd::Unban { target } => run_unban(&client, UnbanArgs { target }, cli.json).await,
 		#[cfg(feature = "tui")]
 		Cmd::Tui => tui::run(&BUILD_INFO),
 	};
@@ -488,27 +582,33 @@ fn run_init(dir: &Path, force: bool) -> anyhow::Result<()> {
 
 /// `vane add <preset>` — author a rule file non-interactively.
 fn run_add(what: &AddCmd) -> anyhow::Result<()> {
-	let (dir, spec) = match what {
-		AddCmd::PortForward { dir, name, listen, upstream, transport } => {
-			(dir, authoring::port_forward_spec(name, listen, upstream, transport))
-		}
-		AddCmd::ReverseProxy { dir, name, listen, upstream, cert, key, sni } => (
-			dir,
-			authoring::reverse_proxy_spec(name, listen, upstream).with_tls(tls_args(
-				cert.as_ref(),
-				key.as_ref(),
-				sni.as_ref(),
-			)?),
-		),
-		AddCmd::StaticSite { dir, name, listen, status, body, cert, key, sni } => (
-			dir,
-			authoring::static_site_spec(name, listen, *status, body).with_tls(tls_args(
-				cert.as_ref(),
-				key.as_ref(),
-				sni.as_ref(),
-			)?),
-		),
-	};
+	let (dir, spec) =
+		match what {
+			AddCmd::PortForward { dir, name, listen, upstream, transport } => {
+				(dir, authoring::port_forward_spec(name, listen, upstream, transport))
+			}
+			AddCmd::ReverseProxy { dir, name, listen, upstream, cert, key, sni } => (
+				dir,
+				authoring::reverse_proxy_spec(name, listen, upstream).with_tls(tls_args(
+					cert.as_ref(),
+					key.as_ref(),
+					sni.as_ref(),
+				)?),
+			),
+			AddCmd::StaticSite { dir, name, listen, status, body, cert, key, sni } => (
+				dir,
+				authoring::static_site_spec(name, listen, *status, body).with_tls(tls_args(
+					cert.as_ref(),
+					key.as_ref(),
+					sni.as_ref(),
+				)?),
+			),
+			AddCmd::FileServer { dir, name, listen, root, spa, cert, key, sni } => (
+				dir,
+				authoring::file_server_spec(name, listen, &root.to_string_lossy(), *spa)
+					.with_tls(tls_args(cert.as_ref(), key.as_ref(), sni.as_ref())?),
+			),
+		};
 	let path = authoring::author_rule(dir, &spec)?;
 	println!("wrote {}", path.display());
 	println!("start: vaned -c {}", dir.display());
@@ -569,6 +669,19 @@ async fn run_shutdown(client: &MgmtTransport, json: bool) -> anyhow::Result<()>
 	Ok(())
 }
 
+async fn run_upgrade(client: &MgmtTransport, json: bool) -> anyhow::Result<()> {
+	let r: UpgradeResult = client.call(VERB_UPGRADE, &NoArgs {}).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		println!(
+			"upgrade started — new vaned pid {}; the old daemon drains once it is serving",
+			r.child_pid
+		);
+	}
+	Ok(())
+}
+
 async fn run_get_config(client: &MgmtTransport) -> anyhow::Result<()> {
 	let r: GetConfigResult = client.call(VERB_GET_CONFIG, &NoArgs {}).await?;
 	// Always JSON — the symbolic graph has no sensible tabular form.
@@ -683,6 +796,88 @@ async fn run_get_upstreams(client: &MgmtTransport, json: bool) -> anyhow::Result
 		print_tcp_upstream_rows(&r.tcp);
 		print_section("quic:");
 		print_quic_upstream_rows(&r.quic);
+		print_section("sets:");
+		print_upstream_set_rows(&r.sets);
+	}
+	Ok(())
+}
+
+async fn run_get_health(client: &MgmtTransport, json: bool) -> anyhow::Result<()> {
+	let r: GetHealthResult = client.call(VERB_GET_HEALTH, &NoArgs {}).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		print_section("targets:");
+		print_health_target_rows(&r.targets);
+	}
+	Ok(())
+}
+
+async fn run_get_cache(
+	client: &MgmtTransport,
+	args: GetCacheArgs,
+	json: bool,
+) -> anyhow::Result<()> {
+	let r: GetCacheResult = client.call(VERB_GET_CACHE, &args).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		println!(
+			"used: {} / {} bytes  keys: {} (showing {} of {})",
+			r.used_bytes,
+			r.capacity_bytes,
+			r.keys,
+			r.entries.len(),
+			r.matched_keys,
+		);
+		print_section("entries:");
+		print_cache_entry_rows(&r.entries);
+	}
+	Ok(())
+}
+
+async fn run_cache_purge(
+	client: &MgmtTransport,
+	args: CachePurgeArgs,
+	json: bool,
+) -> anyhow::Result<()> {
+	let r: CachePurgeResult = client.call(VERB_CACHE_PURGE, &args).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		println!("purged: {}", r.purged);
+	}
+	Ok(())
+}
+
+async fn run_get_bans(client: &MgmtTransport, json: bool) -> anyhow::Result<()> {
+	let r: GetBansResult = client.call(VERB_GET_BANS, &NoArgs {}).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		println!("allow: {}", if r.allow.is_empty() { "-".to_owned() } else { r.allow.join(", ") });
+		print_section("bans:");
+		print_ban_rows(&r.bans);
+	}
+	Ok(())
+}
+
+async fn run_ban(client: &MgmtTransport, args: BanArgs, json: bool) -> anyhow::Result<()> {
+	let r: BanResult = client.call(VERB_BAN, &args).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		print_ban_rows(std::slice::from_ref(&r.ban));
+	}
+	Ok(())
+}
+
+async fn run_unban(client: &MgmtTransport, args: UnbanArgs, json: bool) -> anyhow::Result<()> {
+	let r: UnbanResult = client.call(VERB_UNBAN, &args).await?;
+	if json {
+		print_json(&r)?;
+	} else {
+		println!("removed: {}", r.removed);
 	}
 	Ok(())
 }
@@ -934,6 +1129,91 @@ fn print_quic_upstream_rows(rows: &[QuicUpstreamEntry]) {
 	}
 }
 
+fn print_upstream_set_rows(rows: &[UpstreamSetEntry]) {
+	if rows.is_empty() {
+		print_none_row();
+		return;
+	}
+	for set in rows {
+		println!("  {id}  policy={policy}", id = set.set_id, policy = set.policy);
+		let max_authority = set.members.iter().map(|m| m.authority.len()).max().unwrap_or(0);
+		for m in &set.members {
+			println!(
+				"    {authority:<aw$}  weight={weight} in_flight={in_flight} selected={selected}{health}",
+				authority = m.authority,
+				aw = max_authority,
+				weight = m.weight,
+				in_flight = m.in_flight,
+				selected = m.selected,
+				health = m.health.as_deref().map(|h| format!(" health={h}")).unwrap_or_default(),
+			);
+		}
+	}
+}
+
+fn print_health_target_rows(rows: &[HealthTargetEntry]) {
+	if rows.is_empty() {
+		print_none_row();
+		return;
+	}
+	let max_authority = rows.iter().map(|r| r.authority.len()).max().unwrap_or(0);
+	for row in rows {
+		println!(
+			"  {id}  {authority:<aw$}  {kind:<4} {status:<9}  ok={ok} fail={fail} checked={checked}{error}",
+			id = row.target_id,
+			authority = row.authority,
+			aw = max_authority,
+			kind = row.kind,
+			status = row.status,
+			ok = row.consecutive_successes,
+			fail = row.consecutive_failures,
+			checked = row.last_check_age_ms.map_or_else(|| "-".to_owned(), format_age_ms),
+			error = row.last_error.as_deref().map(|e| format!("  error={e}")).unwrap_or_default(),
+		);
+	}
+}
+
+fn print_cache_entry_rows(rows: &[CacheEntryInfo]) {
+	if rows.is_empty() {
+		print_none_row();
+		return;
+	}
+	let max_key = rows.iter().map(|r| r.key.len()).max().unwrap_or(0);
+	for row in rows {
+		println!(
+			"  {key:<kw$}  {status}  variants={variants} bytes={bytes} age={age}s ttl={ttl}s hits={hits}",
+			key = row.key,
+			kw = max_key,
+			status = row.status,
+			variants = row.variants,
+			bytes = row.bytes,
+			age = row.age_secs,
+			ttl = row.ttl_secs,
+			hits = row.hits,
+		);
+	}
+}
+
+fn print_ban_rows(rows: &[BanInfo]) {
+	if rows.is_empty() {
+		print_none_row();
+		return;
+	}
+	let max_target = rows.iter().map(|r| r.target.len()).max().unwrap_or(0);
+	for row in rows {
+		println!(
+			"  {target:<tw$}  {source:<6} left={left}s level={level}{reason}",
+			target = row.target,
+			tw = max_target,
+			source = row.source,
+			left = row.remaining_secs,
+			level = row.level,
+			reason =
+				if row.reason.is_empty() { String::new() } else { format!("  reason={}", row.reason) },
+		);
+	}
+}
+
 fn print_connection_rows(rows: &[ConnectionInfo]) {
 	if rows.is_empty() {
 		print_none_row();
@@ -34,6 +34,7 @@ pub(crate) fn run() -> anyhow::Result<()> {
 		.item("port_forward", "Port forward (L4)", "raw TCP/UDP byte forward")
 		.item("reverse_proxy", "Reverse proxy (HTTP)", "forward HTTP to an upstream")
 		.item("static_site", "Static response", "return a fixed response")
+		.item("file_server", "File server", "serve a local directory")
 		.interact()?;
 
 	let name: String =
@@ -60,6 +61,15 @@ pub(crate) fn run() -> anyhow::Result<()> {
 			let body: String = cliclack::input("Body").default_input("hello from vane").interact()?;
 			authoring::static_site_spec(&name, &listen, status, &body).with_tls(prompt_tls()?)
 		}
+		"file_server" => {
+			let root: String = cliclack::input("Document root").placeholder("/srv/www").interact()?;
+			let spa: bool = cliclack::confirm("Single-page app (fall back to /index.html)?")
+				.initial_value(false)
+				.interact()?;
+			let root = expand_tilde(&root);
+			authoring::file_server_spec(&name, &listen, &root.to_string_lossy(), spa)
+				.with_tls(prompt_tls()?)
+		}
 		other => anyhow::bail!("unhandled feature {other:?}"),
 	};
 
@@ -94,6 +104,7 @@ fn default_name(feature: &str) -> &'static str {
 		"port_forward" => "fwd",
 		"reverse_proxy" => "proxy",
 		"static_site" => "site",
+		"file_server" => "files",
 		_ => "rule",
 	}
 }
@@ -1,4 +1,6 @@
+use std::any::Any;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
@@ -10,6 +12,99 @@ use crate::error::Error;
 pub type Request = http::Request<Body>;
 pub type Response = http::Response<Body>;
 
+/// Request head carried across the fetch boundary. The fetch consumes
+/// the [`Request`], so the executor copies its head into the response's
+/// extensions when the graph has response-phase middleware; those read
+/// it back to negotiate on request headers (`Accept-Encoding` et al.).
+#[derive(Clone, Debug)]
+pub struct RequestHead {
+	pub method: http::Method,
+	pub uri: http::Uri,
+	pub version: http::Version,
+	pub headers: http::HeaderMap,
+}
+
+impl RequestHead {
+	#[must_use]
+	pub fn of(req: &Request) -> Self {
+		Self {
+			method: req.method().clone(),
+			uri: req.uri().clone(),
+			version: req.version(),
+			headers: req.headers().clone(),
+		}
+	}
+}
+
+/// Values a request-phase middleware needs held until the upstream
+/// response has been fully streamed — a `concurrency_limit` permit,
+/// for instance. Stashed in the request's extensions via
+/// [`StreamGuards::push`]; the executor takes them off the request at
+/// the L7 fetch and [`StreamGuards::attach`]es them to the response
+/// body. A fetch error, a short-circuit or a non-streaming body drops
+/// them with the request, since the upstream is done by then.
+#[derive(Clone, Default)]
+pub struct StreamGuards(Vec<Arc<dyn Any + Send + Sync>>);
+
+impl StreamGuards {
+	/// A set holding just `guard`, for a fetch that ties its own state
+	/// to the response body.
+	#[must_use]
+	pub fn of(guard: impl Any + Send + Sync) -> Self {
+		Self(vec![Arc::new(guard)])
+	}
+
+	/// Add `guard` to the request's set.
+	pub fn push(req: &mut Request, guard: impl Any + Send + Sync) {
+		req.extensions_mut().get_or_insert_default::<Self>().0.push(Arc::new(guard));
+	}
+
+	/// Tie the guards to `body`: dropped at end of stream, on a body
+	/// error, or when the body itself is dropped (client went away).
+	/// `Static` / `Empty` bodies are returned as-is and the guards drop
+	/// here.
+	#[must_use]
+	pub fn attach(self, body: Body) -> Body {
+		match body {
+			Body::Stream(inner) if !self.0.is_empty() => {
+				Body::Stream(Box::pin(GuardedBody { inner, guards: Some(self) }))
+			}
+			other => other,
+		}
+	}
+}
+
+/// Stream wrapper behind [`StreamGuards::attach`].
+struct GuardedBody {
+	inner: Pin<Box<dyn HttpBody<Data = Bytes, Error = Error> + Send + 'static>>,
+	guards: Option<StreamGuards>,
+}
+
+impl HttpBody for GuardedBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let this = self.get_mut();
+		let polled = this.inner.as_mut().poll_frame(cx);
+		if matches!(polled, Poll::Ready(None | Some(Err(_)))) || this.inner.is_end_stream() {
+			this.guards = None;
+		}
+		polled
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
 pub enum Body {
 	Static(Bytes),
 	Empty,
@@ -335,4 +430,41 @@ mod tests {
 			other => panic!("expected converted Compile error, got {other:?}"),
 		}
 	}
+
+	#[test]
+	fn stream_guards_drop_at_end_of_stream_not_before() {
+		let guard = Arc::new(());
+		let mut req = http::Request::new(Body::Empty);
+		StreamGuards::push(&mut req, Arc::clone(&guard));
+		let guards = req.extensions_mut().remove::<StreamGuards>().expect("pushed");
+		drop(req);
+
+		let producer: ScriptedBody<Error> =
+			ScriptedBody::new(vec![Step::Data(Bytes::from_static(b"a")), Step::End]);
+		let mut body = guards.attach(Body::from_producer(producer));
+		assert_eq!(Arc::strong_count(&guard), 2, "held by the body");
+		assert!(matches!(poll_once(&mut body), Poll::Ready(Some(Ok(_)))));
+		assert_eq!(Arc::strong_count(&guard), 2, "still streaming");
+		assert!(matches!(poll_once(&mut body), Poll::Ready(None)));
+		assert_eq!(Arc::strong_count(&guard), 1, "released at end of stream");
+	}
+
+	#[test]
+	fn stream_guards_drop_with_static_bodies_and_dropped_streams() {
+		let guard = Arc::new(());
+		let mut req = http::Request::new(Body::Empty);
+		StreamGuards::push(&mut req, Arc::clone(&guard));
+		let guards = req.extensions().get::<StreamGuards>().cloned().expect("pushed");
+		drop(req);
+		let body = guards.attach(Body::Static(Bytes::from_static(b"x")));
+		assert!(body.as_static().is_some());
+		assert_eq!(Arc::strong_count(&guard), 1);
+
+		let guards = StreamGuards(vec![Arc::clone(&guard) as Arc<dyn Any + Send + Sync>]);
+		let producer: ScriptedBody<Error> = ScriptedBody::new(vec![Step::End]);
+		let body = guards.attach(Body::from_producer(producer));
+		assert_eq!(Arc::strong_count(&guard), 2);
+		drop(body);
+		assert_eq!(Arc::strong_count(&guard), 1, "client went away");
+	}
 }
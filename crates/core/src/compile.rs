@@ -146,7 +146,8 @@ mod tests {
 	}
 
 	fn _unused_mentions() {
-		let _ = TerminateSpec { kind: FetchKind::HttpProxy, args: serde_json::Value::Null };
+		let _ =
+			TerminateSpec { kind: FetchKind::HttpProxy, args: serde_json::Value::Null, fallback: None };
 	}
 
 	#[test]
@@ -339,6 +340,7 @@ mod tests {
 	#[test]
 	#[allow(
 		clippy::cognitive_complexity,
+		clippy::too_many_lines,
 		reason = "exhaustive Node-variant round-trip assertion: complexity grows with the number of variants, not with logic. Splitting to a per-variant helper just renames the variant-tag dispatch"
 	)]
 	fn symbolic_flow_graph_round_trip_preserves_structure_and_revalidates() {
@@ -436,6 +438,8 @@ mod tests {
 						id: ia,
 						next_response: ra,
 						next_tunnel: ta,
+						on_error: ea,
+						on_error_status: sa,
 						collect_body_before: ca,
 						body_limit: la,
 					},
@@ -443,6 +447,8 @@ mod tests {
 						id: ib,
 						next_response: rb,
 						next_tunnel: tb,
+						on_error: eb,
+						on_error_status: sb,
 						collect_body_before: cb,
 						body_limit: lb,
 					},
@@ -450,6 +456,8 @@ mod tests {
 					assert_eq!(ia, ib, "node[{i}] Fetch id");
 					assert_eq!(ra, rb, "node[{i}] Fetch next_response");
 					assert_eq!(ta, tb, "node[{i}] Fetch next_tunnel");
+					assert_eq!(ea, eb, "node[{i}] Fetch on_error");
+					assert_eq!(sa, sb, "node[{i}] Fetch on_error_status");
 					assert_eq!(ca, cb, "node[{i}] Fetch collect_body_before");
 					assert_eq!(la, lb, "node[{i}] Fetch body_limit");
 				}
@@ -1102,6 +1110,184 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn proxy_protocol_aggregates_per_listener() {
+		let pp = serde_json::json!({ "trusted": ["10.0.0.0/8"] });
+		let a = parse_rule(serde_json::json!({
+			"name": "a",
+			"listen": [":7820"],
+			"match": { "http.header.host": { "equals": "a.example.com" } },
+			"proxy_protocol": pp,
+			"terminate": { "type": "http_proxy", "upstream": "127.0.0.1:8080" },
+		}));
+		let b = parse_rule(serde_json::json!({
+			"name": "b",
+			"listen": [":7820"],
+			"proxy_protocol": pp,
+			"terminate": { "type": "http_proxy", "upstream": "127.0.0.1:8081" },
+		}));
+		let plain = parse_rule(serde_json::json!({
+			"name": "plain",
+			"listen": [":7821"],
+			"terminate": { "type": "tcp_forward", "upstream": "10.0.0.5:22" },
+		}));
+		let graph = compile(vec![rule_file("a.json", vec![a, b, plain])], &Providers, &Providers)
+			.expect("compile");
+		let v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 7820);
+		let v6 = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 7820);
+		let cfg =
+			graph.meta.listener_proxy_protocol.get(&v4).expect("v4 listener carries proxy_protocol");
+		assert_eq!(graph.meta.listener_proxy_protocol.get(&v6), Some(cfg));
+		assert!(cfg.trusts(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3))));
+		assert!(cfg.trusts("::ffff:10.1.2.3".parse().expect("mapped v6")));
+		assert!(!cfg.trusts(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+		let plain_v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 7821);
+		assert!(!graph.meta.listener_proxy_protocol.contains_key(&plain_v4));
+	}
+
+	#[test]
+	fn proxy_protocol_rejects_disagreement_udp_and_empty_trust() {
+		let with = parse_rule(serde_json::json!({
+			"name": "with",
+			"listen": [":7822"],
+			"match": { "http.header.host": { "equals": "a.example.com" } },
+			"proxy_protocol": { "trusted": ["10.0.0.0/8"] },
+			"terminate": { "type": "http_proxy" },
+		}));
+		let without = parse_rule(serde_json::json!({
+			"name": "without",
+			"listen": [":7822"],
+			"terminate": { "type": "http_proxy" },
+		}));
+		let err = compile(vec![rule_file("a.json", vec![with, without])], &Providers, &Providers)
+			.expect_err("mixed proxy_protocol must fail");
+		assert!(err.to_string().contains("disagree on `proxy_protocol`"), "got: {err}");
+
+		let udp = parse_rule(serde_json::json!({
+			"name": "udp",
+			"listen": ["udp:7823"],
+			"proxy_protocol": { "trusted": ["10.0.0.0/8"] },
+			"terminate": { "type": "http_proxy" },
+		}));
+		let err = compile(vec![rule_file("a.json", vec![udp])], &Providers, &Providers)
+			.expect_err("udp proxy_protocol must fail");
+		assert!(err.to_string().contains("TCP-only"), "got: {err}");
+
+		let empty = parse_rule(serde_json::json!({
+			"name": "empty",
+			"listen": [":7824"],
+			"proxy_protocol": { "trusted": [] },
+			"terminate": { "type": "http_proxy" },
+		}));
+		let err = compile(vec![rule_file("a.json", vec![empty])], &Providers, &Providers)
+			.expect_err("empty trusted must fail");
+		assert!(err.to_string().contains("at least one CIDR"), "got: {err}");
+	}
+
+	#[test]
+	fn unix_listener_lowers_by_path_with_socket_config() {
+		let a = parse_rule(serde_json::json!({
+			"name": "a",
+			"listen": ["unix:/run/vaned/web.sock"],
+			"match": { "remote.uid": { "equals": 1000 } },
+			"unix_socket": { "mode": "0660", "group": 33 },
+			"terminate": { "type": "http_proxy", "upstream": "127.0.0.1:8080" },
+		}));
+		let b = parse_rule(serde_json::json!({
+			"name": "b",
+			"listen": ["unix:/run/vaned/web.sock"],
+			"unix_socket": { "mode": "0660", "group": 33 },
+			"terminate": { "type": "http_proxy", "upstream": "127.0.0.1:8081" },
+		}));
+		let raw = parse_rule(serde_json::json!({
+			"name": "raw",
+			"listen": ["unix:/run/vaned/ssh.sock"],
+			"terminate": { "type": "tcp_forward", "upstream": "10.0.0.5:22" },
+		}));
+		let graph =
+			compile(vec![rule_file("a.json", vec![a, b, raw])], &Providers, &Providers).expect("compile");
+		assert!(graph.entries.is_empty(), "unix listeners add no network entries");
+
+		let web = graph
+			.meta
+			.unix_listeners
+			.get(std::path::Path::new("/run/vaned/web.sock"))
+			.expect("web listener");
+		assert_eq!(web.kind, crate::ir::ListenerKind::Http);
+		assert_eq!(web.socket.mode, Some(0o660));
+		assert_eq!(web.socket.group, Some(33));
+		assert!(web.socket.remove_stale, "remove_stale defaults on");
+
+		let ssh = graph
+			.meta
+			.unix_listeners
+			.get(std::path::Path::new("/run/vaned/ssh.sock"))
+			.expect("ssh listener");
+		assert_eq!(ssh.kind, crate::ir::ListenerKind::Raw);
+		assert_eq!(ssh.socket, crate::rule::UnixSocketConfig::default());
+	}
+
+	#[test]
+	fn unix_listener_rejections() {
+		let cases = [
+			(
+				serde_json::json!({
+					"name": "relative",
+					"listen": ["unix:run/web.sock"],
+					"terminate": { "type": "http_proxy" },
+				}),
+				"must be absolute",
+			),
+			(
+				serde_json::json!({
+					"name": "mixed",
+					"listen": ["unix:/run/web.sock", ":7830"],
+					"terminate": { "type": "http_proxy" },
+				}),
+				"mixes `unix:` paths and network addresses",
+			),
+			(
+				serde_json::json!({
+					"name": "pp",
+					"listen": ["unix:/run/web.sock"],
+					"proxy_protocol": { "trusted": ["10.0.0.0/8"] },
+					"terminate": { "type": "http_proxy" },
+				}),
+				"not supported on unix sockets",
+			),
+			(
+				serde_json::json!({
+					"name": "inet",
+					"listen": [":7831"],
+					"unix_socket": { "mode": "0600" },
+					"terminate": { "type": "http_proxy" },
+				}),
+				"applies only to `unix:` listeners",
+			),
+		];
+		for (rule, want) in cases {
+			let err = compile(vec![rule_file("a.json", vec![parse_rule(rule)])], &Providers, &Providers)
+				.expect_err("rule must be rejected");
+			assert!(err.to_string().contains(want), "want {want:?}, got: {err}");
+		}
+
+		let long = format!("unix:/{}", "s".repeat(120));
+		let rule = parse_rule(serde_json::json!({
+			"name": "long",
+			"listen": [long],
+			"terminate": { "type": "http_proxy" },
+		}));
+		let err = compile(vec![rule_file("a.json", vec![rule])], &Providers, &Providers)
+			.expect_err("over-long path must fail");
+		assert!(err.to_string().contains("the limit is 107"), "got: {err}");
+
+		let bad_mode = serde_json::from_value::<crate::rule::UnixSocketConfig>(
+			serde_json::json!({ "mode": "660a" }),
+		)
+		.expect_err("non-octal mode must fail");
+		assert!(bad_mode.to_string().contains("octal permission mode"), "got: {bad_mode}");
+	}
+
 	#[test]
 	fn lower_derives_auto_when_l4_and_l7_share_listener() {
 		// `analyze` currently rejects a single rule set with mixed L4 + L7
@@ -1127,6 +1313,8 @@ mod tests {
 				id: FetchId::new(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::new(3)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -1134,24 +1322,16 @@ mod tests {
 				id: FetchId::new(1),
 				next_response: Some(NodeId::new(3)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
 			Node::Terminate(TerminatorId::new(0)),
 		];
 		let fetches = vec![
-			SymbolicFetchRef {
-				kind: FetchKind::L4Forward,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			},
-			SymbolicFetchRef {
-				kind: FetchKind::HttpProxy,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			},
+			SymbolicFetchRef::new(FetchKind::L4Forward, serde_json::Value::Null),
+			SymbolicFetchRef::new(FetchKind::HttpProxy, serde_json::Value::Null),
 		];
 		assert_eq!(derive_listener_kind_for_test(&nodes, &fetches, NodeId::new(0)), ListenerKind::Auto);
 	}
@@ -1259,6 +1439,120 @@ mod tests {
 		assert!(!any_fetch_collects, "max_attempts=1 disables the force-buffering trigger");
 	}
 
+	#[test]
+	fn lower_hedge_with_force_triggers_collect_at_one_attempt() {
+		// A hedge sends the body twice even without retry, so `force`
+		// buffers it at `max_attempts: 1` too.
+		let r = parse_rule(serde_json::json!({
+			"name": "r",
+			"listen": [":7903"],
+			"terminate": {
+				"type": "http_proxy",
+				"upstream": "127.0.0.1:8080",
+				"retry": { "buffering": "force", "hedge": { "delay": "50ms" } },
+			},
+		}));
+		let graph =
+			compile(vec![rule_file("a.json", vec![r])], &Providers, &Providers).expect("compile");
+		let any_fetch_collects = graph.nodes.iter().any(|n| {
+			matches!(
+				n,
+				crate::ir::Node::Fetch { collect_body_before: Some(crate::ir::BodySide::Request), .. },
+			)
+		});
+		assert!(any_fetch_collects, "hedge + force buffering must flag fetch with collect_body_before");
+	}
+
+	#[test]
+	fn lower_fetch_fallback_chains_fetches_through_on_error() {
+		use crate::ir::BodySide;
+		let r = parse_rule(serde_json::json!({
+			"name": "r",
+			"listen": [":7904"],
+			"terminate": {
+				"type": "http_proxy",
+				"upstream": "127.0.0.1:8080",
+				"fallback": {
+					"type": "http_proxy",
+					"upstream": "127.0.0.1:8081",
+					"on_status": [502, 503],
+					"fallback": { "type": "static", "status": 503, "body": "maintenance" },
+				},
+			},
+		}));
+		let graph =
+			compile(vec![rule_file("a.json", vec![r])], &Providers, &Providers).expect("compile");
+		let fetch_node = |upstream: Option<&str>| {
+			graph
+				.nodes
+				.iter()
+				.find(|n| {
+					matches!(n, Node::Fetch { id, .. }
+						if graph.fetches[id.get() as usize].args.get("upstream").and_then(|u| u.as_str()) == upstream)
+				})
+				.expect("fetch node")
+		};
+		let Node::Fetch { on_error: Some(backup), on_error_status, collect_body_before, .. } =
+			fetch_node(Some("127.0.0.1:8080"))
+		else {
+			panic!("primary fetch carries on_error");
+		};
+		assert_eq!(
+			on_error_status,
+			&vec![502, 503],
+			"the fallback's on_status guards the edge into it"
+		);
+		assert_eq!(*collect_body_before, Some(BodySide::Request), "primary buffers for the replay");
+		let Node::Fetch { id, on_error: Some(page), on_error_status, collect_body_before, .. } =
+			&graph.nodes[backup.get() as usize]
+		else {
+			panic!("on_error targets the backup fetch");
+		};
+		assert_eq!(graph.fetches[id.get() as usize].args["upstream"], "127.0.0.1:8081");
+		assert!(on_error_status.is_empty(), "the static page is taken on errors only");
+		assert_eq!(*collect_body_before, None, "already buffered by the primary");
+		let Node::Fetch { id, on_error: None, .. } = &graph.nodes[page.get() as usize] else {
+			panic!("chain ends at the static page");
+		};
+		assert_eq!(graph.fetches[id.get() as usize].kind, FetchKind::HttpSynthesize);
+		assert!(
+			graph.fetches.iter().all(|f| f.args.get("fallback").is_none()),
+			"`fallback` is lifted out of the fetch args",
+		);
+	}
+
+	#[test]
+	fn lower_fetch_fallback_rejected_on_l4_forward() {
+		let r = parse_rule(serde_json::json!({
+			"name": "r",
+			"listen": [":7905"],
+			"terminate": {
+				"type": "tcp_forward",
+				"upstream": "127.0.0.1:8080",
+				"fallback": { "type": "tcp_forward", "upstream": "127.0.0.1:8081" },
+			},
+		}));
+		let err = compile(vec![rule_file("a.json", vec![r])], &Providers, &Providers)
+			.expect_err("L4 fallback must fail");
+		assert!(err.to_string().contains("consumed the connection"), "{err}");
+	}
+
+	#[test]
+	fn lower_fetch_fallback_rejects_l4_fallback_terminate() {
+		let r = parse_rule(serde_json::json!({
+			"name": "r",
+			"listen": [":7906"],
+			"terminate": {
+				"type": "http_proxy",
+				"upstream": "127.0.0.1:8080",
+				"fallback": { "type": "tcp_forward", "upstream": "127.0.0.1:8081" },
+			},
+		}));
+		let err = compile(vec![rule_file("a.json", vec![r])], &Providers, &Providers)
+			.expect_err("L4 fallback terminate must fail");
+		assert!(err.to_string().contains("must be an HTTP terminate"), "{err}");
+	}
+
 	#[test]
 	fn lower_two_l7_listeners_have_independent_synth_entries() {
 		// Two L7 listeners on distinct ports each get their own synth
@@ -217,12 +217,20 @@ const fn field_path_inspection_level(path: &FieldPath) -> InspectionLevel {
 		FieldPath::Transport
 		| FieldPath::RemoteIp
 		| FieldPath::RemotePort
+		| FieldPath::RemoteCountry
+		| FieldPath::RemoteContinent
+		| FieldPath::RemoteAsn
+		| FieldPath::RemoteUid
+		| FieldPath::RemoteGid
+		| FieldPath::RemotePid
 		| FieldPath::LocalIp
 		| FieldPath::LocalPort => InspectionLevel::L4Only,
 		FieldPath::Peek
 		| FieldPath::TlsSni
 		| FieldPath::TlsAlpn
 		| FieldPath::TlsVersion
+		| FieldPath::TlsJa3
+		| FieldPath::TlsJa4
 		| FieldPath::TlsPeerCertPresent
 		| FieldPath::TlsPeerCertSubjectCn
 		| FieldPath::TlsPeerCertSanDns
@@ -231,9 +239,13 @@ const fn field_path_inspection_level(path: &FieldPath) -> InspectionLevel {
 		| FieldPath::TlsPeerCertIssuerCn
 		| FieldPath::TlsPeerCertSerial => InspectionLevel::L4Peek,
 		FieldPath::HttpMethod
+		| FieldPath::HttpVersion
+		| FieldPath::HttpUriHost
 		| FieldPath::HttpUriPath
 		| FieldPath::HttpUriQuery
-		| FieldPath::HttpHeader(_) => InspectionLevel::L7Header,
+		| FieldPath::HttpHeader(_)
+		| FieldPath::HttpCookie(_)
+		| FieldPath::HttpQuery(_) => InspectionLevel::L7Header,
 		FieldPath::HttpBody => InspectionLevel::L7Body,
 	}
 }
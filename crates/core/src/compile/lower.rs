@@ -15,7 +15,7 @@ use crate::error::Error;
 use crate::fetch::{FetchKind, FetchPhase, SymbolicFetchRef, Terminator};
 use crate::ir::{
 	BodySide, FetchId, FlowGraphMeta, ListenerKind, MiddlewareId, Node, NodeId, PredicateId,
-	SymbolicFlowGraph, TerminatorId,
+	SymbolicFlowGraph, TerminatorId, UnixListenerSpec,
 };
 use crate::metadata::{FetchMetadataProvider, MiddlewareMetadataProvider};
 use crate::middleware::{MiddlewareKind, SymbolicMiddlewareRef};
@@ -23,7 +23,7 @@ use crate::predicate::{
 	CompiledOperator, CompiledValue, FieldPath, FieldValueType, Operator, Predicate, PredicateInst,
 	Value,
 };
-use crate::rule::SourceInfo;
+use crate::rule::{SourceInfo, TerminateSpec};
 
 /// Lower an analyzed rule set to a `SymbolicFlowGraph`.
 ///
@@ -41,7 +41,7 @@ pub fn lower(
 	let version_hash = hash_rules(&set.rules);
 	let mut builder = Builder::new();
 
-	let groups = group_by_listener(&set.rules)?;
+	let (groups, unix_groups) = group_by_listener(&set.rules)?;
 	for (transport, addrs, rules) in groups {
 		// TLS termination is per-listener, not per-rule: every rule
 		// sharing an address contributes to the listener's cert pool.
@@ -54,6 +54,13 @@ pub fn lower(
 		// `resolve_listener_tls` already established. See `spec/crates/engine-tls.md`
 		// § _TLS 1.3 0-RTT (early data)_ § _Configuration_.
 		validate_zero_rtt_for_listener(&addrs, &rules, resolved_tls.as_ref())?;
+		let proxy_protocol = resolve_listener_proxy_protocol(&addrs, transport, &rules)?;
+		if let Some(rule) = rules.iter().find(|r| r.raw.unix_socket.is_some()) {
+			return Err(Error::compile(format!(
+				"rule {:?} on listener {addrs:?}: `unix_socket` applies only to `unix:` listeners — drop the field",
+				rule.raw.name,
+			)));
+		}
 		let entry = builder.lower_port(&rules, mw_meta, fetch_meta)?;
 		for addr in &addrs {
 			builder.entries.insert(*addr, entry);
@@ -73,9 +80,24 @@ pub fn lower(
 		for addr in addrs {
 			builder.listener_kinds.insert(addr, kind);
 			builder.listener_transports.insert(addr, transport);
+			if let Some(cfg) = &proxy_protocol {
+				builder.listener_proxy_protocol.insert(addr, cfg.clone());
+			}
 		}
 	}
 
+	// `unix:` listeners: same lowering, but keyed by path and without
+	// the listener-level TLS / PROXY protocol aggregation — a local
+	// socket has neither. Stream sockets, so the fetch check runs as
+	// for TCP.
+	for (path, rules) in unix_groups {
+		let socket = resolve_unix_listener(&path, &rules)?;
+		let entry = builder.lower_port(&rules, mw_meta, fetch_meta)?;
+		let kind = derive_listener_kind(&builder.nodes, &builder.fetches, entry);
+		validate_listener_fetches(&path, Transport::Tcp, &builder.nodes, &builder.fetches, entry)?;
+		builder.unix_listeners.insert(path, UnixListenerSpec { entry, kind, socket });
+	}
+
 	// Per `spec/crates/engine-acme.md` § _Configuration schema_: when any
 	// rule declares a `tls.managed.challenge == "http-01"` SNI but
 	// the operator has no plaintext `:80` listener anywhere in the
@@ -110,8 +132,14 @@ pub fn lower(
 	// right body-buffering point and the validator stays happy. The
 	// validator runs after the dedupe pass as a defense-in-depth check
 	// against any future code path that re-creates the violation.
-	dedupe_body_collect_per_path(&mut builder.nodes, &builder.entries);
-	validate_unique_body_reader_per_path(&builder.nodes, &builder.entries)?;
+	let entry_nodes: Vec<NodeId> = builder
+		.entries
+		.values()
+		.copied()
+		.chain(builder.unix_listeners.values().map(|u| u.entry))
+		.collect();
+	dedupe_body_collect_per_path(&mut builder.nodes, &entry_nodes);
+	validate_unique_body_reader_per_path(&builder.nodes, &entry_nodes)?;
 
 	Ok(SymbolicFlowGraph {
 		nodes: builder.nodes,
@@ -129,6 +157,8 @@ pub fn lower(
 			listener_tls: builder.listener_tls,
 			listener_kinds: builder.listener_kinds,
 			listener_transports: builder.listener_transports,
+			listener_proxy_protocol: builder.listener_proxy_protocol,
+			unix_listeners: builder.unix_listeners,
 			annotations,
 		},
 	})
@@ -174,7 +204,7 @@ fn derive_listener_kind(
 					queue.push_back(*e);
 				}
 			}
-			Node::Fetch { id, next_response, next_tunnel, .. } => {
+			Node::Fetch { id, next_response, next_tunnel, on_error, .. } => {
 				match fetches[id.get() as usize].kind.phase() {
 					FetchPhase::L4 => seen_l4 = true,
 					FetchPhase::L7 => seen_l7 = true,
@@ -185,6 +215,9 @@ fn derive_listener_kind(
 				if let Some(n) = next_tunnel {
 					queue.push_back(*n);
 				}
+				if let Some(e) = on_error {
+					queue.push_back(*e);
+				}
 			}
 			Node::Upgrade { next } => queue.push_back(*next),
 			Node::Terminate(_) => {}
@@ -211,8 +244,8 @@ fn derive_listener_kind(
 /// `L7` fetches and `L4Forward` whose `args.transport` is unset
 /// (defaults to TCP) on a TCP listener are silently accepted.
 ///
-/// `addrs` is purely for the error message — the listener's identity
-/// in operator-facing diagnostics.
+/// `listener` (address list or socket path) is purely for the error
+/// message — the listener's identity in operator-facing diagnostics.
 ///
 /// # Errors
 ///
@@ -222,7 +255,7 @@ fn derive_listener_kind(
 /// the offending fetch's upstream so operators can locate the
 /// conflicting rule.
 fn validate_listener_fetches(
-	addrs: &[SocketAddr],
+	listener: &(impl std::fmt::Debug + ?Sized),
 	listener_transport: Transport,
 	nodes: &[Node],
 	fetches: &[SymbolicFetchRef],
@@ -246,7 +279,7 @@ fn validate_listener_fetches(
 					queue.push_back(*e);
 				}
 			}
-			Node::Fetch { id, next_response, next_tunnel, .. } => {
+			Node::Fetch { id, next_response, next_tunnel, on_error, .. } => {
 				let fetch = &fetches[id.get() as usize];
 				if matches!(fetch.kind, FetchKind::L4Forward) {
 					let fetch_transport =
@@ -255,7 +288,7 @@ fn validate_listener_fetches(
 							Some("tcp") | None => Some(Transport::Tcp),
 							Some(other) => {
 								return Err(Error::compile(format!(
-									"listener {addrs:?}: L4Forward fetch carries unknown transport {other:?}",
+									"listener {listener:?}: L4Forward fetch carries unknown transport {other:?}",
 								)));
 							}
 						};
@@ -265,7 +298,7 @@ fn validate_listener_fetches(
 						let upstream =
 							fetch.args.get("upstream").and_then(serde_json::Value::as_str).unwrap_or("<unknown>");
 						return Err(Error::compile(format!(
-							"listener {addrs:?} declared {listener_transport:?} but reachable L4Forward (upstream {upstream:?}) carries transport {ft:?} — listener prefix and fetch transport must agree",
+							"listener {listener:?} declared {listener_transport:?} but reachable L4Forward (upstream {upstream:?}) carries transport {ft:?} — listener prefix and fetch transport must agree",
 						)));
 					}
 				}
@@ -275,6 +308,9 @@ fn validate_listener_fetches(
 				if let Some(n) = next_tunnel {
 					queue.push_back(*n);
 				}
+				if let Some(e) = on_error {
+					queue.push_back(*e);
+				}
 			}
 			Node::Upgrade { next } => queue.push_back(*next),
 			Node::Terminate(_) => {}
@@ -283,10 +319,31 @@ fn validate_listener_fetches(
 	Ok(())
 }
 
+/// `L4Forward` has no response edge to hang `L7Response` middleware
+/// on. Unknown names are skipped; `lower_rule` reports them itself.
+fn reject_response_middleware(
+	rule: &AnalyzedRule,
+	mw_meta: &dyn MiddlewareMetadataProvider,
+) -> Result<(), Error> {
+	let found = rule
+		.raw
+		.middleware_chain
+		.iter()
+		.find(|m| mw_meta.get(&m.name).is_some_and(|meta| meta.kind == MiddlewareKind::L7Response));
+	match found {
+		Some(m) => Err(Error::compile(format!(
+			"rule {:?}: response middleware {:?} needs an HTTP response, but L4Forward only tunnels bytes",
+			rule.raw.name, m.name
+		))),
+		None => Ok(()),
+	}
+}
+
 /// Peek at a fetch's `args.retry` JSON to decide whether the lower
 /// pass needs to flag the fetch node with `collect_body_before:
 /// Some(BodySide::Request)`. Returns `true` only when the policy
-/// has `max_attempts > 1` and `buffering: "force"`. The full retry
+/// has `buffering: "force"` and either `max_attempts > 1` or a
+/// `hedge` — both send the request body more than once. The full retry
 /// schema is parsed by the engine's fetch factory; this helper is
 /// the minimum the lower pass needs to thread the buffering decision
 /// through to the graph shape.
@@ -298,7 +355,8 @@ fn peek_retry_buffer_required(args: &serde_json::Value) -> bool {
 		return false;
 	};
 	let max_attempts = retry.get("max_attempts").and_then(serde_json::Value::as_u64).unwrap_or(1);
-	if max_attempts <= 1 {
+	let hedged = retry.get("hedge").is_some_and(|h| !h.is_null());
+	if max_attempts <= 1 && !hedged {
 		return false;
 	}
 	let buffering =
@@ -470,27 +528,22 @@ mod listener_fetch_validation_tests {
 			id: FetchId::new(id),
 			next_response: None,
 			next_tunnel: Some(NodeId::new(term)),
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		}
 	}
 
 	fn l4_fetch(transport: &str) -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: serde_json::json!({ "upstream": "127.0.0.1:9", "transport": transport }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(
+			FetchKind::L4Forward,
+			serde_json::json!({ "upstream": "127.0.0.1:9", "transport": transport }),
+		)
 	}
 
 	fn l7_fetch() -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: serde_json::json!({ "upstream": "127.0.0.1:9" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(FetchKind::HttpProxy, serde_json::json!({ "upstream": "127.0.0.1:9" }))
 	}
 
 	fn addr() -> Vec<SocketAddr> {
@@ -517,12 +570,10 @@ mod listener_fetch_validation_tests {
 	fn tcp_listener_with_l4_forward_default_transport_passes() {
 		// `args.transport` absent defaults to TCP at the fetch layer.
 		let nodes = vec![fetch_node(0, 1), Node::Terminate(TerminatorId::new(0))];
-		let fetches = vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: serde_json::json!({ "upstream": "127.0.0.1:9" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}];
+		let fetches = vec![SymbolicFetchRef::new(
+			FetchKind::L4Forward,
+			serde_json::json!({ "upstream": "127.0.0.1:9" }),
+		)];
 		validate_listener_fetches_for_test(&addr(), Transport::Tcp, &nodes, &fetches, NodeId::new(0))
 			.expect("tcp listener + default-transport L4Forward must pass");
 	}
@@ -623,6 +674,12 @@ struct Builder {
 	/// each address group; see [`derive_listener_transport`] for the
 	/// derivation rule and conflict semantics.
 	listener_transports: std::collections::BTreeMap<SocketAddr, Transport>,
+	/// Per-listener PROXY protocol acceptance. Populated by
+	/// [`resolve_listener_proxy_protocol`] per address group.
+	listener_proxy_protocol: std::collections::BTreeMap<SocketAddr, crate::rule::ProxyProtocolConfig>,
+	/// `unix:` listeners, one per socket path. Populated by the path
+	/// loop in [`lower`].
+	unix_listeners: std::collections::BTreeMap<PathBuf, UnixListenerSpec>,
 }
 
 impl Builder {
@@ -640,8 +697,9 @@ impl Builder {
 			short_circuit_response_entry: std::collections::BTreeMap::new(),
 			listener_tls: std::collections::BTreeMap::new(),
 			listener_kinds: std::collections::BTreeMap::new(),
-
 			listener_transports: std::collections::BTreeMap::new(),
+			listener_proxy_protocol: std::collections::BTreeMap::new(),
+			unix_listeners: std::collections::BTreeMap::new(),
 		}
 	}
 
@@ -789,66 +847,8 @@ impl Builder {
 		fetch_meta: &dyn FetchMetadataProvider,
 	) -> Result<NodeId, Error> {
 		// Build tail-first so on_* edges point at already-allocated NodeIds.
-		// `WebSocketUpgrade` is dual-output: the response branch emits a
-		// WriteHttpResponse terminator (for rejection / 4xx), the tunnel
-		// branch emits a ByteTunnel terminator (for the 101-Switching
-		// handoff). Single-output fetches reuse one terminator node on the
-		// active branch only.
-		let fetch_kind = rule.raw.terminate.kind;
-		let retry_buffer_required = peek_retry_buffer_required(&rule.raw.terminate.args);
-		let fid = self.push_fetch(SymbolicFetchRef {
-			kind: fetch_kind,
-			args: rule.raw.terminate.args.clone(),
-			retry_buffer_required,
-			// Lift the rule's `allow_zero_rtt` onto the per-rule fetch so
-			// the executor's `Node::Fetch` arm can consult it without a
-			// rule-side lookup. `None` here means the rule's listener is
-			// not TLS-terminating L7 — the runtime gate is unreachable.
-			// The lower pass has already validated the field's presence
-			// matches the listener type via `validate_zero_rtt_for_rule`.
-			allow_zero_rtt: rule.raw.allow_zero_rtt,
-		});
-		let (next_response, next_tunnel) = match fetch_kind {
-			FetchKind::HttpProxy | FetchKind::HttpSynthesize | FetchKind::AcmeChallenge => {
-				let tid = self.intern_terminator(Terminator::WriteHttpResponse);
-				let term_node = self.push_node(Node::Terminate(tid));
-				(Some(term_node), None)
-			}
-			FetchKind::L4Forward => {
-				let tid = self.intern_terminator(Terminator::ByteTunnel);
-				let term_node = self.push_node(Node::Terminate(tid));
-				(None, Some(term_node))
-			}
-			FetchKind::WebSocketUpgrade => {
-				let resp_tid = self.intern_terminator(Terminator::WriteHttpResponse);
-				let resp_node = self.push_node(Node::Terminate(resp_tid));
-				let tun_tid = self.intern_terminator(Terminator::ByteTunnel);
-				let tun_node = self.push_node(Node::Terminate(tun_tid));
-				(Some(resp_node), Some(tun_node))
-			}
-		};
+		let fetch_node_id = self.lower_terminate(rule, &rule.raw.terminate, mw_meta)?;
 		let _ = fetch_meta;
-		let fetch_node_idx = self.nodes.len();
-		let fetch_node_id = NodeId::new(u32::try_from(fetch_node_idx).expect("node id fits u32"));
-		// `buffering: "force"` on a `max_attempts > 1` retry policy
-		// flags the fetch node itself with `collect_body_before:
-		// Some(BodySide::Request)` — the executor reads this at node
-		// entry, so by the time the fetch runs the body has been
-		// drained from the upstream `Body::Stream` into a
-		// `Body::Static` snapshot the retry loop can replay. See
-		// `spec/crates/engine.md` § _Retry_.
-		let (fetch_collect, fetch_body_limit) = if retry_buffer_required {
-			(Some(BodySide::Request), rule.raw.max_body_bytes_request)
-		} else {
-			(None, 0)
-		};
-		self.nodes.push(Node::Fetch {
-			id: fid,
-			next_response,
-			next_tunnel,
-			collect_body_before: fetch_collect,
-			body_limit: fetch_body_limit,
-		});
 
 		// Middleware chain, reverse-linked so each `next` points at the
 		// already-emitted successor.
@@ -860,6 +860,10 @@ impl Builder {
 			let meta = mw_meta
 				.get(&mw_ref.name)
 				.ok_or_else(|| Error::compile(format!("unknown middleware: {:?}", mw_ref.name)))?;
+			// Response-side entries were placed after the fetch above.
+			if meta.kind == MiddlewareKind::L7Response {
+				continue;
+			}
 			let sym = SymbolicMiddlewareRef {
 				name: Arc::from(mw_ref.name.as_str()),
 				args: mw_ref.args.clone(),
@@ -920,6 +924,149 @@ impl Builder {
 		Ok(head)
 	}
 
+	/// Lower one `terminate` — the rule's own or a `fallback` in its
+	/// chain — into a `Node::Fetch` with its terminators and response
+	/// chain, returning the fetch node.
+	///
+	/// `WebSocketUpgrade` is dual-output: the response branch emits a
+	/// WriteHttpResponse terminator (for rejection / 4xx), the tunnel
+	/// branch emits a ByteTunnel terminator (for the 101-Switching
+	/// handoff). Single-output fetches reuse one terminator node on the
+	/// active branch only.
+	///
+	/// A `fallback` is lowered first so the fetch's `on_error` edge
+	/// points at an already-allocated node. See `spec/flow-model.md`
+	/// § _Fetch fallback_.
+	fn lower_terminate(
+		&mut self,
+		rule: &AnalyzedRule,
+		spec: &TerminateSpec,
+		mw_meta: &dyn MiddlewareMetadataProvider,
+	) -> Result<NodeId, Error> {
+		let (on_error, on_error_status) = match &spec.fallback {
+			Some(fallback) => {
+				// An L4 fetch owns the connection once it runs; there is
+				// nothing left to hand a fallback when it fails.
+				if matches!(spec.kind.phase(), FetchPhase::L4) {
+					return Err(Error::compile(format!(
+						"rule {:?}: `fallback` needs an HTTP terminate, but {:?} has consumed the connection by the time it fails",
+						rule.raw.name, spec.kind,
+					)));
+				}
+				if matches!(fallback.terminate.kind.phase(), FetchPhase::L4) {
+					return Err(Error::compile(format!(
+						"rule {:?}: fallback terminate must be an HTTP terminate, got {:?}",
+						rule.raw.name, fallback.terminate.kind,
+					)));
+				}
+				let target = self.lower_terminate(rule, &fallback.terminate, mw_meta)?;
+				(Some(target), fallback.on_status.clone())
+			}
+			None => (None, Vec::new()),
+		};
+
+		let fetch_kind = spec.kind;
+		let retry_buffer_required = peek_retry_buffer_required(&spec.args);
+		let fid = self.push_fetch(SymbolicFetchRef {
+			kind: fetch_kind,
+			args: spec.args.clone(),
+			retry_buffer_required,
+			// Lift the rule's `allow_zero_rtt` onto the per-rule fetch so
+			// the executor's `Node::Fetch` arm can consult it without a
+			// rule-side lookup. `None` here means the rule's listener is
+			// not TLS-terminating L7 — the runtime gate is unreachable.
+			// The lower pass has already validated the field's presence
+			// matches the listener type via `validate_zero_rtt_for_rule`.
+			allow_zero_rtt: rule.raw.allow_zero_rtt,
+			rule: Some(rule.raw.name.clone()),
+		});
+		let (next_response, next_tunnel) = match fetch_kind {
+			FetchKind::HttpProxy
+			| FetchKind::HttpSynthesize
+			| FetchKind::FileServer
+			| FetchKind::AcmeChallenge => {
+				let tid = self.intern_terminator(Terminator::WriteHttpResponse);
+				let term_node = self.push_node(Node::Terminate(tid));
+				(Some(self.lower_response_chain(rule, term_node, mw_meta)?), None)
+			}
+			FetchKind::L4Forward => {
+				reject_response_middleware(rule, mw_meta)?;
+				let tid = self.intern_terminator(Terminator::ByteTunnel);
+				let term_node = self.push_node(Node::Terminate(tid));
+				(None, Some(term_node))
+			}
+			FetchKind::WebSocketUpgrade => {
+				let resp_tid = self.intern_terminator(Terminator::WriteHttpResponse);
+				let resp_node = self.push_node(Node::Terminate(resp_tid));
+				let resp_node = self.lower_response_chain(rule, resp_node, mw_meta)?;
+				let tun_tid = self.intern_terminator(Terminator::ByteTunnel);
+				let tun_node = self.push_node(Node::Terminate(tun_tid));
+				(Some(resp_node), Some(tun_node))
+			}
+		};
+		// `buffering: "force"` on a `max_attempts > 1` retry policy
+		// flags the fetch node itself with `collect_body_before:
+		// Some(BodySide::Request)` — the executor reads this at node
+		// entry, so by the time the fetch runs the body has been
+		// drained from the upstream `Body::Stream` into a
+		// `Body::Static` snapshot the retry loop can replay. See
+		// `spec/crates/engine.md` § _Retry_. A fallback replays the same
+		// snapshot into its `on_error` target, so it flags the node too;
+		// `dedupe_body_collect_per_path` clears the flag where an
+		// earlier node already buffers.
+		let (fetch_collect, fetch_body_limit) = if retry_buffer_required || on_error.is_some() {
+			(Some(BodySide::Request), rule.raw.max_body_bytes_request)
+		} else {
+			(None, 0)
+		};
+		Ok(self.push_node(Node::Fetch {
+			id: fid,
+			next_response,
+			next_tunnel,
+			on_error,
+			on_error_status,
+			collect_body_before: fetch_collect,
+			body_limit: fetch_body_limit,
+		}))
+	}
+
+	/// Place the rule's `L7Response` middlewares between the fetch's
+	/// response edge and `tail` (the response terminator), in chain
+	/// order. Returns the node the fetch's `next_response` should point
+	/// at — `tail` itself when the rule has none.
+	fn lower_response_chain(
+		&mut self,
+		rule: &AnalyzedRule,
+		tail: NodeId,
+		mw_meta: &dyn MiddlewareMetadataProvider,
+	) -> Result<NodeId, Error> {
+		let mut head = tail;
+		for mw_ref in rule.raw.middleware_chain.iter().rev() {
+			let meta = mw_meta
+				.get(&mw_ref.name)
+				.ok_or_else(|| Error::compile(format!("unknown middleware: {:?}", mw_ref.name)))?;
+			if meta.kind != MiddlewareKind::L7Response {
+				continue;
+			}
+			let id = self.intern_middleware(SymbolicMiddlewareRef {
+				name: Arc::from(mw_ref.name.as_str()),
+				args: mw_ref.args.clone(),
+				kind: meta.kind,
+				stateless: meta.stateless,
+				needs_body: meta.needs_body,
+				on_error: None,
+			});
+			head = self.push_node(Node::Middleware {
+				id,
+				next: head,
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			});
+		}
+		Ok(head)
+	}
+
 	fn lower_predicate(
 		&mut self,
 		pred: &Predicate,
@@ -1002,10 +1149,11 @@ impl Builder {
 	/// - `Node::Middleware { next, on_error }` — both arms continue the
 	///   walk, since each is a distinct downstream path.
 	/// - `Node::Check { on_match, on_miss }` — both branches continue.
-	/// - `Node::Fetch { next_response, next_tunnel }` — response-side
-	///   marking continues past the fetch (post-fetch L7Response
-	///   middlewares exist); request-side stops at the fetch (the body
-	///   has already been consumed by the time the fetch fires).
+	/// - `Node::Fetch { next_response, next_tunnel, on_error }` —
+	///   response-side marking continues past the fetch (post-fetch
+	///   L7Response middlewares exist, on the fallback's chain too);
+	///   request-side stops at the fetch (the body has already been
+	///   consumed by the time the fetch fires).
 	/// - `Node::Terminate(_) | Node::Upgrade { .. }` — terminal.
 	///
 	/// The walk uses a `(node, already_marked_on_this_path)` visited
@@ -1052,9 +1200,10 @@ impl Builder {
 					stack.push((m, already_marked));
 					stack.push((s, already_marked));
 				}
-				Node::Fetch { next_response, next_tunnel, .. } => {
+				Node::Fetch { next_response, next_tunnel, on_error, .. } => {
 					// Response-side marking traverses past the fetch into
-					// any post-fetch L7Response middleware. Request-side
+					// any post-fetch L7Response middleware, including the
+					// response chain of a fallback fetch. Request-side
 					// stops here — the body is by then either consumed or
 					// already buffered upstream.
 					if matches!(side, BodySide::Response) {
@@ -1064,6 +1213,9 @@ impl Builder {
 						if let Some(t) = next_tunnel {
 							stack.push((*t, already_marked));
 						}
+						if let Some(e) = on_error {
+							stack.push((*e, already_marked));
+						}
 					}
 				}
 				Node::Upgrade { next } => {
@@ -1089,12 +1241,20 @@ fn field_path_level(path: &FieldPath) -> Level {
 		FieldPath::Transport
 		| FieldPath::RemoteIp
 		| FieldPath::RemotePort
+		| FieldPath::RemoteCountry
+		| FieldPath::RemoteContinent
+		| FieldPath::RemoteAsn
+		| FieldPath::RemoteUid
+		| FieldPath::RemoteGid
+		| FieldPath::RemotePid
 		| FieldPath::LocalIp
 		| FieldPath::LocalPort => Level::L4Only,
 		FieldPath::Peek
 		| FieldPath::TlsSni
 		| FieldPath::TlsAlpn
 		| FieldPath::TlsVersion
+		| FieldPath::TlsJa3
+		| FieldPath::TlsJa4
 		| FieldPath::TlsPeerCertPresent
 		| FieldPath::TlsPeerCertSubjectCn
 		| FieldPath::TlsPeerCertSanDns
@@ -1103,9 +1263,13 @@ fn field_path_level(path: &FieldPath) -> Level {
 		| FieldPath::TlsPeerCertIssuerCn
 		| FieldPath::TlsPeerCertSerial => Level::L4Peek,
 		FieldPath::HttpMethod
+		| FieldPath::HttpVersion
+		| FieldPath::HttpUriHost
 		| FieldPath::HttpUriPath
 		| FieldPath::HttpUriQuery
-		| FieldPath::HttpHeader(_) => Level::L7Header,
+		| FieldPath::HttpHeader(_)
+		| FieldPath::HttpCookie(_)
+		| FieldPath::HttpQuery(_) => Level::L7Header,
 		FieldPath::HttpBody => Level::L7Body,
 	}
 }
@@ -1170,12 +1334,20 @@ fn predicate_is_l4(pred: Option<&Predicate>) -> bool {
 		FieldPath::Transport
 			| FieldPath::RemoteIp
 			| FieldPath::RemotePort
+			| FieldPath::RemoteCountry
+			| FieldPath::RemoteContinent
+			| FieldPath::RemoteAsn
+			| FieldPath::RemoteUid
+			| FieldPath::RemoteGid
+			| FieldPath::RemotePid
 			| FieldPath::LocalIp
 			| FieldPath::LocalPort
 			| FieldPath::Peek
 			| FieldPath::TlsSni
 			| FieldPath::TlsAlpn
 			| FieldPath::TlsVersion
+			| FieldPath::TlsJa3
+			| FieldPath::TlsJa4
 			| FieldPath::TlsPeerCertPresent
 			| FieldPath::TlsPeerCertSubjectCn
 			| FieldPath::TlsPeerCertSanDns
@@ -1188,6 +1360,13 @@ fn predicate_is_l4(pred: Option<&Predicate>) -> bool {
 
 type ListenerGroup<'a> = (Transport, Vec<SocketAddr>, Vec<&'a AnalyzedRule>);
 
+/// `unix:` listeners by socket path, each with every rule listing it.
+type UnixListenerGroups<'a> = BTreeMap<PathBuf, Vec<&'a AnalyzedRule>>;
+
+/// Longest `unix:` path: `sockaddr_un.sun_path` is 108 bytes including
+/// the terminating NUL.
+const UNIX_PATH_MAX: usize = 107;
+
 /// Per-listener TLS resolution — aggregate every rule's `tls` block
 /// into a `ListenerTlsSpec` cert pool.
 ///
@@ -1380,6 +1559,44 @@ fn resolve_listener_tls(
 	if spec.is_empty() { Ok(None) } else { Ok(Some(spec)) }
 }
 
+/// Aggregate per-rule `proxy_protocol` into one listener-level config.
+/// Like `client_auth`, "not declared" is a distinct value: a rule that
+/// omits the block must not silently inherit header parsing from a
+/// sibling, so every rule on the address must declare the same block
+/// or all omit it. The header is a stream preamble, so UDP listeners
+/// reject it. See `spec/crates/engine.md` § _PROXY protocol_.
+fn resolve_listener_proxy_protocol(
+	addrs: &[SocketAddr],
+	transport: Transport,
+	rules: &[&AnalyzedRule],
+) -> Result<Option<crate::rule::ProxyProtocolConfig>, Error> {
+	let mut resolved: Option<Option<&crate::rule::ProxyProtocolConfig>> = None;
+	for rule in rules {
+		let candidate = rule.raw.proxy_protocol.as_ref();
+		match resolved {
+			None => resolved = Some(candidate),
+			Some(existing) if existing == candidate => {}
+			Some(_) => {
+				return Err(Error::compile(format!(
+					"listener {addrs:?}: rules disagree on `proxy_protocol` — it is a listener-level setting; every rule on the same address must declare the same block (or all omit it)"
+				)));
+			}
+		}
+	}
+	let Some(cfg) = resolved.flatten() else { return Ok(None) };
+	if transport == Transport::Udp {
+		return Err(Error::compile(format!(
+			"listener {addrs:?}: `proxy_protocol` is TCP-only — remove it from the udp listener"
+		)));
+	}
+	if cfg.trusted.is_empty() {
+		return Err(Error::compile(format!(
+			"listener {addrs:?}: `proxy_protocol.trusted` must list at least one CIDR"
+		)));
+	}
+	Ok(Some(cfg.clone()))
+}
+
 /// Render a `TlsConfig`'s `cert_file` for use in a compile diagnostic.
 /// Static configs always have a path post-validation; the `<managed>`
 /// fallback arm is for diagnostic robustness if the validation
@@ -1453,12 +1670,7 @@ fn inject_acme_http01_routes(
 		)),
 	};
 	let pred_id = builder.intern_predicate(predicate);
-	let acme_fetch_ref = SymbolicFetchRef {
-		kind: FetchKind::AcmeChallenge,
-		args: serde_json::Value::Null,
-		retry_buffer_required: false,
-		allow_zero_rtt: None,
-	};
+	let acme_fetch_ref = SymbolicFetchRef::new(FetchKind::AcmeChallenge, serde_json::Value::Null);
 	let fetch_id = builder.push_fetch(acme_fetch_ref);
 	let term_id = builder.intern_terminator(Terminator::WriteHttpResponse);
 	let term_node = builder.push_node(Node::Terminate(term_id));
@@ -1466,6 +1678,8 @@ fn inject_acme_http01_routes(
 		id: fetch_id,
 		next_response: Some(term_node),
 		next_tunnel: None,
+		on_error: None,
+		on_error_status: Vec::new(),
 		collect_body_before: None,
 		body_limit: 0,
 	});
@@ -1720,7 +1934,11 @@ fn compile_client_auth(
 	}
 }
 
-fn group_by_listener<'a>(rules: &'a [AnalyzedRule]) -> Result<Vec<ListenerGroup<'a>>, Error> {
+/// Split the rule set into inet listener groups and `unix:` listeners.
+/// A rule's `listen` must be all inet or all `unix:`.
+fn group_by_listener<'a>(
+	rules: &'a [AnalyzedRule],
+) -> Result<(Vec<ListenerGroup<'a>>, UnixListenerGroups<'a>), Error> {
 	// Keyed by `(transport, sorted addrs)` so rules whose listen
 	// strings expand to the same address set under the same transport
 	// share one group; mismatched transports (e.g. `tcp:443` vs
@@ -1729,11 +1947,20 @@ fn group_by_listener<'a>(rules: &'a [AnalyzedRule]) -> Result<Vec<ListenerGroup<
 	// are explicitly rejected — the lower pipeline carries one
 	// transport per rule entry today; multi-transport rules are a
 	// future enhancement.
+	//
+	// `unix:` paths are grouped per path instead: each path collects
+	// every rule that lists it.
 	let mut groups: HashMap<(Transport, Vec<SocketAddr>), Vec<&'a AnalyzedRule>> = HashMap::new();
+	let mut unix: UnixListenerGroups<'a> = BTreeMap::new();
 	for rule in rules {
 		let mut transport: Option<Transport> = None;
 		let mut addrs: Vec<SocketAddr> = Vec::new();
+		let mut paths: Vec<PathBuf> = Vec::new();
 		for spec in &rule.raw.listen {
+			if let Some(path) = parse_unix_listen(spec)? {
+				paths.push(path);
+				continue;
+			}
 			let (t, more) = parse_listen(spec)?;
 			match transport {
 				None => transport = Some(t),
@@ -1747,6 +1974,20 @@ fn group_by_listener<'a>(rules: &'a [AnalyzedRule]) -> Result<Vec<ListenerGroup<
 			}
 			addrs.extend(more);
 		}
+		if !paths.is_empty() {
+			if !addrs.is_empty() {
+				return Err(Error::compile(format!(
+					"rule {:?}: `listen` mixes `unix:` paths and network addresses in one rule — split into separate rules",
+					rule.raw.name,
+				)));
+			}
+			paths.sort();
+			paths.dedup();
+			for path in paths {
+				unix.entry(path).or_default().push(rule);
+			}
+			continue;
+		}
 		addrs.sort();
 		addrs.dedup();
 		// Defensive: a rule with no listen entries can't produce an
@@ -1759,7 +2000,68 @@ fn group_by_listener<'a>(rules: &'a [AnalyzedRule]) -> Result<Vec<ListenerGroup<
 	let mut out: Vec<ListenerGroup<'_>> =
 		groups.into_iter().map(|((transport, addrs), rules)| (transport, addrs, rules)).collect();
 	out.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
-	Ok(out)
+	Ok((out, unix))
+}
+
+/// Parse a `unix:/path` listen entry. `Ok(None)` for every other
+/// entry. The path must be absolute and fit `sockaddr_un.sun_path`
+/// (107 bytes plus the terminating NUL).
+fn parse_unix_listen(spec: &str) -> Result<Option<PathBuf>, Error> {
+	let Some(rest) = spec.trim().strip_prefix("unix:") else { return Ok(None) };
+	let path = PathBuf::from(rest);
+	if !path.is_absolute() {
+		return Err(Error::compile(format!(
+			"bad listen spec {spec:?}: a unix socket path must be absolute"
+		)));
+	}
+	if rest.len() > UNIX_PATH_MAX {
+		return Err(Error::compile(format!(
+			"bad listen spec {spec:?}: unix socket path is {} bytes, the limit is {UNIX_PATH_MAX}",
+			rest.len()
+		)));
+	}
+	Ok(Some(path))
+}
+
+/// Aggregate per-rule `unix_socket` into the listener's socket
+/// settings and reject the listener-level blocks a local socket cannot
+/// carry. Same agreement rule as `proxy_protocol`: every rule on the
+/// path declares the same block, or all omit it (the default).
+fn resolve_unix_listener(
+	path: &std::path::Path,
+	rules: &[&AnalyzedRule],
+) -> Result<crate::rule::UnixSocketConfig, Error> {
+	let mut resolved: Option<Option<&crate::rule::UnixSocketConfig>> = None;
+	for rule in rules {
+		let offending = if rule.raw.tls.is_some() {
+			Some("tls")
+		} else if rule.raw.allow_zero_rtt.is_some() {
+			Some("allow_zero_rtt")
+		} else if rule.raw.proxy_protocol.is_some() {
+			Some("proxy_protocol")
+		} else {
+			None
+		};
+		if let Some(field) = offending {
+			return Err(Error::compile(format!(
+				"rule {:?} on listener `unix:{}`: `{field}` is not supported on unix sockets — drop the field",
+				rule.raw.name,
+				path.display(),
+			)));
+		}
+		let candidate = rule.raw.unix_socket.as_ref();
+		match resolved {
+			None => resolved = Some(candidate),
+			Some(existing) if existing == candidate => {}
+			Some(_) => {
+				return Err(Error::compile(format!(
+					"listener `unix:{}`: rules disagree on `unix_socket` — it is a listener-level setting; every rule on the same path must declare the same block (or all omit it)",
+					path.display(),
+				)));
+			}
+		}
+	}
+	Ok(resolved.flatten().cloned().unwrap_or_default())
 }
 
 /// Parse one `ListenSpec` entry into its declared `(transport, addrs)`
@@ -1952,7 +2254,7 @@ fn coerce_value(
 			let Value::Str(s) = v else {
 				return Err(mismatch());
 			};
-			ensure_sni_ascii_lowercase(path, s, op_name, source)?;
+			ensure_operand_case(path, s, op_name, source)?;
 			Ok(CompiledValue::Str(Arc::from(s.as_str())))
 		}
 		FieldValueType::Enum => {
@@ -1994,6 +2296,7 @@ fn coerce_enum_value(
 		// `http.method` is open per spec — any HTTP token is admissible
 		// at compile; runtime byte-compares to `Request.method().as_str()`.
 		FieldPath::HttpMethod => None,
+		FieldPath::HttpVersion => Some(&["h1", "h2", "h3"]),
 		// Forward-compat: if a future `FieldPath` variant gets
 		// classified as `FieldValueType::Enum` in `predicate.rs` but
 		// nobody adds an arm here, surface a structured compile error
@@ -2050,7 +2353,7 @@ fn value_to_bytes(
 					))
 				})
 			} else {
-				ensure_sni_ascii_lowercase(path, s, op_name, source)?;
+				ensure_operand_case(path, s, op_name, source)?;
 				Ok(bytes::Bytes::copy_from_slice(s.as_bytes()))
 			}
 		}
@@ -2094,10 +2397,7 @@ fn value_to_bytes(
 /// reached with `seen[side] = false`; only nodes never reached
 /// "unseen" on a side are eligible to have that side's flag
 /// cleared.
-fn dedupe_body_collect_per_path(
-	nodes: &mut [Node],
-	entries: &std::collections::HashMap<SocketAddr, NodeId>,
-) {
+fn dedupe_body_collect_per_path(nodes: &mut [Node], entries: &[NodeId]) {
 	use std::collections::{HashMap, HashSet};
 
 	// `keep[(node_id, side)] = true` once `node` is reached on any path
@@ -2107,7 +2407,7 @@ fn dedupe_body_collect_per_path(
 	// default at clear-time is "do nothing".
 	let mut keep: HashMap<(u32, BodySide), bool> = HashMap::new();
 	let mut visited: HashSet<(u32, bool, bool)> = HashSet::new();
-	let mut stack: Vec<(NodeId, bool, bool)> = entries.values().map(|&n| (n, false, false)).collect();
+	let mut stack: Vec<(NodeId, bool, bool)> = entries.iter().map(|&n| (n, false, false)).collect();
 
 	while let Some((cur, seen_req, seen_resp)) = stack.pop() {
 		if !visited.insert((cur.get(), seen_req, seen_resp)) {
@@ -2148,13 +2448,16 @@ fn dedupe_body_collect_per_path(
 				stack.push((*on_match, next_req, next_resp));
 				stack.push((*on_miss, next_req, next_resp));
 			}
-			Node::Fetch { next_response, next_tunnel, .. } => {
+			Node::Fetch { next_response, next_tunnel, on_error, .. } => {
 				if let Some(n) = next_response {
 					stack.push((*n, next_req, next_resp));
 				}
 				if let Some(t) = next_tunnel {
 					stack.push((*t, next_req, next_resp));
 				}
+				if let Some(e) = on_error {
+					stack.push((*e, next_req, next_resp));
+				}
 			}
 			Node::Upgrade { next } => stack.push((*next, next_req, next_resp)),
 			Node::Terminate(_) => {}
@@ -2190,13 +2493,10 @@ fn dedupe_body_collect_per_path(
 /// counts clamped to 2 so the visited set stays at `O(nodes * 4)`.
 /// Reaching state `(node, 2, _)` or `(node, _, 2)` is the failure
 /// condition.
-fn validate_unique_body_reader_per_path(
-	nodes: &[Node],
-	entries: &std::collections::HashMap<SocketAddr, NodeId>,
-) -> Result<(), Error> {
+fn validate_unique_body_reader_per_path(nodes: &[Node], entries: &[NodeId]) -> Result<(), Error> {
 	use std::collections::HashSet;
 	let mut visited: HashSet<(u32, u8, u8)> = HashSet::new();
-	for &entry in entries.values() {
+	for &entry in entries {
 		let mut stack: Vec<(NodeId, u8, u8)> = vec![(entry, 0, 0)];
 		while let Some((cur, req, resp)) = stack.pop() {
 			if !visited.insert((cur.get(), req, resp)) {
@@ -2236,13 +2536,16 @@ fn validate_unique_body_reader_per_path(
 					stack.push((*on_match, new_req, new_resp));
 					stack.push((*on_miss, new_req, new_resp));
 				}
-				Node::Fetch { next_response, next_tunnel, .. } => {
+				Node::Fetch { next_response, next_tunnel, on_error, .. } => {
 					if let Some(n) = next_response {
 						stack.push((*n, new_req, new_resp));
 					}
 					if let Some(t) = next_tunnel {
 						stack.push((*t, new_req, new_resp));
 					}
+					if let Some(e) = on_error {
+						stack.push((*e, new_req, new_resp));
+					}
 				}
 				Node::Upgrade { next } => stack.push((*next, new_req, new_resp)),
 				Node::Terminate(_) => {}
@@ -2252,59 +2555,26 @@ fn validate_unique_body_reader_per_path(
 	Ok(())
 }
 
-/// Compile a `matches` operand into a fancy-regex with explicit
-/// resource caps:
-///
-/// - `backtrack_limit` keeps matching from spinning on adversarial
-///   inputs (RegEx DoS guard, per [`REGEX_BACKTRACK_LIMIT`]).
-/// - `delegate_size_limit` caps the bytes the engine may allocate for
-///   delegate NFA/DFA structures (per [`REGEX_DELEGATE_SIZE_LIMIT`]).
-///
-/// After compile, runs a smoke test against an adversarial-style input
-/// (`"a".repeat(REGEX_SMOKE_TEST_INPUT_LEN)`) to surface patterns that
-/// trip the backtrack limit even on short, plausibly-legitimate inputs.
-/// If the smoke test reports `BacktrackLimitExceeded`, the rule is
-/// rejected at compile time so the runtime never hits the same wall.
+/// Compile a `matches` operand through
+/// [`compile_bounded_regex`](crate::predicate::compile_bounded_regex):
+/// backtrack and delegate-size caps (RegEx DoS guard, per
+/// [`REGEX_BACKTRACK_LIMIT`](crate::predicate::REGEX_BACKTRACK_LIMIT)),
+/// then a smoke test against an adversarial-style input so patterns
+/// that trip the backtrack limit even on short, plausibly-legitimate
+/// inputs are rejected at compile time and the runtime never hits the
+/// same wall.
 fn compile_matches_regex(
 	pat: &str,
 	path: &FieldPath,
 	source: &SourceInfo,
 ) -> Result<fancy_regex::Regex, Error> {
-	use crate::predicate::{
-		REGEX_BACKTRACK_LIMIT, REGEX_DELEGATE_SIZE_LIMIT, REGEX_SMOKE_TEST_INPUT_LEN,
-	};
-	let re = fancy_regex::RegexBuilder::new(pat)
-		.backtrack_limit(REGEX_BACKTRACK_LIMIT)
-		.delegate_size_limit(REGEX_DELEGATE_SIZE_LIMIT)
-		.build()
-		.map_err(|e| {
-			Error::compile(format!(
-				"{}invalid regex in `matches` operator on field `{}`: {e}",
-				source_prefix(source),
-				path.display_name(),
-			))
-		})?;
-
-	// Smoke-test: feed an adversarial run of `a` to surface patterns
-	// that would spin on production traffic before they reach the
-	// runtime. Anchored or short patterns return quickly; pathological
-	// alternations (e.g. `(a+)+b`) hit the backtrack limit here.
-	let probe: String = "a".repeat(REGEX_SMOKE_TEST_INPUT_LEN);
-	match re.is_match(&probe) {
-		Ok(_) => Ok(re),
-		Err(fancy_regex::Error::RuntimeError(fancy_regex::RuntimeError::BacktrackLimitExceeded)) => {
-			Err(Error::compile(format!(
-				"{}regex in `matches` on field `{}` exceeded backtrack limit on smoke-test input; refusing to compile to avoid runtime ReDoS",
-				source_prefix(source),
-				path.display_name(),
-			)))
-		}
-		Err(e) => Err(Error::compile(format!(
-			"{}regex in `matches` on field `{}` errored on smoke test: {e}",
+	crate::predicate::compile_bounded_regex(pat).map_err(|e| {
+		Error::compile(format!(
+			"{}`matches` operator on field `{}`: {e}",
 			source_prefix(source),
 			path.display_name(),
-		))),
-	}
+		))
+	})
 }
 
 /// Enforce the `tls.sni` operand-lowercase contract. SNI is
@@ -2312,16 +2582,30 @@ fn compile_matches_regex(
 /// lowercases it before populating `ConnContext.tls.sni`; rules that
 /// compare against an upper-case literal would silently never match,
 /// so we hard-reject at compile time instead of soft-tolerating.
-fn ensure_sni_ascii_lowercase(
+/// `http.uri.host` is lowercased by its reader for the same reason and
+/// shares the rule. The geo codes go the other way: GeoIP databases
+/// store `remote.country` / `remote.continent` uppercase, so a
+/// lowercase `de` is the literal that can never match.
+fn ensure_operand_case(
 	path: &FieldPath,
 	s: &str,
 	op_name: &'static str,
 	source: &SourceInfo,
 ) -> Result<(), Error> {
-	if matches!(path, FieldPath::TlsSni) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+	let (bad, case) = match path {
+		FieldPath::TlsSni | FieldPath::HttpUriHost => {
+			(s.bytes().any(|b| b.is_ascii_uppercase()), "lowercase")
+		}
+		FieldPath::RemoteCountry | FieldPath::RemoteContinent => {
+			(s.bytes().any(|b| b.is_ascii_lowercase()), "uppercase")
+		}
+		_ => return Ok(()),
+	};
+	if bad {
 		return Err(Error::compile(format!(
-			"{}operator `{op_name}` on field `tls.sni`: operand {s:?} must be ASCII lowercase",
+			"{}operator `{op_name}` on field `{}`: operand {s:?} must be ASCII {case}",
 			source_prefix(source),
+			path.display_name(),
 		)));
 	}
 	Ok(())
@@ -2877,6 +3161,57 @@ mod compat_tests {
 		}
 	}
 
+	#[test]
+	fn http_uri_host_rejects_uppercase_and_http_version_checks_its_values() {
+		let err = compile_operator(
+			&Operator::Suffix(Value::Str(".Example.com".to_string())),
+			&FieldPath::HttpUriHost,
+			&src(),
+		)
+		.expect_err("uppercase http.uri.host operand must reject");
+		assert!(err.to_string().contains("http.uri.host"), "{err}");
+
+		compile_operator(
+			&Operator::Equals(Value::Str("h3".to_string())),
+			&FieldPath::HttpVersion,
+			&src(),
+		)
+		.expect("h3 is a version");
+		let err = compile_operator(
+			&Operator::In(vec![Value::Str("h2".to_string()), Value::Str("HTTP/2".to_string())]),
+			&FieldPath::HttpVersion,
+			&src(),
+		)
+		.expect_err("HTTP/2 is not an http.version value");
+		assert!(err.to_string().contains("[\"h1\", \"h2\", \"h3\"]"), "{err}");
+	}
+
+	#[test]
+	fn remote_geo_codes_require_uppercase_and_asn_is_an_int() {
+		compile_operator(
+			&Operator::In(vec![Value::Str("DE".to_string()), Value::Str("FR".to_string())]),
+			&FieldPath::RemoteCountry,
+			&src(),
+		)
+		.expect("uppercase country codes");
+		let err = compile_operator(
+			&Operator::Equals(Value::Str("eu".to_string())),
+			&FieldPath::RemoteContinent,
+			&src(),
+		)
+		.expect_err("lowercase continent code must reject");
+		assert!(err.to_string().contains("must be ASCII uppercase"), "{err}");
+
+		compile_operator(&Operator::Equals(Value::Int(13335)), &FieldPath::RemoteAsn, &src())
+			.expect("asn equals int");
+		compile_operator(
+			&Operator::Equals(Value::Str("AS13335".to_string())),
+			&FieldPath::RemoteAsn,
+			&src(),
+		)
+		.expect_err("asn is Int-typed");
+	}
+
 	#[test]
 	fn tls_sni_accepts_lowercase_and_non_ascii_punycode() {
 		// Pure ASCII lowercase is the canonical form.
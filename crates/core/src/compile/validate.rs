@@ -3,13 +3,16 @@ use std::collections::HashSet;
 use crate::error::{Diagnostics, Error};
 use crate::ir::{Node, NodeId, SymbolicFlowGraph};
 use crate::phase::{Phase, PhaseNodeKind, Transition, transition};
+use crate::predicate::compile_bounded_regex;
+use crate::template::Template;
 
 /// Run IR-level structural and phase validation on a freshly-lowered graph.
 ///
 /// # Errors
 /// Returns [`Error::compile`] on missing-id references, Fetch edges that
-/// don't match the kind's output-mode contract, acyclicity violations, or
-/// phase-state-machine mismatches. When multiple violations are found
+/// don't match the kind's output-mode contract, acyclicity violations,
+/// phase-state-machine mismatches, or fetch fallbacks reachable with an
+/// unbuffered request body. When multiple violations are found
 /// they are collapsed into a single message via [`Diagnostics`].
 pub fn validate(graph: &SymbolicFlowGraph) -> Result<(), Error> {
 	validate_collecting(graph).into_result(()).map_err(Error::from)
@@ -24,6 +27,7 @@ pub fn validate(graph: &SymbolicFlowGraph) -> Result<(), Error> {
 pub fn validate_collecting(graph: &SymbolicFlowGraph) -> Diagnostics {
 	let mut d = Diagnostics::new();
 	check_id_ranges(graph, &mut d);
+	check_middleware_args(graph, &mut d);
 	// Every downstream check (fetch-edges, acyclic, phases) walks
 	// `graph.fetches[id]` / `graph.nodes[id]` etc., so dangling IDs
 	// would panic. Gate the rest on a clean id-range pass; the
@@ -32,6 +36,7 @@ pub fn validate_collecting(graph: &SymbolicFlowGraph) -> Diagnostics {
 		check_fetch_edges(graph, &mut d);
 		check_acyclic(graph, &mut d);
 		check_phases_collecting(graph, &mut d);
+		check_fallback_body_buffered(graph, &mut d);
 	}
 	d
 }
@@ -69,7 +74,7 @@ fn check_id_ranges(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 					d.push(Error::compile(format!("node {idx}.on_error dangling")));
 				}
 			}
-			Node::Fetch { id, next_response, next_tunnel, .. } => {
+			Node::Fetch { id, next_response, next_tunnel, on_error, .. } => {
 				if id.get() >= n_fetches {
 					d.push(Error::compile(format!("node {idx}: dangling FetchId({})", id.get())));
 				}
@@ -83,6 +88,11 @@ fn check_id_ranges(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 				{
 					d.push(Error::compile(format!("node {idx}.next_tunnel dangling")));
 				}
+				if let Some(e) = on_error
+					&& e.get() >= n_nodes
+				{
+					d.push(Error::compile(format!("node {idx}.on_error dangling")));
+				}
 			}
 			Node::Upgrade { next } => {
 				if next.get() >= n_nodes {
@@ -98,17 +108,90 @@ fn check_id_ranges(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 	}
 }
 
+/// Built-in middleware whose args carry field-path templates
+/// (`request_headers`, `response_headers`, `redirect`) or regexes
+/// (`rewrite`, `redirect`). A bad `${…}` or pattern fails here, the
+/// same way a bad predicate path does, instead of at link. Non-string
+/// values are left to the factory's own shape checks.
+fn check_middleware_args(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
+	for (idx, mw) in graph.middlewares.iter().enumerate() {
+		let mut errors = Vec::new();
+		match &*mw.name {
+			"request_headers" | "response_headers" => header_templates(&mw.args, &mut errors),
+			"rewrite" => rewrite_pattern(&mw.args, &mut errors),
+			"redirect" => redirect_location(&mw.args, &mut errors),
+			_ => {}
+		}
+		for (field, e) in errors {
+			d.push(Error::compile(format!("middleware {idx} ({}): args.{field}: {e}", mw.name)));
+		}
+	}
+}
+
+fn header_templates(args: &serde_json::Value, errors: &mut Vec<(String, String)>) {
+	for op in ["set", "add"] {
+		let Some(map) = args.get(op).and_then(serde_json::Value::as_object) else {
+			continue;
+		};
+		for (name, value) in map {
+			let Some(src) = value.as_str() else { continue };
+			if let Err(e) = Template::parse(src) {
+				errors.push((format!("{op}.{name}"), e));
+			}
+		}
+	}
+}
+
+fn rewrite_pattern(args: &serde_json::Value, errors: &mut Vec<(String, String)>) {
+	let Some(src) = args.pointer("/replace/pattern").and_then(serde_json::Value::as_str) else {
+		return;
+	};
+	match compile_bounded_regex(src) {
+		Ok(re) => {
+			let with = args.pointer("/replace/with").and_then(serde_json::Value::as_str);
+			if let Some(Err(e)) = with.map(|w| fancy_regex::Expander::default().check(w, &re)) {
+				errors.push(("replace.with".to_owned(), e.to_string()));
+			}
+		}
+		Err(e) => errors.push(("replace.pattern".to_owned(), e)),
+	}
+}
+
+fn redirect_location(args: &serde_json::Value, errors: &mut Vec<(String, String)>) {
+	let groups = match args.get("pattern").and_then(serde_json::Value::as_str) {
+		None => 0,
+		Some(src) => match compile_bounded_regex(src) {
+			Ok(re) => re.captures_len(),
+			Err(e) => return errors.push(("pattern".to_owned(), e)),
+		},
+	};
+	let Some(src) = args.get("location").and_then(serde_json::Value::as_str) else { return };
+	let parsed =
+		if groups == 0 { Template::parse(src) } else { Template::parse_with_captures(src, groups) };
+	if let Err(e) = parsed {
+		errors.push(("location".to_owned(), e));
+	}
+}
+
 fn check_fetch_edges(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 	use crate::fetch::FetchKind::{
-		AcmeChallenge, HttpProxy, HttpSynthesize, L4Forward, WebSocketUpgrade,
+		AcmeChallenge, FileServer, HttpProxy, HttpSynthesize, L4Forward, WebSocketUpgrade,
 	};
 	for (idx, node) in graph.nodes.iter().enumerate() {
-		let Node::Fetch { id, next_response, next_tunnel, .. } = node else {
+		let Node::Fetch { id, next_response, next_tunnel, on_error, on_error_status, .. } = node else {
 			continue;
 		};
 		let kind = graph[*id].kind;
+		if on_error.is_some() && matches!(kind.phase(), crate::fetch::FetchPhase::L4) {
+			d.push(Error::compile(format!(
+				"node {idx}: {kind:?} cannot carry on_error — an L4 fetch consumes the connection"
+			)));
+		}
+		if on_error.is_none() && !on_error_status.is_empty() {
+			d.push(Error::compile(format!("node {idx}: on_error_status without an on_error edge")));
+		}
 		match kind {
-			HttpProxy | HttpSynthesize | AcmeChallenge => {
+			HttpProxy | HttpSynthesize | FileServer | AcmeChallenge => {
 				if next_response.is_none() {
 					d.push(Error::compile(format!("node {idx}: {kind:?} requires next_response")));
 				}
@@ -180,7 +263,19 @@ fn check_acyclic(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 	}
 }
 
+/// Every outgoing edge, the fetch `on_error` fallback included.
 fn successors(node: &Node) -> Vec<NodeId> {
+	let mut v = forward_successors(node);
+	if let Node::Fetch { on_error: Some(e), .. } = node {
+		v.push(*e);
+	}
+	v
+}
+
+/// The edges a node's phase transition leads along: [`successors`]
+/// minus the fetch fallback, which replaces the fetch rather than
+/// following it.
+fn forward_successors(node: &Node) -> Vec<NodeId> {
 	match node {
 		Node::Check { on_match, on_miss, .. } => vec![*on_match, *on_miss],
 		Node::Middleware { next, on_error, .. } => {
@@ -234,7 +329,7 @@ pub fn check_phases(graph: &SymbolicFlowGraph) -> Result<(), Error> {
 
 fn check_phases_collecting(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
 	let mut seen: HashSet<(NodeId, Phase)> = HashSet::new();
-	for &entry in graph.entries.values() {
+	for entry in graph.entry_nodes() {
 		if let Err(e) = visit_phase(graph, entry, Phase::L4Raw, &mut seen) {
 			d.push(e);
 		}
@@ -269,16 +364,21 @@ fn visit_phase(
 			e.got,
 		))
 	})?;
+	// A fetch fallback runs in place of the fetch, so its target starts
+	// from the fetch's own input phase.
+	if let Node::Fetch { on_error: Some(target), .. } = node {
+		visit_phase(graph, *target, phase, seen)?;
+	}
 	match (t, node) {
 		(Transition::Terminal, _) => Ok(()),
 		(Transition::PassThrough, _) => {
-			for succ in successors(node) {
+			for succ in forward_successors(node) {
 				visit_phase(graph, succ, phase, seen)?;
 			}
 			Ok(())
 		}
 		(Transition::Into(next_phase), _) => {
-			for succ in successors(node) {
+			for succ in forward_successors(node) {
 				visit_phase(graph, succ, next_phase, seen)?;
 			}
 			Ok(())
@@ -301,6 +401,39 @@ fn visit_phase(
 	}
 }
 
+/// Prove that every fetch carrying an `on_error` fallback is only
+/// reachable with the request body already buffered: some node on
+/// every path from a listener entry up to and including the fetch has
+/// `collect_body_before: Some(Request)`. The executor replays that
+/// buffered body into the fallback; a streamed body would be gone by
+/// the time the first fetch fails.
+///
+/// Walks `(node, buffered)` states, so the visited set is
+/// `O(nodes * 2)`. Each offending fetch is reported once.
+fn check_fallback_body_buffered(graph: &SymbolicFlowGraph, d: &mut Diagnostics) {
+	use crate::ir::BodySide;
+	let mut visited: HashSet<(NodeId, bool)> = HashSet::new();
+	let mut reported: HashSet<NodeId> = HashSet::new();
+	let mut stack: Vec<(NodeId, bool)> = graph.entry_nodes().map(|e| (e, false)).collect();
+	while let Some((id, buffered)) = stack.pop() {
+		if !visited.insert((id, buffered)) {
+			continue;
+		}
+		let node = &graph[id];
+		let buffered = buffered || node.collect_body_before() == Some(BodySide::Request);
+		if let Node::Fetch { on_error: Some(_), .. } = node
+			&& !buffered
+			&& reported.insert(id)
+		{
+			d.push(Error::compile(format!(
+				"node {}: fetch fallback is reachable with an unbuffered request body; the fetch or a node before it must set collect_body_before=Some(Request)",
+				id.get(),
+			)));
+		}
+		stack.extend(successors(node).into_iter().map(|s| (s, buffered)));
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::HashMap;
@@ -321,6 +454,8 @@ mod tests {
 			listener_tls: std::collections::BTreeMap::new(),
 			listener_kinds: std::collections::BTreeMap::new(),
 			listener_transports: std::collections::BTreeMap::new(),
+			listener_proxy_protocol: std::collections::BTreeMap::new(),
+			unix_listeners: std::collections::BTreeMap::new(),
 			annotations: Vec::new(),
 		}
 	}
@@ -383,17 +518,14 @@ mod tests {
 				id: FetchId::new(0),
 				next_response: Some(NodeId::new(99)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			}],
 			predicates: vec![],
 			middlewares: vec![],
-			fetches: vec![SymbolicFetchRef {
-				kind: FetchKind::HttpProxy,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			}],
+			fetches: vec![SymbolicFetchRef::new(FetchKind::HttpProxy, serde_json::Value::Null)],
 			terminators: vec![],
 			entries: HashMap::new(),
 			meta: empty_meta(),
@@ -412,18 +544,15 @@ mod tests {
 					id: FetchId::new(0),
 					next_response: None,
 					next_tunnel: None,
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
 			],
 			predicates: vec![],
 			middlewares: vec![],
-			fetches: vec![SymbolicFetchRef {
-				kind: FetchKind::HttpProxy,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			}],
+			fetches: vec![SymbolicFetchRef::new(FetchKind::HttpProxy, serde_json::Value::Null)],
 			terminators: vec![Terminator::WriteHttpResponse],
 			entries: HashMap::new(),
 			meta: empty_meta(),
@@ -441,18 +570,15 @@ mod tests {
 					id: FetchId::new(0),
 					next_response: Some(NodeId::new(0)),
 					next_tunnel: Some(NodeId::new(0)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
 			],
 			predicates: vec![],
 			middlewares: vec![],
-			fetches: vec![SymbolicFetchRef {
-				kind: FetchKind::L4Forward,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			}],
+			fetches: vec![SymbolicFetchRef::new(FetchKind::L4Forward, serde_json::Value::Null)],
 			terminators: vec![Terminator::ByteTunnel],
 			entries: HashMap::new(),
 			meta: empty_meta(),
@@ -557,30 +683,15 @@ mod tests {
 	use crate::middleware::{MiddlewareKind, SymbolicMiddlewareRef};
 
 	fn http_fetch_ref() -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: serde_json::Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(FetchKind::HttpProxy, serde_json::Value::Null)
 	}
 
 	fn ws_fetch_ref() -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::WebSocketUpgrade,
-			args: serde_json::Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(FetchKind::WebSocketUpgrade, serde_json::Value::Null)
 	}
 
 	fn l4_fetch_ref() -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: serde_json::Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(FetchKind::L4Forward, serde_json::Value::Null)
 	}
 
 	fn dummy_middleware_ref() -> SymbolicMiddlewareRef {
@@ -727,6 +838,8 @@ mod tests {
 				id: FetchId::new(7),
 				next_response: Some(NodeId::new(0)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			}],
@@ -747,6 +860,8 @@ mod tests {
 				id: FetchId::new(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::new(42)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			}],
@@ -784,6 +899,8 @@ mod tests {
 					id: FetchId::new(0),
 					next_response: Some(NodeId::new(0)),
 					next_tunnel: Some(NodeId::new(0)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -805,6 +922,8 @@ mod tests {
 				id: FetchId::new(0),
 				next_response: None,
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			}],
@@ -828,6 +947,8 @@ mod tests {
 					id: FetchId::new(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::new(0)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -871,6 +992,150 @@ mod tests {
 		assert!(check_phases(&graph).is_err());
 	}
 
+	#[test]
+	fn validate_rejects_bad_header_template() {
+		let headers = |args: serde_json::Value| SymbolicMiddlewareRef {
+			name: std::sync::Arc::from("response_headers"),
+			args,
+			kind: MiddlewareKind::L7Response,
+			..dummy_middleware_ref()
+		};
+		let graph = SymbolicFlowGraph {
+			nodes: vec![],
+			predicates: vec![],
+			middlewares: vec![
+				headers(serde_json::json!({ "set": { "x-client-cn": "${tls.peer_cert.subject_cn}" } })),
+				headers(serde_json::json!({ "add": { "x-peer": "${tls.peer.cn}" } })),
+			],
+			fetches: vec![],
+			terminators: vec![],
+			entries: HashMap::new(),
+			meta: empty_meta(),
+		};
+		let d = validate_collecting(&graph);
+		assert_eq!(d.len(), 1, "{d}");
+		let msg = d.to_string();
+		assert!(msg.contains("middleware 1 (response_headers): args.add.x-peer"), "{msg}");
+		assert!(msg.contains("unknown field path"), "{msg}");
+	}
+
+	#[test]
+	fn validate_rejects_bad_rewrite_and_redirect_patterns() {
+		let mw = |name: &str, args: serde_json::Value| SymbolicMiddlewareRef {
+			name: std::sync::Arc::from(name),
+			args,
+			kind: MiddlewareKind::L7Request,
+			..dummy_middleware_ref()
+		};
+		let graph = SymbolicFlowGraph {
+			nodes: vec![],
+			predicates: vec![],
+			middlewares: vec![
+				mw("rewrite", serde_json::json!({ "replace": { "pattern": "^(a+)*b\\1$", "with": "/" } })),
+				mw(
+					"rewrite",
+					serde_json::json!({ "replace": { "pattern": "^/u/(\\d+)$", "with": "/users/$2" } }),
+				),
+				mw("redirect", serde_json::json!({ "pattern": "^/blog/(.*)$", "location": "/posts/$2" })),
+				mw(
+					"redirect",
+					serde_json::json!({ "pattern": "^/ok/(.*)$", "location": "https://${http.uri.path}/$1" }),
+				),
+			],
+			fetches: vec![],
+			terminators: vec![],
+			entries: HashMap::new(),
+			meta: empty_meta(),
+		};
+		let d = validate_collecting(&graph);
+		let msg = d.to_string();
+		assert_eq!(d.len(), 3, "{msg}");
+		assert!(msg.contains("middleware 0 (rewrite): args.replace.pattern"), "{msg}");
+		assert!(msg.contains("backtrack"), "{msg}");
+		assert!(msg.contains("middleware 1 (rewrite): args.replace.with"), "{msg}");
+		assert!(msg.contains("middleware 2 (redirect): args.location"), "{msg}");
+		assert!(msg.contains("capture group $2 does not exist"), "{msg}");
+	}
+
+	/// `Upgrade → Fetch(primary, on_error → fallback) → Terminate`, the
+	/// fallback a second `HttpProxy` into the same terminator.
+	fn fallback_graph(collect: Option<BodySide>, on_error: NodeId) -> SymbolicFlowGraph {
+		let fetch = |id: u32, on_error: Option<NodeId>, collect_body_before| Node::Fetch {
+			id: FetchId::new(id),
+			next_response: Some(NodeId::new(0)),
+			next_tunnel: None,
+			on_error,
+			on_error_status: Vec::new(),
+			collect_body_before,
+			body_limit: 1024,
+		};
+		SymbolicFlowGraph {
+			nodes: vec![
+				Node::Terminate(TerminatorId::new(0)),
+				fetch(1, None, None),
+				fetch(0, Some(on_error), collect),
+				Node::Upgrade { next: NodeId::new(2) },
+			],
+			predicates: vec![],
+			middlewares: vec![],
+			fetches: vec![http_fetch_ref(), http_fetch_ref()],
+			terminators: vec![Terminator::WriteHttpResponse],
+			entries: {
+				let mut m = HashMap::new();
+				m.insert("127.0.0.1:443".parse().expect("parse"), NodeId::new(3));
+				m
+			},
+			meta: empty_meta(),
+		}
+	}
+
+	#[test]
+	fn validate_accepts_fetch_fallback_with_buffered_body() {
+		validate(&fallback_graph(Some(BodySide::Request), NodeId::new(1))).expect("valid");
+	}
+
+	#[test]
+	fn validate_rejects_fetch_fallback_with_unbuffered_body() {
+		assert_err_contains(
+			&fallback_graph(None, NodeId::new(1)),
+			"node 2: fetch fallback is reachable with an unbuffered request body",
+		);
+	}
+
+	#[test]
+	fn validate_rejects_fetch_fallback_entered_past_the_request_phase() {
+		// The fallback replaces the fetch, so it starts in `L7Request`;
+		// pointing it straight at the response terminator skips a fetch.
+		assert_err_contains(
+			&fallback_graph(Some(BodySide::Request), NodeId::new(0)),
+			"phase mismatch at NodeId(0)",
+		);
+	}
+
+	#[test]
+	fn validate_rejects_dangling_on_error_in_fetch() {
+		assert_err_contains(
+			&fallback_graph(Some(BodySide::Request), NodeId::new(99)),
+			"node 2.on_error dangling",
+		);
+	}
+
+	#[test]
+	fn validate_rejects_on_error_on_l4_forward() {
+		let mut graph = fallback_graph(Some(BodySide::Request), NodeId::new(1));
+		graph.fetches[0] = l4_fetch_ref();
+		assert_err_contains(&graph, "L4Forward cannot carry on_error");
+	}
+
+	#[test]
+	fn validate_rejects_on_error_status_without_on_error() {
+		let mut graph = fallback_graph(Some(BodySide::Request), NodeId::new(1));
+		if let Node::Fetch { on_error_status, .. } = &mut graph.nodes[1] {
+			*on_error_status = vec![503];
+		}
+		assert_err_contains(&graph, "node 1: on_error_status without an on_error edge");
+	}
+
 	// `BodySide` import is kept here to keep test doc consistent with the
 	// `Node` field it accesses in the broader impl.
 	const _: BodySide = BodySide::Request;
@@ -33,7 +33,7 @@ impl EnvReader for ProcessEnv {
 /// CLI arg is the single source of truth, and [`Env::from_reader`]
 /// takes that path explicitly so derived defaults (`wasm_dir`) follow
 /// it without an extra env var to keep in sync.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(clippy::struct_excessive_bools)] // each bool maps 1:1 to a documented env var; collapsing them into bitflags would obscure the surface.
 pub struct Env {
 	/// `VANE_WASM_DIR` — WASM plugin source directory scanned at boot.
@@ -61,6 +61,37 @@ pub struct Env {
 	pub sec_max_conn_per_ip: u32,
 	/// `VANE_SEC_MAX_TOTAL_CONNS` — daemon-wide concurrent-connection cap (default 65536).
 	pub sec_max_total_conns: u32,
+	/// `VANE_SEC_BODY_IDLE_TIMEOUT` — request-body idle timeout, seconds (default 30).
+	pub sec_body_idle_timeout_secs: u32,
+	/// `VANE_SEC_MAX_HANDSHAKE_RATE_PER_IP` — TLS / QUIC handshakes per second per IP (default 10).
+	pub sec_max_handshake_rate_per_ip: u32,
+	/// `VANE_SEC_MAX_PENDING_HANDSHAKES` — daemon-wide in-progress handshake cap (default 1000).
+	pub sec_max_pending_handshakes: u32,
+	/// `VANE_SEC_MAX_STREAMS_PER_CONN` — in-flight H2 / H3 streams per connection (default 100).
+	pub sec_max_streams_per_conn: u32,
+	/// `VANE_SEC_MAX_REQUEST_RATE_PER_CONN` — H2 / H3 requests per second per connection (default 1000).
+	pub sec_max_request_rate_per_conn: u32,
+	/// `VANE_SEC_BAN_STRIKES` — per-IP L1 limit hits plus `rate_limit`
+	/// rejections within `VANE_SEC_BAN_WINDOW` that ban the IP (default
+	/// 0 = no automatic bans on strikes).
+	pub sec_ban_strikes: u32,
+	/// `VANE_SEC_BAN_CLIENT_ERRORS` — per-IP `4xx` responses within
+	/// `VANE_SEC_BAN_WINDOW` that ban the IP (default 0 = off).
+	pub sec_ban_client_errors: u32,
+	/// `VANE_SEC_BAN_WINDOW` — offence counting window, seconds (default 60).
+	pub sec_ban_window_secs: u32,
+	/// `VANE_SEC_BAN_DURATION` — first ban of an IP, seconds (default 600).
+	/// Each repeat ban doubles it, up to `VANE_SEC_BAN_MAX_DURATION`.
+	pub sec_ban_duration_secs: u32,
+	/// `VANE_SEC_BAN_MAX_DURATION` — escalation cap, seconds (default 86400).
+	pub sec_ban_max_duration_secs: u32,
+	/// `VANE_SEC_BAN_ALLOW` — comma-separated CIDRs that are never banned,
+	/// automatically or by the `ban` verb (default `127.0.0.0/8,::1/128`;
+	/// an explicit empty string allow-lists nothing).
+	pub sec_ban_allow: Vec<ipnet::IpNet>,
+	/// `VANE_SEC_BAN_PERSIST` — when truthy, keep the ban table in
+	/// `<state dir>/bans.json` across restarts (default off).
+	pub sec_ban_persist: bool,
 	/// `VANE_BIND_MAX_ATTEMPTS` — bind-retry count per listener address (default 10).
 	pub bind_max_attempts: u32,
 	/// `VANE_BIND_BACKOFF_INITIAL_MS` — initial retry backoff in milliseconds (default 100).
@@ -95,6 +126,13 @@ pub struct Env {
 	/// `VANE_MGMT_HTTP_TOKEN` — bearer token for the HTTP management
 	/// transport (`None` when unset or empty string).
 	pub mgmt_http_token: Option<String>,
+	/// `VANE_METRICS_HTTP_BIND` — socket address of the unauthenticated
+	/// scrape listener (`GET /metrics`, `/healthz`, `/readyz`). `None`
+	/// (default, or an empty string) leaves it off.
+	pub metrics_http_bind: Option<std::net::SocketAddr>,
+	/// `VANE_METRICS_HTTP_ALLOW` — comma-separated source CIDRs admitted
+	/// by the scrape listener. Empty (default) admits any source.
+	pub metrics_http_allow: Vec<ipnet::IpNet>,
 	/// `VANE_NATIVE_ROOTS_REFRESH_INTERVAL_SECS` — cadence at which
 	/// the daemon re-reads the OS native trust store, in seconds
 	/// (default 21 600 = 6h). The refresh is non-blocking; failures
@@ -111,6 +149,40 @@ pub struct Env {
 	/// per-upstream override; the per-upstream flag still has to be
 	/// set explicitly — the env var alone never weakens verification.
 	pub allow_insecure_upstream: bool,
+	/// `VANE_HEDGE_BUDGET_PERCENT` — daemon-wide cap on hedged upstream
+	/// requests, as a percentage of hedge-eligible requests (default 10,
+	/// at most 100). `0` disables hedging regardless of rule config.
+	pub hedge_budget_percent: u8,
+	/// `VANE_OTEL_ENDPOINT` — OTLP collector URL spans are exported to
+	/// (e.g. `http://127.0.0.1:4318` for HTTP, `http://127.0.0.1:4317`
+	/// for gRPC). `None` (default, or an empty string) turns export off.
+	pub otel_endpoint: Option<String>,
+	/// `VANE_OTEL_PROTOCOL` — OTLP transport: `http/protobuf` (default)
+	/// or `grpc`.
+	pub otel_protocol: OtlpProtocol,
+	/// `VANE_OTEL_SAMPLE_RATIO` — head-sampling ratio for traces vane
+	/// starts, `0.0`–`1.0` (default `1.0`). A request carrying a
+	/// `traceparent` from a trusted source
+	/// (`VANE_OTEL_PROPAGATION_ALLOW`) follows the caller's sampled
+	/// flag instead.
+	pub otel_sample_ratio: f64,
+	/// `VANE_OTEL_SERVICE_NAME` — `service.name` resource attribute on
+	/// exported spans (default `vaned`).
+	pub otel_service_name: String,
+	/// `VANE_OTEL_PROPAGATION_ALLOW` — comma-separated source CIDRs whose
+	/// `traceparent` vane continues. Any other source starts a new trace
+	/// that links to the caller's. Empty (default) trusts no source.
+	pub otel_propagation_allow: Vec<ipnet::IpNet>,
+}
+
+/// OTLP export transport selected by `VANE_OTEL_PROTOCOL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OtlpProtocol {
+	/// Protobuf over HTTP/1.1 `POST <endpoint>/v1/traces`.
+	#[default]
+	HttpProtobuf,
+	/// OTLP/gRPC.
+	Grpc,
 }
 
 impl Env {
@@ -145,6 +217,26 @@ impl Env {
 			sec_header_timeout_secs: parse_u32_default(r, "VANE_SEC_HEADER_TIMEOUT", 30)?,
 			sec_max_conn_per_ip: parse_u32_default(r, "VANE_SEC_MAX_CONN_PER_IP", 100)?,
 			sec_max_total_conns: parse_u32_default(r, "VANE_SEC_MAX_TOTAL_CONNS", 65_536)?,
+			sec_body_idle_timeout_secs: parse_u32_default(r, "VANE_SEC_BODY_IDLE_TIMEOUT", 30)?,
+			sec_max_handshake_rate_per_ip: parse_u32_default(
+				r,
+				"VANE_SEC_MAX_HANDSHAKE_RATE_PER_IP",
+				10,
+			)?,
+			sec_max_pending_handshakes: parse_u32_default(r, "VANE_SEC_MAX_PENDING_HANDSHAKES", 1_000)?,
+			sec_max_streams_per_conn: parse_u32_default(r, "VANE_SEC_MAX_STREAMS_PER_CONN", 100)?,
+			sec_max_request_rate_per_conn: parse_u32_default(
+				r,
+				"VANE_SEC_MAX_REQUEST_RATE_PER_CONN",
+				1_000,
+			)?,
+			sec_ban_strikes: parse_u32_default(r, "VANE_SEC_BAN_STRIKES", 0)?,
+			sec_ban_client_errors: parse_u32_default(r, "VANE_SEC_BAN_CLIENT_ERRORS", 0)?,
+			sec_ban_window_secs: parse_u32_default(r, "VANE_SEC_BAN_WINDOW", 60)?,
+			sec_ban_duration_secs: parse_u32_default(r, "VANE_SEC_BAN_DURATION", 600)?,
+			sec_ban_max_duration_secs: parse_u32_default(r, "VANE_SEC_BAN_MAX_DURATION", 86_400)?,
+			sec_ban_allow: parse_cidr_list(r, "VANE_SEC_BAN_ALLOW", "127.0.0.0/8,::1/128")?,
+			sec_ban_persist: parse_truthy(r, "VANE_SEC_BAN_PERSIST"),
 			bind_max_attempts: parse_u32_default(r, "VANE_BIND_MAX_ATTEMPTS", 10)?,
 			bind_backoff_initial_ms: parse_u32_default(r, "VANE_BIND_BACKOFF_INITIAL_MS", 100)?,
 			bind_backoff_max_ms: parse_u32_default(r, "VANE_BIND_BACKOFF_MAX_MS", 5_000)?,
@@ -158,12 +250,23 @@ impl Env {
 			mgmt_http_port: parse_http_port(r)?,
 			mgmt_http_public: parse_truthy(r, "VANE_MGMT_HTTP_PUBLIC"),
 			mgmt_http_token: r.get("VANE_MGMT_HTTP_TOKEN").filter(|s| !s.is_empty()),
+			metrics_http_bind: parse_socket_addr(r, "VANE_METRICS_HTTP_BIND")?,
+			metrics_http_allow: parse_cidr_list(r, "VANE_METRICS_HTTP_ALLOW", "")?,
 			native_roots_refresh_interval_secs: parse_u32_default(
 				r,
 				"VANE_NATIVE_ROOTS_REFRESH_INTERVAL_SECS",
 				21_600,
 			)?,
 			allow_insecure_upstream: parse_truthy(r, "VANE_ALLOW_INSECURE_UPSTREAM"),
+			hedge_budget_percent: parse_percent_default(r, "VANE_HEDGE_BUDGET_PERCENT", 10)?,
+			otel_endpoint: r.get("VANE_OTEL_ENDPOINT").filter(|s| !s.is_empty()),
+			otel_protocol: parse_otlp_protocol(r)?,
+			otel_sample_ratio: parse_ratio_default(r, "VANE_OTEL_SAMPLE_RATIO", 1.0)?,
+			otel_service_name: r
+				.get("VANE_OTEL_SERVICE_NAME")
+				.filter(|s| !s.is_empty())
+				.unwrap_or_else(|| "vaned".to_string()),
+			otel_propagation_allow: parse_cidr_list(r, "VANE_OTEL_PROPAGATION_ALLOW", "")?,
 		})
 	}
 }
@@ -202,6 +305,69 @@ fn parse_u32_default<R: EnvReader>(r: &R, key: &str, default: u32) -> Result<u32
 	}
 }
 
+fn parse_percent_default<R: EnvReader>(r: &R, key: &str, default: u8) -> Result<u8, Error> {
+	let n = parse_u32_default(r, key, u32::from(default))?;
+	u8::try_from(n)
+		.ok()
+		.filter(|p| *p <= 100)
+		.ok_or_else(|| Error::compile(format!("{key} must be between 0 and 100, got {n}")))
+}
+
+fn parse_ratio_default<R: EnvReader>(r: &R, key: &str, default: f64) -> Result<f64, Error> {
+	match r.get(key).filter(|s| !s.is_empty()) {
+		None => Ok(default),
+		Some(s) => s
+			.parse::<f64>()
+			.ok()
+			.filter(|v| (0.0..=1.0).contains(v))
+			.ok_or_else(|| Error::compile(format!("{key} must be between 0.0 and 1.0, got {s:?}"))),
+	}
+}
+
+fn parse_otlp_protocol<R: EnvReader>(r: &R) -> Result<OtlpProtocol, Error> {
+	match r.get("VANE_OTEL_PROTOCOL").as_deref() {
+		None | Some("" | "http/protobuf") => Ok(OtlpProtocol::HttpProtobuf),
+		Some("grpc") => Ok(OtlpProtocol::Grpc),
+		Some(other) => Err(Error::compile(format!(
+			"VANE_OTEL_PROTOCOL must be \"http/protobuf\" or \"grpc\", got {other:?}"
+		))),
+	}
+}
+
+/// Comma-separated CIDR list; a bare address is its host prefix. Unset
+/// takes `default`; an explicit empty string is the empty list.
+fn parse_cidr_list<R: EnvReader>(
+	r: &R,
+	key: &str,
+	default: &str,
+) -> Result<Vec<ipnet::IpNet>, Error> {
+	let raw = r.get(key).unwrap_or_else(|| default.to_string());
+	raw
+		.split(',')
+		.map(str::trim)
+		.filter(|s| !s.is_empty())
+		.map(|s| {
+			s.parse::<ipnet::IpNet>()
+				.or_else(|_| s.parse::<std::net::IpAddr>().map(ipnet::IpNet::from))
+				.map_err(|e| Error::compile(format!("{key}: {e} ({s:?})")))
+		})
+		.collect()
+}
+
+/// Optional `ip:port`; unset or empty is `None`.
+fn parse_socket_addr<R: EnvReader>(
+	r: &R,
+	key: &str,
+) -> Result<Option<std::net::SocketAddr>, Error> {
+	match r.get(key).filter(|s| !s.is_empty()) {
+		None => Ok(None),
+		Some(s) => s
+			.parse::<std::net::SocketAddr>()
+			.map(Some)
+			.map_err(|e| Error::compile(format!("{key}: {e} ({s:?})"))),
+	}
+}
+
 /// Parse `VANE_MGMT_HTTP_PORT`. Unset → default `Some(3333)`; explicit
 /// empty string → `None` (transport disabled). Anything else parses as
 /// a `u16`.
@@ -262,12 +428,40 @@ mod tests {
 		assert_eq!(env.sec_header_timeout_secs, 30);
 		assert_eq!(env.sec_max_conn_per_ip, 100);
 		assert_eq!(env.sec_max_total_conns, 65_536);
+		assert_eq!(env.sec_body_idle_timeout_secs, 30);
+		assert_eq!(env.sec_max_handshake_rate_per_ip, 10);
+		assert_eq!(env.sec_max_pending_handshakes, 1_000);
+		assert_eq!(env.sec_max_streams_per_conn, 100);
+		assert_eq!(env.sec_max_request_rate_per_conn, 1_000);
 		// `/run/vaned.sock` is the no-XDG_RUNTIME_DIR fallback. `/tmp`
 		// is never the default — see `default_mgmt_unix` for rationale.
 		assert_eq!(env.mgmt_unix, PathBuf::from("/run/vaned.sock"));
 		assert_eq!(env.mgmt_http_port, Some(3333));
 		assert!(!env.mgmt_http_public);
 		assert!(env.mgmt_http_token.is_none());
+		assert!(env.metrics_http_bind.is_none());
+		assert!(env.metrics_http_allow.is_empty());
+		assert_eq!(env.hedge_budget_percent, 10);
+		assert!(env.otel_endpoint.is_none());
+		assert_eq!(env.otel_protocol, OtlpProtocol::HttpProtobuf);
+		assert!((env.otel_sample_ratio - 1.0).abs() < f64::EPSILON);
+		assert_eq!(env.otel_service_name, "vaned");
+	}
+
+	#[test]
+	fn otel_propagation_trusts_no_source_by_default() {
+		let env = Env::from_reader(&FakeEnv::empty(), &cfg()).expect("defaults");
+		assert!(env.otel_propagation_allow.is_empty());
+	}
+
+	#[test]
+	fn env_hedge_budget_percent_is_bounded() {
+		let env = Env::from_reader(&FakeEnv::with(&[("VANE_HEDGE_BUDGET_PERCENT", "0")]), &cfg())
+			.expect("zero disables hedging");
+		assert_eq!(env.hedge_budget_percent, 0);
+		let err = Env::from_reader(&FakeEnv::with(&[("VANE_HEDGE_BUDGET_PERCENT", "101")]), &cfg())
+			.expect_err("over 100");
+		assert!(err.to_string().contains("VANE_HEDGE_BUDGET_PERCENT"), "{err}");
 	}
 
 	#[test]
@@ -314,6 +508,11 @@ mod tests {
 				("VANE_SEC_MAX_HEADERS_COUNT", "64"),
 				("VANE_SEC_HEADER_TIMEOUT", "10"),
 				("VANE_SEC_MAX_CONN_PER_IP", "500"),
+				("VANE_SEC_BODY_IDLE_TIMEOUT", "15"),
+				("VANE_SEC_MAX_HANDSHAKE_RATE_PER_IP", "50"),
+				("VANE_SEC_MAX_PENDING_HANDSHAKES", "4000"),
+				("VANE_SEC_MAX_STREAMS_PER_CONN", "250"),
+				("VANE_SEC_MAX_REQUEST_RATE_PER_CONN", "5000"),
 			]),
 			&cfg(),
 		)
@@ -322,6 +521,42 @@ mod tests {
 		assert_eq!(env.sec_max_headers_count, 64);
 		assert_eq!(env.sec_header_timeout_secs, 10);
 		assert_eq!(env.sec_max_conn_per_ip, 500);
+		assert_eq!(env.sec_body_idle_timeout_secs, 15);
+		assert_eq!(env.sec_max_handshake_rate_per_ip, 50);
+		assert_eq!(env.sec_max_pending_handshakes, 4_000);
+		assert_eq!(env.sec_max_streams_per_conn, 250);
+		assert_eq!(env.sec_max_request_rate_per_conn, 5_000);
+	}
+
+	#[test]
+	fn env_sec_ban_defaults() {
+		let env = Env::from_reader(&FakeEnv::empty(), &cfg()).expect("ok");
+		assert_eq!(env.sec_ban_strikes, 0);
+		assert_eq!(env.sec_ban_client_errors, 0);
+		assert_eq!(env.sec_ban_window_secs, 60);
+		assert_eq!(env.sec_ban_duration_secs, 600);
+		assert_eq!(env.sec_ban_max_duration_secs, 86_400);
+		assert_eq!(
+			env.sec_ban_allow,
+			vec!["127.0.0.0/8".parse::<ipnet::IpNet>().unwrap(), "::1/128".parse().unwrap()]
+		);
+		assert!(!env.sec_ban_persist);
+	}
+
+	#[test]
+	fn env_sec_ban_allow_parses_cidrs_and_bare_addresses() {
+		let env =
+			Env::from_reader(&FakeEnv::with(&[("VANE_SEC_BAN_ALLOW", "10.0.0.0/8, 192.0.2.7")]), &cfg())
+				.expect("ok");
+		assert_eq!(
+			env.sec_ban_allow,
+			vec!["10.0.0.0/8".parse::<ipnet::IpNet>().unwrap(), "192.0.2.7/32".parse().unwrap()]
+		);
+		let env = Env::from_reader(&FakeEnv::with(&[("VANE_SEC_BAN_ALLOW", "")]), &cfg()).expect("ok");
+		assert!(env.sec_ban_allow.is_empty(), "empty string allow-lists nothing");
+		let err = Env::from_reader(&FakeEnv::with(&[("VANE_SEC_BAN_ALLOW", "10.0.0.0/33")]), &cfg())
+			.expect_err("bad prefix");
+		assert!(err.to_string().contains("VANE_SEC_BAN_ALLOW"), "{err}");
 	}
 
 	#[test]
@@ -400,6 +635,70 @@ mod tests {
 		assert_eq!(env.mgmt_http_token.as_deref(), Some("hunter2"));
 	}
 
+	#[test]
+	fn env_metrics_http_bind_and_allow_parse() {
+		let env = Env::from_reader(
+			&FakeEnv::with(&[
+				("VANE_METRICS_HTTP_BIND", "0.0.0.0:9100"),
+				("VANE_METRICS_HTTP_ALLOW", "10.0.0.0/8, 192.0.2.7"),
+			]),
+			&cfg(),
+		)
+		.expect("ok");
+		assert_eq!(env.metrics_http_bind, Some("0.0.0.0:9100".parse().unwrap()));
+		assert_eq!(
+			env.metrics_http_allow,
+			vec!["10.0.0.0/8".parse::<ipnet::IpNet>().unwrap(), "192.0.2.7/32".parse().unwrap()]
+		);
+		let off =
+			Env::from_reader(&FakeEnv::with(&[("VANE_METRICS_HTTP_BIND", "")]), &cfg()).expect("ok");
+		assert!(off.metrics_http_bind.is_none(), "empty string leaves the listener off");
+	}
+
+	#[test]
+	fn env_otel_settings_parse() {
+		let env = Env::from_reader(
+			&FakeEnv::with(&[
+				("VANE_OTEL_ENDPOINT", "http://collector:4317"),
+				("VANE_OTEL_PROTOCOL", "grpc"),
+				("VANE_OTEL_SAMPLE_RATIO", "0.25"),
+				("VANE_OTEL_SERVICE_NAME", "edge"),
+				("VANE_OTEL_PROPAGATION_ALLOW", "10.0.0.0/8, 192.0.2.7"),
+			]),
+			&cfg(),
+		)
+		.expect("ok");
+		assert_eq!(env.otel_endpoint.as_deref(), Some("http://collector:4317"));
+		assert_eq!(env.otel_protocol, OtlpProtocol::Grpc);
+		assert!((env.otel_sample_ratio - 0.25).abs() < f64::EPSILON);
+		assert_eq!(env.otel_service_name, "edge");
+		assert_eq!(
+			env.otel_propagation_allow,
+			vec!["10.0.0.0/8".parse::<ipnet::IpNet>().unwrap(), "192.0.2.7/32".parse().unwrap()]
+		);
+	}
+
+	#[test]
+	fn env_otel_invalid_values_error() {
+		for (key, value) in [
+			("VANE_OTEL_PROTOCOL", "http/json"),
+			("VANE_OTEL_SAMPLE_RATIO", "1.5"),
+			("VANE_OTEL_SAMPLE_RATIO", "half"),
+			("VANE_OTEL_PROPAGATION_ALLOW", "10.0.0.0/33"),
+		] {
+			let err = Env::from_reader(&FakeEnv::with(&[(key, value)]), &cfg())
+				.expect_err("invalid value must error");
+			assert!(err.to_string().contains(key), "{key}={value}: {err}");
+		}
+	}
+
+	#[test]
+	fn env_metrics_http_bind_invalid_errors() {
+		let err = Env::from_reader(&FakeEnv::with(&[("VANE_METRICS_HTTP_BIND", "9100")]), &cfg())
+			.expect_err("bare port is not a socket address");
+		assert!(err.to_string().contains("VANE_METRICS_HTTP_BIND"));
+	}
+
 	#[test]
 	fn env_mgmt_unix_default_path() {
 		let env = Env::from_reader(&FakeEnv::empty(), &cfg()).expect("defaults");
@@ -19,7 +19,7 @@
 mod env;
 mod loader;
 
-pub use env::{Env, EnvReader, ProcessEnv};
+pub use env::{Env, EnvReader, OtlpProtocol, ProcessEnv};
 pub use loader::scan_rules_dir;
 
 use std::path::Path;
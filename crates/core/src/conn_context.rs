@@ -7,6 +7,8 @@ use bytes::Bytes;
 use parking_lot::Mutex;
 use rustls_pki_types::CertificateDer;
 
+use crate::geo::{GeoInfo, lookup_geo};
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ConnId(pub u64);
 
@@ -49,6 +51,11 @@ pub struct TlsInfo {
 	/// hot `alpn.clone()` patterns now bump a refcount.
 	pub alpn: Option<Arc<[u8]>>,
 	pub version: Option<TlsVersion>,
+	/// JA3 / JA4 fingerprints of the client's `ClientHello`, stamped
+	/// by the listener when it peeks the hello (TCP peek prelude, QUIC
+	/// pending-peek). `None` on connections that were never peeked.
+	pub ja3: Option<Arc<str>>,
+	pub ja4: Option<Arc<str>>,
 	pub peer_cert: Option<Arc<PeerCertificate>>,
 	/// Whether the client's request arrived (in part or wholly) as
 	/// TLS 1.3 0-RTT (early data). Set at handshake completion in the
@@ -60,6 +67,50 @@ pub struct TlsInfo {
 	pub zero_rtt_used: bool,
 }
 
+/// Credentials of the process on the other end of a Unix socket
+/// connection (`SO_PEERCRED`), captured by the listener at accept.
+/// Backs the `remote.uid` / `remote.gid` / `remote.pid` predicate
+/// fields; absent on network connections. See `spec/crates/engine.md`
+/// § _Unix listeners_.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PeerCred {
+	pub uid: u32,
+	pub gid: u32,
+	/// `None` where the platform does not report the peer pid.
+	pub pid: Option<i32>,
+}
+
+/// What a trusted load balancer told us in its PROXY protocol header.
+/// Stamped by the listener once the header is consumed; absent on
+/// connections that carried none. `ConnContext::remote` already holds
+/// the header's source, so this keeps the balancer's own address and
+/// the v2 TLVs. See `spec/crates/engine.md` § _PROXY protocol_.
+#[derive(Clone, Debug)]
+pub struct ProxyInfo {
+	/// The balancer's socket address — the TCP peer before the header
+	/// replaced `remote`.
+	pub peer: SocketAddr,
+	/// v2 `AUTHORITY` TLV: the host name the client asked the balancer
+	/// for, usually its TLS SNI.
+	pub authority: Option<Arc<str>>,
+	/// v2 `UNIQUE_ID` TLV, opaque bytes (at most 128 per the spec).
+	pub unique_id: Option<Bytes>,
+	/// v2 `SSL` TLV, present when the balancer terminated TLS.
+	pub ssl: Option<ProxySslInfo>,
+}
+
+/// TLS session details the balancer observed on the client side.
+#[derive(Clone, Debug, Default)]
+pub struct ProxySslInfo {
+	/// e.g. `TLSv1.3`, as the balancer spelled it.
+	pub version: Option<Arc<str>>,
+	/// Client certificate subject CN.
+	pub cn: Option<Arc<str>>,
+	pub cipher: Option<Arc<str>>,
+	/// The client presented a certificate and the balancer verified it.
+	pub verified: bool,
+}
+
 /// Verified client certificate captured at TLS handshake time, with
 /// every predicate-readable field pre-extracted so the per-Check
 /// dispatch is allocation-light. Built once by the engine's
@@ -183,6 +234,15 @@ pub struct ConnContext {
 
 	pub tls: Mutex<Option<TlsInfo>>,
 	pub http_version: OnceLock<HttpVersion>,
+	/// `remote.*` geo attribution, resolved on first read by
+	/// [`ConnContext::geo`].
+	pub geo: OnceLock<GeoInfo>,
+	/// PROXY protocol header details, set by the listener before the
+	/// connection is dispatched.
+	pub proxy: OnceLock<ProxyInfo>,
+	/// Peer process credentials, set by the Unix listener before the
+	/// connection is dispatched.
+	pub peer_cred: OnceLock<PeerCred>,
 
 	pub user: Mutex<http::Extensions>,
 }
@@ -211,6 +271,9 @@ impl ConnContext {
 			entered_at,
 			tls: Mutex::new(None),
 			http_version: OnceLock::new(),
+			geo: OnceLock::new(),
+			proxy: OnceLock::new(),
+			peer_cred: OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		}
 	}
@@ -222,6 +285,14 @@ impl ConnContext {
 		self.tls.lock()
 	}
 
+	/// Geo / ASN attribution of the remote address. The first call
+	/// runs the daemon-wide lookup; later calls (every rule and every
+	/// request on the connection) reuse that answer, so a database
+	/// reload only affects connections accepted after it.
+	pub fn geo(&self) -> &GeoInfo {
+		self.geo.get_or_init(|| lookup_geo(self.remote.ip()))
+	}
+
 	/// Closure-scoped mutable access to the per-connection
 	/// extension map. Prefer this over directly grabbing
 	/// `conn.user.lock()` — the closure bound makes the lock window
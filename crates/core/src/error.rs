@@ -54,16 +54,26 @@ pub enum UpstreamReason {
 	Gone,
 	#[error("malformed response")]
 	Malformed,
+	/// The authority's circuit breaker is open; the request was
+	/// rejected locally and never dialled.
+	#[error("circuit open")]
+	CircuitOpen,
 }
 
 #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum TimeoutKind {
 	#[error("connect")]
 	Connect,
+	/// Waiting for a response head after the request was sent
+	/// (`http_proxy`'s `response_header_timeout`).
 	#[error("read")]
 	Read,
+	/// An overall deadline spanning every attempt and the response
+	/// body (`http_proxy`'s `total_timeout`).
 	#[error("total")]
 	Total,
+	/// Too long a gap between two body frames (`http_proxy`'s
+	/// `body_read_idle_timeout`).
 	#[error("idle")]
 	Idle,
 	#[error("handshake")]
@@ -209,6 +219,7 @@ impl Error {
 				UpstreamReason::Refused => "refused",
 				UpstreamReason::Gone => "gone",
 				UpstreamReason::Malformed => "malformed",
+				UpstreamReason::CircuitOpen => "circuit_open",
 			}),
 			ErrorKind::Timeout(t) => Some(match t {
 				TimeoutKind::Connect => "connect",
@@ -248,6 +259,7 @@ impl Error {
 					| UpstreamReason::DnsFailure
 					| UpstreamReason::Refused
 					| UpstreamReason::Gone
+					| UpstreamReason::CircuitOpen
 			),
 			ErrorKind::Timeout(TimeoutKind::Connect | TimeoutKind::Handshake)
 			| ErrorKind::Resource(ResourceKind::ConnectionPool) => true,
@@ -259,13 +271,16 @@ impl Error {
 	/// § _Error classification_:
 	///
 	/// - Pre-connect failures (TCP connect, TLS handshake, DNS,
-	///   connection-pool exhaustion, hyper-pool idle-pickup race)
-	///   return `true` regardless of method — the request never left
-	///   the wire, so retrying a POST is safe.
-	/// - Mid-request failures (`ResetMidRequest`) return `true`
+	///   connection-pool exhaustion, hyper-pool idle-pickup race, open
+	///   circuit breaker) return `true` regardless of method — the
+	///   request never left the wire, so retrying a POST is safe.
+	/// - Mid-request failures (`ResetMidRequest`, and a `Read`
+	///   timeout waiting for the response head) return `true`
 	///   ONLY for idempotent methods (GET / HEAD / PUT / DELETE /
 	///   OPTIONS, per RFC 9110 § 9.2.2). Retrying a non-idempotent
 	///   POST mid-request risks double-delivery.
+	/// - `Total` and `Idle` timeouts never retry: the deadline is
+	///   spent, or the response head has already gone out.
 	/// - All other error kinds return `false`.
 	///
 	/// `Method::TRACE` is treated as non-idempotent in this table
@@ -285,10 +300,12 @@ impl Error {
 				| UpstreamReason::DnsFailure
 				| UpstreamReason::Unreachable
 				| UpstreamReason::Refused
-				| UpstreamReason::ResetOnIdlePickup,
+				| UpstreamReason::ResetOnIdlePickup
+				| UpstreamReason::CircuitOpen,
 			) => true,
 			// Mid-request failures: only idempotent methods retry.
-			ErrorKind::Upstream(UpstreamReason::ResetMidRequest | UpstreamReason::Gone) => matches!(
+			ErrorKind::Upstream(UpstreamReason::ResetMidRequest | UpstreamReason::Gone)
+			| ErrorKind::Timeout(TimeoutKind::Read) => matches!(
 				*method,
 				Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
 			),
@@ -300,9 +317,11 @@ impl Error {
 	pub const fn http_status(&self) -> u16 {
 		match &self.kind {
 			ErrorKind::Protocol => 400,
+			// An open breaker means the upstream is known to be
+			// unavailable, not misbehaving on this request.
+			ErrorKind::Upstream(UpstreamReason::CircuitOpen) | ErrorKind::Resource(_) => 503,
 			ErrorKind::Upstream(_) => 502,
 			ErrorKind::Timeout(_) => 504,
-			ErrorKind::Resource(_) => 503,
 			ErrorKind::Canceled => 499,
 			ErrorKind::Middleware | ErrorKind::Compile | ErrorKind::Internal | ErrorKind::Io => 500,
 		}
@@ -40,6 +40,13 @@ pub enum L7FetchOutput {
 	Tunnel(Tunnel),
 }
 
+/// Response extension marking a response a fetch answered in place of
+/// an error: `http_proxy`'s 504 for an upstream timeout and 503 for an
+/// open circuit breaker. `Node::Fetch.on_error` diverts it like the
+/// `Err` it stands for.
+#[derive(Clone, Copy, Debug)]
+pub struct FetchFailure;
+
 /// Bridge between the executor's `ByteTunnel` arm and a fetch's chosen
 /// transport. `Bidi` is the stream-pair shape that
 /// `tokio::io::copy_bidirectional` consumes — covers TCP forward, TLS
@@ -100,6 +107,9 @@ pub enum FetchKind {
 	HttpSynthesize,
 	WebSocketUpgrade,
 	L4Forward,
+	/// Serves files from a local document root. Response-only, like
+	/// `HttpSynthesize`, but the body streams from disk.
+	FileServer,
 	/// HTTP-01 ACME challenge responder. Injected by the lower
 	/// pass on every plaintext `:80` listener whose listener kind
 	/// is `Http` / `Auto` per `spec/crates/engine-acme.md` § _Challenge: HTTP-01_;
@@ -120,9 +130,11 @@ impl FetchKind {
 	pub const fn phase(self) -> FetchPhase {
 		match self {
 			Self::L4Forward => FetchPhase::L4,
-			Self::HttpProxy | Self::HttpSynthesize | Self::WebSocketUpgrade | Self::AcmeChallenge => {
-				FetchPhase::L7
-			}
+			Self::HttpProxy
+			| Self::HttpSynthesize
+			| Self::WebSocketUpgrade
+			| Self::FileServer
+			| Self::AcmeChallenge => FetchPhase::L7,
 		}
 	}
 }
@@ -144,7 +156,7 @@ pub struct SymbolicFetchRef {
 	pub kind: FetchKind,
 	pub args: serde_json::Value,
 	/// `true` iff this fetch's retry policy is `buffering: "force"`
-	/// with `max_attempts > 1`. Drives `collect_body_before`
+	/// with `max_attempts > 1` or a `hedge`. Drives `collect_body_before`
 	/// placement on the fetch node in the lower pass; the full
 	/// `RetryPolicy` lives in the engine's factory layer. See
 	/// `spec/crates/engine.md` § _Retry_.
@@ -162,6 +174,24 @@ pub struct SymbolicFetchRef {
 	/// _Runtime flow_.
 	#[serde(default)]
 	pub allow_zero_rtt: Option<bool>,
+	/// Name of the rule this fetch terminates, lifted by the lower pass
+	/// so the drivers can label per-rule HTTP metrics without a
+	/// rule-side lookup. `None` for synthesised fetches (the ACME
+	/// challenge responder) and hand-built fixtures. See
+	/// `spec/crates/engine.md` § _Request metrics_.
+	#[serde(default)]
+	pub rule: Option<String>,
+}
+
+impl SymbolicFetchRef {
+	/// A fetch of `kind` with `args` and every lowered field at its
+	/// default: no retry buffering, no 0-RTT gate, no owning rule.
+	/// Hand-built graphs (synthesised fetches, fixtures) start here and
+	/// override what they need with struct-update syntax.
+	#[must_use]
+	pub const fn new(kind: FetchKind, args: serde_json::Value) -> Self {
+		Self { kind, args, retry_buffer_required: false, allow_zero_rtt: None, rule: None }
+	}
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, serde::Serialize, serde::Deserialize)]
@@ -425,6 +455,9 @@ mod tests {
 			entered_at: Instant::now(),
 			tls: Mutex::new(None),
 			http_version: std::sync::OnceLock::new(),
+			geo: std::sync::OnceLock::new(),
+			proxy: std::sync::OnceLock::new(),
+			peer_cred: std::sync::OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		})
 	}
@@ -510,6 +543,7 @@ mod tests {
 			FetchKind::HttpSynthesize,
 			FetchKind::WebSocketUpgrade,
 			FetchKind::L4Forward,
+			FetchKind::FileServer,
 			FetchKind::AcmeChallenge,
 		] {
 			let encoded = serde_json::to_string(&k).expect("serialize");
@@ -553,12 +587,7 @@ mod tests {
 
 	#[test]
 	fn symbolic_fetch_ref_clone_preserves_fields() {
-		let r = SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: json!({ "upstream": "127.0.0.1:8080" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		};
+		let r = SymbolicFetchRef::new(FetchKind::HttpProxy, json!({ "upstream": "127.0.0.1:8080" }));
 		let cloned = r.clone();
 		assert_eq!(cloned.kind, r.kind);
 		assert_eq!(cloned.args, r.args);
@@ -573,13 +602,9 @@ mod tests {
 			FetchKind::HttpSynthesize,
 			FetchKind::WebSocketUpgrade,
 			FetchKind::L4Forward,
+			FetchKind::FileServer,
 		] {
-			let _ = SymbolicFetchRef {
-				kind,
-				args: serde_json::Value::Null,
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			};
+			let _ = SymbolicFetchRef::new(kind, serde_json::Value::Null);
 		}
 	}
 
@@ -588,12 +613,8 @@ mod tests {
 	// `kind` tag and the opaque `args` payload must round-trip.
 	#[test]
 	fn symbolic_fetch_ref_round_trip_preserves_kind_and_args() {
-		let r = SymbolicFetchRef {
-			kind: FetchKind::WebSocketUpgrade,
-			args: json!({ "upstream": "127.0.0.1:9000" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		};
+		let r =
+			SymbolicFetchRef::new(FetchKind::WebSocketUpgrade, json!({ "upstream": "127.0.0.1:9000" }));
 		let encoded = serde_json::to_string(&r).expect("serialize");
 		let decoded: SymbolicFetchRef = serde_json::from_str(&encoded).expect("deserialize");
 		assert_eq!(decoded.kind, r.kind);
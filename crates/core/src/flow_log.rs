@@ -28,6 +28,9 @@ pub enum FlowLogKind {
 	Error,
 	SecurityLimit,
 	Upgrade,
+	/// Per-authority circuit-breaker transition (`closed` / `open` /
+	/// `half_open`). Emitted by the fetch whose request caused it.
+	CircuitBreaker,
 	/// Per-request summary event. The `data` field carries a serialized
 	/// [`FlowTrajectory`]. Always emitted exactly once per request,
 	/// regardless of verbosity.
@@ -38,7 +41,7 @@ pub enum FlowLogKind {
 pub enum FlowLogVerbosity {
 	/// Default. One `Trajectory` event per request, plus the existing
 	/// per-connection milestone events (`Terminate`, `Error`, `Upgrade`,
-	/// `SecurityLimit`).
+	/// `SecurityLimit`, `CircuitBreaker`).
 	Trajectory,
 	/// Adds a per-step event for each `Check` / `Middleware` / `Fetch` /
 	/// `Upgrade` node. Used at incident time; not for production volumes.
@@ -144,6 +147,31 @@ pub struct FlowTrajectory {
 	pub outcome: TrajectoryOutcome,
 	pub started_at_ms: u64,
 	pub finished_at_ms: u64,
+	/// Client fingerprints from `ConnContext.tls` when the listener
+	/// peeked a `ClientHello`, so a trajectory can be traced back to
+	/// the client software after the fact.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tls_ja3: Option<Arc<str>>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tls_ja4: Option<Arc<str>>,
+	/// Which attempt answered when the fetch raced a hedged request.
+	/// Absent when no hedge fired.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub hedge: Option<HedgeWinner>,
+	/// W3C trace id (32 lowercase hex digits) of the walk's span when
+	/// OpenTelemetry export is on, so flow-log entries join with the
+	/// exported traces.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub trace_id: Option<Arc<str>>,
+}
+
+/// The attempt that won a hedged race: the original request, or the
+/// copy sent after the hedge delay. The loser is cancelled.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HedgeWinner {
+	Primary,
+	Hedge,
 }
 
 /// Per-walker accumulator that the executor pushes steps into and
@@ -156,12 +184,13 @@ pub struct TrajectoryBuilder {
 	entry: NodeId,
 	started_at_ms: u64,
 	steps: Vec<TrajectoryStep>,
+	hedge: Option<HedgeWinner>,
 }
 
 impl TrajectoryBuilder {
 	#[must_use]
 	pub fn new(conn: ConnId, entry: NodeId, started_at_ms: u64) -> Self {
-		Self { conn, entry, started_at_ms, steps: Vec::new() }
+		Self { conn, entry, started_at_ms, steps: Vec::new(), hedge: None }
 	}
 
 	/// Detached builder used as a transient placeholder when the
@@ -175,13 +204,19 @@ impl TrajectoryBuilder {
 	/// it or finalize it as if it represented a real trace.
 	#[must_use]
 	pub fn placeholder(conn: ConnId, started_at_ms: u64) -> Self {
-		Self { conn, entry: NodeId::new(0), started_at_ms, steps: Vec::new() }
+		Self { conn, entry: NodeId::new(0), started_at_ms, steps: Vec::new(), hedge: None }
 	}
 
 	pub fn push(&mut self, step: TrajectoryStep) {
 		self.steps.push(step);
 	}
 
+	/// Note which attempt of a hedged fetch answered. The last fetch to
+	/// hedge wins when a flow runs several.
+	pub fn record_hedge(&mut self, winner: HedgeWinner) {
+		self.hedge = Some(winner);
+	}
+
 	#[must_use]
 	pub fn finalize(self, outcome: TrajectoryOutcome, finished_at_ms: u64) -> FlowTrajectory {
 		FlowTrajectory {
@@ -191,6 +226,10 @@ impl TrajectoryBuilder {
 			outcome,
 			started_at_ms: self.started_at_ms,
 			finished_at_ms,
+			tls_ja3: None,
+			tls_ja4: None,
+			hedge: self.hedge,
+			trace_id: None,
 		}
 	}
 }
@@ -266,6 +305,7 @@ mod tests {
 			FlowLogKind::Error,
 			FlowLogKind::SecurityLimit,
 			FlowLogKind::Upgrade,
+			FlowLogKind::CircuitBreaker,
 		] {
 			let encoded = serde_json::to_string(&k).expect("serialize");
 			let decoded: FlowLogKind = serde_json::from_str(&encoded).expect("deserialize");
@@ -396,6 +436,34 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn trajectory_records_hedge_winner() {
+		let b = TrajectoryBuilder::new(ConnId(1), NodeId::new(0), 0);
+		let plain = b.finalize(
+			TrajectoryOutcome::Terminated {
+				node: NodeId::new(0),
+				terminator: TerminatorOutcomeKind::WriteHttpResponse,
+			},
+			1,
+		);
+		let json = serde_json::to_value(&plain).expect("serialize");
+		assert!(json.get("hedge").is_none(), "absent when no hedge fired: {json}");
+
+		let mut b = TrajectoryBuilder::new(ConnId(1), NodeId::new(0), 0);
+		b.record_hedge(HedgeWinner::Hedge);
+		let hedged = b.finalize(
+			TrajectoryOutcome::Terminated {
+				node: NodeId::new(0),
+				terminator: TerminatorOutcomeKind::WriteHttpResponse,
+			},
+			1,
+		);
+		let json = serde_json::to_value(&hedged).expect("serialize");
+		assert_eq!(json["hedge"], "hedge");
+		let back: FlowTrajectory = serde_json::from_value(json).expect("deserialize");
+		assert_eq!(back.hedge, Some(HedgeWinner::Hedge));
+	}
+
 	#[test]
 	fn flow_trajectory_round_trips_through_json() {
 		let mut b = TrajectoryBuilder::new(ConnId(0x1234_5678), NodeId::new(0), 100);
@@ -0,0 +1,85 @@
+//! Geo / ASN attribution behind the `remote.country`,
+//! `remote.continent` and `remote.asn` predicate fields.
+//!
+//! Core owns only the seam: the [`GeoLookup`] trait and a daemon-wide
+//! slot the engine's MMDB reader is installed into at boot. Readers go
+//! through [`ConnContext::geo`](crate::ConnContext::geo), which runs
+//! the lookup at most once per connection. See `spec/crates/core.md`
+//! § _Predicate_.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// What the installed database knows about one address. Every field
+/// is independent: a country-only database leaves `asn` empty, an
+/// ASN-only one leaves both codes empty.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GeoInfo {
+	/// ISO 3166-1 alpha-2 country code, ASCII-uppercase (`DE`).
+	pub country: Option<Arc<str>>,
+	/// Two-letter continent code, ASCII-uppercase (`EU`).
+	pub continent: Option<Arc<str>>,
+	/// Autonomous system number.
+	pub asn: Option<u32>,
+}
+
+/// Address → [`GeoInfo`] source. Implementations must be cheap enough
+/// to call once per accepted connection and must never block on I/O;
+/// reloading is the implementation's own business.
+pub trait GeoLookup: Send + Sync {
+	fn lookup(&self, ip: IpAddr) -> GeoInfo;
+}
+
+static INSTALLED: RwLock<Option<Arc<dyn GeoLookup>>> = RwLock::new(None);
+
+/// Install (or with `None`, remove) the daemon-wide lookup. Connections
+/// that already resolved their [`GeoInfo`] keep it.
+pub fn install_geo_lookup(lookup: Option<Arc<dyn GeoLookup>>) {
+	*INSTALLED.write() = lookup;
+}
+
+/// Resolve `ip` against the installed lookup. IPv4-mapped IPv6
+/// addresses (dual-stack listeners) are looked up as IPv4. With no
+/// lookup installed every field is empty, so geo predicates miss.
+#[must_use]
+pub fn lookup_geo(ip: IpAddr) -> GeoInfo {
+	let lookup = INSTALLED.read().clone();
+	lookup.map(|l| l.lookup(ip.to_canonical())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Fixed;
+
+	impl GeoLookup for Fixed {
+		fn lookup(&self, ip: IpAddr) -> GeoInfo {
+			if ip == IpAddr::from([192, 0, 2, 1]) {
+				GeoInfo {
+					country: Some(Arc::from("DE")),
+					continent: Some(Arc::from("EU")),
+					asn: Some(64500),
+				}
+			} else {
+				GeoInfo::default()
+			}
+		}
+	}
+
+	#[test]
+	fn lookup_canonicalises_mapped_addresses_and_misses_when_uninstalled() {
+		let mapped: IpAddr = "::ffff:192.0.2.1".parse().unwrap();
+		assert_eq!(lookup_geo(mapped), GeoInfo::default());
+
+		install_geo_lookup(Some(Arc::new(Fixed)));
+		assert_eq!(lookup_geo(mapped).country.as_deref(), Some("DE"));
+		assert_eq!(lookup_geo(mapped).asn, Some(64500));
+		assert_eq!(lookup_geo(IpAddr::from([198, 51, 100, 1])), GeoInfo::default());
+
+		install_geo_lookup(None);
+		assert_eq!(lookup_geo(mapped), GeoInfo::default());
+	}
+}
@@ -104,6 +104,17 @@ pub enum Node {
 		id: FetchId,
 		next_response: Option<NodeId>,
 		next_tunnel: Option<NodeId>,
+		/// Fallback edge, taken in the fetch's own (request) phase when
+		/// the fetch errors or answers one of `on_error_status`. The
+		/// executor replays the buffered request into the target; the
+		/// validator proves the body is buffered on every path here.
+		/// L7 fetches only.
+		#[serde(default)]
+		on_error: Option<NodeId>,
+		/// Upstream response statuses that also take `on_error`. Empty
+		/// means only a fetch error does.
+		#[serde(default)]
+		on_error_status: Vec<u16>,
 		collect_body_before: Option<BodySide>,
 		#[serde(default)]
 		body_limit: usize,
@@ -199,6 +210,26 @@ pub struct FlowGraphMeta {
 	#[serde(default)]
 	pub listener_transports: std::collections::BTreeMap<SocketAddr, Transport>,
 
+	/// Per-listener PROXY protocol acceptance, aggregated by the lower
+	/// pass from every rule's `proxy_protocol` on the address (rules
+	/// must agree). The engine's accept loop reads a v1 / v2 header
+	/// from peers in the trusted set before anything else touches the
+	/// connection (`spec/crates/engine.md` § _PROXY protocol_).
+	/// Listeners absent from this map never parse a header.
+	#[serde(default)]
+	pub listener_proxy_protocol:
+		std::collections::BTreeMap<SocketAddr, crate::rule::ProxyProtocolConfig>,
+
+	/// Unix domain socket listeners, keyed by socket path. Each carries
+	/// its own entry node and derived kind because `entries` and the
+	/// per-listener maps above are keyed by `SocketAddr`. The lower pass
+	/// rejects `tls` and `proxy_protocol` on these listeners, so they
+	/// have no counterpart in `listener_tls` /
+	/// `listener_proxy_protocol`. See `spec/crates/engine.md`
+	/// § _Unix listeners_.
+	#[serde(default)]
+	pub unix_listeners: std::collections::BTreeMap<PathBuf, UnixListenerSpec>,
+
 	/// Compile-time annotations the lower pass emits as observations
 	/// about the produced graph — surfaced in `vane compile --dry-run`
 	/// so operators see synthetic-route insertions and rule-shadowing
@@ -211,6 +242,15 @@ pub struct FlowGraphMeta {
 	pub annotations: Vec<DryRunAnnotation>,
 }
 
+/// One `unix:/path` listener in [`FlowGraphMeta::unix_listeners`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UnixListenerSpec {
+	pub entry: NodeId,
+	pub kind: ListenerKind,
+	/// Socket-file settings aggregated from the listener's rules.
+	pub socket: crate::rule::UnixSocketConfig,
+}
+
 /// One observation about the compiled graph, surfaced through
 /// `compile_dry_run` for operator visibility. Currently used by the
 /// ACME inject pass to mark synthesised `:80` challenge routes and
@@ -247,6 +287,15 @@ pub struct SymbolicFlowGraph {
 	pub meta: FlowGraphMeta,
 }
 
+impl SymbolicFlowGraph {
+	/// Every listener's entry node: the `SocketAddr`-keyed `entries`
+	/// followed by the Unix listeners'. Graph-wide walks (phase check,
+	/// body-reader passes) start from these.
+	pub fn entry_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+		self.entries.values().copied().chain(self.meta.unix_listeners.values().map(|u| u.entry))
+	}
+}
+
 impl Index<NodeId> for SymbolicFlowGraph {
 	type Output = Node;
 	fn index(&self, id: NodeId) -> &Node {
@@ -454,6 +503,8 @@ mod tests {
 			id: FetchId::new(0),
 			next_response: None,
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: Some(BodySide::Request),
 			body_limit: 0,
 		};
@@ -463,6 +514,8 @@ mod tests {
 			id: FetchId::new(0),
 			next_response: None,
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		};
@@ -500,12 +553,7 @@ mod tests {
 	}
 
 	fn sample_fetch() -> SymbolicFetchRef {
-		SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}
+		SymbolicFetchRef::new(FetchKind::HttpProxy, Value::Null)
 	}
 
 	fn sample_meta() -> FlowGraphMeta {
@@ -518,6 +566,8 @@ mod tests {
 			listener_tls: std::collections::BTreeMap::new(),
 			listener_kinds: std::collections::BTreeMap::new(),
 			listener_transports: std::collections::BTreeMap::new(),
+			listener_proxy_protocol: std::collections::BTreeMap::new(),
+			unix_listeners: std::collections::BTreeMap::new(),
 			annotations: Vec::new(),
 		}
 	}
@@ -645,6 +695,8 @@ mod tests {
 			id: FetchId::new(7),
 			next_response: Some(NodeId::new(8)),
 			next_tunnel: Some(NodeId::new(9)),
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: Some(BodySide::Request),
 			body_limit: 0,
 		};
@@ -662,6 +714,8 @@ mod tests {
 			id: FetchId::new(0),
 			next_response: None,
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		};
@@ -718,6 +772,8 @@ mod tests {
 			listener_tls: std::collections::BTreeMap::new(),
 			listener_kinds: std::collections::BTreeMap::new(),
 			listener_transports: std::collections::BTreeMap::new(),
+			listener_proxy_protocol: std::collections::BTreeMap::new(),
+			unix_listeners: std::collections::BTreeMap::new(),
 			annotations: Vec::new(),
 		};
 		let encoded = serde_json::to_string(&meta).expect("serialize meta");
@@ -12,7 +12,8 @@ pub enum L4Conn {
 	/// drained part of, with those bytes rewound into the read side via
 	/// `PeekedStream`. Type-erased so `vane-core` doesn't need to know
 	/// the concrete adapter; downstream consumers see the connection
-	/// from byte zero.
+	/// from byte zero. Non-TCP stream listeners (`unix:`) enter here
+	/// too, peeked or not.
 	Peeked(Box<dyn AsyncReadWrite + Send>),
 	/// TLS-terminated stream after a server-side handshake completed.
 	/// The trait object erases the concrete `tokio_rustls::TlsStream`
@@ -8,7 +8,7 @@ pub mod canonical;
 pub mod compile;
 pub use compile::compile;
 pub mod config;
-pub use config::{Env, EnvReader, LoadedConfig, ProcessEnv, load, scan_rules_dir};
+pub use config::{Env, EnvReader, LoadedConfig, OtlpProtocol, ProcessEnv, load, scan_rules_dir};
 pub mod conn_context;
 pub use conn_context::*;
 pub mod error;
@@ -19,6 +19,8 @@ pub mod flow_ctx;
 pub use flow_ctx::*;
 pub mod flow_log;
 pub use flow_log::*;
+pub mod geo;
+pub use geo::*;
 pub mod ir;
 pub use ir::*;
 pub mod l4;
@@ -36,6 +38,7 @@ pub use predicate::*;
 pub mod preset;
 pub use preset::{PresetInvocation, RuleEntry, expand_invocation};
 pub mod rule;
+pub mod template;
 
 pub mod meta {
 	pub const DESCRIPTION: &str = "A compact programmable proxy engine";
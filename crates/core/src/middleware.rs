@@ -246,6 +246,9 @@ mod tests {
 			entered_at: Instant::now(),
 			tls: Mutex::new(None),
 			http_version: std::sync::OnceLock::new(),
+			geo: std::sync::OnceLock::new(),
+			proxy: std::sync::OnceLock::new(),
+			peer_cred: std::sync::OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		})
 	}
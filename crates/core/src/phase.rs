@@ -61,6 +61,7 @@ pub const fn accepted_in_phases(kind: PhaseNodeKind) -> &'static [Phase] {
 		PhaseNodeKind::Fetch(FetchKind::HttpProxy) => L7_REQ,
 		PhaseNodeKind::Fetch(FetchKind::HttpSynthesize) => L7_REQ,
 		PhaseNodeKind::Fetch(FetchKind::WebSocketUpgrade) => L7_REQ,
+		PhaseNodeKind::Fetch(FetchKind::FileServer) => L7_REQ,
 		PhaseNodeKind::Fetch(FetchKind::AcmeChallenge) => L7_REQ,
 		PhaseNodeKind::Terminate(Terminator::WriteHttpResponse) => L7_RESP,
 		PhaseNodeKind::Terminate(Terminator::ByteTunnel) => TUNNEL,
@@ -94,6 +95,7 @@ pub fn transition(kind: PhaseNodeKind, cur: Phase) -> Result<Transition, PhaseEr
 		PhaseNodeKind::Fetch(FetchKind::L4Forward) => Transition::Into(Phase::Tunnel),
 		PhaseNodeKind::Fetch(FetchKind::HttpProxy) => Transition::Into(Phase::L7Response),
 		PhaseNodeKind::Fetch(FetchKind::HttpSynthesize) => Transition::Into(Phase::L7Response),
+		PhaseNodeKind::Fetch(FetchKind::FileServer) => Transition::Into(Phase::L7Response),
 		PhaseNodeKind::Fetch(FetchKind::AcmeChallenge) => Transition::Into(Phase::L7Response),
 		PhaseNodeKind::Fetch(FetchKind::WebSocketUpgrade) => {
 			Transition::BiOutcome { response: Phase::L7Response, tunnel: Phase::Tunnel }
@@ -179,6 +181,7 @@ mod tests {
 			FetchKind::HttpProxy,
 			FetchKind::HttpSynthesize,
 			FetchKind::WebSocketUpgrade,
+			FetchKind::FileServer,
 			FetchKind::AcmeChallenge,
 		] {
 			assert_eq!(accepted_in_phases(PhaseNodeKind::Fetch(f)), &[Phase::L7Request] as &[Phase],);
@@ -263,7 +266,12 @@ mod tests {
 
 	#[test]
 	fn http_fetch_variants_go_to_l7_response() {
-		for f in [FetchKind::HttpProxy, FetchKind::HttpSynthesize, FetchKind::AcmeChallenge] {
+		for f in [
+			FetchKind::HttpProxy,
+			FetchKind::HttpSynthesize,
+			FetchKind::FileServer,
+			FetchKind::AcmeChallenge,
+		] {
 			assert_eq!(
 				transition(PhaseNodeKind::Fetch(f), Phase::L7Request),
 				Ok(Transition::Into(Phase::L7Response)),
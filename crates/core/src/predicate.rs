@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
 use std::sync::Arc;
@@ -14,12 +15,31 @@ pub enum FieldPath {
 	Transport,
 	RemoteIp,
 	RemotePort,
+	/// ISO 3166-1 alpha-2 country of `remote.ip` from the daemon's
+	/// GeoIP database, ASCII-uppercase.
+	RemoteCountry,
+	/// Two-letter continent code of `remote.ip`, ASCII-uppercase.
+	RemoteContinent,
+	/// Autonomous system number announcing `remote.ip`.
+	RemoteAsn,
+	/// Peer process uid on a Unix listener (`SO_PEERCRED`).
+	RemoteUid,
+	/// Peer process gid on a Unix listener.
+	RemoteGid,
+	/// Peer process id on a Unix listener.
+	RemotePid,
 	LocalIp,
 	LocalPort,
 	Peek,
 	TlsSni,
 	TlsAlpn,
 	TlsVersion,
+	/// JA3 fingerprint of the peeked `ClientHello`: 32 lowercase hex
+	/// characters.
+	TlsJa3,
+	/// JA4 fingerprint of the peeked `ClientHello`
+	/// (`t13d1516h2_8daaf6152771_e5627efa2ab1`; `q…` over QUIC).
+	TlsJa4,
 	/// Was a verified peer cert presented this connection? Reads
 	/// `tls.peer_cert.is_some()`. Bool-typed.
 	TlsPeerCertPresent,
@@ -41,9 +61,22 @@ pub enum FieldPath {
 	/// leading-zero stripping.
 	TlsPeerCertSerial,
 	HttpMethod,
+	/// Protocol the request arrived on: `h1` (HTTP/1.0 and 1.1), `h2`
+	/// or `h3`. Enum-typed.
+	HttpVersion,
+	/// Request host, from the URI authority (H2 / H3, absolute-form
+	/// H1) or else the `Host` header; ASCII-lowercased, port and
+	/// trailing dot removed.
+	HttpUriHost,
 	HttpUriPath,
 	HttpUriQuery,
 	HttpHeader(Arc<str>),
+	/// First cookie with this exact (case-sensitive) name across every
+	/// `Cookie` header, value as sent.
+	HttpCookie(Arc<str>),
+	/// First query parameter with this exact (case-sensitive) name,
+	/// name and value percent-decoded (`+` as space).
+	HttpQuery(Arc<str>),
 	HttpBody,
 }
 
@@ -84,21 +117,35 @@ impl FieldPath {
 	#[must_use]
 	pub fn value_type(&self) -> FieldValueType {
 		match self {
-			Self::Transport | Self::TlsVersion | Self::HttpMethod => FieldValueType::Enum,
+			Self::Transport | Self::TlsVersion | Self::HttpMethod | Self::HttpVersion => {
+				FieldValueType::Enum
+			}
 			Self::RemoteIp | Self::LocalIp => FieldValueType::IpAddr,
-			Self::RemotePort | Self::LocalPort => FieldValueType::Int,
+			Self::RemotePort
+			| Self::LocalPort
+			| Self::RemoteAsn
+			| Self::RemoteUid
+			| Self::RemoteGid
+			| Self::RemotePid => FieldValueType::Int,
 			Self::Peek | Self::TlsAlpn | Self::HttpBody => FieldValueType::Bytes,
 			Self::TlsPeerCertPresent => FieldValueType::Bool,
 			Self::TlsPeerCertSanDns => FieldValueType::VecStr,
-			Self::TlsSni
+			Self::RemoteCountry
+			| Self::RemoteContinent
+			| Self::TlsSni
+			| Self::TlsJa3
+			| Self::TlsJa4
 			| Self::TlsPeerCertSubjectCn
 			| Self::TlsPeerCertFingerprintSha256
 			| Self::TlsPeerCertSpkiSha256
 			| Self::TlsPeerCertIssuerCn
 			| Self::TlsPeerCertSerial
+			| Self::HttpUriHost
 			| Self::HttpUriPath
 			| Self::HttpUriQuery
-			| Self::HttpHeader(_) => FieldValueType::Str,
+			| Self::HttpHeader(_)
+			| Self::HttpCookie(_)
+			| Self::HttpQuery(_) => FieldValueType::Str,
 		}
 	}
 
@@ -109,12 +156,20 @@ impl FieldPath {
 			Self::Transport => "transport".to_string(),
 			Self::RemoteIp => "remote.ip".to_string(),
 			Self::RemotePort => "remote.port".to_string(),
+			Self::RemoteCountry => "remote.country".to_string(),
+			Self::RemoteContinent => "remote.continent".to_string(),
+			Self::RemoteAsn => "remote.asn".to_string(),
+			Self::RemoteUid => "remote.uid".to_string(),
+			Self::RemoteGid => "remote.gid".to_string(),
+			Self::RemotePid => "remote.pid".to_string(),
 			Self::LocalIp => "local.ip".to_string(),
 			Self::LocalPort => "local.port".to_string(),
 			Self::Peek => "peek".to_string(),
 			Self::TlsSni => "tls.sni".to_string(),
 			Self::TlsAlpn => "tls.alpn".to_string(),
 			Self::TlsVersion => "tls.version".to_string(),
+			Self::TlsJa3 => "tls.ja3".to_string(),
+			Self::TlsJa4 => "tls.ja4".to_string(),
 			Self::TlsPeerCertPresent => "tls.peer_cert.present".to_string(),
 			Self::TlsPeerCertSubjectCn => "tls.peer_cert.subject_cn".to_string(),
 			Self::TlsPeerCertSanDns => "tls.peer_cert.san_dns".to_string(),
@@ -123,9 +178,13 @@ impl FieldPath {
 			Self::TlsPeerCertIssuerCn => "tls.peer_cert.issuer_cn".to_string(),
 			Self::TlsPeerCertSerial => "tls.peer_cert.serial".to_string(),
 			Self::HttpMethod => "http.method".to_string(),
+			Self::HttpVersion => "http.version".to_string(),
+			Self::HttpUriHost => "http.uri.host".to_string(),
 			Self::HttpUriPath => "http.uri.path".to_string(),
 			Self::HttpUriQuery => "http.uri.query".to_string(),
 			Self::HttpHeader(name) => format!("http.header.{name}"),
+			Self::HttpCookie(name) => format!("http.cookie.{name}"),
+			Self::HttpQuery(name) => format!("http.query.{name}"),
 			Self::HttpBody => "http.body".to_string(),
 		}
 	}
@@ -369,6 +428,124 @@ impl<'a> PredicateView<'a> {
 			Self::L7Req { .. } => None,
 		}
 	}
+
+	fn http_version(&self) -> Option<&'static str> {
+		self.request().map(|req| http_version_str(self.conn(), req.version()))
+	}
+
+	fn uri_host(&self) -> Option<String> {
+		self.request().and_then(|req| uri_host(req.uri(), req.headers()))
+	}
+
+	fn cookie(&self, name: &str) -> Option<&str> {
+		self.request().and_then(|req| cookie_value(req.headers(), name))
+	}
+
+	fn query_param(&self, name: &str) -> Option<Cow<'_, str>> {
+		self.request().and_then(|req| query_value(req.uri().query()?, name))
+	}
+}
+
+/// `http.version` value: the connection's negotiated protocol when the
+/// listener recorded one, else the request's own version.
+pub(crate) fn http_version_str(conn: &ConnContext, version: http::Version) -> &'static str {
+	use crate::conn_context::HttpVersion as V;
+	let version = match conn.http_version.get() {
+		Some(V::Http2) => http::Version::HTTP_2,
+		Some(V::Http3) => http::Version::HTTP_3,
+		Some(V::Http1_0 | V::Http1_1) => http::Version::HTTP_11,
+		None => version,
+	};
+	match version {
+		http::Version::HTTP_2 => "h2",
+		http::Version::HTTP_3 => "h3",
+		// HTTP/0.9, 1.0 and 1.1.
+		_ => "h1",
+	}
+}
+
+/// `http.uri.host` value: URI authority first (H2 / H3 `:authority`,
+/// absolute-form H1), then the `Host` header. Port and a trailing
+/// root dot are dropped and ASCII is lowercased, so `Example.COM.:8443`
+/// compares equal to `example.com`. IPv6 literals keep their brackets.
+pub(crate) fn uri_host(uri: &http::Uri, headers: &http::HeaderMap) -> Option<String> {
+	let raw = if let Some(h) = uri.host() {
+		h
+	} else {
+		let host = headers.get(http::header::HOST)?.to_str().ok()?;
+		match host.rfind(':') {
+			Some(i) if !host[i..].contains(']') => &host[..i],
+			_ => host,
+		}
+	};
+	let host = raw.strip_suffix('.').unwrap_or(raw);
+	(!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// First `name=value` pair with exactly `name` across every `Cookie`
+/// header (RFC 6265 § 5.4 lets H2 / H3 clients split them). Names are
+/// case-sensitive; the value is returned as sent, quotes included.
+#[must_use]
+pub fn cookie_value<'h>(headers: &'h http::HeaderMap, name: &str) -> Option<&'h str> {
+	headers
+		.get_all(http::header::COOKIE)
+		.iter()
+		.filter_map(|v| v.to_str().ok())
+		.flat_map(|v| v.split(';'))
+		.find_map(|pair| {
+			let (k, v) = pair.split_once('=')?;
+			(k.trim() == name).then(|| v.trim())
+		})
+}
+
+/// First parameter named `name` in a raw query string. Both sides
+/// are percent-decoded, with `+` as space, before comparing; a
+/// parameter without `=` has the empty value. A value that does not
+/// decode to UTF-8 misses, like a non-UTF-8 header.
+#[must_use]
+pub fn query_value<'q>(query: &'q str, name: &str) -> Option<Cow<'q, str>> {
+	query.split('&').find_map(|pair| {
+		let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+		if percent_decode(k)? != name {
+			return None;
+		}
+		percent_decode(v)
+	})
+}
+
+fn percent_decode(s: &str) -> Option<Cow<'_, str>> {
+	if !s.bytes().any(|b| b == b'%' || b == b'+') {
+		return Some(Cow::Borrowed(s));
+	}
+	let bytes = s.as_bytes();
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		let escaped = (bytes[i] == b'%')
+			.then(|| bytes.get(i + 1..i + 3))
+			.flatten()
+			.and_then(|h| Some(hex(h[0])? << 4 | hex(h[1])?));
+		match (bytes[i], escaped) {
+			(_, Some(byte)) => {
+				out.push(byte);
+				i += 2;
+			}
+			(b'+', None) => out.push(b' '),
+			// A stray `%` stays literal, as browsers send it.
+			(b, None) => out.push(b),
+		}
+		i += 1;
+	}
+	String::from_utf8(out).ok().map(Cow::Owned)
+}
+
+const fn hex(b: u8) -> Option<u8> {
+	match b {
+		b'0'..=b'9' => Some(b - b'0'),
+		b'a'..=b'f' => Some(b - b'a' + 10),
+		b'A'..=b'F' => Some(b - b'A' + 10),
+		_ => None,
+	}
 }
 
 impl PredicateInst {
@@ -410,6 +587,31 @@ impl PredicateInst {
 			}
 			FieldPath::RemoteIp => test_addr(&self.op, view.conn().remote.ip()),
 			FieldPath::RemotePort => test_int(&self.op, i64::from(view.conn().remote.port())),
+			// Geo fields miss when no database is installed or the
+			// address is not in it, like an absent header.
+			FieldPath::RemoteCountry => {
+				view.conn().geo().country.as_deref().is_some_and(|c| test_str(&self.op, c))
+			}
+			FieldPath::RemoteContinent => {
+				view.conn().geo().continent.as_deref().is_some_and(|c| test_str(&self.op, c))
+			}
+			FieldPath::RemoteAsn => {
+				view.conn().geo().asn.is_some_and(|asn| test_int(&self.op, i64::from(asn)))
+			}
+			// Peer credentials exist only on Unix listener connections;
+			// elsewhere these miss, like the geo fields.
+			FieldPath::RemoteUid => {
+				view.conn().peer_cred.get().is_some_and(|c| test_int(&self.op, i64::from(c.uid)))
+			}
+			FieldPath::RemoteGid => {
+				view.conn().peer_cred.get().is_some_and(|c| test_int(&self.op, i64::from(c.gid)))
+			}
+			FieldPath::RemotePid => view
+				.conn()
+				.peer_cred
+				.get()
+				.and_then(|c| c.pid)
+				.is_some_and(|pid| test_int(&self.op, i64::from(pid))),
 			FieldPath::LocalIp => test_addr(&self.op, view.conn().local.ip()),
 			FieldPath::LocalPort => test_int(&self.op, i64::from(view.conn().local.port())),
 			FieldPath::Peek => view.peek_buffer().is_some_and(|b| test_bytes(&self.op, b)),
@@ -434,6 +636,22 @@ impl PredicateInst {
 				.as_ref()
 				.and_then(|t| t.version)
 				.is_some_and(|v| test_str(&self.op, tls_version_str(v))),
+			// Fingerprints are stamped by the listener's ClientHello
+			// peek; a connection that was never peeked misses.
+			FieldPath::TlsJa3 => view
+				.conn()
+				.tls
+				.lock()
+				.as_ref()
+				.and_then(|t| t.ja3.clone())
+				.is_some_and(|got| test_str(&self.op, &got)),
+			FieldPath::TlsJa4 => view
+				.conn()
+				.tls
+				.lock()
+				.as_ref()
+				.and_then(|t| t.ja4.clone())
+				.is_some_and(|got| test_str(&self.op, &got)),
 			// `tls.peer_cert.*` reads the verified client certificate
 			// captured at TLS handshake completion. The engine's
 			// post-handshake hook pre-extracts every predicate-readable
@@ -501,6 +719,8 @@ impl PredicateInst {
 				let Some(req) = view.request() else { return false };
 				test_str(&self.op, req.method().as_str())
 			}
+			FieldPath::HttpVersion => view.http_version().is_some_and(|v| test_str(&self.op, v)),
+			FieldPath::HttpUriHost => view.uri_host().is_some_and(|h| test_str(&self.op, &h)),
 			FieldPath::HttpUriPath => {
 				let Some(req) = view.request() else { return false };
 				test_str(&self.op, req.uri().path())
@@ -528,6 +748,10 @@ impl PredicateInst {
 				};
 				test_str(&self.op, s)
 			}
+			// Cookie and query lookups miss when the name is absent, the
+			// same as a missing header; a present-but-empty value is "".
+			FieldPath::HttpCookie(name) => view.cookie(name).is_some_and(|v| test_str(&self.op, v)),
+			FieldPath::HttpQuery(name) => view.query_param(name).is_some_and(|v| test_str(&self.op, &v)),
 			// `http.body` reads the request body bytes. Per
 			// `spec/flow-model.md` § _LazyBuffer_, the analyze pass marks
 			// the incoming edge of any `http.body` Check with
@@ -545,7 +769,7 @@ impl PredicateInst {
 	}
 }
 
-fn tls_version_str(v: crate::conn_context::TlsVersion) -> &'static str {
+pub(crate) fn tls_version_str(v: crate::conn_context::TlsVersion) -> &'static str {
 	match v {
 		crate::conn_context::TlsVersion::Tls12 => "1.2",
 		crate::conn_context::TlsVersion::Tls13 => "1.3",
@@ -702,6 +926,39 @@ pub const REGEX_DELEGATE_SIZE_LIMIT: usize = 4 * 1024 * 1024;
 /// for patterns like `(a+)+b`.
 pub const REGEX_SMOKE_TEST_INPUT_LEN: usize = 64;
 
+/// Build a regex under the limits above: source length, backtrack and
+/// delegate-size caps, then the adversarial smoke test. Shared by the
+/// `matches` operator and the `rewrite` / `redirect` middleware, so a
+/// pattern that is too expensive for one is too expensive for all.
+///
+/// # Errors
+/// Returns a message (without field or rule context — callers prefix
+/// their own) when the source is too long, does not parse, or trips
+/// the backtrack limit on the smoke-test input.
+pub fn compile_bounded_regex(pat: &str) -> Result<fancy_regex::Regex, String> {
+	if pat.len() > REGEX_PATTERN_MAX_BYTES {
+		return Err(format!(
+			"regex pattern source exceeds {REGEX_PATTERN_MAX_BYTES}-byte limit: got {} bytes",
+			pat.len(),
+		));
+	}
+	let re = fancy_regex::RegexBuilder::new(pat)
+		.backtrack_limit(REGEX_BACKTRACK_LIMIT)
+		.delegate_size_limit(REGEX_DELEGATE_SIZE_LIMIT)
+		.build()
+		.map_err(|e| format!("invalid regex: {e}"))?;
+	// Anchored or short patterns return quickly; pathological
+	// alternations (e.g. `(a+)+b`) hit the backtrack limit here.
+	let probe: String = "a".repeat(REGEX_SMOKE_TEST_INPUT_LEN);
+	match re.is_match(&probe) {
+		Ok(_) => Ok(re),
+		Err(fancy_regex::Error::RuntimeError(fancy_regex::RuntimeError::BacktrackLimitExceeded)) => {
+			Err("regex exceeded backtrack limit on smoke-test input; refusing to compile to avoid runtime ReDoS".to_owned())
+		}
+		Err(e) => Err(format!("regex errored on smoke test: {e}")),
+	}
+}
+
 /// Maximum nesting depth allowed in a `match` predicate tree.
 ///
 /// Anything beyond this is operator error (no real rule needs deep
@@ -862,7 +1119,22 @@ impl<'de> serde::Deserialize<'de> for CheckMap {
 	}
 }
 
-fn parse_field_path(s: &str) -> Result<FieldPath, String> {
+pub(crate) fn parse_field_path(s: &str) -> Result<FieldPath, String> {
+	// Cookie and query-parameter names are case-sensitive on the wire
+	// (RFC 6265 § 4.1.1, RFC 3986 § 6.2.2.1), so the lowercase rule
+	// stops at the prefix for these two.
+	if let Some(name) = s.strip_prefix("http.cookie.") {
+		if !is_token(name) {
+			return Err(format!("http.cookie.* requires a cookie name (RFC 6265 token): {s:?}"));
+		}
+		return Ok(FieldPath::HttpCookie(Arc::from(name)));
+	}
+	if let Some(name) = s.strip_prefix("http.query.") {
+		if name.is_empty() {
+			return Err(format!("http.query.* requires a parameter name: {s:?}"));
+		}
+		return Ok(FieldPath::HttpQuery(Arc::from(name)));
+	}
 	if s.chars().any(|c| c.is_ascii_uppercase()) {
 		return Err(format!(
 			"field path must be lowercase: {:?} — did you mean {:?}?",
@@ -874,12 +1146,20 @@ fn parse_field_path(s: &str) -> Result<FieldPath, String> {
 		"transport" => Ok(FieldPath::Transport),
 		"remote.ip" => Ok(FieldPath::RemoteIp),
 		"remote.port" => Ok(FieldPath::RemotePort),
+		"remote.country" => Ok(FieldPath::RemoteCountry),
+		"remote.continent" => Ok(FieldPath::RemoteContinent),
+		"remote.asn" => Ok(FieldPath::RemoteAsn),
+		"remote.uid" => Ok(FieldPath::RemoteUid),
+		"remote.gid" => Ok(FieldPath::RemoteGid),
+		"remote.pid" => Ok(FieldPath::RemotePid),
 		"local.ip" => Ok(FieldPath::LocalIp),
 		"local.port" => Ok(FieldPath::LocalPort),
 		"peek" => Ok(FieldPath::Peek),
 		"tls.sni" => Ok(FieldPath::TlsSni),
 		"tls.alpn" => Ok(FieldPath::TlsAlpn),
 		"tls.version" => Ok(FieldPath::TlsVersion),
+		"tls.ja3" => Ok(FieldPath::TlsJa3),
+		"tls.ja4" => Ok(FieldPath::TlsJa4),
 		"tls.peer_cert.present" => Ok(FieldPath::TlsPeerCertPresent),
 		"tls.peer_cert.subject_cn" => Ok(FieldPath::TlsPeerCertSubjectCn),
 		"tls.peer_cert.san_dns" => Ok(FieldPath::TlsPeerCertSanDns),
@@ -888,6 +1168,8 @@ fn parse_field_path(s: &str) -> Result<FieldPath, String> {
 		"tls.peer_cert.issuer_cn" => Ok(FieldPath::TlsPeerCertIssuerCn),
 		"tls.peer_cert.serial" => Ok(FieldPath::TlsPeerCertSerial),
 		"http.method" => Ok(FieldPath::HttpMethod),
+		"http.version" => Ok(FieldPath::HttpVersion),
+		"http.uri.host" => Ok(FieldPath::HttpUriHost),
 		"http.uri.path" => Ok(FieldPath::HttpUriPath),
 		"http.uri.query" => Ok(FieldPath::HttpUriQuery),
 		"http.body" => Ok(FieldPath::HttpBody),
@@ -902,6 +1184,11 @@ fn parse_field_path(s: &str) -> Result<FieldPath, String> {
 	}
 }
 
+/// RFC 7230 `token`, the grammar cookie names share with header names.
+fn is_token(s: &str) -> bool {
+	!s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
 fn validate_operator(op: &Operator) -> Result<(), String> {
 	if let Operator::Matches(pattern) = op
 		&& pattern.len() > REGEX_PATTERN_MAX_BYTES
@@ -1117,6 +1404,9 @@ mod tests {
 			entered_at: Instant::now(),
 			tls: Mutex::new(None),
 			http_version: OnceLock::new(),
+			geo: OnceLock::new(),
+			proxy: OnceLock::new(),
+			peer_cred: OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		})
 	}
@@ -1141,14 +1431,24 @@ mod tests {
 			FieldPath::Transport,
 			FieldPath::RemoteIp,
 			FieldPath::RemotePort,
+			FieldPath::RemoteCountry,
+			FieldPath::RemoteContinent,
+			FieldPath::RemoteAsn,
+			FieldPath::RemoteUid,
+			FieldPath::RemoteGid,
+			FieldPath::RemotePid,
 			FieldPath::LocalIp,
 			FieldPath::LocalPort,
 			FieldPath::Peek,
 			FieldPath::TlsSni,
 			FieldPath::TlsAlpn,
 			FieldPath::TlsVersion,
+			FieldPath::TlsJa3,
+			FieldPath::TlsJa4,
 			FieldPath::TlsPeerCertSubjectCn,
 			FieldPath::HttpMethod,
+			FieldPath::HttpVersion,
+			FieldPath::HttpUriHost,
 			FieldPath::HttpUriPath,
 			FieldPath::HttpUriQuery,
 			FieldPath::HttpBody,
@@ -1474,6 +1774,13 @@ mod tests {
 		let cases = [
 			(serde_json::json!({ "tls.sni": { "equals": "api.example.com" } }), FieldPath::TlsSni),
 			(serde_json::json!({ "remote.port": { "gt": 1024 } }), FieldPath::RemotePort),
+			(serde_json::json!({ "remote.country": { "in": ["DE", "FR"] } }), FieldPath::RemoteCountry),
+			(serde_json::json!({ "remote.asn": { "equals": 13335 } }), FieldPath::RemoteAsn),
+			(serde_json::json!({ "tls.ja4": { "prefix": "t13d" } }), FieldPath::TlsJa4),
+			(
+				serde_json::json!({ "tls.ja3": { "in": ["50a0e1f8c13ee9e5521e3f374a63a021"] } }),
+				FieldPath::TlsJa3,
+			),
 			(serde_json::json!({ "http.method": { "equals": "GET" } }), FieldPath::HttpMethod),
 			(serde_json::json!({ "http.uri.path": { "prefix": "/api" } }), FieldPath::HttpUriPath),
 			(
@@ -1481,6 +1788,19 @@ mod tests {
 				FieldPath::HttpHeader(Arc::from("host")),
 			),
 			(serde_json::json!({ "http.body": { "contains": "hello" } }), FieldPath::HttpBody),
+			(serde_json::json!({ "http.version": { "equals": "h2" } }), FieldPath::HttpVersion),
+			(
+				serde_json::json!({ "http.uri.host": { "suffix": ".example.com" } }),
+				FieldPath::HttpUriHost,
+			),
+			(
+				serde_json::json!({ "http.cookie.session": { "equals": "abc" } }),
+				FieldPath::HttpCookie(Arc::from("session")),
+			),
+			(
+				serde_json::json!({ "http.query.version": { "equals": "2" } }),
+				FieldPath::HttpQuery(Arc::from("version")),
+			),
 		];
 		for (raw, expected_path) in cases {
 			let p = parse_predicate(raw.clone()).unwrap_or_else(|e| panic!("parse {raw}: {e}"));
@@ -1521,6 +1841,21 @@ mod tests {
 		assert!(msg.contains("http.header.host"), "error contains lowercased form: {msg}");
 	}
 
+	#[test]
+	fn parse_cookie_and_query_names_keep_their_case() {
+		let raw = serde_json::json!({ "http.cookie.JSESSIONID": { "equals": "x" } });
+		let p = parse_predicate(raw).expect("parse");
+		assert_eq!(expect_check(&p).path, FieldPath::HttpCookie(Arc::from("JSESSIONID")));
+		let raw = serde_json::json!({ "http.query.pageSize": { "equals": "10" } });
+		let p = parse_predicate(raw).expect("parse");
+		assert_eq!(expect_check(&p).path, FieldPath::HttpQuery(Arc::from("pageSize")));
+
+		for bad in ["http.cookie.", "http.cookie.a b", "http.query."] {
+			let raw = serde_json::json!({ bad: { "equals": "x" } });
+			parse_predicate(raw).expect_err(bad);
+		}
+	}
+
 	#[test]
 	fn parse_multi_key_check_is_rejected() {
 		let raw = serde_json::json!({
@@ -1901,6 +2236,8 @@ mod tests {
 			sni: Some(Arc::from(sni)),
 			alpn: None,
 			version: None,
+			ja3: None,
+			ja4: None,
 			peer_cert: None,
 			zero_rtt_used: false,
 		});
@@ -2070,6 +2407,9 @@ mod tests {
 			entered_at: Instant::now(),
 			tls: Mutex::new(None),
 			http_version: OnceLock::new(),
+			geo: OnceLock::new(),
+			proxy: OnceLock::new(),
+			peer_cred: OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		})
 	}
@@ -2083,6 +2423,9 @@ mod tests {
 			entered_at: Instant::now(),
 			tls: Mutex::new(None),
 			http_version: OnceLock::new(),
+			geo: OnceLock::new(),
+			proxy: OnceLock::new(),
+			peer_cred: OnceLock::new(),
 			user: Mutex::new(http::Extensions::new()),
 		})
 	}
@@ -2093,6 +2436,8 @@ mod tests {
 			sni: None,
 			alpn: Some(Arc::from(alpn)),
 			version: None,
+			ja3: None,
+			ja4: None,
 			peer_cert: None,
 			zero_rtt_used: false,
 		});
@@ -2105,6 +2450,8 @@ mod tests {
 			sni: None,
 			alpn: None,
 			version: Some(v),
+			ja3: None,
+			ja4: None,
 			peer_cert: None,
 			zero_rtt_used: false,
 		});
@@ -2435,6 +2782,146 @@ mod tests {
 		assert!(pred(FieldPath::HttpUriQuery, CompiledOperator::Contains(b(b"b=2"))).test(&v));
 	}
 
+	#[test]
+	fn http_cookie_reader_finds_the_named_cookie_across_headers() {
+		let conn = make_conn();
+		let req = http::Request::builder()
+			.uri("/")
+			.header("cookie", "theme=dark; sessionid=nope")
+			.header("cookie", "session=abc123; Session=upper; empty=")
+			.body(Body::Empty)
+			.unwrap();
+		let v = PredicateView::L7Req { conn: &conn, req: &req };
+		let cookie = |name: &str, want: &str| {
+			pred(FieldPath::HttpCookie(Arc::from(name)), CompiledOperator::Equals(str_val(want))).test(&v)
+		};
+		assert!(cookie("session", "abc123"));
+		assert!(cookie("Session", "upper"));
+		assert!(cookie("empty", ""));
+		// Absent cookie misses even against `not_equals`.
+		let absent =
+			pred(FieldPath::HttpCookie(Arc::from("missing")), CompiledOperator::NotEquals(str_val("x")));
+		assert!(!absent.test(&v));
+	}
+
+	#[test]
+	fn http_query_reader_decodes_names_and_values() {
+		let conn = make_conn();
+		let req = http::Request::builder()
+			.uri("/x?version=2&q=a+b%26c&flag&page%5Bsize%5D=10&bad=%FF&version=3")
+			.body(Body::Empty)
+			.unwrap();
+		let v = PredicateView::L7Req { conn: &conn, req: &req };
+		let query = |name: &str, want: &str| {
+			pred(FieldPath::HttpQuery(Arc::from(name)), CompiledOperator::Equals(str_val(want))).test(&v)
+		};
+		assert!(query("version", "2"), "first occurrence wins");
+		assert!(query("q", "a b&c"));
+		assert!(query("flag", ""));
+		assert!(query("page[size]", "10"));
+		assert!(!query("bad", "\u{fffd}"), "non-UTF-8 value misses");
+		assert!(!query("missing", ""));
+	}
+
+	#[test]
+	fn http_uri_host_prefers_authority_and_normalises() {
+		let conn = make_conn();
+		let host = |req: &Request, want: &str| {
+			let v = PredicateView::L7Req { conn: &conn, req };
+			pred(FieldPath::HttpUriHost, CompiledOperator::Equals(str_val(want))).test(&v)
+		};
+		let header_only = req_with_header("host", "API.Example.com.:8443");
+		assert!(host(&header_only, "api.example.com"));
+		let absolute = http::Request::builder()
+			.uri("https://edge.example.com/p")
+			.header("host", "other.example.com")
+			.body(Body::Empty)
+			.unwrap();
+		assert!(host(&absolute, "edge.example.com"));
+		let v6 = req_with_header("host", "[2001:db8::1]:443");
+		assert!(host(&v6, "[2001:db8::1]"));
+		assert!(!host(&req_with_uri("/"), ""), "no host at all misses");
+	}
+
+	#[test]
+	fn http_version_reader_prefers_the_connection_protocol() {
+		let req = http::Request::builder().uri("/").body(Body::Empty).unwrap();
+		let version = |conn: &Arc<ConnContext>, want: &str| {
+			let v = PredicateView::L7Req { conn, req: &req };
+			pred(FieldPath::HttpVersion, CompiledOperator::Equals(str_val(want))).test(&v)
+		};
+		let conn = make_conn();
+		assert!(version(&conn, "h1"), "falls back to the request's HTTP/1.1");
+		let _ = conn.http_version.set(crate::conn_context::HttpVersion::Http3);
+		assert!(version(&conn, "h3"));
+		let l4 = PredicateView::L4 { conn: &conn, peek: None };
+		assert!(!pred(FieldPath::HttpVersion, CompiledOperator::Equals(str_val("h3"))).test(&l4));
+	}
+
+	#[test]
+	fn remote_geo_readers_use_the_connection_geo_and_miss_when_unknown() {
+		let conn = make_conn();
+		let _ = conn.geo.set(crate::geo::GeoInfo {
+			country: Some(Arc::from("DE")),
+			continent: Some(Arc::from("EU")),
+			asn: None,
+		});
+		let v = PredicateView::L4 { conn: &conn, peek: None };
+		assert!(pred(FieldPath::RemoteCountry, CompiledOperator::Equals(str_val("DE"))).test(&v));
+		assert!(
+			pred(FieldPath::RemoteContinent, CompiledOperator::In(vec![str_val("NA"), str_val("EU")]))
+				.test(&v)
+		);
+		// No ASN in the record: both polarities miss.
+		assert!(!pred(FieldPath::RemoteAsn, CompiledOperator::Gt(0)).test(&v));
+		assert!(
+			!pred(FieldPath::RemoteAsn, CompiledOperator::NotEquals(CompiledValue::Int(1))).test(&v)
+		);
+	}
+
+	#[test]
+	fn peer_credential_readers_miss_off_unix_listeners() {
+		let conn = make_conn();
+		let v = PredicateView::L4 { conn: &conn, peek: None };
+		assert!(!pred(FieldPath::RemoteUid, CompiledOperator::Equals(CompiledValue::Int(0))).test(&v));
+		assert!(
+			!pred(FieldPath::RemoteGid, CompiledOperator::NotEquals(CompiledValue::Int(1))).test(&v)
+		);
+
+		let _ = conn.peer_cred.set(crate::conn_context::PeerCred { uid: 1000, gid: 100, pid: None });
+		assert!(
+			pred(FieldPath::RemoteUid, CompiledOperator::Equals(CompiledValue::Int(1000))).test(&v)
+		);
+		assert!(pred(FieldPath::RemoteGid, CompiledOperator::Lt(1000)).test(&v));
+		// No pid from the kernel: the pid reader still misses.
+		assert!(!pred(FieldPath::RemotePid, CompiledOperator::Gt(0)).test(&v));
+	}
+
+	#[test]
+	fn tls_fingerprint_readers_miss_until_the_hello_is_peeked() {
+		let conn = make_conn();
+		let v = PredicateView::L4 { conn: &conn, peek: None };
+		let ja4 = "t13d1516h2_8daaf6152771_e5627efa2ab1";
+		let prefix = || pred(FieldPath::TlsJa4, CompiledOperator::Prefix(Bytes::from_static(b"t13d")));
+		assert!(!prefix().test(&v));
+		assert!(!pred(FieldPath::TlsJa3, CompiledOperator::NotEquals(str_val("x"))).test(&v));
+
+		*conn.tls.lock() = Some(crate::conn_context::TlsInfo {
+			ja3: Some(Arc::from("50a0e1f8c13ee9e5521e3f374a63a021")),
+			ja4: Some(Arc::from(ja4)),
+			..Default::default()
+		});
+		assert!(prefix().test(&v));
+		assert!(pred(FieldPath::TlsJa4, CompiledOperator::Equals(str_val(ja4))).test(&v));
+		assert!(
+			pred(
+				FieldPath::TlsJa3,
+				CompiledOperator::In(vec![str_val("50a0e1f8c13ee9e5521e3f374a63a021")])
+			)
+			.test(&v)
+		);
+	}
+
 	#[test]
 	fn local_ip_reader_uses_local_socket() {
 		let conn = make_conn_with("10.0.0.5:0", "127.0.0.1:8443");
@@ -2496,6 +2983,8 @@ mod tests {
 			sni: None,
 			alpn: None,
 			version: None,
+			ja3: None,
+			ja4: None,
 			peer_cert: Some(Arc::new(pc)),
 			zero_rtt_used: false,
 		});
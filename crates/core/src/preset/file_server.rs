@@ -0,0 +1,136 @@
+//! `file_server` preset — serve a document root from disk.
+//!
+//! Expands to a single `RawRule` whose terminate is `FileServer`. `root`,
+//! `index`, `precompressed` and `cache_control` pass through to the
+//! fetch; `spa: true` becomes the `try_files` chain
+//! `["$uri", "$uri/", "/<index>"]` so unknown client-side routes fall
+//! back to the app shell. Whether `root` exists is checked by the
+//! engine factory at link time, not here.
+//!
+//! See `spec/crates/core.md` § _Compile pipeline_.
+
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::fetch::FetchKind;
+use crate::preset::PresetInvocation;
+use crate::rule::{RawRule, TerminateSpec};
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Args {
+	root: String,
+	#[serde(default)]
+	index: Option<Vec<String>>,
+	#[serde(default)]
+	spa: bool,
+	#[serde(default)]
+	precompressed: bool,
+	#[serde(default)]
+	cache_control: Option<String>,
+}
+
+pub(super) fn expand(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
+	let args: Args = serde_json::from_value(inv.args.clone())
+		.map_err(|e| Error::compile(format!("preset file_server args: {e}")))?;
+	if args.root.is_empty() {
+		return Err(Error::compile("preset file_server args: root must not be empty"));
+	}
+
+	let mut terminate_args = Map::new();
+	terminate_args.insert("root".to_string(), Value::String(args.root));
+	let fallback_index = match &args.index {
+		Some(index) => {
+			let first = index
+				.first()
+				.cloned()
+				.ok_or_else(|| Error::compile("preset file_server args: index must not be empty"))?;
+			terminate_args.insert(
+				"index".to_string(),
+				Value::Array(index.iter().cloned().map(Value::String).collect()),
+			);
+			first
+		}
+		None => "index.html".to_string(),
+	};
+	if args.spa {
+		terminate_args.insert(
+			"try_files".to_string(),
+			serde_json::json!(["$uri", "$uri/", format!("/{fallback_index}")]),
+		);
+	}
+	if args.precompressed {
+		terminate_args.insert("precompressed".to_string(), Value::Bool(true));
+	}
+	if let Some(cc) = args.cache_control {
+		terminate_args.insert("cache_control".to_string(), Value::String(cc));
+	}
+
+	let allow_zero_rtt = inv.tls.as_ref().map(|_| false);
+	Ok(vec![RawRule {
+		name: inv.name,
+		listen: inv.listen,
+		match_predicate: None,
+		middleware_chain: vec![],
+		terminate: TerminateSpec {
+			kind: FetchKind::FileServer,
+			args: Value::Object(terminate_args),
+			fallback: None,
+		},
+		tls: inv.tls,
+		allow_zero_rtt,
+		proxy_protocol: None,
+		unix_socket: None,
+		max_body_bytes_request: 8 * 1024 * 1024,
+		max_body_bytes_response: 8 * 1024 * 1024,
+		source: inv.source,
+	}])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rule::SourceInfo;
+
+	fn invoke(args: Value) -> PresetInvocation {
+		PresetInvocation {
+			name: "docs".to_string(),
+			preset: "file_server".to_string(),
+			listen: vec![":8080".into()],
+			args,
+			tls: None,
+			source: SourceInfo::default(),
+		}
+	}
+
+	#[test]
+	fn file_server_expands_with_root_only() {
+		let rules = expand(invoke(serde_json::json!({ "root": "/srv/www" }))).expect("expand");
+		assert_eq!(rules.len(), 1);
+		assert_eq!(rules[0].terminate.kind, FetchKind::FileServer);
+		assert_eq!(rules[0].terminate.args, serde_json::json!({ "root": "/srv/www" }));
+	}
+
+	#[test]
+	fn file_server_spa_falls_back_to_first_index() {
+		let rules = expand(invoke(serde_json::json!({
+			"root": "/srv/app",
+			"index": ["app.html", "index.html"],
+			"spa": true,
+			"precompressed": true,
+		})))
+		.expect("expand");
+		let args = &rules[0].terminate.args;
+		assert_eq!(args["try_files"], serde_json::json!(["$uri", "$uri/", "/app.html"]));
+		assert_eq!(args["precompressed"], true);
+	}
+
+	#[test]
+	fn file_server_rejects_missing_root_and_unknown_args() {
+		let err = expand(invoke(serde_json::json!({ "spa": true }))).expect_err("missing root");
+		assert!(err.to_string().contains("root"), "{err}");
+		let err = expand(invoke(serde_json::json!({ "root": "/srv", "listing": true })))
+			.expect_err("unknown field");
+		assert!(err.to_string().contains("listing"), "{err}");
+	}
+}
@@ -1,11 +1,13 @@
 //! Preset expansion: `{"preset": ..., ...}` → `Vec<RawRule>`.
 //!
 //! Presets are opinionated compile-stage expansions that turn high-level
-//! intent into raw-rule bundles. The four built-in presets are
-//! `reverse_proxy`, `port_forward`, `static_site`, and `redirect_https`.
+//! intent into raw-rule bundles. The five built-in presets are
+//! `reverse_proxy`, `port_forward`, `static_site`, `file_server`, and
+//! `redirect_https`.
 //!
 //! See [`spec/crates/core.md` § _Compile pipeline_](../../../../spec/crates/core.md#compile-pipeline).
 
+mod file_server;
 mod port_forward;
 mod redirect_https;
 mod reverse_proxy;
@@ -25,7 +27,7 @@ pub struct PresetInvocation {
 	/// `<name>.ws`, `<name>.ws-allow`, `<name>.ws-deny`).
 	pub name: String,
 	/// Discriminator. One of `reverse_proxy` / `port_forward` /
-	/// `static_site` / `redirect_https`.
+	/// `static_site` / `file_server` / `redirect_https`.
 	pub preset: String,
 	#[serde(deserialize_with = "crate::rule::de_listen_non_empty")]
 	pub listen: Vec<ListenSpec>,
@@ -78,9 +80,10 @@ pub fn expand_invocation(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
 		"reverse_proxy" => reverse_proxy::expand(inv),
 		"port_forward" => port_forward::expand(inv),
 		"static_site" => static_site::expand(inv),
+		"file_server" => file_server::expand(inv),
 		"redirect_https" => redirect_https::expand(inv),
 		other => Err(Error::compile(format!(
-			"unknown preset {other:?}; supported: reverse_proxy / port_forward / static_site / redirect_https"
+			"unknown preset {other:?}; supported: reverse_proxy / port_forward / static_site / file_server / redirect_https"
 		))),
 	}
 }
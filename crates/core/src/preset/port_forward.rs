@@ -43,7 +43,7 @@ pub(super) fn expand(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
 		listen: inv.listen,
 		match_predicate: None,
 		middleware_chain: vec![],
-		terminate: TerminateSpec { kind: FetchKind::L4Forward, args: terminate_args },
+		terminate: TerminateSpec { kind: FetchKind::L4Forward, args: terminate_args, fallback: None },
 		// `lower_port` rejects L4 listeners with `tls` set — TLS
 		// termination on a byte-tunnel makes no sense (vane decrypts
 		// then forwards plaintext to upstream, leaking the channel).
@@ -51,6 +51,8 @@ pub(super) fn expand(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
 		// pointed at the rule rather than silently dropping it.
 		tls: inv.tls,
 		allow_zero_rtt: None,
+		proxy_protocol: None,
+		unix_socket: None,
 		max_body_bytes_request: 8 * 1024 * 1024,
 		max_body_bytes_response: 8 * 1024 * 1024,
 		source: inv.source,
@@ -221,9 +221,15 @@ pub(super) fn expand(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
 		listen: inv.listen,
 		match_predicate: None,
 		middleware_chain: chain,
-		terminate: TerminateSpec { kind: FetchKind::HttpProxy, args: Value::Object(http_proxy_args) },
+		terminate: TerminateSpec {
+			kind: FetchKind::HttpProxy,
+			args: Value::Object(http_proxy_args),
+			fallback: None,
+		},
 		tls: inv.tls,
 		allow_zero_rtt: allow_zero_rtt_main,
+		proxy_protocol: None,
+		unix_socket: None,
 		max_body_bytes_request: 8 * 1024 * 1024,
 		max_body_bytes_response: 8 * 1024 * 1024,
 		source: inv.source,
@@ -253,9 +259,12 @@ fn ws_reject_rule(
 		terminate: TerminateSpec {
 			kind: FetchKind::HttpSynthesize,
 			args: serde_json::json!({ "status": 400 }),
+			fallback: None,
 		},
 		tls,
 		allow_zero_rtt,
+		proxy_protocol: None,
+		unix_socket: None,
 		max_body_bytes_request: 8 * 1024 * 1024,
 		max_body_bytes_response: 8 * 1024 * 1024,
 		source: source.clone(),
@@ -299,9 +308,12 @@ fn ws_passthrough_rule(
 		terminate: TerminateSpec {
 			kind: FetchKind::WebSocketUpgrade,
 			args: serde_json::json!({ "upstream": upstream }),
+			fallback: None,
 		},
 		tls,
 		allow_zero_rtt,
+		proxy_protocol: None,
+		unix_socket: None,
 		max_body_bytes_request: 8 * 1024 * 1024,
 		max_body_bytes_response: 8 * 1024 * 1024,
 		source: source.clone(),
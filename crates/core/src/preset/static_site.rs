@@ -55,9 +55,12 @@ pub(super) fn expand(inv: PresetInvocation) -> Result<Vec<RawRule>, Error> {
 		terminate: TerminateSpec {
 			kind: FetchKind::HttpSynthesize,
 			args: Value::Object(terminate_args),
+			fallback: None,
 		},
 		tls: inv.tls,
 		allow_zero_rtt,
+		proxy_protocol: None,
+		unix_socket: None,
 		max_body_bytes_request: 8 * 1024 * 1024,
 		max_body_bytes_response: 8 * 1024 * 1024,
 		source: inv.source,
@@ -39,6 +39,20 @@ pub struct RawRule {
 	/// `spec/crates/engine-tls.md` § _TLS 1.3 0-RTT (early data)_.
 	#[serde(default)]
 	pub allow_zero_rtt: Option<bool>,
+	/// Accept a PROXY protocol v1 / v2 header from trusted peers on
+	/// this rule's listeners. Listener-level: every rule on the same
+	/// address must agree (lower aggregates them into
+	/// `FlowGraphMeta::listener_proxy_protocol`), and UDP listeners
+	/// cannot carry it. See `spec/crates/engine.md` § _PROXY protocol_.
+	#[serde(default)]
+	pub proxy_protocol: Option<ProxyProtocolConfig>,
+	/// Socket-file settings for this rule's `unix:` listeners. Listener-
+	/// level like `proxy_protocol`: every rule on the same path must
+	/// agree, and inet listeners cannot carry it. Absent means
+	/// [`UnixSocketConfig::default`]. See `spec/crates/engine.md`
+	/// § _Unix listeners_.
+	#[serde(default)]
+	pub unix_socket: Option<UnixSocketConfig>,
 	/// Maximum bytes to buffer for request body `LazyBuffer` collection.
 	/// Default 8 MiB. Exceeding this produces 413 Payload Too Large.
 	#[serde(default = "default_max_body_bytes")]
@@ -489,6 +503,88 @@ pub enum ClientAuthSpec {
 	},
 }
 
+/// Listener-side PROXY protocol acceptance. Only peers inside
+/// `trusted` (the load balancers) may prefix a connection with a
+/// header; their header's source replaces the socket peer as the
+/// connection's `remote`. Every other peer is served as-is, header
+/// bytes included, so a client cannot spoof its own address.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ProxyProtocolConfig {
+	/// CIDRs of the peers allowed to send a header. Must be non-empty.
+	pub trusted: Vec<ipnet::IpNet>,
+}
+
+impl ProxyProtocolConfig {
+	/// Whether `ip` may send a header. IPv4-mapped IPv6 peers (a
+	/// dual-stack socket) match the IPv4 CIDRs.
+	#[must_use]
+	pub fn trusts(&self, ip: std::net::IpAddr) -> bool {
+		let ip = ip.to_canonical();
+		self.trusted.iter().any(|net| net.contains(&ip))
+	}
+}
+
+/// Socket-file settings for a `unix:/path` listener, applied when the
+/// listener binds. `owner` / `group` are numeric ids, like the CGI
+/// fetch's `security.uid` / `gid`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct UnixSocketConfig {
+	/// Permission bits for the socket file, written as an octal string
+	/// (`"0660"`). `None` keeps whatever the process umask produced.
+	#[serde(default, with = "octal_mode", skip_serializing_if = "Option::is_none")]
+	pub mode: Option<u32>,
+	/// uid to `chown` the socket file to.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub owner: Option<u32>,
+	/// gid to `chown` the socket file to.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub group: Option<u32>,
+	/// Remove a socket file left at the path by a process that is no
+	/// longer accepting on it before binding. A live socket, or a path
+	/// that is not a socket, is never removed. Default `true`.
+	#[serde(default = "default_true")]
+	pub remove_stale: bool,
+}
+
+impl Default for UnixSocketConfig {
+	fn default() -> Self {
+		Self { mode: None, owner: None, group: None, remove_stale: true }
+	}
+}
+
+const fn default_true() -> bool {
+	true
+}
+
+/// `"0660"` ⇄ `0o660`. JSON has no octal literal, and a decimal `660`
+/// would silently mean something else.
+mod octal_mode {
+	#[allow(
+		clippy::ref_option,
+		clippy::trivially_copy_pass_by_ref,
+		reason = "serde `with` passes the field by reference"
+	)]
+	pub(super) fn serialize<S: serde::Serializer>(v: &Option<u32>, s: S) -> Result<S::Ok, S::Error> {
+		match v {
+			Some(mode) => s.serialize_str(&format!("{mode:04o}")),
+			None => s.serialize_none(),
+		}
+	}
+
+	pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(
+		d: D,
+	) -> Result<Option<u32>, D::Error> {
+		let raw: String = serde::Deserialize::deserialize(d)?;
+		let digits = raw.strip_prefix("0o").unwrap_or(&raw);
+		match u32::from_str_radix(digits, 8) {
+			Ok(mode) if !digits.is_empty() && mode <= 0o7777 => Ok(Some(mode)),
+			_ => Err(serde::de::Error::custom(format!(
+				"unix_socket.mode {raw:?} is not an octal permission mode like \"0660\""
+			))),
+		}
+	}
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct MiddlewareRef {
 	#[serde(rename = "use")]
@@ -530,10 +626,44 @@ impl<'de> serde::Deserialize<'de> for OnErrorSpec {
 	}
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
 pub struct TerminateSpec {
 	pub kind: FetchKind,
 	pub args: Value,
+	/// `terminate.fallback`: where the request goes when this terminate
+	/// fails. Chains — a fallback may carry its own `fallback`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub fallback: Option<Box<FallbackSpec>>,
+}
+
+/// A fallback terminate: a full `terminate` object plus `on_status`,
+/// the upstream statuses that divert to it alongside fetch errors.
+/// Lowered into a `Node::Fetch` `on_error` edge; see
+/// `spec/flow-model.md` § _Fetch fallback_.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FallbackSpec {
+	pub on_status: Vec<u16>,
+	pub terminate: TerminateSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for FallbackSpec {
+	fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+		let mut v = Value::deserialize(de)?;
+		let obj = v
+			.as_object_mut()
+			.ok_or_else(|| serde::de::Error::custom("`fallback` must be a JSON object"))?;
+		let on_status = match obj.remove("on_status") {
+			None => Vec::new(),
+			Some(raw) => Vec::<u16>::deserialize(raw).map_err(serde::de::Error::custom)?,
+		};
+		if let Some(bad) = on_status.iter().find(|s| !(100..=599).contains(*s)) {
+			return Err(serde::de::Error::custom(format!(
+				"`fallback.on_status` entries must be HTTP statuses 100-599, got {bad}"
+			)));
+		}
+		let terminate = TerminateSpec::deserialize(v).map_err(serde::de::Error::custom)?;
+		Ok(Self { on_status, terminate })
+	}
 }
 
 impl<'de> serde::Deserialize<'de> for TerminateSpec {
@@ -542,6 +672,14 @@ impl<'de> serde::Deserialize<'de> for TerminateSpec {
 		let obj = v
 			.as_object_mut()
 			.ok_or_else(|| serde::de::Error::custom("`terminate` must be a JSON object"))?;
+		// `fallback` is graph shape, not fetch args: lift it out before
+		// the rest of the object becomes the factory's `args`.
+		let fallback = match obj.remove("fallback") {
+			None | Some(Value::Null) => None,
+			Some(raw) => {
+				Some(Box::new(FallbackSpec::deserialize(raw).map_err(serde::de::Error::custom)?))
+			}
+		};
 		let type_val = obj.remove("type").ok_or_else(|| serde::de::Error::missing_field("type"))?;
 		let Value::String(alias) = type_val else {
 			return Err(serde::de::Error::custom("`terminate.type` must be a string"));
@@ -583,7 +721,7 @@ impl<'de> serde::Deserialize<'de> for TerminateSpec {
 		{
 			obj.insert("upstream_kind".to_owned(), Value::String(upstream_kind.to_owned()));
 		}
-		Ok(Self { kind, args: v })
+		Ok(Self { kind, args: v, fallback })
 	}
 }
 
@@ -595,6 +733,7 @@ fn fetch_kind_from_alias(alias: &str) -> Option<FetchKind> {
 		}
 		"websocket" => Some(FetchKind::WebSocketUpgrade),
 		"static" | "redirect_https" => Some(FetchKind::HttpSynthesize),
+		"file_server" => Some(FetchKind::FileServer),
 		_ => None,
 	}
 }
@@ -840,6 +979,7 @@ mod tests {
 			("websocket", FetchKind::WebSocketUpgrade),
 			("static", FetchKind::HttpSynthesize),
 			("redirect_https", FetchKind::HttpSynthesize),
+			("file_server", FetchKind::FileServer),
 		];
 		for (alias, expected) in cases {
 			let raw = serde_json::json!({ "type": alias });
@@ -870,6 +1010,42 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn terminate_spec_fallback_is_lifted_out_of_args_and_chains() {
+		let raw = serde_json::json!({
+			"type": "http_proxy",
+			"upstream": "127.0.0.1:8080",
+			"fallback": {
+				"type": "http2_proxy",
+				"upstream": "127.0.0.1:8081",
+				"on_status": [502, 503, 504],
+				"fallback": { "type": "static", "status": 503 },
+			},
+		});
+		let t: TerminateSpec = serde_json::from_value(raw).expect("parse");
+		assert!(t.args.get("fallback").is_none(), "fallback is graph shape, not fetch args");
+		let backup = t.fallback.expect("fallback");
+		assert_eq!(backup.on_status, vec![502, 503, 504]);
+		assert_eq!(backup.terminate.kind, FetchKind::HttpProxy);
+		assert_eq!(backup.terminate.args["version"], "h2", "aliases resolve inside fallbacks");
+		assert!(backup.terminate.args.get("on_status").is_none());
+		let page = backup.terminate.fallback.expect("chained fallback");
+		assert!(page.on_status.is_empty());
+		assert_eq!(page.terminate.kind, FetchKind::HttpSynthesize);
+		assert!(page.terminate.fallback.is_none());
+	}
+
+	#[test]
+	fn terminate_spec_fallback_rejects_non_http_status() {
+		let raw = serde_json::json!({
+			"type": "http_proxy",
+			"upstream": "127.0.0.1:8080",
+			"fallback": { "type": "static", "status": 503, "on_status": [502, 700] },
+		});
+		let err = serde_json::from_value::<TerminateSpec>(raw).expect_err("700 is not a status");
+		assert!(err.to_string().contains("got 700"), "{err}");
+	}
+
 	#[test]
 	fn terminate_spec_udp_forward_alias_injects_transport_udp() {
 		let raw = serde_json::json!({ "type": "udp_forward", "upstream": "1.2.3.4:53" });
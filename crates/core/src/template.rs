@@ -0,0 +1,448 @@
+//! `${field.path}` templates for values the header middleware writes.
+//!
+//! A template is literal text with `${…}` references to the same field
+//! paths predicates read (`remote.ip`, `tls.sni`,
+//! `tls.peer_cert.subject_cn`, `http.uri.path`, `http.header.<name>`,
+//! …). `$$` writes a literal `$`; any other `$` is literal as-is.
+//! `peek` and `http.body` are bytes off the wire rather than connection
+//! or request-head state, so they are rejected. Templates parsed
+//! alongside a regex (the `redirect` middleware's `location`) also
+//! take `$N` / `${N}` capture-group references.
+//!
+//! Parsing runs twice: in `compile/validate.rs` so a bad reference
+//! fails `vane compile`, and in the engine factory that builds the
+//! middleware. Rendering never fails — a reference to state the request
+//! does not have (`tls.sni` on cleartext, an absent header) renders as
+//! the empty string, the template equivalent of a predicate miss.
+//!
+//! See `spec/crates/core.md` § _Templates_.
+
+use std::fmt::Write as _;
+
+use http::{HeaderMap, Method, Uri, Version};
+
+use crate::body::{Request, RequestHead};
+use crate::conn_context::{ConnContext, Transport};
+use crate::predicate::{
+	FieldPath, cookie_value, http_version_str, parse_field_path, query_value, tls_version_str,
+	uri_host,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+	Literal(String),
+	Field(FieldPath),
+	Capture(usize),
+}
+
+/// A parsed template. Cheap to render; holds no per-request state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+	parts: Vec<Part>,
+}
+
+/// The request head a template's `http.*` references read. Response
+/// middleware builds one from the [`RequestHead`] the executor copies
+/// into the response extensions.
+#[derive(Clone, Copy)]
+pub struct RequestRef<'a> {
+	pub method: &'a Method,
+	pub uri: &'a Uri,
+	pub version: Version,
+	pub headers: &'a HeaderMap,
+}
+
+impl<'a> From<&'a Request> for RequestRef<'a> {
+	fn from(req: &'a Request) -> Self {
+		Self { method: req.method(), uri: req.uri(), version: req.version(), headers: req.headers() }
+	}
+}
+
+impl<'a> From<&'a RequestHead> for RequestRef<'a> {
+	fn from(head: &'a RequestHead) -> Self {
+		Self { method: &head.method, uri: &head.uri, version: head.version, headers: &head.headers }
+	}
+}
+
+impl Template {
+	/// Parse `src`.
+	///
+	/// # Errors
+	/// Returns a message naming the offending reference for an
+	/// unterminated `${`, an empty `${}`, an unknown or uppercase field
+	/// path, or a path that cannot be rendered into a header value.
+	pub fn parse(src: &str) -> Result<Self, String> {
+		Self::parse_inner(src, None)
+	}
+
+	/// Parse `src` for use with a regex of `groups` capture groups
+	/// (group 0 included): `$N` and `${N}` refer to group `N`.
+	///
+	/// # Errors
+	/// As [`Template::parse`], plus a reference to a group the pattern
+	/// does not have.
+	pub fn parse_with_captures(src: &str, groups: usize) -> Result<Self, String> {
+		Self::parse_inner(src, Some(groups))
+	}
+
+	fn parse_inner(src: &str, groups: Option<usize>) -> Result<Self, String> {
+		let mut parts = Vec::new();
+		let mut literal = String::new();
+		let mut rest = src;
+		while let Some(at) = rest.find('$') {
+			literal.push_str(&rest[..at]);
+			rest = &rest[at..];
+			if let Some(tail) = rest.strip_prefix("$$") {
+				literal.push('$');
+				rest = tail;
+			} else if let Some(tail) = rest.strip_prefix("${") {
+				let end = tail.find('}').ok_or_else(|| format!("unterminated `${{` in {src:?}"))?;
+				let inner = tail[..end].trim();
+				let part = match (groups, inner.parse::<usize>()) {
+					(Some(groups), Ok(n)) => Part::Capture(capture(n, groups)?),
+					_ => Part::Field(parse_reference(inner)?),
+				};
+				if !literal.is_empty() {
+					parts.push(Part::Literal(std::mem::take(&mut literal)));
+				}
+				parts.push(part);
+				rest = &tail[end + 1..];
+			} else if let (Some(groups), Some(d)) =
+				(groups, rest[1..].chars().next().and_then(|c| c.to_digit(10)))
+			{
+				if !literal.is_empty() {
+					parts.push(Part::Literal(std::mem::take(&mut literal)));
+				}
+				parts.push(Part::Capture(capture(d as usize, groups)?));
+				rest = &rest[2..];
+			} else {
+				literal.push('$');
+				rest = &rest[1..];
+			}
+		}
+		literal.push_str(rest);
+		if !literal.is_empty() {
+			parts.push(Part::Literal(literal));
+		}
+		Ok(Self { parts })
+	}
+
+	/// The literal text when the template has no `${…}` references.
+	#[must_use]
+	pub fn as_literal(&self) -> Option<&str> {
+		match self.parts.as_slice() {
+			[] => Some(""),
+			[Part::Literal(s)] => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Render against the connection and, when in scope, the request
+	/// head. Missing state renders as the empty string.
+	#[must_use]
+	pub fn render(&self, conn: &ConnContext, req: Option<RequestRef<'_>>) -> String {
+		self.render_inner(conn, req, None)
+	}
+
+	/// [`Template::render`] with capture-group references filled from
+	/// `caps`; a group that did not participate renders empty.
+	#[must_use]
+	pub fn render_with_captures(
+		&self,
+		conn: &ConnContext,
+		req: Option<RequestRef<'_>>,
+		caps: &fancy_regex::Captures<'_>,
+	) -> String {
+		self.render_inner(conn, req, Some(caps))
+	}
+
+	fn render_inner(
+		&self,
+		conn: &ConnContext,
+		req: Option<RequestRef<'_>>,
+		caps: Option<&fancy_regex::Captures<'_>>,
+	) -> String {
+		let mut out = String::new();
+		for part in &self.parts {
+			match part {
+				Part::Literal(s) => out.push_str(s),
+				Part::Field(path) => render_field(&mut out, path, conn, req),
+				Part::Capture(n) => {
+					if let Some(m) = caps.and_then(|c| c.get(*n)) {
+						out.push_str(m.as_str());
+					}
+				}
+			}
+		}
+		out
+	}
+}
+
+fn capture(n: usize, groups: usize) -> Result<usize, String> {
+	if n < groups {
+		Ok(n)
+	} else {
+		Err(format!("capture group ${n} does not exist; the pattern has {} group(s)", groups - 1))
+	}
+}
+
+fn parse_reference(inner: &str) -> Result<FieldPath, String> {
+	if inner.is_empty() {
+		return Err("empty `${}` reference".to_owned());
+	}
+	match parse_field_path(inner)? {
+		FieldPath::Peek | FieldPath::HttpBody => {
+			Err(format!("field path {inner:?} cannot be used in a template"))
+		}
+		path => Ok(path),
+	}
+}
+
+fn render_field(
+	out: &mut String,
+	path: &FieldPath,
+	conn: &ConnContext,
+	req: Option<RequestRef<'_>>,
+) {
+	match path {
+		FieldPath::Transport => out.push_str(match conn.transport {
+			Transport::Tcp => "tcp",
+			Transport::Udp => "udp",
+		}),
+		FieldPath::RemoteIp => {
+			let _ = write!(out, "{}", conn.remote.ip());
+		}
+		FieldPath::RemotePort => {
+			let _ = write!(out, "{}", conn.remote.port());
+		}
+		FieldPath::LocalIp => {
+			let _ = write!(out, "{}", conn.local.ip());
+		}
+		FieldPath::LocalPort => {
+			let _ = write!(out, "{}", conn.local.port());
+		}
+		FieldPath::RemoteCountry => out.push_str(conn.geo().country.as_deref().unwrap_or("")),
+		FieldPath::RemoteContinent => out.push_str(conn.geo().continent.as_deref().unwrap_or("")),
+		FieldPath::RemoteAsn => {
+			if let Some(asn) = conn.geo().asn {
+				let _ = write!(out, "{asn}");
+			}
+		}
+		FieldPath::RemoteUid => {
+			if let Some(c) = conn.peer_cred.get() {
+				let _ = write!(out, "{}", c.uid);
+			}
+		}
+		FieldPath::RemoteGid => {
+			if let Some(c) = conn.peer_cred.get() {
+				let _ = write!(out, "{}", c.gid);
+			}
+		}
+		FieldPath::RemotePid => {
+			if let Some(pid) = conn.peer_cred.get().and_then(|c| c.pid) {
+				let _ = write!(out, "{pid}");
+			}
+		}
+		FieldPath::HttpMethod => {
+			if let Some(r) = req {
+				out.push_str(r.method.as_str());
+			}
+		}
+		FieldPath::HttpUriPath => {
+			if let Some(r) = req {
+				out.push_str(r.uri.path());
+			}
+		}
+		FieldPath::HttpUriQuery => {
+			if let Some(r) = req {
+				out.push_str(r.uri.query().unwrap_or(""));
+			}
+		}
+		// First value only, as with predicates; non-UTF-8 misses.
+		FieldPath::HttpHeader(name) => {
+			if let Some(v) = req.and_then(|r| r.headers.get(name.as_ref())).and_then(|v| v.to_str().ok())
+			{
+				out.push_str(v);
+			}
+		}
+		FieldPath::HttpVersion => {
+			if let Some(r) = req {
+				out.push_str(http_version_str(conn, r.version));
+			}
+		}
+		FieldPath::HttpUriHost => {
+			if let Some(host) = req.and_then(|r| uri_host(r.uri, r.headers)) {
+				out.push_str(&host);
+			}
+		}
+		FieldPath::HttpCookie(name) => {
+			if let Some(v) = req.and_then(|r| cookie_value(r.headers, name)) {
+				out.push_str(v);
+			}
+		}
+		FieldPath::HttpQuery(name) => {
+			if let Some(v) = req.and_then(|r| query_value(r.uri.query()?, name)) {
+				out.push_str(&v);
+			}
+		}
+		FieldPath::Peek | FieldPath::HttpBody => {}
+		tls => render_tls(out, tls, conn),
+	}
+}
+
+fn render_tls(out: &mut String, path: &FieldPath, conn: &ConnContext) {
+	let guard = conn.tls.lock();
+	let Some(tls) = guard.as_ref() else { return };
+	let cert = tls.peer_cert.as_deref();
+	match path {
+		FieldPath::TlsSni => out.push_str(tls.sni.as_deref().unwrap_or("")),
+		FieldPath::TlsAlpn => {
+			if let Some(alpn) = &tls.alpn {
+				out.push_str(&String::from_utf8_lossy(alpn));
+			}
+		}
+		FieldPath::TlsVersion => out.push_str(tls.version.map_or("", tls_version_str)),
+		FieldPath::TlsJa3 => out.push_str(tls.ja3.as_deref().unwrap_or("")),
+		FieldPath::TlsJa4 => out.push_str(tls.ja4.as_deref().unwrap_or("")),
+		FieldPath::TlsPeerCertPresent => out.push_str(if cert.is_some() { "true" } else { "false" }),
+		FieldPath::TlsPeerCertSubjectCn => {
+			out.push_str(cert.and_then(|c| c.subject_cn.as_deref()).unwrap_or(""));
+		}
+		FieldPath::TlsPeerCertSanDns => {
+			if let Some(c) = cert {
+				out.push_str(&c.san_dns.join(","));
+			}
+		}
+		FieldPath::TlsPeerCertFingerprintSha256 => {
+			out.push_str(cert.map_or("", |c| &c.fingerprint_sha256));
+		}
+		FieldPath::TlsPeerCertSpkiSha256 => out.push_str(cert.map_or("", |c| &c.spki_sha256)),
+		FieldPath::TlsPeerCertIssuerCn => {
+			out.push_str(cert.and_then(|c| c.issuer_cn.as_deref()).unwrap_or(""));
+		}
+		FieldPath::TlsPeerCertSerial => out.push_str(cert.map_or("", |c| &c.serial)),
+		_ => {}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, OnceLock};
+	use std::time::Instant;
+
+	use parking_lot::Mutex;
+
+	use super::*;
+	use crate::body::Body;
+	use crate::conn_context::{ConnId, PeerCertificate, TlsInfo};
+
+	fn conn() -> ConnContext {
+		ConnContext {
+			id: ConnId(1),
+			remote: "203.0.113.7:51000".parse().expect("remote"),
+			local: "10.0.0.1:443".parse().expect("local"),
+			transport: Transport::Tcp,
+			entered_at: Instant::now(),
+			tls: Mutex::new(None),
+			http_version: OnceLock::new(),
+			geo: OnceLock::new(),
+			proxy: OnceLock::new(),
+			peer_cred: OnceLock::new(),
+			user: Mutex::new(http::Extensions::new()),
+		}
+	}
+
+	fn request() -> Request {
+		http::Request::builder()
+			.uri("/api/v1?x=1")
+			.header("x-request-id", "abc")
+			.body(Body::Empty)
+			.expect("request")
+	}
+
+	#[test]
+	fn literal_and_escapes() {
+		let t = Template::parse("max-age=63072000; $$5 $x").expect("parse");
+		assert_eq!(t.as_literal(), Some("max-age=63072000; $5 $x"));
+		assert_eq!(Template::parse("").expect("parse").as_literal(), Some(""));
+	}
+
+	#[test]
+	fn renders_connection_and_request_fields() {
+		let t = Template::parse(
+			"${remote.ip}:${remote.port} ${http.uri.path}?${http.uri.query} id=${http.header.x-request-id}",
+		)
+		.expect("parse");
+		assert_eq!(t.as_literal(), None);
+		let req = request();
+		assert_eq!(
+			t.render(&conn(), Some(RequestRef::from(&req))),
+			"203.0.113.7:51000 /api/v1?x=1 id=abc"
+		);
+		// Without a request in scope, `http.*` renders empty.
+		assert_eq!(t.render(&conn(), None), "203.0.113.7:51000 ? id=");
+	}
+
+	#[test]
+	fn renders_cookie_query_host_and_version() {
+		let t = Template::parse("${http.version} ${http.uri.host} ${http.cookie.sid} ${http.query.x}")
+			.expect("parse");
+		let req = http::Request::builder()
+			.uri("/?x=a%20b")
+			.header("host", "Shop.Example:8080")
+			.header("cookie", "sid=s-1")
+			.body(Body::Empty)
+			.expect("request");
+		assert_eq!(t.render(&conn(), Some(RequestRef::from(&req))), "h1 shop.example s-1 a b");
+	}
+
+	#[test]
+	fn renders_tls_fields_and_misses_on_cleartext() {
+		let t = Template::parse("${tls.sni}|${tls.peer_cert.subject_cn}|${tls.peer_cert.san_dns}")
+			.expect("parse");
+		let c = conn();
+		assert_eq!(t.render(&c, None), "||");
+		*c.tls.lock() = Some(TlsInfo {
+			sni: Some(Arc::from("api.example.com")),
+			peer_cert: Some(Arc::new(PeerCertificate {
+				subject_cn: Some(Arc::from("svc-billing")),
+				san_dns: Arc::from(vec![Arc::from("a.internal"), Arc::from("b.internal")]),
+				..PeerCertificate::default()
+			})),
+			..TlsInfo::default()
+		});
+		assert_eq!(t.render(&c, None), "api.example.com|svc-billing|a.internal,b.internal");
+	}
+
+	#[test]
+	fn capture_references_need_a_pattern() {
+		let re = fancy_regex::Regex::new("^/blog/(\\d+)/(.*)$").expect("regex");
+		let t = Template::parse_with_captures("https://b.example/${1}/$2?from=${remote.ip}$$3", 3)
+			.expect("parse");
+		let caps = re.captures("/blog/42/hello").expect("match").expect("captures");
+		assert_eq!(
+			t.render_with_captures(&conn(), None, &caps),
+			"https://b.example/42/hello?from=203.0.113.7$3"
+		);
+		// Without a pattern `$1` is plain text.
+		assert_eq!(Template::parse("$1").expect("parse").as_literal(), Some("$1"));
+		let err = Template::parse_with_captures("/$3", 3).expect_err("no group 3");
+		assert!(err.contains("capture group $3 does not exist"), "{err}");
+	}
+
+	#[test]
+	fn rejects_bad_references() {
+		for (src, want) in [
+			("${remote.ip", "unterminated"),
+			("${}", "empty"),
+			("${remote.addr}", "unknown field path"),
+			("${Remote.Ip}", "lowercase"),
+			("${http.header.}", "requires a header name"),
+			("${http.body}", "cannot be used in a template"),
+			("${peek}", "cannot be used in a template"),
+		] {
+			let err = Template::parse(src).expect_err(src);
+			assert!(err.contains(want), "{src}: {err}");
+		}
+	}
+}
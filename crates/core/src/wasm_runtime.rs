@@ -112,12 +112,22 @@ pub struct SynthResponse {
 	pub body: Vec<u8>,
 }
 
+/// Arguments of the WIT `ban` function from `vane:host/security@0.1.0`.
+#[derive(Debug, Clone)]
+pub struct BanRequest {
+	pub duration_secs: Option<u32>,
+	pub reason: String,
+}
+
 /// Mirrors the WIT `l7-request-decision` variant from `vane:plugin/handler-l7-request@0.1.0`.
 #[derive(Debug)]
 pub enum L7RequestDecision {
 	Continue,
 	Short(SynthResponse),
 	Close,
+	/// No WIT case: the runtime reports it when `handle` called
+	/// `vane:host/security.ban`, whatever decision it returned.
+	Ban(BanRequest),
 }
 
 /// Mirrors the WIT `l7-response-input` record from `vane:plugin/handler-l7-response@0.1.0`.
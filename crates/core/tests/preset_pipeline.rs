@@ -1,7 +1,7 @@
 //! Integration tests for preset expansion through `compile()`.
 //!
-//! Black-box validation that each MVP preset (`port_forward`,
-//! `static_site`, `redirect_https`, `reverse_proxy`), when fed through
+//! Black-box validation that each preset (`port_forward`,
+//! `static_site`, `file_server`, `redirect_https`, `reverse_proxy`), when fed through
 //! the full compile pipeline (`merge → expand → analyze → lower →
 //! validate`), produces a valid `Arc<SymbolicFlowGraph>`. Internals of
 //! each expander are deliberately treated as a black box; assertions
@@ -127,6 +127,21 @@ fn static_site_preset_compiles_to_graph_with_http_synthesize_fetch() {
 	);
 }
 
+#[test]
+fn file_server_preset_compiles_to_graph_with_file_server_fetch() {
+	// The document root is only opened by the engine factory, so compile
+	// succeeds for a path that does not exist on this machine.
+	let entry =
+		preset_entry("docs", "file_server", ":8080", json!({ "root": "/srv/www", "spa": true }));
+	let graph = compile(vec![rule_file("a.json", vec![entry])], &Providers, &Providers)
+		.expect("file_server compiles");
+	assert!(
+		graph.fetches.iter().any(|f| f.kind == FetchKind::FileServer),
+		"expected FileServer in fetch slab",
+	);
+	assert!(graph.terminators.iter().any(|t| matches!(t, Terminator::WriteHttpResponse)));
+}
+
 #[test]
 fn redirect_https_preset_compiles_to_graph_with_308_synth() {
 	// `spec/crates/core.md` § _Compile pipeline_: expansion is a single rule emitting an
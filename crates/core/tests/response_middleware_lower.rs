@@ -0,0 +1,133 @@
+//! Lowering of `L7Response` entries in a rule's `middleware_chain`.
+//!
+//! Request-side entries run before the fetch; response-side entries are
+//! hung off the fetch's `next_response` edge, in chain order, ahead of
+//! the `WriteHttpResponse` terminator. A tunnel-only fetch has no
+//! response edge, so naming a response middleware there fails compile.
+//! See `spec/flow-model.md` § _Phase state machine_.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::json;
+use vane_core::compile::{RawRuleFile, compile};
+use vane_core::error::Error;
+use vane_core::fetch::{FetchKind, FetchOutputModes, FetchPhase};
+use vane_core::ir::{Node, NodeId, SymbolicFlowGraph};
+use vane_core::metadata::{
+	FetchMetadata, FetchMetadataProvider, MiddlewareMetadata, MiddlewareMetadataProvider,
+};
+use vane_core::middleware::MiddlewareKind;
+use vane_core::preset::RuleEntry;
+
+/// Names starting with `resp_` are `L7Response`; everything else is a
+/// plain `L7Request` middleware.
+struct Providers;
+
+fn validate_ok(_: &serde_json::Value) -> Result<(), Error> {
+	Ok(())
+}
+
+impl MiddlewareMetadataProvider for Providers {
+	fn get(&self, name: &str) -> Option<MiddlewareMetadata> {
+		let kind = if name.starts_with("resp_") {
+			MiddlewareKind::L7Response
+		} else {
+			MiddlewareKind::L7Request
+		};
+		Some(MiddlewareMetadata {
+			kind,
+			stateless: true,
+			needs_body: false,
+			validate_args: validate_ok,
+		})
+	}
+}
+
+impl FetchMetadataProvider for Providers {
+	fn get(&self, kind: FetchKind) -> Option<FetchMetadata> {
+		Some(FetchMetadata {
+			kind,
+			phase: match kind {
+				FetchKind::L4Forward => FetchPhase::L4,
+				_ => FetchPhase::L7,
+			},
+			output_modes: match kind {
+				FetchKind::L4Forward => FetchOutputModes { response: false, tunnel: true },
+				FetchKind::WebSocketUpgrade => FetchOutputModes { response: true, tunnel: true },
+				_ => FetchOutputModes { response: true, tunnel: false },
+			},
+			validate_args: validate_ok,
+		})
+	}
+}
+
+fn compile_one(raw: serde_json::Value) -> Result<Arc<SymbolicFlowGraph>, Error> {
+	let entry: RuleEntry = serde_json::from_value(raw).expect("parse rule entry");
+	let file =
+		RawRuleFile { path: PathBuf::from("rules/response_mw.json"), order: 0, rules: vec![entry] };
+	compile(vec![file], &Providers, &Providers)
+}
+
+fn middleware_name(graph: &SymbolicFlowGraph, node: NodeId) -> Option<&str> {
+	match &graph.nodes[node.get() as usize] {
+		Node::Middleware { id, .. } => Some(&graph.middlewares[id.get() as usize].name),
+		_ => None,
+	}
+}
+
+fn next_of(graph: &SymbolicFlowGraph, node: NodeId) -> NodeId {
+	match &graph.nodes[node.get() as usize] {
+		Node::Middleware { next, .. } => *next,
+		other => panic!("expected a middleware node, got {other:?}"),
+	}
+}
+
+#[test]
+fn response_middleware_follows_the_fetch_in_chain_order() {
+	let graph = compile_one(json!({
+		"name": "site",
+		"listen": [":7100"],
+		"middleware_chain": [
+			{ "use": "resp_first" },
+			{ "use": "req_only" },
+			{ "use": "resp_second" },
+		],
+		"terminate": { "type": "http_proxy", "upstream": "127.0.0.1:8080" },
+	}))
+	.expect("response middleware in the chain must compile");
+
+	let next_response = graph
+		.nodes
+		.iter()
+		.find_map(|n| match n {
+			Node::Fetch { next_response, .. } => *next_response,
+			_ => None,
+		})
+		.expect("http_proxy fetch has a response edge");
+	assert_eq!(middleware_name(&graph, next_response), Some("resp_first"));
+	let second = next_of(&graph, next_response);
+	assert_eq!(middleware_name(&graph, second), Some("resp_second"));
+	assert!(matches!(graph.nodes[next_of(&graph, second).get() as usize], Node::Terminate(_)));
+
+	// The request side keeps only `req_only`.
+	let request_side: Vec<&str> = graph
+		.middlewares
+		.iter()
+		.filter(|m| m.kind == MiddlewareKind::L7Request)
+		.map(|m| &*m.name)
+		.collect();
+	assert_eq!(request_side, ["req_only"]);
+}
+
+#[test]
+fn response_middleware_on_l4_forward_is_rejected() {
+	let err = compile_one(json!({
+		"name": "tunnel",
+		"listen": [":7101"],
+		"middleware_chain": [{ "use": "resp_first" }],
+		"terminate": { "type": "tcp_forward", "upstream": "127.0.0.1:8080" },
+	}))
+	.expect_err("L4Forward has no response edge");
+	assert!(err.to_string().contains("resp_first"), "{err}");
+}
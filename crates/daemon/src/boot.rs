@@ -9,6 +9,7 @@ use std::sync::Arc;
 
 use arc_swap::ArcSwap;
 use tokio::signal::unix::{Signal, SignalKind, signal};
+use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
 #[cfg(feature = "wasm")]
 use vane_core::PluginPolicyTable;
@@ -17,9 +18,11 @@ use vane_core::{Error, FlowLogSink, SymbolicFlowGraph};
 use vane_engine::factories::{FetchFactories, MiddlewareFactories};
 use vane_engine::flow_graph::{FlowGraph, LinkError, PluginRegistry};
 use vane_engine::flow_log_sink::{BroadcastSink, FanoutSink, default_sink_from_env};
-use vane_engine::{ListenerSet, SecurityConfig, SecurityState, VerbosityState};
+use vane_engine::geoip::GeoIpDb;
+use vane_engine::{InheritedSockets, ListenerSet, SecurityConfig, SecurityState, VerbosityState};
 
 use crate::providers::MetadataProviders;
+use crate::upgrade::UpgradeCtl;
 #[cfg(feature = "wasm")]
 use crate::wasm_loader;
 use crate::{collect_missing_plugin_refs, spawn_boot_health_watchdog};
@@ -85,9 +88,9 @@ fn install_session_ticketer() {
 }
 
 /// Base directory for all daemon-owned state (ACME accounts/certs,
-/// persistent ticket key, pre-compiled cwasm cache). Redirect all of it
-/// with a single knob via `VANE_STATE_DIR`; defaults to `/var/lib/vaned`.
-#[cfg(any(feature = "aws-lc-rs", feature = "acme"))]
+/// persistent ticket key, pre-compiled cwasm cache, persisted bans).
+/// Redirect all of it with a single knob via `VANE_STATE_DIR`; defaults
+/// to `/var/lib/vaned`.
 pub(crate) fn state_dir() -> std::path::PathBuf {
 	std::env::var_os("VANE_STATE_DIR")
 		.map_or_else(|| std::path::PathBuf::from("/var/lib/vaned"), std::path::PathBuf::from)
@@ -123,6 +126,48 @@ pub(crate) fn log_cgi_concurrency_cap() {
 	tracing::info!(cgi_max_concurrent, "cgi concurrency cap resolved");
 }
 
+/// Phase: adopt the sockets systemd passed through socket activation
+/// (`LISTEN_FDS` / `LISTEN_FDNAMES`). Runs before anything can spawn a
+/// child so the descriptors are marked close-on-exec first. Not
+/// activated yields an empty set and every listener binds itself.
+/// Spec: `spec/crates/engine.md` § _Socket activation_.
+///
+/// # Errors
+/// A malformed `LISTEN_*` environment refuses boot — a unit file that
+/// passes sockets the daemon cannot read is a deployment bug.
+pub(crate) fn init_socket_activation() -> Result<InheritedSockets, Error> {
+	let inherited = InheritedSockets::from_env()?;
+	if !inherited.is_empty() {
+		tracing::info!(
+			sockets = inherited.iter().count(),
+			"socket activation: adopted inherited sockets"
+		);
+	}
+	Ok(inherited)
+}
+
+/// Phase: open the GeoIP databases named by `VANE_GEOIP_DB` (a
+/// `PATH`-style list, highest priority first) and install them as the
+/// daemon-wide geo lookup behind `remote.country` / `remote.continent`
+/// / `remote.asn`. Unset or empty leaves no lookup installed and those
+/// fields miss. Spec: `spec/crates/engine.md` § _GeoIP_.
+///
+/// # Errors
+/// A configured file that cannot be mapped refuses boot — rules that
+/// block by country would otherwise silently admit everyone.
+pub(crate) fn init_geoip() -> Result<Option<Arc<GeoIpDb>>, Error> {
+	let paths: Vec<std::path::PathBuf> = std::env::var_os("VANE_GEOIP_DB")
+		.map(|v| std::env::split_paths(&v).filter(|p| !p.as_os_str().is_empty()).collect())
+		.unwrap_or_default();
+	if paths.is_empty() {
+		return Ok(None);
+	}
+	let db = Arc::new(GeoIpDb::open(paths)?);
+	vane_core::install_geo_lookup(Some(Arc::clone(&db) as Arc<dyn vane_core::GeoLookup>));
+	tracing::info!(files = db.paths().len(), "geoip database loaded");
+	Ok(Some(db))
+}
+
 /// Phase: WASM boot scan + plugin-ref check. Returns the registry +
 /// policy handles that thread through `MetadataProviders`, the initial
 /// link, and `ReloadCtx`. Refuses to start if any rule references a
@@ -208,9 +253,30 @@ pub(crate) fn init_security(
 		header_timeout_secs = security_cfg.header_timeout.as_secs(),
 		max_conn_per_ip = security_cfg.max_conn_per_ip,
 		max_total_conns = security_cfg.max_total_conns,
+		body_idle_timeout_secs = security_cfg.body_idle_timeout.as_secs(),
+		max_handshake_rate_per_ip = security_cfg.max_handshake_rate_per_ip,
+		max_pending_handshakes = security_cfg.max_pending_handshakes,
+		max_streams_per_conn = security_cfg.max_in_flight_streams_per_conn,
+		max_request_rate_per_conn = security_cfg.max_request_rate_per_conn,
+		ban_strikes = security_cfg.ban.strikes,
+		ban_client_errors = security_cfg.ban.client_errors,
+		ban_duration_secs = security_cfg.ban.duration.as_secs(),
+		ban_persist = env.sec_ban_persist,
 		crl_cache = security_cfg.crl_cache.is_some(),
 		"L1 security floor configured",
 	);
+	if env.sec_ban_persist {
+		// A store that cannot be read is not worth refusing to boot
+		// over: the daemon runs with an in-memory table and says so.
+		let dir = state_dir();
+		match std::fs::create_dir_all(&dir).and_then(|()| security.set_ban_store(dir.join("bans.json")))
+		{
+			Ok(restored) => tracing::info!(restored, "ban table restored"),
+			Err(e) => {
+				tracing::warn!(dir = %dir.display(), error = %e, "ban table not persisted");
+			}
+		}
+	}
 	Ok((security_cfg, security))
 }
 
@@ -284,19 +350,20 @@ pub(crate) async fn compose_log_sink()
 	Ok((sink, broadcast_sink))
 }
 
-/// Phase: install POSIX shutdown-signal streams BEFORE any listener
-/// starts. From this point on SIGTERM / SIGINT are queued onto the
+/// Phase: install POSIX signal streams BEFORE any listener starts.
+/// From this point on SIGTERM / SIGINT / SIGUSR2 are queued onto the
 /// returned streams instead of taking their default termination
 /// disposition. Awaited at the end of `main::run` via
-/// `wait_for_shutdown_signal`.
+/// `wait_for_shutdown_signal`; SIGUSR2 starts a binary upgrade there.
 ///
 /// # Panics
 /// Panics if the kernel-level signal handler install fails — that's
 /// unrecoverable.
-pub(crate) fn install_signal_handlers() -> (Signal, Signal) {
+pub(crate) fn install_signal_handlers() -> (Signal, Signal, Signal) {
 	let sigterm = signal(SignalKind::terminate()).expect("install SIGTERM handler");
 	let sigint = signal(SignalKind::interrupt()).expect("install SIGINT handler");
-	(sigterm, sigint)
+	let sigusr2 = signal(SignalKind::user_defined2()).expect("install SIGUSR2 handler");
+	(sigterm, sigint, sigusr2)
 }
 
 /// Phase: kick off ACME first-time issuance for every `tls.managed`
@@ -341,17 +408,18 @@ pub(crate) fn spawn_file_watcher(
 /// Handles produced by [`spawn_mgmt_plane`]. Threaded into
 /// `wait_for_shutdown_signal` so a triggered shutdown can cancel the
 /// mgmt cancel token, await the unix socket task, and abort each HTTP
-/// listener task.
+/// listener task. The scrape listener shares the mgmt cancel token.
 pub(crate) struct MgmtPlaneHandles {
 	pub cancel: CancellationToken,
 	pub unix_handle: Option<tokio::task::JoinHandle<()>>,
 	pub http_handles: Vec<tokio::task::JoinHandle<()>>,
+	pub metrics_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 /// State passed to `wait_for_shutdown_signal`: every cancel token, every
 /// background JoinHandle that needs awaiting on a triggered shutdown,
-/// the listener set to drain, the POSIX signal streams, and the soft-
-/// drain budget. Built once at the end of `main::run` and consumed.
+/// the listener set to drain, the POSIX signal streams, the upgrade
+/// driver SIGUSR2 starts, and the soft-drain budget. Built once at the end of `main::run` and consumed.
 pub(crate) struct ShutdownContext {
 	pub listeners: Arc<ListenerSet>,
 	pub watcher_cancel: CancellationToken,
@@ -360,21 +428,25 @@ pub(crate) struct ShutdownContext {
 	pub shutdown_trigger: CancellationToken,
 	pub sigterm: tokio::signal::unix::Signal,
 	pub sigint: tokio::signal::unix::Signal,
+	pub sigusr2: tokio::signal::unix::Signal,
+	pub upgrade: Arc<UpgradeCtl>,
 	pub soft_drain: std::time::Duration,
 }
 
 /// Phase: build [`crate::mgmt_handlers::MgmtState`] from the live daemon handles, bind the
-/// Unix mgmt socket, bind the HTTP mgmt listeners. Bind failures on
-/// either transport are logged and the daemon continues serving traffic
-/// without that flavour of mgmt — operators can fix the path / config
-/// and restart.
+/// Unix mgmt socket, bind the HTTP mgmt listeners and the scrape
+/// listener (`VANE_METRICS_HTTP_BIND`). Bind failures on the Unix
+/// socket and the scrape listener are logged and the daemon continues
+/// serving traffic without them — operators can fix the address and
+/// restart. After a binary upgrade the TCP binds are retried while the
+/// old daemon lets go of the ports.
 ///
 /// # Errors
 /// Surfaces the HTTP bind path's `Result` (typed once it gains real
 /// failure modes); the Unix bind path is internally infallible.
 #[allow(
 	clippy::too_many_arguments,
-	reason = "boot orchestrator wiring eight independent daemon-wide handles into MgmtState construction + two server binds"
+	reason = "boot orchestrator wiring nine independent daemon-wide handles into MgmtState construction + two server binds"
 )]
 pub(crate) async fn spawn_mgmt_plane(
 	reload: &Arc<crate::reload::ReloadCtx>,
@@ -385,6 +457,8 @@ pub(crate) async fn spawn_mgmt_plane(
 	tracing_broadcast: tracing_broadcast::BroadcastTracingLayer,
 	shutdown_trigger: &CancellationToken,
 	plugins: &PluginBootState,
+	upgrade: &Arc<UpgradeCtl>,
+	after_upgrade: bool,
 	env: &Env,
 ) -> Result<MgmtPlaneHandles, Box<dyn std::error::Error + Send + Sync>> {
 	#[cfg(feature = "wasm")]
@@ -408,40 +482,68 @@ pub(crate) async fn spawn_mgmt_plane(
 		tracing_broadcast,
 		shutdown_trigger: shutdown_trigger.clone(),
 		wasm_pool_stats,
+		upgrade: Arc::clone(upgrade),
 	});
 	let cancel = CancellationToken::new();
 	let unix_handle =
 		crate::bind_mgmt_unix_server(Arc::clone(&mgmt_state), cancel.clone(), &env.mgmt_unix).await;
-	let http_handles =
-		crate::bind_mgmt_http_server(Arc::clone(&mgmt_state), cancel.clone(), env).await?;
-	Ok(MgmtPlaneHandles { cancel, unix_handle, http_handles })
+	let http_handles = if after_upgrade {
+		crate::upgrade::bind_mgmt_http_after_upgrade(Arc::clone(&mgmt_state), cancel.clone(), env)
+			.await?
+	} else {
+		crate::bind_mgmt_http_server(Arc::clone(&mgmt_state), cancel.clone(), env).await?
+	};
+	let bind_attempts = if after_upgrade { crate::upgrade::MGMT_HTTP_BIND_ATTEMPTS } else { 1 };
+	// After an upgrade the old daemon has already handed over, so a
+	// failed scrape bind must not take the data plane down with it.
+	let metrics_handle =
+		match crate::metrics_http::spawn(env, Arc::clone(listeners), cancel.clone(), bind_attempts)
+			.await
+		{
+			Ok(h) => h,
+			Err(e) => {
+				tracing::warn!(error = %e, "metrics http bind failed; daemon continues without it");
+				None
+			}
+		};
+	Ok(MgmtPlaneHandles { cancel, unix_handle, http_handles, metrics_handle })
 }
 
 /// Phase: spawn the post-`listeners.start` background services that
 /// run for the daemon's lifetime — CRL URL refresher, L1 security
 /// state cleanup, and the boot-health watchdog. Each task observes
 /// `shutdown_trigger` (or dies with the runtime when the daemon exits).
+///
+/// The returned receiver resolves once the watchdog lets the daemon
+/// keep serving (everything bound, or partial coverage at timeout); it
+/// errors when the watchdog gives up. Binary upgrade waits on it before
+/// telling the old daemon to drain.
 pub(crate) fn spawn_boot_background_services(
 	security_cfg: &SecurityConfig,
 	security: &Arc<SecurityState>,
 	listeners: &Arc<ListenerSet>,
 	shutdown_trigger: &CancellationToken,
 	boot_health_timeout_secs: u32,
-) {
+) -> oneshot::Receiver<()> {
 	if let Some(cache) = &security_cfg.crl_cache {
 		cache.spawn_refresher(shutdown_trigger);
 	}
 	Arc::clone(security).spawn_cleanup(shutdown_trigger.clone());
 
+	let (ready_tx, ready_rx) = oneshot::channel();
 	let expected_listener_count = listeners.expected_count();
 	if expected_listener_count == 0 {
 		tracing::warn!("graph has no listener entries; daemon will serve nothing");
+		crate::BOOT_READY.store(true, std::sync::atomic::Ordering::Release);
+		let _ = ready_tx.send(());
 	} else {
 		spawn_boot_health_watchdog(
 			Arc::clone(listeners),
 			shutdown_trigger.clone(),
 			expected_listener_count,
 			boot_health_timeout_secs,
+			ready_tx,
 		);
 	}
+	ready_rx
 }
@@ -19,13 +19,21 @@
 //! flag so `main` returns a non-zero exit code. Partial bind failure stays
 //! a warn — the daemon serves whatever bound, and operators can read
 //! per-listener status via `vane stats`.
+//!
+//! A `vaned` started with `VANE_UPGRADE_PARENT_PID` is the new half of a
+//! binary upgrade (`vane upgrade` / SIGUSR2): it takes its listening
+//! sockets from the old daemon instead of socket activation, and binds
+//! its mgmt plane only after the watchdog passed and the old daemon
+//! started draining. See [`upgrade`].
 
 #[cfg(feature = "acme")]
 mod acme_boot;
 mod boot;
+mod metrics_http;
 mod mgmt_handlers;
 mod providers;
 mod reload;
+mod upgrade;
 #[cfg(feature = "wasm")]
 mod wasm_loader;
 mod watcher;
@@ -106,6 +114,11 @@ struct Args {
 /// instead of leaving an empty daemon up.
 static BOOT_HEALTH_EXIT: AtomicBool = AtomicBool::new(false);
 
+/// Set once the boot health watchdog lets the daemon serve — every
+/// listener bound, partial coverage at timeout, or a graph with no
+/// listeners. Read by the `/readyz` probe (`metrics_http`).
+static BOOT_READY: AtomicBool = AtomicBool::new(false);
+
 #[tokio::main]
 async fn main() -> std::process::ExitCode {
 	// Pre-clap fast path: `--version` / `-v` prints the build banner and
@@ -160,7 +173,10 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 			metrics::counter!("vane.trace.broadcast_dropped", "reason" => "no_subscribers").increment(1);
 		}),
 	);
-	init_tracing(tracing_broadcast.clone(), &loaded.env.log_level);
+	#[cfg(feature = "otel")]
+	let otel = init_tracing(tracing_broadcast.clone(), &loaded.env)?;
+	#[cfg(not(feature = "otel"))]
+	init_tracing(tracing_broadcast.clone(), &loaded.env)?;
 
 	tracing::info!(config_dir = %args.config_dir.display(), "loading config");
 	tracing::info!(
@@ -171,7 +187,13 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 	);
 
 	boot::install_global_runtime();
+	let upgrade_parent = upgrade::UpgradeParent::from_env(&loaded.env.mgmt_unix)?;
+	let inherited_sockets = match &upgrade_parent {
+		Some(parent) => parent.take_listeners().await?,
+		None => boot::init_socket_activation()?,
+	};
 	boot::log_cgi_concurrency_cap();
+	let geoip = boot::init_geoip()?;
 
 	let plugins = boot::init_plugin_state(&loaded).await?;
 
@@ -188,6 +210,10 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 	let crl_cache = init_crl_cache(&symbolic).await?;
 	let (security_cfg, security) = boot::init_security(&loaded.env, crl_cache.clone())?;
 
+	// Fixed before linking: no `http_proxy` request can hedge until a
+	// graph is live.
+	vane_engine::fetch::retry::init_hedge_budget(loaded.env.hedge_budget_percent);
+
 	let mw_factories = Arc::new(build_middleware_factories());
 
 	// Open the ACME registry before linking so the AcmeChallenge fetch
@@ -242,12 +268,15 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 	security.set_log_sink(Arc::clone(&sink));
 	let verbosity = Arc::new(VerbosityState::new());
 
-	let (sigterm, sigint) = boot::install_signal_handlers();
+	let (sigterm, sigint, sigusr2) = boot::install_signal_handlers();
 
-	let listeners = Arc::new(ListenerSet::from_security_and_bind_config(
-		Arc::clone(&security),
-		BindConfig::from(&loaded.env),
-	));
+	let listeners = Arc::new(
+		ListenerSet::from_security_and_bind_config(
+			Arc::clone(&security),
+			BindConfig::from(&loaded.env),
+		)
+		.with_inherited_sockets(inherited_sockets),
+	);
 
 	// Phase 1 of file-watcher startup: build the FSEvents subscription
 	// BEFORE calling `listeners.start`. Once a listener is reachable on
@@ -264,6 +293,9 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 			None
 		}
 	};
+	// GeoIP files usually live outside the config dir, so they get
+	// their own subscriptions, armed in the same pre-bind window.
+	let geoip_subs = geoip.as_deref().map(watcher::arm_geoip_subscriptions).unwrap_or_default();
 
 	listeners.start(&graph_swap, &verbosity, &sink);
 	tracing::info!(active = listeners.len(), "listeners started");
@@ -278,13 +310,18 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 		boot::spawn_acme_boot_tasks(registry, &graph_swap, &shutdown_trigger).await;
 	}
 
-	boot::spawn_boot_background_services(
+	let boot_ready = boot::spawn_boot_background_services(
 		&security_cfg,
 		&security,
 		&listeners,
 		&shutdown_trigger,
 		loaded.env.boot_health_timeout_secs,
 	);
+	let upgrade = Arc::new(upgrade::UpgradeCtl::new(
+		Arc::clone(&listeners),
+		shutdown_trigger.clone(),
+		loaded.env.boot_health_timeout_secs,
+	)?);
 
 	let _native_roots_refresh = spawn_native_roots_refresh(
 		shutdown_trigger.clone(),
@@ -305,6 +342,15 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 			watcher_cancel.clone(),
 		)
 	});
+	let _geoip_watch_handles =
+		geoip.as_ref().map(|db| watcher::spawn_geoip_handler(geoip_subs, db, &watcher_cancel));
+
+	// Upgrade child: the old daemon still owns the mgmt socket and
+	// port. Wait for the watchdog, let the old daemon start draining,
+	// then bind them here.
+	if let Some(parent) = &upgrade_parent {
+		parent.hand_over(boot_ready).await?;
+	}
 
 	let mgmt = boot::spawn_mgmt_plane(
 		&reload_ctx,
@@ -315,6 +361,8 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 		tracing_broadcast,
 		&shutdown_trigger,
 		&plugins,
+		&upgrade,
+		upgrade_parent.is_some(),
 		&loaded.env,
 	)
 	.await?;
@@ -327,31 +375,69 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 		shutdown_trigger,
 		sigterm,
 		sigint,
+		sigusr2,
+		upgrade,
 		soft_drain: Duration::from_secs(loaded.env.drain_timeout_secs.into()),
 	})
 	.await;
+	// Flush the spans of the drained requests before exit.
+	#[cfg(feature = "otel")]
+	if let Some(otel) = otel {
+		otel.shutdown().await;
+	}
 	Ok(())
 }
 
-fn init_tracing(tail_layer: BroadcastTracingLayer, fallback_filter: &str) {
+/// Tracer-provider guard returned by [`init_tracing`], flushed after
+/// the drain; `()` without the `otel` feature.
+#[cfg(feature = "otel")]
+type OtelGuard = Option<vane_engine::tracing_init::OtelGuard>;
+#[cfg(not(feature = "otel"))]
+type OtelGuard = ();
+
+fn init_tracing(
+	tail_layer: BroadcastTracingLayer,
+	env: &vane_core::Env,
+) -> Result<OtelGuard, Box<dyn std::error::Error + Send + Sync>> {
 	// The fmt-to-stderr layer's filter source priority:
 	//   1. `RUST_LOG` (operator ad-hoc override at the shell)
 	//   2. `VANE_LOG_LEVEL` from `<config>/.env` or OS env (typed via
-	//      `loaded.env.log_level`, supplied here as `fallback_filter`)
+	//      `env.log_level`)
 	//   3. The `"info"` default baked into `Env`.
 	//
 	// The broadcast layer is intentionally unfiltered so that `vane
 	// tail log` shows every event the daemon emits regardless of how
 	// noisy the operator's terminal is configured to be. Operators who
 	// want to thin the stream client-side can pipe to `jq`.
+	//
+	// The OTLP layer (`VANE_OTEL_ENDPOINT`) carries its own span filter
+	// — see `vane_engine::tracing_init::otel_layer`.
 	use tracing_subscriber::Layer;
 	use tracing_subscriber::layer::SubscriberExt;
 	use tracing_subscriber::util::SubscriberInitExt;
 	let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-		EnvFilter::try_new(fallback_filter).unwrap_or_else(|_| EnvFilter::new("info"))
+		EnvFilter::try_new(&env.log_level).unwrap_or_else(|_| EnvFilter::new("info"))
 	});
 	let fmt_layer = tracing_subscriber::fmt::layer().with_target(true).with_filter(filter);
-	tracing_subscriber::registry().with(fmt_layer).with(tail_layer).init();
+	#[cfg(feature = "otel")]
+	{
+		let (otel_layer, guard) = vane_engine::tracing_init::otel_layer(env)?.unzip();
+		tracing_subscriber::registry().with(otel_layer).with(fmt_layer).with(tail_layer).init();
+		if let Some(endpoint) = &env.otel_endpoint {
+			tracing::info!(%endpoint, protocol = ?env.otel_protocol, "otel span export on");
+		}
+		Ok(guard)
+	}
+	#[cfg(not(feature = "otel"))]
+	{
+		tracing_subscriber::registry().with(fmt_layer).with(tail_layer).init();
+		if env.otel_endpoint.is_some() {
+			tracing::warn!(
+				"VANE_OTEL_ENDPOINT is set but vaned was built without the `otel` feature; spans are not exported"
+			);
+		}
+		Ok(())
+	}
 }
 
 fn build_middleware_factories() -> MiddlewareFactories {
@@ -360,7 +446,11 @@ fn build_middleware_factories() -> MiddlewareFactories {
 	vane_engine::middleware::path_prefix::register(&mut mw);
 	vane_engine::middleware::method_match::register(&mut mw);
 	vane_engine::middleware::forward_client_ip::register(&mut mw);
+	vane_engine::middleware::headers::register(&mut mw);
 	vane_engine::middleware::rate_limit::register(&mut mw);
+	vane_engine::middleware::concurrency_limit::register(&mut mw);
+	vane_engine::middleware::rewrite::register(&mut mw);
+	vane_engine::middleware::compress::register(&mut mw);
 	vane_engine::middleware::sni_peek::register(&mut mw);
 	mw
 }
@@ -401,6 +491,7 @@ fn build_fetch_factories(
 	vane_engine::fetch::l4_forward::register(&mut fetch);
 	vane_engine::fetch::http_proxy::register(&mut fetch, crl_cache.clone());
 	vane_engine::fetch::http_synthesize::register(&mut fetch);
+	vane_engine::fetch::file_server::register(&mut fetch);
 	vane_engine::fetch::websocket_upgrade::register(&mut fetch, crl_cache);
 	#[cfg(feature = "acme")]
 	if let Some(registry) = acme_registry {
@@ -564,6 +655,7 @@ pub(crate) fn spawn_boot_health_watchdog(
 	shutdown_trigger: CancellationToken,
 	expected: usize,
 	timeout_secs: u32,
+	ready: tokio::sync::oneshot::Sender<()>,
 ) {
 	let timeout_secs = u64::from(timeout_secs);
 	tokio::spawn(async move {
@@ -572,6 +664,8 @@ pub(crate) fn spawn_boot_health_watchdog(
 			let bound = listeners.bound_count();
 			if bound == expected {
 				tracing::info!(bound, expected, "all listeners bound successfully");
+				BOOT_READY.store(true, Ordering::Release);
+				let _ = ready.send(());
 				return;
 			}
 			if Instant::now() >= deadline {
@@ -590,6 +684,8 @@ pub(crate) fn spawn_boot_health_watchdog(
 						timeout_secs,
 						"boot health timeout reached; daemon continues with partial coverage",
 					);
+					BOOT_READY.store(true, Ordering::Release);
+					let _ = ready.send(());
 				}
 				return;
 			}
@@ -607,20 +703,28 @@ async fn wait_for_shutdown_signal(ctx: boot::ShutdownContext) {
 		shutdown_trigger,
 		mut sigterm,
 		mut sigint,
+		mut sigusr2,
+		upgrade,
 		soft_drain,
 	} = ctx;
-	let drain = tokio::select! {
-		_ = sigterm.recv() => {
-			tracing::info!(drain_secs = soft_drain.as_secs(), "SIGTERM received — soft drain");
-			soft_drain
-		}
-		_ = sigint.recv() => {
-			tracing::info!("SIGINT received — immediate shutdown");
-			Duration::from_secs(0)
-		}
-		() = shutdown_trigger.cancelled() => {
-			tracing::info!(drain_secs = soft_drain.as_secs(), "mgmt shutdown verb received — soft drain");
-			soft_drain
+	let drain = loop {
+		tokio::select! {
+			_ = sigterm.recv() => {
+				tracing::info!(drain_secs = soft_drain.as_secs(), "SIGTERM received — soft drain");
+				break soft_drain;
+			}
+			_ = sigint.recv() => {
+				tracing::info!("SIGINT received — immediate shutdown");
+				break Duration::from_secs(0);
+			}
+			_ = sigusr2.recv() => {
+				tracing::info!("SIGUSR2 received — starting binary upgrade");
+				upgrade.start_logged();
+			}
+			() = shutdown_trigger.cancelled() => {
+				tracing::info!(drain_secs = soft_drain.as_secs(), "shutdown triggered — soft drain");
+				break soft_drain;
+			}
 		}
 	};
 	watcher_cancel.cancel();
@@ -634,7 +738,11 @@ async fn wait_for_shutdown_signal(ctx: boot::ShutdownContext) {
 	for h in mgmt.http_handles {
 		let _ = h.await;
 	}
+	if let Some(h) = mgmt.metrics_handle {
+		let _ = h.await;
+	}
 	listeners.shutdown(drain).await;
+	listeners.security().flush_bans();
 	tracing::info!("vaned exited cleanly");
 }
 
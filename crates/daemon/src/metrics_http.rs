@@ -0,0 +1,259 @@
+//! Unauthenticated scrape listener behind `VANE_METRICS_HTTP_BIND`.
+//!
+//! Serves three read-only routes on its own socket, apart from the
+//! management transports:
+//!
+//! - `GET /metrics` — the Prometheus text exposition `get_metrics`
+//!   renders.
+//! - `GET /healthz` — liveness: `200` until the boot health watchdog
+//!   gives up on a daemon that bound nothing.
+//! - `GET /readyz` — readiness: `503` while listeners are still
+//!   binding, `200` once the watchdog lets the daemon serve.
+//!
+//! No mgmt verb is reachable here. `VANE_METRICS_HTTP_ALLOW` narrows
+//! the accepted sources; a peer outside it is closed before a byte is
+//! read. The listener lives on the mgmt cancel token, so it closes at
+//! drain start and frees the port for an upgraded daemon.
+//! Spec: `spec/crates/mgmt.md` § _Scrape listener_.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use vane_engine::ListenerSet;
+
+/// Concurrent scrape connections. Scrapers and probes hold one short
+/// connection each; the cap turns a connect flood into accept-queue
+/// backpressure instead of unbounded task spawning.
+const MAX_CONCURRENT_CONNECTIONS: usize = 64;
+
+/// Header-section read timeout, so a slowloris peer cannot pin a task.
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prometheus text exposition format, version 0.0.4.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Bind backoff between attempts after a binary upgrade.
+const BIND_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Bind `VANE_METRICS_HTTP_BIND` and spawn its accept loop. `None` when
+/// the variable is unset. `attempts` is `1` on a plain boot; after a
+/// binary upgrade the old daemon may still hold the port for a moment.
+///
+/// # Errors
+/// The last bind attempt's error.
+pub(crate) async fn spawn(
+	env: &vane_core::Env,
+	listeners: Arc<ListenerSet>,
+	cancel: CancellationToken,
+	attempts: u32,
+) -> Result<Option<tokio::task::JoinHandle<()>>, Box<dyn std::error::Error + Send + Sync>> {
+	let Some(addr) = env.metrics_http_bind else {
+		return Ok(None);
+	};
+	let mut attempt = 1;
+	let listener = loop {
+		match TcpListener::bind(addr).await {
+			Ok(l) => break l,
+			Err(e) if attempt < attempts => {
+				tracing::debug!(attempt, %addr, error = %e, "metrics http bind failed; retrying");
+				attempt += 1;
+				tokio::time::sleep(BIND_BACKOFF).await;
+			}
+			Err(e) => return Err(format!("metrics http bind {addr}: {e}").into()),
+		}
+	};
+	let allow: Arc<[ipnet::IpNet]> = env.metrics_http_allow.clone().into();
+	tracing::info!(%addr, allow = allow.len(), "metrics http listening");
+	Ok(Some(tokio::spawn(run_accept_loop(listener, allow, listeners, cancel))))
+}
+
+async fn run_accept_loop(
+	listener: TcpListener,
+	allow: Arc<[ipnet::IpNet]>,
+	listeners: Arc<ListenerSet>,
+	cancel: CancellationToken,
+) {
+	let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_CONNECTIONS));
+	loop {
+		let permit = tokio::select! {
+			biased;
+			() = cancel.cancelled() => return,
+			p = Arc::clone(&semaphore).acquire_owned() => match p {
+				Ok(p) => p,
+				Err(_) => return,
+			},
+		};
+		let (stream, peer) = tokio::select! {
+			biased;
+			() = cancel.cancelled() => return,
+			res = listener.accept() => match res {
+				Ok(v) => v,
+				Err(e) => {
+					tracing::debug!(error = %e, "metrics http accept error");
+					continue;
+				}
+			},
+		};
+		if !source_allowed(&allow, peer.ip()) {
+			tracing::debug!(%peer, "metrics http: source not in VANE_METRICS_HTTP_ALLOW");
+			continue;
+		}
+		let listeners = Arc::clone(&listeners);
+		let cancel = cancel.clone();
+		tokio::spawn(async move {
+			let _permit = permit;
+			serve_one_connection(stream, peer, listeners, cancel).await;
+		});
+	}
+}
+
+async fn serve_one_connection(
+	stream: tokio::net::TcpStream,
+	peer: SocketAddr,
+	listeners: Arc<ListenerSet>,
+	cancel: CancellationToken,
+) {
+	let svc = service_fn(move |req: Request<Incoming>| {
+		let listeners = Arc::clone(&listeners);
+		async move { Ok::<_, std::convert::Infallible>(handle_one_request(&req, &listeners)) }
+	});
+	let mut builder = http1::Builder::new();
+	builder.timer(TokioTimer::new()).header_read_timeout(HEADER_READ_TIMEOUT);
+	let conn = builder.serve_connection(TokioIo::new(stream), svc);
+	tokio::select! {
+		biased;
+		() = cancel.cancelled() => {}
+		res = conn => {
+			if let Err(e) = res {
+				tracing::trace!(%peer, error = %e, "metrics http connection ended");
+			}
+		}
+	}
+}
+
+fn handle_one_request(req: &Request<Incoming>, listeners: &ListenerSet) -> Response<Full<Bytes>> {
+	if req.method() != Method::GET && req.method() != Method::HEAD {
+		return Response::builder()
+			.status(StatusCode::METHOD_NOT_ALLOWED)
+			.header(hyper::header::ALLOW, "GET, HEAD")
+			.body(Full::default())
+			.expect("static response");
+	}
+	let boot = BootState {
+		ready: crate::BOOT_READY.load(Ordering::Acquire),
+		failed: crate::BOOT_HEALTH_EXIT.load(Ordering::Acquire),
+		bound: listeners.bound_count(),
+		expected: listeners.expected_count(),
+	};
+	let (status, content_type, body) = match req.uri().path() {
+		"/metrics" => match vane_engine::metrics::render_prometheus() {
+			Some(text) => (StatusCode::OK, PROMETHEUS_CONTENT_TYPE, text),
+			None => {
+				(StatusCode::SERVICE_UNAVAILABLE, "text/plain", "metrics recorder not installed\n".into())
+			}
+		},
+		"/healthz" => {
+			let (status, body) = boot.health();
+			(status, "text/plain", body)
+		}
+		"/readyz" => {
+			let (status, body) = boot.readiness();
+			(status, "text/plain", body)
+		}
+		_ => (StatusCode::NOT_FOUND, "text/plain", "not found\n".into()),
+	};
+	Response::builder()
+		.status(status)
+		.header(hyper::header::CONTENT_TYPE, content_type)
+		.header(hyper::header::CACHE_CONTROL, "no-store")
+		.body(Full::from(body))
+		.expect("static response")
+}
+
+/// An empty allow-list admits every source.
+fn source_allowed(allow: &[ipnet::IpNet], ip: IpAddr) -> bool {
+	let ip = ip.to_canonical();
+	allow.is_empty() || allow.iter().any(|net| net.contains(&ip))
+}
+
+/// Snapshot of the boot watchdog and listener-bind state the probes
+/// report.
+struct BootState {
+	/// The watchdog let the daemon serve (all bound, partial coverage
+	/// at timeout, or a graph with no listeners).
+	ready: bool,
+	/// The watchdog found nothing bound and is shutting the daemon down.
+	failed: bool,
+	bound: usize,
+	expected: usize,
+}
+
+impl BootState {
+	fn health(&self) -> (StatusCode, String) {
+		if self.failed {
+			(StatusCode::SERVICE_UNAVAILABLE, format!("unhealthy: no listener bound\n{}", self.tally()))
+		} else {
+			(StatusCode::OK, format!("ok\n{}", self.tally()))
+		}
+	}
+
+	fn readiness(&self) -> (StatusCode, String) {
+		if self.failed {
+			(StatusCode::SERVICE_UNAVAILABLE, format!("not ready: no listener bound\n{}", self.tally()))
+		} else if self.ready {
+			(StatusCode::OK, format!("ready\n{}", self.tally()))
+		} else {
+			(StatusCode::SERVICE_UNAVAILABLE, format!("not ready: binding listeners\n{}", self.tally()))
+		}
+	}
+
+	fn tally(&self) -> String {
+		format!("listeners bound {}/{}\n", self.bound, self.expected)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn state(ready: bool, failed: bool, bound: usize) -> BootState {
+		BootState { ready, failed, bound, expected: 2 }
+	}
+
+	#[test]
+	fn readiness_follows_the_boot_watchdog() {
+		let (status, body) = state(false, false, 1).readiness();
+		assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+		assert!(body.contains("binding listeners") && body.contains("bound 1/2"), "{body}");
+		assert_eq!(state(true, false, 1).readiness().0, StatusCode::OK, "partial coverage serves");
+		assert_eq!(state(false, true, 0).readiness().0, StatusCode::SERVICE_UNAVAILABLE);
+	}
+
+	#[test]
+	fn health_fails_only_when_nothing_bound() {
+		assert_eq!(state(false, false, 0).health().0, StatusCode::OK, "still binding is alive");
+		assert_eq!(state(true, false, 2).health().0, StatusCode::OK);
+		assert_eq!(state(false, true, 0).health().0, StatusCode::SERVICE_UNAVAILABLE);
+	}
+
+	#[test]
+	fn allow_list_matches_canonical_sources() {
+		let allow: Vec<ipnet::IpNet> = vec!["10.0.0.0/8".parse().unwrap()];
+		assert!(source_allowed(&allow, "10.1.2.3".parse().unwrap()));
+		assert!(source_allowed(&allow, "::ffff:10.1.2.3".parse().unwrap()), "v4-mapped peer");
+		assert!(!source_allowed(&allow, "192.0.2.1".parse().unwrap()));
+		assert!(source_allowed(&[], "192.0.2.1".parse().unwrap()), "empty list admits all");
+	}
+}
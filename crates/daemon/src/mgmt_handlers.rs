@@ -28,15 +28,18 @@ use vane_mgmt::protocol::{Request, WireError, WireErrorKind};
 use vane_mgmt::server::{DispatchOutcome, EventStream, Handler};
 use vane_mgmt::verb::{
 	CompileDryRunArgs, CompileDryRunResult, ConnectionInfo, GetConfigResult, GetConnectionsResult,
-	GetMetricsArgs, GetMetricsResult, GetPoolsResult, GetUpstreamsResult, ListenerStatus, PingResult,
-	ReloadResult, ShutdownResult, StatsResult, TcpUpstreamEntry, VERB_COMPILE_DRY_RUN,
-	VERB_FORCE_RENEW, VERB_GET_CERTS, VERB_GET_CONFIG, VERB_GET_CONNECTIONS, VERB_GET_METRICS,
-	VERB_GET_POOLS, VERB_GET_UPSTREAMS, VERB_PING, VERB_RELOAD, VERB_SHUTDOWN, VERB_STATS,
-	VERB_TAIL_FLOW, VERB_TAIL_LOG, WasmPoolEntry,
+	GetHealthResult, GetMetricsArgs, GetMetricsResult, GetPoolsResult, GetUpstreamsResult,
+	HealthTargetEntry, ListenerStatus, PingResult, ReloadResult, ShutdownResult, StatsResult,
+	TcpUpstreamEntry, UpgradeChildArgs, UpgradeResult, UpstreamMemberEntry, UpstreamSetEntry,
+	VERB_COMPILE_DRY_RUN, VERB_FORCE_RENEW, VERB_GET_CACHE, VERB_GET_CERTS, VERB_GET_CONFIG,
+	VERB_GET_CONNECTIONS, VERB_GET_HEALTH, VERB_GET_METRICS, VERB_GET_POOLS, VERB_GET_UPSTREAMS,
+	VERB_PING, VERB_RELOAD, VERB_SHUTDOWN, VERB_STATS, VERB_TAIL_FLOW, VERB_TAIL_LOG, VERB_UPGRADE,
+	VERB_UPGRADE_READY, VERB_UPGRADE_TAKE_LISTENERS, WasmPoolEntry,
 };
 
 use crate::providers::MetadataProviders;
 use crate::reload::{ReloadCtx, ReloadOutcome, reload_once};
+use crate::upgrade::UpgradeCtl;
 
 /// Live daemon state visible to mgmt verb handlers. Built once during
 /// boot in `main::run` and shared by every accepted mgmt connection
@@ -65,6 +68,9 @@ pub(crate) struct MgmtState {
 	/// `get_pools` then returns an empty `wasm` list, and CGI / TCP
 	/// pool data still flows through.
 	pub wasm_pool_stats: Option<Arc<dyn WasmPoolStats>>,
+	/// Binary-upgrade driver behind the `upgrade` verb and its two
+	/// internal follow-ups (also reached through SIGUSR2).
+	pub upgrade: Arc<UpgradeCtl>,
 }
 
 #[async_trait]
@@ -81,10 +87,20 @@ impl Handler for MgmtState {
 			let rx = self.tracing_broadcast.subscribe();
 			return DispatchOutcome::Stream(Box::new(TailLogStream { rx }));
 		}
+		// The listener hand-off carries descriptors next to its reply,
+		// so it gets its own outcome too.
+		if req.verb == VERB_UPGRADE_TAKE_LISTENERS {
+			return match self.handle_upgrade_take_listeners(req.args) {
+				Ok((value, fds)) => DispatchOutcome::OneShotWithFds(value, fds),
+				Err(e) => DispatchOutcome::OneShot(Err(e)),
+			};
+		}
 		let result: Result<serde_json::Value, WireError> = match req.verb.as_str() {
 			VERB_PING => self.handle_ping(),
 			VERB_STATS => self.handle_stats(),
 			VERB_SHUTDOWN => self.handle_shutdown(),
+			VERB_UPGRADE => self.handle_upgrade(),
+			VERB_UPGRADE_READY => self.handle_upgrade_ready(req.args),
 			VERB_GET_CONFIG => self.handle_get_config(),
 			VERB_RELOAD => self.handle_reload().await,
 			VERB_COMPILE_DRY_RUN => self.handle_compile_dry_run(req.args),
@@ -92,6 +108,12 @@ impl Handler for MgmtState {
 			VERB_GET_METRICS => self.handle_get_metrics(req.args),
 			VERB_GET_POOLS => self.handle_get_pools(),
 			VERB_GET_UPSTREAMS => self.handle_get_upstreams(),
+			VERB_GET_HEALTH => Self::handle_get_health(),
+			VERB_GET_CACHE => Self::handle_get_cache(req.args),
+			vane_mgmt::verb::VERB_CACHE_PURGE => Self::handle_cache_purge(req.args),
+			vane_mgmt::verb::VERB_GET_BANS => self.handle_get_bans(),
+			vane_mgmt::verb::VERB_BAN => self.handle_ban(req.args),
+			vane_mgmt::verb::VERB_UNBAN => self.handle_unban(req.args),
 			vane_mgmt::verb::VERB_RELOAD_NATIVE_ROOTS => Self::handle_reload_native_roots(),
 			vane_mgmt::verb::VERB_POOL_DRAIN => Self::handle_pool_drain(req.args),
 			#[cfg(feature = "acme")]
@@ -297,6 +319,22 @@ fn json<R: serde::Serialize>(r: &R) -> Result<serde_json::Value, WireError> {
 		.map_err(|e| WireError::new(WireErrorKind::Internal, format!("encode: {e}")))
 }
 
+fn unix_ms_now() -> u64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn ban_info(r: vane_engine::security::BanRecord, now_ms: u64) -> vane_mgmt::verb::BanInfo {
+	vane_mgmt::verb::BanInfo {
+		target: r.target,
+		remaining_secs: r.until_ms.saturating_sub(now_ms).div_ceil(1_000),
+		level: r.level,
+		reason: r.reason,
+		source: r.source.as_str().to_string(),
+	}
+}
+
 /// Render a [`std::time::SystemTime`] as RFC 3339 / ISO 8601 UTC.
 /// Used by `get_certs` to format the wire-shape timestamp fields
 /// per `spec/crates/engine-acme.md` § _mgmt verbs_. Falls back to
@@ -384,6 +422,34 @@ impl MgmtState {
 		json(&ShutdownResult { draining: true })
 	}
 
+	fn handle_upgrade(&self) -> Result<serde_json::Value, WireError> {
+		let child_pid = self
+			.upgrade
+			.start()
+			.map_err(|e| WireError::new(WireErrorKind::Internal, format!("upgrade: {e}")))?;
+		json(&UpgradeResult { child_pid })
+	}
+
+	fn handle_upgrade_take_listeners(
+		&self,
+		args: serde_json::Value,
+	) -> Result<(serde_json::Value, Vec<std::os::fd::OwnedFd>), WireError> {
+		let UpgradeChildArgs { pid } = parse_args(args)?;
+		let (result, fds) = self.upgrade.take_listeners(pid).map_err(|e| {
+			WireError::new(WireErrorKind::BadArgs, format!("upgrade_take_listeners: {e}"))
+		})?;
+		Ok((json(&result)?, fds))
+	}
+
+	fn handle_upgrade_ready(&self, args: serde_json::Value) -> Result<serde_json::Value, WireError> {
+		let UpgradeChildArgs { pid } = parse_args(args)?;
+		let result = self
+			.upgrade
+			.child_ready(pid)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("upgrade_ready: {e}")))?;
+		json(&result)
+	}
+
 	fn handle_get_config(&self) -> Result<serde_json::Value, WireError> {
 		let graph = self.reload.graph.load();
 		let serialized = serde_json::to_value(graph.symbolic().as_ref())
@@ -445,7 +511,10 @@ impl MgmtState {
 			.into_iter()
 			.map(|c| ConnectionInfo {
 				conn_id: c.conn_id.to_string(),
-				listener_addr: c.listener_addr.to_string(),
+				listener_addr: c
+					.listener_path
+					.as_ref()
+					.map_or_else(|| c.listener_addr.to_string(), |p| format!("unix:{}", p.display())),
 				remote: c.remote.to_string(),
 				age_ms: u64::try_from(now.saturating_duration_since(c.accepted_at).as_millis())
 					.unwrap_or(u64::MAX),
@@ -519,7 +588,151 @@ impl MgmtState {
 			})
 			.collect();
 		let quic = quic_upstream_entries();
-		json(&GetUpstreamsResult { tcp, quic })
+		let sets = vane_engine::fetch::balance::snapshot()
+			.into_iter()
+			.map(|s| UpstreamSetEntry {
+				set_id: s.set_id,
+				policy: s.policy,
+				members: s
+					.members
+					.into_iter()
+					.map(|m| UpstreamMemberEntry {
+						health: vane_engine::fetch::health::status_for(&m.authority).map(|h| h.to_string()),
+						authority: m.authority,
+						weight: m.weight,
+						in_flight: m.in_flight,
+						selected: m.selected,
+					})
+					.collect(),
+			})
+			.collect();
+		json(&GetUpstreamsResult { tcp, quic, sets })
+	}
+
+	fn handle_get_health() -> Result<serde_json::Value, WireError> {
+		let targets = vane_engine::fetch::health::snapshot()
+			.into_iter()
+			.map(|t| HealthTargetEntry {
+				target_id: t.target_id,
+				authority: t.authority,
+				kind: t.kind.to_string(),
+				status: t.status.to_string(),
+				consecutive_successes: t.consecutive_successes,
+				consecutive_failures: t.consecutive_failures,
+				last_check_age_ms: t.last_check_age_ms,
+				last_change_age_ms: t.last_change_age_ms,
+				last_error: t.last_error,
+			})
+			.collect();
+		json(&GetHealthResult { targets })
+	}
+
+	fn handle_get_cache(args: serde_json::Value) -> Result<serde_json::Value, WireError> {
+		use vane_mgmt::verb::{CacheEntryInfo, GetCacheArgs, GetCacheResult};
+
+		let parsed: GetCacheArgs = serde_json::from_value(args)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("get_cache args: {e}")))?;
+		let snap = vane_engine::fetch::cache::store()
+			.snapshot(parsed.prefix.as_deref(), parsed.limit.unwrap_or(100));
+		json(&GetCacheResult {
+			capacity_bytes: snap.capacity_bytes,
+			used_bytes: snap.used_bytes,
+			keys: snap.keys,
+			matched_keys: snap.matched_keys,
+			entries: snap
+				.entries
+				.into_iter()
+				.map(|e| CacheEntryInfo {
+					key: e.key,
+					status: e.status,
+					variants: e.variants,
+					bytes: e.bytes,
+					age_secs: e.age_secs,
+					ttl_secs: e.ttl_secs,
+					hits: e.hits,
+				})
+				.collect(),
+		})
+	}
+
+	/// `cache_purge` verb: drop one key or every key under a prefix
+	/// from the shared response cache.
+	fn handle_cache_purge(args: serde_json::Value) -> Result<serde_json::Value, WireError> {
+		use vane_mgmt::verb::{CachePurgeArgs, CachePurgeResult};
+
+		let parsed: CachePurgeArgs = serde_json::from_value(args)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("cache_purge args: {e}")))?;
+		let store = vane_engine::fetch::cache::store();
+		let purged = match (parsed.key.as_deref(), parsed.prefix.as_deref()) {
+			(Some(key), None) => store.purge_key(key),
+			(None, Some(prefix)) => store.purge_prefix(prefix),
+			_ => {
+				return Err(WireError::new(
+					WireErrorKind::BadArgs,
+					"cache_purge: exactly one of key / prefix is required",
+				));
+			}
+		};
+		json(&CachePurgeResult { purged })
+	}
+
+	fn handle_get_bans(&self) -> Result<serde_json::Value, WireError> {
+		let bans = self.listeners.security().bans();
+		let now_ms = unix_ms_now();
+		json(&vane_mgmt::verb::GetBansResult {
+			allow: bans.config().allow.iter().map(ToString::to_string).collect(),
+			bans: bans.snapshot().into_iter().map(|r| ban_info(r, now_ms)).collect(),
+		})
+	}
+
+	/// `ban` verb: ban an IP or CIDR by hand. Refused for prefixes that
+	/// overlap `VANE_SEC_BAN_ALLOW`.
+	fn handle_ban(&self, args: serde_json::Value) -> Result<serde_json::Value, WireError> {
+		use vane_engine::security::{BanSource, ban::parse_target};
+		use vane_mgmt::verb::{BanArgs, BanResult};
+
+		let parsed: BanArgs = serde_json::from_value(args)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("ban args: {e}")))?;
+		let target = parse_target(parsed.target.trim()).ok_or_else(|| {
+			WireError::new(
+				WireErrorKind::BadArgs,
+				format!("ban: target {:?} is not an IP address or CIDR", parsed.target),
+			)
+		})?;
+		if parsed.duration_secs == Some(0) {
+			return Err(WireError::new(WireErrorKind::BadArgs, "ban: duration_secs must be positive"));
+		}
+		let security = self.listeners.security();
+		let record = security
+			.bans()
+			.ban(
+				target,
+				parsed.duration_secs.map(std::time::Duration::from_secs),
+				parsed.reason.unwrap_or_default(),
+				BanSource::Manual,
+			)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("ban: {e}")))?;
+		security.flush_bans();
+		json(&BanResult { ban: ban_info(record, unix_ms_now()) })
+	}
+
+	/// `unban` verb: lift the ban on exactly `target`.
+	fn handle_unban(&self, args: serde_json::Value) -> Result<serde_json::Value, WireError> {
+		use vane_mgmt::verb::{UnbanArgs, UnbanResult};
+
+		let parsed: UnbanArgs = serde_json::from_value(args)
+			.map_err(|e| WireError::new(WireErrorKind::BadArgs, format!("unban args: {e}")))?;
+		let target =
+			vane_engine::security::ban::parse_target(parsed.target.trim()).ok_or_else(|| {
+				WireError::new(
+					WireErrorKind::BadArgs,
+					format!("unban: target {:?} is not an IP address or CIDR", parsed.target),
+				)
+			})?;
+		let security = self.listeners.security();
+		let removed = security.bans().unban(target);
+		security.flush_bans();
+		json(&UnbanResult { removed })
 	}
 
 	/// `reload_native_roots` verb: re-read the OS trust store and
@@ -696,16 +909,18 @@ impl MgmtState {
 	/// the in-flight connection list.
 	fn listener_status(&self) -> Vec<ListenerStatus> {
 		let graph = self.reload.graph.load();
-		graph
-			.symbolic()
-			.entries
-			.keys()
-			.map(|addr| ListenerStatus {
-				addr: addr.to_string(),
-				bound: self.listeners.is_bound(addr),
-				in_flight_count: self.listeners.in_flight_count(addr).unwrap_or(0),
-			})
-			.collect()
+		let symbolic = graph.symbolic();
+		let net = symbolic.entries.keys().map(|addr| ListenerStatus {
+			addr: addr.to_string(),
+			bound: self.listeners.is_bound(addr),
+			in_flight_count: self.listeners.in_flight_count(addr).unwrap_or(0),
+		});
+		let unix = symbolic.meta.unix_listeners.keys().map(|path| ListenerStatus {
+			addr: format!("unix:{}", path.display()),
+			bound: self.listeners.is_unix_bound(path),
+			in_flight_count: self.listeners.unix_in_flight_count(path).unwrap_or(0),
+		});
+		net.chain(unix).collect()
 	}
 }
 
@@ -759,6 +974,7 @@ mod tests {
 	async fn one_shot(state: &MgmtState, req: Request) -> Result<serde_json::Value, WireError> {
 		match state.dispatch(req).await {
 			DispatchOutcome::OneShot(r) => r,
+			DispatchOutcome::OneShotWithFds(..) => panic!("expected OneShot, got OneShotWithFds"),
 			DispatchOutcome::Stream(_) => panic!("expected OneShot, got Stream"),
 		}
 	}
@@ -791,16 +1007,21 @@ mod tests {
 			acme_registry: None,
 			run_lock: tokio::sync::Mutex::new(()),
 		});
+		let listeners = Arc::new(ListenerSet::new());
+		let shutdown_trigger = CancellationToken::new();
+		let upgrade =
+			UpgradeCtl::new(Arc::clone(&listeners), shutdown_trigger.clone(), 60).expect("upgrade ctl");
 		Arc::new(MgmtState {
 			started_at: Instant::now(),
 			reload,
-			listeners: Arc::new(ListenerSet::new()),
+			listeners,
 			verbosity: Arc::new(VerbosityState::new()),
 			log_sink: Arc::new(NullSink),
 			broadcast: Arc::new(BroadcastSink::new()),
 			tracing_broadcast: BroadcastTracingLayer::new(),
-			shutdown_trigger: CancellationToken::new(),
+			shutdown_trigger,
 			wasm_pool_stats: None,
+			upgrade: Arc::new(upgrade),
 		})
 	}
 
@@ -990,7 +1211,9 @@ mod tests {
 			.await;
 		let mut stream = match outcome {
 			DispatchOutcome::Stream(s) => s,
-			DispatchOutcome::OneShot(_) => panic!("tail_flow must produce a Stream"),
+			DispatchOutcome::OneShot(_) | DispatchOutcome::OneShotWithFds(..) => {
+				panic!("tail_flow must produce a Stream")
+			}
 		};
 
 		// Emit a FlowLogEvent through the broadcast sink and observe it
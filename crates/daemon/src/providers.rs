@@ -2,11 +2,12 @@
 //!
 //! Lists exactly the middleware / fetch shapes that the daemon registers
 //! with engine factories — `host_header_match`, `path_prefix`,
-//! `method_match`, `forward_client_ip`, plus `HttpProxy`,
-//! `HttpSynthesize`, `L4Forward`. Compile and link agree on the
-//! registered set: anything else fails compile with `unknown
-//! middleware` / `unknown fetch`, or fails link with `UnknownFetch` /
-//! `UnknownMiddleware` if the metadata provider is permissive.
+//! `method_match`, `forward_client_ip`, `rate_limit`, `concurrency_limit`,
+//! `compress`, plus `HttpProxy`, `HttpSynthesize`, `L4Forward`. Compile
+//! and link agree on the registered set: anything else fails compile
+//! with `unknown middleware` / `unknown fetch`, or fails link with
+//! `UnknownFetch` / `UnknownMiddleware` if the metadata provider is
+//! permissive.
 //!
 //! Lives in its own module so both `main.rs` boot path and `reload.rs`
 //! recompile path can share one source of truth.
@@ -68,14 +69,14 @@ impl MiddlewareMetadataProvider for MetadataProviders {
 			return self.lookup_plugin(name);
 		}
 		let (kind, stateless, needs_body) = match name {
-			"host_header_match" | "path_prefix" | "method_match" | "forward_client_ip" => {
-				(MiddlewareKind::L7Request, true, false)
-			}
+			"host_header_match" | "path_prefix" | "method_match" | "forward_client_ip"
+			| "request_headers" | "rewrite" | "redirect" => (MiddlewareKind::L7Request, true, false),
 			// `rate_limit` is the canonical stateful middleware — per
 			// spec/crates/engine.md § _Middleware_,
 			// `stateless: false` so `lower::intern_middleware` skips
 			// dedup and every call site gets its own bucket.
-			"rate_limit" => (MiddlewareKind::L7Request, false, false),
+			"rate_limit" | "concurrency_limit" => (MiddlewareKind::L7Request, false, false),
+			"compress" | "response_headers" => (MiddlewareKind::L7Response, true, false),
 			_ => return None,
 		};
 		Some(MiddlewareMetadata { kind, stateless, needs_body, validate_args: validate_args_pass })
@@ -105,7 +106,10 @@ impl FetchMetadataProvider for MetadataProviders {
 	fn get(&self, kind: FetchKind) -> Option<FetchMetadata> {
 		let (phase, output_modes) = match kind {
 			FetchKind::L4Forward => (FetchPhase::L4, FetchOutputModes { response: false, tunnel: true }),
-			FetchKind::HttpProxy | FetchKind::HttpSynthesize | FetchKind::AcmeChallenge => {
+			FetchKind::HttpProxy
+			| FetchKind::HttpSynthesize
+			| FetchKind::FileServer
+			| FetchKind::AcmeChallenge => {
 				(FetchPhase::L7, FetchOutputModes { response: true, tunnel: false })
 			}
 			FetchKind::WebSocketUpgrade => {
@@ -0,0 +1,341 @@
+//! Zero-downtime binary upgrade: the running daemon starts a fresh
+//! `vaned` and hands it every listening socket over the mgmt Unix socket
+//! (`SCM_RIGHTS`), so the new process serves on the same sockets without
+//! rebinding. The old process drains only once the new one has passed its
+//! boot health watchdog; if the new one dies or never gets there, nothing
+//! was stopped and the old one keeps serving.
+//!
+//! Two halves, one per process:
+//! - [`UpgradeCtl`] (old daemon): spawns the child, answers its
+//!   `upgrade_take_listeners` / `upgrade_ready` calls, and fires the
+//!   shutdown trigger on success.
+//! - [`UpgradeParent`] (new daemon): adopts the handed-over sockets at
+//!   boot and reports readiness before binding its own mgmt plane.
+//!
+//! See `spec/crates/daemon.md` § _Binary upgrade_.
+
+use std::ffi::OsString;
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use tokio::process::Child;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+use vane_core::{Error, Transport};
+use vane_engine::{InheritedSockets, ListenerSet};
+use vane_mgmt::UnixMgmtClient;
+use vane_mgmt::verb::{
+	HandoffSocketInfo, UpgradeChildArgs, UpgradeReadyResult, UpgradeTakeListenersResult,
+	VERB_UPGRADE_READY, VERB_UPGRADE_TAKE_LISTENERS,
+};
+
+/// Set on the child to the old daemon's pid. Its presence is what puts
+/// a booting `vaned` into upgrade mode.
+pub(crate) const ENV_UPGRADE_PARENT_PID: &str = "VANE_UPGRADE_PARENT_PID";
+
+/// Budget on top of the child's boot health timeout for everything that
+/// precedes the watchdog (config load, compile, CRL fetch). Past it the
+/// child is killed and the upgrade rolled back.
+const READY_GRACE: Duration = Duration::from_mins(1);
+
+/// How long the child waits for the old daemon to close its mgmt plane
+/// before binding its own anyway.
+const PARENT_RELEASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP mgmt (and scrape listener) bind attempts after an upgrade. The
+/// old daemon closes its HTTP listeners a moment after its Unix socket,
+/// so the first bind can still find the port taken.
+pub(crate) const MGMT_HTTP_BIND_ATTEMPTS: u32 = 20;
+const MGMT_HTTP_BIND_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Old-daemon side of an upgrade. At most one runs at a time.
+pub(crate) struct UpgradeCtl {
+	/// Binary and arguments captured at boot. Resolving the executable
+	/// later would name the replaced (deleted) inode on Linux, not the
+	/// new binary installed at the same path.
+	exe: PathBuf,
+	args: Vec<OsString>,
+	listeners: Arc<ListenerSet>,
+	shutdown_trigger: CancellationToken,
+	ready_timeout: Duration,
+	in_progress: Mutex<Option<InProgress>>,
+}
+
+struct InProgress {
+	child_pid: u32,
+	/// Taken by the first `upgrade_ready` call.
+	ready: Option<oneshot::Sender<()>>,
+}
+
+impl UpgradeCtl {
+	/// # Errors
+	/// The path of the running executable cannot be resolved.
+	pub(crate) fn new(
+		listeners: Arc<ListenerSet>,
+		shutdown_trigger: CancellationToken,
+		boot_health_timeout_secs: u32,
+	) -> std::io::Result<Self> {
+		Ok(Self {
+			exe: std::env::current_exe()?,
+			args: std::env::args_os().skip(1).collect(),
+			listeners,
+			shutdown_trigger,
+			ready_timeout: Duration::from_secs(boot_health_timeout_secs.into()) + READY_GRACE,
+			in_progress: Mutex::new(None),
+		})
+	}
+
+	fn state(&self) -> MutexGuard<'_, Option<InProgress>> {
+		self.in_progress.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Start the new `vaned` and return its pid. The outcome is decided
+	/// in the background: on readiness this process starts its soft
+	/// drain, otherwise the upgrade is rolled back and logged.
+	///
+	/// # Errors
+	/// Another upgrade is in progress, or the spawn failed.
+	pub(crate) fn start(self: &Arc<Self>) -> Result<u32, String> {
+		let mut state = self.state();
+		if let Some(p) = state.as_ref() {
+			return Err(format!("an upgrade is already in progress (new vaned pid {})", p.child_pid));
+		}
+		let child = tokio::process::Command::new(&self.exe)
+			.args(&self.args)
+			.env(ENV_UPGRADE_PARENT_PID, std::process::id().to_string())
+			.stdin(Stdio::null())
+			.spawn()
+			.map_err(|e| format!("spawn {}: {e}", self.exe.display()))?;
+		let child_pid = child.id().ok_or_else(|| "new vaned exited immediately".to_owned())?;
+		let (ready_tx, ready_rx) = oneshot::channel();
+		*state = Some(InProgress { child_pid, ready: Some(ready_tx) });
+		drop(state);
+		tracing::info!(child_pid, exe = %self.exe.display(), "upgrade: started new vaned");
+		tokio::spawn(Arc::clone(self).supervise(child, child_pid, ready_rx));
+		Ok(child_pid)
+	}
+
+	/// [`Self::start`] for SIGUSR2, where there is no caller to report to.
+	pub(crate) fn start_logged(self: &Arc<Self>) {
+		if let Err(reason) = self.start() {
+			tracing::warn!(%reason, "upgrade not started");
+		}
+	}
+
+	async fn supervise(
+		self: Arc<Self>,
+		mut child: Child,
+		child_pid: u32,
+		ready: oneshot::Receiver<()>,
+	) {
+		let outcome = tokio::select! {
+			status = child.wait() => Err(match status {
+				Ok(status) => format!("new vaned exited ({status}) before it was ready"),
+				Err(e) => format!("waiting on new vaned failed: {e}"),
+			}),
+			r = ready => r.map_err(|_| "upgrade state dropped".to_owned()),
+			() = tokio::time::sleep(self.ready_timeout) => {
+				let _ = child.kill().await;
+				Err(format!("new vaned not ready within {:?}; killed it", self.ready_timeout))
+			}
+		};
+		*self.state() = None;
+		match outcome {
+			Ok(()) => {
+				tracing::info!(child_pid, "upgrade: new vaned is serving; draining this process");
+				self.shutdown_trigger.cancel();
+			}
+			Err(reason) => {
+				tracing::error!(child_pid, %reason, "upgrade rolled back; this process keeps serving");
+			}
+		}
+	}
+
+	fn check_child(&self, pid: u32) -> Result<(), String> {
+		match self.state().as_ref() {
+			Some(p) if p.child_pid == pid => Ok(()),
+			Some(p) => Err(format!("the upgrade in progress is for pid {}, not {pid}", p.child_pid)),
+			None => Err("no upgrade in progress".to_owned()),
+		}
+	}
+
+	/// `upgrade_take_listeners`: duplicates of every listening socket,
+	/// for the child `pid` this process started.
+	///
+	/// # Errors
+	/// `pid` is not the child of the upgrade in progress.
+	pub(crate) fn take_listeners(
+		&self,
+		pid: u32,
+	) -> Result<(UpgradeTakeListenersResult, Vec<OwnedFd>), String> {
+		self.check_child(pid)?;
+		let (sockets, fds): (Vec<HandoffSocketInfo>, Vec<OwnedFd>) = self
+			.listeners
+			.handoff_sockets()
+			.into_iter()
+			.map(|s| {
+				let (transport, addr) = match (&s.path, s.transport) {
+					(Some(path), _) => ("unix", path.display().to_string()),
+					(None, Transport::Tcp) => ("tcp", s.addr.to_string()),
+					(None, Transport::Udp) => ("udp", s.addr.to_string()),
+				};
+				(HandoffSocketInfo { transport: transport.to_owned(), addr }, s.fd)
+			})
+			.unzip();
+		tracing::info!(
+			child_pid = pid,
+			sockets = sockets.len(),
+			"upgrade: handing listening sockets over"
+		);
+		Ok((UpgradeTakeListenersResult { sockets }, fds))
+	}
+
+	/// `upgrade_ready`: the child passed its boot health watchdog.
+	///
+	/// # Errors
+	/// `pid` is not the child of the upgrade in progress.
+	pub(crate) fn child_ready(&self, pid: u32) -> Result<UpgradeReadyResult, String> {
+		let mut state = self.state();
+		match state.as_mut() {
+			Some(p) if p.child_pid == pid => {
+				if let Some(tx) = p.ready.take() {
+					let _ = tx.send(());
+				}
+				Ok(UpgradeReadyResult { draining: true })
+			}
+			Some(p) => Err(format!("the upgrade in progress is for pid {}, not {pid}", p.child_pid)),
+			None => Err("no upgrade in progress".to_owned()),
+		}
+	}
+}
+
+/// New-daemon side: present when `VANE_UPGRADE_PARENT_PID` is set.
+pub(crate) struct UpgradeParent {
+	pid: u32,
+	mgmt_unix: PathBuf,
+}
+
+impl UpgradeParent {
+	/// # Errors
+	/// `VANE_UPGRADE_PARENT_PID` is set but not a process id.
+	pub(crate) fn from_env(mgmt_unix: &Path) -> Result<Option<Self>, Error> {
+		let Ok(raw) = std::env::var(ENV_UPGRADE_PARENT_PID) else {
+			return Ok(None);
+		};
+		let pid = raw
+			.parse()
+			.map_err(|_| Error::io(format!("{ENV_UPGRADE_PARENT_PID}={raw:?} is not a process id")))?;
+		Ok(Some(Self { pid, mgmt_unix: mgmt_unix.to_path_buf() }))
+	}
+
+	fn client(&self) -> UnixMgmtClient {
+		UnixMgmtClient::new(&self.mgmt_unix)
+	}
+
+	fn child_args() -> UpgradeChildArgs {
+		UpgradeChildArgs { pid: std::process::id() }
+	}
+
+	/// Take the old daemon's listening sockets.
+	///
+	/// # Errors
+	/// The old daemon is unreachable or refused; boot stops and the old
+	/// daemon, seeing this process exit, keeps serving.
+	pub(crate) async fn take_listeners(&self) -> Result<InheritedSockets, Error> {
+		let (result, fds): (UpgradeTakeListenersResult, Vec<OwnedFd>) = self
+			.client()
+			.call_with_fds(VERB_UPGRADE_TAKE_LISTENERS, &Self::child_args())
+			.await
+			.map_err(|e| Error::io(format!("taking listeners from pid {}: {e}", self.pid)))?;
+		if fds.len() != result.sockets.len() {
+			return Err(Error::io(format!(
+				"old daemon listed {} sockets but passed {} descriptors",
+				result.sockets.len(),
+				fds.len()
+			)));
+		}
+		let mut sockets = InheritedSockets::default();
+		for (info, fd) in result.sockets.iter().zip(fds) {
+			match info.transport.as_str() {
+				"tcp" => {
+					sockets.add_tcp(std::net::TcpListener::from(fd))?;
+				}
+				"udp" => {
+					sockets.add_udp(std::net::UdpSocket::from(fd))?;
+				}
+				"unix" => {
+					sockets.add_unix(std::os::unix::net::UnixListener::from(fd))?;
+				}
+				other => {
+					tracing::warn!(transport = other, addr = %info.addr, "unknown handed-over socket; closing it");
+				}
+			}
+		}
+		tracing::info!(
+			parent_pid = self.pid,
+			sockets = sockets.iter().count() + sockets.unix_paths().count(),
+			"upgrade: adopted listening sockets from the old daemon",
+		);
+		Ok(sockets)
+	}
+
+	/// Wait for the boot health watchdog, tell the old daemon to drain,
+	/// then wait for it to close its mgmt plane so this process can bind
+	/// the same socket and port.
+	///
+	/// # Errors
+	/// The watchdog gave up (nothing bound); the old daemon keeps serving.
+	pub(crate) async fn hand_over(&self, boot_ready: oneshot::Receiver<()>) -> Result<(), Error> {
+		boot_ready
+			.await
+			.map_err(|_| Error::io("boot health watchdog failed; leaving the old daemon in charge"))?;
+		match self.client().call::<_, UpgradeReadyResult>(VERB_UPGRADE_READY, &Self::child_args()).await
+		{
+			Ok(_) => tracing::info!(parent_pid = self.pid, "upgrade: old daemon is draining"),
+			Err(e) => tracing::warn!(
+				parent_pid = self.pid,
+				error = %e,
+				"upgrade: old daemon did not acknowledge readiness; serving anyway",
+			),
+		}
+		let deadline = Instant::now() + PARENT_RELEASE_TIMEOUT;
+		while tokio::net::UnixStream::connect(&self.mgmt_unix).await.is_ok() {
+			if Instant::now() >= deadline {
+				tracing::warn!(
+					socket = %self.mgmt_unix.display(),
+					"old daemon still holds the mgmt socket; taking it over",
+				);
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+		Ok(())
+	}
+}
+
+/// [`crate::bind_mgmt_http_server`] retried for a few seconds, for the
+/// window in which the old daemon still holds the HTTP mgmt port.
+///
+/// # Errors
+/// The last attempt's error.
+pub(crate) async fn bind_mgmt_http_after_upgrade(
+	mgmt_state: Arc<crate::mgmt_handlers::MgmtState>,
+	cancel: CancellationToken,
+	env: &vane_core::Env,
+) -> Result<Vec<tokio::task::JoinHandle<()>>, Box<dyn std::error::Error + Send + Sync>> {
+	let mut attempt = 1;
+	loop {
+		match crate::bind_mgmt_http_server(Arc::clone(&mgmt_state), cancel.clone(), env).await {
+			Ok(handles) => return Ok(handles),
+			Err(e) if attempt < MGMT_HTTP_BIND_ATTEMPTS => {
+				tracing::debug!(attempt, error = %e, "mgmt http bind after upgrade failed; retrying");
+				attempt += 1;
+				tokio::time::sleep(MGMT_HTTP_BIND_BACKOFF).await;
+			}
+			Err(e) => return Err(e),
+		}
+	}
+}
@@ -1,7 +1,8 @@
 //! Daemon-side wrapper around [`notify_twophase`]. The lib owns the
 //! `notify-debouncer-full` plumbing and the reload-worthy event
 //! filter; this module wires the daemon's reload pipeline into a
-//! `Subscription::recv` loop.
+//! `Subscription::recv` loop. GeoIP database files get their own
+//! subscriptions that feed `GeoIpDb::reload` instead.
 //!
 //! ## Two-phase startup
 //!
@@ -21,14 +22,16 @@
 //!
 //! See `spec/crates/engine.md` § _Hot reload_.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use notify_twophase::Subscription;
+use notify_twophase::notify_debouncer_full::DebouncedEvent;
 use tokio_util::sync::CancellationToken;
 use vane_core::FlowLogSink;
 use vane_engine::ListenerSet;
 use vane_engine::VerbosityState;
+use vane_engine::geoip::GeoIpDb;
 
 use crate::reload::{ReloadCtx, ReloadOutcome, reload_once};
 
@@ -112,6 +115,72 @@ pub(crate) fn spawn_watcher_handler(
 	})
 }
 
+/// Arm one subscription per GeoIP database file. `notify` cannot
+/// watch a path across the rename that replaces it, so each one
+/// watches the file's parent directory and keeps only batches that
+/// touch the file's own name. Like [`arm_watcher_subscription`], called
+/// before listener bind; a file whose directory cannot be watched is
+/// logged and left without auto-reload.
+pub(crate) fn arm_geoip_subscriptions(db: &GeoIpDb) -> Vec<Subscription> {
+	db.paths()
+		.iter()
+		.filter_map(|path| {
+			let name = path.file_name()?.to_owned();
+			let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+			let filter = move |events: &[DebouncedEvent], _root: &Path| {
+				events.iter().any(|d| {
+					notify_twophase::is_reloadable_kind(d.event.kind)
+						&& d.event.paths.iter().any(|p| p.file_name() == Some(name.as_os_str()))
+				})
+			};
+			notify_twophase::arm_with(dir, notify_twophase::DEFAULT_DEBOUNCE, filter)
+				.inspect_err(|e| {
+					tracing::warn!(
+						path = %path.display(), error = %e,
+						"geoip watcher disabled — database auto-reload unavailable",
+					);
+				})
+				.ok()
+		})
+		.collect()
+}
+
+/// Drain GeoIP subscriptions into [`GeoIpDb::reload`]. A failed
+/// reload (half-written file, wrong format) keeps the previous
+/// mapping serving; the next change event retries.
+pub(crate) fn spawn_geoip_handler(
+	subs: Vec<Subscription>,
+	db: &Arc<GeoIpDb>,
+	cancel: &CancellationToken,
+) -> Vec<tokio::task::JoinHandle<()>> {
+	subs
+		.into_iter()
+		.map(|mut sub| {
+			let db = Arc::clone(db);
+			let cancel = cancel.clone();
+			tokio::spawn(async move {
+				loop {
+					tokio::select! {
+						biased;
+						() = cancel.cancelled() => return,
+						evt = sub.recv() => {
+							if evt.is_none() {
+								return;
+							}
+							match db.reload() {
+								Ok(()) => tracing::info!("geoip database reloaded"),
+								Err(e) => tracing::error!(
+									error = %e.tracing(), "geoip reload failed; previous database kept",
+								),
+							}
+						}
+					}
+				}
+			})
+		})
+		.collect()
+}
+
 fn hex32(bytes: &[u8; 32]) -> String {
 	use std::fmt::Write as _;
 	let mut s = String::with_capacity(64);
@@ -245,6 +314,44 @@ mod tests {
 		panic!("watcher did not propagate edit within 3s");
 	}
 
+	#[tokio::test]
+	async fn geoip_watcher_reloads_on_database_replace() {
+		use std::net::{IpAddr, Ipv4Addr};
+
+		use vane_core::GeoLookup as _;
+		use vane_testutil::mmdb::{self, GeoRecord};
+
+		let tmp = tempfile::tempdir().expect("tempdir");
+		let path = tmp.path().join("country.mmdb");
+		let replace = |code: &'static str| {
+			let record = GeoRecord { country: Some(code), ..GeoRecord::default() };
+			let staged = tmp.path().join("country.mmdb.tmp");
+			fs::write(&staged, mmdb::build(&[(Ipv4Addr::new(192, 0, 2, 0), 24, record)])).unwrap();
+			fs::rename(&staged, &path).unwrap();
+		};
+		replace("DE");
+		let db = Arc::new(GeoIpDb::open(vec![path.clone()]).expect("open"));
+		let ip = IpAddr::from([192, 0, 2, 1]);
+
+		let subs = arm_geoip_subscriptions(&db);
+		assert_eq!(subs.len(), 1);
+		let cancel = CancellationToken::new();
+		let _handles = spawn_geoip_handler(subs, &db, &cancel);
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+		replace("FR");
+
+		let deadline = Instant::now() + Duration::from_secs(3);
+		while Instant::now() < deadline {
+			if db.lookup(ip).country.as_deref() == Some("FR") {
+				cancel.cancel();
+				return;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+		panic!("geoip watcher did not reload the replaced database within 3s");
+	}
+
 	#[tokio::test]
 	async fn watcher_cancels_cleanly() {
 		let tmp = tempfile::tempdir().expect("tempdir");
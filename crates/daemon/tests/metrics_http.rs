@@ -0,0 +1,174 @@
+//! End-to-end tests for the `VANE_METRICS_HTTP_BIND` scrape listener.
+//!
+//! Each test spawns a real `vaned` subprocess with the listener on a
+//! loopback port and speaks plain HTTP/1.0 to it.
+
+use std::fs;
+use std::io::{Read as _, Write as _};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use assert_cmd::cargo::CommandCargoExt;
+
+// helpers
+struct Daemon {
+	child: std::process::Child,
+	_tmp: tempfile::TempDir,
+}
+
+impl Drop for Daemon {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+fn ephemeral_port() -> u16 {
+	let l = TcpListener::bind("127.0.0.1:0").expect("bind ephemeral");
+	let port = l.local_addr().expect("local addr").port();
+	drop(l);
+	port
+}
+
+fn loopback(port: u16) -> SocketAddr {
+	SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn write_static_rule(dir: &Path, port: u16) {
+	let rules = dir.join("rules");
+	fs::create_dir_all(&rules).expect("rules/");
+	fs::write(
+		rules.join("site.json"),
+		format!(
+			r#"{{"rules":[{{"preset":"static_site","name":"site","listen":["127.0.0.1:{port}"],"args":{{"status":200,"body":"ok"}}}}]}}"#
+		),
+	)
+	.expect("write rule");
+}
+
+fn spawn_daemon(rule_dir: &Path, metrics: SocketAddr, allow: &str) -> Daemon {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	let socket: PathBuf = tmp.path().join("vaned.sock");
+	let mut cmd = std::process::Command::cargo_bin("vaned").expect("locate vaned binary");
+	cmd
+		.arg("-c")
+		.arg(rule_dir)
+		.env("VANE_MGMT_UNIX", &socket)
+		.env("VANE_MGMT_HTTP_PORT", "")
+		.env("VANE_METRICS_HTTP_BIND", metrics.to_string())
+		.env("VANE_METRICS_HTTP_ALLOW", allow)
+		.env("RUST_LOG", "warn")
+		.stdout(Stdio::null())
+		.stderr(Stdio::null());
+	let child = cmd.spawn().expect("spawn vaned");
+	Daemon { child, _tmp: tmp }
+}
+
+fn wait_for_listener(addr: SocketAddr, timeout: Duration) {
+	let deadline = Instant::now() + timeout;
+	while Instant::now() < deadline {
+		if TcpStream::connect_timeout(&addr, Duration::from_millis(100)).is_ok() {
+			return;
+		}
+		std::thread::sleep(Duration::from_millis(50));
+	}
+	panic!("listener {addr} did not bind within {timeout:?}");
+}
+
+/// One HTTP/1.0 exchange; returns the raw response (empty when the
+/// server closed without answering).
+fn request(addr: SocketAddr, method: &str, path: &str) -> String {
+	let mut conn = TcpStream::connect_timeout(&addr, Duration::from_secs(2)).expect("connect");
+	conn.set_read_timeout(Some(Duration::from_secs(5))).expect("read timeout");
+	let _ = conn.write_all(format!("{method} {path} HTTP/1.0\r\nHost: localhost\r\n\r\n").as_bytes());
+	let mut buf = Vec::new();
+	let _ = conn.read_to_end(&mut buf);
+	String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Status code from the response's status line.
+fn status(resp: &str) -> Option<u16> {
+	resp.split(' ').nth(1)?.parse().ok()
+}
+
+/// Poll `/readyz` until it answers 200.
+fn wait_ready(addr: SocketAddr, timeout: Duration) -> String {
+	let deadline = Instant::now() + timeout;
+	loop {
+		let resp = request(addr, "GET", "/readyz");
+		if status(&resp) == Some(200) || Instant::now() >= deadline {
+			return resp;
+		}
+		std::thread::sleep(Duration::from_millis(100));
+	}
+}
+
+// tests
+#[test]
+fn scrape_listener_serves_metrics_and_probes() {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	write_static_rule(tmp.path(), ephemeral_port());
+	let metrics = loopback(ephemeral_port());
+	let _d = spawn_daemon(tmp.path(), metrics, "");
+	wait_for_listener(metrics, Duration::from_secs(5));
+
+	let ready = wait_ready(metrics, Duration::from_secs(10));
+	assert_eq!(status(&ready), Some(200), "readyz once the listener bound: {ready}");
+	assert!(ready.contains("listeners bound 1/1"), "{ready}");
+
+	let health = request(metrics, "GET", "/healthz");
+	assert_eq!(status(&health), Some(200), "{health}");
+
+	let scrape = request(metrics, "GET", "/metrics");
+	assert_eq!(status(&scrape), Some(200), "{scrape}");
+	assert!(
+		scrape.to_ascii_lowercase().contains("content-type: text/plain; version=0.0.4"),
+		"{scrape}"
+	);
+}
+
+#[test]
+fn scrape_listener_exposes_no_mgmt_surface() {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	write_static_rule(tmp.path(), ephemeral_port());
+	let metrics = loopback(ephemeral_port());
+	let _d = spawn_daemon(tmp.path(), metrics, "");
+	wait_for_listener(metrics, Duration::from_secs(5));
+
+	let post = request(metrics, "POST", "/");
+	assert_eq!(status(&post), Some(405), "mgmt POST is refused: {post}");
+	assert!(post.to_ascii_lowercase().contains("allow: get, head"), "{post}");
+	let other = request(metrics, "GET", "/stats");
+	assert_eq!(status(&other), Some(404), "{other}");
+}
+
+#[test]
+fn scrape_listener_drops_sources_outside_the_allow_list() {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	write_static_rule(tmp.path(), ephemeral_port());
+	let metrics = loopback(ephemeral_port());
+	let _d = spawn_daemon(tmp.path(), metrics, "192.0.2.0/24");
+	wait_for_listener(metrics, Duration::from_secs(5));
+
+	let resp = request(metrics, "GET", "/metrics");
+	assert!(resp.is_empty(), "loopback is outside the allow-list: {resp}");
+}
+
+#[test]
+fn taken_scrape_port_leaves_the_daemon_serving() {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	let site = loopback(ephemeral_port());
+	write_static_rule(tmp.path(), site.port());
+	let squatter = TcpListener::bind("127.0.0.1:0").expect("hold a scrape port");
+	let metrics = squatter.local_addr().expect("local addr");
+	let mut d = spawn_daemon(tmp.path(), metrics, "");
+
+	wait_for_listener(site, Duration::from_secs(10));
+	// Give a failing scrape bind time to take the process down.
+	std::thread::sleep(Duration::from_millis(500));
+	assert!(d.child.try_wait().expect("poll vaned").is_none(), "vaned exited on the scrape bind");
+	let resp = request(site, "GET", "/");
+	assert_eq!(status(&resp), Some(200), "{resp}");
+}
@@ -0,0 +1,233 @@
+//! Binary upgrade end-to-end. Each test boots a real `vaned`, triggers
+//! an upgrade (mgmt verb or SIGUSR2) that re-executes the same binary,
+//! and checks the hand-over from the outside:
+//!
+//! - the old process exits cleanly once the new one is serving;
+//! - the listener keeps answering across the swap;
+//! - the mgmt socket ends up owned by the new process;
+//! - a new process that fails to boot leaves the old one in charge.
+//!
+//! The new process is not our child, so it is stopped through the mgmt
+//! `shutdown` verb at the end of each test.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{Signal, kill};
+use nix::unistd::Pid;
+use vane_mgmt::UnixMgmtClient;
+use vane_mgmt::client::ClientError;
+use vane_mgmt::protocol::WireErrorKind;
+use vane_mgmt::verb::{
+	NoArgs, PingResult, ShutdownResult, UpgradeChildArgs, UpgradeResult, UpgradeTakeListenersResult,
+	VERB_PING, VERB_SHUTDOWN, VERB_UPGRADE, VERB_UPGRADE_TAKE_LISTENERS,
+};
+
+/// Upper bound on one hand-over: child boot, watchdog poll, the old
+/// process's one-second drain.
+const HANDOVER_BUDGET: Duration = Duration::from_secs(30);
+
+struct Daemon {
+	child: Child,
+	socket: PathBuf,
+	port: u16,
+	config_dir: PathBuf,
+	_tmp: tempfile::TempDir,
+}
+
+impl Drop for Daemon {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+fn ephemeral_port() -> u16 {
+	let l = TcpListener::bind("127.0.0.1:0").expect("bind ephemeral");
+	let port = l.local_addr().expect("local addr").port();
+	drop(l);
+	port
+}
+
+fn static_site_rule(port: u16, body: &str) -> String {
+	format!(
+		r#"{{
+			"rules": [{{
+				"preset": "static_site",
+				"name": "site_{port}",
+				"listen": ["127.0.0.1:{port}"],
+				"args": {{ "status": 200, "body": "{body}" }}
+			}}]
+		}}"#
+	)
+}
+
+fn write_rule(dir: &Path, body: &str) {
+	let rules = dir.join("rules");
+	fs::create_dir_all(&rules).expect("create rules/");
+	fs::write(rules.join("site.json"), body).expect("write rule");
+}
+
+fn spawn_vaned() -> Daemon {
+	let tmp = tempfile::tempdir().expect("tempdir");
+	let config_dir = tmp.path().to_path_buf();
+	let port = ephemeral_port();
+	write_rule(&config_dir, &static_site_rule(port, "served"));
+	let socket = tmp.path().join("vaned.sock");
+
+	let cmd = assert_cmd::Command::cargo_bin("vaned").expect("locate vaned bin");
+	let child = Command::new(cmd.get_program())
+		.arg("-c")
+		.arg(&config_dir)
+		.env("VANE_MGMT_UNIX", &socket)
+		.env("VANE_MGMT_HTTP_PORT", "")
+		.env("VANE_DRAIN_TIMEOUT_SECS", "1")
+		.env("VANE_BOOT_HEALTH_TIMEOUT_SECS", "5")
+		// The new process inherits these; keep its output out of the
+		// test harness too.
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.spawn()
+		.expect("spawn vaned");
+	let daemon = Daemon { child, socket, port, config_dir, _tmp: tmp };
+	wait_until(Duration::from_secs(10), "vaned serves", || served(daemon.port));
+	daemon
+}
+
+fn wait_until(timeout: Duration, what: &str, mut f: impl FnMut() -> bool) {
+	let deadline = Instant::now() + timeout;
+	while Instant::now() < deadline {
+		if f() {
+			return;
+		}
+		std::thread::sleep(Duration::from_millis(50));
+	}
+	panic!("{what}: not observed within {timeout:?}");
+}
+
+fn http_get(port: u16) -> std::io::Result<String> {
+	let mut stream = TcpStream::connect_timeout(
+		&format!("127.0.0.1:{port}").parse().expect("addr"),
+		Duration::from_secs(1),
+	)?;
+	stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+	stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+	let mut buf = String::new();
+	stream.read_to_string(&mut buf)?;
+	Ok(buf)
+}
+
+fn served(port: u16) -> bool {
+	http_get(port).is_ok_and(|r| r.starts_with("HTTP/1.1 200") && r.ends_with("served"))
+}
+
+fn exited(child: &mut Child) -> Option<std::process::ExitStatus> {
+	child.try_wait().expect("try_wait")
+}
+
+/// Wait for the old process to exit while checking, every poll, that
+/// the listener never stops answering. Returns its exit status.
+fn wait_for_handover(daemon: &mut Daemon) -> std::process::ExitStatus {
+	let deadline = Instant::now() + HANDOVER_BUDGET;
+	loop {
+		assert!(served(daemon.port), "listener stopped answering during the upgrade");
+		if let Some(status) = exited(&mut daemon.child) {
+			return status;
+		}
+		assert!(Instant::now() < deadline, "old vaned did not exit within {HANDOVER_BUDGET:?}");
+		std::thread::sleep(Duration::from_millis(100));
+	}
+}
+
+/// Ping the new process over the shared mgmt socket path, then shut it
+/// down and wait for the listener to go away.
+async fn ping_then_stop_new_daemon(daemon: &Daemon) {
+	let client = UnixMgmtClient::new(&daemon.socket);
+	let deadline = Instant::now() + Duration::from_secs(15);
+	loop {
+		if let Ok(r) = client.call::<_, PingResult>(VERB_PING, &NoArgs {}).await {
+			assert!(r.pong);
+			break;
+		}
+		assert!(Instant::now() < deadline, "new vaned never bound the mgmt socket");
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+	assert!(served(daemon.port), "new vaned does not serve the listener");
+	let r: ShutdownResult = client.call(VERB_SHUTDOWN, &NoArgs {}).await.expect("shutdown new vaned");
+	assert!(r.draining);
+	wait_until(Duration::from_secs(10), "new vaned exits", || {
+		TcpStream::connect(("127.0.0.1", daemon.port)).is_err()
+	});
+}
+
+#[tokio::test]
+async fn upgrade_verb_hands_listeners_to_new_process() {
+	let mut daemon = spawn_vaned();
+	let client = UnixMgmtClient::new(&daemon.socket);
+	let r: UpgradeResult = client.call(VERB_UPGRADE, &NoArgs {}).await.expect("upgrade");
+	assert_ne!(r.child_pid, daemon.child.id());
+
+	let status = wait_for_handover(&mut daemon);
+	assert!(status.success(), "old vaned exit status: {status}");
+	ping_then_stop_new_daemon(&daemon).await;
+}
+
+#[tokio::test]
+async fn sigusr2_starts_an_upgrade() {
+	let mut daemon = spawn_vaned();
+	let pid = Pid::from_raw(daemon.child.id().try_into().expect("pid fits i32"));
+	kill(pid, Signal::SIGUSR2).expect("send SIGUSR2");
+
+	let status = wait_for_handover(&mut daemon);
+	assert!(status.success(), "old vaned exit status: {status}");
+	ping_then_stop_new_daemon(&daemon).await;
+}
+
+#[tokio::test]
+async fn failed_new_process_rolls_back_to_the_old_one() {
+	let mut daemon = spawn_vaned();
+	let client = UnixMgmtClient::new(&daemon.socket);
+	assert!(take_listeners_refusal(&client).await.contains("no upgrade in progress"));
+
+	// The running graph stays as loaded; only a fresh boot reads this.
+	write_rule(&daemon.config_dir, "{ not json");
+	let r: UpgradeResult = client.call(VERB_UPGRADE, &NoArgs {}).await.expect("upgrade");
+	assert!(take_listeners_refusal(&client).await.contains(&format!("for pid {}", r.child_pid)));
+
+	// The new process fails config load and exits; the old one keeps
+	// serving and clears the upgrade.
+	let deadline = Instant::now() + HANDOVER_BUDGET;
+	while !take_listeners_refusal(&client).await.contains("no upgrade in progress") {
+		assert!(exited(&mut daemon.child).is_none(), "old vaned exited after a failed upgrade");
+		assert!(served(daemon.port), "old vaned stopped serving after a failed upgrade");
+		assert!(Instant::now() < deadline, "failed upgrade never rolled back");
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+
+	// A later upgrade with a bootable config goes through.
+	write_rule(&daemon.config_dir, &static_site_rule(daemon.port, "served"));
+	let _: UpgradeResult = client.call(VERB_UPGRADE, &NoArgs {}).await.expect("second upgrade");
+	let status = wait_for_handover(&mut daemon);
+	assert!(status.success(), "old vaned exit status: {status}");
+	ping_then_stop_new_daemon(&daemon).await;
+}
+
+/// `upgrade_take_listeners` from a pid that is not the upgrade child
+/// must be refused; the message says which upgrade, if any, is running.
+async fn take_listeners_refusal(client: &UnixMgmtClient) -> String {
+	let err = client
+		.call_with_fds::<_, UpgradeTakeListenersResult>(
+			VERB_UPGRADE_TAKE_LISTENERS,
+			&UpgradeChildArgs { pid: 1 },
+		)
+		.await
+		.expect_err("pid 1 is never the upgrade child");
+	match err {
+		ClientError::Server(e) if e.kind == WireErrorKind::BadArgs => e.message,
+		other => panic!("unexpected error: {other}"),
+	}
+}
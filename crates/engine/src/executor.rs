@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use tracing::Instrument as _;
 use vane_core::{
 	Body, BodySide, BytesView, CloseReason, ConnContext, ContextEntry, Decision, Error, FlowCtx,
 	FlowLogEvent, FlowLogKind, FlowLogVerbosity, Header, L4BytesDecision, L4BytesInput, L4Conn,
@@ -10,6 +11,7 @@ use vane_core::{
 };
 
 use crate::flow_graph::{FetchInst, FlowGraph, MiddlewareInst};
+use crate::request_metrics::RequestMeter;
 use crate::time::now_unix_ms;
 
 // Both variants are boxed: `L4Conn` embeds a `TcpStream` / `UdpAssoc` and
@@ -88,11 +90,24 @@ impl std::fmt::Debug for ExecutorOutput {
 /// the phase DFS guarantees each consumer reaches its variant's slot
 /// only in the phase that fills it). An engine driving an un-validated
 /// or hand-forged graph may hit these; don't.
+pub async fn execute(
+	graph: &Arc<FlowGraph>,
+	entry: NodeId,
+	input: ExecutorInput,
+	conn: &Arc<ConnContext>,
+	ctx: &mut FlowCtx,
+) -> Result<ExecutorOutput, Error> {
+	// The walk span is the parent of every middleware / fetch span
+	// below (spec/crates/engine.md § _Distributed tracing_).
+	let span = tracing::debug_span!(parent: &ctx.span, "walk", entry = entry.get());
+	walk(graph, entry, input, conn, ctx).instrument(span).await
+}
+
 #[allow(
 	clippy::too_many_lines,
 	reason = "the four `Node` variants and their decision-interpretation share the same mutable l4/req/resp/tunnel/cur/seq locals; the biggest natural cuts (body-collect prelude, middleware variant dispatch, Upgrade arm, Terminate arm) are already extracted as helpers, and pushing further would only rename the noise"
 )]
-pub async fn execute(
+async fn walk(
 	graph: &Arc<FlowGraph>,
 	entry: NodeId,
 	input: ExecutorInput,
@@ -153,6 +168,11 @@ pub async fn execute(
 
 			Node::Middleware { id, next, on_error, .. } => {
 				record_step(ctx, conn, &mut seq, cur, FlowLogKind::Middleware, None);
+				let span = tracing::debug_span!(
+					"middleware",
+					name = %sym.middlewares[id.get() as usize].name,
+					wasm = matches!(graph[*id], MiddlewareInst::Wasm(_)),
+				);
 				let outcome = dispatch_middleware_inst(
 					graph,
 					*id,
@@ -163,6 +183,7 @@ pub async fn execute(
 					ctx,
 					peek_bytes.as_deref(),
 				)
+				.instrument(span)
 				.await;
 
 				match outcome {
@@ -279,7 +300,7 @@ pub async fn execute(
 				}
 			}
 
-			Node::Fetch { id, next_response, next_tunnel, .. } => {
+			Node::Fetch { id, next_response, next_tunnel, on_error, on_error_status, .. } => {
 				record_step(ctx, conn, &mut seq, cur, FlowLogKind::Fetch, None);
 				match &graph[*id] {
 					FetchInst::L7(f) => {
@@ -327,9 +348,65 @@ pub async fn execute(
 							continue;
 						}
 
-						let r = req.take().expect("phase invariant: L7Fetch needs Request");
-						match f.fetch(r, conn, ctx).await {
+						let mut r = req.take().expect("phase invariant: L7Fetch needs Request");
+						let head = graph.has_response_middleware().then(|| vane_core::RequestHead::of(&r));
+						// Request-phase guards (`concurrency_limit` permits)
+						// ride the response body so they release when the
+						// upstream has finished streaming, not at the headers.
+						let guards = r.extensions_mut().remove::<vane_core::StreamGuards>();
+						// A fallback replays the request into `on_error`;
+						// the validator proved the body is buffered here.
+						let replay = on_error.and_then(|_| replay_copy(&r));
+						// Per-rule request metrics: the driver's meter rides
+						// the request; name the rule and time the fetch.
+						let meter = r.extensions().get::<RequestMeter>().cloned();
+						if let Some(meter) = &meter {
+							meter.enter_rule(graph.symbolic().fetches[id.get() as usize].rule.as_deref());
+						}
+						let started = std::time::Instant::now();
+						let result = f.fetch(r, conn, ctx).instrument(fetch_span(graph, *id)).await;
+						if let Some(meter) = &meter {
+							meter.upstream(started.elapsed());
+						}
+						let divert = match &result {
+							Err(_) => true,
 							Ok(vane_core::L7FetchOutput::Response(rp)) => {
+								is_fetch_failure(rp) || on_error_status.contains(&rp.status().as_u16())
+							}
+							Ok(vane_core::L7FetchOutput::Tunnel(_)) => false,
+						};
+						if divert && let (Some(target), Some(mut replay)) = (*on_error, replay) {
+							let cause = match &result {
+								Err(e) => {
+									emit_error_event(ctx, cur, &mut seq, conn, e);
+									"error"
+								}
+								Ok(out) => {
+									emit_fallback_status_event(ctx, cur, &mut seq, conn, out, target);
+									match out {
+										vane_core::L7FetchOutput::Response(rp) if is_fetch_failure(rp) => "error",
+										_ => "status",
+									}
+								}
+							};
+							metrics::counter!("vane.fetch.fallback_total", "cause" => cause).increment(1);
+							drop(result);
+							if let Some(guards) = guards {
+								replay.extensions_mut().insert(guards);
+							}
+							req = Some(replay);
+							cur = target;
+							continue;
+						}
+						match result {
+							Ok(vane_core::L7FetchOutput::Response(mut rp)) => {
+								if let Some(head) = head {
+									rp.extensions_mut().insert(head);
+								}
+								if let Some(guards) = guards {
+									let body = std::mem::replace(rp.body_mut(), Body::Empty);
+									*rp.body_mut() = guards.attach(body);
+								}
 								resp = Some(rp);
 								cur = next_response.expect("validator guarantees Some on L7 paths for Response");
 							}
@@ -342,7 +419,7 @@ pub async fn execute(
 					}
 					FetchInst::L4(f) => {
 						let c = l4.take().expect("phase invariant: L4Fetch needs L4Conn");
-						match f.fetch(c, conn, ctx).await {
+						match f.fetch(c, conn, ctx).instrument(fetch_span(graph, *id)).await {
 							Ok(t) => {
 								tunnel = Some(t);
 								cur = next_tunnel.expect("validator guarantees Some on L4 paths");
@@ -797,6 +874,13 @@ fn finish_error(
 	err
 }
 
+/// Span around one `Node::Fetch` dispatch; `http_proxy` nests an
+/// `upstream_attempt` span per retry / hedge attempt under it.
+fn fetch_span(graph: &FlowGraph, id: vane_core::FetchId) -> tracing::Span {
+	let fetch = &graph.symbolic().fetches[id.get() as usize];
+	tracing::debug_span!("fetch", kind = ?fetch.kind, rule = fetch.rule.as_deref().unwrap_or(""))
+}
+
 fn emit_trajectory(
 	ctx: &mut FlowCtx,
 	conn: &Arc<ConnContext>,
@@ -807,11 +891,16 @@ fn emit_trajectory(
 	// (which consumes by value). Replace with a fresh empty builder so the
 	// `FlowCtx` stays in a valid state — same conn, same entry, no steps.
 	let conn_id = conn.id;
-	let traj = std::mem::replace(
+	let mut traj = std::mem::replace(
 		&mut ctx.trajectory,
 		vane_core::TrajectoryBuilder::placeholder(conn_id, now_unix_ms()),
 	)
 	.finalize(outcome, now_unix_ms());
+	if let Some(tls) = conn.tls.lock().as_ref() {
+		traj.tls_ja3.clone_from(&tls.ja3);
+		traj.tls_ja4.clone_from(&tls.ja4);
+	}
+	traj.trace_id = crate::trace_propagation::trace_id(&ctx.span);
 
 	let data = serde_json::to_value(&traj).ok();
 	ctx.log.emit(FlowLogEvent {
@@ -843,6 +932,58 @@ fn emit_error_event(
 	});
 }
 
+/// Milestone for a fetch whose response status diverted the request
+/// to its `on_error` fallback. Fetch errors take the same edge but are
+/// logged through [`emit_error_event`].
+fn emit_fallback_status_event(
+	ctx: &mut FlowCtx,
+	cur: NodeId,
+	seq: &mut u32,
+	conn: &Arc<ConnContext>,
+	out: &vane_core::L7FetchOutput,
+	target: NodeId,
+) {
+	let vane_core::L7FetchOutput::Response(rp) = out else { return };
+	ctx.log.emit(FlowLogEvent {
+		t: now_unix_ms(),
+		conn: conn.id,
+		seq: bump(seq),
+		kind: FlowLogKind::Fetch,
+		node: Some(cur),
+		error: None,
+		data: Some(serde_json::json!({
+			"fallback": target.get(),
+			"status": rp.status().as_u16(),
+		})),
+	});
+}
+
+/// Whether the fetch answered `rp` in place of an error.
+fn is_fetch_failure(rp: &vane_core::Response) -> bool {
+	rp.extensions().get::<vane_core::FetchFailure>().is_some()
+}
+
+/// Copy of a request for a fetch fallback to replay. `None` while the
+/// body is still streaming, which a validated graph never reaches: the
+/// lower pass buffers the body before any fetch that has a fallback.
+fn replay_copy(r: &Request) -> Option<Request> {
+	let body = match r.body() {
+		Body::Static(b) => Body::Static(b.clone()),
+		Body::Empty => Body::Empty,
+		Body::Stream(_) => {
+			tracing::warn!("fetch fallback skipped: request body is not buffered");
+			return None;
+		}
+	};
+	let mut copy = http::Request::new(body);
+	*copy.method_mut() = r.method().clone();
+	*copy.uri_mut() = r.uri().clone();
+	*copy.version_mut() = r.version();
+	*copy.headers_mut() = r.headers().clone();
+	*copy.extensions_mut() = r.extensions().clone();
+	Some(copy)
+}
+
 fn bump(seq: &mut u32) -> u32 {
 	let n = *seq;
 	*seq = seq.saturating_add(1);
@@ -939,7 +1080,7 @@ pub async fn dispatch_wasm(
 	match export.map(|e| e.kind) {
 		Some(MiddlewareKind::L4Peek) => dispatch_wasm_l4_peek(w, ctx, conn).await,
 		Some(MiddlewareKind::L4Bytes) => dispatch_wasm_l4_bytes(w, ctx, l4, conn).await,
-		Some(MiddlewareKind::L7Request) => dispatch_wasm_l7_request(w, ctx, req).await,
+		Some(MiddlewareKind::L7Request) => dispatch_wasm_l7_request(w, ctx, req, conn).await,
 		Some(MiddlewareKind::L7Response) => dispatch_wasm_l7_response(w, ctx, resp).await,
 		None => Err(Error::middleware(format!(
 			"export '{}' not found in plugin metadata for module '{}'",
@@ -1026,11 +1167,12 @@ async fn dispatch_wasm_l4_bytes(
 /// L7Request dispatch: pack method / URI / headers + optional body
 /// (`needs_body`-gated) into the WASM ABI; translate the plugin's
 /// decision back to executor terms (Continue / Short(synth response) /
-/// Close).
+/// Close). `Ban` puts the source in the daemon's ban table, then closes.
 async fn dispatch_wasm_l7_request(
 	w: &crate::flow_graph::WasmMiddleware,
 	ctx: Vec<ContextEntry>,
 	req: &mut Option<Request>,
+	conn: &Arc<ConnContext>,
 ) -> Result<Decision, Error> {
 	let req_ref = req.as_mut().expect("phase invariant: L7Request wasm needs Request");
 	let method = req_ref.method().to_string();
@@ -1051,6 +1193,18 @@ async fn dispatch_wasm_l7_request(
 		Ok(L7RequestDecision::Close) => Ok(Decision::Short(ShortCircuit::Close(
 			CloseReason::PolicyDenied(std::borrow::Cow::Borrowed("plugin l7-request close")),
 		))),
+		Ok(L7RequestDecision::Ban(ban)) => {
+			// Outside a listener (no floor) the ban degrades to a close.
+			if let Some(floor) = crate::security::ConnFloor::of(conn) {
+				let duration = ban.duration_secs.map(|s| std::time::Duration::from_secs(s.into()));
+				if let Err(e) = floor.ban(duration, ban.reason) {
+					tracing::warn!(module = %w.module_id.0, error = %e, "plugin ban refused");
+				}
+			}
+			Ok(Decision::Short(ShortCircuit::Close(CloseReason::PolicyDenied(
+				std::borrow::Cow::Borrowed("plugin l7-request ban"),
+			))))
+		}
 		Err(pe) => plugin_error_to_decision(pe),
 	}
 }
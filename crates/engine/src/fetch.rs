@@ -5,10 +5,15 @@
 
 #[cfg(feature = "acme")]
 pub mod acme_challenge;
+pub mod balance;
+pub mod breaker;
+pub mod cache;
 #[cfg(feature = "cgi")]
 pub mod cgi;
 pub mod client_cache;
 pub mod dns;
+pub mod file_server;
+pub mod health;
 pub mod hop_by_hop;
 pub mod http_proxy;
 pub mod http_synthesize;
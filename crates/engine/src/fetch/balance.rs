@@ -0,0 +1,698 @@
+//! Daemon-level upstream sets for multi-member `http_proxy` rules.
+//!
+//! A rule with `args.upstreams: [...]` resolves to one [`UpstreamSet`]
+//! keyed by `(policy, members)`. Sets are looked up through a weak
+//! registry so a reload that reproduces the same member list reuses the
+//! same round-robin cursor and selection counters, while sets no live
+//! graph references drop out of `get_upstreams`. In-flight counts are
+//! tracked per authority, not per set — two rules that share a backend
+//! see the same load when `least_in_flight` / `p2c` compare members.
+//!
+//! See `spec/crates/engine.md` § _Load balancing_.
+
+use std::hash::Hasher as _;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Weak};
+
+use dashmap::DashMap;
+use http::HeaderMap;
+use http::uri::Authority;
+use rand::RngExt as _;
+
+/// Upper bound on `args.upstreams[].weight`. Keeps the consistent-hash
+/// ring (one run of virtual nodes per weight unit) a few thousand
+/// entries per member at most.
+pub const MAX_WEIGHT: u32 = 100;
+
+/// Virtual nodes placed on the consistent-hash ring per unit of weight.
+const RING_VNODES_PER_WEIGHT: u32 = 64;
+
+/// One `args.upstream` / `args.upstreams[]` entry after parsing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberSpec {
+	pub upstream: String,
+	pub weight: u32,
+}
+
+/// Member-selection policy, from `args.lb`. Defaults to `RoundRobin`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LbPolicy {
+	/// Weighted round-robin over a shared cursor.
+	RoundRobin,
+	/// Weighted random pick per request.
+	WeightedRandom,
+	/// Lowest `in_flight / weight`; ties rotate with the cursor.
+	LeastInFlight,
+	/// Two weighted-random candidates, keep the less loaded one.
+	PowerOfTwoChoices,
+	/// Ketama-style ring keyed on a request attribute. Requests that
+	/// lack the attribute fall back to round-robin.
+	ConsistentHash(HashOn),
+}
+
+/// Request attribute a `consistent_hash` policy keys on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HashOn {
+	Header(http::HeaderName),
+	Cookie(String),
+	RemoteIp,
+}
+
+impl std::fmt::Display for LbPolicy {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::RoundRobin => f.write_str("round_robin"),
+			Self::WeightedRandom => f.write_str("weighted_random"),
+			Self::LeastInFlight => f.write_str("least_in_flight"),
+			Self::PowerOfTwoChoices => f.write_str("p2c"),
+			Self::ConsistentHash(HashOn::Header(name)) => write!(f, "consistent_hash(header:{name})"),
+			Self::ConsistentHash(HashOn::Cookie(name)) => write!(f, "consistent_hash(cookie:{name})"),
+			Self::ConsistentHash(HashOn::RemoteIp) => f.write_str("consistent_hash(remote_ip)"),
+		}
+	}
+}
+
+/// Parse the member list from `args.upstream` (single string) or
+/// `args.upstreams` (array of `"host:port"` strings or
+/// `{ "upstream": "host:port", "weight": n }` objects). Exactly one of
+/// the two keys must be present.
+///
+/// # Errors
+/// Returns a human-readable message naming the offending arg.
+pub fn parse_members(args: &serde_json::Value) -> Result<Vec<MemberSpec>, String> {
+	match (args.get("upstream"), args.get("upstreams")) {
+		(Some(_), Some(_)) => {
+			Err("args.upstream and args.upstreams are mutually exclusive".to_string())
+		}
+		(None, None) => Err(
+			"missing args.upstream (string \"host:port\") or args.upstreams (non-empty array)"
+				.to_string(),
+		),
+		(Some(single), None) => {
+			let upstream = single
+				.as_str()
+				.ok_or_else(|| "missing args.upstream (string \"host:port\")".to_string())?;
+			if upstream.is_empty() {
+				return Err("args.upstream must not be empty".to_string());
+			}
+			Ok(vec![MemberSpec { upstream: upstream.to_owned(), weight: 1 }])
+		}
+		(None, Some(list)) => {
+			let items = list
+				.as_array()
+				.filter(|a| !a.is_empty())
+				.ok_or_else(|| "args.upstreams must be a non-empty array".to_string())?;
+			let mut out: Vec<MemberSpec> = Vec::with_capacity(items.len());
+			for (i, item) in items.iter().enumerate() {
+				let spec = parse_member(item).map_err(|e| format!("args.upstreams[{i}]: {e}"))?;
+				if out.iter().any(|m| m.upstream == spec.upstream) {
+					return Err(format!("args.upstreams[{i}]: duplicate upstream {:?}", spec.upstream));
+				}
+				out.push(spec);
+			}
+			Ok(out)
+		}
+	}
+}
+
+fn parse_member(item: &serde_json::Value) -> Result<MemberSpec, String> {
+	let (upstream, weight) = match item {
+		serde_json::Value::String(s) => (s.as_str(), 1),
+		serde_json::Value::Object(obj) => {
+			let upstream = obj
+				.get("upstream")
+				.and_then(serde_json::Value::as_str)
+				.ok_or_else(|| "missing upstream (string \"host:port\")".to_string())?;
+			let weight = match obj.get("weight") {
+				None => 1,
+				Some(w) => w
+					.as_u64()
+					.and_then(|w| u32::try_from(w).ok())
+					.filter(|w| (1..=MAX_WEIGHT).contains(w))
+					.ok_or_else(|| format!("weight must be an integer in 1..={MAX_WEIGHT}"))?,
+			};
+			(upstream, weight)
+		}
+		_ => return Err("must be a \"host:port\" string or { upstream, weight } object".to_string()),
+	};
+	if upstream.is_empty() {
+		return Err("upstream must not be empty".to_string());
+	}
+	Ok(MemberSpec { upstream: upstream.to_owned(), weight })
+}
+
+/// Parse `args.lb`. Accepts a bare policy name or
+/// `{ "policy": "...", "hash_on": "header:<name>" | "cookie:<name>" | "remote_ip" }`.
+/// `hash_on` is required for, and only accepted with, `consistent_hash`.
+///
+/// # Errors
+/// Returns a human-readable message; the caller prefixes `args.lb`.
+pub fn parse_policy(v: Option<&serde_json::Value>) -> Result<LbPolicy, String> {
+	let (name, hash_on) = match v {
+		None => return Ok(LbPolicy::RoundRobin),
+		Some(serde_json::Value::String(s)) => (s.as_str(), None),
+		Some(serde_json::Value::Object(obj)) => {
+			let name = obj
+				.get("policy")
+				.and_then(serde_json::Value::as_str)
+				.ok_or_else(|| "missing policy (string)".to_string())?;
+			let hash_on = match obj.get("hash_on") {
+				None => None,
+				Some(h) => Some(h.as_str().ok_or_else(|| "hash_on must be a string".to_string())?),
+			};
+			(name, hash_on)
+		}
+		Some(_) => return Err("must be a policy name or { policy, hash_on } object".to_string()),
+	};
+	let policy = match name {
+		"round_robin" => LbPolicy::RoundRobin,
+		"weighted_random" => LbPolicy::WeightedRandom,
+		"least_in_flight" => LbPolicy::LeastInFlight,
+		"p2c" => LbPolicy::PowerOfTwoChoices,
+		"consistent_hash" => {
+			let raw = hash_on.ok_or_else(|| "consistent_hash requires hash_on".to_string())?;
+			return parse_hash_on(raw).map(LbPolicy::ConsistentHash);
+		}
+		other => {
+			return Err(format!(
+				"policy must be one of 'round_robin' / 'weighted_random' / 'least_in_flight' / 'p2c' / 'consistent_hash' — got {other:?}"
+			));
+		}
+	};
+	if hash_on.is_some() {
+		return Err(format!("hash_on is only valid with consistent_hash (policy is {name:?})"));
+	}
+	Ok(policy)
+}
+
+fn parse_hash_on(raw: &str) -> Result<HashOn, String> {
+	if raw == "remote_ip" {
+		return Ok(HashOn::RemoteIp);
+	}
+	if let Some(name) = raw.strip_prefix("header:") {
+		let name = http::HeaderName::from_bytes(name.trim().as_bytes())
+			.map_err(|e| format!("hash_on {raw:?}: invalid header name: {e}"))?;
+		return Ok(HashOn::Header(name));
+	}
+	if let Some(name) = raw.strip_prefix("cookie:") {
+		let name = name.trim();
+		if name.is_empty() {
+			return Err(format!("hash_on {raw:?}: empty cookie name"));
+		}
+		return Ok(HashOn::Cookie(name.to_owned()));
+	}
+	Err(format!("hash_on must be 'remote_ip', 'header:<name>' or 'cookie:<name>' — got {raw:?}"))
+}
+
+/// Load counter shared by every set that lists the same authority.
+/// The registry holds it weakly; the last set or in-flight guard to
+/// let go evicts the entry.
+#[derive(Debug)]
+struct AuthorityLoad {
+	authority: Authority,
+	in_flight: AtomicU64,
+}
+
+impl Drop for AuthorityLoad {
+	fn drop(&mut self) {
+		// A concurrent `load_for` may already have replaced the dead
+		// entry with a live counter; keep that one.
+		AUTHORITY_LOAD.remove_if(&self.authority, |_, w| w.strong_count() == 0);
+	}
+}
+
+static AUTHORITY_LOAD: LazyLock<DashMap<Authority, Weak<AuthorityLoad>>> =
+	LazyLock::new(DashMap::new);
+
+fn load_for(authority: &Authority) -> Arc<AuthorityLoad> {
+	let mut slot = AUTHORITY_LOAD.entry(authority.clone()).or_default();
+	if let Some(live) = slot.upgrade() {
+		return live;
+	}
+	let load = Arc::new(AuthorityLoad { authority: authority.clone(), in_flight: AtomicU64::new(0) });
+	*slot = Arc::downgrade(&load);
+	load
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct SetKey {
+	policy: LbPolicy,
+	members: Vec<(Authority, u32)>,
+}
+
+static SETS: LazyLock<DashMap<SetKey, Weak<UpstreamSet>>> = LazyLock::new(DashMap::new);
+
+struct SetMember {
+	authority: Authority,
+	weight: u32,
+	load: Arc<AuthorityLoad>,
+	selected: AtomicU64,
+}
+
+/// Selection state for one `(policy, members)` tuple. Member indices
+/// are stable and match the order of `args.upstreams`.
+pub struct UpstreamSet {
+	id: String,
+	policy: LbPolicy,
+	members: Box<[SetMember]>,
+	cursor: AtomicU64,
+	/// Sorted `(point, member index)` pairs. Empty unless the policy is
+	/// `ConsistentHash`.
+	ring: Box<[(u64, usize)]>,
+}
+
+/// Look up (or build) the set for `(policy, members)`. Reuses a set
+/// that a live graph still holds so a reload keeps its cursor and
+/// counters.
+#[must_use]
+pub fn get_or_build(policy: LbPolicy, members: Vec<(Authority, u32)>) -> Arc<UpstreamSet> {
+	let key = SetKey { policy, members };
+	let mut slot = SETS.entry(key.clone()).or_default();
+	if let Some(live) = slot.upgrade() {
+		return live;
+	}
+	let set = Arc::new(UpstreamSet::new(&key));
+	*slot = Arc::downgrade(&set);
+	set
+}
+
+impl UpstreamSet {
+	fn new(key: &SetKey) -> Self {
+		let members: Box<[SetMember]> = key
+			.members
+			.iter()
+			.map(|(authority, weight)| SetMember {
+				authority: authority.clone(),
+				weight: *weight,
+				load: load_for(authority),
+				selected: AtomicU64::new(0),
+			})
+			.collect();
+		let ring = match key.policy {
+			LbPolicy::ConsistentHash(_) => build_ring(&members),
+			_ => Box::default(),
+		};
+		Self {
+			id: stable_hex(key),
+			policy: key.policy.clone(),
+			members,
+			cursor: AtomicU64::new(0),
+			ring,
+		}
+	}
+
+	/// Number of members. Always at least one.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.members.len()
+	}
+
+	/// Always `false` — the factory rejects empty member lists.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.members.is_empty()
+	}
+
+	/// Derive the consistent-hash key for one request. `None` for other
+	/// policies and for requests that lack the configured attribute.
+	#[must_use]
+	pub fn hash_key(&self, headers: &HeaderMap, remote: IpAddr) -> Option<u64> {
+		let LbPolicy::ConsistentHash(on) = &self.policy else {
+			return None;
+		};
+		match on {
+			HashOn::RemoteIp => Some(hash_of(&remote)),
+			HashOn::Header(name) => headers.get(name).map(|v| hash_of(v.as_bytes())),
+			HashOn::Cookie(name) => cookie_value(headers, name).map(hash_of),
+		}
+	}
+
+	/// Pick a member index. Candidates narrow in tiers: untried members
+	/// that `available` accepts, else any untried member, else every
+	/// member. Retries thereby fail over to a different backend before
+	/// revisiting one that already failed, and members marked down (see
+	/// [`crate::fetch::health`]) only take traffic when nothing else
+	/// is left — panic routing beats a guaranteed 502.
+	#[must_use]
+	pub fn select(
+		&self,
+		hash_key: Option<u64>,
+		tried: &[usize],
+		available: impl Fn(usize) -> bool,
+	) -> usize {
+		let idx = if self.members.len() == 1 {
+			0
+		} else {
+			let untried = |i: usize| !tried.contains(&i);
+			let n = self.members.len();
+			let tier = if (0..n).any(|i| untried(i) && available(i)) {
+				0
+			} else if (0..n).any(untried) {
+				1
+			} else {
+				2
+			};
+			let eligible = |i: usize| match tier {
+				0 => untried(i) && available(i),
+				1 => untried(i),
+				_ => true,
+			};
+			match (&self.policy, hash_key) {
+				(LbPolicy::ConsistentHash(_), Some(key)) => self.pick_ring(key, eligible),
+				(LbPolicy::RoundRobin | LbPolicy::ConsistentHash(_), _) => {
+					let total = self.eligible_weight(eligible);
+					let n = self.cursor.fetch_add(1, Ordering::Relaxed) % total;
+					self.pick_by_weight(n, eligible)
+				}
+				(LbPolicy::WeightedRandom, _) => self.pick_random(eligible),
+				(LbPolicy::LeastInFlight, _) => self.pick_least_loaded(eligible),
+				(LbPolicy::PowerOfTwoChoices, _) => {
+					let a = self.pick_random(eligible);
+					let other = |i: usize| eligible(i) && i != a;
+					if (0..self.members.len()).any(other) {
+						let b = self.pick_random(other);
+						if self.lighter(b, a) { b } else { a }
+					} else {
+						a
+					}
+				}
+			}
+		};
+		self.members[idx].selected.fetch_add(1, Ordering::Relaxed);
+		idx
+	}
+
+	/// Count one in-flight request against member `idx` until the guard
+	/// drops.
+	#[must_use]
+	pub fn enter(&self, idx: usize) -> InFlightGuard {
+		let load = Arc::clone(&self.members[idx].load);
+		load.in_flight.fetch_add(1, Ordering::Relaxed);
+		InFlightGuard { load }
+	}
+
+	fn eligible_weight(&self, eligible: impl Fn(usize) -> bool) -> u64 {
+		self
+			.members
+			.iter()
+			.enumerate()
+			.filter(|(i, _)| eligible(*i))
+			.map(|(_, m)| u64::from(m.weight))
+			.sum::<u64>()
+			.max(1)
+	}
+
+	/// Walk eligible members accumulating weight until `n` falls inside
+	/// one member's span.
+	fn pick_by_weight(&self, mut n: u64, eligible: impl Fn(usize) -> bool) -> usize {
+		let mut last = 0;
+		for (i, m) in self.members.iter().enumerate().filter(|(i, _)| eligible(*i)) {
+			let w = u64::from(m.weight);
+			if n < w {
+				return i;
+			}
+			n -= w;
+			last = i;
+		}
+		last
+	}
+
+	fn pick_random(&self, eligible: impl Fn(usize) -> bool + Copy) -> usize {
+		let total = self.eligible_weight(eligible);
+		self.pick_by_weight(rand::rng().random_range(0..total), eligible)
+	}
+
+	fn pick_least_loaded(&self, eligible: impl Fn(usize) -> bool) -> usize {
+		let len = self.members.len();
+		// Rotating start so equal loads spread instead of pinning member 0.
+		let start =
+			usize::try_from(self.cursor.fetch_add(1, Ordering::Relaxed) % len as u64).unwrap_or_default();
+		let mut best: Option<usize> = None;
+		for i in (0..len).map(|k| (start + k) % len).filter(|i| eligible(*i)) {
+			if best.is_none_or(|b| self.lighter(i, b)) {
+				best = Some(i);
+			}
+		}
+		best.unwrap_or(start)
+	}
+
+	/// `a` carries strictly less load per unit of weight than `b`.
+	/// Cross-multiplied to stay in integers.
+	fn lighter(&self, a: usize, b: usize) -> bool {
+		let (ma, mb) = (&self.members[a], &self.members[b]);
+		let la = ma.load.in_flight.load(Ordering::Relaxed);
+		let lb = mb.load.in_flight.load(Ordering::Relaxed);
+		u128::from(la) * u128::from(mb.weight) < u128::from(lb) * u128::from(ma.weight)
+	}
+
+	fn pick_ring(&self, key: u64, eligible: impl Fn(usize) -> bool) -> usize {
+		let start = self.ring.partition_point(|(point, _)| *point < key);
+		self
+			.ring
+			.iter()
+			.cycle()
+			.skip(start)
+			.take(self.ring.len())
+			.map(|(_, idx)| *idx)
+			.find(|idx| eligible(*idx))
+			.unwrap_or(0)
+	}
+}
+
+/// Decrements the member's authority-wide in-flight count on drop.
+pub struct InFlightGuard {
+	load: Arc<AuthorityLoad>,
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		self.load.in_flight.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+fn build_ring(members: &[SetMember]) -> Box<[(u64, usize)]> {
+	let mut ring: Vec<(u64, usize)> = members
+		.iter()
+		.enumerate()
+		.flat_map(|(idx, m)| {
+			(0..m.weight * RING_VNODES_PER_WEIGHT)
+				.map(move |vnode| (hash_of(&(m.authority.as_str(), vnode)), idx))
+		})
+		.collect();
+	ring.sort_unstable();
+	ring.into_boxed_slice()
+}
+
+fn hash_of<T: std::hash::Hash + ?Sized>(value: &T) -> u64 {
+	let mut h = std::collections::hash_map::DefaultHasher::new();
+	value.hash(&mut h);
+	h.finish()
+}
+
+fn stable_hex<T: std::hash::Hash>(value: &T) -> String {
+	format!("{:016x}", hash_of(value))
+}
+
+/// First value of cookie `name` across every `Cookie` header.
+fn cookie_value<'h>(headers: &'h HeaderMap, name: &str) -> Option<&'h [u8]> {
+	headers
+		.get_all(http::header::COOKIE)
+		.iter()
+		.flat_map(|v| v.as_bytes().split(|b| *b == b';'))
+		.find_map(|pair| {
+			let pair = pair.trim_ascii();
+			let eq = pair.iter().position(|b| *b == b'=')?;
+			(pair[..eq].trim_ascii() == name.as_bytes()).then(|| pair[eq + 1..].trim_ascii())
+		})
+}
+
+/// Read-only view of one member, surfaced via `get_upstreams`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamMemberSummary {
+	pub authority: String,
+	pub weight: u32,
+	/// Requests currently dispatched to this authority across every set.
+	pub in_flight: u64,
+	/// Times this set picked the member since the set was built.
+	pub selected: u64,
+}
+
+/// Read-only view of one live upstream set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamSetSummary {
+	/// 16-char hex hash of `(policy, members)`.
+	pub set_id: String,
+	pub policy: String,
+	pub members: Vec<UpstreamMemberSummary>,
+}
+
+/// Snapshot every set still held by a live graph, sorted by `set_id`.
+/// Dead registry slots are pruned on the way.
+#[must_use]
+pub fn snapshot() -> Vec<UpstreamSetSummary> {
+	SETS.retain(|_, weak| weak.strong_count() > 0);
+	let mut out: Vec<UpstreamSetSummary> = SETS
+		.iter()
+		.filter_map(|entry| entry.value().upgrade())
+		.map(|set| UpstreamSetSummary {
+			set_id: set.id.clone(),
+			policy: set.policy.to_string(),
+			members: set
+				.members
+				.iter()
+				.map(|m| UpstreamMemberSummary {
+					authority: m.authority.to_string(),
+					weight: m.weight,
+					in_flight: m.load.in_flight.load(Ordering::Relaxed),
+					selected: m.selected.load(Ordering::Relaxed),
+				})
+				.collect(),
+		})
+		.collect();
+	out.sort_by(|a, b| a.set_id.cmp(&b.set_id));
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::Ipv4Addr;
+
+	use serde_json::json;
+
+	use super::*;
+
+	fn set(policy: LbPolicy, members: &[(&str, u32)]) -> Arc<UpstreamSet> {
+		let members = members.iter().map(|(a, w)| (a.parse().expect("authority"), *w)).collect();
+		get_or_build(policy, members)
+	}
+
+	#[test]
+	fn parse_members_accepts_single_upstream() {
+		let m = parse_members(&json!({ "upstream": "a:1" })).expect("parse");
+		assert_eq!(m, vec![MemberSpec { upstream: "a:1".to_owned(), weight: 1 }]);
+	}
+
+	#[test]
+	fn parse_members_accepts_mixed_list() {
+		let m = parse_members(&json!({ "upstreams": ["a:1", { "upstream": "b:1", "weight": 3 }] }))
+			.expect("parse");
+		assert_eq!(m[1], MemberSpec { upstream: "b:1".to_owned(), weight: 3 });
+	}
+
+	#[test]
+	fn parse_members_rejects_both_keys_empty_list_and_duplicates() {
+		let both = parse_members(&json!({ "upstream": "a:1", "upstreams": ["b:1"] }));
+		assert!(both.unwrap_err().contains("mutually exclusive"));
+		let empty = parse_members(&json!({ "upstreams": [] }));
+		assert!(empty.unwrap_err().contains("non-empty"));
+		let dup = parse_members(&json!({ "upstreams": ["a:1", "a:1"] }));
+		assert!(dup.unwrap_err().contains("duplicate"));
+		let zero = parse_members(&json!({ "upstreams": [{ "upstream": "a:1", "weight": 0 }] }));
+		assert!(zero.unwrap_err().contains("weight"));
+	}
+
+	#[test]
+	fn parse_policy_covers_names_and_hash_on() {
+		assert_eq!(parse_policy(None).unwrap(), LbPolicy::RoundRobin);
+		assert_eq!(parse_policy(Some(&json!("p2c"))).unwrap(), LbPolicy::PowerOfTwoChoices);
+		assert_eq!(
+			parse_policy(Some(&json!({ "policy": "consistent_hash", "hash_on": "cookie:sid" }))).unwrap(),
+			LbPolicy::ConsistentHash(HashOn::Cookie("sid".to_owned())),
+		);
+		assert!(parse_policy(Some(&json!("consistent_hash"))).unwrap_err().contains("hash_on"));
+		assert!(
+			parse_policy(Some(&json!({ "policy": "p2c", "hash_on": "remote_ip" })))
+				.unwrap_err()
+				.contains("only valid")
+		);
+		assert!(parse_policy(Some(&json!("fastest"))).is_err());
+	}
+
+	#[test]
+	fn round_robin_honours_weights() {
+		let s = set(LbPolicy::RoundRobin, &[("rr-a:1", 3), ("rr-b:1", 1)]);
+		let picks: Vec<usize> = (0..8).map(|_| s.select(None, &[], |_| true)).collect();
+		assert_eq!(picks.iter().filter(|i| **i == 0).count(), 6);
+		assert_eq!(picks.iter().filter(|i| **i == 1).count(), 2);
+	}
+
+	#[test]
+	fn select_skips_tried_members_until_exhausted() {
+		let s = set(LbPolicy::RoundRobin, &[("tried-a:1", 1), ("tried-b:1", 1)]);
+		for _ in 0..4 {
+			assert_eq!(s.select(None, &[0], |_| true), 1);
+		}
+		// Everything tried: selection falls back to the full set.
+		let _ = s.select(None, &[0, 1], |_| true);
+	}
+
+	#[test]
+	fn select_avoids_unavailable_members_unless_nothing_else_is_left() {
+		let s = set(LbPolicy::RoundRobin, &[("down-a:1", 1), ("down-b:1", 1), ("down-c:1", 1)]);
+		for _ in 0..6 {
+			assert_ne!(s.select(None, &[], |i| i != 1), 1);
+		}
+		// The only available member was tried: untried-but-down wins.
+		assert_ne!(s.select(None, &[0], |i| i == 0), 0);
+		// Everything down: panic routing still returns a member.
+		let _ = s.select(None, &[], |_| false);
+	}
+
+	#[test]
+	fn least_in_flight_prefers_idle_member() {
+		let s = set(LbPolicy::LeastInFlight, &[("lif-a:1", 1), ("lif-b:1", 1)]);
+		let _busy = s.enter(0);
+		for _ in 0..4 {
+			assert_eq!(s.select(None, &[], |_| true), 1);
+		}
+	}
+
+	#[test]
+	fn consistent_hash_is_sticky_per_key() {
+		let s = set(
+			LbPolicy::ConsistentHash(HashOn::RemoteIp),
+			&[("ch-a:1", 1), ("ch-b:1", 1), ("ch-c:1", 1)],
+		);
+		let key = s.hash_key(&HeaderMap::new(), IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)));
+		let first = s.select(key, &[], |_| true);
+		for _ in 0..8 {
+			assert_eq!(s.select(key, &[], |_| true), first);
+		}
+		assert_ne!(s.select(key, &[first], |_| true), first);
+	}
+
+	#[test]
+	fn cookie_value_finds_named_pair() {
+		let mut h = HeaderMap::new();
+		h.append(http::header::COOKIE, "a=1; sid=xyz".parse().unwrap());
+		assert_eq!(cookie_value(&h, "sid"), Some(&b"xyz"[..]));
+		assert_eq!(cookie_value(&h, "missing"), None);
+	}
+
+	#[test]
+	fn registry_reuses_live_set_and_reports_in_flight() {
+		let a = set(LbPolicy::WeightedRandom, &[("snap-a:1", 2), ("snap-b:1", 1)]);
+		let b = set(LbPolicy::WeightedRandom, &[("snap-a:1", 2), ("snap-b:1", 1)]);
+		assert!(Arc::ptr_eq(&a, &b));
+		let _guard = a.enter(1);
+		let snap = snapshot();
+		let entry = snap.iter().find(|s| s.set_id == a.id).expect("live set in snapshot");
+		assert_eq!(entry.policy, "weighted_random");
+		assert_eq!(entry.members[1].in_flight, 1);
+		let id = a.id.clone();
+		drop((a, b));
+		assert!(snapshot().iter().all(|s| s.set_id != id), "dropped set is pruned");
+	}
+
+	#[test]
+	fn authority_load_is_evicted_with_its_last_holder() {
+		let authority: Authority = "evict-a:1".parse().unwrap();
+		let a = set(LbPolicy::LeastInFlight, &[("evict-a:1", 1), ("evict-b:1", 1)]);
+		let guard = a.enter(0);
+		drop(a);
+		assert!(AUTHORITY_LOAD.contains_key(&authority), "in-flight guard keeps the counter");
+		drop(guard);
+		assert!(!AUTHORITY_LOAD.contains_key(&authority), "last holder evicts the entry");
+	}
+}
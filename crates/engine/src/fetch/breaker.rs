@@ -0,0 +1,411 @@
+//! Passive outlier detection: a per-authority circuit breaker fed by
+//! the outcome of real requests.
+//!
+//! `consecutive_failures` connect failures, timeouts or 5xx responses
+//! in a row eject the authority for `base_ejection`, doubling on every
+//! re-ejection up to `max_ejection`. Once the ejection expires one
+//! request is let through as a half-open probe: success closes the
+//! breaker and resets the back-off, failure re-opens it. Breakers live
+//! at daemon scope, keyed weakly by `(authority, spec)` like upstream
+//! sets, so a reload does not forget an ejected backend.
+//!
+//! See `spec/crates/engine.md` § _Circuit breaking_.
+
+use std::sync::{Arc, LazyLock, Weak};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use vane_core::{Error, ErrorKind, L7FetchOutput, UpstreamReason};
+
+use crate::fetch::retry::parse_duration;
+
+const DEFAULT_CONSECUTIVE_FAILURES: u32 = 5;
+const DEFAULT_BASE_EJECTION: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_EJECTION: Duration = Duration::from_mins(5);
+/// Doubling stops contributing long before this; it only keeps the
+/// shift in range.
+const MAX_BACKOFF_SHIFT: u32 = 16;
+
+/// Parsed `args.circuit_breaker`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BreakerSpec {
+	pub consecutive_failures: u32,
+	pub base_ejection: Duration,
+	pub max_ejection: Duration,
+}
+
+/// Parse `args.circuit_breaker`. Absent means no breaker; `{}` takes
+/// every default.
+///
+/// ```json
+/// { "consecutive_failures": 5, "base_ejection": "10s", "max_ejection": "5m" }
+/// ```
+///
+/// # Errors
+/// Returns a human-readable message; the caller prefixes
+/// `args.circuit_breaker`.
+pub fn parse(v: Option<&serde_json::Value>) -> Result<Option<BreakerSpec>, String> {
+	let Some(v) = v else { return Ok(None) };
+	let obj = v.as_object().ok_or("must be an object")?;
+	let consecutive_failures = match obj.get("consecutive_failures") {
+		None => DEFAULT_CONSECUTIVE_FAILURES,
+		Some(n) => n
+			.as_u64()
+			.and_then(|n| u32::try_from(n).ok())
+			.filter(|n| (1..=1000).contains(n))
+			.ok_or("consecutive_failures must be an integer in 1..=1000")?,
+	};
+	let duration = |key: &str, default: Duration| -> Result<Duration, String> {
+		let Some(v) = obj.get(key) else { return Ok(default) };
+		let s = v.as_str().ok_or_else(|| format!("{key} must be a duration string"))?;
+		let d = parse_duration(s).map_err(|e| format!("{key}: {e}"))?;
+		if d.is_zero() {
+			return Err(format!("{key} must be greater than zero"));
+		}
+		Ok(d)
+	};
+	let base_ejection = duration("base_ejection", DEFAULT_BASE_EJECTION)?;
+	let max_ejection = duration("max_ejection", DEFAULT_MAX_EJECTION.max(base_ejection))?;
+	if max_ejection < base_ejection {
+		return Err("max_ejection must not be shorter than base_ejection".to_string());
+	}
+	Ok(Some(BreakerSpec { consecutive_failures, base_ejection, max_ejection }))
+}
+
+/// How one finished attempt feeds the breaker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+	Success,
+	Failure,
+	/// Says nothing about the upstream (local pool exhaustion,
+	/// cancellation, a mid-stream reset) — counters stay put.
+	Neutral,
+}
+
+impl Outcome {
+	/// Classify an attempt's result. Connect-class errors (the
+	/// [`Error::is_retryable`] set plus TLS handshake failures), every
+	/// timeout, and 5xx responses count against the upstream.
+	#[must_use]
+	pub fn of(result: &Result<L7FetchOutput, Error>) -> Self {
+		match result {
+			Ok(L7FetchOutput::Response(resp)) if resp.status().is_server_error() => Self::Failure,
+			Ok(_) => Self::Success,
+			Err(e) => match e.kind() {
+				ErrorKind::Upstream(UpstreamReason::CircuitOpen) => Self::Neutral,
+				ErrorKind::Upstream(UpstreamReason::TlsHandshake) | ErrorKind::Timeout(_) => Self::Failure,
+				ErrorKind::Upstream(_) if e.is_retryable() => Self::Failure,
+				_ => Self::Neutral,
+			},
+		}
+	}
+}
+
+/// Breaker position, as reported in flow-log events and metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+	Closed,
+	Open,
+	HalfOpen,
+}
+
+impl BreakerState {
+	#[must_use]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Closed => "closed",
+			Self::Open => "open",
+			Self::HalfOpen => "half_open",
+		}
+	}
+}
+
+/// One state change, handed back so the caller can mirror it into the
+/// request's flow log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+	pub from: BreakerState,
+	pub to: BreakerState,
+	/// Ejection length when `to` is `Open`.
+	pub ejection: Option<Duration>,
+}
+
+/// Verdict for one attempt about to be dispatched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Admit {
+	/// Closed breaker — dispatch normally.
+	Pass,
+	/// This attempt is the half-open probe; its outcome decides.
+	Probe,
+	/// Ejected — fail fast without dialling.
+	Reject,
+}
+
+enum Phase {
+	Closed,
+	Open {
+		until: Instant,
+	},
+	/// `probe` is when the in-flight probe started; `None` means the
+	/// next attempt becomes the probe.
+	HalfOpen {
+		probe: Option<Instant>,
+	},
+}
+
+struct Inner {
+	phase: Phase,
+	failures: u32,
+	/// Consecutive ejections without a successful probe in between.
+	ejections: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct BreakerKey {
+	authority: String,
+	spec: BreakerSpec,
+}
+
+static BREAKERS: LazyLock<DashMap<BreakerKey, Weak<Breaker>>> = LazyLock::new(DashMap::new);
+
+/// Look up (or create) the breaker for `(authority, spec)`.
+#[must_use]
+pub fn get_or_build(authority: &str, spec: &BreakerSpec) -> Arc<Breaker> {
+	let key = BreakerKey { authority: authority.to_owned(), spec: spec.clone() };
+	let mut slot = BREAKERS.entry(key).or_default();
+	if let Some(live) = slot.upgrade() {
+		return live;
+	}
+	let breaker = Arc::new(Breaker {
+		authority: authority.to_owned(),
+		spec: spec.clone(),
+		inner: Mutex::new(Inner { phase: Phase::Closed, failures: 0, ejections: 0 }),
+	});
+	*slot = Arc::downgrade(&breaker);
+	drop(slot);
+	BREAKERS.retain(|_, weak| weak.strong_count() > 0);
+	breaker
+}
+
+pub struct Breaker {
+	authority: String,
+	spec: BreakerSpec,
+	inner: Mutex<Inner>,
+}
+
+impl Breaker {
+	#[must_use]
+	pub fn authority(&self) -> &str {
+		&self.authority
+	}
+
+	/// `true` while the breaker would reject an attempt: open and not
+	/// yet expired, or half-open with a live probe. Selection uses it to
+	/// steer around the member; it never transitions state.
+	#[must_use]
+	pub fn is_ejected(&self) -> bool {
+		let inner = self.inner.lock();
+		match inner.phase {
+			Phase::Closed | Phase::HalfOpen { probe: None } => false,
+			Phase::Open { until } => Instant::now() < until,
+			Phase::HalfOpen { probe: Some(started) } => !self.probe_is_stale(started),
+		}
+	}
+
+	/// Gate one attempt. An expired ejection moves to half-open and the
+	/// caller's attempt becomes the probe.
+	pub fn admit(&self) -> (Admit, Option<Transition>) {
+		let mut inner = self.inner.lock();
+		let now = Instant::now();
+		match inner.phase {
+			Phase::Closed => (Admit::Pass, None),
+			Phase::Open { until } if now < until => (Admit::Reject, None),
+			Phase::Open { .. } => {
+				inner.phase = Phase::HalfOpen { probe: Some(now) };
+				drop(inner);
+				let t = Transition { from: BreakerState::Open, to: BreakerState::HalfOpen, ejection: None };
+				self.observe(&t);
+				(Admit::Probe, Some(t))
+			}
+			Phase::HalfOpen { probe: Some(started) } if !self.probe_is_stale(started) => {
+				(Admit::Reject, None)
+			}
+			Phase::HalfOpen { .. } => {
+				inner.phase = Phase::HalfOpen { probe: Some(now) };
+				(Admit::Probe, None)
+			}
+		}
+	}
+
+	/// Fold one admitted attempt's outcome into the breaker.
+	pub fn record(&self, admit: Admit, outcome: Outcome) -> Option<Transition> {
+		let mut inner = self.inner.lock();
+		let from = match inner.phase {
+			Phase::Closed => BreakerState::Closed,
+			Phase::Open { .. } => BreakerState::Open,
+			Phase::HalfOpen { .. } => BreakerState::HalfOpen,
+		};
+		let is_probe = admit == Admit::Probe && from == BreakerState::HalfOpen;
+		let transition = match (outcome, is_probe) {
+			(Outcome::Neutral, true) => {
+				// Inconclusive probe: hand the slot to the next attempt.
+				inner.phase = Phase::HalfOpen { probe: None };
+				None
+			}
+			(Outcome::Neutral, false) => None,
+			(Outcome::Success, true) => {
+				inner.phase = Phase::Closed;
+				inner.failures = 0;
+				inner.ejections = 0;
+				Some(Transition { from, to: BreakerState::Closed, ejection: None })
+			}
+			(Outcome::Success, false) => {
+				inner.failures = 0;
+				None
+			}
+			(Outcome::Failure, true) => Some(self.eject(&mut inner, from)),
+			(Outcome::Failure, false) => {
+				inner.failures = inner.failures.saturating_add(1);
+				(from == BreakerState::Closed && inner.failures >= self.spec.consecutive_failures)
+					.then(|| self.eject(&mut inner, from))
+			}
+		};
+		drop(inner);
+		if let Some(t) = &transition {
+			self.observe(t);
+		}
+		transition
+	}
+
+	fn eject(&self, inner: &mut Inner, from: BreakerState) -> Transition {
+		let shift = inner.ejections.min(MAX_BACKOFF_SHIFT);
+		let ejection = self.spec.base_ejection.saturating_mul(1 << shift).min(self.spec.max_ejection);
+		inner.ejections = inner.ejections.saturating_add(1);
+		inner.failures = 0;
+		inner.phase = Phase::Open { until: Instant::now() + ejection };
+		Transition { from, to: BreakerState::Open, ejection: Some(ejection) }
+	}
+
+	/// A probe whose request never reported back (dropped future) must
+	/// not wedge the breaker half-open forever.
+	fn probe_is_stale(&self, started: Instant) -> bool {
+		started.elapsed() >= self.spec.base_ejection
+	}
+
+	fn observe(&self, t: &Transition) {
+		metrics::counter!(
+			"vane.upstream.circuit.transitions_total",
+			"upstream" => self.authority.clone(),
+			"to" => t.to.as_str(),
+		)
+		.increment(1);
+		match t.to {
+			BreakerState::Open => tracing::warn!(
+				upstream = %self.authority,
+				from = t.from.as_str(),
+				ejection_ms = t.ejection.map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+				"circuit breaker opened",
+			),
+			BreakerState::HalfOpen => {
+				tracing::info!(upstream = %self.authority, "circuit breaker half-open, probing");
+			}
+			BreakerState::Closed => tracing::info!(upstream = %self.authority, "circuit breaker closed"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use vane_core::TimeoutKind;
+
+	use super::*;
+
+	fn spec(threshold: u32, base_ms: u64, max_ms: u64) -> BreakerSpec {
+		BreakerSpec {
+			consecutive_failures: threshold,
+			base_ejection: Duration::from_millis(base_ms),
+			max_ejection: Duration::from_millis(max_ms),
+		}
+	}
+
+	#[test]
+	fn parse_defaults_and_rejects_bad_values() {
+		let s = parse(Some(&json!({}))).expect("parse").expect("some");
+		assert_eq!(s, spec(5, 10_000, 300_000));
+		assert!(parse(None).expect("parse").is_none());
+		assert!(parse(Some(&json!({ "consecutive_failures": 0 }))).is_err());
+		assert!(
+			parse(Some(&json!({ "base_ejection": "10s", "max_ejection": "1s" })))
+				.unwrap_err()
+				.contains("max_ejection")
+		);
+	}
+
+	#[test]
+	fn outcome_classifies_connect_timeout_and_5xx() {
+		let resp = |code: u16| {
+			Ok(L7FetchOutput::Response(
+				http::Response::builder().status(code).body(vane_core::Body::Empty).unwrap(),
+			))
+		};
+		assert_eq!(Outcome::of(&resp(200)), Outcome::Success);
+		assert_eq!(Outcome::of(&resp(404)), Outcome::Success);
+		assert_eq!(Outcome::of(&resp(503)), Outcome::Failure);
+		assert_eq!(Outcome::of(&Err(Error::upstream(UpstreamReason::Unreachable))), Outcome::Failure);
+		assert_eq!(Outcome::of(&Err(Error::timeout(TimeoutKind::Read))), Outcome::Failure);
+		assert_eq!(Outcome::of(&Err(Error::upstream(UpstreamReason::CircuitOpen))), Outcome::Neutral);
+		assert_eq!(Outcome::of(&Err(Error::canceled())), Outcome::Neutral);
+	}
+
+	#[test]
+	fn circuit_open_error_fails_fast_as_503_and_retries_elsewhere() {
+		let e = Error::upstream(UpstreamReason::CircuitOpen);
+		assert_eq!(e.http_status(), 503);
+		assert!(e.is_retryable_in(&http::Method::POST));
+	}
+
+	#[test]
+	fn threshold_opens_then_probe_closes() {
+		let b = get_or_build("open-close.test:1", &spec(2, 20, 1_000));
+		assert_eq!(b.record(Admit::Pass, Outcome::Failure), None);
+		let t = b.record(Admit::Pass, Outcome::Failure).expect("opens");
+		assert_eq!((t.from, t.to), (BreakerState::Closed, BreakerState::Open));
+		assert!(b.is_ejected());
+		assert_eq!(b.admit().0, Admit::Reject);
+
+		std::thread::sleep(Duration::from_millis(30));
+		let (admit, t) = b.admit();
+		assert_eq!(admit, Admit::Probe);
+		assert_eq!(t.map(|t| t.to), Some(BreakerState::HalfOpen));
+		assert_eq!(b.admit().0, Admit::Reject, "only one probe in flight");
+		let t = b.record(Admit::Probe, Outcome::Success).expect("closes");
+		assert_eq!(t.to, BreakerState::Closed);
+		assert_eq!(b.admit().0, Admit::Pass);
+	}
+
+	#[test]
+	fn failed_probe_doubles_ejection_up_to_max() {
+		let b = get_or_build("backoff.test:1", &spec(1, 10, 25));
+		let first = b.record(Admit::Pass, Outcome::Failure).and_then(|t| t.ejection);
+		assert_eq!(first, Some(Duration::from_millis(10)));
+		std::thread::sleep(Duration::from_millis(15));
+		assert_eq!(b.admit().0, Admit::Probe);
+		let second = b.record(Admit::Probe, Outcome::Failure).and_then(|t| t.ejection);
+		assert_eq!(second, Some(Duration::from_millis(20)));
+		std::thread::sleep(Duration::from_millis(25));
+		assert_eq!(b.admit().0, Admit::Probe);
+		let third = b.record(Admit::Probe, Outcome::Failure).and_then(|t| t.ejection);
+		assert_eq!(third, Some(Duration::from_millis(25)), "capped at max_ejection");
+	}
+
+	#[test]
+	fn success_resets_consecutive_count() {
+		let b = get_or_build("reset.test:1", &spec(2, 1_000, 1_000));
+		assert_eq!(b.record(Admit::Pass, Outcome::Failure), None);
+		assert_eq!(b.record(Admit::Pass, Outcome::Success), None);
+		assert_eq!(b.record(Admit::Pass, Outcome::Failure), None);
+		assert!(!b.is_ejected());
+	}
+}
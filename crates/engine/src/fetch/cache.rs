@@ -0,0 +1,1104 @@
+//! Shared HTTP response cache (RFC 9111) consulted by `http_proxy`.
+//!
+//! One byte-bounded LRU store lives at daemon scope, so a reload that
+//! rebuilds every `HttpProxyFetch` keeps what was cached. Rules opt in
+//! with `args.cache`; the rule's [`CacheSpec`] decides how requests map
+//! to keys and which defaults apply when the upstream is silent about
+//! stale serving. Freshness itself comes from the stored response's
+//! `Cache-Control` / `Expires` / `Last-Modified`.
+//!
+//! Each key holds one variant per distinct set of request values for
+//! the response's `Vary` headers. Misses for the same key are
+//! coalesced: the first request becomes the leader and fills the
+//! entry while streaming to its own client through [`FillBody`]; the
+//! others wait for the fill to finish (or give up after
+//! [`COALESCE_WAIT`]) and then look again.
+//!
+//! The request-path orchestration lives in `http_proxy/cached.rs`.
+//! See `spec/crates/engine.md` § _Response cache_.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry as MapEntry;
+use http::header::{
+	AGE, CACHE_CONTROL, CONTENT_LENGTH, COOKIE, DATE, ETAG, EXPIRES, HOST, LAST_MODIFIED, PRAGMA,
+	SET_COOKIE, VARY,
+};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use parking_lot::Mutex;
+use pin_project_lite::pin_project;
+use tokio::sync::watch;
+use vane_core::{Body, Error};
+
+use crate::fetch::retry::parse_duration;
+
+/// Store capacity when `VANE_CACHE_MAX_BYTES` is unset.
+const DEFAULT_CAPACITY_BYTES: usize = 256 * 1024 * 1024;
+const DEFAULT_MAX_OBJECT_BYTES: usize = 8 * 1024 * 1024;
+/// Upper bound on the `Last-Modified` heuristic (RFC 9111 §4.2.2).
+const HEURISTIC_CAP: Duration = Duration::from_hours(24);
+/// How long a coalesced request waits on the leader before going to
+/// the upstream itself.
+pub const COALESCE_WAIT: Duration = Duration::from_secs(10);
+
+/// Statuses that may be cached on heuristic freshness (RFC 9110
+/// §15.1). Anything else needs explicit freshness to be stored.
+const HEURISTIC_STATUSES: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// How the query string contributes to the key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum QueryKey {
+	/// The whole query, verbatim.
+	All,
+	/// Ignored.
+	None,
+	/// Only these parameters, sorted by name.
+	Only(Vec<String>),
+}
+
+/// Parsed `args.cache`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheSpec {
+	pub query: QueryKey,
+	/// Request headers appended to the key, lower-cased.
+	pub headers: Vec<HeaderName>,
+	/// Request cookies appended to the key.
+	pub cookies: Vec<String>,
+	pub max_object_bytes: usize,
+	/// Lifetime for responses that carry no freshness information.
+	pub default_ttl: Option<Duration>,
+	/// Used when the response has no `stale-while-revalidate`.
+	pub stale_while_revalidate: Duration,
+	/// Used when the response has no `stale-if-error`.
+	pub stale_if_error: Duration,
+}
+
+/// Parse `args.cache`. Absent means the rule does not cache; `{}`
+/// takes every default.
+///
+/// ```json
+/// {
+///   "key": { "query": true, "headers": ["accept-language"], "cookies": ["lang"] },
+///   "max_object_bytes": 8388608,
+///   "default_ttl": "1m",
+///   "stale_while_revalidate": "30s",
+///   "stale_if_error": "5m"
+/// }
+/// ```
+///
+/// # Errors
+/// Returns a human-readable message; the caller prefixes `args.cache`.
+pub fn parse(v: Option<&serde_json::Value>) -> Result<Option<CacheSpec>, String> {
+	let Some(v) = v else { return Ok(None) };
+	let obj = v.as_object().ok_or("must be an object")?;
+	for k in obj.keys() {
+		if !matches!(
+			k.as_str(),
+			"key" | "max_object_bytes" | "default_ttl" | "stale_while_revalidate" | "stale_if_error"
+		) {
+			return Err(format!("unknown field {k:?}"));
+		}
+	}
+	let (query, headers, cookies) = match obj.get("key") {
+		None => (QueryKey::All, Vec::new(), Vec::new()),
+		Some(k) => parse_key(k)?,
+	};
+	let max_object_bytes = match obj.get("max_object_bytes") {
+		None => DEFAULT_MAX_OBJECT_BYTES,
+		Some(n) => n
+			.as_u64()
+			.and_then(|n| usize::try_from(n).ok())
+			.filter(|n| *n > 0)
+			.ok_or("max_object_bytes must be a positive integer")?,
+	};
+	let duration = |key: &str| -> Result<Option<Duration>, String> {
+		let Some(v) = obj.get(key) else { return Ok(None) };
+		let s = v.as_str().ok_or_else(|| format!("{key} must be a duration string"))?;
+		parse_duration(s).map(Some).map_err(|e| format!("{key}: {e}"))
+	};
+	Ok(Some(CacheSpec {
+		query,
+		headers,
+		cookies,
+		max_object_bytes,
+		default_ttl: duration("default_ttl")?.filter(|d| !d.is_zero()),
+		stale_while_revalidate: duration("stale_while_revalidate")?.unwrap_or_default(),
+		stale_if_error: duration("stale_if_error")?.unwrap_or_default(),
+	}))
+}
+
+fn parse_key(v: &serde_json::Value) -> Result<(QueryKey, Vec<HeaderName>, Vec<String>), String> {
+	let obj = v.as_object().ok_or("key must be an object")?;
+	for k in obj.keys() {
+		if !matches!(k.as_str(), "query" | "headers" | "cookies") {
+			return Err(format!("key: unknown field {k:?}"));
+		}
+	}
+	let strings = |field: &str| -> Result<Vec<String>, String> {
+		let Some(v) = obj.get(field) else { return Ok(Vec::new()) };
+		v.as_array()
+			.ok_or_else(|| format!("key.{field} must be an array of strings"))?
+			.iter()
+			.map(|e| {
+				e.as_str().map(str::to_owned).ok_or_else(|| format!("key.{field} entries must be strings"))
+			})
+			.collect()
+	};
+	let query = match obj.get("query") {
+		None | Some(serde_json::Value::Bool(true)) => QueryKey::All,
+		Some(serde_json::Value::Bool(false)) => QueryKey::None,
+		Some(serde_json::Value::Array(_)) => {
+			let mut names = strings("query")?;
+			names.sort();
+			names.dedup();
+			QueryKey::Only(names)
+		}
+		Some(_) => {
+			return Err("key.query must be a boolean or an array of parameter names".to_string());
+		}
+	};
+	let headers = strings("headers")?
+		.iter()
+		.map(|h| {
+			HeaderName::from_bytes(h.to_ascii_lowercase().as_bytes())
+				.map_err(|e| format!("key.headers {h:?}: {e}"))
+		})
+		.collect::<Result<_, _>>()?;
+	Ok((query, headers, strings("cookies")?))
+}
+
+impl CacheSpec {
+	/// `host/path[?query]`, followed by one `|h:name=value` per keyed
+	/// header and `|c:name=value` per keyed cookie. Purge-by-prefix
+	/// works on this string, so `example.com/static/` drops a subtree.
+	#[must_use]
+	pub fn key(&self, uri: &http::Uri, headers: &HeaderMap) -> String {
+		let host = uri
+			.authority()
+			.map(http::uri::Authority::as_str)
+			.or_else(|| headers.get(HOST).and_then(|h| h.to_str().ok()))
+			.unwrap_or("")
+			.to_ascii_lowercase();
+		let mut key = format!("{host}{}", uri.path());
+		match (&self.query, uri.query()) {
+			(QueryKey::All, Some(q)) if !q.is_empty() => {
+				key.push('?');
+				key.push_str(q);
+			}
+			(QueryKey::Only(names), Some(q)) => {
+				let mut kept: Vec<&str> = q
+					.split('&')
+					.filter(|p| names.iter().any(|n| p.split('=').next() == Some(n.as_str())))
+					.collect();
+				kept.sort_unstable();
+				if !kept.is_empty() {
+					key.push('?');
+					key.push_str(&kept.join("&"));
+				}
+			}
+			_ => {}
+		}
+		for name in &self.headers {
+			let joined =
+				headers.get_all(name).iter().filter_map(|v| v.to_str().ok()).collect::<Vec<_>>().join(",");
+			let _ = write!(key, "|h:{name}={joined}");
+		}
+		for name in &self.cookies {
+			let value = cookie_value(headers, name).unwrap_or_default();
+			let _ = write!(key, "|c:{name}={value}");
+		}
+		key
+	}
+}
+
+fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+	headers
+		.get_all(COOKIE)
+		.iter()
+		.filter_map(|v| v.to_str().ok())
+		.flat_map(|v| v.split(';'))
+		.find_map(|pair| {
+			let (k, v) = pair.trim().split_once('=')?;
+			(k == name).then(|| v.to_owned())
+		})
+}
+
+/// Comma-separated `Cache-Control` directives, lower-cased, with their
+/// optional (unquoted) argument.
+fn directives(headers: &HeaderMap) -> Vec<(String, Option<String>)> {
+	headers
+		.get_all(CACHE_CONTROL)
+		.iter()
+		.filter_map(|v| v.to_str().ok())
+		.flat_map(|v| v.split(','))
+		.filter_map(|d| {
+			let d = d.trim();
+			if d.is_empty() {
+				return None;
+			}
+			Some(match d.split_once('=') {
+				Some((k, v)) => {
+					(k.trim().to_ascii_lowercase(), Some(v.trim().trim_matches('"').to_owned()))
+				}
+				None => (d.to_ascii_lowercase(), None),
+			})
+		})
+		.collect()
+}
+
+fn seconds(v: Option<&String>) -> Option<Duration> {
+	v.and_then(|s| s.parse::<u64>().ok()).map(Duration::from_secs)
+}
+
+/// The request directives the cache acts on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestDirectives {
+	pub no_store: bool,
+	/// `no-cache`, or `Pragma: no-cache` without any `Cache-Control`.
+	pub no_cache: bool,
+	pub max_age: Option<Duration>,
+	pub min_fresh: Option<Duration>,
+}
+
+impl RequestDirectives {
+	#[must_use]
+	pub fn parse(headers: &HeaderMap) -> Self {
+		let mut out = Self::default();
+		let dirs = directives(headers);
+		if dirs.is_empty() {
+			out.no_cache = headers
+				.get(PRAGMA)
+				.and_then(|v| v.to_str().ok())
+				.is_some_and(|v| v.to_ascii_lowercase().contains("no-cache"));
+		}
+		for (name, arg) in &dirs {
+			match name.as_str() {
+				"no-store" => out.no_store = true,
+				"no-cache" => out.no_cache = true,
+				"max-age" => out.max_age = seconds(arg.as_ref()),
+				"min-fresh" => out.min_fresh = seconds(arg.as_ref()),
+				_ => {}
+			}
+		}
+		out
+	}
+}
+
+/// How a stored response may be used for this request right now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Freshness {
+	Fresh,
+	/// Stale, but inside `stale-while-revalidate`: serve it and
+	/// refresh in the background.
+	StaleWhileRevalidate,
+	/// Must be revalidated before use.
+	Stale,
+}
+
+/// One stored response.
+pub struct Entry {
+	pub status: StatusCode,
+	pub headers: HeaderMap,
+	pub body: Bytes,
+	/// Request values of the response's `Vary` headers at store time.
+	vary: Vec<(HeaderName, Option<HeaderValue>)>,
+	stored_at: Instant,
+	/// `Age` the response already had when it arrived.
+	initial_age: Duration,
+	lifetime: Duration,
+	stale_while_revalidate: Duration,
+	stale_if_error: Duration,
+	/// `must-revalidate` / `proxy-revalidate` / `no-cache`: never
+	/// served stale, `no-cache` never served without revalidation.
+	must_revalidate: bool,
+	no_cache: bool,
+	hits: AtomicU64,
+}
+
+impl Entry {
+	fn bytes(&self, key: &str) -> usize {
+		let headers: usize = self.headers.iter().map(|(k, v)| k.as_str().len() + v.len() + 4).sum();
+		key.len() + headers + self.body.len()
+	}
+
+	fn age(&self, now: Instant) -> Duration {
+		self.initial_age + now.saturating_duration_since(self.stored_at)
+	}
+
+	/// Current `Age` value in whole seconds.
+	#[must_use]
+	pub fn age_secs(&self) -> u64 {
+		self.age(Instant::now()).as_secs()
+	}
+
+	#[must_use]
+	pub fn state(&self, now: Instant, req: &RequestDirectives) -> Freshness {
+		let age = self.age(now);
+		let mut lifetime = self.lifetime;
+		if let Some(max_age) = req.max_age {
+			lifetime = lifetime.min(max_age);
+		}
+		let needed = age + req.min_fresh.unwrap_or_default();
+		if !self.no_cache && !req.no_cache && needed < lifetime {
+			return Freshness::Fresh;
+		}
+		if !self.must_revalidate && !req.no_cache && age < self.lifetime + self.stale_while_revalidate {
+			return Freshness::StaleWhileRevalidate;
+		}
+		Freshness::Stale
+	}
+
+	/// Whether a failed revalidation may fall back to this entry.
+	#[must_use]
+	pub fn usable_on_error(&self, now: Instant) -> bool {
+		!self.must_revalidate && self.age(now) < self.lifetime + self.stale_if_error
+	}
+
+	/// Validators for a conditional revalidation request.
+	#[must_use]
+	pub fn validators(&self) -> (Option<&HeaderValue>, Option<&HeaderValue>) {
+		(self.headers.get(ETAG), self.headers.get(LAST_MODIFIED))
+	}
+
+	fn matches(&self, req: &HeaderMap) -> bool {
+		self.vary.iter().all(|(name, stored)| {
+			let now = joined(req, name);
+			now.as_ref() == stored.as_ref()
+		})
+	}
+
+	pub(crate) fn record_hit(&self) {
+		self.hits.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+fn joined(headers: &HeaderMap, name: &HeaderName) -> Option<HeaderValue> {
+	let values: Vec<&[u8]> = headers.get_all(name).iter().map(HeaderValue::as_bytes).collect();
+	if values.is_empty() {
+		return None;
+	}
+	HeaderValue::from_bytes(&values.join(&b", "[..])).ok()
+}
+
+fn vary_names(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+	let mut out = Vec::new();
+	for v in headers.get_all(VARY).iter().filter_map(|v| v.to_str().ok()) {
+		for name in v.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+			if name == "*" {
+				return None;
+			}
+			out.push(HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes()).ok()?);
+		}
+	}
+	Some(out)
+}
+
+/// A response head that passed [`storable`], waiting for its body.
+pub struct Pending {
+	entry: Entry,
+}
+
+/// Decide whether a response to `req` may be stored, and with what
+/// lifetime. `None` when it must not be stored.
+#[must_use]
+pub fn storable(
+	spec: &CacheSpec,
+	req: &http::request::Parts,
+	status: StatusCode,
+	headers: &HeaderMap,
+) -> Option<Pending> {
+	if req.method != Method::GET {
+		return None;
+	}
+	policy(spec, &req.headers, status, headers).map(|entry| Pending { entry })
+}
+
+/// The method-independent half of [`storable`]; also applied to the
+/// merged headers after a `304`.
+fn policy(
+	spec: &CacheSpec,
+	req: &HeaderMap,
+	status: StatusCode,
+	headers: &HeaderMap,
+) -> Option<Entry> {
+	if headers.contains_key(SET_COOKIE)
+		|| status.is_informational()
+		|| matches!(status, StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED)
+	{
+		return None;
+	}
+	let dirs = directives(headers);
+	let has = |n: &str| dirs.iter().any(|(k, _)| k == n);
+	let arg = |n: &str| dirs.iter().find(|(k, _)| k == n).and_then(|(_, v)| v.as_ref());
+	if has("no-store") || has("private") {
+		return None;
+	}
+	let vary = vary_names(headers)?;
+	if let Some(len) =
+		headers.get(CONTENT_LENGTH).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<usize>().ok())
+		&& len > spec.max_object_bytes
+	{
+		return None;
+	}
+
+	let no_cache = has("no-cache");
+	let lifetime = seconds(arg("s-maxage"))
+		.or_else(|| seconds(arg("max-age")))
+		.or_else(|| expires_lifetime(headers))
+		.or_else(|| {
+			HEURISTIC_STATUSES.contains(&status.as_u16()).then(|| heuristic_lifetime(headers)).flatten()
+		})
+		.or(spec.default_ttl);
+	let lifetime = match lifetime {
+		Some(l) => l,
+		// Stored only to be revalidated; useless without a validator.
+		None if no_cache && (headers.contains_key(ETAG) || headers.contains_key(LAST_MODIFIED)) => {
+			Duration::ZERO
+		}
+		None => return None,
+	};
+	let initial_age = headers
+		.get(AGE)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.parse::<u64>().ok())
+		.map(Duration::from_secs)
+		.unwrap_or_default();
+
+	let mut stored = headers.clone();
+	stored.remove(AGE);
+	Some(Entry {
+		status,
+		headers: stored,
+		body: Bytes::new(),
+		vary: vary.iter().map(|n| (n.clone(), joined(req, n))).collect(),
+		stored_at: Instant::now(),
+		initial_age,
+		lifetime,
+		stale_while_revalidate: seconds(arg("stale-while-revalidate"))
+			.unwrap_or(spec.stale_while_revalidate),
+		stale_if_error: seconds(arg("stale-if-error")).unwrap_or(spec.stale_if_error),
+		must_revalidate: has("must-revalidate") || has("proxy-revalidate"),
+		no_cache,
+		hits: AtomicU64::new(0),
+	})
+}
+
+fn http_date(headers: &HeaderMap, name: &HeaderName) -> Option<SystemTime> {
+	headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+/// `Expires - Date`. An unparsable `Expires` means already expired.
+fn expires_lifetime(headers: &HeaderMap) -> Option<Duration> {
+	let raw = headers.get(EXPIRES)?;
+	let Some(expires) = raw.to_str().ok().and_then(|v| httpdate::parse_http_date(v).ok()) else {
+		return Some(Duration::ZERO);
+	};
+	let date = http_date(headers, &DATE).unwrap_or_else(SystemTime::now);
+	Some(expires.duration_since(date).unwrap_or_default())
+}
+
+/// 10% of the time since `Last-Modified`, capped at a day.
+fn heuristic_lifetime(headers: &HeaderMap) -> Option<Duration> {
+	let modified = http_date(headers, &LAST_MODIFIED)?;
+	let date = http_date(headers, &DATE).unwrap_or_else(SystemTime::now);
+	Some((date.duration_since(modified).unwrap_or_default() / 10).min(HEURISTIC_CAP))
+}
+
+struct Slot {
+	variants: Vec<Arc<Entry>>,
+	bytes: usize,
+	tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+	map: HashMap<String, Slot>,
+	/// `tick -> key`, oldest first.
+	order: BTreeMap<u64, String>,
+	next_tick: u64,
+	bytes: usize,
+}
+
+impl Lru {
+	fn touch(&mut self, key: &str) {
+		let tick = self.next_tick;
+		if let Some(slot) = self.map.get_mut(key) {
+			self.order.remove(&slot.tick);
+			slot.tick = tick;
+			self.order.insert(tick, key.to_owned());
+			self.next_tick += 1;
+		}
+	}
+
+	fn remove(&mut self, key: &str) -> usize {
+		let Some(slot) = self.map.remove(key) else { return 0 };
+		self.order.remove(&slot.tick);
+		self.bytes -= slot.bytes;
+		slot.variants.len()
+	}
+}
+
+/// Byte-bounded LRU over keys. Every variant of a key is evicted
+/// together.
+pub struct Store {
+	capacity: usize,
+	lru: Mutex<Lru>,
+}
+
+impl Store {
+	#[must_use]
+	pub fn new(capacity: usize) -> Self {
+		Self { capacity, lru: Mutex::new(Lru::default()) }
+	}
+
+	/// The variant of `key` whose `Vary` values match `req`.
+	#[must_use]
+	pub fn lookup(&self, key: &str, req: &HeaderMap) -> Option<Arc<Entry>> {
+		let mut lru = self.lru.lock();
+		let found = lru.map.get(key)?.variants.iter().find(|e| e.matches(req)).cloned()?;
+		lru.touch(key);
+		Some(found)
+	}
+
+	/// Store `entry` under `key`, replacing the variant with the same
+	/// `Vary` values, then evict least-recently-used keys until the
+	/// store fits. An entry larger than the whole store is dropped.
+	pub fn insert(&self, key: &str, entry: Arc<Entry>) {
+		let size = entry.bytes(key);
+		if size > self.capacity {
+			return;
+		}
+		let mut lru = self.lru.lock();
+		let tick = lru.next_tick;
+		lru.next_tick += 1;
+		let mut freed = 0;
+		if let Some(slot) = lru.map.get_mut(key) {
+			slot.variants.retain(|v| {
+				let same = v.vary == entry.vary;
+				if same {
+					freed += v.bytes(key);
+				}
+				!same
+			});
+			slot.variants.push(entry);
+			slot.bytes = slot.bytes - freed + size;
+			let old_tick = std::mem::replace(&mut slot.tick, tick);
+			lru.order.remove(&old_tick);
+		} else {
+			lru.map.insert(key.to_owned(), Slot { variants: vec![entry], bytes: size, tick });
+		}
+		lru.order.insert(tick, key.to_owned());
+		lru.bytes = lru.bytes - freed + size;
+		let mut evicted = 0_u64;
+		while lru.bytes > self.capacity {
+			let Some((_, victim)) = lru.order.pop_first() else { break };
+			if let Some(slot) = lru.map.remove(&victim) {
+				lru.bytes -= slot.bytes;
+				evicted += 1;
+			}
+		}
+		if evicted > 0 {
+			metrics::counter!("vane.cache.evictions_total").increment(evicted);
+		}
+		publish_bytes(lru.bytes);
+	}
+
+	/// Drop every variant of `key`. Returns the number removed.
+	pub fn purge_key(&self, key: &str) -> usize {
+		let mut lru = self.lru.lock();
+		let n = lru.remove(key);
+		publish_bytes(lru.bytes);
+		n
+	}
+
+	/// Drop every key starting with `prefix`. Returns the number of
+	/// variants removed.
+	pub fn purge_prefix(&self, prefix: &str) -> usize {
+		let mut lru = self.lru.lock();
+		let keys: Vec<String> = lru.map.keys().filter(|k| k.starts_with(prefix)).cloned().collect();
+		let n = keys.iter().map(|k| lru.remove(k)).sum();
+		publish_bytes(lru.bytes);
+		n
+	}
+
+	/// Keys (optionally under `prefix`), sorted, at most `limit`.
+	#[must_use]
+	pub fn snapshot(&self, prefix: Option<&str>, limit: usize) -> CacheSnapshot {
+		let now = Instant::now();
+		let lru = self.lru.lock();
+		let mut entries: Vec<CacheEntrySummary> = lru
+			.map
+			.iter()
+			.filter(|(k, _)| prefix.is_none_or(|p| k.starts_with(p)))
+			.map(|(k, slot)| {
+				// Slots are never left empty.
+				let newest = &slot.variants[slot.variants.len() - 1];
+				let age = newest.age(now);
+				CacheEntrySummary {
+					key: k.clone(),
+					status: newest.status.as_u16(),
+					variants: slot.variants.len(),
+					bytes: slot.bytes,
+					age_secs: age.as_secs(),
+					ttl_secs: i64::try_from(newest.lifetime.as_secs()).unwrap_or(i64::MAX)
+						- i64::try_from(age.as_secs()).unwrap_or(i64::MAX),
+					hits: slot.variants.iter().map(|e| e.hits.load(Ordering::Relaxed)).sum(),
+				}
+			})
+			.collect();
+		let total_keys = entries.len();
+		entries.sort_by(|a, b| a.key.cmp(&b.key));
+		entries.truncate(limit);
+		CacheSnapshot {
+			capacity_bytes: self.capacity,
+			used_bytes: lru.bytes,
+			keys: lru.map.len(),
+			matched_keys: total_keys,
+			entries,
+		}
+	}
+}
+
+/// Read-only view of the store, surfaced via `get_cache`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheSnapshot {
+	pub capacity_bytes: usize,
+	pub used_bytes: usize,
+	pub keys: usize,
+	/// Keys under the requested prefix, before `limit`.
+	pub matched_keys: usize,
+	pub entries: Vec<CacheEntrySummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntrySummary {
+	pub key: String,
+	/// Status of the most recently stored variant.
+	pub status: u16,
+	pub variants: usize,
+	pub bytes: usize,
+	pub age_secs: u64,
+	/// Seconds of freshness left on the newest variant; negative once
+	/// stale.
+	pub ttl_secs: i64,
+	pub hits: u64,
+}
+
+#[expect(clippy::cast_precision_loss, reason = "gauge is approximate above 2^52 bytes")]
+fn publish_bytes(bytes: usize) {
+	metrics::gauge!("vane.cache.bytes").set(bytes as f64);
+}
+
+static STORE: LazyLock<Store> = LazyLock::new(|| {
+	let capacity = std::env::var("VANE_CACHE_MAX_BYTES")
+		.ok()
+		.and_then(|s| s.parse::<usize>().ok())
+		.filter(|n| *n > 0)
+		.unwrap_or(DEFAULT_CAPACITY_BYTES);
+	Store::new(capacity)
+});
+
+/// The daemon-wide store.
+#[must_use]
+pub fn store() -> &'static Store {
+	&STORE
+}
+
+/// Fold a `304 Not Modified` into a stored entry (RFC 9111 §4.3.4):
+/// its headers replace the stored ones of the same name and the
+/// freshness clock restarts. The refreshed entry replaces `old` in
+/// the store and is returned; if the merged headers are no longer
+/// storable the key is purged and the merged response is returned
+/// for this one request only.
+#[must_use]
+pub fn refresh(
+	spec: &CacheSpec,
+	key: &str,
+	req: &HeaderMap,
+	old: &Entry,
+	not_modified: &HeaderMap,
+) -> Arc<Entry> {
+	let mut headers = old.headers.clone();
+	for name in not_modified.keys() {
+		if name == CONTENT_LENGTH {
+			continue;
+		}
+		headers.remove(name);
+		for v in not_modified.get_all(name) {
+			headers.append(name.clone(), v.clone());
+		}
+	}
+	let Some(mut entry) = policy(spec, req, old.status, &headers) else {
+		store().purge_key(key);
+		return Arc::new(Entry {
+			status: old.status,
+			headers,
+			body: old.body.clone(),
+			vary: old.vary.clone(),
+			stored_at: Instant::now(),
+			initial_age: Duration::ZERO,
+			lifetime: Duration::ZERO,
+			stale_while_revalidate: Duration::ZERO,
+			stale_if_error: Duration::ZERO,
+			must_revalidate: true,
+			no_cache: true,
+			hits: AtomicU64::new(0),
+		});
+	};
+	entry.body = old.body.clone();
+	entry.hits = AtomicU64::new(old.hits.load(Ordering::Relaxed));
+	let entry = Arc::new(entry);
+	store().insert(key, Arc::clone(&entry));
+	entry
+}
+
+static IN_FLIGHT: LazyLock<DashMap<String, watch::Receiver<()>>> = LazyLock::new(DashMap::new);
+
+/// Outcome of joining the fill for a key.
+pub enum Flight {
+	/// This request fetches and fills; dropping the guard releases the
+	/// followers.
+	Leader(FillGuard),
+	/// Another request is filling; `wait` on this.
+	Follower(watch::Receiver<()>),
+}
+
+/// Become the leader for `key`, or follow the current one.
+#[must_use]
+pub fn join(key: &str) -> Flight {
+	match IN_FLIGHT.entry(key.to_owned()) {
+		MapEntry::Occupied(e) => Flight::Follower(e.get().clone()),
+		MapEntry::Vacant(e) => {
+			let (tx, rx) = watch::channel(());
+			e.insert(rx);
+			Flight::Leader(FillGuard { key: key.to_owned(), _tx: tx })
+		}
+	}
+}
+
+/// Wait until the leader's fill ends, or `COALESCE_WAIT` passes.
+pub async fn wait(mut rx: watch::Receiver<()>) {
+	// The leader never sends; `changed` resolves with an error once
+	// its guard (and with it the sender) is dropped.
+	let _ = tokio::time::timeout(COALESCE_WAIT, rx.changed()).await;
+}
+
+/// Held by the leader of a fill until the response body has been
+/// stored (or abandoned).
+pub struct FillGuard {
+	key: String,
+	_tx: watch::Sender<()>,
+}
+
+impl Drop for FillGuard {
+	fn drop(&mut self) {
+		IN_FLIGHT.remove(&self.key);
+	}
+}
+
+pin_project! {
+	/// Streams the upstream body to the client while copying it aside;
+	/// at end of stream the copy is stored. A body that outgrows
+	/// `max_object_bytes`, errors, or is dropped early stores nothing.
+	pub struct FillBody {
+		#[pin]
+		inner: Body,
+		buf: BytesMut,
+		limit: usize,
+		key: String,
+		pending: Option<Pending>,
+		guard: Option<FillGuard>,
+	}
+}
+
+impl FillBody {
+	#[must_use]
+	pub fn new(
+		inner: Body,
+		key: String,
+		pending: Pending,
+		limit: usize,
+		guard: Option<FillGuard>,
+	) -> Self {
+		// An empty body may never be polled at all.
+		if inner.is_end_stream() {
+			complete(&key, Some(pending), BytesMut::new());
+			return Self { inner, buf: BytesMut::new(), limit, key, pending: None, guard: None };
+		}
+		Self { inner, buf: BytesMut::new(), limit, key, pending: Some(pending), guard }
+	}
+}
+
+fn complete(key: &str, pending: Option<Pending>, body: BytesMut) {
+	if let Some(mut pending) = pending {
+		pending.entry.body = body.freeze();
+		pending.entry.stored_at = Instant::now();
+		store().insert(key, Arc::new(pending.entry));
+	}
+}
+
+impl HttpBody for FillBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+		let mut this = self.project();
+		let polled = this.inner.as_mut().poll_frame(cx);
+		match &polled {
+			Poll::Ready(Some(Ok(frame))) => {
+				if let Some(data) = frame.data_ref() {
+					if this.buf.len() + data.len() > *this.limit {
+						*this.pending = None;
+						this.buf.clear();
+					} else if this.pending.is_some() {
+						this.buf.extend_from_slice(data);
+					}
+				}
+				// hyper stops polling once the body reports its end, so
+				// the last data frame may be the last call we get.
+				if this.inner.is_end_stream() {
+					complete(this.key, this.pending.take(), std::mem::take(this.buf));
+					*this.guard = None;
+				}
+			}
+			Poll::Ready(Some(Err(_))) => {
+				*this.pending = None;
+				*this.guard = None;
+			}
+			Poll::Ready(None) => {
+				complete(this.key, this.pending.take(), std::mem::take(this.buf));
+				*this.guard = None;
+			}
+			Poll::Pending => {}
+		}
+		polled
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn spec() -> CacheSpec {
+		parse(Some(&serde_json::json!({}))).expect("parse").expect("some")
+	}
+
+	fn get(uri: &str, headers: &[(&str, &str)]) -> http::request::Parts {
+		let mut b = http::Request::builder().uri(uri);
+		for (k, v) in headers {
+			b = b.header(*k, *v);
+		}
+		b.body(()).expect("request").into_parts().0
+	}
+
+	fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+		let mut h = HeaderMap::new();
+		for (k, v) in pairs {
+			h.append(
+				HeaderName::from_bytes(k.as_bytes()).expect("name"),
+				HeaderValue::from_str(v).expect("value"),
+			);
+		}
+		h
+	}
+
+	fn entry(
+		spec: &CacheSpec,
+		req: &http::request::Parts,
+		resp: &[(&str, &str)],
+		body: &str,
+	) -> Arc<Entry> {
+		let mut e = storable(spec, req, StatusCode::OK, &headers(resp)).expect("storable").entry;
+		e.body = Bytes::copy_from_slice(body.as_bytes());
+		Arc::new(e)
+	}
+
+	#[test]
+	fn parse_accepts_defaults_and_rejects_typos() {
+		assert_eq!(parse(None).expect("absent"), None);
+		let s = spec();
+		assert_eq!(s.query, QueryKey::All);
+		assert_eq!(s.max_object_bytes, DEFAULT_MAX_OBJECT_BYTES);
+		assert!(s.default_ttl.is_none());
+
+		let s = parse(Some(&serde_json::json!({
+			"key": { "query": ["page", "sort"], "headers": ["Accept-Language"], "cookies": ["lang"] },
+			"default_ttl": "1m",
+			"stale_if_error": "5m",
+		})))
+		.expect("parse")
+		.expect("some");
+		assert_eq!(s.query, QueryKey::Only(vec!["page".into(), "sort".into()]));
+		assert_eq!(s.headers, vec![HeaderName::from_static("accept-language")]);
+		assert_eq!(s.default_ttl, Some(Duration::from_mins(1)));
+
+		for (bad, needle) in [
+			(serde_json::json!({ "ttl": "1m" }), "unknown field"),
+			(serde_json::json!({ "key": { "path": true } }), "key: unknown field"),
+			(serde_json::json!({ "key": { "query": "yes" } }), "key.query"),
+			(serde_json::json!({ "max_object_bytes": 0 }), "max_object_bytes"),
+			(serde_json::json!({ "stale_if_error": 5 }), "stale_if_error"),
+		] {
+			let err = parse(Some(&bad)).expect_err("must reject");
+			assert!(err.contains(needle), "{bad}: {err}");
+		}
+	}
+
+	#[test]
+	fn key_derivation_follows_spec() {
+		let mut s = spec();
+		let req = get(
+			"http://Example.COM/a/b?z=1&page=2&sort=x",
+			&[("accept-language", "en"), ("cookie", "sid=9; lang=fr")],
+		);
+		assert_eq!(s.key(&req.uri, &req.headers), "example.com/a/b?z=1&page=2&sort=x");
+		s.query = QueryKey::None;
+		assert_eq!(s.key(&req.uri, &req.headers), "example.com/a/b");
+		s.query = QueryKey::Only(vec!["sort".into(), "page".into()]);
+		s.headers = vec![HeaderName::from_static("accept-language")];
+		s.cookies = vec!["lang".into()];
+		assert_eq!(
+			s.key(&req.uri, &req.headers),
+			"example.com/a/b?page=2&sort=x|h:accept-language=en|c:lang=fr"
+		);
+
+		// Origin-form request: the host comes from `Host`.
+		let req = get("/x", &[("host", "site.test")]);
+		assert_eq!(spec().key(&req.uri, &req.headers), "site.test/x");
+	}
+
+	#[test]
+	fn storable_honours_response_directives() {
+		let s = spec();
+		let req = get("/", &[]);
+		let ok = |h: &[(&str, &str)]| storable(&s, &req, StatusCode::OK, &headers(h)).is_some();
+		assert!(ok(&[("cache-control", "max-age=60")]));
+		assert!(ok(&[("cache-control", "public, s-maxage=60")]));
+		assert!(ok(&[("last-modified", "Sun, 06 Nov 1994 08:49:37 GMT")]));
+		assert!(!ok(&[]), "no freshness and no default_ttl");
+		assert!(!ok(&[("cache-control", "no-store, max-age=60")]));
+		assert!(!ok(&[("cache-control", "private, max-age=60")]));
+		assert!(!ok(&[("cache-control", "max-age=60"), ("set-cookie", "a=b")]));
+		assert!(!ok(&[("cache-control", "max-age=60"), ("vary", "*")]));
+		assert!(!ok(&[("cache-control", "max-age=60"), ("content-length", "99999999")]));
+		assert!(ok(&[("cache-control", "no-cache"), ("etag", "\"v\"")]));
+
+		let post = http::Request::post("/").body(()).expect("req").into_parts().0;
+		assert!(
+			storable(&s, &post, StatusCode::OK, &headers(&[("cache-control", "max-age=60")])).is_none()
+		);
+
+		// `s-maxage` wins over `max-age`; `Age` eats into the lifetime.
+		let e = entry(&s, &req, &[("cache-control", "max-age=5, s-maxage=100"), ("age", "90")], "");
+		let none = RequestDirectives::default();
+		assert_eq!(e.state(Instant::now(), &none), Freshness::Fresh);
+		let e = entry(&s, &req, &[("cache-control", "max-age=100"), ("age", "100")], "");
+		assert_eq!(e.state(Instant::now(), &none), Freshness::Stale);
+	}
+
+	#[test]
+	fn freshness_states_and_request_directives() {
+		let s = spec();
+		let req = get("/", &[]);
+		let none = RequestDirectives::default();
+		let e = entry(&s, &req, &[("cache-control", "max-age=10, stale-while-revalidate=30")], "");
+		let t0 = Instant::now();
+		assert_eq!(e.state(t0, &none), Freshness::Fresh);
+		assert_eq!(e.state(t0 + Duration::from_secs(20), &none), Freshness::StaleWhileRevalidate);
+		assert_eq!(e.state(t0 + Duration::from_mins(1), &none), Freshness::Stale);
+
+		let no_cache = RequestDirectives::parse(&headers(&[("cache-control", "no-cache")]));
+		assert_eq!(e.state(t0, &no_cache), Freshness::Stale);
+		let pragma = RequestDirectives::parse(&headers(&[("pragma", "no-cache")]));
+		assert!(pragma.no_cache);
+		let min_fresh = RequestDirectives::parse(&headers(&[("cache-control", "min-fresh=20")]));
+		assert_ne!(e.state(t0, &min_fresh), Freshness::Fresh);
+
+		let strict = entry(&s, &req, &[("cache-control", "max-age=10, must-revalidate")], "");
+		assert_eq!(strict.state(t0 + Duration::from_secs(11), &none), Freshness::Stale);
+		assert!(!strict.usable_on_error(t0 + Duration::from_secs(11)));
+
+		let mut lenient = s.clone();
+		lenient.stale_if_error = Duration::from_mins(5);
+		let e = entry(&lenient, &req, &[("cache-control", "max-age=10")], "");
+		assert!(e.usable_on_error(t0 + Duration::from_secs(100)));
+		assert!(!e.usable_on_error(t0 + Duration::from_secs(400)));
+	}
+
+	#[test]
+	fn vary_selects_variant() {
+		let s = spec();
+		let store = Store::new(1 << 20);
+		let en = get("/", &[("accept-language", "en")]);
+		let fr = get("/", &[("accept-language", "fr")]);
+		let resp = [("cache-control", "max-age=60"), ("vary", "Accept-Language")];
+		store.insert("k", entry(&s, &en, &resp, "hello"));
+		store.insert("k", entry(&s, &fr, &resp, "bonjour"));
+		assert_eq!(store.lookup("k", &en.headers).expect("en").body, "hello");
+		assert_eq!(store.lookup("k", &fr.headers).expect("fr").body, "bonjour");
+		assert!(store.lookup("k", &get("/", &[]).headers).is_none());
+
+		// Same variant again replaces, not appends.
+		store.insert("k", entry(&s, &en, &resp, "hi"));
+		assert_eq!(store.snapshot(None, 10).entries[0].variants, 2);
+	}
+
+	#[test]
+	fn lru_evicts_oldest_and_purges_by_prefix() {
+		let s = spec();
+		let req = get("/", &[]);
+		let resp = [("cache-control", "max-age=60")];
+		let size = entry(&s, &req, &resp, &"x".repeat(1000)).bytes("site/a");
+		let store = Store::new(size * 3);
+		for k in ["site/a", "site/b", "site/c"] {
+			store.insert(k, entry(&s, &req, &resp, &"x".repeat(1000)));
+		}
+		// Touch `a` so `b` is the oldest, then overflow.
+		assert!(store.lookup("site/a", &req.headers).is_some());
+		store.insert("site/d", entry(&s, &req, &resp, &"x".repeat(1000)));
+		assert!(store.lookup("site/b", &req.headers).is_none());
+		assert!(store.lookup("site/a", &req.headers).is_some());
+
+		assert_eq!(store.purge_prefix("site/"), 3);
+		assert_eq!(store.snapshot(None, 10).used_bytes, 0);
+
+		// Bigger than the store: not stored at all.
+		store.insert("big", entry(&s, &req, &resp, &"x".repeat(size * 4)));
+		assert_eq!(store.snapshot(None, 10).keys, 0);
+	}
+
+	#[tokio::test]
+	async fn second_joiner_follows_until_leader_drops() {
+		let Flight::Leader(guard) = join("coalesce-test") else { panic!("first is leader") };
+		let Flight::Follower(rx) = join("coalesce-test") else { panic!("second follows") };
+		let waiter = tokio::spawn(wait(rx));
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(!waiter.is_finished());
+		drop(guard);
+		tokio::time::timeout(Duration::from_secs(1), waiter)
+			.await
+			.expect("follower released")
+			.expect("join");
+		assert!(matches!(join("coalesce-test"), Flight::Leader(_)));
+	}
+}
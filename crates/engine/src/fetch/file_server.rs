@@ -0,0 +1,948 @@
+//! `FileServerFetch` — serve a document root from local disk.
+//!
+//! Resolution follows nginx's `try_files`: each candidate is the request
+//! path templated into `$uri`, a trailing `/` asks for the directory's
+//! index file, and a final `=NNN` answers with a bare status. Every
+//! candidate is canonicalised and must stay under the canonical root, so
+//! neither `..` segments nor a symlink pointing outside the root can
+//! reach other files.
+//!
+//! Responses carry `ETag` / `Last-Modified`, honour the RFC 9110
+//! preconditions and a single `Range` (with `If-Range`), and stream the
+//! file through `Body::Stream`. With `precompressed` on, a `.br` /
+//! `.zst` / `.gz` sibling is served in place of the original when the
+//! client accepts that coding.
+//!
+//! See `spec/crates/engine.md` § _File server_.
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use http::header::{
+	ACCEPT_ENCODING, ACCEPT_RANGES, ALLOW, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH,
+	CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE,
+	IF_UNMODIFIED_SINCE, LAST_MODIFIED, LOCATION, RANGE, VARY,
+};
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, AsyncSeekExt, Take};
+use vane_core::{Body, ConnContext, Error, FetchKind, FlowCtx, L7Fetch, L7FetchOutput, Request};
+
+use crate::factories::{FactoryError, FetchFactories};
+use crate::flow_graph::FetchInst;
+
+/// Read granularity for the streamed body.
+const CHUNK: usize = 64 * 1024;
+
+/// Precompressed siblings in preference order: `(content-coding, suffix)`.
+const PRECOMPRESSED: [(&str, &str); 3] = [("br", "br"), ("zstd", "zst"), ("gzip", "gz")];
+
+/// One `try_files` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TryFile {
+	/// Path template; `$uri` is replaced by the decoded request path.
+	/// A trailing `/` means "the index file of this directory".
+	Path(String),
+	/// `=NNN` — stop and answer with this status.
+	Status(StatusCode),
+}
+
+pub struct FileServerFetch {
+	/// Canonical document root. Every served path must start with it.
+	root: PathBuf,
+	index: Vec<String>,
+	try_files: Vec<TryFile>,
+	precompressed: bool,
+	cache_control: Option<HeaderValue>,
+}
+
+/// A file picked by `try_files`, before content negotiation.
+struct Resolved {
+	path: PathBuf,
+	/// `$uri/` hit on a request path without the trailing slash —
+	/// answered with a redirect so relative links resolve.
+	needs_slash: bool,
+}
+
+enum Lookup {
+	File(Resolved),
+	Status(StatusCode),
+	NotFound,
+}
+
+#[async_trait]
+impl L7Fetch for FileServerFetch {
+	async fn fetch(
+		&self,
+		req: Request,
+		_conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<L7FetchOutput, Error> {
+		// Only the head matters; dropping the body up front also keeps
+		// the future `Send` (`Body` is not `Sync`).
+		let (parts, _body) = req.into_parts();
+		let resp = self.serve(&parts).await?;
+		Ok(L7FetchOutput::Response(resp))
+	}
+}
+
+impl FileServerFetch {
+	async fn serve(&self, req: &http::request::Parts) -> Result<http::Response<Body>, Error> {
+		let method = &req.method;
+		if method != Method::GET && method != Method::HEAD {
+			return build(
+				http::Response::builder().status(StatusCode::METHOD_NOT_ALLOWED).header(ALLOW, "GET, HEAD"),
+				Body::Empty,
+			);
+		}
+		let Some(uri_path) = decode_path(req.uri.path()) else {
+			return status_only(StatusCode::BAD_REQUEST);
+		};
+
+		let resolved = match self.lookup(&uri_path).await? {
+			Lookup::File(r) => r,
+			Lookup::Status(s) => return status_only(s),
+			Lookup::NotFound => return status_only(StatusCode::NOT_FOUND),
+		};
+		if resolved.needs_slash {
+			return slash_redirect(&req.uri);
+		}
+
+		let content_type = mime_for(&resolved.path);
+		let (path, coding) = if self.precompressed {
+			self.pick_precompressed(&resolved.path, &req.headers).await
+		} else {
+			(resolved.path, None)
+		};
+		let meta = match tokio::fs::metadata(&path).await {
+			Ok(m) => m,
+			Err(e) => return io_status(e),
+		};
+		let len = meta.len();
+		let mtime = meta.modified().ok().map(truncate_to_secs);
+		let etag = etag_for(len, mtime, coding);
+
+		let mut head = http::Response::builder()
+			.header(ETAG, &etag)
+			.header(ACCEPT_RANGES, "bytes")
+			.header(CONTENT_TYPE, content_type);
+		if let Some(m) = mtime {
+			head = head.header(LAST_MODIFIED, httpdate::fmt_http_date(m));
+		}
+		if let Some(c) = coding {
+			head = head.header(CONTENT_ENCODING, c);
+		}
+		if self.precompressed {
+			head = head.header(VARY, "accept-encoding");
+		}
+		if let Some(cc) = &self.cache_control {
+			head = head.header(CACHE_CONTROL, cc);
+		}
+
+		match preconditions(&req.headers, &etag, mtime) {
+			Precondition::Proceed => {}
+			Precondition::NotModified => {
+				return build(head.status(StatusCode::NOT_MODIFIED), Body::Empty);
+			}
+			Precondition::Failed => {
+				return build(head.status(StatusCode::PRECONDITION_FAILED), Body::Empty);
+			}
+		}
+
+		let range = if method == Method::GET && if_range_holds(&req.headers, &etag, mtime) {
+			req.headers.get(RANGE).and_then(|v| v.to_str().ok()).map(|v| parse_range(v, len))
+		} else {
+			None
+		};
+		let (status, start, count) = match range {
+			None | Some(RangeSpec::Ignore) => (StatusCode::OK, 0, len),
+			Some(RangeSpec::Unsatisfiable) => {
+				return build(
+					head
+						.status(StatusCode::RANGE_NOT_SATISFIABLE)
+						.header(CONTENT_RANGE, format!("bytes */{len}")),
+					Body::Empty,
+				);
+			}
+			Some(RangeSpec::Bytes { start, end }) => {
+				head = head.header(CONTENT_RANGE, format!("bytes {start}-{end}/{len}"));
+				(StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+			}
+		};
+		head = head.status(status).header(CONTENT_LENGTH, count);
+
+		if method == Method::HEAD || count == 0 {
+			return build(head, Body::Empty);
+		}
+		let mut file = match tokio::fs::File::open(&path).await {
+			Ok(f) => f,
+			Err(e) => return io_status(e),
+		};
+		if start > 0 {
+			file
+				.seek(SeekFrom::Start(start))
+				.await
+				.map_err(|e| Error::from(e).with_ctx(format!("file_server seek {}", path.display())))?;
+		}
+		build(head, Body::from_producer(FileBody::new(tokio::io::AsyncReadExt::take(file, count))))
+	}
+
+	/// Walk `try_files` until a candidate resolves to a regular file
+	/// inside the root.
+	async fn lookup(&self, uri_path: &str) -> Result<Lookup, Error> {
+		for entry in &self.try_files {
+			let template = match entry {
+				TryFile::Status(s) => return Ok(Lookup::Status(*s)),
+				TryFile::Path(t) => t.replace("$uri", uri_path),
+			};
+			let Some(rel) = relative_segments(&template) else {
+				return Ok(Lookup::Status(StatusCode::BAD_REQUEST));
+			};
+			let candidate = rel.iter().fold(self.root.clone(), |p, s| p.join(s));
+			let Some(canon) = self.contain(&candidate).await else {
+				continue;
+			};
+			let Ok(meta) = tokio::fs::metadata(&canon).await else {
+				continue;
+			};
+			if template.ends_with('/') {
+				if !meta.is_dir() {
+					continue;
+				}
+				if let Some(index) = self.index_in(&canon).await {
+					// Only the request's own directory needs the slash
+					// redirect; a literal fallback like `/app/` does not.
+					let needs_slash = entry == &TryFile::Path("$uri/".to_owned()) && !uri_path.ends_with('/');
+					return Ok(Lookup::File(Resolved { path: index, needs_slash }));
+				}
+			} else if meta.is_file() {
+				return Ok(Lookup::File(Resolved { path: canon, needs_slash: false }));
+			}
+		}
+		Ok(Lookup::NotFound)
+	}
+
+	async fn index_in(&self, dir: &Path) -> Option<PathBuf> {
+		for name in &self.index {
+			if let Some(p) = self.contain(&dir.join(name)).await
+				&& tokio::fs::metadata(&p).await.is_ok_and(|m| m.is_file())
+			{
+				return Some(p);
+			}
+		}
+		None
+	}
+
+	/// Canonicalise `path` and keep it only if it stays under the root.
+	/// This is the symlink-escape guard: a link is followed, then the
+	/// target is checked.
+	async fn contain(&self, path: &Path) -> Option<PathBuf> {
+		let canon = tokio::fs::canonicalize(path).await.ok()?;
+		if canon.starts_with(&self.root) {
+			Some(canon)
+		} else {
+			tracing::debug!(path = %path.display(), "file_server: path resolves outside root");
+			None
+		}
+	}
+
+	async fn pick_precompressed(
+		&self,
+		path: &Path,
+		headers: &HeaderMap,
+	) -> (PathBuf, Option<&'static str>) {
+		let accepted = headers.get_all(ACCEPT_ENCODING);
+		for (coding, suffix) in PRECOMPRESSED {
+			if !accepts_coding(accepted.iter(), coding) {
+				continue;
+			}
+			let mut sibling = path.as_os_str().to_owned();
+			sibling.push(".");
+			sibling.push(suffix);
+			if let Some(p) = self.contain(Path::new(&sibling)).await
+				&& tokio::fs::metadata(&p).await.is_ok_and(|m| m.is_file())
+			{
+				return (p, Some(coding));
+			}
+		}
+		(path.to_path_buf(), None)
+	}
+}
+
+fn build(builder: http::response::Builder, body: Body) -> Result<http::Response<Body>, Error> {
+	builder.body(body).map_err(|e| Error::internal(format!("file_server response build: {e}")))
+}
+
+/// `301` to the same path with a trailing `/`, query preserved.
+/// Leading slashes collapse to one: `//dir/` in `Location` would be a
+/// scheme-relative link to the host `dir`.
+fn slash_redirect(uri: &http::Uri) -> Result<http::Response<Body>, Error> {
+	let mut location = format!("/{}/", uri.path().trim_start_matches('/'));
+	if let Some(q) = uri.query() {
+		location.push('?');
+		location.push_str(q);
+	}
+	build(
+		http::Response::builder().status(StatusCode::MOVED_PERMANENTLY).header(LOCATION, location),
+		Body::Empty,
+	)
+}
+
+fn status_only(status: StatusCode) -> Result<http::Response<Body>, Error> {
+	build(http::Response::builder().status(status), Body::Empty)
+}
+
+/// Map a late I/O failure (the file vanished or turned unreadable after
+/// lookup) onto a status instead of a synthetic 500.
+fn io_status(e: std::io::Error) -> Result<http::Response<Body>, Error> {
+	match e.kind() {
+		std::io::ErrorKind::NotFound => status_only(StatusCode::NOT_FOUND),
+		std::io::ErrorKind::PermissionDenied => status_only(StatusCode::FORBIDDEN),
+		_ => Err(Error::from(e).with_ctx("file_server open")),
+	}
+}
+
+/// Percent-decode the request path. `None` for non-UTF-8 or NUL bytes.
+fn decode_path(raw: &str) -> Option<String> {
+	let decoded = percent_encoding::percent_decode_str(raw).decode_utf8().ok()?;
+	if decoded.contains('\0') {
+		return None;
+	}
+	Some(decoded.into_owned())
+}
+
+/// Split a templated path into root-relative segments. Empty and `.`
+/// segments are dropped; `..` (or a `\` that Windows would treat as a
+/// separator) rejects the whole path.
+fn relative_segments(path: &str) -> Option<Vec<&str>> {
+	let mut out = Vec::new();
+	for seg in path.split('/') {
+		match seg {
+			"" | "." => {}
+			".." => return None,
+			s if s.contains('\\') => return None,
+			s => out.push(s),
+		}
+	}
+	Some(out)
+}
+
+fn truncate_to_secs(t: SystemTime) -> SystemTime {
+	let secs = t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+	UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Strong validator from mtime and length, suffixed with the coding so
+/// each precompressed representation gets its own tag.
+fn etag_for(len: u64, mtime: Option<SystemTime>, coding: Option<&str>) -> String {
+	let secs = mtime.and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+	match coding {
+		Some(c) => format!("\"{secs:x}-{len:x}-{c}\""),
+		None => format!("\"{secs:x}-{len:x}\""),
+	}
+}
+
+fn accepts_coding<'a>(values: impl Iterator<Item = &'a HeaderValue>, coding: &str) -> bool {
+	let mut wildcard = false;
+	for v in values {
+		let Ok(s) = v.to_str() else { continue };
+		for item in s.split(',') {
+			let mut parts = item.split(';');
+			let name = parts.next().unwrap_or("").trim();
+			let q_zero = parts.any(|p| {
+				p.trim()
+					.strip_prefix("q=")
+					.and_then(|q| q.trim().parse::<f32>().ok())
+					.is_some_and(|q| q <= 0.0)
+			});
+			if name.eq_ignore_ascii_case(coding) {
+				return !q_zero;
+			}
+			if name == "*" {
+				wildcard = !q_zero;
+			}
+		}
+	}
+	wildcard
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Precondition {
+	Proceed,
+	NotModified,
+	Failed,
+}
+
+/// RFC 9110 §13.2.2 evaluation order, restricted to GET / HEAD.
+fn preconditions(headers: &HeaderMap, etag: &str, mtime: Option<SystemTime>) -> Precondition {
+	if let Some(v) = header_str(headers, &IF_MATCH) {
+		if !etag_list_matches(v, etag, false) {
+			return Precondition::Failed;
+		}
+	} else if let (Some(since), Some(m)) = (header_date(headers, &IF_UNMODIFIED_SINCE), mtime)
+		&& m > since
+	{
+		return Precondition::Failed;
+	}
+	if let Some(v) = header_str(headers, &IF_NONE_MATCH) {
+		if etag_list_matches(v, etag, true) {
+			return Precondition::NotModified;
+		}
+	} else if let (Some(since), Some(m)) = (header_date(headers, &IF_MODIFIED_SINCE), mtime)
+		&& m <= since
+	{
+		return Precondition::NotModified;
+	}
+	Precondition::Proceed
+}
+
+/// `If-Range` absent, or a strong entity-tag / exact date match.
+fn if_range_holds(headers: &HeaderMap, etag: &str, mtime: Option<SystemTime>) -> bool {
+	let Some(v) = header_str(headers, &IF_RANGE) else {
+		return true;
+	};
+	if v.starts_with('"') {
+		return v == etag;
+	}
+	if v.starts_with("W/") {
+		return false;
+	}
+	httpdate::parse_http_date(v).ok().is_some_and(|d| Some(d) == mtime)
+}
+
+fn etag_list_matches(list: &str, etag: &str, weak: bool) -> bool {
+	list.split(',').map(str::trim).any(|t| {
+		if t == "*" {
+			return true;
+		}
+		match t.strip_prefix("W/") {
+			Some(opaque) => weak && opaque == etag,
+			None => t == etag,
+		}
+	})
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &http::HeaderName) -> Option<&'a str> {
+	headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn header_date(headers: &HeaderMap, name: &http::HeaderName) -> Option<SystemTime> {
+	header_str(headers, name).and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeSpec {
+	/// Malformed or multi-range — serve the whole representation.
+	Ignore,
+	Unsatisfiable,
+	/// Inclusive byte span.
+	Bytes {
+		start: u64,
+		end: u64,
+	},
+}
+
+/// Parse a single `bytes=` range against a representation of `len`
+/// bytes. Multi-range requests are answered with the full body, which
+/// RFC 9110 §14.2 permits.
+fn parse_range(value: &str, len: u64) -> RangeSpec {
+	let Some(spec) = value.trim().strip_prefix("bytes=") else {
+		return RangeSpec::Ignore;
+	};
+	if spec.contains(',') {
+		return RangeSpec::Ignore;
+	}
+	let Some((first, last)) = spec.trim().split_once('-') else {
+		return RangeSpec::Ignore;
+	};
+	if first.is_empty() {
+		let Ok(suffix) = last.parse::<u64>() else {
+			return RangeSpec::Ignore;
+		};
+		if suffix == 0 || len == 0 {
+			return RangeSpec::Unsatisfiable;
+		}
+		return RangeSpec::Bytes { start: len.saturating_sub(suffix), end: len - 1 };
+	}
+	let Ok(start) = first.parse::<u64>() else {
+		return RangeSpec::Ignore;
+	};
+	let end = if last.is_empty() {
+		len.saturating_sub(1)
+	} else {
+		match last.parse::<u64>() {
+			Ok(e) if e >= start => e.min(len.saturating_sub(1)),
+			_ => return RangeSpec::Ignore,
+		}
+	};
+	if start >= len {
+		return RangeSpec::Unsatisfiable;
+	}
+	RangeSpec::Bytes { start, end }
+}
+
+/// Content type by file extension. Text types carry `charset=utf-8`;
+/// anything unknown is `application/octet-stream`.
+fn mime_for(path: &Path) -> &'static str {
+	let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+	match ext.as_deref() {
+		Some("html" | "htm") => "text/html; charset=utf-8",
+		Some("css") => "text/css; charset=utf-8",
+		Some("js" | "mjs") => "text/javascript; charset=utf-8",
+		Some("json" | "map") => "application/json",
+		Some("webmanifest") => "application/manifest+json",
+		Some("txt") => "text/plain; charset=utf-8",
+		Some("md") => "text/markdown; charset=utf-8",
+		Some("csv") => "text/csv; charset=utf-8",
+		Some("xml") => "application/xml",
+		Some("svg") => "image/svg+xml",
+		Some("png") => "image/png",
+		Some("jpg" | "jpeg") => "image/jpeg",
+		Some("gif") => "image/gif",
+		Some("webp") => "image/webp",
+		Some("avif") => "image/avif",
+		Some("ico") => "image/x-icon",
+		Some("woff") => "font/woff",
+		Some("woff2") => "font/woff2",
+		Some("ttf") => "font/ttf",
+		Some("otf") => "font/otf",
+		Some("wasm") => "application/wasm",
+		Some("pdf") => "application/pdf",
+		Some("zip") => "application/zip",
+		Some("mp4") => "video/mp4",
+		Some("webm") => "video/webm",
+		Some("mp3") => "audio/mpeg",
+		Some("ogg") => "audio/ogg",
+		_ => "application/octet-stream",
+	}
+}
+
+pin_project! {
+	/// Streams a bounded file reader as `Frame::data` chunks.
+	struct FileBody<R> {
+		#[pin]
+		reader: Take<R>,
+		remaining: u64,
+	}
+}
+
+impl<R: AsyncRead> FileBody<R> {
+	fn new(reader: Take<R>) -> Self {
+		let remaining = reader.limit();
+		Self { reader, remaining }
+	}
+}
+
+impl<R: AsyncRead> http_body::Body for FileBody<R> {
+	type Data = Bytes;
+	type Error = std::io::Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, std::io::Error>>> {
+		let this = self.project();
+		if *this.remaining == 0 {
+			return Poll::Ready(None);
+		}
+		let cap = usize::try_from(*this.remaining).map_or(CHUNK, |r| r.min(CHUNK));
+		let mut buf = BytesMut::with_capacity(cap);
+		match tokio_util::io::poll_read_buf(this.reader, cx, &mut buf) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+			// The file shrank under us; end early rather than hang.
+			Poll::Ready(Ok(0)) => {
+				*this.remaining = 0;
+				Poll::Ready(Some(Err(std::io::Error::new(
+					std::io::ErrorKind::UnexpectedEof,
+					"file truncated while streaming",
+				))))
+			}
+			Poll::Ready(Ok(n)) => {
+				*this.remaining -= n as u64;
+				Poll::Ready(Some(Ok(Frame::data(buf.freeze()))))
+			}
+		}
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.remaining == 0
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		SizeHint::with_exact(self.remaining)
+	}
+}
+
+/// Args parser exposed as a registry-friendly factory.
+///
+/// Args shape:
+///
+/// ```json
+/// {
+///   "root":          "/srv/www",
+///   "index":         ["index.html"],
+///   "try_files":     ["$uri", "$uri/", "/index.html"],
+///   "precompressed": true,
+///   "cache_control": "public, max-age=300"
+/// }
+/// ```
+///
+/// `root` is required and must be an existing directory; it is
+/// canonicalised once here. `index` defaults to `["index.html"]` and
+/// holds bare file names. `try_files` defaults to `["$uri", "$uri/"]`;
+/// a `=NNN` entry may only come last. `precompressed` defaults to
+/// `false`.
+///
+/// # Errors
+/// Returns [`FactoryError`] when `root` is missing or not a directory,
+/// an `index` entry contains `/`, `try_files` is empty or has a
+/// misplaced / out-of-range `=NNN`, or `cache_control` is not a valid
+/// header value.
+pub fn factory(args: &serde_json::Value) -> Result<FetchInst, FactoryError> {
+	let root_raw = args
+		.get("root")
+		.and_then(serde_json::Value::as_str)
+		.ok_or_else(|| FactoryError::Invalid("missing args.root (directory path)".to_owned()))?;
+	let root = std::fs::canonicalize(root_raw)
+		.map_err(|e| FactoryError::Invalid(format!("args.root {root_raw:?}: {e}")))?;
+	if !root.is_dir() {
+		return Err(FactoryError::Invalid(format!("args.root {root_raw:?} is not a directory")));
+	}
+
+	let index = match args.get("index") {
+		None => vec!["index.html".to_owned()],
+		Some(v) => string_list(v, "index")?,
+	};
+	if let Some(bad) = index.iter().find(|n| n.is_empty() || n.contains('/') || *n == "..") {
+		return Err(FactoryError::Invalid(format!("args.index: {bad:?} must be a bare file name")));
+	}
+
+	let try_raw = match args.get("try_files") {
+		None => vec!["$uri".to_owned(), "$uri/".to_owned()],
+		Some(v) => string_list(v, "try_files")?,
+	};
+	if try_raw.is_empty() {
+		return Err(FactoryError::Invalid("args.try_files: must not be empty".to_owned()));
+	}
+	let last = try_raw.len() - 1;
+	let mut try_files = Vec::with_capacity(try_raw.len());
+	for (i, entry) in try_raw.into_iter().enumerate() {
+		if let Some(code) = entry.strip_prefix('=') {
+			if i != last {
+				return Err(FactoryError::Invalid(format!(
+					"args.try_files: {entry:?} must be the last entry"
+				)));
+			}
+			let status = code
+				.parse::<u16>()
+				.ok()
+				.filter(|c| (200..=599).contains(c))
+				.and_then(|c| StatusCode::from_u16(c).ok())
+				.ok_or_else(|| {
+					FactoryError::Invalid(format!("args.try_files: {entry:?} is not =200..=599"))
+				})?;
+			try_files.push(TryFile::Status(status));
+		} else {
+			try_files.push(TryFile::Path(entry));
+		}
+	}
+
+	let precompressed = match args.get("precompressed") {
+		None => false,
+		Some(v) => v
+			.as_bool()
+			.ok_or_else(|| FactoryError::Invalid("args.precompressed: expected a boolean".to_owned()))?,
+	};
+	let cache_control = match args.get("cache_control") {
+		None => None,
+		Some(v) => {
+			let s = v
+				.as_str()
+				.ok_or_else(|| FactoryError::Invalid("args.cache_control: expected a string".to_owned()))?;
+			Some(
+				HeaderValue::from_str(s)
+					.map_err(|e| FactoryError::Invalid(format!("args.cache_control: {e}")))?,
+			)
+		}
+	};
+
+	Ok(FetchInst::L7(Arc::new(FileServerFetch {
+		root,
+		index,
+		try_files,
+		precompressed,
+		cache_control,
+	})))
+}
+
+fn string_list(v: &serde_json::Value, field: &str) -> Result<Vec<String>, FactoryError> {
+	let arr = v
+		.as_array()
+		.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: expected an array")))?;
+	arr
+		.iter()
+		.map(|e| {
+			e.as_str()
+				.map(str::to_owned)
+				.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: entries must be strings")))
+		})
+		.collect()
+}
+
+/// Plug `FetchKind::FileServer` into a `FetchFactories` registry.
+pub fn register(factories: &mut FetchFactories) {
+	factories.register(FetchKind::FileServer, factory);
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::SocketAddr;
+	use std::time::Instant;
+
+	use http_body_util::BodyExt;
+	use tokio_util::sync::CancellationToken;
+	use vane_core::{
+		ConnId, FlowLogEvent, FlowLogSink, FlowLogVerbosity, NodeId, TrajectoryBuilder, Transport,
+	};
+
+	use super::*;
+
+	struct NullSink;
+	impl FlowLogSink for NullSink {
+		fn emit(&self, _event: FlowLogEvent) {}
+	}
+
+	fn fetch_for(root: &Path, extra: serde_json::Value) -> Arc<dyn L7Fetch> {
+		let mut args = serde_json::json!({ "root": root });
+		if let serde_json::Value::Object(m) = extra {
+			args.as_object_mut().expect("object").extend(m);
+		}
+		match factory(&args).expect("factory") {
+			FetchInst::L7(f) => f,
+			FetchInst::L4(_) => unreachable!("file_server is L7"),
+		}
+	}
+
+	async fn get(
+		fetch: &Arc<dyn L7Fetch>,
+		method: Method,
+		uri: &str,
+		headers: &[(&str, &str)],
+	) -> (http::response::Parts, Bytes) {
+		let mut b = http::Request::builder().method(method).uri(uri);
+		for (k, v) in headers {
+			b = b.header(*k, *v);
+		}
+		let req = b.body(Body::Empty).expect("request");
+		let addr: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+		let conn = Arc::new(ConnContext::new(ConnId(0), addr, addr, Transport::Tcp, Instant::now()));
+		let mut ctx = FlowCtx {
+			span: tracing::Span::none(),
+			log: Arc::new(NullSink),
+			cancel: CancellationToken::new(),
+			accept_cancel: CancellationToken::new(),
+			verbosity: FlowLogVerbosity::Trajectory,
+			trajectory: TrajectoryBuilder::new(conn.id, NodeId::for_testing(0), 0),
+		};
+		let L7FetchOutput::Response(resp) = fetch.fetch(req, &conn, &mut ctx).await.expect("fetch")
+		else {
+			unreachable!("file_server never tunnels");
+		};
+		let (parts, body) = resp.into_parts();
+		let bytes = body.collect().await.expect("body").to_bytes();
+		(parts, bytes)
+	}
+
+	fn site() -> tempfile::TempDir {
+		let dir = tempfile::tempdir().expect("tempdir");
+		std::fs::write(dir.path().join("index.html"), "<h1>home</h1>").expect("write");
+		std::fs::create_dir(dir.path().join("docs")).expect("mkdir");
+		std::fs::write(dir.path().join("docs/index.html"), "docs").expect("write");
+		std::fs::write(dir.path().join("app.js"), "0123456789").expect("write");
+		dir
+	}
+
+	#[tokio::test]
+	async fn serves_file_with_validators_and_mime() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[]).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(body, "0123456789");
+		assert_eq!(parts.headers[CONTENT_TYPE], "text/javascript; charset=utf-8");
+		assert_eq!(parts.headers[CONTENT_LENGTH], "10");
+		assert!(parts.headers.contains_key(ETAG));
+		assert!(parts.headers.contains_key(LAST_MODIFIED));
+	}
+
+	#[tokio::test]
+	async fn directory_serves_index_and_redirects_without_slash() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, body) = get(&f, Method::GET, "/docs/", &[]).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(body, "docs");
+		let (parts, _) = get(&f, Method::GET, "/docs?x=1", &[]).await;
+		assert_eq!(parts.status, StatusCode::MOVED_PERMANENTLY);
+		assert_eq!(parts.headers[LOCATION], "/docs/?x=1");
+	}
+
+	#[tokio::test]
+	async fn slash_redirect_never_points_off_host() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, _) = get(&f, Method::GET, "//docs", &[]).await;
+		assert_eq!(parts.status, StatusCode::MOVED_PERMANENTLY);
+		assert_eq!(parts.headers[LOCATION], "/docs/");
+	}
+
+	#[tokio::test]
+	async fn try_files_falls_back_for_spa_routes() {
+		let dir = site();
+		let f =
+			fetch_for(dir.path(), serde_json::json!({ "try_files": ["$uri", "$uri/", "/index.html"] }));
+		let (parts, body) = get(&f, Method::GET, "/some/client/route", &[]).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(body, "<h1>home</h1>");
+
+		let f = fetch_for(dir.path(), serde_json::json!({ "try_files": ["$uri", "=410"] }));
+		let (parts, _) = get(&f, Method::GET, "/missing", &[]).await;
+		assert_eq!(parts.status, StatusCode::GONE);
+	}
+
+	#[tokio::test]
+	async fn traversal_and_symlink_escape_are_refused() {
+		let outside = tempfile::tempdir().expect("tempdir");
+		std::fs::write(outside.path().join("secret"), "nope").expect("write");
+		let dir = site();
+		#[cfg(unix)]
+		std::os::unix::fs::symlink(outside.path().join("secret"), dir.path().join("leak"))
+			.expect("symlink");
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+
+		let (parts, _) = get(&f, Method::GET, "/%2e%2e/secret", &[]).await;
+		assert_eq!(parts.status, StatusCode::BAD_REQUEST);
+		#[cfg(unix)]
+		{
+			let (parts, _) = get(&f, Method::GET, "/leak", &[]).await;
+			assert_eq!(parts.status, StatusCode::NOT_FOUND);
+		}
+	}
+
+	#[tokio::test]
+	async fn conditional_requests_return_304_and_412() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, _) = get(&f, Method::GET, "/app.js", &[]).await;
+		let etag = parts.headers[ETAG].to_str().expect("etag").to_owned();
+		let lm = parts.headers[LAST_MODIFIED].to_str().expect("lm").to_owned();
+
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[("if-none-match", &etag)]).await;
+		assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+		assert!(body.is_empty());
+		let (parts, _) = get(&f, Method::GET, "/app.js", &[("if-modified-since", &lm)]).await;
+		assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+		let (parts, _) = get(&f, Method::GET, "/app.js", &[("if-match", "\"other\"")]).await;
+		assert_eq!(parts.status, StatusCode::PRECONDITION_FAILED);
+	}
+
+	#[tokio::test]
+	async fn range_and_if_range() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[("range", "bytes=2-4")]).await;
+		assert_eq!(parts.status, StatusCode::PARTIAL_CONTENT);
+		assert_eq!(body, "234");
+		assert_eq!(parts.headers[CONTENT_RANGE], "bytes 2-4/10");
+
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[("range", "bytes=-3")]).await;
+		assert_eq!(parts.status, StatusCode::PARTIAL_CONTENT);
+		assert_eq!(body, "789");
+
+		let (parts, _) = get(&f, Method::GET, "/app.js", &[("range", "bytes=20-")]).await;
+		assert_eq!(parts.status, StatusCode::RANGE_NOT_SATISFIABLE);
+		assert_eq!(parts.headers[CONTENT_RANGE], "bytes */10");
+
+		let (parts, body) =
+			get(&f, Method::GET, "/app.js", &[("range", "bytes=2-4"), ("if-range", "\"stale\"")]).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(body.len(), 10);
+	}
+
+	#[tokio::test]
+	async fn precompressed_sibling_is_negotiated() {
+		let dir = site();
+		std::fs::write(dir.path().join("app.js.br"), "BR").expect("write");
+		std::fs::write(dir.path().join("app.js.gz"), "GZ").expect("write");
+		let f = fetch_for(dir.path(), serde_json::json!({ "precompressed": true }));
+
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[("accept-encoding", "gzip, br")]).await;
+		assert_eq!(body, "BR");
+		assert_eq!(parts.headers[CONTENT_ENCODING], "br");
+		assert_eq!(parts.headers[CONTENT_TYPE], "text/javascript; charset=utf-8");
+		assert_eq!(parts.headers[VARY], "accept-encoding");
+
+		let (parts, body) =
+			get(&f, Method::GET, "/app.js", &[("accept-encoding", "gzip, br;q=0")]).await;
+		assert_eq!(body, "GZ");
+		assert_eq!(parts.headers[CONTENT_ENCODING], "gzip");
+
+		let (parts, body) = get(&f, Method::GET, "/app.js", &[]).await;
+		assert_eq!(body, "0123456789");
+		assert!(!parts.headers.contains_key(CONTENT_ENCODING));
+	}
+
+	#[tokio::test]
+	async fn head_omits_body_and_other_methods_are_rejected() {
+		let dir = site();
+		let f = fetch_for(dir.path(), serde_json::json!({}));
+		let (parts, body) = get(&f, Method::HEAD, "/app.js", &[]).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(parts.headers[CONTENT_LENGTH], "10");
+		assert!(body.is_empty());
+		let (parts, _) = get(&f, Method::POST, "/app.js", &[]).await;
+		assert_eq!(parts.status, StatusCode::METHOD_NOT_ALLOWED);
+		assert_eq!(parts.headers[ALLOW], "GET, HEAD");
+	}
+
+	#[test]
+	fn parse_range_shapes() {
+		assert_eq!(parse_range("bytes=0-0", 5), RangeSpec::Bytes { start: 0, end: 0 });
+		assert_eq!(parse_range("bytes=3-", 5), RangeSpec::Bytes { start: 3, end: 4 });
+		assert_eq!(parse_range("bytes=1-99", 5), RangeSpec::Bytes { start: 1, end: 4 });
+		assert_eq!(parse_range("bytes=-10", 5), RangeSpec::Bytes { start: 0, end: 4 });
+		assert_eq!(parse_range("bytes=0-1,3-4", 5), RangeSpec::Ignore);
+		assert_eq!(parse_range("bytes=4-2", 5), RangeSpec::Ignore);
+		assert_eq!(parse_range("items=0-1", 5), RangeSpec::Ignore);
+		assert_eq!(parse_range("bytes=5-", 5), RangeSpec::Unsatisfiable);
+		assert_eq!(parse_range("bytes=-0", 5), RangeSpec::Unsatisfiable);
+	}
+
+	#[test]
+	fn factory_rejects_bad_args() {
+		let dir = site();
+		let root = dir.path().to_str().expect("utf-8");
+		for (args, needle) in [
+			(serde_json::json!({}), "args.root"),
+			(serde_json::json!({ "root": "/definitely/not/here" }), "args.root"),
+			(serde_json::json!({ "root": root, "index": ["a/b.html"] }), "args.index"),
+			(serde_json::json!({ "root": root, "try_files": [] }), "args.try_files"),
+			(serde_json::json!({ "root": root, "try_files": ["=404", "$uri"] }), "last entry"),
+			(serde_json::json!({ "root": root, "try_files": ["=99"] }), "args.try_files"),
+			(serde_json::json!({ "root": root, "precompressed": "yes" }), "args.precompressed"),
+		] {
+			let Err(FactoryError::Invalid(msg)) = factory(&args) else {
+				panic!("{args} must be rejected");
+			};
+			assert!(msg.contains(needle), "{args}: {msg}");
+		}
+	}
+}
@@ -0,0 +1,547 @@
+//! Active upstream health checks.
+//!
+//! A rule's `args.health_check` turns every upstream it references
+//! into a [`HealthTarget`]: a daemon-level record plus a background
+//! probe loop (TCP connect, TLS handshake, or HTTP GET) that flips the
+//! target between healthy and unhealthy after `rise` / `fall`
+//! consecutive results. Targets are registered weakly by
+//! `(authority, spec, tls)` — a reload that keeps the same check reuses
+//! the running probe and its state, so a backend known to be down stays
+//! down; once no live graph holds a target its loop exits on the next
+//! tick.
+//!
+//! See `spec/crates/engine.md` § _Health checks_.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, LazyLock, Weak};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::{BodyExt as _, Empty, Limited};
+use hyper_util::rt::TokioIo;
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::fetch::client_cache::TlsConfigFingerprint;
+use crate::fetch::retry::parse_duration;
+use crate::fetch::upstream::{UpstreamTls, dial_upstream};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_RISE: u32 = 2;
+const DEFAULT_FALL: u32 = 3;
+/// Cap on how much of an HTTP probe's body is read for `expect_body`.
+const MAX_PROBE_BODY: usize = 64 * 1024;
+
+/// What a probe does against the target.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProbeKind {
+	/// Plain TCP connect.
+	Tcp,
+	/// TCP connect plus a full TLS handshake with the rule's TLS config.
+	Tls,
+	/// HTTP/1.1 `GET path` (over TLS when the rule is TLS), checked
+	/// against `expect_status` and, when set, a body substring.
+	Http { path: String, expect_status: StatusMatch, expect_body: Option<String> },
+}
+
+/// Accepted probe response status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusMatch {
+	Exact(u16),
+	/// Leading digit, e.g. `2` for `"2xx"`.
+	Class(u8),
+}
+
+impl StatusMatch {
+	fn matches(self, status: u16) -> bool {
+		match self {
+			Self::Exact(s) => status == s,
+			Self::Class(c) => status / 100 == u16::from(c),
+		}
+	}
+}
+
+/// Parsed `args.health_check`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HealthCheckSpec {
+	pub kind: ProbeKind,
+	pub interval: Duration,
+	pub timeout: Duration,
+	pub rise: u32,
+	pub fall: u32,
+}
+
+impl ProbeKind {
+	fn label(&self) -> &'static str {
+		match self {
+			Self::Tcp => "tcp",
+			Self::Tls => "tls",
+			Self::Http { .. } => "http",
+		}
+	}
+}
+
+/// Parse `args.health_check`. Absent means no active checking.
+///
+/// ```json
+/// {
+///   "type": "http" | "tcp" | "tls",
+///   "interval": "10s", "timeout": "2s", "rise": 2, "fall": 3,
+///   "path": "/healthz", "expect_status": 200 | "2xx", "expect_body": "ok"
+/// }
+/// ```
+///
+/// `path` / `expect_status` / `expect_body` are only valid with
+/// `type: "http"`; `expect_status` defaults to `"2xx"`.
+///
+/// # Errors
+/// Returns a human-readable message; the caller prefixes
+/// `args.health_check`.
+pub fn parse(v: Option<&serde_json::Value>) -> Result<Option<HealthCheckSpec>, String> {
+	let Some(v) = v else { return Ok(None) };
+	let obj = v.as_object().ok_or("must be an object")?;
+	let ty = obj.get("type").and_then(serde_json::Value::as_str).ok_or("missing type (string)")?;
+	let kind = match ty {
+		"tcp" => ProbeKind::Tcp,
+		"tls" => ProbeKind::Tls,
+		"http" => {
+			let path = obj.get("path").map_or(Ok("/"), |p| p.as_str().ok_or("path must be a string"))?;
+			if !path.starts_with('/') {
+				return Err(format!("path must start with '/', got {path:?}"));
+			}
+			let expect_status = match obj.get("expect_status") {
+				None => StatusMatch::Class(2),
+				Some(s) => parse_status_match(s)?,
+			};
+			let expect_body = match obj.get("expect_body") {
+				None => None,
+				Some(b) => Some(b.as_str().ok_or("expect_body must be a string")?.to_owned()),
+			};
+			ProbeKind::Http { path: path.to_owned(), expect_status, expect_body }
+		}
+		other => return Err(format!("type must be 'http', 'tcp' or 'tls', got {other:?}")),
+	};
+	if !matches!(kind, ProbeKind::Http { .. }) {
+		for key in ["path", "expect_status", "expect_body"] {
+			if obj.contains_key(key) {
+				return Err(format!("{key} is only valid with type 'http'"));
+			}
+		}
+	}
+	let interval = parse_positive_duration(obj.get("interval"), "interval", DEFAULT_INTERVAL)?;
+	let timeout = parse_positive_duration(obj.get("timeout"), "timeout", DEFAULT_TIMEOUT)?;
+	if timeout > interval {
+		return Err("timeout must not exceed interval".to_string());
+	}
+	let rise = parse_threshold(obj.get("rise"), "rise", DEFAULT_RISE)?;
+	let fall = parse_threshold(obj.get("fall"), "fall", DEFAULT_FALL)?;
+	Ok(Some(HealthCheckSpec { kind, interval, timeout, rise, fall }))
+}
+
+fn parse_status_match(v: &serde_json::Value) -> Result<StatusMatch, String> {
+	if let Some(n) = v.as_u64() {
+		return u16::try_from(n)
+			.ok()
+			.filter(|s| (100..=599).contains(s))
+			.map(StatusMatch::Exact)
+			.ok_or_else(|| format!("expect_status {n} is not an HTTP status"));
+	}
+	match v.as_str().map(str::as_bytes) {
+		Some([d @ b'1'..=b'5', b'x', b'x']) => Ok(StatusMatch::Class(d - b'0')),
+		_ => Err(format!("expect_status must be a status code or \"Nxx\" class, got {v}")),
+	}
+}
+
+fn parse_positive_duration(
+	v: Option<&serde_json::Value>,
+	key: &str,
+	default: Duration,
+) -> Result<Duration, String> {
+	let Some(v) = v else { return Ok(default) };
+	let s = v.as_str().ok_or_else(|| format!("{key} must be a duration string"))?;
+	let d = parse_duration(s).map_err(|e| format!("{key}: {e}"))?;
+	if d.is_zero() {
+		return Err(format!("{key} must be greater than zero"));
+	}
+	Ok(d)
+}
+
+fn parse_threshold(v: Option<&serde_json::Value>, key: &str, default: u32) -> Result<u32, String> {
+	let Some(v) = v else { return Ok(default) };
+	v.as_u64()
+		.and_then(|n| u32::try_from(n).ok())
+		.filter(|n| (1..=100).contains(n))
+		.ok_or_else(|| format!("{key} must be an integer in 1..=100"))
+}
+
+/// Current verdict for one target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthStatus {
+	/// No verdict yet. Treated as available so a fresh boot does not
+	/// blackhole traffic before the first `rise` successes.
+	Unknown,
+	Healthy,
+	Unhealthy,
+}
+
+impl HealthStatus {
+	const fn to_u8(self) -> u8 {
+		match self {
+			Self::Unknown => 0,
+			Self::Healthy => 1,
+			Self::Unhealthy => 2,
+		}
+	}
+
+	const fn from_u8(v: u8) -> Self {
+		match v {
+			1 => Self::Healthy,
+			2 => Self::Unhealthy,
+			_ => Self::Unknown,
+		}
+	}
+}
+
+impl std::fmt::Display for HealthStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Unknown => "unknown",
+			Self::Healthy => "healthy",
+			Self::Unhealthy => "unhealthy",
+		})
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TargetKey {
+	authority: String,
+	spec: HealthCheckSpec,
+	tls: Option<(TlsConfigFingerprint, String)>,
+}
+
+static TARGETS: LazyLock<DashMap<TargetKey, Weak<HealthTarget>>> = LazyLock::new(DashMap::new);
+
+#[derive(Default)]
+struct ProbeRecord {
+	successes: u32,
+	failures: u32,
+	last_check: Option<Instant>,
+	last_change: Option<Instant>,
+	last_error: Option<String>,
+}
+
+/// One probed upstream. Shared by every fetch that references the same
+/// `(authority, spec, tls)`; the probe loop holds only a `Weak`.
+pub struct HealthTarget {
+	id: String,
+	authority: String,
+	spec: HealthCheckSpec,
+	tls: Option<UpstreamTls>,
+	status: AtomicU8,
+	record: Mutex<ProbeRecord>,
+	/// Fired on drop so an in-flight probe stops immediately instead of
+	/// running out its timeout against an upstream nobody routes to.
+	cancel: CancellationToken,
+}
+
+impl Drop for HealthTarget {
+	fn drop(&mut self) {
+		self.cancel.cancel();
+	}
+}
+
+/// Look up (or register) the target for `(authority, spec, tls)`. A new
+/// target starts its probe loop on the current tokio runtime; outside
+/// a runtime (unit tests that only link a graph) the target stays
+/// `Unknown`.
+#[must_use]
+pub fn watch(
+	authority: &str,
+	spec: &HealthCheckSpec,
+	tls: Option<&UpstreamTls>,
+) -> Arc<HealthTarget> {
+	let key = TargetKey {
+		authority: authority.to_owned(),
+		spec: spec.clone(),
+		tls: tls.map(|t| (t.fingerprint.clone(), t.verify_hostname.clone())),
+	};
+	let mut slot = TARGETS.entry(key.clone()).or_default();
+	if let Some(live) = slot.upgrade() {
+		return live;
+	}
+	let target = Arc::new(HealthTarget {
+		id: stable_hex(&key),
+		authority: key.authority,
+		spec: key.spec,
+		tls: tls.cloned(),
+		status: AtomicU8::new(HealthStatus::Unknown.to_u8()),
+		record: Mutex::new(ProbeRecord::default()),
+		cancel: CancellationToken::new(),
+	});
+	*slot = Arc::downgrade(&target);
+	if let Ok(handle) = tokio::runtime::Handle::try_current() {
+		handle.spawn(probe_loop(Arc::downgrade(&target)));
+	}
+	target
+}
+
+impl HealthTarget {
+	#[must_use]
+	pub fn status(&self) -> HealthStatus {
+		HealthStatus::from_u8(self.status.load(Ordering::Relaxed))
+	}
+
+	/// `false` only once the target has been marked unhealthy.
+	#[must_use]
+	pub fn is_available(&self) -> bool {
+		self.status() != HealthStatus::Unhealthy
+	}
+
+	/// Fold one probe result into the rise / fall counters and flip the
+	/// verdict when a threshold is crossed.
+	fn record(&self, result: Result<(), String>) {
+		let mut rec = self.record.lock();
+		let now = Instant::now();
+		rec.last_check = Some(now);
+		let current = self.status();
+		let next = match result {
+			Ok(()) => {
+				rec.successes = rec.successes.saturating_add(1);
+				rec.failures = 0;
+				rec.last_error = None;
+				(current != HealthStatus::Healthy && rec.successes >= self.spec.rise)
+					.then_some(HealthStatus::Healthy)
+			}
+			Err(e) => {
+				rec.failures = rec.failures.saturating_add(1);
+				rec.successes = 0;
+				rec.last_error = Some(e);
+				(current != HealthStatus::Unhealthy && rec.failures >= self.spec.fall)
+					.then_some(HealthStatus::Unhealthy)
+			}
+		};
+		let Some(next) = next else { return };
+		self.status.store(next.to_u8(), Ordering::Relaxed);
+		rec.last_change = Some(now);
+		metrics::counter!(
+			"vane.upstream.health.transitions_total",
+			"kind" => self.spec.kind.label(),
+			"to" => next.to_string(),
+		)
+		.increment(1);
+		if next == HealthStatus::Unhealthy {
+			tracing::warn!(
+				upstream = %self.authority,
+				error = rec.last_error.as_deref().unwrap_or_default(),
+				"upstream marked unhealthy",
+			);
+		} else {
+			tracing::info!(upstream = %self.authority, "upstream marked healthy");
+		}
+	}
+
+	async fn probe(&self) -> Result<(), String> {
+		let tls = match self.spec.kind {
+			ProbeKind::Tcp => None,
+			ProbeKind::Tls | ProbeKind::Http { .. } => self.tls.as_ref(),
+		};
+		let stream = dial_upstream(&self.authority, tls, &self.cancel, self.spec.timeout)
+			.await
+			.map_err(|e| e.to_string())?;
+		let ProbeKind::Http { path, expect_status, expect_body } = &self.spec.kind else {
+			return Ok(());
+		};
+		let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+			.await
+			.map_err(|e| format!("http handshake: {e}"))?;
+		tokio::spawn(async move {
+			let _ = conn.await;
+		});
+		let req = http::Request::get(path.as_str())
+			.header(http::header::HOST, self.authority.as_str())
+			.header(http::header::USER_AGENT, "vane-health-check")
+			.body(Empty::<Bytes>::new())
+			.map_err(|e| format!("build request: {e}"))?;
+		let resp = sender.send_request(req).await.map_err(|e| format!("http request: {e}"))?;
+		let status = resp.status().as_u16();
+		if !expect_status.matches(status) {
+			return Err(format!("unexpected status {status}"));
+		}
+		let Some(needle) = expect_body else { return Ok(()) };
+		let body = Limited::new(resp.into_body(), MAX_PROBE_BODY)
+			.collect()
+			.await
+			.map_err(|e| format!("read body: {e}"))?
+			.to_bytes();
+		if memchr::memmem::find(&body, needle.as_bytes()).is_none() {
+			return Err(format!("body does not contain {needle:?}"));
+		}
+		Ok(())
+	}
+}
+
+/// Probe immediately, then every `interval`, until the last strong
+/// reference to the target is gone.
+async fn probe_loop(target: Weak<HealthTarget>) {
+	loop {
+		let Some(t) = target.upgrade() else { return };
+		let cancel = t.cancel.clone();
+		let result = match tokio::time::timeout(t.spec.timeout, t.probe()).await {
+			Ok(r) => r,
+			Err(_) => Err(format!("probe timed out after {:?}", t.spec.timeout)),
+		};
+		if cancel.is_cancelled() {
+			return;
+		}
+		t.record(result);
+		let interval = t.spec.interval;
+		drop(t);
+		tokio::select! {
+			() = cancel.cancelled() => return,
+			() = tokio::time::sleep(interval) => {}
+		}
+	}
+}
+
+fn stable_hex<T: std::hash::Hash>(value: &T) -> String {
+	use std::hash::Hasher as _;
+	let mut h = std::collections::hash_map::DefaultHasher::new();
+	value.hash(&mut h);
+	format!("{:016x}", h.finish())
+}
+
+/// Worst verdict across every live target probing `authority`, or
+/// `None` when nothing checks it.
+#[must_use]
+pub fn status_for(authority: &str) -> Option<HealthStatus> {
+	TARGETS
+		.iter()
+		.filter(|e| e.key().authority == authority)
+		.filter_map(|e| e.value().upgrade())
+		.map(|t| t.status())
+		.max_by_key(|s| s.to_u8())
+}
+
+/// Read-only view of one target, surfaced via `get_health`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthTargetSummary {
+	/// 16-char hex hash of `(authority, spec, tls)`.
+	pub target_id: String,
+	pub authority: String,
+	pub kind: &'static str,
+	pub status: HealthStatus,
+	pub consecutive_successes: u32,
+	pub consecutive_failures: u32,
+	pub last_check_age_ms: Option<u64>,
+	pub last_change_age_ms: Option<u64>,
+	pub last_error: Option<String>,
+}
+
+/// Snapshot every live target, sorted by authority then id. Dead
+/// registry slots are pruned on the way.
+#[must_use]
+pub fn snapshot() -> Vec<HealthTargetSummary> {
+	TARGETS.retain(|_, weak| weak.strong_count() > 0);
+	let age_ms =
+		|t: Option<Instant>| t.map(|t| u64::try_from(t.elapsed().as_millis()).unwrap_or(u64::MAX));
+	let mut out: Vec<HealthTargetSummary> = TARGETS
+		.iter()
+		.filter_map(|e| e.value().upgrade())
+		.map(|t| {
+			let rec = t.record.lock();
+			HealthTargetSummary {
+				target_id: t.id.clone(),
+				authority: t.authority.clone(),
+				kind: t.spec.kind.label(),
+				status: t.status(),
+				consecutive_successes: rec.successes,
+				consecutive_failures: rec.failures,
+				last_check_age_ms: age_ms(rec.last_check),
+				last_change_age_ms: age_ms(rec.last_change),
+				last_error: rec.last_error.clone(),
+			}
+		})
+		.collect();
+	out.sort_by(|a, b| a.authority.cmp(&b.authority).then_with(|| a.target_id.cmp(&b.target_id)));
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn spec(rise: u32, fall: u32) -> HealthCheckSpec {
+		HealthCheckSpec {
+			kind: ProbeKind::Tcp,
+			interval: Duration::from_secs(1),
+			timeout: Duration::from_millis(500),
+			rise,
+			fall,
+		}
+	}
+
+	#[test]
+	fn parse_http_defaults() {
+		let s = parse(Some(&json!({ "type": "http" }))).expect("parse").expect("some");
+		assert_eq!(
+			s.kind,
+			ProbeKind::Http {
+				path: "/".to_owned(),
+				expect_status: StatusMatch::Class(2),
+				expect_body: None
+			},
+		);
+		assert_eq!((s.interval, s.timeout, s.rise, s.fall), (DEFAULT_INTERVAL, DEFAULT_TIMEOUT, 2, 3));
+	}
+
+	#[test]
+	fn parse_rejects_http_fields_on_tcp_and_bad_values() {
+		assert!(parse(Some(&json!({ "type": "tcp", "path": "/" }))).unwrap_err().contains("path"));
+		assert!(parse(Some(&json!({ "type": "udp" }))).is_err());
+		assert!(parse(Some(&json!({ "type": "tcp", "rise": 0 }))).unwrap_err().contains("rise"));
+		assert!(
+			parse(Some(&json!({ "type": "tcp", "interval": "1s", "timeout": "5s" })))
+				.unwrap_err()
+				.contains("timeout")
+		);
+		assert!(parse(Some(&json!({ "type": "http", "expect_status": "2yy" }))).is_err());
+	}
+
+	#[test]
+	fn status_match_exact_and_class() {
+		assert!(StatusMatch::Class(2).matches(204));
+		assert!(!StatusMatch::Class(2).matches(301));
+		assert!(StatusMatch::Exact(418).matches(418));
+	}
+
+	#[test]
+	fn rise_and_fall_thresholds_flip_status() {
+		let t = watch("rise-fall.test:1", &spec(2, 3), None);
+		assert_eq!(t.status(), HealthStatus::Unknown);
+		t.record(Err("refused".to_owned()));
+		t.record(Err("refused".to_owned()));
+		assert!(t.is_available(), "two failures stay under fall=3");
+		t.record(Err("refused".to_owned()));
+		assert_eq!(t.status(), HealthStatus::Unhealthy);
+		t.record(Ok(()));
+		assert_eq!(t.status(), HealthStatus::Unhealthy, "one success stays under rise=2");
+		t.record(Ok(()));
+		assert_eq!(t.status(), HealthStatus::Healthy);
+	}
+
+	#[test]
+	fn watch_reuses_live_target_and_prunes_dead_ones() {
+		let a = watch("reuse.test:1", &spec(1, 1), None);
+		let b = watch("reuse.test:1", &spec(1, 1), None);
+		assert!(Arc::ptr_eq(&a, &b));
+		a.record(Err("down".to_owned()));
+		assert_eq!(status_for("reuse.test:1"), Some(HealthStatus::Unhealthy));
+		drop((a, b));
+		assert!(snapshot().iter().all(|s| s.authority != "reuse.test:1"));
+		assert_eq!(status_for("reuse.test:1"), None);
+	}
+}
@@ -0,0 +1,290 @@
+//! The `args.cache` path: consult the shared store before
+//! [`super::dispatch`] and fill it from what comes back.
+//!
+//! Per request, in order:
+//!
+//! * unsafe methods go upstream and, on a non-error answer, purge the
+//!   key they name;
+//! * `no-store`, `Authorization` and `Range` requests bypass the cache;
+//! * a fresh entry is served as `HIT`; a stale one inside
+//!   `stale-while-revalidate` is served as `STALE` while a background
+//!   task refreshes it;
+//! * anything else joins the key's fill: the leader revalidates (with
+//!   the entry's validators) or fetches, the followers wait and look
+//!   again. A `304` refreshes the entry (`REVALIDATED`); a 5xx or a
+//!   transport error inside `stale-if-error` falls back to the entry
+//!   (`STALE`); any other answer is stored and relayed as `MISS`.
+//!
+//! Every response on this path carries `X-Cache`. See
+//! `spec/crates/engine.md` § _Response cache_.
+
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use http::header::{
+	AGE, AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use http_body_util::BodyExt;
+use vane_core::{Body, ConnContext, Error, FlowLogSink, L7FetchOutput, Request};
+
+use super::HttpProxyFetch;
+use super::dispatch::clone_parts_for_retry;
+use crate::fetch::cache::{
+	self, CacheSpec, Entry, FillBody, FillGuard, Flight, Freshness, RequestDirectives,
+};
+
+const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+
+/// Value of the `X-Cache` header and the `result` metric label.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+	Hit,
+	Miss,
+	Stale,
+	Revalidated,
+	Bypass,
+}
+
+impl Outcome {
+	const fn as_str(self) -> &'static str {
+		match self {
+			Self::Hit => "HIT",
+			Self::Miss => "MISS",
+			Self::Stale => "STALE",
+			Self::Revalidated => "REVALIDATED",
+			Self::Bypass => "BYPASS",
+		}
+	}
+
+	fn record(self) {
+		metrics::counter!("vane.cache.requests_total", "result" => self.as_str()).increment(1);
+	}
+}
+
+impl HttpProxyFetch {
+	pub(super) async fn fetch_cached(
+		&self,
+		spec: &CacheSpec,
+		req: Request,
+		conn: &Arc<ConnContext>,
+		log: &Arc<dyn FlowLogSink>,
+	) -> Result<L7FetchOutput, Error> {
+		let method = req.method().clone();
+		if method != Method::GET && method != Method::HEAD {
+			// RFC 9111 §4.4: a successful unsafe request invalidates the
+			// target URI.
+			let key = (!method.is_safe()).then(|| spec.key(req.uri(), req.headers()));
+			let out = self.fetch_upstream(req, conn, log).await?;
+			if let (Some(key), L7FetchOutput::Response(resp)) = (key, &out)
+				&& resp.status().as_u16() < 400
+			{
+				cache::store().purge_key(&key);
+			}
+			return Ok(tagged(out, Outcome::Bypass));
+		}
+		let dirs = RequestDirectives::parse(req.headers());
+		if dirs.no_store
+			|| req.headers().contains_key(AUTHORIZATION)
+			|| req.headers().contains_key(RANGE)
+		{
+			let out = self.fetch_upstream(req, conn, log).await?;
+			return Ok(tagged(out, Outcome::Bypass));
+		}
+
+		let key = spec.key(req.uri(), req.headers());
+		let head = method == Method::HEAD;
+		let mut entry = cache::store().lookup(&key, req.headers());
+		match entry.as_ref().map(|e| (e, e.state(Instant::now(), &dirs))) {
+			Some((e, Freshness::Fresh)) => return serve(e, req.headers(), head, Outcome::Hit),
+			Some((e, Freshness::StaleWhileRevalidate)) => {
+				self.spawn_refresh(&req, key, Arc::clone(e), conn, log);
+				return serve(e, req.headers(), head, Outcome::Stale);
+			}
+			// A `HEAD` miss cannot fill the store; relay it.
+			None if head => {
+				let out = self.fetch_upstream(req, conn, log).await?;
+				return Ok(tagged(out, Outcome::Miss));
+			}
+			_ => {}
+		}
+
+		let guard = match cache::join(&key) {
+			Flight::Leader(guard) => Some(guard),
+			Flight::Follower(rx) => {
+				cache::wait(rx).await;
+				entry = cache::store().lookup(&key, req.headers());
+				if let Some(e) = &entry
+					&& e.state(Instant::now(), &dirs) != Freshness::Stale
+				{
+					return serve(e, req.headers(), head, Outcome::Hit);
+				}
+				None
+			}
+		};
+		match entry {
+			Some(e) => self.revalidate(spec, key, req, &e, guard, conn, log).await,
+			None => self.fill(spec, key, req, guard, conn, log).await,
+		}
+	}
+
+	/// Fetch and, if the answer is storable, stream it into the store.
+	async fn fill(
+		&self,
+		spec: &CacheSpec,
+		key: String,
+		req: Request,
+		guard: Option<FillGuard>,
+		conn: &Arc<ConnContext>,
+		log: &Arc<dyn FlowLogSink>,
+	) -> Result<L7FetchOutput, Error> {
+		let (parts, body) = req.into_parts();
+		let snapshot = clone_parts_for_retry(&parts);
+		let out = self.fetch_upstream(Request::from_parts(parts, body), conn, log).await?;
+		Ok(tagged(store_response(spec, key, &snapshot, out, guard), Outcome::Miss))
+	}
+
+	/// Ask the upstream whether `entry` is still current, replacing the
+	/// client's own validators with the stored ones.
+	#[expect(clippy::too_many_arguments, reason = "one request's worth of context")]
+	async fn revalidate(
+		&self,
+		spec: &CacheSpec,
+		key: String,
+		req: Request,
+		entry: &Entry,
+		guard: Option<FillGuard>,
+		conn: &Arc<ConnContext>,
+		log: &Arc<dyn FlowLogSink>,
+	) -> Result<L7FetchOutput, Error> {
+		let (mut parts, body) = req.into_parts();
+		let snapshot = clone_parts_for_retry(&parts);
+		let head = parts.method == Method::HEAD;
+		parts.headers.remove(IF_NONE_MATCH);
+		parts.headers.remove(IF_MODIFIED_SINCE);
+		let (etag, last_modified) = entry.validators();
+		if let Some(v) = etag {
+			parts.headers.insert(IF_NONE_MATCH, v.clone());
+		}
+		if let Some(v) = last_modified {
+			parts.headers.insert(IF_MODIFIED_SINCE, v.clone());
+		}
+		match self.fetch_upstream(Request::from_parts(parts, body), conn, log).await {
+			Ok(L7FetchOutput::Response(resp)) if resp.status() == StatusCode::NOT_MODIFIED => {
+				let fresh = cache::refresh(spec, &key, &snapshot.headers, entry, resp.headers());
+				serve(&fresh, &snapshot.headers, head, Outcome::Revalidated)
+			}
+			Ok(L7FetchOutput::Response(resp))
+				if resp.status().is_server_error() && entry.usable_on_error(Instant::now()) =>
+			{
+				tracing::debug!(status = %resp.status(), key, "serving stale on upstream error");
+				serve(entry, &snapshot.headers, head, Outcome::Stale)
+			}
+			Err(e) if entry.usable_on_error(Instant::now()) => {
+				tracing::debug!(error = %e, key, "serving stale on upstream failure");
+				serve(entry, &snapshot.headers, head, Outcome::Stale)
+			}
+			Ok(out) => Ok(tagged(store_response(spec, key, &snapshot, out, guard), Outcome::Miss)),
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Refresh `entry` off the request path. Skipped when another
+	/// request is already filling the key, or the fetch has been
+	/// replaced by a reload.
+	fn spawn_refresh(
+		&self,
+		req: &Request,
+		key: String,
+		entry: Arc<Entry>,
+		conn: &Arc<ConnContext>,
+		log: &Arc<dyn FlowLogSink>,
+	) {
+		let Some(this) = self.this.upgrade() else { return };
+		let Flight::Leader(guard) = cache::join(&key) else { return };
+		let (mut parts, ()) = http::Request::new(()).into_parts();
+		parts.uri = req.uri().clone();
+		parts.version = req.version();
+		parts.headers = req.headers().clone();
+		let (conn, log) = (Arc::clone(conn), Arc::clone(log));
+		tokio::spawn(async move {
+			let Some(spec) = &this.cache else { return };
+			let req = Request::from_parts(parts, Body::Empty);
+			let out = this.revalidate(spec, key, req, &entry, Some(guard), &conn, &log).await;
+			// A new response only reaches the store once its body has
+			// been read to the end.
+			if let Ok(L7FetchOutput::Response(resp)) = out {
+				let _ = resp.into_body().collect().await;
+			}
+		});
+	}
+}
+
+/// Wrap a storable upstream response so its body fills the store as it
+/// streams. Anything else passes through and releases `guard`.
+fn store_response(
+	spec: &CacheSpec,
+	key: String,
+	req: &http::request::Parts,
+	out: L7FetchOutput,
+	guard: Option<FillGuard>,
+) -> L7FetchOutput {
+	let L7FetchOutput::Response(resp) = out else { return out };
+	let (parts, body) = resp.into_parts();
+	let body = match cache::storable(spec, req, parts.status, &parts.headers) {
+		Some(pending) => {
+			Body::from_producer(FillBody::new(body, key, pending, spec.max_object_bytes, guard))
+		}
+		None => body,
+	};
+	L7FetchOutput::Response(http::Response::from_parts(parts, body))
+}
+
+fn tagged(mut out: L7FetchOutput, outcome: Outcome) -> L7FetchOutput {
+	if let L7FetchOutput::Response(resp) = &mut out {
+		resp.headers_mut().insert(X_CACHE, HeaderValue::from_static(outcome.as_str()));
+	}
+	outcome.record();
+	out
+}
+
+/// Answer from `entry`, honouring the client's own conditional headers.
+fn serve(
+	entry: &Entry,
+	req: &HeaderMap,
+	head: bool,
+	outcome: Outcome,
+) -> Result<L7FetchOutput, Error> {
+	entry.record_hit();
+	outcome.record();
+	let not_modified = entry.status == StatusCode::OK && not_modified(entry, req);
+	let mut resp = http::Response::builder()
+		.status(if not_modified { StatusCode::NOT_MODIFIED } else { entry.status })
+		.body(if head || not_modified { Body::Empty } else { Body::Static(entry.body.clone()) })
+		.map_err(|e| Error::protocol("cached response").with_source(e))?;
+	*resp.headers_mut() = entry.headers.clone();
+	resp.headers_mut().insert(AGE, HeaderValue::from(entry.age_secs()));
+	resp.headers_mut().insert(X_CACHE, HeaderValue::from_static(outcome.as_str()));
+	Ok(L7FetchOutput::Response(resp))
+}
+
+/// RFC 9110 §13.1.1 / §13.1.3: `If-None-Match` (weak comparison) wins;
+/// `If-Modified-Since` is only consulted without it.
+fn not_modified(entry: &Entry, req: &HeaderMap) -> bool {
+	if let Some(inm) = req.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+		let Some(etag) = entry.headers.get(ETAG).and_then(|v| v.to_str().ok()) else {
+			return false;
+		};
+		let weak = |t: &str| t.trim().trim_start_matches("W/").to_owned();
+		return inm.trim() == "*" || inm.split(',').any(|t| weak(t) == weak(etag));
+	}
+	let parse =
+		|v: &HeaderValue| -> Option<SystemTime> { httpdate::parse_http_date(v.to_str().ok()?).ok() };
+	match (
+		req.get(IF_MODIFIED_SINCE).and_then(parse),
+		entry.headers.get(LAST_MODIFIED).and_then(parse),
+	) {
+		(Some(since), Some(modified)) => modified <= since,
+		_ => false,
+	}
+}
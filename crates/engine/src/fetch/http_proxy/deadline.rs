@@ -0,0 +1,126 @@
+//! Time bounds on the upstream exchange past the response head:
+//! `args.body_read_idle_timeout` and the body half of
+//! `args.total_timeout`, plus the flow-log event every upstream
+//! timeout is classified by.
+//!
+//! A timeout before the head is answered `504` by
+//! [`HttpProxyFetch::fetch_upstream`](super::HttpProxyFetch); once the
+//! head has been relayed the status is gone, so [`DeadlineBody`] ends
+//! the stream with the timeout error instead.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use tokio::time::{Instant, Sleep};
+use vane_core::{
+	Body, ConnId, Error, FlowLogEvent, FlowLogKind, FlowLogSink, SerializedError, TimeoutKind,
+};
+
+use crate::time::now_unix_ms;
+
+/// Record an upstream timeout in the flow log of the request it ended.
+/// Always emitted, like the other milestone kinds; `error.reason`
+/// names the stage (`read` / `idle` / `total`).
+pub(super) fn emit_timeout(log: &dyn FlowLogSink, conn: ConnId, err: &Error) {
+	log.emit(FlowLogEvent {
+		t: now_unix_ms(),
+		conn,
+		seq: 0,
+		kind: FlowLogKind::Error,
+		node: None,
+		error: Some(Arc::new(SerializedError::from(err))),
+		data: None,
+	});
+}
+
+/// Wrap a streamed response body in the rule's body-phase bounds.
+/// `Static` / `Empty` bodies and rules without either bound pass
+/// through untouched.
+pub(super) fn bound(
+	body: Body,
+	idle: Option<Duration>,
+	deadline: Option<Instant>,
+	log: &Arc<dyn FlowLogSink>,
+	conn: ConnId,
+) -> Body {
+	match body {
+		Body::Stream(inner) if idle.is_some() || deadline.is_some() => {
+			Body::Stream(Box::pin(DeadlineBody {
+				inner,
+				idle: idle.map(|d| (d, Box::pin(tokio::time::sleep(d)))),
+				total: deadline.map(|at| Box::pin(tokio::time::sleep_until(at))),
+				log: Arc::clone(log),
+				conn,
+				expired: false,
+			}))
+		}
+		other => other,
+	}
+}
+
+/// Body stream that fails with `Timeout(Idle)` when no frame arrives
+/// within the idle bound (re-armed on every frame) and with
+/// `Timeout(Total)` at the exchange deadline.
+struct DeadlineBody {
+	inner: Pin<Box<dyn HttpBody<Data = Bytes, Error = Error> + Send + 'static>>,
+	idle: Option<(Duration, Pin<Box<Sleep>>)>,
+	total: Option<Pin<Box<Sleep>>>,
+	log: Arc<dyn FlowLogSink>,
+	conn: ConnId,
+	expired: bool,
+}
+
+impl DeadlineBody {
+	fn expire(&mut self, kind: TimeoutKind) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+		self.expired = true;
+		let err = Error::timeout(kind).with_ctx("upstream response body");
+		tracing::debug!(error = %err, conn = %self.conn, "upstream response body timed out");
+		emit_timeout(&*self.log, self.conn, &err);
+		Poll::Ready(Some(Err(err)))
+	}
+}
+
+impl HttpBody for DeadlineBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let this = self.get_mut();
+		if this.expired {
+			return Poll::Ready(None);
+		}
+		if this.total.as_mut().is_some_and(|t| t.as_mut().poll(cx).is_ready()) {
+			return this.expire(TimeoutKind::Total);
+		}
+		match this.inner.as_mut().poll_frame(cx) {
+			Poll::Ready(frame) => {
+				if let Some((d, timer)) = &mut this.idle {
+					timer.as_mut().reset(Instant::now() + *d);
+				}
+				Poll::Ready(frame)
+			}
+			Poll::Pending => {
+				if this.idle.as_mut().is_some_and(|(_, t)| t.as_mut().poll(cx).is_ready()) {
+					return this.expire(TimeoutKind::Idle);
+				}
+				Poll::Pending
+			}
+		}
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.expired || self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
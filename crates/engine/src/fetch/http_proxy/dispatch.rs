@@ -10,54 +10,104 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::Connect;
+use tokio::time::Instant;
+use tracing::Instrument as _;
 use vane_core::{
-	Body, ConnContext, Error, FlowCtx, L7Fetch, L7FetchOutput, Request, UpstreamReason,
+	Body, ConnContext, Error, ErrorKind, FetchFailure, FlowCtx, FlowLogEvent, FlowLogKind,
+	FlowLogSink, HedgeWinner, L7Fetch, L7FetchOutput, Request, StreamGuards, TimeoutKind,
+	UpstreamReason, timeout_with,
 };
 
-use super::{Dispatch, HttpProxyFetch, UpstreamVersion};
+use super::hedge::Replay;
+use super::{Dispatch, HttpProxyFetch, UpstreamVersion, deadline};
 use crate::body_adapter::IncomingAdapter;
-use crate::fetch::client_cache::ProxyClient;
+use crate::fetch::breaker::{Admit, Breaker, Outcome, Transition};
 use crate::fetch::pool;
+use crate::fetch::retry::{fits_deadline, hedge_budget, is_hedgeable};
+use crate::time::now_unix_ms;
 
 #[async_trait]
 impl L7Fetch for HttpProxyFetch {
 	async fn fetch(
 		&self,
-		mut req: Request,
-		_conn: &Arc<ConnContext>,
-		_ctx: &mut FlowCtx,
+		req: Request,
+		conn: &Arc<ConnContext>,
+		ctx: &mut FlowCtx,
 	) -> Result<L7FetchOutput, Error> {
-		// `max_concurrent_per_host` gate, per `spec/crates/engine.md`
-		// § _Exhaustion defaults (per upstream)_. Hyper-util's legacy
-		// client has no native semaphore, so the limiter is enforced
-		// here. The permit is held across the URI rewrite, retry
-		// loop, H3 dispatch, and the moment the response is handed
-		// back — releasing earlier would let saturated upstreams
-		// silently exceed the documented cap. Saturated waits beyond
-		// `pool::CONNECT_TIMEOUT` surface as `Unreachable` (503).
-		let _limit_permit = pool::limiter().acquire(&self.authority).await?;
+		let mut out = match &self.cache {
+			Some(spec) => self.fetch_cached(spec, req, conn, &ctx.log).await,
+			None => self.fetch_upstream(req, conn, &ctx.log).await,
+		}?;
+		if let L7FetchOutput::Response(resp) = &mut out
+			&& let Some(winner) = resp.extensions_mut().remove::<HedgeWinner>()
+		{
+			ctx.trajectory.record_hedge(winner);
+		}
+		Ok(out)
+	}
+}
 
-		// Compose the upstream URI from `scheme + authority` resolved
-		// once at factory time and the inbound path/query, refcounted
-		// where possible. For TCP (hyper-util Client) the connector
-		// reads `http://` / `https://` to pick cleartext vs TLS; for QUIC
-		// (h3 client) the scheme + authority become :scheme / :authority
-		// pseudo-headers so the upstream sees the rewritten target.
-		// `PathAndQuery` clones are refcounted; `Scheme` / `Authority`
-		// clones are `Bytes`-backed refcount bumps. No format!, no
-		// per-request parse — only the `Uri::builder` assembly cost.
-		let path_and_query = req
-			.uri()
-			.path_and_query()
-			.cloned()
-			.unwrap_or_else(|| http::uri::PathAndQuery::from_static("/"));
-		*req.uri_mut() = http::Uri::builder()
-			.scheme(self.scheme.clone())
-			.authority(self.authority.clone())
-			.path_and_query(path_and_query)
-			.build()
-			.map_err(|e| Error::protocol("upstream uri rewrite").with_source(e))?;
+impl HttpProxyFetch {
+	/// One trip to the upstream, bypassing the cache. The listener
+	/// drivers turn every fetch `Err` into a 500, so an open breaker
+	/// that survives member re-selection is answered here with the 503
+	/// it stands for, and a timeout before the response head with a
+	/// 504 plus the flow-log `Error` event that names its stage.
+	///
+	/// `total_timeout` starts here and covers the retry loop and then
+	/// the response body, which is wrapped in the body-phase bounds.
+	pub(super) async fn fetch_upstream(
+		&self,
+		req: Request,
+		conn: &Arc<ConnContext>,
+		log: &Arc<dyn FlowLogSink>,
+	) -> Result<L7FetchOutput, Error> {
+		let deadline = self.deadlines.total.map(|d| Instant::now() + d);
+		let attempt = self.fetch_with_retry(req, conn, &**log, deadline);
+		let result = match deadline {
+			Some(at) => tokio::time::timeout_at(at, attempt)
+				.await
+				.unwrap_or_else(|_| Err(Error::timeout(TimeoutKind::Total))),
+			None => attempt.await,
+		};
+		match result {
+			Ok(L7FetchOutput::Response(resp)) => {
+				let (parts, body) = resp.into_parts();
+				let body = deadline::bound(body, self.deadlines.body_read_idle, deadline, log, conn.id);
+				Ok(L7FetchOutput::Response(http::Response::from_parts(parts, body)))
+			}
+			Err(e) if matches!(e.kind(), ErrorKind::Upstream(UpstreamReason::CircuitOpen)) => {
+				tracing::debug!(error = %e, "failing fast on open circuit breaker");
+				let resp = http::Response::builder()
+					.status(http::StatusCode::SERVICE_UNAVAILABLE)
+					.extension(FetchFailure)
+					.body(Body::Empty)
+					.map_err(|e| Error::protocol("circuit-open response").with_source(e))?;
+				Ok(L7FetchOutput::Response(resp))
+			}
+			Err(e) if matches!(e.kind(), ErrorKind::Timeout(_)) => {
+				tracing::debug!(error = %e, "upstream timed out before the response head");
+				deadline::emit_timeout(&**log, conn.id, &e);
+				let resp = http::Response::builder()
+					.status(http::StatusCode::GATEWAY_TIMEOUT)
+					.extension(FetchFailure)
+					.body(Body::Empty)
+					.map_err(|e| Error::protocol("gateway-timeout response").with_source(e))?;
+				Ok(L7FetchOutput::Response(resp))
+			}
+			other => other,
+		}
+	}
 
+	async fn fetch_with_retry(
+		&self,
+		mut req: Request,
+		conn: &Arc<ConnContext>,
+		log: &dyn FlowLogSink,
+		deadline: Option<Instant>,
+	) -> Result<L7FetchOutput, Error> {
 		// Strip hop-by-hop headers (RFC 7230 §6.1) before any retry
 		// snapshot of the request. `HttpProxyFetch` does not handle
 		// WebSocket upgrades — that path is owned by
@@ -68,6 +118,10 @@ impl L7Fetch for HttpProxyFetch {
 		// See `fetch/hop_by_hop.rs`.
 		crate::fetch::hop_by_hop::strip_hop_by_hop_request(req.headers_mut());
 
+		// Derived once so every attempt of a sticky request lands on
+		// the same ring position; retries then walk past tried members.
+		let hash_key = self.upstreams.hash_key(req.headers(), conn.remote.ip());
+
 		// Snapshot the request body for replay. `Body::Stream` is
 		// one-shot — it collapses retry to a single attempt
 		// regardless of `max_attempts`. `Body::Static` clones via
@@ -78,8 +132,8 @@ impl L7Fetch for HttpProxyFetch {
 		// implemented earlier in the lower pass — by the time the
 		// fetch sees the request, a `force` policy has already
 		// converted the body to `Body::Static`. The TCP and H3 arms
-		// below share this snapshot — their retry semantics are
-		// symmetric per `spec/crates/engine.md` § _Retry_.
+		// share this snapshot — their retry semantics are symmetric
+		// per `spec/crates/engine.md` § _Retry_.
 		//
 		// `method_allowed` is the operator-configured whitelist
 		// (defaults to the RFC 9110 idempotent set). The per-attempt
@@ -96,38 +150,20 @@ impl L7Fetch for HttpProxyFetch {
 			Body::Stream(_) => None,
 		};
 		let max_attempts = if replay.is_some() && method_allowed { self.retry.max_attempts } else { 1 };
-		// Capture for the retry loop's per-error gate. Cloning a
-		// `Method` is cheap (small enum or `Arc<str>`-backed for
-		// custom verbs).
-		let method = req.method().clone();
-
-		// H3 dispatch: route through the QuicPool. Single-attempt path
-		// (streaming body, non-whitelisted method, or `max_attempts: 1`)
-		// short-circuits; the multi-attempt arm below mirrors the TCP
-		// path's loop using the per-request `Bytes` replay snapshot.
-		#[cfg(feature = "h3")]
-		if let Dispatch::Quic(quic) = &self.dispatch {
-			if max_attempts <= 1 {
-				return self.send_one_attempt_h3(req).await;
-			}
-			let (parts, _orig) = req.into_parts();
-			let replay = replay.expect("max_attempts > 1 implies replay snapshot");
-			return self.dispatch_h3_with_retry(parts, replay, max_attempts, quic).await;
+		// Hedging has its own, stricter gate: a replayable body and an
+		// idempotent method, whatever `methods` allows. Every eligible
+		// request pays into the hedge budget, hedged or not.
+		let hedge =
+			self.retry.hedge.as_ref().filter(|_| replay.is_some() && is_hedgeable(req.method()));
+		if hedge.is_some() {
+			hedge_budget().deposit();
 		}
 
-		// Normalise the outbound request version to the upstream's
-		// posture before any TCP-family send. The inbound version is
-		// whatever the *downstream* listener negotiated (HTTP/2 for an H2
-		// client); leaking it to an H1 upstream makes hyper-util's legacy
-		// client reject the request ("Connection is HTTP/1, but request
-		// requires HTTP/2"). The upstream protocol is independent of the
-		// client's — mirror the H3 path, which pins HTTP/3.
-		*req.version_mut() = self.tcp_request_version();
-
-		// Streaming or non-retryable-method TCP path: single attempt,
+		// Streaming or non-retryable-method path: single attempt,
 		// original body, no clones.
-		if max_attempts <= 1 {
-			return self.send_one_attempt_tcp(req).await;
+		if max_attempts <= 1 && hedge.is_none() {
+			let idx = self.upstreams.select(hash_key, &[], |i| self.member_available(i));
+			return self.send_to_member(idx, req, conn, log).await;
 		}
 
 		// Retryable path: rebuild the request from `(method, uri,
@@ -136,46 +172,58 @@ impl L7Fetch for HttpProxyFetch {
 		// set extensions don't survive retries (a fresh hyper request
 		// can't carry the inbound `OnUpgrade` future, and other
 		// extensions are typically per-request and shouldn't leak).
+		// Each attempt re-selects a member with the already-tried ones
+		// excluded, so a multi-member rule fails over instead of
+		// hammering the backend that just failed.
+		// A hedge is one more rebuilt attempt, sent to a member the loop
+		// has not tried yet.
 		let (parts, _orig_body) = req.into_parts();
-		let replay = replay.expect("max_attempts > 1 path requires replay snapshot");
-		let client = self.tcp_client_or_panic();
-
+		let replay =
+			Replay { parts, body: replay.expect("retry and hedge paths require a replay snapshot") };
+		let mut tried: Vec<usize> = Vec::with_capacity(self.members.len());
 		let mut last_err: Option<Error> = None;
 		for attempt in 1..=max_attempts {
-			let req_attempt =
-				http::Request::from_parts(clone_parts_for_retry(&parts), Body::Static(replay.clone()));
+			let idx = self.upstreams.select(hash_key, &tried, |i| self.member_available(i));
+			if !tried.contains(&idx) {
+				tried.push(idx);
+			}
 			// Per `spec/crates/engine.md` § _Error classification_,
 			// upstream 4xx/5xx (incl. 503/429) are not retry-eligible —
 			// they are complete responses, and a retry would duplicate
 			// the request. The `Retry-After` header is forwarded to the
-			// client unchanged via the response pass-through below.
-			match client.request(req_attempt).await {
-				Ok(resp) => {
-					let (mut parts, incoming) = resp.into_parts();
-					// Strip upstream's hop-by-hop headers before
-					// handing the response to the client. The 101
-					// switching-protocols exception is gated on
-					// status; `HttpProxyFetch` never produces a 101
-					// (upgrades go through `WebSocketUpgradeFetch`),
-					// but we evaluate the predicate honestly so the
-					// future plumbing matches.
-					let is_101 = parts.status == http::StatusCode::SWITCHING_PROTOCOLS;
-					crate::fetch::hop_by_hop::strip_hop_by_hop_response(&mut parts.headers, is_101);
-					let body = Body::Stream(Box::pin(IncomingAdapter::new(incoming)));
-					return Ok(L7FetchOutput::Response(http::Response::from_parts(parts, body)));
+			// client unchanged via the response pass-through.
+			let result = match hedge {
+				Some(policy) => {
+					let pick_hedge = || {
+						let idx = self.upstreams.select(hash_key, &tried, |i| self.member_available(i));
+						if !tried.contains(&idx) {
+							tried.push(idx);
+						}
+						idx
+					};
+					self.send_hedged(policy, idx, &replay, pick_hedge, conn, log).await
 				}
-				Err(e) => {
-					let err = Error::upstream(UpstreamReason::Unreachable).with_source(e);
+				None => self.send_to_member(idx, replay.request(), conn, log).await,
+			};
+			match result {
+				Ok(out) => return Ok(out),
+				Err(err) => {
 					tracing::debug!(
 						attempt,
 						max_attempts,
+						member = %self.members[idx].authority,
 						version = ?self.version,
 						"upstream request failed",
 					);
-					if attempt >= max_attempts || !err.is_retryable_in(&method) {
+					if attempt >= max_attempts || !err.is_retryable_in(&replay.parts.method) {
 						return Err(err);
 					}
+					// `total_timeout` is the retry budget's wall clock: a
+					// retry whose backoff would outlast it is not started.
 					let delay = self.retry.backoff.delay_for_attempt(attempt + 1);
+					if !fits_deadline(deadline, delay) {
+						return Err(err);
+					}
 					if !delay.is_zero() {
 						tokio::time::sleep(delay).await;
 					}
@@ -185,20 +233,138 @@ impl L7Fetch for HttpProxyFetch {
 		}
 		Err(last_err.expect("retry loop runs at least once"))
 	}
-}
 
-impl HttpProxyFetch {
-	/// Borrow the TCP-family client. Panics for the QUIC dispatch path —
-	/// the H3 arm short-circuits before the retry loop reaches this
-	/// helper, so the panic is unreachable in any well-typed code path.
-	fn tcp_client_or_panic(&self) -> &Arc<ProxyClient> {
-		match &self.dispatch {
-			Dispatch::Tcp(c) => c,
+	/// Members without a health check or breaker are always available.
+	fn member_available(&self, idx: usize) -> bool {
+		let member = &self.members[idx];
+		member.health.as_ref().is_none_or(|h| h.is_available())
+			&& member.breaker.as_ref().is_none_or(|b| !b.is_ejected())
+	}
+
+	/// One attempt against member `idx`, gated by its circuit breaker.
+	/// An ejected member fails fast with `CircuitOpen` (503) before any
+	/// permit or dial; admitted attempts report their outcome back.
+	pub(super) async fn send_to_member(
+		&self,
+		idx: usize,
+		req: Request,
+		conn: &ConnContext,
+		log: &dyn FlowLogSink,
+	) -> Result<L7FetchOutput, Error> {
+		let Some(breaker) = &self.members[idx].breaker else {
+			return self.dispatch_to_member(idx, req, conn).await;
+		};
+		let (admit, transition) = breaker.admit();
+		if let Some(t) = transition {
+			emit_breaker_transition(log, conn, breaker, &t);
+		}
+		if admit == Admit::Reject {
+			metrics::counter!("vane.upstream.circuit.rejected_total").increment(1);
+			return Err(
+				Error::upstream(UpstreamReason::CircuitOpen)
+					.with_ctx(format!("circuit open for {}", breaker.authority())),
+			);
+		}
+		let result = self.dispatch_to_member(idx, req, conn).await;
+		if let Some(t) = breaker.record(admit, Outcome::of(&result)) {
+			emit_breaker_transition(log, conn, breaker, &t);
+		}
+		result
+	}
+
+	/// One `upstream_attempt` span per attempt — the first try, each
+	/// retry and each hedge — with the attempt's `traceparent` forwarded
+	/// to the member (spec/crates/engine.md § _Distributed tracing_).
+	async fn dispatch_to_member(
+		&self,
+		idx: usize,
+		mut req: Request,
+		conn: &ConnContext,
+	) -> Result<L7FetchOutput, Error> {
+		let span = tracing::debug_span!("upstream_attempt", member = %self.members[idx].authority);
+		crate::trace_propagation::inject(&span, req.headers_mut());
+		self.send_via_member(idx, req, conn).instrument(span).await
+	}
+
+	/// Take the per-authority concurrency permit, count the request as
+	/// in flight, point the URI at member `idx` and hand off to the
+	/// member's transport family.
+	async fn send_via_member(
+		&self,
+		idx: usize,
+		mut req: Request,
+		conn: &ConnContext,
+	) -> Result<L7FetchOutput, Error> {
+		let member = &self.members[idx];
+
+		// `max_concurrent_per_host` gate, per `spec/crates/engine.md`
+		// § _Exhaustion defaults (per upstream)_. Hyper-util's legacy
+		// client has no native semaphore, so the limiter is enforced
+		// here. The permit is held until the response head is handed
+		// back — releasing earlier would let saturated upstreams
+		// silently exceed the documented cap. Saturated waits beyond
+		// `pool::CONNECT_TIMEOUT` surface as `Unreachable` (503).
+		let _limit_permit = pool::limiter().acquire(&member.authority).await?;
+		let in_flight = self.upstreams.enter(idx);
+		let start = std::time::Instant::now();
+
+		// Compose the upstream URI from `scheme + authority` resolved
+		// once at factory time and the inbound path/query, refcounted
+		// where possible. For TCP (hyper-util Client) the connector
+		// reads `http://` / `https://` to pick cleartext vs TLS; for QUIC
+		// (h3 client) the scheme + authority become :scheme / :authority
+		// pseudo-headers so the upstream sees the rewritten target.
+		// `PathAndQuery` clones are refcounted; `Scheme` / `Authority`
+		// clones are `Bytes`-backed refcount bumps. No format!, no
+		// per-request parse — only the `Uri::builder` assembly cost.
+		let path_and_query = req
+			.uri()
+			.path_and_query()
+			.cloned()
+			.unwrap_or_else(|| http::uri::PathAndQuery::from_static("/"));
+		*req.uri_mut() = http::Uri::builder()
+			.scheme(self.scheme.clone())
+			.authority(member.authority.clone())
+			.path_and_query(path_and_query)
+			.build()
+			.map_err(|e| Error::protocol("upstream uri rewrite").with_source(e))?;
+
+		let result = match &member.dispatch {
+			Dispatch::Tcp(client) => {
+				// Normalise the outbound request version to the upstream's
+				// posture. The inbound version is whatever the *downstream*
+				// listener negotiated (HTTP/2 for an H2 client); leaking it
+				// to an H1 upstream makes hyper-util's legacy client reject
+				// the request ("Connection is HTTP/1, but request requires
+				// HTTP/2"). The upstream protocol is independent of the
+				// client's — mirror the H3 path, which pins HTTP/3.
+				*req.version_mut() = self.tcp_request_version();
+				self.send_one_attempt_tcp(client, req).await
+			}
+			Dispatch::TcpProxied(proxied) => {
+				*req.version_mut() = self.tcp_request_version();
+				self.send_one_attempt_tcp(&proxied.client(conn), req).await
+			}
 			#[cfg(feature = "h3")]
-			Dispatch::Quic(_) => unreachable!(
-				"H3 dispatch routes through send_one_attempt_h3 above; tcp helper is unreachable",
-			),
+			Dispatch::Quic(quic) => {
+				Self::send_one_attempt_h3(quic, req, self.deadlines.response_header).await
+			}
+		};
+		// Feeds a percentile hedge delay; failed attempts say nothing
+		// about how long a response takes.
+		if let (Some(window), Ok(_)) = (&member.latency, &result) {
+			window.record(start.elapsed());
 		}
+		// The member stays loaded until the body has streamed, so
+		// `least_in_flight` / `p2c` see long downloads.
+		result.map(|out| match out {
+			L7FetchOutput::Response(mut rp) => {
+				let body = std::mem::replace(rp.body_mut(), Body::Empty);
+				*rp.body_mut() = StreamGuards::of(in_flight).attach(body);
+				L7FetchOutput::Response(rp)
+			}
+			tunnel @ L7FetchOutput::Tunnel(_) => tunnel,
+		})
 	}
 
 	/// HTTP version to stamp on the outbound TCP-family request, derived
@@ -214,26 +380,41 @@ impl HttpProxyFetch {
 		}
 	}
 
-	/// Single-attempt TCP-family path used when retry is disabled
-	/// (`max_attempts == 1`, streaming body, or method not in
-	/// whitelist). Skips the snapshot + clone work.
-	async fn send_one_attempt_tcp(&self, req: Request) -> Result<L7FetchOutput, Error> {
+	/// One TCP-family round-trip. Shared by the single-attempt path and
+	/// every iteration of the retry loop. `response_header_timeout`
+	/// bounds the whole `request` call, which includes the dial on a
+	/// pool miss.
+	async fn send_one_attempt_tcp<C>(
+		&self,
+		client: &Client<C, Body>,
+		req: Request,
+	) -> Result<L7FetchOutput, Error>
+	where
+		C: Connect + Clone + Send + Sync + 'static,
+	{
 		// Elapsed from call entry includes the connect time for connections
 		// that need to dial — the pooled client dials inside `request`.
 		// This is "request total elapsed including connect" rather than
 		// a pure connect measurement.
-		let client = self.tcp_client_or_panic();
 		let start = std::time::Instant::now();
-		let resp = client.request(req).await.map_err(|e| {
-			tracing::debug!(error = ?e, version = ?self.version, "upstream request failed");
-			Error::upstream(UpstreamReason::Unreachable).with_source(e)
-		})?;
+		let request = async {
+			client.request(req).await.map_err(|e| {
+				tracing::debug!(error = ?e, version = ?self.version, "upstream request failed");
+				Error::upstream(UpstreamReason::Unreachable).with_source(e)
+			})
+		};
+		let resp = match self.deadlines.response_header {
+			Some(limit) => timeout_with(TimeoutKind::Read, limit, request).await?,
+			None => request.await?,
+		};
 		metrics::histogram!("vane.upstream.connect.duration_ms", "kind" => "http_proxy")
 			.record(start.elapsed().as_secs_f64() * 1000.0);
 		let (mut parts, incoming) = resp.into_parts();
-		// Strip upstream hop-by-hop before relaying. See the retry
-		// loop above for the rationale; this is the single-attempt
-		// twin.
+		// Strip upstream hop-by-hop before relaying. The 101
+		// switching-protocols exception is gated on status;
+		// `HttpProxyFetch` never produces a 101 (upgrades go through
+		// `WebSocketUpgradeFetch`), but we evaluate the predicate
+		// honestly so the future plumbing matches.
 		let is_101 = parts.status == http::StatusCode::SWITCHING_PROTOCOLS;
 		crate::fetch::hop_by_hop::strip_hop_by_hop_response(&mut parts.headers, is_101);
 		// spec/crates/engine.md `spec/crates/engine.md` § _Concrete fetches_: never collect into `Body::Static`.
@@ -241,49 +422,6 @@ impl HttpProxyFetch {
 		Ok(L7FetchOutput::Response(http::Response::from_parts(parts, body)))
 	}
 
-	/// Multi-attempt H3 dispatch. Mirrors the TCP retry loop's shape:
-	/// rebuild the request from `(method, uri, version, headers)` plus
-	/// the cached `Bytes` snapshot on every attempt, run
-	/// `send_one_attempt_h3`, retry whenever
-	/// [`Error::is_retryable`] permits and `attempt < max_attempts`.
-	/// `quic_pool::evict` runs inside `send_one_attempt_h3` on dead
-	/// pool entries; the next loop iteration re-dials through
-	/// `get_or_dial`'s cache-miss path.
-	#[cfg(feature = "h3")]
-	async fn dispatch_h3_with_retry(
-		&self,
-		parts: http::request::Parts,
-		replay: Bytes,
-		max_attempts: u32,
-		_quic: &super::QuicDispatchState,
-	) -> Result<L7FetchOutput, Error> {
-		let mut last_err: Option<Error> = None;
-		for attempt in 1..=max_attempts {
-			let req_attempt =
-				http::Request::from_parts(clone_parts_for_retry(&parts), Body::Static(replay.clone()));
-			match self.send_one_attempt_h3(req_attempt).await {
-				Ok(out) => return Ok(out),
-				Err(err) => {
-					tracing::debug!(
-						attempt,
-						max_attempts,
-						version = ?self.version,
-						"upstream h3 request failed",
-					);
-					if attempt >= max_attempts || !err.is_retryable_in(&parts.method) {
-						return Err(err);
-					}
-					let delay = self.retry.backoff.delay_for_attempt(attempt + 1);
-					if !delay.is_zero() {
-						tokio::time::sleep(delay).await;
-					}
-					last_err = Some(err);
-				}
-			}
-		}
-		Err(last_err.expect("retry loop runs at least once"))
-	}
-
 	/// Single-attempt QUIC-family path. Resolves the dispatch's
 	/// `host:port` to a `SocketAddr`, composes the per-request `QuicFingerprint`,
 	/// acquires the pooled `h3::client::SendRequest` (dialing on miss),
@@ -291,14 +429,16 @@ impl HttpProxyFetch {
 	/// body wrapped in `Body::Stream(Box::pin(H3Body::new(...)))` per
 	/// `spec/crates/engine.md` § _Body streaming_ +
 	/// `spec/crates/engine.md` § _Concrete fetches_.
+	/// `response_header` bounds the wait for the response head once the
+	/// request body has been sent.
 	#[cfg(feature = "h3")]
-	async fn send_one_attempt_h3(&self, req: Request) -> Result<L7FetchOutput, Error> {
+	async fn send_one_attempt_h3(
+		quic: &super::QuicDispatchState,
+		req: Request,
+		response_header: Option<std::time::Duration>,
+	) -> Result<L7FetchOutput, Error> {
 		use http_body::Body as _;
 
-		let Dispatch::Quic(quic) = &self.dispatch else {
-			unreachable!("send_one_attempt_h3 called on non-QUIC dispatch")
-		};
-
 		let start = std::time::Instant::now();
 
 		// Per-fetch hickory resolver — same code path as the TCP family
@@ -407,14 +547,15 @@ impl HttpProxyFetch {
 			);
 		}
 
-		let resp_head = match stream.recv_response().await {
-			Ok(r) => r,
-			Err(e) => {
-				return Err(
-					Error::upstream(UpstreamReason::Unreachable)
-						.with_source(std::io::Error::other(format!("h3 recv_response: {e}"))),
-				);
-			}
+		let recv = async {
+			stream.recv_response().await.map_err(|e| {
+				Error::upstream(UpstreamReason::Unreachable)
+					.with_source(std::io::Error::other(format!("h3 recv_response: {e}")))
+			})
+		};
+		let resp_head = match response_header {
+			Some(limit) => timeout_with(TimeoutKind::Read, limit, recv).await?,
+			None => recv.await?,
 		};
 
 		// Normalise the response version. h3 sets it to HTTP/3.0, but
@@ -443,7 +584,7 @@ impl HttpProxyFetch {
 /// retry loop needs from the four fields that are individually
 /// `Clone`. `Extensions` is dropped on purpose — middleware-set
 /// extensions don't survive retries.
-fn clone_parts_for_retry(p: &http::request::Parts) -> http::request::Parts {
+pub(super) fn clone_parts_for_retry(p: &http::request::Parts) -> http::request::Parts {
 	let (mut new, _body) = http::Request::new(()).into_parts();
 	new.method = p.method.clone();
 	new.uri = p.uri.clone();
@@ -451,3 +592,28 @@ fn clone_parts_for_retry(p: &http::request::Parts) -> http::request::Parts {
 	new.headers = p.headers.clone();
 	new
 }
+
+/// Mirror a breaker transition into the flow log of the request that
+/// caused it. Always emitted, like the other milestone kinds —
+/// transitions are rare and are what alerting keys on.
+fn emit_breaker_transition(
+	log: &dyn FlowLogSink,
+	conn: &ConnContext,
+	breaker: &Breaker,
+	t: &Transition,
+) {
+	log.emit(FlowLogEvent {
+		t: now_unix_ms(),
+		conn: conn.id,
+		seq: 0,
+		kind: FlowLogKind::CircuitBreaker,
+		node: None,
+		error: None,
+		data: Some(serde_json::json!({
+			"upstream": breaker.authority(),
+			"from": t.from.as_str(),
+			"to": t.to.as_str(),
+			"ejection_ms": t.ejection.map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+		})),
+	});
+}
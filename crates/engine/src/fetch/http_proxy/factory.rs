@@ -11,16 +11,19 @@ use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::rt::{TokioExecutor, TokioTimer};
 use vane_core::{Body, FetchKind};
 
-use super::{Dispatch, HttpProxyFetch, UpstreamVersion};
+use super::proxied::ProxiedDispatch;
+use super::{Dispatch, HttpProxyFetch, Member, UpstreamVersion};
 #[cfg(feature = "h3")]
 use super::{H3_CONNECT_TIMEOUT_DEFAULT, QuicDispatchState};
 use crate::factories::{FactoryError, FetchFactories};
 use crate::fetch::client_cache::ClientFingerprint;
 use crate::fetch::dns::{DnsConfig, HickoryDnsResolver, parse_dns_args};
 use crate::fetch::pool;
-use crate::fetch::retry::RetryPolicy;
+use crate::fetch::retry::{HedgeDelay, LatencyWindow};
 use crate::fetch::upstream::{UpstreamTls, parse_tls_args};
+use crate::fetch::{balance, breaker, cache, health};
 use crate::flow_graph::FetchInst;
+use crate::proxy_header;
 
 /// Split an `args.upstream` `host:port` string into its parts. The
 /// returned host has surrounding brackets stripped (`[::1]` → `::1`)
@@ -55,35 +58,8 @@ fn build_client(
 	tls: Option<&UpstreamTls>,
 	dns: &DnsConfig,
 ) -> Client<HttpsConnector<HttpConnector<HickoryDnsResolver>>, Body> {
-	let tls_cfg = match tls {
-		Some(t) => Arc::clone(&t.client_config),
-		// Cleartext path never reaches the rustls handshake; supply a
-		// minimal default config so `HttpsConnectorBuilder` is happy.
-		// The connector picks the cleartext branch the moment it sees
-		// an `http://` URI.
-		None => Arc::new(
-			rustls::ClientConfig::builder()
-				.with_root_certificates(rustls::RootCertStore::empty())
-				.with_no_client_auth(),
-		),
-	};
-
-	// Resolver is built per Client; spec does not require global sharing
-	// and (version, tls, dns) tuples are bounded in production.
-	// Hickory's TTL cache lives inside this resolver instance.
-	let resolver = HickoryDnsResolver::build(dns).expect("build hickory resolver");
-	let mut http = HttpConnector::new_with_resolver(resolver);
-	// Permit https:// URIs through the inner connector — TLS is wrapped
-	// by hyper-rustls one layer up. Mirrors `HttpConnector::new`'s
-	// posture for the GaiResolver path.
-	http.enforce_http(false);
-	// Bound TCP connect at the SLA documented in
-	// `spec/crates/engine.md` § _Exhaustion defaults (per upstream)_.
-	// Without this, hyper-util defaults to no connect deadline and a
-	// slow DNS / route-blackhole upstream blocks fetches well past the
-	// 5 s SLA. TLS handshake happens above this layer and has its own
-	// budget threaded through `tokio-rustls`.
-	http.set_connect_timeout(Some(pool::CONNECT_TIMEOUT));
+	let tls_cfg = tls_config_or_cleartext(tls);
+	let http = http_connector(dns);
 
 	let connector_with_protocols =
 		hyper_rustls::HttpsConnectorBuilder::new().with_tls_config((*tls_cfg).clone()).https_or_http();
@@ -135,6 +111,42 @@ fn build_client(
 	builder.build(https)
 }
 
+/// The member's rustls config. The cleartext path never reaches the
+/// rustls handshake; it gets a minimal default config so the
+/// connector is happy, and picks the cleartext branch the moment it
+/// sees an `http://` URI.
+fn tls_config_or_cleartext(tls: Option<&UpstreamTls>) -> Arc<rustls::ClientConfig> {
+	match tls {
+		Some(t) => Arc::clone(&t.client_config),
+		None => Arc::new(
+			rustls::ClientConfig::builder()
+				.with_root_certificates(rustls::RootCertStore::empty())
+				.with_no_client_auth(),
+		),
+	}
+}
+
+/// TCP connector under the TLS layer, resolving through `dns`.
+fn http_connector(dns: &DnsConfig) -> HttpConnector<HickoryDnsResolver> {
+	// Resolver is built per Client; spec does not require global sharing
+	// and (version, tls, dns) tuples are bounded in production.
+	// Hickory's TTL cache lives inside this resolver instance.
+	let resolver = HickoryDnsResolver::build(dns).expect("build hickory resolver");
+	let mut http = HttpConnector::new_with_resolver(resolver);
+	// Permit https:// URIs through the inner connector — TLS is wrapped
+	// by hyper-rustls one layer up. Mirrors `HttpConnector::new`'s
+	// posture for the GaiResolver path.
+	http.enforce_http(false);
+	// Bound TCP connect at the SLA documented in
+	// `spec/crates/engine.md` § _Exhaustion defaults (per upstream)_.
+	// Without this, hyper-util defaults to no connect deadline and a
+	// slow DNS / route-blackhole upstream blocks fetches well past the
+	// 5 s SLA. TLS handshake happens above this layer and has its own
+	// budget threaded through `tokio-rustls`.
+	http.set_connect_timeout(Some(pool::CONNECT_TIMEOUT));
+	http
+}
+
 /// Fork point for `args.upstream_kind` (injected by the alias-
 /// resolution layer in `vane_core::rule::TerminateSpec`, see
 /// `spec/crates/engine.md` § _Concrete fetches_): socket-based aliases produce
@@ -165,25 +177,49 @@ fn dispatch_upstream_kind(args: &serde_json::Value) -> Option<Result<FetchInst,
 ///
 /// ```json
 /// {
-///   "upstream": "host:port",
-///   "version":  "auto" | "h1" | "h2" | "h3",
+///   "upstream":  "host:port",
+///   "upstreams": ["host:port", { "upstream": "host:port", "weight": 3 }],
+///   "lb":        "round_robin" | { "policy": "consistent_hash", "hash_on": "cookie:sid" },
+///   "version":   "auto" | "h1" | "h2" | "h3",
 ///   "tls": {
 ///     "verify_hostname":      "api.example.com",
 ///     "insecure_skip_verify": false
-///   }
+///   },
+///   "health_check": { "type": "http", "path": "/healthz", "interval": "10s" },
+///   "circuit_breaker": { "consecutive_failures": 5, "base_ejection": "10s" },
+///   "cache": { "key": { "query": true }, "stale_if_error": "5m" },
+///   "send_proxy_protocol": "v1" | "v2",
+///   "response_header_timeout": "30s",
+///   "body_read_idle_timeout":  "60s",
+///   "total_timeout":           "5m"
 /// }
 /// ```
 ///
-/// `version` defaults to `"auto"`. `"h3"` is reserved for the future
-/// `h3` cargo feature; factories on builds without it return an
-/// error pointing operators at the right rebuild flag. `tls` is
-/// optional — absent means cleartext upstream.
+/// Exactly one of `upstream` / `upstreams` is required; `lb` defaults
+/// to `"round_robin"` and only matters with more than one member.
+/// `version` defaults to `"auto"`. `"h3"` requires the `h3` cargo
+/// feature; factories on builds without it return an error pointing
+/// operators at the right rebuild flag. `tls` is optional — absent
+/// means cleartext upstream — and applies to every member.
+/// `health_check` is optional and probes every member independently;
+/// see [`health::parse`] for its shape. `circuit_breaker` is optional
+/// and trips per member; see [`breaker::parse`]. `cache` opts the rule
+/// into the shared response cache; see [`cache::parse`].
+/// `send_proxy_protocol` prefixes every upstream connection with a
+/// PROXY header and turns off connection reuse; see [`super::proxied`].
+/// The three timeouts are optional and unbounded when absent; see
+/// [`crate::fetch::retry::Deadlines`].
 ///
 /// # Errors
-/// Returns [`FactoryError`] when `upstream` is missing/empty, when
-/// `version` is not one of the four accepted strings, when
-/// `version: "h3"` is requested on a build without the `h3` feature,
-/// or when the TLS client config fails to build.
+/// Returns [`FactoryError`] when the member list is missing, empty or
+/// malformed, when `lb` is not a known policy, when `version` is not
+/// one of the four accepted strings, when `version: "h3"` is requested
+/// on a build without the `h3` feature, when the TLS client config
+/// fails to build, when `health_check` is malformed or unsupported
+/// for the chosen version / TLS posture, when `circuit_breaker` or
+/// `cache` is malformed, when `send_proxy_protocol` is not
+/// `"v1"` / `"v2"` or combined with `version: "h3"`, or when a timeout
+/// is not a positive duration string.
 pub fn factory(
 	args: &serde_json::Value,
 	crl_cache: Option<&Arc<crate::tls::CrlCache>>,
@@ -191,36 +227,125 @@ pub fn factory(
 	if let Some(out) = dispatch_upstream_kind(args) {
 		return out;
 	}
-	let upstream = args.get("upstream").and_then(serde_json::Value::as_str).ok_or_else(|| {
-		FactoryError::Invalid("missing args.upstream (string \"host:port\")".to_string())
-	})?;
-	if upstream.is_empty() {
-		return Err(FactoryError::Invalid("args.upstream must not be empty".to_string()));
-	}
+	let specs = balance::parse_members(args).map_err(FactoryError::Invalid)?;
+	let policy = balance::parse_policy(args.get("lb"))
+		.map_err(|e| FactoryError::Invalid(format!("args.lb: {e}")))?;
 	let version = parse_version_arg(args)?;
-	let tls = parse_tls_args(upstream, args.get("tls"), crl_cache)
-		.map_err(|e| FactoryError::Invalid(format!("args.tls: {e}")))?;
 	let dns =
 		parse_dns_args(args.get("dns")).map_err(|e| FactoryError::Invalid(format!("args.dns: {e}")))?;
+	let retry = crate::fetch::retry::parse(args.get("retry"))
+		.map_err(|e| FactoryError::Invalid(format!("args.retry: {e}")))?;
+	let deadlines = crate::fetch::retry::parse_deadlines(args).map_err(FactoryError::Invalid)?;
+	let health_check = health::parse(args.get("health_check"))
+		.map_err(|e| FactoryError::Invalid(format!("args.health_check: {e}")))?;
+	let circuit_breaker = breaker::parse(args.get("circuit_breaker"))
+		.map_err(|e| FactoryError::Invalid(format!("args.circuit_breaker: {e}")))?;
+	let cache = cache::parse(args.get("cache"))
+		.map_err(|e| FactoryError::Invalid(format!("args.cache: {e}")))?;
+	let send_proxy_protocol = proxy_header::parse_send_arg(args)?;
+	#[cfg(feature = "h3")]
+	if send_proxy_protocol.is_some() && matches!(version, UpstreamVersion::Http3) {
+		return Err(FactoryError::Invalid(
+			"args.send_proxy_protocol is not supported with version: \"h3\"".to_string(),
+		));
+	}
 
-	if matches!(version, UpstreamVersion::Auto) && tls.is_none() {
+	let is_tls = args.get("tls").is_some();
+	if let Some(hc) = &health_check {
+		check_health_args(hc, version, is_tls)?;
+	}
+	if matches!(version, UpstreamVersion::Auto) && !is_tls {
 		// Cleartext has no ALPN to negotiate on, so `auto` collapses
 		// to H1. Surface the degradation so operators who actually
 		// wanted h2c add `version: "h2"` explicitly.
 		tracing::warn!(
-			upstream,
+			upstream = specs[0].upstream,
 			"cleartext upstream + version=auto: no ALPN to negotiate, falling back to h1; \
 			 set version: h2 explicitly for prior-knowledge h2c",
 		);
 	}
 
-	let retry = crate::fetch::retry::parse(args.get("retry"))
-		.map_err(|e| FactoryError::Invalid(format!("args.retry: {e}")))?;
+	// A percentile hedge delay reads each member's own latency window.
+	let track_latency =
+		retry.hedge.as_ref().is_some_and(|h| matches!(h.delay, HedgeDelay::Percentile(_)));
+	let mut members = Vec::with_capacity(specs.len());
+	for spec in &specs {
+		let upstream = spec.upstream.as_str();
+		let tls = parse_tls_args(upstream, args.get("tls"), crl_cache)
+			.map_err(|e| FactoryError::Invalid(format!("args.tls: {e}")))?;
+		let authority: http::uri::Authority = upstream.parse().map_err(|e| {
+			FactoryError::Invalid(format!("args.upstream {upstream:?}: invalid authority: {e}"))
+		})?;
+		let health = health_check.as_ref().map(|hc| health::watch(upstream, hc, tls.as_ref()));
+		let breaker = circuit_breaker.as_ref().map(|cb| breaker::get_or_build(upstream, cb));
+		let dispatch = build_dispatch(args, upstream, version, tls, &dns, send_proxy_protocol)?;
+		let latency = track_latency.then(LatencyWindow::new);
+		members.push(Member { authority, dispatch, health, breaker, latency });
+	}
+	let upstreams = balance::get_or_build(
+		policy,
+		members.iter().zip(&specs).map(|(m, s)| (m.authority.clone(), s.weight)).collect(),
+	);
+
+	let scheme = if is_tls { http::uri::Scheme::HTTPS } else { http::uri::Scheme::HTTP };
+	let members = members.into_boxed_slice();
+	let retry = Arc::new(retry);
+	Ok(FetchInst::L7(Arc::new_cyclic(|this| HttpProxyFetch {
+		version,
+		scheme,
+		members,
+		upstreams,
+		retry,
+		deadlines,
+		cache,
+		this: this.clone(),
+	})))
+}
 
+/// Reject probe shapes the rule cannot carry: a `tls` probe needs
+/// `args.tls`, and H3 upstreams listen on UDP where none of the TCP
+/// probes mean anything.
+fn check_health_args(
+	hc: &health::HealthCheckSpec,
+	version: UpstreamVersion,
+	is_tls: bool,
+) -> Result<(), FactoryError> {
 	#[cfg(feature = "h3")]
 	if matches!(version, UpstreamVersion::Http3) {
-		return build_h3_dispatch(args, upstream, version, tls, &dns, retry);
+		return Err(FactoryError::Invalid(
+			"args.health_check is not supported with version: \"h3\"".to_string(),
+		));
+	}
+	#[cfg(not(feature = "h3"))]
+	let _ = version;
+	if matches!(hc.kind, health::ProbeKind::Tls) && !is_tls {
+		return Err(FactoryError::Invalid(
+			"args.health_check: type 'tls' requires args.tls".to_string(),
+		));
 	}
+	Ok(())
+}
+
+/// Build one member's pooled dispatch. H3 goes through the QUIC pool;
+/// everything else resolves a cached `legacy::Client` by fingerprint,
+/// so members with the same TLS posture share one client and differ
+/// only in the authority each request is pointed at. With
+/// `send_proxy_protocol` there is no pool to share: the member keeps
+/// the pieces to build an unpooled client per request.
+fn build_dispatch(
+	args: &serde_json::Value,
+	upstream: &str,
+	version: UpstreamVersion,
+	tls: Option<UpstreamTls>,
+	dns: &DnsConfig,
+	send_proxy_protocol: Option<proxy_protocol::Version>,
+) -> Result<Dispatch, FactoryError> {
+	#[cfg(feature = "h3")]
+	if matches!(version, UpstreamVersion::Http3) {
+		return build_h3_dispatch(args, upstream, tls, dns);
+	}
+	#[cfg(not(feature = "h3"))]
+	let _ = (args, upstream);
 
 	// TCP family — compute the cache key. The connector wires ALPN
 	// via `enable_http1` / `enable_http2`, which is `version`-driven,
@@ -235,30 +360,27 @@ pub fn factory(
 		#[cfg(feature = "h3")]
 		UpstreamVersion::Http3 => unreachable!("H3 dispatch returns above"),
 	};
+	if let Some(proxy_protocol) = send_proxy_protocol {
+		let mut tls_cfg = (*tls_config_or_cleartext(tls.as_ref())).clone();
+		tls_cfg.alpn_protocols = alpn_protocols;
+		return Ok(Dispatch::TcpProxied(ProxiedDispatch::new(
+			http_connector(dns),
+			Arc::new(tls_cfg),
+			version,
+			proxy_protocol,
+		)));
+	}
 	let tls_fp = tls.as_ref().map(|t| {
 		let mut fp = t.fingerprint.clone();
 		fp.alpn_protocols = alpn_protocols;
 		fp
 	});
 	let client_fp = ClientFingerprint { version, tls: tls_fp, dns: dns.clone() };
-	let tls_for_build = tls.clone();
 	let dns_for_build = dns.clone();
 	let client = crate::fetch::client_cache::get_or_build(client_fp, move || {
-		build_client(version, tls_for_build.as_ref(), &dns_for_build)
+		build_client(version, tls.as_ref(), &dns_for_build)
 	});
-
-	let scheme = if tls.is_some() { http::uri::Scheme::HTTPS } else { http::uri::Scheme::HTTP };
-	let authority: http::uri::Authority = upstream.parse().map_err(|e| {
-		FactoryError::Invalid(format!("args.upstream {upstream:?}: invalid authority: {e}"))
-	})?;
-
-	Ok(FetchInst::L7(Arc::new(HttpProxyFetch {
-		version,
-		scheme,
-		authority,
-		dispatch: Dispatch::Tcp(client),
-		retry: Arc::new(retry),
-	})))
+	Ok(Dispatch::Tcp(client))
 }
 
 /// Plug `FetchKind::HttpProxy` into a `FetchFactories` registry. The
@@ -289,20 +411,18 @@ fn parse_version_arg(args: &serde_json::Value) -> Result<UpstreamVersion, Factor
 	}
 }
 
-/// Build the H3 dispatch state and wrap it as a [`FetchInst::L7`].
-/// TLS is mandatory (RFC 9114 mandates QUIC + TLS 1.3); cleartext H3
-/// is rejected at factory time. The rustls config is cloned and ALPN
-/// is pinned to `[b"h3"]` since the QUIC pool embeds ALPN into the
-/// rustls config (vs the hyper-rustls connector's `enable_httpN`).
+/// Build one member's H3 dispatch state. TLS is mandatory (RFC 9114
+/// mandates QUIC + TLS 1.3); cleartext H3 is rejected at factory time.
+/// The rustls config is cloned and ALPN is pinned to `[b"h3"]` since
+/// the QUIC pool embeds ALPN into the rustls config (vs the
+/// hyper-rustls connector's `enable_httpN`).
 #[cfg(feature = "h3")]
 fn build_h3_dispatch(
 	args: &serde_json::Value,
 	upstream: &str,
-	version: UpstreamVersion,
 	tls: Option<UpstreamTls>,
 	dns: &DnsConfig,
-	retry: RetryPolicy,
-) -> Result<FetchInst, FactoryError> {
+) -> Result<Dispatch, FactoryError> {
 	let tls = tls.ok_or_else(|| {
 		FactoryError::Invalid("version 'h3' requires args.tls (h3 mandates QUIC + TLS 1.3)".to_string())
 	})?;
@@ -320,7 +440,7 @@ fn build_h3_dispatch(
 		.map_err(|e| FactoryError::Invalid(format!("args.dns hickory build: {e}")))?;
 	let (host, port) = split_host_port(upstream)
 		.map_err(|e| FactoryError::Invalid(format!("args.upstream {upstream:?}: {e}")))?;
-	let dispatch = Dispatch::Quic(QuicDispatchState {
+	Ok(Dispatch::Quic(QuicDispatchState {
 		rustls_cfg: h3_rustls,
 		sni: Arc::from(tls.verify_hostname.as_str()),
 		tls_fp,
@@ -328,17 +448,7 @@ fn build_h3_dispatch(
 		resolver: Arc::new(resolver),
 		host: Arc::from(host.as_str()),
 		port,
-	});
-	let authority: http::uri::Authority = upstream.parse().map_err(|e| {
-		FactoryError::Invalid(format!("args.upstream {upstream:?}: invalid authority: {e}"))
-	})?;
-	Ok(FetchInst::L7(Arc::new(HttpProxyFetch {
-		version,
-		scheme: http::uri::Scheme::HTTPS,
-		authority,
-		dispatch,
-		retry: Arc::new(retry),
-	})))
+	}))
 }
 
 #[cfg(test)]
@@ -481,6 +591,43 @@ mod tests {
 		assert!(result.is_ok(), "h2 cleartext (h2c) must build");
 	}
 
+	#[test]
+	fn factory_builds_one_member_per_upstreams_entry() {
+		install_crypto();
+		let Ok(FetchInst::L7(_)) = factory(
+			&serde_json::json!({
+				"upstreams": ["127.0.0.1:9001", { "upstream": "127.0.0.1:9002", "weight": 2 }],
+				"lb": "least_in_flight",
+			}),
+			None,
+		) else {
+			panic!("multi-member rule must build");
+		};
+	}
+
+	#[test]
+	fn factory_rejects_unknown_lb_policy() {
+		install_crypto();
+		let Err(FactoryError::Invalid(msg)) =
+			factory(&serde_json::json!({ "upstreams": ["127.0.0.1:9001"], "lb": "fastest" }), None)
+		else {
+			panic!("unknown policy must be rejected");
+		};
+		assert!(msg.starts_with("args.lb"), "{msg}");
+	}
+
+	#[test]
+	fn factory_rejects_malformed_timeout() {
+		install_crypto();
+		let Err(FactoryError::Invalid(msg)) = factory(
+			&serde_json::json!({ "upstream": "127.0.0.1:9001", "response_header_timeout": "soon" }),
+			None,
+		) else {
+			panic!("unparseable timeout must be rejected");
+		};
+		assert!(msg.starts_with("args.response_header_timeout"), "{msg}");
+	}
+
 	#[cfg(feature = "h3")]
 	#[test]
 	fn split_host_port_accepts_ipv4() {
@@ -0,0 +1,94 @@
+//! `args.retry.hedge`: race a second attempt against a first one that
+//! has not produced a response head within the hedge delay.
+//!
+//! The hedge goes to another member when the set has one (the retry
+//! loop's `tried` list is the exclusion), otherwise to the same member
+//! on a fresh pooled connection. Whichever attempt answers first wins;
+//! the other future is dropped, which cancels its upstream request.
+//! The winner is tagged on the response as a [`HedgeWinner`]
+//! extension, which [`super::HttpProxyFetch`]'s `fetch` moves into the
+//! flow's trajectory.
+
+use bytes::Bytes;
+use vane_core::{Body, ConnContext, Error, FlowLogSink, HedgeWinner, L7FetchOutput, Request};
+
+use super::HttpProxyFetch;
+use super::dispatch::clone_parts_for_retry;
+use crate::fetch::retry::{HedgePolicy, hedge_budget};
+
+/// The request a retried or hedged attempt is rebuilt from.
+pub(super) struct Replay {
+	pub(super) parts: http::request::Parts,
+	pub(super) body: Bytes,
+}
+
+impl Replay {
+	pub(super) fn request(&self) -> Request {
+		http::Request::from_parts(clone_parts_for_retry(&self.parts), Body::Static(self.body.clone()))
+	}
+}
+
+impl HttpProxyFetch {
+	/// One attempt against `primary`, hedged per `policy`. `pick_hedge`
+	/// selects the hedge's member and is only called once the delay has
+	/// passed and the daemon-wide budget granted a hedge.
+	pub(super) async fn send_hedged(
+		&self,
+		policy: &HedgePolicy,
+		primary: usize,
+		replay: &Replay,
+		pick_hedge: impl FnOnce() -> usize,
+		conn: &ConnContext,
+		log: &dyn FlowLogSink,
+	) -> Result<L7FetchOutput, Error> {
+		// Both attempts live on the heap: two inline dispatch futures
+		// would double the size of every `http_proxy` fetch future.
+		let mut first = Box::pin(self.send_to_member(primary, replay.request(), conn, log));
+		// A percentile delay with too few samples yet means no hedge.
+		let Some(delay) = policy.delay.resolve(self.members[primary].latency.as_ref()) else {
+			return first.await;
+		};
+		tokio::select! {
+			result = &mut first => return result,
+			() = tokio::time::sleep(delay) => {}
+		}
+		if !hedge_budget().try_withdraw() {
+			metrics::counter!("vane.upstream.hedge_total", "outcome" => "budget_exhausted").increment(1);
+			return first.await;
+		}
+
+		let idx = pick_hedge();
+		tracing::debug!(
+			delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+			member = %self.members[idx].authority,
+			"hedging slow upstream attempt",
+		);
+		let mut second = Box::pin(self.send_to_member(idx, replay.request(), conn, log));
+		// First success wins and drops the other future; a failure waits
+		// on the other attempt, so a hedge never answers worse than the
+		// request alone would have.
+		let (winner, result) = tokio::select! {
+			result = &mut first => match result {
+				Ok(out) => (HedgeWinner::Primary, Ok(out)),
+				Err(_) => (HedgeWinner::Hedge, second.await),
+			},
+			result = &mut second => match result {
+				Ok(out) => (HedgeWinner::Hedge, Ok(out)),
+				Err(_) => (HedgeWinner::Primary, first.await),
+			},
+		};
+		let outcome = match (&result, winner) {
+			(Err(_), _) => "failed",
+			(Ok(_), HedgeWinner::Primary) => "primary",
+			(Ok(_), HedgeWinner::Hedge) => "hedge",
+		};
+		metrics::counter!("vane.upstream.hedge_total", "outcome" => outcome).increment(1);
+		result.map(|out| match out {
+			L7FetchOutput::Response(mut resp) => {
+				resp.extensions_mut().insert(winner);
+				L7FetchOutput::Response(resp)
+			}
+			other @ L7FetchOutput::Tunnel(_) => other,
+		})
+	}
+}
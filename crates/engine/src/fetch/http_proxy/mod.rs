@@ -27,7 +27,7 @@
 //! | `h3`      | ALPN: only `h3` (TLS req'd) | rejected (h3 mandates QUIC TLS) |
 //!
 //! See `spec/crates/engine.md` `spec/crates/engine.md` § _Concrete fetches_,
-//! `spec/crates/engine.md` § _Body streaming_, § _Upstream pools_,
+//! `spec/crates/engine.md` § _Body streaming_, § _Upstream pools_, § _Load balancing_,
 //! and `spec/crates/engine-tls.md` § _Library policy_.
 //!
 //! ## Module layout
@@ -37,19 +37,38 @@
 //! - `dispatch` — `impl L7Fetch for HttpProxyFetch` and the
 //!   per-request send / receive helpers (TCP retry loop + H3
 //!   send-body + recv-response pump).
+//! - `proxied` — the `args.send_proxy_protocol` dispatch: an
+//!   unpooled per-request client whose connector writes a PROXY
+//!   header onto each upstream connection.
+//! - `deadline` — the response-body half of the rule's time bounds
+//!   (`body_read_idle_timeout`, `total_timeout`) and the flow-log
+//!   event that classifies every upstream timeout.
+//! - `hedge` — the `args.retry.hedge` race: a second attempt sent
+//!   after the hedge delay, first response wins.
+//! - `cached` — the `args.cache` path in front of `dispatch`: lookup,
+//!   conditional revalidation, stale serving and miss coalescing
+//!   against the daemon-wide [`crate::fetch::cache`] store.
 //! - `factory` — args parsing, `build_client`, `build_h3_dispatch`,
 //!   the public `factory` / `register` entry points, and the factory
 //!   test suite. Public re-exports flow back through this module so
 //!   downstream callers continue to import
 //!   `crate::fetch::http_proxy::{factory, register}`.
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
+use crate::fetch::balance::UpstreamSet;
+use crate::fetch::breaker::Breaker;
+use crate::fetch::cache::CacheSpec;
 use crate::fetch::client_cache::ProxyClient;
-use crate::fetch::retry::RetryPolicy;
+use crate::fetch::health::HealthTarget;
+use crate::fetch::retry::{Deadlines, LatencyWindow, RetryPolicy};
 
+mod cached;
+mod deadline;
 mod dispatch;
 mod factory;
+mod hedge;
+mod proxied;
 
 pub use factory::{factory, register};
 
@@ -70,6 +89,11 @@ pub enum UpstreamVersion {
 /// or `Quic` (via [`crate::fetch::quic_pool`] for the H3 path). Each
 /// family owns its own pooling discipline; see the module-level
 /// docstring for the full posture matrix.
+///
+/// A single `args.upstream` is a one-member set; `args.upstreams`
+/// builds one [`Member`] per entry, each with its own pooled dispatch,
+/// and [`crate::fetch::balance::UpstreamSet`] picks among them per
+/// attempt.
 pub struct HttpProxyFetch {
 	pub(super) version: UpstreamVersion,
 	/// `http::uri::Scheme` resolved once at factory time. Both
@@ -78,19 +102,42 @@ pub struct HttpProxyFetch {
 	/// `format!`-driven URI rebuild is avoided in favour of
 	/// `Uri::builder().scheme(...).authority(...)`.
 	pub(super) scheme: http::uri::Scheme,
-	/// `http::uri::Authority` parsed once at factory time and
-	/// `Clone`d per request (`Authority` wraps a refcounted `Bytes`).
+	/// Indexed in `args.upstreams` order, matching `upstreams`.
+	pub(super) members: Box<[Member]>,
+	pub(super) upstreams: Arc<UpstreamSet>,
+	pub(super) retry: Arc<RetryPolicy>,
+	/// `args.response_header_timeout` / `body_read_idle_timeout` /
+	/// `total_timeout`.
+	pub(super) deadlines: Deadlines,
+	/// `args.cache`; `None` sends every request upstream.
+	pub(super) cache: Option<CacheSpec>,
+	/// Back-reference for the background `stale-while-revalidate`
+	/// refresh, which outlives the request that triggered it.
+	pub(super) this: Weak<Self>,
+}
+
+/// One upstream member. `authority` is parsed once at factory time and
+/// `Clone`d per request (`Authority` wraps a refcounted `Bytes`).
+pub(super) struct Member {
 	pub(super) authority: http::uri::Authority,
 	pub(super) dispatch: Dispatch,
-	pub(super) retry: Arc<RetryPolicy>,
+	/// Active health-check target when `args.health_check` is set.
+	pub(super) health: Option<Arc<HealthTarget>>,
+	/// Passive breaker when `args.circuit_breaker` is set.
+	pub(super) breaker: Option<Arc<Breaker>>,
+	/// Recent response-head latencies, kept only when
+	/// `args.retry.hedge.delay` is a percentile.
+	pub(super) latency: Option<LatencyWindow>,
 }
 
 /// Per-version dispatch state. `Tcp` carries the cached pooled
-/// `legacy::Client`; `Quic` carries the rustls config the QUIC pool
+/// `legacy::Client`; `TcpProxied` the pieces for a per-request
+/// unpooled client when `args.send_proxy_protocol` is set; `Quic` carries the rustls config the QUIC pool
 /// needs at dial time plus the SNI / TLS-fingerprint pieces the
 /// per-request fingerprint composes from.
 pub(super) enum Dispatch {
 	Tcp(Arc<ProxyClient>),
+	TcpProxied(proxied::ProxiedDispatch),
 	#[cfg(feature = "h3")]
 	Quic(QuicDispatchState),
 }
@@ -0,0 +1,128 @@
+//! `args.send_proxy_protocol` dispatch: every upstream connection
+//! opens with a PROXY header naming the request's client, so it can
+//! only ever carry that client's requests. The per-member pooled
+//! `legacy::Client` is replaced by one pooled client per (member,
+//! downstream connection), stashed in `ConnContext.user`: the header is
+//! fixed for the connection's lifetime, so its keep-alive requests
+//! reuse the upstream connection, and the pool goes away with it. The
+//! connector writes the header right after the TCP connect (before the
+//! TLS handshake, which the header precedes on the wire).
+//!
+//! See `spec/crates/engine.md` § _PROXY protocol_.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tower_service::Service;
+use vane_core::{Body, ConnContext};
+
+use super::UpstreamVersion;
+use crate::fetch::dns::HickoryDnsResolver;
+use crate::fetch::pool;
+use crate::proxy_header;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub(super) type ProxiedClient = Client<HttpsConnector<HeaderConnector>, Body>;
+
+/// Distinguishes members in a connection's [`ConnClients`]; never
+/// reused, so a member built by a reload cannot pick up a client for
+/// the member it replaced.
+static NEXT_DISPATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Factory-time state for one member. `tls_cfg` already carries the
+/// version's ALPN list: the per-connection connector is built with
+/// `HttpsConnector::from`, which skips the builder's ALPN wiring.
+pub(crate) struct ProxiedDispatch {
+	id: u64,
+	http: HttpConnector<HickoryDnsResolver>,
+	tls_cfg: Arc<rustls::ClientConfig>,
+	version: UpstreamVersion,
+	proxy_protocol: proxy_protocol::Version,
+}
+
+/// The downstream connection's clients, keyed by [`ProxiedDispatch::id`].
+#[derive(Clone, Default)]
+struct ConnClients(HashMap<u64, ProxiedClient>);
+
+impl ProxiedDispatch {
+	pub(super) fn new(
+		http: HttpConnector<HickoryDnsResolver>,
+		tls_cfg: Arc<rustls::ClientConfig>,
+		version: UpstreamVersion,
+		proxy_protocol: proxy_protocol::Version,
+	) -> Self {
+		Self {
+			id: NEXT_DISPATCH_ID.fetch_add(1, Ordering::Relaxed),
+			http,
+			tls_cfg,
+			version,
+			proxy_protocol,
+		}
+	}
+
+	/// Client whose connections announce `conn`'s client, shared by
+	/// every request on `conn` to this member. Its idle connections
+	/// close with `conn` (or after [`pool::IDLE_TIMEOUT`]).
+	pub(super) fn client(&self, conn: &ConnContext) -> ProxiedClient {
+		let mut user = conn.user.lock();
+		let clients = user.get_or_insert_default::<ConnClients>();
+		clients.0.entry(self.id).or_insert_with(|| self.build(conn)).clone()
+	}
+
+	fn build(&self, conn: &ConnContext) -> ProxiedClient {
+		let connector = HeaderConnector {
+			inner: self.http.clone(),
+			header: Bytes::from(proxy_header::outbound(self.proxy_protocol, conn)),
+		};
+		let https = HttpsConnector::from((connector, Arc::clone(&self.tls_cfg)));
+		let mut builder = Client::builder(TokioExecutor::new());
+		builder
+			.pool_max_idle_per_host(pool::MAX_IDLE_PER_HOST)
+			.pool_idle_timeout(Some(pool::IDLE_TIMEOUT))
+			.pool_timer(TokioTimer::new());
+		if self.version == UpstreamVersion::Http2 {
+			builder.http2_only(true);
+			builder.http2_max_concurrent_reset_streams(pool::H2_MAX_CONCURRENT_RESET_STREAMS);
+		}
+		builder.build(https)
+	}
+}
+
+/// `HttpConnector` that writes `header` onto each fresh connection.
+#[derive(Clone)]
+pub(super) struct HeaderConnector {
+	inner: HttpConnector<HickoryDnsResolver>,
+	header: Bytes,
+}
+
+impl Service<http::Uri> for HeaderConnector {
+	type Response = TokioIo<TcpStream>;
+	type Error = BoxError;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+		self.inner.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, dst: http::Uri) -> Self::Future {
+		let connecting = self.inner.call(dst);
+		let header = self.header.clone();
+		Box::pin(async move {
+			let mut tcp = connecting.await?.into_inner();
+			tcp.write_all(&header).await?;
+			Ok(TokioIo::new(tcp))
+		})
+	}
+}
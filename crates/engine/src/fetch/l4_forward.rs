@@ -22,6 +22,7 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use tokio::io::AsyncWriteExt;
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
@@ -31,11 +32,14 @@ use vane_core::{
 };
 
 use crate::factories::{FactoryError, FetchFactories};
+use crate::fetch::health::{self, HealthTarget};
 use crate::fetch::retry::parse_duration;
+use crate::fetch::upstream::parse_tls_args;
 use crate::flow_graph::FetchInst;
 use crate::listener_udp::{
 	DispatchHandle, DispatchKey, DispatchTable, L4ForwardSession, SESSION_INBOUND_CAPACITY,
 };
+use crate::proxy_header;
 
 const DEFAULT_UDP_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
 const UDP_RECV_BUFFER: usize = 65535;
@@ -48,6 +52,13 @@ pub struct L4ForwardFetch {
 	upstream: String,
 	transport: Transport,
 	idle_timeout: Duration,
+	/// Kept alive so the daemon-scoped probe loop runs while this rule
+	/// is linked. A single upstream has nothing to fail over to, so the
+	/// verdict is surfaced through `get_health` but never gates a dial.
+	_health: Option<Arc<HealthTarget>>,
+	/// `args.send_proxy_protocol`: prefix every upstream connection
+	/// with a PROXY header naming the client. TCP only.
+	send_proxy_protocol: Option<proxy_protocol::Version>,
 }
 
 #[async_trait]
@@ -77,9 +88,9 @@ impl L4Fetch for L4ForwardFetch {
 				// the executor can route through `splice(2)` on Linux
 				// (and `copy_bidirectional` everywhere else); see
 				// [`Self::forward_tcp_native`].
-				self.forward_tcp_native(s).await
+				self.forward_tcp_native(s, conn).await
 			}
-			(L4Conn::Peeked(s), Transport::Tcp) => self.forward_tcp(s).await,
+			(L4Conn::Peeked(s), Transport::Tcp) => self.forward_tcp(s, conn).await,
 			(L4Conn::Tls(_), Transport::Tcp) => Err(Error::internal(
 				"L4Forward fetch received a TLS-terminated stream — listener-tls + L4 byte forward is rejected by `lower_port`; this is a lower-stage invariant violation",
 			)),
@@ -88,11 +99,28 @@ impl L4Fetch for L4ForwardFetch {
 }
 
 impl L4ForwardFetch {
-	async fn forward_tcp(&self, client: Box<dyn AsyncReadWrite + Send>) -> Result<Tunnel, Error> {
-		let upstream = TcpStream::connect(&self.upstream)
+	/// Dial the upstream and, with `send_proxy_protocol`, write the
+	/// PROXY header before any client byte can follow it.
+	async fn connect_upstream(&self, conn: &ConnContext) -> Result<TcpStream, Error> {
+		let mut upstream = TcpStream::connect(&self.upstream)
 			.await
 			.map_err(|e| Error::upstream(UpstreamReason::Unreachable).with_source(e))?;
 		let _ = upstream.set_nodelay(true);
+		if let Some(version) = self.send_proxy_protocol {
+			upstream
+				.write_all(&proxy_header::outbound(version, conn))
+				.await
+				.map_err(|e| Error::upstream(UpstreamReason::Unreachable).with_source(e))?;
+		}
+		Ok(upstream)
+	}
+
+	async fn forward_tcp(
+		&self,
+		client: Box<dyn AsyncReadWrite + Send>,
+		conn: &ConnContext,
+	) -> Result<Tunnel, Error> {
+		let upstream = self.connect_upstream(conn).await?;
 		Ok(Tunnel::Bidi {
 			client,
 			upstream: Box::new(upstream) as Box<dyn AsyncReadWrite + Send>,
@@ -108,11 +136,12 @@ impl L4ForwardFetch {
 	/// the only arm that emits [`Tunnel::SpliceBidi`]; the `Peeked` /
 	/// TLS / virtual arms fall through to [`Self::forward_tcp`] with
 	/// a trait-object client.
-	async fn forward_tcp_native(&self, client: TcpStream) -> Result<Tunnel, Error> {
-		let upstream = TcpStream::connect(&self.upstream)
-			.await
-			.map_err(|e| Error::upstream(UpstreamReason::Unreachable).with_source(e))?;
-		let _ = upstream.set_nodelay(true);
+	async fn forward_tcp_native(
+		&self,
+		client: TcpStream,
+		conn: &ConnContext,
+	) -> Result<Tunnel, Error> {
+		let upstream = self.connect_upstream(conn).await?;
 		Ok(Tunnel::SpliceBidi { client, upstream, close_reason_tx: None })
 	}
 
@@ -322,20 +351,26 @@ async fn udp_forwarder_loop(
 /// {
 ///   "upstream":     "host:port",
 ///   "transport":    "tcp" | "udp",
-///   "idle_timeout": "30s"
+///   "idle_timeout": "30s",
+///   "health_check": { "type": "tcp", "interval": "10s" },
+///   "send_proxy_protocol": "v1" | "v2"
 /// }
 /// ```
 ///
 /// `transport` defaults to `"tcp"` and is normally injected by the
 /// `tcp_forward` / `udp_forward` alias in
 /// [`vane_core::rule::TerminateSpec`]. `idle_timeout` applies only to
-/// the UDP arm and defaults to 30 s. Wider knobs (`tcp_keepalive`,
+/// the UDP arm and defaults to 30 s. `health_check` is TCP-only; see
+/// [`parse_health_check`]. `send_proxy_protocol` is TCP-only too and
+/// off by default. Wider knobs (`tcp_keepalive`,
 /// `dns_cache_ttl`) are post-MVP.
 ///
 /// # Errors
 /// Returns [`FactoryError`] when `upstream` is missing/empty, when
-/// `transport` is not `"tcp"` / `"udp"`, or when `idle_timeout` is
-/// not a parseable duration string.
+/// `transport` is not `"tcp"` / `"udp"`, when `idle_timeout` is
+/// not a parseable duration string, when `health_check` is
+/// malformed or set on a UDP forward, or when `send_proxy_protocol`
+/// is not `"v1"` / `"v2"` or set on a UDP forward.
 pub fn factory(args: &serde_json::Value) -> Result<FetchInst, FactoryError> {
 	let upstream = args.get("upstream").and_then(serde_json::Value::as_str).ok_or_else(|| {
 		FactoryError::Invalid("missing args.upstream (string \"host:port\")".to_string())
@@ -359,13 +394,53 @@ pub fn factory(args: &serde_json::Value) -> Result<FetchInst, FactoryError> {
 		}
 		None => DEFAULT_UDP_IDLE_TIMEOUT,
 	};
+	let health = parse_health_check(args, upstream, transport)?;
+	let send_proxy_protocol = proxy_header::parse_send_arg(args)?;
+	if send_proxy_protocol.is_some() && transport == Transport::Udp {
+		return Err(FactoryError::Invalid(
+			"args.send_proxy_protocol is only supported with transport 'tcp'".to_string(),
+		));
+	}
 	Ok(FetchInst::L4(Arc::new(L4ForwardFetch {
 		upstream: upstream.to_string(),
 		transport,
 		idle_timeout,
+		_health: health,
+		send_proxy_protocol,
 	})))
 }
 
+/// Parse `args.health_check` and register the probe target. TCP only —
+/// a UDP upstream has no connection to probe. `tls` / HTTPS probes take
+/// their client config from `args.health_check.tls` (same shape as
+/// `http_proxy`'s `args.tls`), since the forwarded bytes themselves are
+/// never TLS-terminated here.
+fn parse_health_check(
+	args: &serde_json::Value,
+	upstream: &str,
+	transport: Transport,
+) -> Result<Option<Arc<HealthTarget>>, FactoryError> {
+	let raw = args.get("health_check");
+	let Some(spec) =
+		health::parse(raw).map_err(|e| FactoryError::Invalid(format!("args.health_check: {e}")))?
+	else {
+		return Ok(None);
+	};
+	if transport == Transport::Udp {
+		return Err(FactoryError::Invalid(
+			"args.health_check is only supported with transport 'tcp'".to_string(),
+		));
+	}
+	let tls = parse_tls_args(upstream, raw.and_then(|v| v.get("tls")), None)
+		.map_err(|e| FactoryError::Invalid(format!("args.health_check.tls: {e}")))?;
+	if matches!(spec.kind, health::ProbeKind::Tls) && tls.is_none() {
+		return Err(FactoryError::Invalid(
+			"args.health_check: type 'tls' requires args.health_check.tls".to_string(),
+		));
+	}
+	Ok(Some(health::watch(upstream, &spec, tls.as_ref())))
+}
+
 /// Convenience: register this fetch against `FetchKind::L4Forward` on a
 /// `FetchFactories`.
 pub fn register(factories: &mut FetchFactories) {
@@ -392,6 +467,58 @@ mod tests {
 		assert!(matches!(inst, FetchInst::L4(_)));
 	}
 
+	#[test]
+	fn factory_accepts_tcp_health_check_and_rejects_udp() {
+		let inst = factory(&json!({
+			"upstream": "127.0.0.1:9000",
+			"health_check": { "type": "tcp", "interval": "5s" },
+		}))
+		.expect("ok");
+		assert!(matches!(inst, FetchInst::L4(_)));
+		let Err(FactoryError::Invalid(msg)) = factory(&json!({
+			"upstream": "1.2.3.4:53",
+			"transport": "udp",
+			"health_check": { "type": "tcp" },
+		})) else {
+			panic!("udp + health_check must be rejected");
+		};
+		assert!(msg.contains("transport 'tcp'"), "{msg}");
+	}
+
+	#[test]
+	fn factory_parses_send_proxy_protocol_and_rejects_udp() {
+		for v in ["v1", "v2"] {
+			let inst =
+				factory(&json!({ "upstream": "127.0.0.1:9000", "send_proxy_protocol": v })).expect("ok");
+			assert!(matches!(inst, FetchInst::L4(_)));
+		}
+		let Err(FactoryError::Invalid(msg)) =
+			factory(&json!({ "upstream": "127.0.0.1:9000", "send_proxy_protocol": "v3" }))
+		else {
+			panic!("unknown version must be rejected");
+		};
+		assert!(msg.contains("send_proxy_protocol"), "{msg}");
+		let Err(FactoryError::Invalid(msg)) = factory(&json!({
+			"upstream": "1.2.3.4:53",
+			"transport": "udp",
+			"send_proxy_protocol": "v2",
+		})) else {
+			panic!("udp + send_proxy_protocol must be rejected");
+		};
+		assert!(msg.contains("transport 'tcp'"), "{msg}");
+	}
+
+	#[test]
+	fn factory_rejects_tls_health_check_without_tls_config() {
+		let Err(FactoryError::Invalid(msg)) = factory(&json!({
+			"upstream": "127.0.0.1:9000",
+			"health_check": { "type": "tls" },
+		})) else {
+			panic!("tls probe without tls config must be rejected");
+		};
+		assert!(msg.contains("health_check.tls"), "{msg}");
+	}
+
 	#[test]
 	fn factory_accepts_idle_timeout() {
 		let inst = factory(&json!({
@@ -8,11 +8,12 @@
 //!   "max_attempts": 3,
 //!   "methods":      ["GET", "HEAD", "PUT", "DELETE", "OPTIONS"],
 //!   "backoff":      "exponential",
-//!   "buffering":    "opportunistic"
+//!   "buffering":    "opportunistic",
+//!   "hedge":        { "delay": "50ms" }
 //! }
 //! ```
 //!
-//! All four fields are optional. The retry decision itself goes
+//! All five fields are optional. The retry decision itself goes
 //! through [`vane_core::Error::is_retryable`] — the spec's
 //! single-source error-classification table — so this module owns
 //! the *policy* (how many attempts, when, on which methods) and
@@ -21,13 +22,43 @@
 //! The policy types and `parse_duration` helper live in
 //! [`http_retry_policy`]; this module re-exports them and adds the
 //! vane-specific JSON schema parser.
+//!
+//! The rule's upstream time bounds ([`Deadlines`]) sit beside the
+//! policy because they spend the same budget: a `response_header_timeout`
+//! fails one attempt, and `total_timeout` caps every attempt plus the
+//! backoff between them.
+//!
+//! `hedge` races a second attempt against a slow first one; how many
+//! hedges fire is capped daemon-wide by [`hedge_budget`].
 
 use std::collections::HashSet;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use http::Method;
+use tokio::time::Instant;
+
+pub use http_retry_policy::{
+	Backoff, BufferingPolicy, HedgeBudget, HedgeDelay, HedgePolicy, LatencyWindow, RetryPolicy,
+	is_hedgeable, parse_duration,
+};
 
-pub use http_retry_policy::{Backoff, BufferingPolicy, RetryPolicy, parse_duration};
+/// Budget used when the daemon never called [`init_hedge_budget`]
+/// (the `VANE_HEDGE_BUDGET_PERCENT` default).
+const DEFAULT_HEDGE_BUDGET_PERCENT: u8 = 10;
+
+static HEDGE_BUDGET: OnceLock<HedgeBudget> = OnceLock::new();
+
+/// Fix the daemon-wide hedge budget. Must run before the first hedged
+/// request; later calls are no-ops (`OnceLock::set`).
+pub fn init_hedge_budget(percent: u8) {
+	let _ = HEDGE_BUDGET.set(HedgeBudget::new(percent));
+}
+
+/// Process-wide hedge budget shared by every `http_proxy` fetch.
+pub(crate) fn hedge_budget() -> &'static HedgeBudget {
+	HEDGE_BUDGET.get_or_init(|| HedgeBudget::new(DEFAULT_HEDGE_BUDGET_PERCENT))
+}
 
 /// Parse `args.retry` into a `RetryPolicy`. Missing / null / empty
 /// object yields the default (no retry).
@@ -81,9 +112,85 @@ pub fn parse(retry: Option<&serde_json::Value>) -> Result<RetryPolicy, String> {
 		};
 	}
 
+	if let Some(h) = retry.get("hedge").filter(|h| !h.is_null()) {
+		policy.hedge = Some(parse_hedge(h)?);
+	}
+
 	Ok(policy)
 }
 
+/// `hedge: { "delay": "50ms" }` fires after a fixed delay;
+/// `hedge: { "delay": "p95" }` after the upstream's observed 95th
+/// percentile latency.
+fn parse_hedge(v: &serde_json::Value) -> Result<HedgePolicy, String> {
+	let delay = v
+		.get("delay")
+		.ok_or("hedge.delay is required")?
+		.as_str()
+		.ok_or("hedge.delay must be a duration string or a percentile like \"p95\"")?;
+	let delay = if let Some(p) = delay.strip_prefix('p') {
+		let p: u8 = p.parse().map_err(|e| format!("hedge.delay {delay:?}: {e}"))?;
+		if !(1..=99).contains(&p) {
+			return Err(format!("hedge.delay {delay:?}: percentile must be between p1 and p99"));
+		}
+		HedgeDelay::Percentile(p)
+	} else {
+		let d = parse_duration(delay).map_err(|e| format!("hedge.delay: {e}"))?;
+		if d.is_zero() {
+			return Err("hedge.delay must be greater than zero".to_owned());
+		}
+		HedgeDelay::Fixed(d)
+	};
+	Ok(HedgePolicy { delay })
+}
+
+/// Per-rule upstream time bounds. Each is optional; absent leaves that
+/// stage unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Deadlines {
+	/// Per attempt, from handing the request to the upstream until the
+	/// response head arrives. Expiry is `Timeout(Read)`, retried like a
+	/// mid-request reset (idempotent methods only).
+	pub response_header: Option<Duration>,
+	/// Longest gap between two response body frames. Expiry is
+	/// `Timeout(Idle)` and aborts the body stream.
+	pub body_read_idle: Option<Duration>,
+	/// The whole exchange: every attempt, the backoff between them and
+	/// the response body. Expiry is `Timeout(Total)` and ends retrying.
+	pub total: Option<Duration>,
+}
+
+/// Parse `args.response_header_timeout`, `args.body_read_idle_timeout`
+/// and `args.total_timeout` (duration strings, e.g. `"30s"`).
+///
+/// # Errors
+/// String description naming the offending field: not a string, an
+/// unparseable duration, or zero.
+pub fn parse_deadlines(args: &serde_json::Value) -> Result<Deadlines, String> {
+	let field = |name: &str| -> Result<Option<Duration>, String> {
+		let Some(v) = args.get(name) else { return Ok(None) };
+		let s = v.as_str().ok_or_else(|| format!("args.{name} must be a duration string"))?;
+		let d = parse_duration(s).map_err(|e| format!("args.{name}: {e}"))?;
+		if d.is_zero() {
+			return Err(format!("args.{name} must be greater than zero"));
+		}
+		Ok(Some(d))
+	};
+	Ok(Deadlines {
+		response_header: field("response_header_timeout")?,
+		body_read_idle: field("body_read_idle_timeout")?,
+		total: field("total_timeout")?,
+	})
+}
+
+/// Whether a retry that first sleeps `delay` would still start before
+/// `deadline`. A retry that cannot is not attempted, and the failed
+/// attempt's own error is returned instead of a `Total` timeout.
+#[must_use]
+pub fn fits_deadline(deadline: Option<Instant>, delay: Duration) -> bool {
+	deadline.is_none_or(|at| Instant::now() + delay < at)
+}
+
 fn parse_backoff(v: &serde_json::Value) -> Result<Backoff, String> {
 	if let Some(s) = v.as_str() {
 		return match s {
@@ -240,6 +347,55 @@ mod tests {
 		assert!(err.contains("opportunistic") && err.contains("force"), "{err}");
 	}
 
+	#[test]
+	fn parse_deadlines_reads_all_three_fields() {
+		let d = parse_deadlines(&json!({
+			"response_header_timeout": "2s",
+			"body_read_idle_timeout": "500ms",
+			"total_timeout": "10s",
+		}))
+		.expect("ok");
+		assert_eq!(d.response_header, Some(Duration::from_secs(2)));
+		assert_eq!(d.body_read_idle, Some(Duration::from_millis(500)));
+		assert_eq!(d.total, Some(Duration::from_secs(10)));
+		assert_eq!(parse_deadlines(&json!({})).expect("absent"), Deadlines::default());
+	}
+
+	#[test]
+	fn parse_deadlines_rejects_zero_and_non_strings() {
+		let err = parse_deadlines(&json!({ "total_timeout": "0s" })).expect_err("zero");
+		assert!(err.contains("args.total_timeout"), "{err}");
+		let err = parse_deadlines(&json!({ "response_header_timeout": 5 })).expect_err("number");
+		assert!(err.contains("duration string"), "{err}");
+	}
+
+	#[test]
+	fn fits_deadline_refuses_a_delay_past_the_deadline() {
+		assert!(fits_deadline(None, Duration::from_hours(1)));
+		let at = Instant::now() + Duration::from_secs(1);
+		assert!(fits_deadline(Some(at), Duration::from_millis(10)));
+		assert!(!fits_deadline(Some(at), Duration::from_secs(2)));
+	}
+
+	#[test]
+	fn parse_hedge_fixed_and_percentile_delays() {
+		let p = parse(Some(&json!({ "hedge": { "delay": "50ms" } }))).expect("fixed");
+		assert_eq!(p.hedge, Some(HedgePolicy { delay: HedgeDelay::Fixed(Duration::from_millis(50)) }));
+		let p = parse(Some(&json!({ "hedge": { "delay": "p95" } }))).expect("percentile");
+		assert_eq!(p.hedge, Some(HedgePolicy { delay: HedgeDelay::Percentile(95) }));
+		assert_eq!(parse(Some(&json!({ "hedge": null }))).expect("null").hedge, None);
+	}
+
+	#[test]
+	fn parse_hedge_rejects_bad_delays() {
+		for bad in
+			[json!({}), json!({ "delay": 50 }), json!({ "delay": "p0" }), json!({ "delay": "0ms" })]
+		{
+			let err = parse(Some(&json!({ "hedge": bad }))).expect_err("bad hedge");
+			assert!(err.contains("hedge.delay"), "{err}");
+		}
+	}
+
 	#[test]
 	fn parse_rejects_invalid_method_string() {
 		let err = parse(Some(&json!({ "methods": ["NOT A METHOD"] }))).expect_err("bad method");
@@ -108,6 +108,9 @@ impl L7Fetch for WebSocketUpgradeFetch {
 		// `Sec-WebSocket-*` family is not in the hop-by-hop set so
 		// it passes through untouched.
 		crate::fetch::hop_by_hop::strip_hop_by_hop_request(req.headers_mut());
+		// The executor's `fetch` span is current; the upstream's handshake
+		// span continues from it.
+		crate::trace_propagation::inject(&tracing::Span::current(), req.headers_mut());
 
 		// One-shot H1 dial via the shared upstream helper — supports
 		// both cleartext WS and WSS depending on `self.tls`. The
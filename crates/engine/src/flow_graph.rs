@@ -7,7 +7,8 @@ use arc_swap::ArcSwap;
 use vane_core::{
 	FetchId, FetchKind, FlowGraphMeta, L4BytesMiddleware, L4Fetch, L4PeekMiddleware, L7Fetch,
 	L7RequestMiddleware, L7ResponseMiddleware, MiddlewareId, MiddlewareKind, ModuleId, Node, NodeId,
-	PluginMetadata, SymbolicFlowGraph, WasmRuntime, rule::ListenerTlsSpec,
+	PluginMetadata, SymbolicFlowGraph, WasmRuntime,
+	rule::{ListenerTlsSpec, ProxyProtocolConfig},
 };
 
 #[cfg(feature = "acme")]
@@ -133,6 +134,9 @@ pub struct FlowGraph {
 	/// from the daemon's env; default values used for test graphs that
 	/// don't need floor-enforcement tuning.
 	security_cfg: Arc<SecurityConfig>,
+	/// Any middleware in the graph runs in the `L7Response` phase. Gates
+	/// the executor's per-request [`vane_core::RequestHead`] copy.
+	response_middleware: bool,
 }
 
 impl FlowGraph {
@@ -154,6 +158,11 @@ impl FlowGraph {
 		&self.security_cfg
 	}
 
+	#[must_use]
+	pub(crate) const fn has_response_middleware(&self) -> bool {
+		self.response_middleware
+	}
+
 	/// Per-listener parsed TLS server config. `None` for cleartext
 	/// listeners. Looked up by bind address in the accept loop.
 	#[must_use]
@@ -184,12 +193,22 @@ impl FlowGraph {
 		self.meta.listener_kinds.get(addr).copied().unwrap_or(ListenerKind::Http)
 	}
 
+	/// PROXY protocol acceptance for the listener at `addr`; `None`
+	/// when the listener never parses a header. See
+	/// `spec/crates/engine.md` § _PROXY protocol_.
+	#[must_use]
+	pub fn listener_proxy_protocol(&self, addr: &SocketAddr) -> Option<&ProxyProtocolConfig> {
+		self.meta.listener_proxy_protocol.get(addr)
+	}
+
 	/// Rule-level reachability: does any path from `entry` to an
 	/// `L4Forward` fetch cross a `tls.sni` predicate `Check`? When
 	/// `true`, the UDP listener routes the cold-path datagram through
 	/// the pending-peek state machine — accumulate Initial datagrams,
 	/// extract SNI, then enter the `FlowGraph` with `ConnContext.tls.sni`
-	/// populated so the matching `tls.sni` rule routes correctly.
+	/// populated so the matching `tls.sni` rule routes correctly. The
+	/// `tls.ja3` / `tls.ja4` fingerprint predicates read the same
+	/// parsed `ClientHello` and count as `tls.sni` here.
 	///
 	/// Per `spec/crates/engine.md` § _Multi-packet peek_,
 	/// the spec definition is per-rule conjunction (`tls.sni` predicate
@@ -230,10 +249,9 @@ impl FlowGraph {
 			};
 			match node {
 				Node::Check { predicate, on_match, on_miss, .. } => {
-					let predicate_is_sni = sym
-						.predicates
-						.get(predicate.get() as usize)
-						.is_some_and(|p| matches!(p.path, FieldPath::TlsSni));
+					let predicate_is_sni = sym.predicates.get(predicate.get() as usize).is_some_and(|p| {
+						matches!(p.path, FieldPath::TlsSni | FieldPath::TlsJa3 | FieldPath::TlsJa4)
+					});
 					let new_sni_seen = sni_seen || predicate_is_sni;
 					stack.push((*on_match, new_sni_seen));
 					stack.push((*on_miss, new_sni_seen));
@@ -244,7 +262,7 @@ impl FlowGraph {
 						stack.push((*e, sni_seen));
 					}
 				}
-				Node::Fetch { id, next_response, next_tunnel, .. } => {
+				Node::Fetch { id, next_response, next_tunnel, on_error, .. } => {
 					let is_l4_forward = matches!(
 						sym.fetches.get(id.get() as usize).map(|f| f.kind),
 						Some(FetchKind::L4Forward),
@@ -258,6 +276,9 @@ impl FlowGraph {
 					if let Some(n) = next_tunnel {
 						stack.push((*n, sni_seen));
 					}
+					if let Some(e) = on_error {
+						stack.push((*e, sni_seen));
+					}
 				}
 				Node::Upgrade { next } => stack.push((*next, sni_seen)),
 				Node::Terminate(_) => {}
@@ -303,13 +324,16 @@ impl FlowGraph {
 						queue.push_back(*e);
 					}
 				}
-				Node::Fetch { next_response, next_tunnel, .. } => {
+				Node::Fetch { next_response, next_tunnel, on_error, .. } => {
 					if let Some(n) = next_response {
 						queue.push_back(*n);
 					}
 					if let Some(n) = next_tunnel {
 						queue.push_back(*n);
 					}
+					if let Some(e) = on_error {
+						queue.push_back(*e);
+					}
 				}
 				Node::Upgrade { next } => queue.push_back(*next),
 				Node::Terminate(_) => {}
@@ -514,9 +538,12 @@ impl FlowGraph {
 			listener_tls: sym.meta.listener_tls.clone(),
 			listener_kinds,
 			listener_transports: sym.meta.listener_transports.clone(),
+			listener_proxy_protocol: sym.meta.listener_proxy_protocol.clone(),
+			unix_listeners: sym.meta.unix_listeners.clone(),
 			annotations: sym.meta.annotations.clone(),
 		};
 
+		let response_middleware = middlewares.iter().any(|m| m.kind() == MiddlewareKind::L7Response);
 		Ok(Arc::new(Self {
 			symbolic: sym,
 			middlewares,
@@ -525,6 +552,7 @@ impl FlowGraph {
 			listener_tls,
 			listener_populators,
 			security_cfg,
+			response_middleware,
 		}))
 	}
 }
@@ -627,7 +655,7 @@ fn derive_kind(sym: &SymbolicFlowGraph, entry: NodeId) -> ListenerKind {
 					queue.push_back(*e);
 				}
 			}
-			Node::Fetch { id, next_response, next_tunnel, .. } => {
+			Node::Fetch { id, next_response, next_tunnel, on_error, .. } => {
 				match sym.fetches[id.get() as usize].kind.phase() {
 					FetchPhase::L4 => seen_l4 = true,
 					FetchPhase::L7 => seen_l7 = true,
@@ -638,6 +666,9 @@ fn derive_kind(sym: &SymbolicFlowGraph, entry: NodeId) -> ListenerKind {
 				if let Some(n) = next_tunnel {
 					queue.push_back(*n);
 				}
+				if let Some(e) = on_error {
+					queue.push_back(*e);
+				}
 			}
 			Node::Upgrade { next } => queue.push_back(*next),
 			Node::Terminate(_) => {}
@@ -1135,6 +1166,8 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_kinds: BTreeMap::new(),
 				listener_transports: BTreeMap::new(),
+				listener_proxy_protocol: BTreeMap::new(),
+				unix_listeners: BTreeMap::new(),
 				annotations: Vec::new(),
 			}
 		}
@@ -1177,6 +1210,7 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_populators: BTreeMap::new(),
 				security_cfg: Arc::new(SecurityConfig::default()),
+				response_middleware: false,
 			}
 		}
 
@@ -1318,6 +1352,7 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_populators: BTreeMap::new(),
 				security_cfg: Arc::new(SecurityConfig::default()),
+				response_middleware: false,
 			};
 			assert!(g.declares_tls(&addr), "spec declared TLS, accessor must see it");
 			assert!(g.listener_tls(&addr).is_none(), "capability map empty in this fixture");
@@ -1353,6 +1388,8 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_kinds,
 				listener_transports: BTreeMap::new(),
+				listener_proxy_protocol: BTreeMap::new(),
+				unix_listeners: BTreeMap::new(),
 				annotations: Vec::new(),
 			}
 		}
@@ -1369,12 +1406,14 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_kinds,
 				listener_transports: BTreeMap::new(),
+				listener_proxy_protocol: BTreeMap::new(),
+				unix_listeners: BTreeMap::new(),
 				annotations: Vec::new(),
 			}
 		}
 
 		fn fetch_ref(kind: FetchKind) -> SymbolicFetchRef {
-			SymbolicFetchRef { kind, args: json!({}), retry_buffer_required: false, allow_zero_rtt: None }
+			SymbolicFetchRef::new(kind, json!({}))
 		}
 
 		fn sni_predicate() -> PredicateInst {
@@ -1414,6 +1453,7 @@ mod tests {
 				listener_tls: BTreeMap::new(),
 				listener_populators: BTreeMap::new(),
 				security_cfg: Arc::new(SecurityConfig::default()),
+				response_middleware: false,
 			}
 		}
 
@@ -1434,6 +1474,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(2)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1469,6 +1511,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(1)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1498,6 +1542,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(2)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1544,6 +1590,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(2)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1578,6 +1626,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(2)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1611,6 +1661,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(2)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1643,6 +1695,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: Some(NodeId::for_testing(2)),
 					next_tunnel: None,
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
@@ -1683,6 +1737,8 @@ mod tests {
 					id: FetchId::for_testing(0),
 					next_response: None,
 					next_tunnel: Some(NodeId::for_testing(3)),
+					on_error: None,
+					on_error_status: Vec::new(),
 					collect_body_before: None,
 					body_limit: 0,
 				},
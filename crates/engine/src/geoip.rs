@@ -0,0 +1,203 @@
+//! Memory-mapped MaxMind DB reader behind `remote.country`,
+//! `remote.continent` and `remote.asn`.
+//!
+//! One [`GeoIpDb`] serves the whole daemon: boot opens it from
+//! `VANE_GEOIP_DB` and installs it through
+//! [`vane_core::install_geo_lookup`], and the daemon's file watcher
+//! calls [`GeoIpDb::reload`] when a database file changes. Several
+//! files may be configured — GeoLite2 ships country and ASN data
+//! separately — and each field is answered by the first file that
+//! has it.
+//!
+//! Files must be replaced by rename (what `geoipupdate` does). The
+//! reader maps the file, so truncating and rewriting it in place
+//! under a live daemon is undefined behaviour, not just a bad read.
+//!
+//! See `spec/crates/engine.md` § _GeoIP_.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use maxminddb::{Mmap, Reader};
+use vane_core::{Error, GeoInfo, GeoLookup};
+
+/// The subset of a GeoIP2 / GeoLite2 record vane reads. Country and
+/// ASN databases each fill their own part; unknown keys are skipped.
+#[derive(serde::Deserialize)]
+struct Record<'a> {
+	#[serde(borrow)]
+	country: Option<CountryRecord<'a>>,
+	#[serde(borrow)]
+	continent: Option<ContinentRecord<'a>>,
+	autonomous_system_number: Option<u32>,
+}
+
+#[derive(serde::Deserialize)]
+struct CountryRecord<'a> {
+	iso_code: Option<&'a str>,
+}
+
+#[derive(serde::Deserialize)]
+struct ContinentRecord<'a> {
+	code: Option<&'a str>,
+}
+
+/// Daemon-wide GeoIP database set. Lookups are lock-free against the
+/// current mapping; a reload swaps in a whole new set of mappings,
+/// and the old ones unmap when the last in-flight lookup drops them.
+pub struct GeoIpDb {
+	paths: Vec<PathBuf>,
+	readers: ArcSwap<Vec<Reader<Mmap>>>,
+}
+
+impl GeoIpDb {
+	/// Map every file in `paths`, in priority order.
+	///
+	/// # Errors
+	/// `Error::io` naming the first file that cannot be opened or is
+	/// not a MaxMind DB.
+	pub fn open(paths: Vec<PathBuf>) -> Result<Self, Error> {
+		let readers = open_all(&paths)?;
+		Ok(Self { paths, readers: ArcSwap::from_pointee(readers) })
+	}
+
+	/// Files this database was opened from, in priority order.
+	#[must_use]
+	pub fn paths(&self) -> &[PathBuf] {
+		&self.paths
+	}
+
+	/// Re-map every file. All-or-nothing: when any file fails to open
+	/// the previous set keeps serving and the error is returned.
+	///
+	/// # Errors
+	/// As [`GeoIpDb::open`].
+	pub fn reload(&self) -> Result<(), Error> {
+		self.readers.store(Arc::new(open_all(&self.paths)?));
+		Ok(())
+	}
+}
+
+fn open_all(paths: &[PathBuf]) -> Result<Vec<Reader<Mmap>>, Error> {
+	paths.iter().map(|p| open_one(p)).collect()
+}
+
+fn open_one(path: &Path) -> Result<Reader<Mmap>, Error> {
+	Reader::open_mmap(path)
+		.map_err(|e| Error::io(format!("geoip: open {}", path.display())).with_source(e))
+}
+
+impl GeoLookup for GeoIpDb {
+	fn lookup(&self, ip: IpAddr) -> GeoInfo {
+		let readers = self.readers.load();
+		let mut info = GeoInfo::default();
+		for reader in readers.iter() {
+			// Not-found and decode errors both mean "this file has
+			// nothing for the address"; the next file may.
+			let Ok(record) = reader.lookup::<Record<'_>>(ip) else { continue };
+			if info.country.is_none() {
+				info.country = record.country.and_then(|c| c.iso_code).map(Arc::from);
+			}
+			if info.continent.is_none() {
+				info.continent = record.continent.and_then(|c| c.code).map(Arc::from);
+			}
+			if info.asn.is_none() {
+				info.asn = record.autonomous_system_number;
+			}
+		}
+		info
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::Ipv4Addr;
+
+	use vane_testutil::mmdb::{self, GeoRecord};
+
+	use super::*;
+
+	fn write(dir: &Path, name: &str, prefixes: &[(Ipv4Addr, u8, GeoRecord<'_>)]) -> PathBuf {
+		let path = dir.join(name);
+		// Write-then-rename, the same way an operator must replace a
+		// mapped database.
+		let tmp = dir.join(format!("{name}.tmp"));
+		std::fs::write(&tmp, mmdb::build(prefixes)).unwrap();
+		std::fs::rename(&tmp, &path).unwrap();
+		path
+	}
+
+	fn country(code: &'static str, continent: &'static str) -> GeoRecord<'static> {
+		GeoRecord { country: Some(code), continent: Some(continent), asn: None }
+	}
+
+	fn asn(n: u32) -> GeoRecord<'static> {
+		GeoRecord { asn: Some(n), ..GeoRecord::default() }
+	}
+
+	#[test]
+	fn lookup_merges_fields_across_files_in_order() {
+		let dir = tempfile::tempdir().unwrap();
+		let countries = write(
+			dir.path(),
+			"country.mmdb",
+			&[
+				(Ipv4Addr::new(192, 0, 2, 0), 24, country("DE", "EU")),
+				(Ipv4Addr::new(198, 51, 100, 0), 24, country("US", "NA")),
+			],
+		);
+		let asns = write(
+			dir.path(),
+			"asn.mmdb",
+			&[
+				(Ipv4Addr::new(192, 0, 2, 0), 25, asn(64500)),
+				(Ipv4Addr::new(203, 0, 113, 0), 24, asn(64501)),
+			],
+		);
+		let db = GeoIpDb::open(vec![countries, asns]).unwrap();
+
+		let info = db.lookup(IpAddr::from([192, 0, 2, 10]));
+		assert_eq!(info.country.as_deref(), Some("DE"));
+		assert_eq!(info.continent.as_deref(), Some("EU"));
+		assert_eq!(info.asn, Some(64500));
+
+		// Outside the /25 ASN prefix: country only.
+		let info = db.lookup(IpAddr::from([192, 0, 2, 200]));
+		assert_eq!((info.country.as_deref(), info.asn), (Some("DE"), None));
+
+		let info = db.lookup(IpAddr::from([203, 0, 113, 1]));
+		assert_eq!((info.country.as_deref(), info.asn), (None, Some(64501)));
+
+		assert_eq!(db.lookup(IpAddr::from([10, 0, 0, 1])), GeoInfo::default());
+		assert_eq!(db.lookup("2001:db8::1".parse().unwrap()), GeoInfo::default());
+	}
+
+	#[test]
+	fn reload_swaps_the_mapping_and_keeps_it_on_failure() {
+		let dir = tempfile::tempdir().unwrap();
+		let prefix = Ipv4Addr::new(192, 0, 2, 0);
+		let path = write(dir.path(), "geo.mmdb", &[(prefix, 24, country("DE", "EU"))]);
+		let db = GeoIpDb::open(vec![path.clone()]).unwrap();
+		let ip = IpAddr::from([192, 0, 2, 1]);
+
+		write(dir.path(), "geo.mmdb", &[(prefix, 24, country("FR", "EU"))]);
+		assert_eq!(db.lookup(ip).country.as_deref(), Some("DE"), "no reload yet");
+		db.reload().unwrap();
+		assert_eq!(db.lookup(ip).country.as_deref(), Some("FR"));
+
+		std::fs::write(dir.path().join("geo.mmdb.tmp"), b"not a database").unwrap();
+		std::fs::rename(dir.path().join("geo.mmdb.tmp"), &path).unwrap();
+		let err = db.reload().unwrap_err();
+		assert!(err.to_string().contains("geo.mmdb"), "{err}");
+		assert_eq!(db.lookup(ip).country.as_deref(), Some("FR"), "previous mapping serves");
+	}
+
+	#[test]
+	fn open_names_the_missing_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let err = GeoIpDb::open(vec![dir.path().join("absent.mmdb")]).err().unwrap();
+		assert!(err.to_string().contains("absent.mmdb"), "{err}");
+	}
+}
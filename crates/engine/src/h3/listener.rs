@@ -20,6 +20,7 @@ use virtual_socket::VirtualUdpSocket;
 use crate::flow_graph::FlowGraph;
 use crate::listener_ctx::UdpAcceptCtx;
 use crate::listener_udp::{DispatchHandle, DispatchKey};
+use crate::security::SecurityState;
 use crate::verbosity::VerbosityState;
 
 /// Build the per-listener `quinn::ServerConfig` for ALPN `h3`. Reuses
@@ -72,7 +73,18 @@ pub(crate) fn spawn_h3_endpoint(
 	ctx: &Arc<UdpAcceptCtx>,
 	tls_cfg: &Arc<rustls::ServerConfig>,
 ) -> Result<(), String> {
-	let server_config = build_quic_server_config(tls_cfg)?;
+	let mut server_config = build_quic_server_config(tls_cfg)?;
+	// Transport-level stream credit at twice the L1 in-flight cap: a
+	// hard bound on what one connection can open, set above the cap so
+	// streams past it still reach the driver's admission check and are
+	// refused with `H3_REQUEST_REJECTED` (and counted) rather than
+	// silently stalled.
+	let stream_credit = u32::try_from(ctx.base.security.cfg.max_in_flight_streams_per_conn)
+		.unwrap_or(u32::MAX)
+		.saturating_mul(2);
+	let mut transport = quinn::TransportConfig::default();
+	transport.max_concurrent_bidi_streams(quinn::VarInt::from_u32(stream_credit));
+	server_config.transport_config(Arc::new(transport));
 
 	let virtual_socket: Arc<VirtualUdpSocket> = VirtualUdpSocket::new(Arc::clone(&ctx.socket));
 	ctx.dispatch_table.insert(
@@ -96,6 +108,7 @@ pub(crate) fn spawn_h3_endpoint(
 	let accept_cancel = ctx.base.accept_cancel.clone();
 	let force_cancel = ctx.base.force_cancel.clone();
 	let in_flight = Arc::clone(&ctx.base.in_flight);
+	let security = Arc::clone(&ctx.base.security);
 	tokio::spawn(async move {
 		run_h3_accept_loop(
 			addr,
@@ -106,14 +119,17 @@ pub(crate) fn spawn_h3_endpoint(
 			accept_cancel,
 			force_cancel,
 			in_flight,
+			security,
 		)
 		.await;
 	});
 	Ok(())
 }
 
-/// Accept-loop task: pulls each `Incoming` from the endpoint, fully
-/// negotiates the QUIC handshake, then spawns
+/// Accept-loop task: pulls each `Incoming` from the endpoint, admits
+/// it against the L1 handshake floor (refused before any crypto work
+/// when over the per-IP rate or the pending cap), fully negotiates the
+/// QUIC handshake, then spawns
 /// [`crate::upgrade::drive_h3_server`] into the listener-wide
 /// `in_flight` `JoinSet` so the same shutdown tier
 /// (`accept_cancel` → drain → `force_cancel` → abort) that drains TCP
@@ -139,6 +155,7 @@ async fn run_h3_accept_loop(
 	accept_cancel: CancellationToken,
 	force_cancel: CancellationToken,
 	in_flight: Arc<InFlightSet>,
+	security: Arc<SecurityState>,
 ) {
 	loop {
 		tokio::select! {
@@ -159,6 +176,17 @@ async fn run_h3_accept_loop(
 				let Some(incoming) = incoming else {
 					return; // endpoint closed
 				};
+				if security.drop_banned(incoming.remote_address().ip()) {
+					tracing::debug!(?addr, remote = ?incoming.remote_address(), "banned source: ignoring quic connection");
+					incoming.ignore();
+					continue;
+				}
+				let Some(handshake_guard) = security.begin_handshake(incoming.remote_address().ip())
+				else {
+					tracing::debug!(?addr, remote = ?incoming.remote_address(), "L1 handshake cap: refusing quic connection");
+					incoming.refuse();
+					continue;
+				};
 				let connecting = match incoming.accept() {
 					Ok(c) => c,
 					Err(e) => {
@@ -171,12 +199,15 @@ async fn run_h3_accept_loop(
 				let verbosity = Arc::clone(verbosity);
 				let accept_cancel = accept_cancel.clone();
 				let force_cancel = force_cancel.clone();
+				let security = Arc::clone(&security);
 				// Spawn into the listener's `in_flight` set so the
 				// per-listener drain (shutdown / reconcile) joins on
 				// the H3 driver instead of leaking it as a detached
 				// `tokio::spawn`.
 				in_flight.spawn(async move {
-					match connecting.await {
+					let handshake = connecting.await;
+					drop(handshake_guard);
+					match handshake {
 						Ok(quic_conn) => {
 							crate::upgrade::drive_h3_server(
 								addr,
@@ -186,6 +217,7 @@ async fn run_h3_accept_loop(
 								accept_cancel,
 								force_cancel,
 								verbosity,
+								&security,
 							)
 							.await;
 						}
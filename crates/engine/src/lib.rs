@@ -31,19 +31,25 @@ pub mod factories;
 pub mod fetch;
 pub mod flow_graph;
 pub mod flow_log_sink;
+pub mod geoip;
 #[cfg(feature = "h3")]
 pub mod h3;
 pub mod hot_reload;
 pub mod listener;
 pub(crate) mod listener_ctx;
 pub mod listener_udp;
+pub(crate) mod listener_unix;
 pub mod metrics;
 pub mod middleware;
 pub mod preset;
+pub(crate) mod proxy_header;
+pub(crate) mod request_metrics;
 pub mod security;
+pub mod socket_activation;
 pub mod terminator;
 pub(crate) mod time;
 pub mod tls;
+pub(crate) mod trace_propagation;
 pub mod tracing_init;
 pub mod upgrade;
 pub mod verbosity;
@@ -52,6 +58,7 @@ pub mod wasm_fetch;
 
 pub use listener::{BindConfig, ListenerSet};
 pub use security::{ConnSecGuard, SecurityConfig, SecurityState};
+pub use socket_activation::{HandoffSocket, InheritedSockets};
 pub use verbosity::VerbosityState;
 
 pub mod crypto {
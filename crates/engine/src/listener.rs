@@ -16,29 +16,39 @@
 //!    cancel token surfaces `CloseReason::Cancelled`).
 //! 4. After a short secondary grace window any still-alive task is aborted.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::io;
 use std::net::SocketAddr;
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 use arc_swap::ArcSwap;
+use clienthello::{ClientHello, Ja4Transport};
 use dashmap::DashMap;
 use in_flight_set::InFlightSet;
 use parking_lot::Mutex;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument as _;
 use vane_core::{
 	ConnContext, ConnId, DetectedProtocol, FlowCtx, FlowLogSink, HttpVersion, L4Conn, ListenerKind,
-	NodeId, TlsInfo, TlsVersion, TrajectoryBuilder, Transport, config::Env,
+	NodeId, ProxyInfo, TlsInfo, TlsVersion, TrajectoryBuilder, Transport, UnixListenerSpec,
+	config::Env, rule::UnixSocketConfig,
 };
 
 use crate::executor::{ExecutorInput, execute};
 use crate::flow_graph::FlowGraph;
-use crate::listener_ctx::{AcceptCtx, ConnDispatchCtx};
+use crate::listener_ctx::{AcceptCtx, ConnDispatchCtx, UnixAcceptCtx};
 use crate::listener_udp::run_udp_listener;
-use crate::security::{SecurityConfig, SecurityState};
+use crate::listener_unix::{apply_socket_attrs, run_unix_listener};
+use crate::proxy_header;
+use crate::security::{ConnFloor, SecurityConfig, SecurityState};
+use crate::socket_activation::{HandoffSocket, InheritedSockets};
 use crate::time::now_unix_ms;
 use crate::verbosity::VerbosityState;
 use guess::classify;
@@ -100,13 +110,17 @@ pub(crate) fn next_conn_id() -> ConnId {
 	ConnId(NEXT_CONN_ID.fetch_add(1, Ordering::Relaxed))
 }
 
-/// Per-(transport, address) listener registry. Today: TCP only.
+/// Per-(transport, address) listener registry, plus Unix listeners
+/// keyed by socket path.
 ///
 /// Listener configuration changes only occur at boot and reload
 /// (spec/topology.md § _Listener lifecycle_). `start` is idempotent on
 /// duplicate addresses (already-running keys are skipped with a warn).
 pub struct ListenerSet {
 	running: Mutex<HashMap<SocketAddr, ListenerHandle>>,
+	/// `unix:/path` listeners. Kept apart from `running` because they
+	/// have no address and never take part in socket hand-off.
+	unix: Mutex<HashMap<PathBuf, UnixListenerHandle>>,
 	/// Background drain tasks spawned by [`Self::reconcile`] when a
 	/// listener is removed. Tracked here (instead of being
 	/// `tokio::spawn`'d and forgotten) so [`Self::shutdown`] can
@@ -127,6 +141,11 @@ pub struct ListenerSet {
 	/// counters). Survives hot-reload so counters are never reset by
 	/// a config change.
 	security: Arc<SecurityState>,
+	/// Sockets passed by systemd socket activation. Listeners on a
+	/// matching `(transport, addr)` serve on a `dup` of the inherited
+	/// socket instead of binding; the originals live as long as the
+	/// set, so a reload that drops a rule never closes them.
+	inherited: Arc<InheritedSockets>,
 }
 
 /// Bookkeeping for one in-flight `drain_handle_async` task spawned by
@@ -135,6 +154,7 @@ pub struct ListenerSet {
 /// drops it).
 struct DrainingHandle {
 	addr: SocketAddr,
+	path: Option<PathBuf>,
 	deadline: Instant,
 	in_flight_count: Arc<AtomicUsize>,
 	join: JoinHandle<()>,
@@ -144,7 +164,9 @@ struct DrainingHandle {
 /// the mgmt API so operators can watch the post-reconcile tail.
 #[derive(Clone, Debug)]
 pub struct DrainingListenerStatus {
+	/// `0.0.0.0:0` for a Unix listener, which sets `path` instead.
 	pub addr: SocketAddr,
+	pub path: Option<PathBuf>,
 	pub in_flight: usize,
 	/// Hard deadline at which the drain task will fire `force_cancel`
 	/// and start the abort sequence. Operators read this against
@@ -160,7 +182,11 @@ pub struct DrainingListenerStatus {
 pub struct ConnEntry {
 	pub conn_id: ConnId,
 	/// Local address of the listener that accepted this connection.
+	/// The `0.0.0.0:0` placeholder for Unix listeners.
 	pub listener_addr: SocketAddr,
+	/// Socket path of the Unix listener that accepted this connection;
+	/// `None` for network listeners.
+	pub listener_path: Option<PathBuf>,
 	pub remote: SocketAddr,
 	pub accepted_at: Instant,
 }
@@ -169,9 +195,9 @@ pub struct ConnEntry {
 /// registry when dropped. One guard per spawned `handle_connection`
 /// task — ensures the registry doesn't leak entries on panic /
 /// cancellation, just like [`InFlightGuard`] for the counter.
-struct ConnRegistration {
-	registry: Arc<DashMap<ConnId, ConnEntry>>,
-	conn_id: ConnId,
+pub(crate) struct ConnRegistration {
+	pub(crate) registry: Arc<DashMap<ConnId, ConnEntry>>,
+	pub(crate) conn_id: ConnId,
 }
 
 impl Drop for ConnRegistration {
@@ -196,13 +222,26 @@ struct ListenerHandle {
 	/// [`ListenerSet::bound_count`] so the daemon's boot health watchdog
 	/// can distinguish "still trying" / "succeeded" / "gave up".
 	bind_ready: Arc<AtomicBool>,
+	transport: Transport,
+	/// Shared with the accept loop's [`AcceptCtx::bound_socket`].
+	bound_socket: Arc<Mutex<Option<OwnedFd>>>,
 	join: JoinHandle<()>,
 }
 
+/// A Unix listener's handle and the socket-file settings it was bound
+/// with, so reconcile can re-apply changed attributes in place.
+struct UnixListenerHandle {
+	handle: ListenerHandle,
+	socket: UnixSocketConfig,
+	/// Shared with [`UnixAcceptCtx::keep_file`]; set once the socket is
+	/// handed off so the exiting loop does not unlink the file.
+	keep_file: Arc<AtomicBool>,
+}
+
 /// RAII guard: decrements the per-listener in-flight counter when
 /// dropped. Construct one per spawned `handle_connection` call so the
 /// counter survives panics, cancellations, and `?`-early-returns.
-struct InFlightGuard(Arc<AtomicUsize>);
+pub(crate) struct InFlightGuard(pub(crate) Arc<AtomicUsize>);
 
 impl Drop for InFlightGuard {
 	fn drop(&mut self) {
@@ -246,13 +285,31 @@ impl ListenerSet {
 	pub fn from_security_and_bind_config(security: Arc<SecurityState>, cfg: BindConfig) -> Self {
 		Self {
 			running: Mutex::new(HashMap::new()),
+			unix: Mutex::new(HashMap::new()),
 			draining: Mutex::new(Vec::new()),
 			connections: Arc::new(DashMap::new()),
 			bind_cfg: Arc::new(cfg),
 			security,
+			inherited: Arc::new(InheritedSockets::default()),
 		}
 	}
 
+	/// The daemon-scoped L1 state shared by every listener — the mgmt
+	/// ban verbs reach the ban table through it.
+	#[must_use]
+	pub const fn security(&self) -> &Arc<SecurityState> {
+		&self.security
+	}
+
+	/// Serve listeners whose address matches one of `sockets` on the
+	/// inherited socket instead of binding (spec/crates/engine.md
+	/// § _Socket activation_). Unmatched listeners still bind.
+	#[must_use]
+	pub fn with_inherited_sockets(mut self, sockets: InheritedSockets) -> Self {
+		self.inherited = Arc::new(sockets);
+		self
+	}
+
 	/// Snapshot the in-flight connection registry. Each entry is cloned
 	/// from the shared [`DashMap`]; the snapshot is independent of the
 	/// underlying registry once the call returns.
@@ -312,6 +369,15 @@ impl ListenerSet {
 			})
 			.collect();
 		drop(initial);
+		for (transport, addr) in self.inherited.iter() {
+			if transports.get(&addr) != Some(&transport) {
+				tracing::info!(
+					%addr,
+					?transport,
+					"inherited socket has no listener yet; holding it for a later reload",
+				);
+			}
+		}
 		for addr in addrs {
 			let mut running = self.running.lock();
 			if running.contains_key(&addr) {
@@ -328,6 +394,31 @@ impl ListenerSet {
 			);
 			running.insert(addr, handle);
 		}
+
+		let unix_specs = graph.load_full().symbolic().meta.unix_listeners.clone();
+		for path in self.inherited.unix_paths() {
+			if !unix_specs.contains_key(path) {
+				tracing::info!(
+					?path,
+					"inherited socket has no listener yet; holding it for a later reload",
+				);
+			}
+		}
+		let mut unix = self.unix.lock();
+		for (path, spec) in unix_specs {
+			if unix.contains_key(&path) {
+				tracing::warn!(?path, "listener already running for this path; skipping");
+				continue;
+			}
+			let handle = self.spawn_unix_listener(
+				path.clone(),
+				spec.socket,
+				Arc::clone(graph),
+				Arc::clone(verbosity),
+				Arc::clone(log_sink),
+			);
+			unix.insert(path, handle);
+		}
 	}
 
 	/// Pick the right accept loop for `addr` based on the active
@@ -341,12 +432,52 @@ impl ListenerSet {
 		graph: Arc<ArcSwap<FlowGraph>>,
 		verbosity: Arc<VerbosityState>,
 		log_sink: Arc<dyn FlowLogSink>,
+	) -> ListenerHandle {
+		self.spawn_with(addr, transport, graph, verbosity, log_sink, |ctx| match transport {
+			Transport::Tcp => tokio::spawn(run_accept_loop(ctx)),
+			Transport::Udp => tokio::spawn(run_udp_listener(ctx)),
+		})
+	}
+
+	/// Spawn the accept loop for a `unix:` listener. `AcceptCtx.addr`
+	/// carries the `0.0.0.0:0` placeholder; the path lives on the
+	/// [`UnixAcceptCtx`].
+	fn spawn_unix_listener(
+		&self,
+		path: PathBuf,
+		socket: UnixSocketConfig,
+		graph: Arc<ArcSwap<FlowGraph>>,
+		verbosity: Arc<VerbosityState>,
+		log_sink: Arc<dyn FlowLogSink>,
+	) -> UnixListenerHandle {
+		let placeholder = SocketAddr::from(([0, 0, 0, 0], 0));
+		let keep_file = Arc::new(AtomicBool::new(false));
+		let ctx_socket = socket.clone();
+		let ctx_keep = Arc::clone(&keep_file);
+		let handle = self.spawn_with(placeholder, Transport::Tcp, graph, verbosity, log_sink, |base| {
+			let ctx = UnixAcceptCtx { base, path, socket: ctx_socket, keep_file: ctx_keep };
+			tokio::spawn(run_unix_listener(Arc::new(ctx)))
+		});
+		UnixListenerHandle { handle, socket, keep_file }
+	}
+
+	/// Build the listener-wide [`AcceptCtx`] and hand it to `run`, which
+	/// spawns the accept loop.
+	fn spawn_with(
+		&self,
+		addr: SocketAddr,
+		transport: Transport,
+		graph: Arc<ArcSwap<FlowGraph>>,
+		verbosity: Arc<VerbosityState>,
+		log_sink: Arc<dyn FlowLogSink>,
+		run: impl FnOnce(Arc<AcceptCtx>) -> JoinHandle<()>,
 	) -> ListenerHandle {
 		let accept_cancel = CancellationToken::new();
 		let force_cancel = CancellationToken::new();
 		let in_flight = Arc::new(InFlightSet::new());
 		let in_flight_count = Arc::new(AtomicUsize::new(0));
 		let bind_ready = Arc::new(AtomicBool::new(false));
+		let bound_socket = Arc::new(Mutex::new(None));
 
 		let ctx = Arc::new(AcceptCtx {
 			addr,
@@ -361,13 +492,21 @@ impl ListenerSet {
 			bind_ready: Arc::clone(&bind_ready),
 			bind_cfg: Arc::clone(&self.bind_cfg),
 			connections: Arc::clone(&self.connections),
+			inherited: Arc::clone(&self.inherited),
+			bound_socket: Arc::clone(&bound_socket),
 		});
 
-		let join = match transport {
-			Transport::Tcp => tokio::spawn(run_accept_loop(ctx)),
-			Transport::Udp => tokio::spawn(run_udp_listener(ctx)),
-		};
-		ListenerHandle { accept_cancel, force_cancel, in_flight, in_flight_count, bind_ready, join }
+		let join = run(ctx);
+		ListenerHandle {
+			accept_cancel,
+			force_cancel,
+			in_flight,
+			in_flight_count,
+			bind_ready,
+			transport,
+			bound_socket,
+			join,
+		}
 	}
 
 	/// Whether a listener is currently running for `addr`. Useful for tests
@@ -393,6 +532,71 @@ impl ListenerSet {
 		self.running.lock().get(addr).is_some_and(|h| h.bind_ready.load(Ordering::Acquire))
 	}
 
+	/// Whether the Unix listener at `path` has bound its socket file.
+	/// Counterpart of [`Self::is_bound`].
+	#[must_use]
+	pub fn is_unix_bound(&self, path: &Path) -> bool {
+		self.unix.lock().get(path).is_some_and(|h| h.handle.bind_ready.load(Ordering::Acquire))
+	}
+
+	/// Live in-flight connection count of the Unix listener at `path`.
+	/// Counterpart of [`Self::in_flight_count`].
+	#[must_use]
+	pub fn unix_in_flight_count(&self, path: &Path) -> Option<usize> {
+		self.unix.lock().get(path).map(|h| h.handle.in_flight_count.load(Ordering::Relaxed))
+	}
+
+	/// A `dup` of every socket a replacement daemon should serve on:
+	/// each bound listener's socket, plus inherited sockets still held
+	/// for a later reload. Binary upgrade (spec/crates/daemon.md
+	/// § _Binary upgrade_) passes these over the mgmt socket; the
+	/// listeners here keep serving on their own descriptors.
+	#[must_use]
+	pub fn handoff_sockets(&self) -> Vec<HandoffSocket> {
+		let mut out: Vec<HandoffSocket> = Vec::new();
+		for (addr, h) in self.running.lock().iter() {
+			let Some(fd) = h.bound_socket.lock().as_ref().map(OwnedFd::try_clone) else {
+				continue;
+			};
+			match fd {
+				Ok(fd) => out.push(HandoffSocket { transport: h.transport, addr: *addr, path: None, fd }),
+				Err(e) => {
+					tracing::warn!(%addr, error = %e, "dup of bound socket failed; not handing it off");
+				}
+			}
+		}
+		for (path, h) in self.unix.lock().iter() {
+			let Some(fd) = h.handle.bound_socket.lock().as_ref().map(OwnedFd::try_clone) else {
+				continue;
+			};
+			match fd {
+				Ok(fd) => {
+					// The replacement serves on this file now; the exiting
+					// accept loop must leave it in place.
+					h.keep_file.store(true, Ordering::Release);
+					out.push(HandoffSocket {
+						transport: Transport::Tcp,
+						addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+						path: Some(path.clone()),
+						fd,
+					});
+				}
+				Err(e) => {
+					tracing::warn!(?path, error = %e, "dup of bound socket failed; not handing it off");
+				}
+			}
+		}
+		for held in self.inherited.handoff() {
+			if !out
+				.iter()
+				.any(|s| s.transport == held.transport && s.addr == held.addr && s.path == held.path)
+			{
+				out.push(held);
+			}
+		}
+		out
+	}
+
 	/// Live count of in-flight connections accepted by the listener at
 	/// `addr`. Returns `None` if no listener is currently bound there.
 	///
@@ -405,16 +609,16 @@ impl ListenerSet {
 		self.running.lock().get(addr).map(|h| h.in_flight_count.load(Ordering::Relaxed))
 	}
 
-	/// Number of running listeners.
+	/// Number of running listeners, Unix listeners included.
 	#[must_use]
 	pub fn len(&self) -> usize {
-		self.running.lock().len()
+		self.running.lock().len() + self.unix.lock().len()
 	}
 
 	/// Whether no listeners are currently running.
 	#[must_use]
 	pub fn is_empty(&self) -> bool {
-		self.running.lock().is_empty()
+		self.len() == 0
 	}
 
 	/// Number of listener tasks whose `bind_with_retry` has succeeded —
@@ -426,7 +630,10 @@ impl ListenerSet {
 	/// sees the bound socket's effects.
 	#[must_use]
 	pub fn bound_count(&self) -> usize {
-		self.running.lock().values().filter(|h| h.bind_ready.load(Ordering::Acquire)).count()
+		let net = self.running.lock().values().filter(|h| h.bind_ready.load(Ordering::Acquire)).count();
+		let unix =
+			self.unix.lock().values().filter(|h| h.handle.bind_ready.load(Ordering::Acquire)).count();
+		net + unix
 	}
 
 	/// Number of listeners managed regardless of bind state. Equal to
@@ -435,7 +642,7 @@ impl ListenerSet {
 	/// [`Self::bound_count`] to decide whether the boot completed.
 	#[must_use]
 	pub fn expected_count(&self) -> usize {
-		self.running.lock().len()
+		self.len()
 	}
 
 	/// Soft-drain shutdown per spec/topology.md § _Listener lifecycle_ step 3.
@@ -467,10 +674,11 @@ impl ListenerSet {
 	/// it — a fatal state that warrants halting shutdown rather than
 	/// silently leaking the abort-all step.
 	pub async fn shutdown(&self, drain_timeout: Duration) {
-		let handles: Vec<(SocketAddr, ListenerHandle)> = {
+		let mut handles: Vec<(String, ListenerHandle)> = {
 			let mut running = self.running.lock();
-			running.drain().collect()
+			running.drain().map(|(addr, h)| (addr.to_string(), h)).collect()
 		};
+		handles.extend(self.unix.lock().drain().map(|(path, h)| (unix_label(&path), h.handle)));
 
 		// Step 1: fire all accept_cancels at once so accept loops stop
 		// admitting new work in parallel, not one-by-one.
@@ -485,6 +693,8 @@ impl ListenerSet {
 				in_flight,
 				in_flight_count: _,
 				bind_ready: _,
+				transport: _,
+				bound_socket: _,
 				join,
 			} = handle;
 
@@ -495,10 +705,10 @@ impl ListenerSet {
 
 			// Step 2: wait drain_timeout for in-flight to clear naturally.
 			if tokio::time::timeout(drain_timeout, in_flight.drain()).await.is_ok() {
-				tracing::debug!(?addr, "in-flight drain completed within timeout");
+				tracing::debug!(%addr, "in-flight drain completed within timeout");
 			} else {
 				tracing::warn!(
-					?addr,
+					%addr,
 					?drain_timeout,
 					"drain timed out — firing force_cancel for in-flight",
 				);
@@ -536,6 +746,7 @@ impl ListenerSet {
 			.filter(|d| !d.join.is_finished())
 			.map(|d| DrainingListenerStatus {
 				addr: d.addr,
+				path: d.path.clone(),
 				in_flight: d.in_flight_count.load(Ordering::Relaxed),
 				deadline: d.deadline,
 			})
@@ -584,20 +795,7 @@ impl ListenerSet {
 		for addr in removed {
 			if let Some(handle) = running.remove(&addr) {
 				tracing::info!(?addr, "reconcile: removing listener");
-				let in_flight_count = Arc::clone(&handle.in_flight_count);
-				let deadline = Instant::now() + self.bind_cfg.reconcile_drain_timeout;
-				let join = tokio::spawn(drain_handle_async(
-					addr,
-					handle,
-					self.bind_cfg.force_cancel_grace,
-					self.bind_cfg.reconcile_drain_timeout,
-				));
-				// Sweep dead drains before pushing — `shutdown` would
-				// otherwise have to wade through every drain ever
-				// spawned to find the still-running ones.
-				let mut draining = self.draining.lock();
-				draining.retain(|d| !d.join.is_finished());
-				draining.push(DrainingHandle { addr, deadline, in_flight_count, join });
+				self.retire(addr, None, handle);
 			}
 		}
 
@@ -619,11 +817,81 @@ impl ListenerSet {
 			);
 			running.insert(addr, handle);
 		}
-		drop(active);
+		drop(running);
 		// Unchanged addresses: the per-accept `entries.get(&addr)` in
 		// `run_accept_loop` already picks up the post-swap NodeId on
 		// the next connection — nothing to do here.
+
+		self.reconcile_unix(&active.symbolic().meta.unix_listeners, graph, verbosity, log_sink);
 	}
+
+	/// The `unix:` half of [`Self::reconcile`], keyed by path. A path
+	/// whose `unix_socket` settings changed keeps its listener; the new
+	/// mode / ownership is applied to the existing socket file.
+	fn reconcile_unix(
+		&self,
+		target: &BTreeMap<PathBuf, UnixListenerSpec>,
+		graph: &Arc<ArcSwap<FlowGraph>>,
+		verbosity: &Arc<VerbosityState>,
+		log_sink: &Arc<dyn FlowLogSink>,
+	) {
+		let mut unix = self.unix.lock();
+		let removed: Vec<PathBuf> = unix.keys().filter(|p| !target.contains_key(*p)).cloned().collect();
+		for path in removed {
+			if let Some(h) = unix.remove(&path) {
+				tracing::info!(?path, "reconcile: removing unix listener");
+				self.retire(SocketAddr::from(([0, 0, 0, 0], 0)), Some(path), h.handle);
+			}
+		}
+		for (path, spec) in target {
+			match unix.get_mut(path) {
+				Some(h) if h.socket == spec.socket => {}
+				Some(h) => {
+					tracing::info!(?path, "reconcile: updating unix socket attributes");
+					if let Err(e) = apply_socket_attrs(path, &spec.socket) {
+						tracing::warn!(?path, error = %e, "applying unix socket attributes failed");
+					}
+					h.socket = spec.socket.clone();
+				}
+				None => {
+					tracing::info!(?path, "reconcile: adding unix listener");
+					let handle = self.spawn_unix_listener(
+						path.clone(),
+						spec.socket.clone(),
+						Arc::clone(graph),
+						Arc::clone(verbosity),
+						Arc::clone(log_sink),
+					);
+					unix.insert(path.clone(), handle);
+				}
+			}
+		}
+	}
+
+	/// Drain a listener removed by reconcile in the background, tracked
+	/// in `draining` so [`Self::shutdown`] can join it.
+	fn retire(&self, addr: SocketAddr, path: Option<PathBuf>, handle: ListenerHandle) {
+		let in_flight_count = Arc::clone(&handle.in_flight_count);
+		let deadline = Instant::now() + self.bind_cfg.reconcile_drain_timeout;
+		let label = path.as_deref().map_or_else(|| addr.to_string(), unix_label);
+		let join = tokio::spawn(drain_handle_async(
+			label,
+			handle,
+			self.bind_cfg.force_cancel_grace,
+			self.bind_cfg.reconcile_drain_timeout,
+		));
+		// Sweep dead drains before pushing — `shutdown` would
+		// otherwise have to wade through every drain ever
+		// spawned to find the still-running ones.
+		let mut draining = self.draining.lock();
+		draining.retain(|d| !d.join.is_finished());
+		draining.push(DrainingHandle { addr, path, deadline, in_flight_count, join });
+	}
+}
+
+/// `unix:/path`, the listen-spec spelling, for logs and the mgmt plane.
+pub(crate) fn unix_label(path: &Path) -> String {
+	format!("unix:{}", path.display())
 }
 
 /// Background drain of a removed listener's handle. Mirrors the
@@ -631,7 +899,7 @@ impl ListenerSet {
 /// `tokio::spawn`'d task so [`ListenerSet::reconcile`] returns
 /// immediately.
 async fn drain_handle_async(
-	addr: SocketAddr,
+	addr: String,
 	handle: ListenerHandle,
 	force_cancel_grace: Duration,
 	reconcile_drain_timeout: Duration,
@@ -642,6 +910,8 @@ async fn drain_handle_async(
 		in_flight,
 		in_flight_count: _,
 		bind_ready: _,
+		transport: _,
+		bound_socket: _,
 		join,
 	} = handle;
 
@@ -651,15 +921,15 @@ async fn drain_handle_async(
 
 	// Soft drain.
 	if tokio::time::timeout(reconcile_drain_timeout, in_flight.drain()).await.is_ok() {
-		tracing::info!(?addr, "reconcile drain complete");
+		tracing::info!(%addr, "reconcile drain complete");
 		return;
 	}
 
-	tracing::warn!(?addr, "reconcile drain timed out; firing force_cancel for in-flight");
+	tracing::warn!(%addr, "reconcile drain timed out; firing force_cancel for in-flight");
 	force_cancel.cancel();
 	let _ = tokio::time::timeout(force_cancel_grace, in_flight.drain()).await;
 	in_flight.drain_with_abort().await;
-	tracing::info!(?addr, "reconcile drain complete (forced)");
+	tracing::info!(%addr, "reconcile drain complete (forced)");
 }
 
 impl Default for ListenerSet {
@@ -668,16 +938,31 @@ impl Default for ListenerSet {
 	}
 }
 
-async fn run_accept_loop(ctx: Arc<AcceptCtx>) {
+/// The listening socket for `ctx.addr`: a `dup` of the inherited
+/// socket when socket activation passed one, otherwise a fresh bind
+/// with retry.
+async fn bind_tcp(ctx: &AcceptCtx) -> Option<TcpListener> {
+	match ctx.inherited.tcp(&ctx.addr) {
+		Some(Ok(listener)) => {
+			tracing::info!(addr = ?ctx.addr, "serving on inherited socket");
+			return Some(listener);
+		}
+		Some(Err(e)) => {
+			tracing::warn!(addr = ?ctx.addr, error = %e, "inherited socket unusable; binding instead");
+		}
+		None => {}
+	}
 	let bind_policy = tokio_bind_retry::Policy {
 		max_attempts: ctx.bind_cfg.max_bind_attempts,
 		initial: ctx.bind_cfg.bind_backoff_initial,
 		max: ctx.bind_cfg.bind_backoff_max,
 		..tokio_bind_retry::Policy::default()
 	};
-	let Some(listener) =
-		tokio_bind_retry::tcp(ctx.addr, &ctx.accept_cancel, &bind_policy, TCP_LISTEN_BACKLOG).await
-	else {
+	tokio_bind_retry::tcp(ctx.addr, &ctx.accept_cancel, &bind_policy, TCP_LISTEN_BACKLOG).await
+}
+
+async fn run_accept_loop(ctx: Arc<AcceptCtx>) {
+	let Some(listener) = bind_tcp(&ctx).await else {
 		tracing::error!(
 			addr = ?ctx.addr,
 			attempts = ctx.bind_cfg.max_bind_attempts,
@@ -687,7 +972,7 @@ async fn run_accept_loop(ctx: Arc<AcceptCtx>) {
 		// watchdog observes the failed listener and can react.
 		return;
 	};
-	ctx.bind_ready.store(true, Ordering::Release);
+	let _bound = ctx.publish_bound(&listener);
 
 	loop {
 		tokio::select! {
@@ -755,10 +1040,35 @@ async fn run_accept_loop(ctx: Arc<AcceptCtx>) {
 	}
 }
 
+/// Consume the PROXY header when the listener expects one from
+/// `remote`, rewriting `remote` to the header's source. Untrusted peers
+/// and listeners without `proxy_protocol` are served as-is (`Ok(None)`).
+/// Spec: spec/crates/engine.md § _PROXY protocol_.
+async fn read_proxy_header(
+	ctx: &AcceptCtx,
+	graph: &FlowGraph,
+	stream: &mut TcpStream,
+	remote: &mut SocketAddr,
+) -> io::Result<Option<ProxyInfo>> {
+	let Some(cfg) = graph.listener_proxy_protocol(&ctx.addr) else { return Ok(None) };
+	if !cfg.trusts(remote.ip()) {
+		return Ok(None);
+	}
+	let timeout = ctx.security.cfg.header_timeout;
+	let header = tokio::time::timeout(timeout, proxy_header::read(stream))
+		.await
+		.map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "PROXY header timeout"))??;
+	let peer = *remote;
+	if let Some(addresses) = header.addresses {
+		*remote = addresses.source;
+	}
+	Ok(Some(proxy_header::info(peer, &header)))
+}
+
 async fn handle_connection(
 	ctx: Arc<AcceptCtx>,
-	stream: TcpStream,
-	remote: SocketAddr,
+	mut stream: TcpStream,
+	mut remote: SocketAddr,
 	entry: NodeId,
 	graph: Arc<FlowGraph>,
 	tls_cfg: Option<Arc<rustls::ServerConfig>>,
@@ -766,6 +1076,24 @@ async fn handle_connection(
 	// decrement runs on every exit path including panics and cancellation.
 	_in_flight_guard: InFlightGuard,
 ) {
+	// PROXY protocol: a trusted balancer's header names the real client,
+	// which replaces `remote` before the floor, the registry, or any
+	// predicate sees the connection.
+	let proxy_info = match read_proxy_header(&ctx, &graph, &mut stream, &mut remote).await {
+		Ok(info) => info,
+		Err(e) => {
+			tracing::debug!(error = %e, ?remote, "PROXY header rejected; dropping connection");
+			return;
+		}
+	};
+
+	// Banned sources are dropped before the floor counts them, so a
+	// banned flood cannot crowd out the per-IP and global caps.
+	if ctx.security.drop_banned(remote.ip()) {
+		tracing::debug!(?remote, "banned source: dropping connection");
+		return;
+	}
+
 	// L1 security floor: enforce per-IP and global connection caps
 	// before any further work. On rejection the stream is dropped here,
 	// which sends TCP RST to the client.
@@ -789,10 +1117,66 @@ async fn handle_connection(
 	// `ConnRegistration` construction is panic-free, so the registry
 	// can never see a stranded entry — the guard's `Drop` always runs.
 	let accepted_at = Instant::now();
-	ctx.connections.insert(conn_id, ConnEntry { conn_id, listener_addr: local, remote, accepted_at });
+	ctx.connections.insert(
+		conn_id,
+		ConnEntry { conn_id, listener_addr: local, listener_path: None, remote, accepted_at },
+	);
 	let _conn_registration = ConnRegistration { registry: Arc::clone(&ctx.connections), conn_id };
 	let conn = Arc::new(ConnContext::new(conn_id, remote, local, Transport::Tcp, accepted_at));
+	if let Some(info) = proxy_info {
+		let _ = conn.proxy.set(info);
+	}
+
+	// Disable Nagle once, before either the peek phase or the TLS
+	// handshake gets a chance to consume the socket. L4Forward used to
+	// own this call, but the peek path erases the concrete TcpStream
+	// behind a `PeekedStream` adapter, so the listener has to do it
+	// while the type is still in scope.
+	let _ = stream.set_nodelay(true);
+
+	let kind = graph.listener_kind(&local);
+	dispatch_stream(&ctx, stream, conn, entry, graph, kind, tls_cfg).await;
+}
+
+/// An accepted connection the dispatch path can serve. TCP keeps its
+/// concrete [`L4Conn::Tcp`] so `L4Forward` can use its `TcpStream`
+/// fast path; other stream types enter the graph type-erased.
+pub(crate) trait AcceptedStream:
+	tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static
+{
+	fn into_l4(self) -> L4Conn;
+}
+
+impl AcceptedStream for TcpStream {
+	fn into_l4(self) -> L4Conn {
+		L4Conn::Tcp(self)
+	}
+}
 
+impl AcceptedStream for tokio::net::UnixStream {
+	fn into_l4(self) -> L4Conn {
+		L4Conn::Peeked(Box::new(self))
+	}
+}
+
+/// Everything after accept-time bookkeeping: the peek prelude when the
+/// graph needs it, then the dispatch table. Shared by the TCP and Unix
+/// accept loops; `conn` is already registered and `kind` resolved by
+/// the caller.
+pub(crate) async fn dispatch_stream<S: AcceptedStream>(
+	ctx: &AcceptCtx,
+	stream: S,
+	conn: Arc<ConnContext>,
+	entry: NodeId,
+	graph: Arc<FlowGraph>,
+	kind: ListenerKind,
+	tls_cfg: Option<Arc<rustls::ServerConfig>>,
+) {
+	let remote = conn.remote;
+	// The handshake, stream and body limits are enforced past the
+	// listener (TLS termination, the L7 drivers); they find the floor
+	// through the connection.
+	ConnFloor::install(&conn, &ctx.security);
 	let span = tracing::info_span!("conn", id = %conn.id);
 	let mut flow_ctx = FlowCtx {
 		span,
@@ -803,22 +1187,13 @@ async fn handle_connection(
 		trajectory: TrajectoryBuilder::new(conn.id, entry, now_unix_ms()),
 	};
 
-	// Disable Nagle once, before either the peek phase or the TLS
-	// handshake gets a chance to consume the socket. L4Forward used to
-	// own this call, but the peek path erases the concrete TcpStream
-	// behind a `PeekedStream` adapter, so the listener has to do it
-	// while the type is still in scope.
-	let _ = stream.set_nodelay(true);
-
-	let kind = graph.listener_kind(&local);
-
 	let dispatch_ctx = ConnDispatchCtx {
 		kind,
 		graph: Arc::clone(&graph),
 		entry,
 		conn: Arc::clone(&conn),
 		remote,
-		local_addr: local,
+		local_addr: ctx.addr,
 		tls_cfg,
 	};
 
@@ -857,16 +1232,22 @@ async fn handle_connection(
 			}
 		};
 
-	// Pre-fill ConnContext.tls.sni from the parsed ClientHello so L4
-	// middleware running before an `Upgrade` node can read it. `tls.alpn`
-	// and `tls.version` are post-handshake values; they are populated in
-	// the TLS termination path below (`spec/crates/engine-tls.md` § _Termination flow (L4 → L7 upgrade)_).
-	if let Some(tls_hello) = peek_result.tls.as_ref()
-		&& tls_hello.sni.is_some()
-	{
-		let mut guard = conn.tls.lock();
-		let info = guard.get_or_insert_with(TlsInfo::default);
-		info.sni = tls_hello.sni.as_deref().map(Arc::from);
+	// Pre-fill ConnContext.tls.sni and the JA3 / JA4 fingerprints from
+	// the parsed ClientHello so L4 middleware running before an
+	// `Upgrade` node can read them. `tls.alpn` and `tls.version` are
+	// post-handshake values; they are populated in the TLS termination
+	// path below (`spec/crates/engine-tls.md` § _Termination flow (L4 → L7 upgrade)_).
+	if let Some(tls_hello) = peek_result.tls.as_ref() {
+		let fingerprinted = ClientHello::parse_records(&peeked_buffer).ok().flatten();
+		if tls_hello.sni.is_some() || fingerprinted.is_some() {
+			let mut guard = conn.tls.lock();
+			let info = guard.get_or_insert_with(TlsInfo::default);
+			info.sni = tls_hello.sni.as_deref().map(Arc::from);
+			if let Some(hello) = fingerprinted {
+				info.ja3 = Some(Arc::from(hello.ja3()));
+				info.ja4 = Some(Arc::from(hello.ja4(Ja4Transport::Tcp)));
+			}
+		}
 	}
 
 	let detected = peek_result.detected;
@@ -883,13 +1264,13 @@ async fn handle_connection(
 /// reachable from `entry`, so we never read a prefix and `detected`
 /// is always `None`. The decision table reduces to `(kind,
 /// listener_tls)`. Spec: spec/crates/engine.md § _Dispatch table_.
-async fn dispatch_no_peek(stream: TcpStream, dctx: &ConnDispatchCtx, ctx: &mut FlowCtx) {
+async fn dispatch_no_peek<S: AcceptedStream>(stream: S, dctx: &ConnDispatchCtx, ctx: &mut FlowCtx) {
 	match (dctx.kind, dctx.tls_cfg.as_ref()) {
 		(ListenerKind::Raw, _) => {
 			let result = execute(
 				&dctx.graph,
 				dctx.entry,
-				ExecutorInput::L4(Box::new(L4Conn::Tcp(stream))),
+				ExecutorInput::L4(Box::new(stream.into_l4())),
 				&dctx.conn,
 				ctx,
 			)
@@ -944,7 +1325,7 @@ async fn dispatch_no_peek(stream: TcpStream, dctx: &ConnDispatchCtx, ctx: &mut F
 			let result = execute(
 				&dctx.graph,
 				dctx.entry,
-				ExecutorInput::L4(Box::new(L4Conn::Tcp(stream))),
+				ExecutorInput::L4(Box::new(stream.into_l4())),
 				&dctx.conn,
 				ctx,
 			)
@@ -959,8 +1340,8 @@ async fn dispatch_no_peek(stream: TcpStream, dctx: &ConnDispatchCtx, ctx: &mut F
 /// Post-peek dispatch implementing spec/crates/engine.md § _Dispatch table_
 /// in full. `detected` may be `None` if the peek prelude exited
 /// without a detector committing — treated as `Unknown` per spec.
-async fn dispatch_peeked(
-	peeked: PeekedStream<TcpStream>,
+async fn dispatch_peeked<S: AcceptedStream>(
+	peeked: PeekedStream<S>,
 	detected: Option<DetectedProtocol>,
 	dctx: &ConnDispatchCtx,
 	ctx: &mut FlowCtx,
@@ -1032,8 +1413,8 @@ async fn dispatch_peeked(
 /// is invisible to downstream middleware / fetches. Cleartext H1 and
 /// h2c paths share this entry — `conn.http_version` is what tells
 /// the executor's `Node::Upgrade` arm which hyper builder to pick.
-async fn l4_subgraph(
-	peeked: PeekedStream<TcpStream>,
+async fn l4_subgraph<S: AcceptedStream>(
+	peeked: PeekedStream<S>,
 	graph: &Arc<FlowGraph>,
 	entry: NodeId,
 	conn: &Arc<ConnContext>,
@@ -1052,6 +1433,10 @@ async fn l4_subgraph(
 /// `PeekedStream<TcpStream>` for the post-peek path. Generic so the
 /// rewind buffer is invisible to rustls: `LazyConfigAcceptor` reads
 /// from offset zero in either case.
+#[allow(
+	clippy::too_many_lines,
+	reason = "one linear handshake sequence: L1 handshake gate, ClientHello, SNI capture, handshake, session facts, dispatch; each step reads the previous one's locals"
+)]
 async fn run_tls<S>(
 	stream: S,
 	tls_cfg: Arc<rustls::ServerConfig>,
@@ -1063,8 +1448,26 @@ async fn run_tls<S>(
 ) where
 	S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
+	// L1 handshake floor: per-IP handshake rate and the daemon-wide
+	// pending cap. Rejection drops the stream before the ClientHello is
+	// read; the guard's slot is held until the handshake settles.
+	let handshake_guard = ConnFloor::of(conn).map(|floor| floor.begin_handshake());
+	if matches!(handshake_guard, Some(None)) {
+		tracing::debug!(conn_id = %conn.id, ?remote, "L1 handshake cap: dropping connection");
+		return;
+	}
+	// Covers ClientHello read through handshake completion; exported
+	// on the connection's trace (spec/crates/engine.md
+	// § _Distributed tracing_).
+	let hs_span = tracing::debug_span!(
+		parent: &ctx.span,
+		"tls_handshake",
+		sni = tracing::field::Empty,
+		alpn = tracing::field::Empty,
+		version = tracing::field::Empty,
+	);
 	let lazy = tokio_rustls::LazyConfigAcceptor::new(rustls::server::Acceptor::default(), stream);
-	let start = match lazy.await {
+	let start = match lazy.instrument(hs_span.clone()).await {
 		Ok(s) => s,
 		Err(e) => {
 			tracing::debug!(
@@ -1079,13 +1482,16 @@ async fn run_tls<S>(
 
 	{
 		let hello = start.client_hello();
-		let sni = hello.server_name().map(|s| Arc::from(s.to_ascii_lowercase()));
+		let sni: Option<Arc<str>> = hello.server_name().map(|s| Arc::from(s.to_ascii_lowercase()));
+		if let Some(sni) = &sni {
+			hs_span.record("sni", &**sni);
+		}
 		let mut guard = conn.tls.lock();
 		let info = guard.get_or_insert_with(TlsInfo::default);
 		info.sni = sni;
 	}
 
-	let mut tls_stream = match start.into_stream(tls_cfg).await {
+	let mut tls_stream = match start.into_stream(tls_cfg).instrument(hs_span.clone()).await {
 		Ok(s) => s,
 		Err(e) => {
 			tracing::debug!(
@@ -1097,6 +1503,7 @@ async fn run_tls<S>(
 			return;
 		}
 	};
+	drop(handshake_guard);
 
 	let alpn;
 	let tls_version;
@@ -1130,6 +1537,13 @@ async fn run_tls<S>(
 			rustls::ProtocolVersion::TLSv1_3 => Some(TlsVersion::Tls13),
 			_ => None,
 		});
+		if let Some(alpn) = alpn.as_deref() {
+			hs_span.record("alpn", String::from_utf8_lossy(alpn).as_ref());
+		}
+		if let Some(v) = server_conn.protocol_version() {
+			hs_span.record("version", tracing::field::debug(v));
+		}
+		drop(hs_span);
 
 		// TLS 1.3 0-RTT (early data) detection + drain. Per
 		// `spec/crates/engine-tls.md` § _TLS 1.3 0-RTT (early data)_, rustls's server
@@ -1202,11 +1616,11 @@ async fn run_tls<S>(
 /// [`classify`] after every read until a detector commits or the
 /// buffer fills. Returns the accumulated buffer (as
 /// [`bytes::Bytes`] for the [`PeekedStream`] rewind side), the
-/// original `TcpStream` (so the caller can keep wrapping it), and
-/// the structured [`PeekResult`].
-async fn run_peek_phase(
-	mut stream: TcpStream,
-) -> std::io::Result<(bytes::Bytes, TcpStream, PeekResult)> {
+/// original stream (so the caller can keep wrapping it), and the
+/// structured [`PeekResult`].
+async fn run_peek_phase<S: AcceptedStream>(
+	mut stream: S,
+) -> std::io::Result<(bytes::Bytes, S, PeekResult)> {
 	use tokio::io::AsyncReadExt;
 
 	let mut buf = Vec::with_capacity(MAX_PEEK_BYTES);
@@ -3,7 +3,8 @@
 //! handler needs (graph swap, verbosity, log sink, security, in-flight
 //! tracking, cancel tokens, bind config, conn registry). UDP listeners
 //! extend it with [`UdpAcceptCtx`] for the physical socket + dispatch
-//! table; per-connection dispatch helpers receive a small
+//! table, Unix listeners with [`UnixAcceptCtx`] for the socket path;
+//! per-connection dispatch helpers receive a small
 //! [`ConnDispatchCtx`] carrying the captured graph snapshot and the
 //! resolved per-accept state.
 //!
@@ -14,19 +15,24 @@
 //! per-conn) and lets `tokio::spawn` clone a single `Arc` instead of N.
 
 use std::net::SocketAddr;
+use std::os::fd::{AsFd, OwnedFd};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use in_flight_set::InFlightSet;
+use parking_lot::Mutex;
 
 use tokio_util::sync::CancellationToken;
+use vane_core::rule::UnixSocketConfig;
 use vane_core::{ConnContext, ConnId, FlowLogSink, ListenerKind, NodeId};
 
 use crate::flow_graph::FlowGraph;
 use crate::listener::{BindConfig, ConnEntry};
 use crate::security::SecurityState;
+use crate::socket_activation::InheritedSockets;
 use crate::verbosity::VerbosityState;
 
 /// State shared by a single listener's accept loop and every per-connection
@@ -50,6 +56,38 @@ pub(crate) struct AcceptCtx {
 	pub bind_ready: Arc<AtomicBool>,
 	pub bind_cfg: Arc<BindConfig>,
 	pub connections: Arc<DashMap<ConnId, ConnEntry>>,
+	/// Socket-activation sockets shared by every listener; the accept
+	/// loop takes a `dup` of the one matching `addr`, if any.
+	pub inherited: Arc<InheritedSockets>,
+	/// A `dup` of the socket the loop is serving on, published once the
+	/// bind succeeds and cleared when the loop exits. Read by
+	/// [`crate::ListenerSet::handoff_sockets`] for binary upgrade.
+	pub bound_socket: Arc<Mutex<Option<OwnedFd>>>,
+}
+
+impl AcceptCtx {
+	/// Mark the listener bound: flip `bind_ready` and publish a `dup`
+	/// of `socket` for hand-off. The returned guard withdraws the `dup`
+	/// when the loop exits.
+	pub(crate) fn publish_bound(&self, socket: &impl AsFd) -> BoundSocketGuard {
+		match socket.as_fd().try_clone_to_owned() {
+			Ok(fd) => *self.bound_socket.lock() = Some(fd),
+			Err(e) => {
+				tracing::warn!(addr = ?self.addr, error = %e, "dup of bound socket failed; it cannot be handed off");
+			}
+		}
+		self.bind_ready.store(true, Ordering::Release);
+		BoundSocketGuard(Arc::clone(&self.bound_socket))
+	}
+}
+
+/// RAII guard from [`AcceptCtx::publish_bound`].
+pub(crate) struct BoundSocketGuard(Arc<Mutex<Option<OwnedFd>>>);
+
+impl Drop for BoundSocketGuard {
+	fn drop(&mut self) {
+		self.0.lock().take();
+	}
 }
 
 /// UDP listener extension: adds the physical socket + per-listener
@@ -61,6 +99,19 @@ pub(crate) struct UdpAcceptCtx {
 	pub dispatch_table: Arc<crate::listener_udp::DispatchTable>,
 }
 
+/// Unix listener extension: the socket path that keys the graph's
+/// `unix_listeners` map, and the file attributes applied after bind.
+/// `base.addr` is a placeholder (`0.0.0.0:0`); a path listener has no
+/// socket address.
+pub(crate) struct UnixAcceptCtx {
+	pub base: Arc<AcceptCtx>,
+	pub path: PathBuf,
+	pub socket: UnixSocketConfig,
+	/// Set when the socket is handed to a replacement daemon: the loop
+	/// then leaves the socket file in place on exit.
+	pub keep_file: Arc<AtomicBool>,
+}
+
 /// Per-connection dispatch state shared by `dispatch_no_peek` and
 /// `dispatch_peeked`. Built once inside `handle_connection` after the
 /// graph snapshot + `ConnContext` are in hand.
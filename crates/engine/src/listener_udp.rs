@@ -14,7 +14,7 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 use bytes::Bytes;
-use clienthello::{Extractor, PushOutcome};
+use clienthello::{ClientHello, Extractor, Ja4Transport, PushOutcome};
 use dashmap::DashMap;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
@@ -138,7 +138,30 @@ pub struct UdpListenerHandle {
 	pub dispatch_table: Arc<DispatchTable>,
 }
 
-/// Bind a UDP socket on `addr` with bind-retry, then run the recv +
+/// UDP counterpart of the TCP listener's `bind_tcp`: a `dup` of the
+/// inherited socket when socket activation passed one, otherwise a
+/// fresh bind with retry.
+async fn bind_udp(base: &AcceptCtx) -> Option<tokio::net::UdpSocket> {
+	match base.inherited.udp(&base.addr) {
+		Some(Ok(socket)) => {
+			tracing::info!(addr = ?base.addr, "serving on inherited udp socket");
+			return Some(socket);
+		}
+		Some(Err(e)) => {
+			tracing::warn!(addr = ?base.addr, error = %e, "inherited udp socket unusable; binding instead");
+		}
+		None => {}
+	}
+	let bind_policy = tokio_bind_retry::Policy {
+		max_attempts: base.bind_cfg.max_bind_attempts,
+		initial: base.bind_cfg.bind_backoff_initial,
+		max: base.bind_cfg.bind_backoff_max,
+		..tokio_bind_retry::Policy::default()
+	};
+	tokio_bind_retry::udp(base.addr, &base.accept_cancel, &bind_policy).await
+}
+
+/// Bind (or adopt the inherited) UDP socket on `addr`, then run the recv +
 /// dispatch loop until `accept_cancel` fires. Cold-path datagrams
 /// spawn one tracked task each (`in_flight`), inheriting
 /// `force_cancel` through `FlowCtx::cancel` for shutdown drain.
@@ -147,14 +170,7 @@ pub struct UdpListenerHandle {
 /// § _`udp_dispatch`_ for the per-session
 /// timeout (owned by the `L4Forward` forwarder).
 pub(crate) async fn run_udp_listener(base: Arc<AcceptCtx>) {
-	let bind_policy = tokio_bind_retry::Policy {
-		max_attempts: base.bind_cfg.max_bind_attempts,
-		initial: base.bind_cfg.bind_backoff_initial,
-		max: base.bind_cfg.bind_backoff_max,
-		..tokio_bind_retry::Policy::default()
-	};
-	let Some(socket) = tokio_bind_retry::udp(base.addr, &base.accept_cancel, &bind_policy).await
-	else {
+	let Some(socket) = bind_udp(&base).await else {
 		tracing::error!(
 			addr = ?base.addr,
 			attempts = base.bind_cfg.max_bind_attempts,
@@ -162,7 +178,7 @@ pub(crate) async fn run_udp_listener(base: Arc<AcceptCtx>) {
 		);
 		return;
 	};
-	base.bind_ready.store(true, Ordering::Release);
+	let _bound = base.publish_bound(&socket);
 	let socket = Arc::new(socket);
 	let ctx = Arc::new(UdpAcceptCtx {
 		base,
@@ -347,12 +363,12 @@ fn advance_existing_pending_peek(
 	let state = Arc::clone(state);
 	drop(entry);
 	match advance_pending_peek(&state, &datagram) {
-		PendingAdvance::Sni(sni) => {
+		PendingAdvance::Hello(tls) => {
 			let datagrams = drain_pending(&state, datagram);
 			if ctx.dispatch_table.remove(&pending_key).is_some() {
 				pending_count.fetch_sub(1, Ordering::Relaxed);
 			}
-			spawn_cold_path(ctx, peer, datagrams, Some(sni));
+			spawn_cold_path(ctx, peer, datagrams, Some(tls));
 		}
 		PendingAdvance::NeedMore => {
 			// Buffered for later datagram; no spawn.
@@ -401,9 +417,9 @@ fn dispatch_cold_datagram(
 	}
 	let state = Arc::new(PendingPeekState::new());
 	match advance_pending_peek(&state, &datagram) {
-		PendingAdvance::Sni(sni) => {
+		PendingAdvance::Hello(tls) => {
 			let datagrams = drain_pending(&state, datagram);
-			spawn_cold_path(ctx, peer, datagrams, Some(sni));
+			spawn_cold_path(ctx, peer, datagrams, Some(tls));
 		}
 		PendingAdvance::NeedMore => {
 			let pending_key = DispatchKey::PendingPeek(peer);
@@ -423,9 +439,10 @@ fn dispatch_cold_datagram(
 	}
 }
 
-/// Outcome of a single [`advance_pending_peek`] step.
+/// Outcome of a single [`advance_pending_peek`] step. `Hello` carries
+/// the `ConnContext.tls` seed built from the completed `ClientHello`.
 enum PendingAdvance {
-	Sni(String),
+	Hello(TlsInfo),
 	NeedMore,
 	Drop(PeekDropReason),
 }
@@ -489,8 +506,9 @@ fn advance_pending_peek(state: &PendingPeekState, datagram: &Bytes) -> PendingAd
 	}
 	state.bytes.store(new_bytes, Ordering::Relaxed);
 
-	match state.extractor.lock().push(datagram) {
-		Ok(PushOutcome::Sni(s)) => PendingAdvance::Sni(s),
+	let mut extractor = state.extractor.lock();
+	match extractor.push(datagram) {
+		Ok(PushOutcome::Sni(s)) => PendingAdvance::Hello(peeked_tls_info(s, extractor.client_hello())),
 		Ok(PushOutcome::NeedMore) => PendingAdvance::NeedMore,
 		Err(clienthello::Error::UnsupportedVersion(version)) => {
 			// QUIC v2 (0x6b33_43cf) and any other version probe lands
@@ -511,6 +529,16 @@ fn advance_pending_peek(state: &PendingPeekState, datagram: &Bytes) -> PendingAd
 	}
 }
 
+/// SNI plus the JA3 / JA4 fingerprints of the hello it came from.
+fn peeked_tls_info(sni: String, hello: Option<&ClientHello>) -> TlsInfo {
+	TlsInfo {
+		sni: Some(Arc::from(sni)),
+		ja3: hello.map(|h| Arc::from(h.ja3())),
+		ja4: hello.map(|h| Arc::from(h.ja4(Ja4Transport::Quic))),
+		..TlsInfo::default()
+	}
+}
+
 /// Drain the buffered datagrams out of a pending-peek state so they
 /// can be replayed to the matched `L4Forward` handler. The triggering
 /// datagram is already buffered (added by `advance_pending_peek` for
@@ -536,7 +564,7 @@ fn spawn_cold_path(
 	ctx: &Arc<UdpAcceptCtx>,
 	peer: SocketAddr,
 	first_packets: Vec<Bytes>,
-	sni: Option<String>,
+	tls: Option<TlsInfo>,
 ) {
 	let captured: Arc<FlowGraph> = ctx.base.graph.load_full();
 	let Some(entry) = captured.symbolic().entries.get(&ctx.base.addr).copied() else {
@@ -553,7 +581,7 @@ fn spawn_cold_path(
 		Arc::clone(ctx),
 		peer,
 		first_packets,
-		sni,
+		tls,
 		entry,
 		captured,
 		in_flight_guard,
@@ -624,15 +652,15 @@ impl Drop for InFlightGuard {
 /// fetch sends to upstream verbatim before subscribing to the
 /// inbound mpsc.
 ///
-/// `sni` is `Some` only on the pending-peek completion path — the
-/// pre-extracted SNI is stamped onto `ConnContext.tls.sni` so the
-/// matching `tls.sni` predicate evaluates correctly without the
-/// listener needing TLS termination.
+/// `tls` is `Some` only on the pending-peek completion path — the
+/// pre-extracted SNI and fingerprints are stamped onto
+/// `ConnContext.tls` so the matching `tls.sni` / `tls.ja4` predicate
+/// evaluates correctly without the listener needing TLS termination.
 async fn handle_cold_path(
 	ctx: Arc<UdpAcceptCtx>,
 	peer: SocketAddr,
 	first_packets: Vec<Bytes>,
-	sni: Option<String>,
+	tls: Option<TlsInfo>,
 	entry: NodeId,
 	graph: Arc<FlowGraph>,
 	_in_flight_guard: InFlightGuard,
@@ -645,9 +673,8 @@ async fn handle_cold_path(
 		.increment(1);
 
 	let conn_id = crate::listener::next_conn_id();
-	let initial_tls = sni.map(|s| TlsInfo { sni: Some(Arc::from(s)), ..TlsInfo::default() });
 	let conn = Arc::new(ConnContext::new(conn_id, peer, local, Transport::Udp, Instant::now()));
-	if let Some(info) = initial_tls {
+	if let Some(info) = tls {
 		*conn.tls.lock() = Some(info);
 	}
 	// Stash the dispatch table so L4Forward (or any future UDP fetch)
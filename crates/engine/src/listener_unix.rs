@@ -0,0 +1,227 @@
+//! Unix-domain-socket listener: bind a `unix:/path` entry, apply the
+//! socket file's mode / ownership, and feed accepted streams into the
+//! same peek + dispatch path as TCP. The peer's `SO_PEERCRED`
+//! credentials land in [`ConnContext::peer_cred`] for the
+//! `remote.uid` / `remote.gid` / `remote.pid` predicates.
+//!
+//! See `spec/crates/engine.md` § _Unix listeners_.
+
+use std::io;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use tokio::net::{UnixListener, UnixStream};
+use vane_core::rule::UnixSocketConfig;
+use vane_core::{ConnContext, ListenerKind, NodeId, PeerCred, Transport};
+
+use crate::flow_graph::FlowGraph;
+use crate::listener::{ConnEntry, ConnRegistration, InFlightGuard, dispatch_stream, next_conn_id};
+use crate::listener_ctx::UnixAcceptCtx;
+
+pub(crate) async fn run_unix_listener(ctx: Arc<UnixAcceptCtx>) {
+	let Some((listener, _socket_file)) = bind_unix(&ctx).await else {
+		tracing::error!(
+			path = ?ctx.path,
+			attempts = ctx.base.bind_cfg.max_bind_attempts,
+			"unix listener bind failed after exhausting retries — giving up on this path",
+		);
+		return;
+	};
+	let _bound = ctx.base.publish_bound(&listener);
+
+	loop {
+		tokio::select! {
+			biased;
+			() = ctx.base.accept_cancel.cancelled() => return,
+			accepted = listener.accept() => {
+				let stream = match accepted {
+					Ok((s, _)) => s,
+					Err(e) => {
+						tracing::warn!(path = ?ctx.path, ?e, "accept failed; backing off");
+						let cancelled = tokio_bind_retry::sleep_or_cancel(
+							ctx.base.bind_cfg.bind_backoff_initial,
+							&ctx.base.accept_cancel,
+						)
+						.await;
+						if cancelled {
+							return;
+						}
+						continue;
+					}
+				};
+
+				// Per-accept entry lookup by path, for the same reason the
+				// TCP loop looks up by address: `NodeId`s are reassigned on
+				// every recompile (spec/crates/engine.md § _Hot reload_).
+				let captured: Arc<FlowGraph> = ctx.base.graph.load_full();
+				let Some(spec) = captured.symbolic().meta.unix_listeners.get(&ctx.path) else {
+					tracing::debug!(path = ?ctx.path, "no entry in active graph; dropping connection");
+					continue;
+				};
+				let (entry, kind) = (spec.entry, spec.kind);
+				ctx.base.in_flight_count.fetch_add(1, Ordering::Relaxed);
+				let in_flight_guard = InFlightGuard(Arc::clone(&ctx.base.in_flight_count));
+				ctx.base.in_flight.spawn(handle_unix_connection(
+					Arc::clone(&ctx),
+					stream,
+					entry,
+					kind,
+					captured,
+					in_flight_guard,
+				));
+			}
+		}
+	}
+}
+
+async fn handle_unix_connection(
+	ctx: Arc<UnixAcceptCtx>,
+	stream: UnixStream,
+	entry: NodeId,
+	kind: ListenerKind,
+	graph: Arc<FlowGraph>,
+	_in_flight_guard: InFlightGuard,
+) {
+	// Local peers share no source IP, so only the global cap applies.
+	let Some(_sec_guard) = ctx.base.security.check_and_register_local() else {
+		tracing::debug!(path = ?ctx.path, "L1 connection cap: dropping connection");
+		return;
+	};
+	metrics::counter!("vane.requests.total", "listener_port" => "unix").increment(1);
+
+	// A path listener has no socket addresses; both sides carry the
+	// placeholder from `AcceptCtx.addr`.
+	let placeholder = ctx.base.addr;
+	let conn_id = next_conn_id();
+	let accepted_at = Instant::now();
+	ctx.base.connections.insert(
+		conn_id,
+		ConnEntry {
+			conn_id,
+			listener_addr: placeholder,
+			listener_path: Some(ctx.path.clone()),
+			remote: placeholder,
+			accepted_at,
+		},
+	);
+	let _conn_registration =
+		ConnRegistration { registry: Arc::clone(&ctx.base.connections), conn_id };
+	let conn =
+		Arc::new(ConnContext::new(conn_id, placeholder, placeholder, Transport::Tcp, accepted_at));
+	match stream.peer_cred() {
+		Ok(cred) => {
+			let _ = conn.peer_cred.set(PeerCred { uid: cred.uid(), gid: cred.gid(), pid: cred.pid() });
+		}
+		Err(e) => tracing::debug!(path = ?ctx.path, error = %e, "SO_PEERCRED unavailable"),
+	}
+
+	dispatch_stream(&ctx.base, stream, conn, entry, graph, kind, None).await;
+}
+
+/// The listening socket for `ctx.path`: a `dup` of an inherited socket
+/// (socket activation or binary upgrade), which the loop never unlinks,
+/// otherwise a fresh bind with the bind config's retry budget. Each
+/// failed attempt (live socket still at the path, stale socket with
+/// `remove_stale: false`, attribute errors) backs off and retries, so a
+/// listener re-added on reload waits out the old one's exit.
+async fn bind_unix(ctx: &UnixAcceptCtx) -> Option<(UnixListener, Option<SocketFile>)> {
+	match ctx.base.inherited.unix(&ctx.path) {
+		Some(Ok(listener)) => {
+			tracing::info!(path = ?ctx.path, "serving on inherited socket");
+			return Some((listener, None));
+		}
+		Some(Err(e)) => {
+			tracing::warn!(path = ?ctx.path, error = %e, "inherited socket unusable; binding instead");
+		}
+		None => {}
+	}
+	let cfg = &ctx.base.bind_cfg;
+	let mut delay = cfg.bind_backoff_initial;
+	for attempt in 1..=cfg.max_bind_attempts {
+		match bind_once(ctx).await {
+			Ok((listener, file)) => return Some((listener, Some(file))),
+			Err(e) => {
+				tracing::warn!(path = ?ctx.path, attempt, error = %e, "unix listener bind failed");
+			}
+		}
+		if attempt == cfg.max_bind_attempts
+			|| tokio_bind_retry::sleep_or_cancel(delay, &ctx.base.accept_cancel).await
+		{
+			break;
+		}
+		delay = (delay * 2).min(cfg.bind_backoff_max);
+	}
+	None
+}
+
+async fn bind_once(ctx: &UnixAcceptCtx) -> io::Result<(UnixListener, SocketFile)> {
+	let (path, cfg) = (ctx.path.as_path(), &ctx.socket);
+	match std::fs::symlink_metadata(path) {
+		Ok(meta) if !meta.file_type().is_socket() => {
+			return Err(io::Error::new(io::ErrorKind::AlreadyExists, "path exists and is not a socket"));
+		}
+		Ok(_) => match UnixStream::connect(path).await {
+			Ok(_) => return Err(io::Error::new(io::ErrorKind::AddrInUse, "socket is live")),
+			Err(e) if e.kind() == io::ErrorKind::ConnectionRefused && cfg.remove_stale => {
+				tracing::info!(?path, "removing stale unix socket");
+				std::fs::remove_file(path)?;
+			}
+			Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+				return Err(io::Error::new(
+					io::ErrorKind::AddrInUse,
+					"stale socket at path and remove_stale is false",
+				));
+			}
+			Err(e) => return Err(e),
+		},
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+		Err(e) => return Err(e),
+	}
+
+	let listener = UnixListener::bind(path)?;
+	let file = SocketFile::new(path, Arc::clone(&ctx.keep_file))?;
+	apply_socket_attrs(path, cfg)?;
+	Ok((listener, file))
+}
+
+/// Apply `mode` / `owner` / `group` to the bound socket file. Until
+/// this runs the file carries the process umask's permissions.
+pub(crate) fn apply_socket_attrs(path: &Path, cfg: &UnixSocketConfig) -> io::Result<()> {
+	if let Some(mode) = cfg.mode {
+		std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+	}
+	if cfg.owner.is_some() || cfg.group.is_some() {
+		std::os::unix::fs::chown(path, cfg.owner, cfg.group)?;
+	}
+	Ok(())
+}
+
+/// Removes the socket file when the listener exits — unless the socket
+/// was handed off (`keep`), or something else has since replaced the
+/// file (a listener re-added on reload binding the same path), detected
+/// by inode.
+struct SocketFile {
+	path: PathBuf,
+	ino: u64,
+	keep: Arc<AtomicBool>,
+}
+
+impl SocketFile {
+	fn new(path: &Path, keep: Arc<AtomicBool>) -> io::Result<Self> {
+		Ok(Self { path: path.to_path_buf(), ino: std::fs::symlink_metadata(path)?.ino(), keep })
+	}
+}
+
+impl Drop for SocketFile {
+	fn drop(&mut self) {
+		if self.keep.load(Ordering::Acquire) {
+			return;
+		}
+		if std::fs::symlink_metadata(&self.path).is_ok_and(|m| m.ino() == self.ino) {
+			let _ = std::fs::remove_file(&self.path);
+		}
+	}
+}
@@ -3,15 +3,20 @@
 //!
 //! Catalog:
 //! - L7 stateless: `host_header_match`, `path_prefix`, `method_match`,
-//!   `forward_client_ip`.
-//! - L7 stateful: `rate_limit`.
+//!   `forward_client_ip`, `request_headers`, `rewrite`, `redirect`.
+//! - L7 stateful: `rate_limit`, `concurrency_limit`.
+//! - L7 response: `compress`, `response_headers`.
 //! - L4 peek: `sni_peek`.
 //!
 //! See [`spec/crates/engine.md` § _Middleware_](../../../spec/crates/engine.md#middleware).
 
+pub mod compress;
+pub mod concurrency_limit;
 pub mod forward_client_ip;
+pub mod headers;
 pub mod host_header_match;
 pub mod method_match;
 pub mod path_prefix;
 pub mod rate_limit;
+pub mod rewrite;
 pub mod sni_peek;
@@ -0,0 +1,684 @@
+//! `compress` — streaming response compression, L7 response middleware.
+//!
+//! Negotiates `Accept-Encoding` against the configured codings and
+//! re-encodes the upstream body frame by frame: each data frame is fed
+//! to the encoder and flushed, so the body stays `Body::Stream` and the
+//! LazyBuffer response track is never forced. Server-sent events and
+//! long polls keep working because nothing waits for end of stream.
+//!
+//! The request is gone by the time the response phase runs; the
+//! `Accept-Encoding` header comes from the [`RequestHead`] the executor
+//! attaches to the response.
+//!
+//! See `spec/crates/engine.md` § _Middleware_.
+
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::header::{
+	ACCEPT_ENCODING, ACCEPT_RANGES, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE,
+	ETAG, VARY,
+};
+use http::{HeaderMap, HeaderValue, Method};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use vane_core::{
+	Body, ConnContext, Decision, Error, FlowCtx, L7ResponseMiddleware, MiddlewareKind, RequestHead,
+	Response,
+};
+
+use crate::factories::{FactoryError, MiddlewareFactories};
+use crate::flow_graph::MiddlewareInst;
+
+/// Default `content_types`: text and the structured formats that
+/// compress well. `image/*` other than SVG and media types are already
+/// compressed.
+const DEFAULT_CONTENT_TYPES: &[&str] = &[
+	"text/*",
+	"application/json",
+	"application/javascript",
+	"application/xml",
+	"application/wasm",
+	"application/manifest+json",
+	"image/svg+xml",
+];
+
+const DEFAULT_MIN_SIZE: u64 = 1024;
+
+/// Streaming-friendly levels: cheap enough to run per frame on the
+/// request path, still most of the ratio of the defaults.
+const GZIP_LEVEL: u32 = 5;
+const BROTLI_QUALITY: u32 = 4;
+const BROTLI_LGWIN: u32 = 22;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coding {
+	Brotli,
+	Zstd,
+	Gzip,
+}
+
+impl Coding {
+	fn parse(s: &str) -> Option<Self> {
+		match s {
+			"br" => Some(Self::Brotli),
+			"zstd" => Some(Self::Zstd),
+			"gzip" => Some(Self::Gzip),
+			_ => None,
+		}
+	}
+
+	const fn token(self) -> &'static str {
+		match self {
+			Self::Brotli => "br",
+			Self::Zstd => "zstd",
+			Self::Gzip => "gzip",
+		}
+	}
+
+	fn encoder(self) -> Encoder {
+		match self {
+			Self::Brotli => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+				Vec::new(),
+				4096,
+				BROTLI_QUALITY,
+				BROTLI_LGWIN,
+			))),
+			Self::Zstd => Encoder::Zstd(
+				zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL)
+					.expect("zstd encoder with a valid level"),
+			),
+			Self::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+				Vec::new(),
+				flate2::Compression::new(GZIP_LEVEL),
+			)),
+		}
+	}
+}
+
+/// One `content_types` entry: an exact essence or a `type/*` wildcard.
+#[derive(Debug, Clone)]
+enum TypePattern {
+	Exact(String),
+	Prefix(String),
+}
+
+impl TypePattern {
+	fn parse(s: &str) -> Self {
+		let s = s.trim().to_ascii_lowercase();
+		match s.strip_suffix("/*") {
+			Some(top) => Self::Prefix(format!("{top}/")),
+			None => Self::Exact(s),
+		}
+	}
+
+	fn matches(&self, essence: &str) -> bool {
+		match self {
+			Self::Exact(e) => e == essence,
+			Self::Prefix(p) => essence.starts_with(p.as_str()),
+		}
+	}
+}
+
+pub struct CompressMiddleware {
+	/// Codings in server preference order; breaks client q-value ties.
+	codings: Vec<Coding>,
+	content_types: Vec<TypePattern>,
+	min_size: u64,
+}
+
+#[async_trait]
+impl L7ResponseMiddleware for CompressMiddleware {
+	async fn run(
+		&self,
+		resp: &mut Response,
+		_conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		let Some(head) = resp.extensions().get::<RequestHead>() else {
+			return Ok(Decision::Continue);
+		};
+		if !self.eligible(resp) {
+			return Ok(Decision::Continue);
+		}
+		let coding =
+			if head.method == Method::HEAD { None } else { negotiate(&head.headers, &self.codings) };
+		// From here the representation depends on Accept-Encoding,
+		// whether or not this particular client gets a compressed one.
+		append_vary(resp.headers_mut());
+		let Some(coding) = coding else {
+			return Ok(Decision::Continue);
+		};
+
+		let headers = resp.headers_mut();
+		headers.remove(CONTENT_LENGTH);
+		headers.remove(ACCEPT_RANGES);
+		headers.insert(CONTENT_ENCODING, HeaderValue::from_static(coding.token()));
+		weaken_etag(headers);
+		metrics::counter!("vane.http.compress.responses_total", "coding" => coding.token())
+			.increment(1);
+
+		let body = std::mem::replace(resp.body_mut(), Body::Empty);
+		*resp.body_mut() =
+			Body::from_producer(CompressBody { inner: body, encoder: Some(coding.encoder()) });
+		Ok(Decision::Continue)
+	}
+}
+
+impl CompressMiddleware {
+	/// Status, existing coding, `no-transform`, content type and size —
+	/// everything that does not depend on the client.
+	fn eligible(&self, resp: &Response) -> bool {
+		let status = resp.status().as_u16();
+		// 206 bodies are byte ranges of the identity representation;
+		// 204 / 304 / 1xx have no body to encode.
+		if !(200..300).contains(&status) || status == 204 || status == 206 {
+			return false;
+		}
+		let headers = resp.headers();
+		if headers.get(CONTENT_ENCODING).is_some_and(|v| v.as_bytes() != b"identity") {
+			return false;
+		}
+		if headers
+			.get_all(CACHE_CONTROL)
+			.iter()
+			.filter_map(|v| v.to_str().ok())
+			.flat_map(|v| v.split(','))
+			.any(|d| d.trim().eq_ignore_ascii_case("no-transform"))
+		{
+			return false;
+		}
+		let Some(essence) = headers
+			.get(CONTENT_TYPE)
+			.and_then(|v| v.to_str().ok())
+			.map(|v| v.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+		else {
+			return false;
+		};
+		if !self.content_types.iter().any(|p| p.matches(&essence)) {
+			return false;
+		}
+		let declared = headers
+			.get(CONTENT_LENGTH)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.parse::<u64>().ok())
+			.or_else(|| resp.body().size_hint().exact());
+		declared.is_none_or(|len| len >= self.min_size)
+	}
+}
+
+/// Highest-q coding the client accepts; ties go to server order. A
+/// coding the client does not list falls back to `*`'s weight.
+fn negotiate(headers: &HeaderMap, codings: &[Coding]) -> Option<Coding> {
+	let mut listed: Vec<(String, f32)> = Vec::new();
+	for v in headers.get_all(ACCEPT_ENCODING) {
+		let Ok(s) = v.to_str() else { continue };
+		for item in s.split(',') {
+			let mut parts = item.split(';');
+			let name = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+			if name.is_empty() {
+				continue;
+			}
+			let q = parts
+				.find_map(|p| p.trim().strip_prefix("q=").map(|q| q.trim().parse::<f32>().unwrap_or(0.0)))
+				.unwrap_or(1.0);
+			listed.push((name, q));
+		}
+	}
+	let weight = |token: &str| {
+		listed
+			.iter()
+			.find(|(n, _)| n == token)
+			.or_else(|| listed.iter().find(|(n, _)| n == "*"))
+			.map_or(0.0, |(_, q)| *q)
+	};
+	let mut best: Option<(Coding, f32)> = None;
+	for &c in codings {
+		let q = weight(c.token());
+		if q > 0.0 && best.is_none_or(|(_, bq)| q > bq) {
+			best = Some((c, q));
+		}
+	}
+	best.map(|(c, _)| c)
+}
+
+fn append_vary(headers: &mut HeaderMap) {
+	let present = headers.get_all(VARY).iter().filter_map(|v| v.to_str().ok()).any(|v| {
+		v.split(',').any(|t| {
+			let t = t.trim();
+			t == "*" || t.eq_ignore_ascii_case("accept-encoding")
+		})
+	});
+	if !present {
+		headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+	}
+}
+
+/// The encoded bytes differ from the upstream's, so a strong validator
+/// would lie. A weak one still lets the upstream answer a conditional
+/// GET with 304: `If-None-Match` compares weakly.
+fn weaken_etag(headers: &mut HeaderMap) {
+	let Some(tag) = headers.get(ETAG) else { return };
+	if tag.as_bytes().starts_with(b"W/") {
+		return;
+	}
+	let mut weak = b"W/".to_vec();
+	weak.extend_from_slice(tag.as_bytes());
+	if let Ok(v) = HeaderValue::from_bytes(&weak) {
+		headers.insert(ETAG, v);
+	}
+}
+
+enum Encoder {
+	Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+	Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+	Gzip(flate2::write::GzEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+	/// Compress `data` and flush, returning whatever the encoder emitted.
+	fn push(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+		let out = match self {
+			Self::Brotli(w) => {
+				w.write_all(data)?;
+				w.flush()?;
+				std::mem::take(w.get_mut())
+			}
+			Self::Zstd(w) => {
+				w.write_all(data)?;
+				w.flush()?;
+				std::mem::take(w.get_mut())
+			}
+			Self::Gzip(w) => {
+				w.write_all(data)?;
+				w.flush()?;
+				std::mem::take(w.get_mut())
+			}
+		};
+		Ok(Bytes::from(out))
+	}
+
+	/// Write the stream trailer (gzip CRC, zstd epilogue, brotli last
+	/// meta-block).
+	fn finish(self) -> std::io::Result<Bytes> {
+		let out = match self {
+			Self::Brotli(w) => w.into_inner(),
+			Self::Zstd(w) => w.finish()?,
+			Self::Gzip(w) => w.finish()?,
+		};
+		Ok(Bytes::from(out))
+	}
+}
+
+pin_project! {
+	/// Wraps the upstream body; every data frame goes through the
+	/// encoder, trailers pass through after the encoder's epilogue.
+	struct CompressBody {
+		#[pin]
+		inner: Body,
+		encoder: Option<Encoder>,
+	}
+}
+
+impl HttpBody for CompressBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+		let mut this = self.project();
+		loop {
+			let Some(encoder) = this.encoder.as_mut() else {
+				return this.inner.as_mut().poll_frame(cx);
+			};
+			match this.inner.as_mut().poll_frame(cx) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+				Poll::Ready(Some(Ok(frame))) => match frame.into_data() {
+					Ok(data) => match encoder.push(&data) {
+						// Small frames may not produce output until the
+						// flush boundary; skip empty chunks.
+						Ok(out) if out.is_empty() => {}
+						Ok(out) => return Poll::Ready(Some(Ok(Frame::data(out)))),
+						Err(e) => return Poll::Ready(Some(Err(Error::from(e).with_ctx("compress")))),
+					},
+					Err(trailers) => {
+						let tail = this.encoder.take().map(Encoder::finish);
+						return match tail {
+							Some(Ok(out)) if !out.is_empty() => {
+								// Hold the trailers for the next poll;
+								// the encoder is done so `inner` is
+								// polled through from here.
+								*this.inner = trailer_body(trailers);
+								Poll::Ready(Some(Ok(Frame::data(out))))
+							}
+							Some(Err(e)) => Poll::Ready(Some(Err(Error::from(e).with_ctx("compress")))),
+							_ => Poll::Ready(Some(Ok(trailers))),
+						};
+					}
+				},
+				Poll::Ready(None) => {
+					let tail = this.encoder.take().map(Encoder::finish);
+					return match tail {
+						Some(Ok(out)) if !out.is_empty() => Poll::Ready(Some(Ok(Frame::data(out)))),
+						Some(Err(e)) => Poll::Ready(Some(Err(Error::from(e).with_ctx("compress")))),
+						_ => Poll::Ready(None),
+					};
+				}
+			}
+		}
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.encoder.is_none() && self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		SizeHint::default()
+	}
+}
+
+/// A body that yields one trailers frame.
+fn trailer_body(trailers: Frame<Bytes>) -> Body {
+	Body::from_producer(TrailerOnce { frame: Some(trailers) })
+}
+
+struct TrailerOnce {
+	frame: Option<Frame<Bytes>>,
+}
+
+impl HttpBody for TrailerOnce {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		_cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+		Poll::Ready(self.frame.take().map(Ok))
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.frame.is_none()
+	}
+}
+
+/// Args parser exposed as a registry-friendly factory.
+///
+/// Args shape:
+///
+/// ```json
+/// {
+///   "algorithms":    ["br", "zstd", "gzip"],
+///   "content_types": ["text/*", "application/json"],
+///   "min_size":      1024
+/// }
+/// ```
+///
+/// Every field is optional. `algorithms` lists the codings to offer in
+/// server preference order (default all three). `content_types` entries
+/// are media-type essences or `type/*` wildcards (default: text, JSON,
+/// JavaScript, XML, WASM, web manifests and SVG). `min_size` skips
+/// bodies whose declared length is smaller; unknown lengths are always
+/// compressed.
+///
+/// # Errors
+/// Returns [`FactoryError`] for an unknown or duplicated coding, an
+/// empty `algorithms` / `content_types` list, a non-string entry, or a
+/// non-integer `min_size`.
+pub fn factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	let codings = match args.get("algorithms") {
+		None => vec![Coding::Brotli, Coding::Zstd, Coding::Gzip],
+		Some(v) => {
+			let mut out = Vec::new();
+			for s in string_list(v, "algorithms")? {
+				let c = Coding::parse(&s).ok_or_else(|| {
+					FactoryError::Invalid(format!(
+						"args.algorithms: unknown coding {s:?}; supported: br / zstd / gzip"
+					))
+				})?;
+				if out.contains(&c) {
+					return Err(FactoryError::Invalid(format!("args.algorithms: {s:?} listed twice")));
+				}
+				out.push(c);
+			}
+			out
+		}
+	};
+	let content_types = match args.get("content_types") {
+		None => DEFAULT_CONTENT_TYPES.iter().map(|s| TypePattern::parse(s)).collect(),
+		Some(v) => string_list(v, "content_types")?.iter().map(|s| TypePattern::parse(s)).collect(),
+	};
+	let min_size = match args.get("min_size") {
+		None => DEFAULT_MIN_SIZE,
+		Some(v) => v.as_u64().ok_or_else(|| {
+			FactoryError::Invalid("args.min_size: expected a non-negative integer".to_string())
+		})?,
+	};
+	Ok(MiddlewareInst::L7Response(Arc::new(CompressMiddleware { codings, content_types, min_size })))
+}
+
+fn string_list(v: &serde_json::Value, field: &str) -> Result<Vec<String>, FactoryError> {
+	let arr = v
+		.as_array()
+		.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: expected an array")))?;
+	if arr.is_empty() {
+		return Err(FactoryError::Invalid(format!("args.{field}: must not be empty")));
+	}
+	arr
+		.iter()
+		.map(|e| {
+			e.as_str()
+				.map(str::to_owned)
+				.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: entries must be strings")))
+		})
+		.collect()
+}
+
+/// Plug `compress` into a `MiddlewareFactories` registry.
+pub fn register(factories: &mut MiddlewareFactories) {
+	factories.register("compress", MiddlewareKind::L7Response, factory);
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Read;
+	use std::net::SocketAddr;
+	use std::time::Instant;
+
+	use http_body_util::BodyExt;
+	use tokio_util::sync::CancellationToken;
+	use vane_core::{
+		ConnId, FlowLogEvent, FlowLogSink, FlowLogVerbosity, NodeId, TrajectoryBuilder, Transport,
+	};
+
+	use super::*;
+
+	struct NullSink;
+	impl FlowLogSink for NullSink {
+		fn emit(&self, _event: FlowLogEvent) {}
+	}
+
+	fn middleware(args: &serde_json::Value) -> Arc<dyn L7ResponseMiddleware> {
+		match factory(args).expect("factory") {
+			MiddlewareInst::L7Response(m) => m,
+			_ => unreachable!("compress is L7Response"),
+		}
+	}
+
+	fn response(accept: Option<&str>, headers: &[(&str, &str)], body: Body) -> Response {
+		let mut req = http::Request::builder().uri("/");
+		if let Some(a) = accept {
+			req = req.header(ACCEPT_ENCODING, a);
+		}
+		let req = req.body(Body::Empty).expect("request");
+		let mut b = http::Response::builder().status(200);
+		for (k, v) in headers {
+			b = b.header(*k, *v);
+		}
+		let mut resp = b.body(body).expect("response");
+		resp.extensions_mut().insert(RequestHead::of(&req));
+		resp
+	}
+
+	async fn run(m: &Arc<dyn L7ResponseMiddleware>, resp: &mut Response) {
+		let addr: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+		let conn = Arc::new(ConnContext::new(ConnId(0), addr, addr, Transport::Tcp, Instant::now()));
+		let mut ctx = FlowCtx {
+			span: tracing::Span::none(),
+			log: Arc::new(NullSink),
+			cancel: CancellationToken::new(),
+			accept_cancel: CancellationToken::new(),
+			verbosity: FlowLogVerbosity::Trajectory,
+			trajectory: TrajectoryBuilder::new(conn.id, NodeId::for_testing(0), 0),
+		};
+		assert!(matches!(m.run(resp, &conn, &mut ctx).await.expect("run"), Decision::Continue));
+	}
+
+	async fn collect(resp: Response) -> Bytes {
+		resp.into_body().collect().await.expect("collect").to_bytes()
+	}
+
+	fn text() -> Bytes {
+		Bytes::from("the quick brown fox jumps over the lazy dog. ".repeat(100))
+	}
+
+	#[tokio::test]
+	async fn negotiates_each_coding_and_round_trips() {
+		let m = middleware(&serde_json::json!({}));
+		for (accept, coding) in [("gzip", "gzip"), ("br, gzip", "br"), ("zstd;q=1, br;q=0.5", "zstd")] {
+			let mut resp = response(
+				Some(accept),
+				&[("content-type", "text/plain"), ("content-length", "4500"), ("etag", "\"v1\"")],
+				Body::Static(text()),
+			);
+			run(&m, &mut resp).await;
+			assert_eq!(resp.headers()[CONTENT_ENCODING], coding, "accept {accept}");
+			assert!(!resp.headers().contains_key(CONTENT_LENGTH));
+			assert_eq!(resp.headers()[VARY], "accept-encoding");
+			assert_eq!(resp.headers()[ETAG], "W/\"v1\"");
+			let encoded = collect(resp).await;
+			assert!(encoded.len() < text().len());
+			let mut decoded = Vec::new();
+			match coding {
+				"gzip" => {
+					flate2::read::GzDecoder::new(&encoded[..]).read_to_end(&mut decoded).expect("gunzip");
+				}
+				"br" => {
+					brotli::Decompressor::new(&encoded[..], 4096).read_to_end(&mut decoded).expect("unbr");
+				}
+				_ => decoded = zstd::decode_all(&encoded[..]).expect("unzstd"),
+			}
+			assert_eq!(decoded, text());
+		}
+	}
+
+	/// Data frames pulled from a channel, so the test controls when the
+	/// upstream "sends" the next chunk.
+	struct ChannelBody(tokio::sync::mpsc::Receiver<Bytes>);
+
+	impl HttpBody for ChannelBody {
+		type Data = Bytes;
+		type Error = Error;
+
+		fn poll_frame(
+			mut self: Pin<&mut Self>,
+			cx: &mut Context<'_>,
+		) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+			self.0.poll_recv(cx).map(|o| o.map(|b| Ok(Frame::data(b))))
+		}
+	}
+
+	#[tokio::test]
+	async fn streamed_frames_are_flushed_individually() {
+		let (tx, rx) = tokio::sync::mpsc::channel::<Bytes>(4);
+		let m = middleware(&serde_json::json!({ "algorithms": ["gzip"] }));
+		let mut resp = response(
+			Some("gzip"),
+			&[("content-type", "text/event-stream")],
+			Body::from_producer(ChannelBody(rx)),
+		);
+		run(&m, &mut resp).await;
+		let mut body = resp.into_body();
+
+		tx.send(Bytes::from_static(b"data: one\n\n")).await.expect("send");
+		let first = body.frame().await.expect("frame").expect("ok").into_data().expect("data");
+		assert!(!first.is_empty(), "first event must leave the encoder before end of stream");
+		drop(tx);
+		let mut encoded = first.to_vec();
+		while let Some(frame) = body.frame().await {
+			encoded.extend_from_slice(&frame.expect("ok").into_data().expect("data"));
+		}
+		let mut decoded = String::new();
+		flate2::read::GzDecoder::new(&encoded[..]).read_to_string(&mut decoded).expect("gunzip");
+		assert_eq!(decoded, "data: one\n\n");
+	}
+
+	#[tokio::test]
+	async fn skips_ineligible_responses() {
+		let m = middleware(&serde_json::json!({}));
+		let cases: [(&[(&str, &str)], &str); 5] = [
+			(&[("content-type", "image/png")], "content type"),
+			(&[("content-type", "text/plain"), ("content-encoding", "br")], "already encoded"),
+			(
+				&[("content-type", "text/plain"), ("cache-control", "public, no-transform")],
+				"no-transform",
+			),
+			(&[("content-type", "text/plain"), ("content-length", "10")], "below min_size"),
+			(&[], "no content type"),
+		];
+		for (headers, why) in cases {
+			let mut resp = response(Some("gzip"), headers, Body::Static(text()));
+			run(&m, &mut resp).await;
+			let enc = resp.headers().get(CONTENT_ENCODING).map(|v| v.to_str().expect("ascii"));
+			assert_ne!(enc, Some("gzip"), "{why}");
+		}
+
+		// No acceptable coding: body untouched, but Vary still set.
+		let mut resp =
+			response(Some("identity, gzip;q=0"), &[("content-type", "text/html")], Body::Static(text()));
+		run(&m, &mut resp).await;
+		assert!(!resp.headers().contains_key(CONTENT_ENCODING));
+		assert_eq!(resp.headers()[VARY], "accept-encoding");
+		assert_eq!(collect(resp).await, text());
+	}
+
+	#[test]
+	fn negotiate_honours_q_values_and_wildcard() {
+		let all = [Coding::Brotli, Coding::Zstd, Coding::Gzip];
+		let h = |v: &str| {
+			let mut m = HeaderMap::new();
+			m.insert(ACCEPT_ENCODING, HeaderValue::from_str(v).expect("value"));
+			m
+		};
+		assert_eq!(negotiate(&h("gzip, br"), &all), Some(Coding::Brotli));
+		assert_eq!(negotiate(&h("gzip;q=1, br;q=0.9"), &all), Some(Coding::Gzip));
+		assert_eq!(negotiate(&h("*"), &all), Some(Coding::Brotli));
+		assert_eq!(negotiate(&h("*, br;q=0"), &all), Some(Coding::Zstd));
+		assert_eq!(negotiate(&h("identity"), &all), None);
+		assert_eq!(negotiate(&HeaderMap::new(), &all), None);
+	}
+
+	#[test]
+	fn factory_rejects_bad_args() {
+		for (args, needle) in [
+			(serde_json::json!({ "algorithms": ["deflate"] }), "unknown coding"),
+			(serde_json::json!({ "algorithms": ["br", "br"] }), "twice"),
+			(serde_json::json!({ "algorithms": [] }), "must not be empty"),
+			(serde_json::json!({ "content_types": "text/*" }), "expected an array"),
+			(serde_json::json!({ "min_size": -1 }), "args.min_size"),
+		] {
+			let Err(FactoryError::Invalid(msg)) = factory(&args) else {
+				panic!("{args} must be rejected");
+			};
+			assert!(msg.contains(needle), "{args}: {msg}");
+		}
+	}
+}
@@ -0,0 +1,241 @@
+//! `concurrency_limit` — max-in-flight L7 stateful middleware.
+//!
+//! Caps the requests in flight per key (keyed exactly like
+//! `rate_limit`: remote IP prefix, header / cookie / query value,
+//! composite, or global). A request takes a permit on the way in and
+//! hands it to the executor as a [`StreamGuards`] entry, so the permit
+//! is released when the upstream response body finishes streaming —
+//! a slow upstream stays protected for the whole transfer, not just
+//! until headers. Over the cap, a request optionally waits in a
+//! bounded FIFO queue for `queue.timeout`; otherwise (or on timeout)
+//! it gets `Decision::Short(ShortCircuit::Response(_))` with the
+//! `on_limit` payload (default 503).
+//!
+//! Token buckets (`rate_limit`) bound arrival rate; this bounds
+//! concurrency. Like `rate_limit`, the metadata provider marks it
+//! `stateless: false` so each call site gets its own permit table.
+//! The table only holds keys with requests in flight or queued — the
+//! last permit out removes the entry — so it needs no `max_keys` cap.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use dashmap::DashMap;
+use http::{HeaderName, HeaderValue};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use vane_core::{
+	Body, ConnContext, Decision, Error, FlowCtx, L7RequestMiddleware, MiddlewareKind, Request,
+	ShortCircuit, StreamGuards,
+};
+
+use crate::factories::{FactoryError, MiddlewareFactories};
+use crate::fetch::retry::parse_duration;
+use crate::flow_graph::MiddlewareInst;
+use crate::middleware::rate_limit::{BucketKey, KeyConfig, parse_on_limit};
+
+/// Queue wait when `queue` is given without a `timeout`.
+const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Slots = DashMap<BucketKey, Arc<Slot>>;
+
+/// Per-key permit pool. `tokio::sync::Semaphore` hands released
+/// permits to waiters in arrival order, which is the FIFO queue.
+struct Slot {
+	permits: Arc<Semaphore>,
+	waiting: AtomicUsize,
+}
+
+/// A held permit. Dropping it releases the permit and, when no other
+/// request holds or waits on the key, removes the key's slot.
+struct Permit {
+	held: Option<OwnedSemaphorePermit>,
+	key: BucketKey,
+	slot: Arc<Slot>,
+	slots: Arc<Slots>,
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		drop(self.held.take());
+		retire(&self.slots, &self.key, &self.slot);
+	}
+}
+
+/// Remove `slot` from the table if the table and the caller hold the
+/// only references. Every holder and waiter keeps an `Arc<Slot>`, and
+/// new ones are only cloned out of the table under its shard lock, so
+/// the count check inside `remove_if` cannot race a fresh arrival.
+fn retire(slots: &Slots, key: &BucketKey, slot: &Arc<Slot>) {
+	slots.remove_if(key, |_, s| Arc::ptr_eq(s, slot) && Arc::strong_count(s) == 2);
+}
+
+/// Decrements the queue depth however the wait ends, including the
+/// client going away mid-wait.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+pub struct ConcurrencyLimitMiddleware {
+	slots: Arc<Slots>,
+	key: KeyConfig,
+	max: usize,
+	/// Requests allowed to wait per key; `0` rejects immediately.
+	queue_size: usize,
+	queue_timeout: Duration,
+	response_status: u16,
+	response_headers: Vec<(HeaderName, HeaderValue)>,
+	response_body: Bytes,
+}
+
+impl ConcurrencyLimitMiddleware {
+	async fn acquire(&self, slot: &Slot, ctx: &FlowCtx) -> Option<OwnedSemaphorePermit> {
+		if let Ok(permit) = Arc::clone(&slot.permits).try_acquire_owned() {
+			return Some(permit);
+		}
+		if slot.waiting.fetch_add(1, Ordering::Relaxed) >= self.queue_size {
+			slot.waiting.fetch_sub(1, Ordering::Relaxed);
+			return None;
+		}
+		let _queued = Queued(&slot.waiting);
+		tokio::select! {
+			acquired = tokio::time::timeout(self.queue_timeout, Arc::clone(&slot.permits).acquire_owned()) => {
+				acquired.ok().and_then(Result::ok)
+			}
+			() = ctx.cancel.cancelled() => None,
+		}
+	}
+}
+
+#[async_trait]
+impl L7RequestMiddleware for ConcurrencyLimitMiddleware {
+	async fn run(
+		&self,
+		req: &mut Request,
+		conn: &Arc<ConnContext>,
+		ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		let key = self.key.bucket_key(req, conn);
+		// Read shard on the hot path, as in `rate_limit`; only a key
+		// with nothing in flight takes the write shard.
+		let existing = self.slots.get(&key).map(|s| Arc::clone(&s));
+		let slot = existing.unwrap_or_else(|| {
+			let fresh = || {
+				Arc::new(Slot { permits: Arc::new(Semaphore::new(self.max)), waiting: AtomicUsize::new(0) })
+			};
+			Arc::clone(&self.slots.entry(key.clone()).or_insert_with(fresh))
+		});
+
+		if let Some(permit) = self.acquire(&slot, ctx).await {
+			StreamGuards::push(
+				req,
+				Permit { held: Some(permit), key, slot, slots: Arc::clone(&self.slots) },
+			);
+			return Ok(Decision::Continue);
+		}
+		retire(&self.slots, &key, &slot);
+
+		// Same closed-label counter as `rate_limit`; the key value
+		// belongs in the flow log, not in a label.
+		metrics::counter!("vane.security.limit_hit_total", "limit" => "concurrency").increment(1);
+		let body = if self.response_body.is_empty() {
+			Body::Empty
+		} else {
+			Body::Static(self.response_body.clone())
+		};
+		let mut builder = http::Response::builder().status(self.response_status);
+		for (name, value) in &self.response_headers {
+			builder = builder.header(name, value);
+		}
+		let resp = builder
+			.body(body)
+			.map_err(|e| Error::internal(format!("concurrency_limit reject build: {e}")))?;
+		Ok(Decision::Short(ShortCircuit::Response(resp)))
+	}
+}
+
+/// Args parser exposed as a registry-friendly factory.
+///
+/// Args shape:
+///
+/// ```json
+/// {
+///   "key":         <same forms as rate_limit.key>,
+///   "ipv4_prefix": <0..=32>,
+///   "ipv6_prefix": <0..=128>,
+///   "max":         <in-flight requests per key, ≥ 1>,
+///   "queue":       { "size": <waiters per key, ≥ 1>, "timeout": "5s" },
+///   "on_limit":    { "status": 503, "headers": { ... }, "body": "<base64>" }
+/// }
+/// ```
+///
+/// Defaults: `key = "remote_ip"` (with `rate_limit`'s prefixes), no
+/// queue, `queue.timeout = "5s"`, `on_limit = { status: 503, headers:
+/// {}, body: "" }`. `max` is required.
+///
+/// # Errors
+/// Returns [`FactoryError`] for a missing or zero `max`, any `key` /
+/// prefix error `rate_limit` reports, a `queue` without a positive
+/// `size`, a malformed `queue.timeout`, or a malformed `on_limit`.
+pub fn factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	let max = args
+		.get("max")
+		.and_then(serde_json::Value::as_u64)
+		.and_then(|n| usize::try_from(n).ok())
+		.filter(|n| (1..=Semaphore::MAX_PERMITS).contains(n))
+		.ok_or_else(|| {
+			FactoryError::Invalid(
+				"args.max must be an integer ≥ 1 (in-flight requests per key)".to_string(),
+			)
+		})?;
+
+	let key = KeyConfig::from_args(args)?;
+
+	let (queue_size, queue_timeout) = match args.get("queue") {
+		None => (0, DEFAULT_QUEUE_TIMEOUT),
+		Some(q) => {
+			let size = q
+				.get("size")
+				.and_then(serde_json::Value::as_u64)
+				.and_then(|n| usize::try_from(n).ok())
+				.filter(|n| *n >= 1)
+				.ok_or_else(|| {
+					FactoryError::Invalid("args.queue.size must be an integer ≥ 1".to_string())
+				})?;
+			let timeout = match q.get("timeout") {
+				None => DEFAULT_QUEUE_TIMEOUT,
+				Some(v) => v
+					.as_str()
+					.ok_or_else(|| FactoryError::Invalid("args.queue.timeout must be a string".to_string()))
+					.and_then(|s| {
+						parse_duration(s).map_err(|e| FactoryError::Invalid(format!("args.queue.timeout: {e}")))
+					})?,
+			};
+			(size, timeout)
+		}
+	};
+
+	let (status, headers, body) = parse_on_limit(args.get("on_limit"), 503)?;
+
+	Ok(MiddlewareInst::L7Request(Arc::new(ConcurrencyLimitMiddleware {
+		slots: Arc::new(DashMap::new()),
+		key,
+		max,
+		queue_size,
+		queue_timeout,
+		response_status: status,
+		response_headers: headers,
+		response_body: body,
+	})))
+}
+
+/// Plug `concurrency_limit` into a `MiddlewareFactories` registry.
+pub fn register(factories: &mut MiddlewareFactories) {
+	factories.register("concurrency_limit", MiddlewareKind::L7Request, factory);
+}
@@ -0,0 +1,418 @@
+//! `request_headers` / `response_headers` — declarative header rewrite
+//! on either side of the fetch.
+//!
+//! Both names share one args shape and one set of operations, applied
+//! in a fixed order: `remove`, then `rename`, then `set`, then `add`.
+//! Values are [`Template`]s, so `${remote.ip}`,
+//! `${tls.peer_cert.subject_cn}`, `${http.header.<name>}` and the other
+//! predicate field paths can be written into a header. Templates read
+//! the request as it reached the middleware, before any of its own
+//! operations. On the response side the request is gone; `http.*`
+//! references read the [`RequestHead`] the executor attaches instead.
+//!
+//! A templated value that renders empty is not written. For `set` the
+//! existing header is still dropped, so a client-supplied
+//! `X-Client-CN` never survives a rule that forwards the mTLS identity
+//! on a connection without a client certificate.
+//!
+//! Always returns `Decision::Continue`.
+//!
+//! See `spec/crates/engine.md` § _Middleware_.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use http::header::{CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING};
+use http::{HeaderMap, HeaderName, HeaderValue};
+use vane_core::template::{RequestRef, Template};
+use vane_core::{
+	ConnContext, Decision, Error, FlowCtx, L7RequestMiddleware, L7ResponseMiddleware, MiddlewareKind,
+	Request, RequestHead, Response,
+};
+
+use crate::factories::{FactoryError, MiddlewareFactories};
+use crate::flow_graph::MiddlewareInst;
+
+/// Message framing belongs to the protocol drivers; rewriting these
+/// would desync the body from its declared length.
+const FRAMING: [HeaderName; 3] = [CONTENT_LENGTH, TRANSFER_ENCODING, CONNECTION];
+
+/// A `set` / `add` value: pre-validated when it has no references.
+enum Value {
+	Static(HeaderValue),
+	Template(Template),
+}
+
+/// The parsed operations, shared by both phases.
+struct HeaderOps {
+	remove: Vec<HeaderName>,
+	rename: Vec<(HeaderName, HeaderName)>,
+	set: Vec<(HeaderName, Value)>,
+	add: Vec<(HeaderName, Value)>,
+}
+
+impl HeaderOps {
+	/// Render every value up front; `None` means "write nothing".
+	fn render(
+		values: &[(HeaderName, Value)],
+		conn: &ConnContext,
+		req: Option<RequestRef<'_>>,
+	) -> Vec<Option<HeaderValue>> {
+		values
+			.iter()
+			.map(|(name, value)| match value {
+				Value::Static(v) => Some(v.clone()),
+				Value::Template(t) => {
+					let s = t.render(conn, req);
+					if s.is_empty() {
+						return None;
+					}
+					let v = HeaderValue::from_str(&s).ok();
+					if v.is_none() {
+						tracing::debug!(header = %name, "templated value is not a valid header value; skipped");
+					}
+					v
+				}
+			})
+			.collect()
+	}
+
+	fn apply(&self, conn: &ConnContext, req: Option<RequestRef<'_>>, headers: &mut HeaderMap) {
+		let set = Self::render(&self.set, conn, req);
+		let add = Self::render(&self.add, conn, req);
+		self.apply_rendered(headers, set, add);
+	}
+
+	fn apply_rendered(
+		&self,
+		headers: &mut HeaderMap,
+		set: Vec<Option<HeaderValue>>,
+		add: Vec<Option<HeaderValue>>,
+	) {
+		for name in &self.remove {
+			headers.remove(name);
+		}
+		for (from, to) in &self.rename {
+			if !headers.contains_key(from) {
+				continue;
+			}
+			let values: Vec<HeaderValue> = headers.get_all(from).iter().cloned().collect();
+			headers.remove(from);
+			headers.remove(to);
+			for v in values {
+				headers.append(to, v);
+			}
+		}
+		for ((name, _), value) in self.set.iter().zip(set) {
+			match value {
+				Some(v) => {
+					headers.insert(name, v);
+				}
+				None => {
+					headers.remove(name);
+				}
+			}
+		}
+		for ((name, _), value) in self.add.iter().zip(add) {
+			if let Some(v) = value {
+				headers.append(name, v);
+			}
+		}
+	}
+}
+
+pub struct RequestHeaders(HeaderOps);
+
+#[async_trait]
+impl L7RequestMiddleware for RequestHeaders {
+	async fn run(
+		&self,
+		req: &mut Request,
+		conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		// Render against the untouched request, then mutate it.
+		let set = HeaderOps::render(&self.0.set, conn, Some(RequestRef::from(&*req)));
+		let add = HeaderOps::render(&self.0.add, conn, Some(RequestRef::from(&*req)));
+		self.0.apply_rendered(req.headers_mut(), set, add);
+		Ok(Decision::Continue)
+	}
+}
+
+pub struct ResponseHeaders(HeaderOps);
+
+#[async_trait]
+impl L7ResponseMiddleware for ResponseHeaders {
+	async fn run(
+		&self,
+		resp: &mut Response,
+		conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		let head = resp.extensions_mut().remove::<RequestHead>();
+		self.0.apply(conn, head.as_ref().map(RequestRef::from), resp.headers_mut());
+		if let Some(head) = head {
+			resp.extensions_mut().insert(head);
+		}
+		Ok(Decision::Continue)
+	}
+}
+
+/// Factory for `request_headers`.
+///
+/// Args shape (at least one operation):
+///
+/// ```json
+/// {
+///   "remove": ["x-powered-by"],
+///   "rename": { "x-old-name": "x-new-name" },
+///   "set": { "x-client-cn": "${tls.peer_cert.subject_cn}" },
+///   "add": { "via": "1.1 vane" }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns [`FactoryError`] on unknown keys, an empty operation set,
+/// an invalid header name or static value, a framing header
+/// (`content-length`, `transfer-encoding`, `connection`), or a template
+/// that fails to parse.
+pub fn request_factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	Ok(MiddlewareInst::L7Request(Arc::new(RequestHeaders(parse(args)?))))
+}
+
+/// Factory for `response_headers`; same args as [`request_factory`].
+///
+/// # Errors
+/// As [`request_factory`].
+pub fn response_factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	Ok(MiddlewareInst::L7Response(Arc::new(ResponseHeaders(parse(args)?))))
+}
+
+fn parse(args: &serde_json::Value) -> Result<HeaderOps, FactoryError> {
+	let obj =
+		args.as_object().ok_or_else(|| FactoryError::Invalid("args: expected an object".to_owned()))?;
+	if let Some(k) = obj.keys().find(|k| !matches!(k.as_str(), "remove" | "rename" | "set" | "add")) {
+		return Err(FactoryError::Invalid(format!(
+			"args.{k}: unknown field; expected remove / rename / set / add"
+		)));
+	}
+	let remove = match obj.get("remove") {
+		None => Vec::new(),
+		Some(v) => {
+			let arr = v
+				.as_array()
+				.ok_or_else(|| FactoryError::Invalid("args.remove: expected an array".to_owned()))?;
+			arr
+				.iter()
+				.map(|e| {
+					let s = e.as_str().ok_or_else(|| {
+						FactoryError::Invalid("args.remove: entries must be strings".to_owned())
+					})?;
+					header_name("remove", s)
+				})
+				.collect::<Result<_, _>>()?
+		}
+	};
+	let rename = object(obj.get("rename"), "rename")?
+		.into_iter()
+		.map(|(from, to)| {
+			let to = to.as_str().ok_or_else(|| {
+				FactoryError::Invalid(format!("args.rename.{from}: expected a header name"))
+			})?;
+			Ok((header_name("rename", from)?, header_name("rename", to)?))
+		})
+		.collect::<Result<Vec<_>, FactoryError>>()?;
+	let set = values(obj.get("set"), "set")?;
+	let add = values(obj.get("add"), "add")?;
+	if remove.is_empty() && rename.is_empty() && set.is_empty() && add.is_empty() {
+		return Err(FactoryError::Invalid(
+			"args: at least one of remove / rename / set / add is required".to_owned(),
+		));
+	}
+	Ok(HeaderOps { remove, rename, set, add })
+}
+
+fn object<'a>(
+	v: Option<&'a serde_json::Value>,
+	field: &str,
+) -> Result<Vec<(&'a String, &'a serde_json::Value)>, FactoryError> {
+	match v {
+		None => Ok(Vec::new()),
+		Some(v) => v
+			.as_object()
+			.map(|m| m.iter().collect())
+			.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: expected an object"))),
+	}
+}
+
+fn values(
+	v: Option<&serde_json::Value>,
+	field: &str,
+) -> Result<Vec<(HeaderName, Value)>, FactoryError> {
+	object(v, field)?
+		.into_iter()
+		.map(|(name, value)| {
+			let src = value
+				.as_str()
+				.ok_or_else(|| FactoryError::Invalid(format!("args.{field}.{name}: expected a string")))?;
+			let template = Template::parse(src)
+				.map_err(|e| FactoryError::Invalid(format!("args.{field}.{name}: {e}")))?;
+			let value = match template.as_literal() {
+				Some(s) => Value::Static(HeaderValue::from_str(s).map_err(|_| {
+					FactoryError::Invalid(format!("args.{field}.{name}: invalid header value"))
+				})?),
+				None => Value::Template(template),
+			};
+			Ok((header_name(field, name)?, value))
+		})
+		.collect()
+}
+
+fn header_name(field: &str, s: &str) -> Result<HeaderName, FactoryError> {
+	let name = HeaderName::from_bytes(s.as_bytes())
+		.map_err(|_| FactoryError::Invalid(format!("args.{field}: invalid header name {s:?}")))?;
+	if FRAMING.contains(&name) {
+		return Err(FactoryError::Invalid(format!(
+			"args.{field}: {s:?} is a framing header and cannot be rewritten"
+		)));
+	}
+	Ok(name)
+}
+
+/// Plug `request_headers` and `response_headers` into a
+/// `MiddlewareFactories` registry.
+pub fn register(factories: &mut MiddlewareFactories) {
+	factories.register("request_headers", MiddlewareKind::L7Request, request_factory);
+	factories.register("response_headers", MiddlewareKind::L7Response, response_factory);
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::SocketAddr;
+	use std::time::Instant;
+
+	use tokio_util::sync::CancellationToken;
+	use vane_core::{
+		Body, ConnId, FlowLogEvent, FlowLogSink, FlowLogVerbosity, NodeId, TrajectoryBuilder, Transport,
+	};
+
+	use super::*;
+
+	struct NullSink;
+	impl FlowLogSink for NullSink {
+		fn emit(&self, _event: FlowLogEvent) {}
+	}
+
+	fn conn() -> Arc<ConnContext> {
+		let remote: SocketAddr = "198.51.100.4:40000".parse().expect("addr");
+		let local: SocketAddr = "127.0.0.1:443".parse().expect("addr");
+		Arc::new(ConnContext::new(ConnId(0), remote, local, Transport::Tcp, Instant::now()))
+	}
+
+	fn ctx(conn: &ConnContext) -> FlowCtx {
+		FlowCtx {
+			span: tracing::Span::none(),
+			log: Arc::new(NullSink),
+			cancel: CancellationToken::new(),
+			accept_cancel: CancellationToken::new(),
+			verbosity: FlowLogVerbosity::Trajectory,
+			trajectory: TrajectoryBuilder::new(conn.id, NodeId::for_testing(0), 0),
+		}
+	}
+
+	fn values<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+		headers.get_all(name).iter().map(|v| v.to_str().expect("ascii")).collect()
+	}
+
+	#[tokio::test]
+	async fn request_ops_apply_in_order_and_render_templates() {
+		let MiddlewareInst::L7Request(m) = request_factory(&serde_json::json!({
+			"remove": ["x-debug"],
+			"rename": { "x-old": "x-new" },
+			"set": {
+				"x-real-ip": "${remote.ip}",
+				"x-client-cn": "${tls.peer_cert.subject_cn}",
+				"x-path": "${http.uri.path} via ${http.header.x-old}",
+			},
+			"add": { "via": "1.1 vane" },
+		}))
+		.expect("factory") else {
+			unreachable!("request_headers is L7Request")
+		};
+		let mut req = http::Request::builder()
+			.uri("/orders/7")
+			.header("x-debug", "1")
+			.header("x-old", "a")
+			.header("x-old", "b")
+			.header("x-new", "stale")
+			.header("x-client-cn", "forged")
+			.header("via", "1.1 edge")
+			.body(Body::Empty)
+			.expect("request");
+		let conn = conn();
+		let d = m.run(&mut req, &conn, &mut ctx(&conn)).await.expect("run");
+		assert!(matches!(d, Decision::Continue));
+
+		let h = req.headers();
+		assert!(!h.contains_key("x-debug"));
+		assert!(!h.contains_key("x-old"));
+		assert_eq!(values(h, "x-new"), ["a", "b"]);
+		assert_eq!(values(h, "x-real-ip"), ["198.51.100.4"]);
+		// No client certificate: the forged header is dropped, not kept.
+		assert!(!h.contains_key("x-client-cn"));
+		assert_eq!(values(h, "x-path"), ["/orders/7 via a"]);
+		assert_eq!(values(h, "via"), ["1.1 edge", "1.1 vane"]);
+	}
+
+	#[tokio::test]
+	async fn response_ops_read_the_request_head() {
+		let MiddlewareInst::L7Response(m) = response_factory(&serde_json::json!({
+			"remove": ["server", "x-powered-by"],
+			"set": {
+				"strict-transport-security": "max-age=63072000; includeSubDomains",
+				"x-request-id": "${http.header.x-request-id}",
+			},
+		}))
+		.expect("factory") else {
+			unreachable!("response_headers is L7Response")
+		};
+		let req = http::Request::builder()
+			.uri("/")
+			.header("x-request-id", "r-42")
+			.body(Body::Empty)
+			.expect("request");
+		let mut resp = http::Response::builder()
+			.header("server", "nginx")
+			.header("x-powered-by", "php")
+			.body(Body::Empty)
+			.expect("response");
+		resp.extensions_mut().insert(RequestHead::of(&req));
+		let conn = conn();
+		m.run(&mut resp, &conn, &mut ctx(&conn)).await.expect("run");
+
+		let h = resp.headers();
+		assert!(!h.contains_key("server") && !h.contains_key("x-powered-by"));
+		assert_eq!(values(h, "strict-transport-security"), ["max-age=63072000; includeSubDomains"]);
+		assert_eq!(values(h, "x-request-id"), ["r-42"]);
+		assert!(resp.extensions().get::<RequestHead>().is_some(), "head stays for later middleware");
+	}
+
+	#[test]
+	fn factory_rejects_bad_args() {
+		for (args, want) in [
+			(serde_json::json!({}), "at least one"),
+			(serde_json::json!({ "append": {} }), "args.append: unknown field"),
+			(serde_json::json!({ "remove": "server" }), "args.remove: expected an array"),
+			(serde_json::json!({ "remove": ["bad name"] }), "invalid header name"),
+			(serde_json::json!({ "set": { "content-length": "0" } }), "framing header"),
+			(serde_json::json!({ "rename": { "x-a": "transfer-encoding" } }), "framing header"),
+			(serde_json::json!({ "set": { "x-a": 1 } }), "args.set.x-a: expected a string"),
+			(serde_json::json!({ "add": { "x-a": "bad\nvalue" } }), "invalid header value"),
+			(serde_json::json!({ "set": { "x-a": "${http.body}" } }), "cannot be used in a template"),
+		] {
+			let Err(err) = request_factory(&args) else { panic!("{args} must be rejected") };
+			let msg = err.message();
+			assert!(msg.contains(want), "{args}: {msg}");
+		}
+	}
+}
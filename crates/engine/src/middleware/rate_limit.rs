@@ -1,11 +1,14 @@
 //! `rate_limit` — token-bucket L7 stateful middleware.
 //!
-//! Per-IP or global key, configurable rate / burst / window. On
-//! exhaustion, returns `Decision::Short(ShortCircuit::Response(_))`
-//! with a configurable `on_limit` payload (default 429). The wire-level
-//! 429 only appears once the executor's
-//! `meta.short_circuit_response_entry` routing is in place; until that
-//! lands the middleware itself is correct but the daemon emits 500.
+//! Keyed per remote IP (IPv6 aggregated to a prefix), per request
+//! header / cookie / query parameter, per composite of those, or
+//! globally; configurable rate / burst / window. On exhaustion, returns
+//! `Decision::Short(ShortCircuit::Response(_))` with a configurable
+//! `on_limit` payload (default 429 carrying `Retry-After` and
+//! `RateLimit-*` headers). The wire-level 429 only appears once the
+//! executor's `meta.short_circuit_response_entry` routing is in place;
+//! until that lands the middleware itself is correct but the daemon
+//! emits 500.
 //!
 //! See `spec/crates/core.md` § _Rate limit (L2)_, and
 //! `spec/crates/engine.md` § _Middleware_ —
@@ -32,24 +35,120 @@ use vane_core::{
 
 use crate::factories::{FactoryError, MiddlewareFactories};
 use crate::flow_graph::MiddlewareInst;
+use crate::security::{ConnFloor, Offense, OffenseRecorded};
 
 /// `spec/crates/core.md` § _Rate limit (L2)_ constrains the window range to `[1s, 60s]`.
 const MIN_WINDOW_SECS: u64 = 1;
 const MAX_WINDOW_SECS: u64 = 60;
 
-/// Subset of spec § _Rate limit (L2)_ that this round implements.
-/// Header / Cookie / Query / Composite are post-MVP — they need a
-/// request-field reader helper that doesn't exist yet.
-#[derive(Debug, Clone, Copy)]
-enum KeyDerivation {
+/// Default `max_keys`: distinct buckets one call site keeps before
+/// new keys fall into the shared overflow bucket.
+const DEFAULT_MAX_KEYS: usize = 100_000;
+
+/// Default `remote_ip` aggregation. A single IPv6 host usually owns a
+/// whole /64, so keying per address would let it rotate freely.
+const DEFAULT_IPV4_PREFIX: u8 = 32;
+const DEFAULT_IPV6_PREFIX: u8 = 64;
+
+/// Spec § _Rate limit (L2)_ key derivations. `Composite` holds only
+/// per-request parts — never `Global` or another `Composite`.
+#[derive(Debug, Clone)]
+pub(crate) enum KeyDerivation {
 	RemoteIp,
+	Header(HeaderName),
+	Cookie(Box<str>),
+	Query(Box<str>),
+	Composite(Box<[KeyDerivation]>),
 	Global,
 }
 
+impl KeyDerivation {
+	/// Closed `limit` label set for `vane.security.limit_hit_total`.
+	const fn limit_label(&self) -> &'static str {
+		match self {
+			Self::RemoteIp => "per_ip",
+			Self::Header(_) => "per_header",
+			Self::Cookie(_) => "per_cookie",
+			Self::Query(_) => "per_query",
+			Self::Composite(_) => "per_composite",
+			Self::Global => "global",
+		}
+	}
+}
+
+/// The `key` / `ipv4_prefix` / `ipv6_prefix` args. Shared with
+/// `concurrency_limit`, which keys its permits the same way.
+pub(crate) struct KeyConfig {
+	pub(crate) derivation: KeyDerivation,
+	ipv4_prefix: u8,
+	ipv6_prefix: u8,
+}
+
+impl KeyConfig {
+	/// # Errors
+	/// Unsupported or malformed `key`, or a prefix out of range.
+	pub(crate) fn from_args(args: &serde_json::Value) -> Result<Self, FactoryError> {
+		let derivation = match args.get("key") {
+			None => KeyDerivation::RemoteIp,
+			Some(v) => parse_key(v, true)?,
+		};
+		Ok(Self {
+			derivation,
+			ipv4_prefix: parse_prefix(args, "ipv4_prefix", 32, DEFAULT_IPV4_PREFIX)?,
+			ipv6_prefix: parse_prefix(args, "ipv6_prefix", 128, DEFAULT_IPV6_PREFIX)?,
+		})
+	}
+
+	pub(crate) fn bucket_key(&self, req: &Request, conn: &ConnContext) -> BucketKey {
+		match &self.derivation {
+			KeyDerivation::Global => BucketKey::Global,
+			KeyDerivation::Composite(parts) => {
+				BucketKey::Composite(parts.iter().map(|p| self.key_part(p, req, conn)).collect())
+			}
+			single => BucketKey::One(self.key_part(single, req, conn)),
+		}
+	}
+
+	/// A request missing the named header / cookie / parameter is keyed
+	/// by its remote IP prefix instead, so omitting the key neither
+	/// bypasses the limit nor lands every such client in one bucket.
+	fn key_part(&self, derivation: &KeyDerivation, req: &Request, conn: &ConnContext) -> KeyPart {
+		let value = match derivation {
+			KeyDerivation::Header(name) => {
+				req.headers().get(name).map(|v| Bytes::copy_from_slice(v.as_bytes()))
+			}
+			KeyDerivation::Cookie(name) => {
+				vane_core::cookie_value(req.headers(), name).map(|v| Bytes::copy_from_slice(v.as_bytes()))
+			}
+			KeyDerivation::Query(name) => req
+				.uri()
+				.query()
+				.and_then(|q| vane_core::query_value(q, name))
+				.map(|v| Bytes::copy_from_slice(v.as_bytes())),
+			KeyDerivation::RemoteIp | KeyDerivation::Composite(_) | KeyDerivation::Global => None,
+		};
+		value.map_or_else(
+			|| KeyPart::Ip(mask_ip(conn.remote.ip(), self.ipv4_prefix, self.ipv6_prefix)),
+			KeyPart::Value,
+		)
+	}
+}
+
 #[derive(Hash, Eq, PartialEq, Clone)]
-enum BucketKey {
-	RemoteIp(IpAddr),
+pub(crate) enum KeyPart {
+	/// Remote address masked to the configured prefix.
+	Ip(IpAddr),
+	Value(Bytes),
+}
+
+#[derive(Hash, Eq, PartialEq, Clone)]
+pub(crate) enum BucketKey {
+	One(KeyPart),
+	Composite(Box<[KeyPart]>),
 	Global,
+	/// Shared by every new key once the map is at `max_keys` and no
+	/// idle bucket could be evicted.
+	Overflow,
 }
 
 struct TokenBucket {
@@ -66,8 +165,9 @@ impl TokenBucket {
 		Self { state: Mutex::new(TokenState { tokens: f64::from(burst), last_refill: Instant::now() }) }
 	}
 
-	/// Returns `true` if a token was consumed (request allowed).
-	fn try_consume(&self, rate_per_sec: f64, capacity: u32) -> bool {
+	/// Consume one token. On exhaustion returns the tokens left (< 1)
+	/// so the caller can tell the client when the next one is due.
+	fn try_consume(&self, rate_per_sec: f64, capacity: u32) -> Result<(), f64> {
 		let mut s = self.state.lock();
 		let now = Instant::now();
 		let elapsed = now.duration_since(s.last_refill).as_secs_f64();
@@ -75,35 +175,108 @@ impl TokenBucket {
 		s.last_refill = now;
 		if s.tokens >= 1.0 {
 			s.tokens -= 1.0;
-			true
+			Ok(())
 		} else {
-			false
+			Err(s.tokens)
 		}
 	}
+
+	/// A bucket that has refilled to capacity is indistinguishable
+	/// from a fresh one, so evicting it loses no state.
+	fn is_full(&self, now: Instant, rate_per_sec: f64, capacity: u32) -> bool {
+		let s = self.state.lock();
+		let elapsed = now.saturating_duration_since(s.last_refill).as_secs_f64();
+		s.tokens + elapsed * rate_per_sec >= f64::from(capacity)
+	}
 }
 
 pub struct RateLimitMiddleware {
 	buckets: DashMap<BucketKey, TokenBucket>,
+	rate: u32,
+	window: Duration,
 	rate_per_sec: f64,
 	capacity: u32,
-	key_derivation: KeyDerivation,
+	key: KeyConfig,
+	max_keys: usize,
+	/// Last idle-bucket sweep; sweeps run at most once per `window`
+	/// so a flood of unique keys cannot turn every miss into an O(n)
+	/// scan.
+	last_sweep: Mutex<Instant>,
 	response_status: u16,
 	response_headers: Vec<(HeaderName, HeaderValue)>,
 	response_body: Bytes,
+	rate_limit_headers: bool,
+}
+
+impl RateLimitMiddleware {
+	/// Insert path for a key with no bucket yet. At `max_keys`, first
+	/// evict buckets that have refilled to capacity; if the map is
+	/// still full, the request shares the overflow bucket rather than
+	/// growing the map or resetting some other client's bucket.
+	fn admit_key(&self, key: BucketKey) -> BucketKey {
+		if self.buckets.len() < self.max_keys {
+			return key;
+		}
+		if let Some(mut last) = self.last_sweep.try_lock() {
+			let now = Instant::now();
+			if now.duration_since(*last) >= self.window {
+				*last = now;
+				self.buckets.retain(|k, b| {
+					matches!(k, BucketKey::Overflow) || !b.is_full(now, self.rate_per_sec, self.capacity)
+				});
+			}
+		}
+		if self.buckets.len() < self.max_keys { key } else { BucketKey::Overflow }
+	}
+
+	fn reject(&self, tokens_left: f64) -> Result<Response, Error> {
+		let mut builder = http::Response::builder().status(self.response_status);
+		for (name, value) in &self.response_headers {
+			builder = builder.header(name, value);
+		}
+		if self.rate_limit_headers {
+			// Seconds until the next token, rounded up; a zero rate
+			// never refills, so point at the end of the window.
+			let wait = if self.rate_per_sec > 0.0 {
+				((1.0 - tokens_left) / self.rate_per_sec).ceil().max(1.0)
+			} else {
+				self.window.as_secs_f64()
+			};
+			let retry_after = format!("{wait:.0}");
+			let limit = self.rate.to_string();
+			let policy = format!("{};w={};burst={}", self.rate, self.window.as_secs(), self.capacity);
+			// draft-ietf-httpapi-ratelimit-headers fields. Names the
+			// operator already set in `on_limit.headers` win.
+			for (name, value) in [
+				("retry-after", retry_after.as_str()),
+				("ratelimit-limit", limit.as_str()),
+				("ratelimit-remaining", "0"),
+				("ratelimit-reset", retry_after.as_str()),
+				("ratelimit-policy", policy.as_str()),
+			] {
+				if !self.response_headers.iter().any(|(n, _)| n.as_str() == name) {
+					builder = builder.header(name, value);
+				}
+			}
+		}
+		let body = if self.response_body.is_empty() {
+			Body::Empty
+		} else {
+			Body::Static(self.response_body.clone())
+		};
+		builder.body(body).map_err(|e| Error::internal(format!("rate_limit reject build: {e}")))
+	}
 }
 
 #[async_trait]
 impl L7RequestMiddleware for RateLimitMiddleware {
 	async fn run(
 		&self,
-		_req: &mut Request,
+		req: &mut Request,
 		conn: &Arc<ConnContext>,
 		_ctx: &mut FlowCtx,
 	) -> Result<Decision, Error> {
-		let key = match self.key_derivation {
-			KeyDerivation::RemoteIp => BucketKey::RemoteIp(conn.remote.ip()),
-			KeyDerivation::Global => BucketKey::Global,
-		};
+		let key = self.key.bucket_key(req, conn);
 		// Hot path: try the read-only `get` first. `TokenBucket::try_consume`
 		// takes `&self` (it stores its mutable state in atomics), so a
 		// cache hit only takes DashMap's read shard. Only the cold-start
@@ -111,42 +284,50 @@ impl L7RequestMiddleware for RateLimitMiddleware {
 		// which acquires the write shard. The old code unconditionally
 		// took the write shard on every request — at scale every
 		// hot-key request serialized on the same shard's RwLock.
-		let allowed = if let Some(bucket) = self.buckets.get(&key) {
+		let outcome = if let Some(bucket) = self.buckets.get(&key) {
 			bucket.try_consume(self.rate_per_sec, self.capacity)
 		} else {
+			let key = self.admit_key(key);
 			let bucket = self.buckets.entry(key).or_insert_with(|| TokenBucket::new(self.capacity));
 			bucket.try_consume(self.rate_per_sec, self.capacity)
 		};
-		if allowed {
-			Ok(Decision::Continue)
-		} else {
-			let limit_label: &'static str = match self.key_derivation {
-				KeyDerivation::RemoteIp => "per_ip",
-				KeyDerivation::Global => "global",
-			};
-			// Cardinality discipline: the metric carries only the closed
-			// `limit` label set (`per_ip` / `global`). The high-cardinality
-			// `source` IP belongs in the flow log, not in a counter — see
-			// `spec/flow-model.md` § _Flow log verbosity_; emitting one
-			// label value per unique client IP would explode the prometheus
-			// admit table.
-			metrics::counter!(
-				"vane.security.limit_hit_total",
-				"limit" => limit_label,
-			)
-			.increment(1);
-			let body = if self.response_body.is_empty() {
-				Body::Empty
-			} else {
-				Body::Static(self.response_body.clone())
-			};
-			let mut builder = http::Response::builder().status(self.response_status);
-			for (name, value) in &self.response_headers {
-				builder = builder.header(name, value);
+		match outcome {
+			Ok(()) => Ok(Decision::Continue),
+			Err(tokens_left) => {
+				// Cardinality discipline: the metric carries only the closed
+				// `limit` label set (see `KeyDerivation::limit_label`). The
+				// high-cardinality `source` IP or key value belongs in the
+				// flow log, not in a counter — see
+				// `spec/flow-model.md` § _Flow log verbosity_; emitting one
+				// label value per unique client would explode the prometheus
+				// admit table.
+				metrics::counter!(
+					"vane.security.limit_hit_total",
+					"limit" => self.key.derivation.limit_label(),
+				)
+				.increment(1);
+				if let Some(floor) = ConnFloor::of(conn) {
+					floor.offend(Offense::RateLimited);
+				}
+				let mut response = self.reject(tokens_left)?;
+				response.extensions_mut().insert(OffenseRecorded);
+				Ok(Decision::Short(ShortCircuit::Response(response)))
 			}
-			let resp: Response =
-				builder.body(body).map_err(|e| Error::internal(format!("rate_limit reject build: {e}")))?;
-			Ok(Decision::Short(ShortCircuit::Response(resp)))
+		}
+	}
+}
+
+/// Zero every bit of `ip` past the family's prefix length. IPv4-mapped
+/// IPv6 peers (dual-stack sockets) are treated as IPv4.
+fn mask_ip(ip: IpAddr, ipv4_prefix: u8, ipv6_prefix: u8) -> IpAddr {
+	match ip.to_canonical() {
+		IpAddr::V4(v4) => {
+			let mask = u32::MAX.checked_shl(32 - u32::from(ipv4_prefix)).unwrap_or(0);
+			IpAddr::V4((u32::from(v4) & mask).into())
+		}
+		IpAddr::V6(v6) => {
+			let mask = u128::MAX.checked_shl(128 - u32::from(ipv6_prefix)).unwrap_or(0);
+			IpAddr::V6((u128::from(v6) & mask).into())
 		}
 	}
 }
@@ -157,23 +338,33 @@ impl L7RequestMiddleware for RateLimitMiddleware {
 ///
 /// ```json
 /// {
-///   "key":      "remote_ip" | "global",
-///   "rate":     <u32 tokens per window>,
-///   "burst":    <u32 bucket capacity, ≥ 1>,
-///   "window":   "<N>s"  (1..=60),
-///   "on_limit": { "status": 429, "headers": { ... }, "body": "<base64>" }
+///   "key":         "remote_ip" | "global" | { "header": "<name>" }
+///                  | { "cookie": "<name>" } | { "query": "<name>" }
+///                  | { "composite": [<remote_ip / header / cookie / query>, ...] },
+///   "rate":        <u32 tokens per window>,
+///   "burst":       <u32 bucket capacity, ≥ 1>,
+///   "window":      "<N>s"  (1..=60),
+///   "ipv4_prefix": <0..=32>,
+///   "ipv6_prefix": <0..=128>,
+///   "max_keys":    <distinct buckets, ≥ 1>,
+///   "on_limit":    { "status": 429, "headers": { ... }, "body": "<base64>",
+///                    "rate_limit_headers": true }
 /// }
 /// ```
 ///
-/// Defaults: `key = "remote_ip"`, `on_limit = { status: 429, headers:
-/// {}, body: "" }`. `rate`, `burst`, `window` are required.
+/// Defaults: `key = "remote_ip"`, `ipv4_prefix = 32`, `ipv6_prefix =
+/// 64`, `max_keys = 100000`, `on_limit = { status: 429, headers: {},
+/// body: "", rate_limit_headers: true }`. `rate`, `burst`, `window`
+/// are required. The prefixes apply wherever a key falls back to the
+/// remote IP.
 ///
 /// # Errors
 /// Returns [`FactoryError`] for any of: missing `rate` / `burst` /
-/// `window`, `burst == 0`, malformed `window`, unsupported `key`,
-/// invalid `on_limit.status` (out of u16 or HTTP range), unrecognised
-/// header name, non-string header value, or malformed `on_limit.body`
-/// base64.
+/// `window`, `burst == 0`, malformed `window`, unsupported or malformed
+/// `key` (including an empty or nested `composite`), prefix out of
+/// range, `max_keys == 0`, invalid `on_limit.status` (out of u16 or
+/// HTTP range), unrecognised header name, non-string header value, or
+/// malformed `on_limit.body` base64.
 pub fn factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
 	let rate = args.get("rate").and_then(serde_json::Value::as_u64).ok_or_else(|| {
 		FactoryError::Invalid("missing args.rate (u32 tokens-per-window)".to_string())
@@ -196,34 +387,97 @@ pub fn factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError>
 	})?;
 	let window = parse_window(window_str)?;
 
-	let key_str = args.get("key").and_then(serde_json::Value::as_str).unwrap_or("remote_ip");
-	let key_derivation = match key_str {
-		"remote_ip" => KeyDerivation::RemoteIp,
-		"global" => KeyDerivation::Global,
-		other => {
-			return Err(FactoryError::Invalid(format!(
-				"unsupported args.key {other:?}; supported: remote_ip / global \
-				 (header / cookie / query / composite are post-MVP per \
-				 spec/crates/core.md § _Rate limit (L2)_)"
-			)));
-		}
+	let key = KeyConfig::from_args(args)?;
+
+	let max_keys = match args.get("max_keys") {
+		None => DEFAULT_MAX_KEYS,
+		Some(v) => v
+			.as_u64()
+			.and_then(|n| usize::try_from(n).ok())
+			.filter(|n| *n >= 1)
+			.ok_or_else(|| FactoryError::Invalid("args.max_keys must be an integer ≥ 1".to_string()))?,
 	};
 
-	let (status, headers, body) = parse_on_limit(args.get("on_limit"))?;
+	let (status, headers, body) = parse_on_limit(args.get("on_limit"), 429)?;
+	let rate_limit_headers = parse_rate_limit_headers(args.get("on_limit"))?;
 
 	let rate_per_sec = f64::from(rate) / window.as_secs_f64();
 
 	Ok(MiddlewareInst::L7Request(Arc::new(RateLimitMiddleware {
 		buckets: DashMap::new(),
+		rate,
+		window,
 		rate_per_sec,
 		capacity: burst,
-		key_derivation,
+		key,
+		max_keys,
+		last_sweep: Mutex::new(Instant::now()),
 		response_status: status,
 		response_headers: headers,
 		response_body: body,
+		rate_limit_headers,
 	})))
 }
 
+/// One `args.key` entry. `top_level` is false inside `composite`,
+/// where `global` and nested composites are meaningless.
+fn parse_key(v: &serde_json::Value, top_level: bool) -> Result<KeyDerivation, FactoryError> {
+	const SUPPORTED: &str = "supported: \"remote_ip\" / \"global\" / { \"header\" } / \
+	                         { \"cookie\" } / { \"query\" } / { \"composite\" }";
+	if let Some(s) = v.as_str() {
+		return match s {
+			"remote_ip" => Ok(KeyDerivation::RemoteIp),
+			"global" if top_level => Ok(KeyDerivation::Global),
+			other => Err(FactoryError::Invalid(format!("unsupported args.key {other:?}; {SUPPORTED}"))),
+		};
+	}
+	let Some((kind, arg)) = v.as_object().filter(|o| o.len() == 1).and_then(|o| o.iter().next())
+	else {
+		return Err(FactoryError::Invalid(format!(
+			"args.key must be a string or a single-entry object; {SUPPORTED}"
+		)));
+	};
+	let name = || {
+		arg
+			.as_str()
+			.filter(|n| !n.is_empty())
+			.ok_or_else(|| FactoryError::Invalid(format!("args.key.{kind} must be a non-empty string")))
+	};
+	match kind.as_str() {
+		"header" => {
+			let raw = name()?;
+			HeaderName::try_from(raw)
+				.map(KeyDerivation::Header)
+				.map_err(|e| FactoryError::Invalid(format!("args.key.header {raw:?}: {e}")))
+		}
+		"cookie" => Ok(KeyDerivation::Cookie(name()?.into())),
+		"query" => Ok(KeyDerivation::Query(name()?.into())),
+		"composite" if top_level => {
+			let parts = arg.as_array().filter(|a| !a.is_empty()).ok_or_else(|| {
+				FactoryError::Invalid("args.key.composite must be a non-empty array".to_string())
+			})?;
+			let parts = parts.iter().map(|p| parse_key(p, false)).collect::<Result<Box<[_]>, _>>()?;
+			Ok(KeyDerivation::Composite(parts))
+		}
+		other => Err(FactoryError::Invalid(format!("unsupported args.key {other:?}; {SUPPORTED}"))),
+	}
+}
+
+fn parse_prefix(
+	args: &serde_json::Value,
+	field: &str,
+	max: u8,
+	default: u8,
+) -> Result<u8, FactoryError> {
+	let Some(v) = args.get(field) else {
+		return Ok(default);
+	};
+	v.as_u64()
+		.and_then(|n| u8::try_from(n).ok())
+		.filter(|n| *n <= max)
+		.ok_or_else(|| FactoryError::Invalid(format!("args.{field} must be an integer in 0..={max}")))
+}
+
 fn parse_window(s: &str) -> Result<Duration, FactoryError> {
 	let trimmed = s.strip_suffix('s').ok_or_else(|| {
 		FactoryError::Invalid(format!("args.window {s:?}: must end with 's' (e.g. \"1s\")"))
@@ -238,13 +492,19 @@ fn parse_window(s: &str) -> Result<Duration, FactoryError> {
 	Ok(Duration::from_secs(n))
 }
 
-type OnLimit = (u16, Vec<(HeaderName, HeaderValue)>, Bytes);
+pub(crate) type OnLimit = (u16, Vec<(HeaderName, HeaderValue)>, Bytes);
 
-fn parse_on_limit(v: Option<&serde_json::Value>) -> Result<OnLimit, FactoryError> {
+/// `on_limit.{status, headers, body}`; shared with `concurrency_limit`,
+/// which defaults to 503 instead of 429.
+pub(crate) fn parse_on_limit(
+	v: Option<&serde_json::Value>,
+	default_status: u16,
+) -> Result<OnLimit, FactoryError> {
 	let Some(obj) = v.and_then(|v| v.as_object()) else {
-		return Ok((429, vec![], Bytes::new()));
+		return Ok((default_status, vec![], Bytes::new()));
 	};
-	let status_raw = obj.get("status").and_then(serde_json::Value::as_u64).unwrap_or(429);
+	let status_raw =
+		obj.get("status").and_then(serde_json::Value::as_u64).unwrap_or(u64::from(default_status));
 	let status = u16::try_from(status_raw)
 		.map_err(|_| FactoryError::Invalid(format!("on_limit.status {status_raw} out of u16 range")))?;
 	if !(100..=599).contains(&status) {
@@ -280,6 +540,15 @@ fn parse_on_limit(v: Option<&serde_json::Value>) -> Result<OnLimit, FactoryError
 	Ok((status, headers, body))
 }
 
+fn parse_rate_limit_headers(v: Option<&serde_json::Value>) -> Result<bool, FactoryError> {
+	match v.and_then(|v| v.get("rate_limit_headers")) {
+		None => Ok(true),
+		Some(v) => v.as_bool().ok_or_else(|| {
+			FactoryError::Invalid("on_limit.rate_limit_headers must be a bool".to_string())
+		}),
+	}
+}
+
 /// Plug `rate_limit` into a `MiddlewareFactories` registry.
 pub fn register(factories: &mut MiddlewareFactories) {
 	factories.register("rate_limit", MiddlewareKind::L7Request, factory);
@@ -0,0 +1,404 @@
+//! `rewrite` / `redirect` — URI rewriting before the fetch and regex
+//! redirects, both L7 request middleware.
+//!
+//! `rewrite` changes the request's path and query in place, in a fixed
+//! order: `strip_prefix`, then `replace`, then `add_prefix`. The prefix
+//! operations work on the path on segment boundaries (`/api` strips
+//! `/api` and `/api/x`, not `/apiary`); `replace` runs one regex
+//! substitution over the path-and-query string, so it can rewrite the
+//! query too, with `$1` / `${name}` expanding capture groups. Scheme and
+//! authority are kept.
+//!
+//! `redirect` matches its optional `pattern` against the same
+//! path-and-query string and, on a match, short-circuits with a
+//! 301 / 302 / 307 / 308 whose `Location` is a [`Template`]: field paths
+//! as in `request_headers`, plus `$N` / `${N}` for the pattern's groups.
+//! No match passes the request on.
+//!
+//! Patterns go through [`compile_bounded_regex`], the same size,
+//! backtrack and smoke-test limits as the predicate `matches` operator.
+//! A runtime backtrack overrun is a miss: the URI is left alone and no
+//! redirect is sent.
+//!
+//! See `spec/crates/engine.md` § _Middleware_.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fancy_regex::{Expander, Regex};
+use http::uri::PathAndQuery;
+use http::{HeaderValue, StatusCode, Uri};
+use vane_core::predicate::compile_bounded_regex;
+use vane_core::template::{RequestRef, Template};
+use vane_core::{
+	Body, ConnContext, Decision, Error, FlowCtx, L7RequestMiddleware, MiddlewareKind, Request,
+	ShortCircuit,
+};
+
+use crate::factories::{FactoryError, MiddlewareFactories};
+use crate::flow_graph::MiddlewareInst;
+
+pub struct Rewrite {
+	strip_prefix: Option<String>,
+	replace: Option<(Regex, String)>,
+	add_prefix: Option<String>,
+}
+
+impl Rewrite {
+	/// The new path-and-query, or `None` when nothing changed.
+	fn target(&self, pq: &str) -> Option<String> {
+		let (path, query) = pq.split_once('?').map_or((pq, None), |(p, q)| (p, Some(q)));
+		let mut path = path.to_owned();
+		if let Some(prefix) = &self.strip_prefix
+			&& let Some(rest) = strip_segment_prefix(&path, prefix)
+		{
+			path = if rest.starts_with('/') { rest.to_owned() } else { format!("/{rest}") };
+		}
+		let mut out = match query {
+			Some(q) => format!("{path}?{q}"),
+			None => path,
+		};
+		if let Some((re, with)) = &self.replace {
+			match re.try_replacen(&out, 1, with.as_str()) {
+				Ok(replaced) => out = replaced.into_owned(),
+				Err(e) => tracing::debug!(error = %e, "rewrite pattern failed at runtime; left as is"),
+			}
+		}
+		if let Some(prefix) = &self.add_prefix {
+			out =
+				if out == "/" { prefix.clone() } else { format!("{}{out}", prefix.trim_end_matches('/')) };
+		}
+		(out != pq).then_some(out)
+	}
+}
+
+/// `path` minus `prefix`, when `prefix` ends on a segment boundary.
+fn strip_segment_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+	let rest = path.strip_prefix(prefix)?;
+	(prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+#[async_trait]
+impl L7RequestMiddleware for Rewrite {
+	async fn run(
+		&self,
+		req: &mut Request,
+		_conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		let pq = req.uri().path_and_query().map_or("/", PathAndQuery::as_str);
+		let Some(target) = self.target(pq) else {
+			return Ok(Decision::Continue);
+		};
+		let pq = PathAndQuery::try_from(target.as_str())
+			.map_err(|e| Error::protocol("rewrite produced an invalid URI").with_source(e))?;
+		let mut parts = req.uri().clone().into_parts();
+		parts.path_and_query = Some(pq);
+		*req.uri_mut() = Uri::from_parts(parts)
+			.map_err(|e| Error::protocol("rewrite produced an invalid URI").with_source(e))?;
+		Ok(Decision::Continue)
+	}
+}
+
+pub struct Redirect {
+	pattern: Option<Regex>,
+	location: Template,
+	status: StatusCode,
+}
+
+#[async_trait]
+impl L7RequestMiddleware for Redirect {
+	async fn run(
+		&self,
+		req: &mut Request,
+		conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<Decision, Error> {
+		let head = RequestRef::from(&*req);
+		let location = match &self.pattern {
+			None => self.location.render(conn, Some(head)),
+			Some(re) => {
+				let pq = req.uri().path_and_query().map_or("/", PathAndQuery::as_str);
+				match re.captures(pq) {
+					Ok(Some(caps)) => self.location.render_with_captures(conn, Some(head), &caps),
+					Ok(None) => return Ok(Decision::Continue),
+					Err(e) => {
+						tracing::debug!(error = %e, "redirect pattern failed at runtime; not redirecting");
+						return Ok(Decision::Continue);
+					}
+				}
+			}
+		};
+		let location = HeaderValue::try_from(location).map_err(|e| {
+			Error::protocol("redirect location is not a valid header value").with_source(e)
+		})?;
+		let resp = http::Response::builder()
+			.status(self.status)
+			.header(http::header::LOCATION, location)
+			.header(http::header::CONTENT_LENGTH, 0)
+			.body(Body::Empty)
+			.map_err(|e| Error::internal(format!("redirect build: {e}")))?;
+		Ok(Decision::Short(ShortCircuit::Response(resp)))
+	}
+}
+
+/// Factory for `rewrite`.
+///
+/// Args shape (at least one operation):
+///
+/// ```json
+/// {
+///   "strip_prefix": "/api",
+///   "replace": { "pattern": "^/users/(\\d+)$", "with": "/v2/users?id=$1" },
+///   "add_prefix": "/internal"
+/// }
+/// ```
+///
+/// # Errors
+/// Returns [`FactoryError`] on unknown keys, an empty operation set, a
+/// prefix that does not start with `/`, a pattern rejected by
+/// [`compile_bounded_regex`], or a `with` that names a group the
+/// pattern does not have.
+pub fn rewrite_factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	let obj = object(args, &["strip_prefix", "replace", "add_prefix"])?;
+	let strip_prefix = prefix(obj.get("strip_prefix"), "strip_prefix")?;
+	let add_prefix = prefix(obj.get("add_prefix"), "add_prefix")?;
+	let replace = match obj.get("replace") {
+		None => None,
+		Some(v) => {
+			let replace = object(v, &["pattern", "with"])
+				.map_err(|e| FactoryError::Invalid(format!("args.replace: {}", e.message())))?;
+			let re = pattern(replace.get("pattern"), "replace.pattern")?;
+			let with = replace
+				.get("with")
+				.and_then(serde_json::Value::as_str)
+				.ok_or_else(|| FactoryError::Invalid("args.replace.with: expected a string".to_owned()))?;
+			Expander::default()
+				.check(with, &re)
+				.map_err(|e| FactoryError::Invalid(format!("args.replace.with: {e}")))?;
+			Some((re, with.to_owned()))
+		}
+	};
+	if strip_prefix.is_none() && replace.is_none() && add_prefix.is_none() {
+		return Err(FactoryError::Invalid(
+			"args: at least one of strip_prefix / replace / add_prefix is required".to_owned(),
+		));
+	}
+	Ok(MiddlewareInst::L7Request(Arc::new(Rewrite { strip_prefix, replace, add_prefix })))
+}
+
+/// Factory for `redirect`.
+///
+/// Args shape:
+///
+/// ```json
+/// {
+///   "pattern": "^/blog/(\\d+)",
+///   "location": "https://blog.example.com/posts/$1",
+///   "status": 301
+/// }
+/// ```
+///
+/// `location` is required; `pattern` defaults to matching every
+/// request; `status` defaults to 302.
+///
+/// # Errors
+/// Returns [`FactoryError`] on unknown keys, a missing `location`, a
+/// status outside 301 / 302 / 307 / 308, a pattern rejected by
+/// [`compile_bounded_regex`], or a `location` template that fails to
+/// parse or names a group the pattern does not have.
+pub fn redirect_factory(args: &serde_json::Value) -> Result<MiddlewareInst, FactoryError> {
+	let obj = object(args, &["pattern", "location", "status"])?;
+	let pattern = match obj.get("pattern") {
+		None => None,
+		Some(v) => Some(pattern(Some(v), "pattern")?),
+	};
+	let src = obj
+		.get("location")
+		.and_then(serde_json::Value::as_str)
+		.ok_or_else(|| FactoryError::Invalid("args.location: required string".to_owned()))?;
+	let location = match &pattern {
+		None => Template::parse(src),
+		Some(re) => Template::parse_with_captures(src, re.captures_len()),
+	}
+	.map_err(|e| FactoryError::Invalid(format!("args.location: {e}")))?;
+	let status = match obj.get("status") {
+		None => StatusCode::FOUND,
+		Some(v) => match v.as_u64() {
+			Some(301) => StatusCode::MOVED_PERMANENTLY,
+			Some(302) => StatusCode::FOUND,
+			Some(307) => StatusCode::TEMPORARY_REDIRECT,
+			Some(308) => StatusCode::PERMANENT_REDIRECT,
+			_ => {
+				return Err(FactoryError::Invalid(format!(
+					"args.status: {v} is not a redirect status; expected 301 / 302 / 307 / 308"
+				)));
+			}
+		},
+	};
+	Ok(MiddlewareInst::L7Request(Arc::new(Redirect { pattern, location, status })))
+}
+
+fn object<'a>(
+	v: &'a serde_json::Value,
+	fields: &[&str],
+) -> Result<&'a serde_json::Map<String, serde_json::Value>, FactoryError> {
+	let obj =
+		v.as_object().ok_or_else(|| FactoryError::Invalid("args: expected an object".to_owned()))?;
+	if let Some(k) = obj.keys().find(|k| !fields.contains(&k.as_str())) {
+		return Err(FactoryError::Invalid(format!(
+			"args.{k}: unknown field; expected {}",
+			fields.join(" / ")
+		)));
+	}
+	Ok(obj)
+}
+
+fn prefix(v: Option<&serde_json::Value>, field: &str) -> Result<Option<String>, FactoryError> {
+	let Some(v) = v else { return Ok(None) };
+	match v.as_str() {
+		Some(s) if s.starts_with('/') => Ok(Some(s.to_owned())),
+		_ => Err(FactoryError::Invalid(format!("args.{field}: expected a path starting with '/'"))),
+	}
+}
+
+fn pattern(v: Option<&serde_json::Value>, field: &str) -> Result<Regex, FactoryError> {
+	let src = v
+		.and_then(serde_json::Value::as_str)
+		.ok_or_else(|| FactoryError::Invalid(format!("args.{field}: expected a string")))?;
+	compile_bounded_regex(src).map_err(|e| FactoryError::Invalid(format!("args.{field}: {e}")))
+}
+
+/// Plug `rewrite` and `redirect` into a `MiddlewareFactories` registry.
+pub fn register(factories: &mut MiddlewareFactories) {
+	factories.register("rewrite", MiddlewareKind::L7Request, rewrite_factory);
+	factories.register("redirect", MiddlewareKind::L7Request, redirect_factory);
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::SocketAddr;
+	use std::time::Instant;
+
+	use tokio_util::sync::CancellationToken;
+	use vane_core::{
+		ConnId, FlowLogEvent, FlowLogSink, FlowLogVerbosity, NodeId, TrajectoryBuilder, Transport,
+	};
+
+	use super::*;
+
+	struct NullSink;
+	impl FlowLogSink for NullSink {
+		fn emit(&self, _event: FlowLogEvent) {}
+	}
+
+	fn middleware(inst: Result<MiddlewareInst, FactoryError>) -> Arc<dyn L7RequestMiddleware> {
+		match inst.expect("factory") {
+			MiddlewareInst::L7Request(m) => m,
+			_ => unreachable!("rewrite / redirect are L7Request"),
+		}
+	}
+
+	async fn run(m: &Arc<dyn L7RequestMiddleware>, uri: &str) -> (Request, Decision) {
+		let addr: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+		let conn = Arc::new(ConnContext::new(ConnId(0), addr, addr, Transport::Tcp, Instant::now()));
+		let mut ctx = FlowCtx {
+			span: tracing::Span::none(),
+			log: Arc::new(NullSink),
+			cancel: CancellationToken::new(),
+			accept_cancel: CancellationToken::new(),
+			verbosity: FlowLogVerbosity::Trajectory,
+			trajectory: TrajectoryBuilder::new(conn.id, NodeId::for_testing(0), 0),
+		};
+		let mut req = http::Request::builder().uri(uri).body(Body::Empty).expect("request");
+		let d = m.run(&mut req, &conn, &mut ctx).await.expect("run");
+		(req, d)
+	}
+
+	async fn rewritten(args: serde_json::Value, uri: &str) -> String {
+		let m = middleware(rewrite_factory(&args));
+		let (req, d) = run(&m, uri).await;
+		assert!(matches!(d, Decision::Continue));
+		req.uri().to_string()
+	}
+
+	#[tokio::test]
+	async fn strip_prefix_respects_segment_boundaries() {
+		let args = serde_json::json!({ "strip_prefix": "/api" });
+		assert_eq!(rewritten(args.clone(), "/api/users?x=1").await, "/users?x=1");
+		assert_eq!(rewritten(args.clone(), "/api").await, "/");
+		assert_eq!(rewritten(args, "/apiary").await, "/apiary");
+	}
+
+	#[tokio::test]
+	async fn operations_compose_and_keep_the_authority() {
+		let args = serde_json::json!({
+			"strip_prefix": "/api/",
+			"replace": { "pattern": "^/users/(?<id>\\d+)$", "with": "/users?id=${id}" },
+			"add_prefix": "/v2/",
+		});
+		assert_eq!(
+			rewritten(args, "http://example.com/api/users/42").await,
+			"http://example.com/v2/users?id=42"
+		);
+		let args =
+			serde_json::json!({ "replace": { "pattern": "(\\?|&)utm_[a-z]+=[^&]*", "with": "" } });
+		assert_eq!(rewritten(args, "/p?utm_source=x").await, "/p");
+	}
+
+	#[tokio::test]
+	async fn redirect_expands_captures_and_passes_misses_through() {
+		let m = middleware(redirect_factory(&serde_json::json!({
+			"pattern": "^/blog/(\\d+)",
+			"location": "https://blog.example.com/posts/$1?from=${http.uri.path}",
+			"status": 308,
+		})));
+		let (_, d) = run(&m, "/blog/7/comments").await;
+		let Decision::Short(ShortCircuit::Response(resp)) = d else { panic!("expected a redirect") };
+		assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+		assert_eq!(
+			resp.headers()[http::header::LOCATION],
+			"https://blog.example.com/posts/7?from=/blog/7/comments"
+		);
+
+		let (req, d) = run(&m, "/about").await;
+		assert!(matches!(d, Decision::Continue));
+		assert_eq!(req.uri(), "/about");
+	}
+
+	#[test]
+	fn factories_reject_bad_args() {
+		for (result, want) in [
+			(rewrite_factory(&serde_json::json!({})), "at least one"),
+			(rewrite_factory(&serde_json::json!({ "strip": "/a" })), "args.strip: unknown field"),
+			(rewrite_factory(&serde_json::json!({ "add_prefix": "v2" })), "starting with '/'"),
+			(
+				rewrite_factory(&serde_json::json!({ "replace": { "pattern": "(", "with": "" } })),
+				"args.replace.pattern: invalid regex",
+			),
+			(
+				rewrite_factory(
+					&serde_json::json!({ "replace": { "pattern": "^(a+)*b\\1$", "with": "" } }),
+				),
+				"backtrack",
+			),
+			(
+				rewrite_factory(&serde_json::json!({ "replace": { "pattern": "^/(x)", "with": "/$2" } })),
+				"args.replace.with",
+			),
+			(redirect_factory(&serde_json::json!({ "status": 301 })), "args.location: required"),
+			(
+				redirect_factory(&serde_json::json!({ "location": "/", "status": 303 })),
+				"not a redirect status",
+			),
+			(
+				redirect_factory(&serde_json::json!({ "pattern": "^/(x)", "location": "/$2" })),
+				"capture group $2 does not exist",
+			),
+		] {
+			let Err(err) = result else { panic!("expected {want:?}") };
+			let msg = err.message();
+			assert!(msg.contains(want), "want {want:?}: {msg}");
+		}
+	}
+}
@@ -1,5 +1,5 @@
 //! Preset expansion implementations registered against core's `expand`
 //! stage. Catalog: `port_forward`, `reverse_proxy` (with the WS gate),
-//! `static_site`, `redirect_https`.
+//! `static_site`, `file_server`, `redirect_https`.
 //!
 //! See [`spec/crates/core.md` § _Compile pipeline_](../../../spec/crates/core.md#compile-pipeline).
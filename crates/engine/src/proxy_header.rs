@@ -0,0 +1,116 @@
+//! PROXY protocol v1 / v2 on both edges of the proxy: reading the
+//! header a trusted load balancer prefixes onto an accepted connection,
+//! and building the one `send_proxy_protocol` fetches write onto their
+//! upstream connections.
+//!
+//! See `spec/crates/engine.md` § _PROXY protocol_.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use proxy_protocol::{Header, Version};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use vane_core::{ConnContext, ProxyInfo, ProxySslInfo};
+
+use crate::factories::FactoryError;
+
+/// Shortest legal header of either version: `PROXY UNKNOWN\r\n`. Every
+/// header starts with at least this many bytes, so the first read can
+/// take them without eating into the application stream.
+const MIN_HEADER_LEN: usize = 15;
+
+/// Consume the PROXY header at the start of `stream`, leaving the
+/// socket positioned at the first application byte so the listener can
+/// keep the concrete `TcpStream` (splice fast path, TLS handshake).
+///
+/// Never reads past the header: v2 announces its length up front, and
+/// v1's CRLF is located by peeking before the line is read. The caller
+/// bounds the whole read with the L1 header timeout.
+pub(crate) async fn read(stream: &mut TcpStream) -> io::Result<Header> {
+	let mut buf = vec![0u8; MIN_HEADER_LEN];
+	stream.read_exact(&mut buf).await?;
+	loop {
+		match Header::parse(&buf) {
+			Ok(Some((header, consumed))) => {
+				debug_assert_eq!(consumed, buf.len(), "header read past its end");
+				return Ok(header);
+			}
+			Ok(None) => {}
+			Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+		}
+		if buf.starts_with(&proxy_protocol::V2_SIGNATURE) {
+			// 16-byte prefix first, then exactly the announced length.
+			let want =
+				if buf.len() < 16 { 16 } else { 16 + usize::from(u16::from_be_bytes([buf[14], buf[15]])) };
+			let have = buf.len();
+			buf.resize(want, 0);
+			stream.read_exact(&mut buf[have..]).await?;
+		} else {
+			// v1: peek for the CRLF and read through it. When the line has
+			// not fully arrived yet, take one byte so the next peek waits
+			// for fresh data instead of spinning on what is buffered.
+			let mut peek = [0u8; proxy_protocol::V1_MAX_LEN];
+			let room = proxy_protocol::V1_MAX_LEN - buf.len();
+			let n = stream.peek(&mut peek[..room]).await?;
+			if n == 0 {
+				return Err(io::ErrorKind::UnexpectedEof.into());
+			}
+			let seam = buf.last() == Some(&b'\r') && peek[0] == b'\n';
+			let take = if seam {
+				1
+			} else {
+				peek[..n].windows(2).position(|w| w == b"\r\n").map_or(1, |at| at + 2)
+			};
+			let have = buf.len();
+			buf.resize(have + take, 0);
+			stream.read_exact(&mut buf[have..]).await?;
+		}
+	}
+}
+
+/// Per-connection record of a consumed header. `peer` is the balancer's
+/// socket address; the header's source has already replaced `remote`.
+pub(crate) fn info(peer: SocketAddr, header: &Header) -> ProxyInfo {
+	let ssl = header.ssl().map(|ssl| ProxySslInfo {
+		version: ssl.version.map(Arc::from),
+		cn: ssl.common_name.map(Arc::from),
+		cipher: ssl.cipher.map(Arc::from),
+		verified: ssl.verified(),
+	});
+	ProxyInfo {
+		peer,
+		authority: header.authority().map(Arc::from),
+		unique_id: header.unique_id().map(Bytes::copy_from_slice),
+		ssl,
+	}
+}
+
+/// Parse a fetch's `args.send_proxy_protocol` (`"v1"` / `"v2"`).
+/// Absent means the upstream gets no header.
+pub(crate) fn parse_send_arg(args: &serde_json::Value) -> Result<Option<Version>, FactoryError> {
+	match args.get("send_proxy_protocol") {
+		None | Some(serde_json::Value::Null) => Ok(None),
+		Some(serde_json::Value::String(s)) if s == "v1" => Ok(Some(Version::V1)),
+		Some(serde_json::Value::String(s)) if s == "v2" => Ok(Some(Version::V2)),
+		Some(other) => Err(FactoryError::Invalid(format!(
+			"args.send_proxy_protocol must be \"v1\" or \"v2\", got {other}"
+		))),
+	}
+}
+
+/// Header naming `conn`'s client to an upstream. v2 also forwards the
+/// SNI the client sent as the `AUTHORITY` TLV, so a TLS-passthrough
+/// backend behind `tcp_forward` can still route by name.
+pub(crate) fn outbound(version: Version, conn: &ConnContext) -> Vec<u8> {
+	let mut header = Header::proxy(version, conn.remote, conn.local);
+	if version == Version::V2 {
+		let sni = conn.tls().as_ref().and_then(|t| t.sni.clone());
+		if let Some(sni) = sni {
+			header = header.with_tlv(proxy_protocol::tlv::AUTHORITY, sni.as_bytes());
+		}
+	}
+	header.encode()
+}
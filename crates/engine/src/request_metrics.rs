@@ -0,0 +1,379 @@
+//! Per-rule HTTP request metrics.
+//!
+//! The H1 / H2 / H3 drivers open a [`RequestMeter`] when a request is
+//! decoded and ride it on the request's extensions through the
+//! executor. The `Node::Fetch` arm names the rule (lifted onto
+//! `SymbolicFetchRef::rule` by the lower pass) and times the upstream;
+//! the driver closes the meter over the response it hands back to
+//! hyper, and the response body reports duration and bytes when the
+//! last frame has gone out (or the client walked away).
+//!
+//! Emitted series (spec/crates/engine.md § _Request metrics_):
+//!
+//! - `vane.http.requests_total{rule,method,status_class}`
+//! - `vane.http.request.duration_ms{rule,method}`
+//! - `vane.http.request.ttfb_ms{rule,method}`
+//! - `vane.http.request_bytes_total{rule}` / `vane.http.response_bytes_total{rule}`
+//! - `vane.http.upstream.duration_ms{rule}`
+//!
+//! `method` and `status_class` are closed sets. `rule` is operator
+//! authored and admitted through a [`CardinalityRegistry`] capped by
+//! `VANE_HTTP_METRIC_RULE_CAP` (default 256); rules past the cap fold
+//! into `_other`.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use prom_cardinality_cap::CardinalityRegistry;
+use vane_core::{Body, Error, Request, Response};
+
+/// Distinct rule labels admitted when `VANE_HTTP_METRIC_RULE_CAP` is
+/// unset.
+const DEFAULT_RULE_CAP: usize = 256;
+
+/// Rule label for a request that never reached a fetch: refused by a
+/// request middleware, closed for want of a matching rule, or failed
+/// before dispatch.
+const NO_RULE: &str = "_none";
+
+/// Rule label for rules turned away by the cardinality cap.
+const OVERFLOW_RULE: &str = "_other";
+
+static RULES: LazyLock<CardinalityRegistry> = LazyLock::new(|| {
+	let cap = std::env::var("VANE_HTTP_METRIC_RULE_CAP")
+		.ok()
+		.and_then(|s| s.parse::<usize>().ok())
+		.filter(|n| *n > 0)
+		.unwrap_or(DEFAULT_RULE_CAP);
+	CardinalityRegistry::with_cap(cap)
+});
+
+static NAMESPACE: LazyLock<Arc<str>> = LazyLock::new(|| Arc::from("vane.http"));
+
+/// Resolve a rule name to its metric label, folding it into `_other`
+/// once the cap is reached.
+fn rule_label(registry: &CardinalityRegistry, namespace: &Arc<str>, rule: &str) -> String {
+	if registry.try_admit(namespace, "rule", &[("rule".to_owned(), rule.to_owned())]) {
+		rule.to_owned()
+	} else {
+		OVERFLOW_RULE.to_owned()
+	}
+}
+
+/// Closed `method` label: the nine RFC 9110 / RFC 5789 methods, with
+/// extension methods collapsed to `OTHER`.
+fn method_label(method: &http::Method) -> &'static str {
+	match *method {
+		http::Method::GET => "GET",
+		http::Method::HEAD => "HEAD",
+		http::Method::POST => "POST",
+		http::Method::PUT => "PUT",
+		http::Method::DELETE => "DELETE",
+		http::Method::PATCH => "PATCH",
+		http::Method::OPTIONS => "OPTIONS",
+		http::Method::CONNECT => "CONNECT",
+		http::Method::TRACE => "TRACE",
+		_ => "OTHER",
+	}
+}
+
+fn status_class(status: http::StatusCode) -> &'static str {
+	match status.as_u16() {
+		100..=199 => "1xx",
+		200..=299 => "2xx",
+		300..=399 => "3xx",
+		400..=499 => "4xx",
+		_ => "5xx",
+	}
+}
+
+fn millis(d: Duration) -> f64 {
+	d.as_secs_f64() * 1000.0
+}
+
+fn body_len(body: &Body) -> Option<u64> {
+	match body {
+		Body::Static(b) => Some(b.len() as u64),
+		Body::Empty => Some(0),
+		Body::Stream(_) => None,
+	}
+}
+
+struct MeterState {
+	start: Instant,
+	method: &'static str,
+	rule: parking_lot::Mutex<Option<String>>,
+	request_bytes: AtomicU64,
+}
+
+/// Per-request metrics handle. Cheap to clone; every clone feeds the
+/// same request.
+#[derive(Clone)]
+pub(crate) struct RequestMeter(Arc<MeterState>);
+
+impl RequestMeter {
+	/// Open a meter for `req`: start the clock, count its body as it
+	/// is read, and stash a clone on its extensions for the executor.
+	pub(crate) fn begin(req: Request) -> (Request, Self) {
+		let meter = Self(Arc::new(MeterState {
+			start: Instant::now(),
+			method: method_label(req.method()),
+			rule: parking_lot::Mutex::new(None),
+			request_bytes: AtomicU64::new(0),
+		}));
+		let (mut parts, body) = req.into_parts();
+		parts.extensions.insert(meter.clone());
+		let body = match body_len(&body) {
+			Some(n) => {
+				meter.0.request_bytes.store(n, Ordering::Relaxed);
+				body
+			}
+			None => meter.count(body, Counted::Request),
+		};
+		(Request::from_parts(parts, body), meter)
+	}
+
+	/// Name the rule whose fetch is about to run. A `fallback` fetch
+	/// belongs to the same rule, so a second call relabels nothing.
+	pub(crate) fn enter_rule(&self, rule: Option<&str>) {
+		let label = rule.map(|r| rule_label(&RULES, &NAMESPACE, r));
+		*self.0.rule.lock() = label;
+	}
+
+	/// Record how long the fetch took to produce response headers (or
+	/// fail).
+	pub(crate) fn upstream(&self, elapsed: Duration) {
+		metrics::histogram!("vane.http.upstream.duration_ms", "rule" => self.rule())
+			.record(millis(elapsed));
+	}
+
+	fn rule(&self) -> String {
+		self.0.rule.lock().clone().unwrap_or_else(|| NO_RULE.to_owned())
+	}
+
+	/// Close the meter over the response going back to the client:
+	/// count the request, record time to first byte, and wrap the body
+	/// so duration and bytes land once it has been written.
+	pub(crate) fn finish(self, resp: Response) -> Response {
+		let rule = self.rule();
+		metrics::counter!(
+			"vane.http.requests_total",
+			"rule" => rule.clone(),
+			"method" => self.0.method,
+			"status_class" => status_class(resp.status()),
+		)
+		.increment(1);
+		metrics::histogram!("vane.http.request.ttfb_ms", "rule" => rule, "method" => self.0.method)
+			.record(millis(self.0.start.elapsed()));
+		let (parts, body) = resp.into_parts();
+		let body = match body_len(&body) {
+			Some(n) => {
+				self.complete(n);
+				body
+			}
+			None => self.count(body, Counted::Response),
+		};
+		Response::from_parts(parts, body)
+	}
+
+	fn complete(&self, response_bytes: u64) {
+		let rule = self.rule();
+		metrics::histogram!(
+			"vane.http.request.duration_ms",
+			"rule" => rule.clone(),
+			"method" => self.0.method,
+		)
+		.record(millis(self.0.start.elapsed()));
+		metrics::counter!("vane.http.request_bytes_total", "rule" => rule.clone())
+			.increment(self.0.request_bytes.load(Ordering::Relaxed));
+		metrics::counter!("vane.http.response_bytes_total", "rule" => rule).increment(response_bytes);
+	}
+
+	fn count(&self, body: Body, side: Counted) -> Body {
+		let Body::Stream(inner) = body else {
+			return body;
+		};
+		Body::Stream(Box::pin(CountingBody { inner, meter: self.clone(), side, bytes: 0, done: false }))
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Counted {
+	Request,
+	Response,
+}
+
+/// Stream wrapper behind [`RequestMeter::begin`] / [`RequestMeter::finish`].
+/// Request bodies add to the meter as they are read; response bodies
+/// complete the meter at end of stream, on error, or on drop when the
+/// client disconnects mid-body.
+struct CountingBody {
+	inner: Pin<Box<dyn HttpBody<Data = Bytes, Error = Error> + Send + 'static>>,
+	meter: RequestMeter,
+	side: Counted,
+	bytes: u64,
+	done: bool,
+}
+
+impl CountingBody {
+	fn settle(&mut self) {
+		if !self.done && self.side == Counted::Response {
+			self.done = true;
+			self.meter.complete(self.bytes);
+		}
+	}
+}
+
+impl HttpBody for CountingBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let this = self.get_mut();
+		let polled = this.inner.as_mut().poll_frame(cx);
+		if let Poll::Ready(Some(Ok(frame))) = &polled
+			&& let Some(data) = frame.data_ref()
+		{
+			let n = data.len() as u64;
+			this.bytes += n;
+			if this.side == Counted::Request {
+				this.meter.0.request_bytes.fetch_add(n, Ordering::Relaxed);
+			}
+		}
+		if matches!(polled, Poll::Ready(None | Some(Err(_)))) || this.inner.is_end_stream() {
+			this.settle();
+		}
+		polled
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+impl Drop for CountingBody {
+	fn drop(&mut self) {
+		self.settle();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use http_body_util::BodyExt;
+
+	use super::*;
+
+	#[test]
+	fn method_label_collapses_extension_methods() {
+		assert_eq!(method_label(&http::Method::GET), "GET");
+		assert_eq!(method_label(&http::Method::PATCH), "PATCH");
+		let propfind = http::Method::from_bytes(b"PROPFIND").unwrap();
+		assert_eq!(method_label(&propfind), "OTHER");
+	}
+
+	#[test]
+	fn status_class_buckets() {
+		assert_eq!(status_class(http::StatusCode::SWITCHING_PROTOCOLS), "1xx");
+		assert_eq!(status_class(http::StatusCode::NO_CONTENT), "2xx");
+		assert_eq!(status_class(http::StatusCode::NOT_MODIFIED), "3xx");
+		assert_eq!(status_class(http::StatusCode::TOO_MANY_REQUESTS), "4xx");
+		assert_eq!(status_class(http::StatusCode::BAD_GATEWAY), "5xx");
+	}
+
+	#[test]
+	fn rules_past_the_cap_fold_into_other() {
+		let registry = CardinalityRegistry::with_cap(2);
+		let ns: Arc<str> = Arc::from("test");
+		assert_eq!(rule_label(&registry, &ns, "a"), "a");
+		assert_eq!(rule_label(&registry, &ns, "b"), "b");
+		assert_eq!(rule_label(&registry, &ns, "c"), OVERFLOW_RULE);
+		assert_eq!(rule_label(&registry, &ns, "a"), "a", "admitted rules stay admitted");
+	}
+
+	#[tokio::test]
+	async fn streamed_request_body_is_counted_as_read() {
+		let full = http_body_util::Full::new(Bytes::from_static(b"hello"))
+			.map_err(|never: std::convert::Infallible| -> Error { match never {} });
+		let stream = Body::from_producer(full);
+		let (req, meter) = RequestMeter::begin(http::Request::new(stream));
+		assert!(req.extensions().get::<RequestMeter>().is_some(), "executor sees the meter");
+		let collected = req.into_body().collect().await.unwrap().to_bytes();
+		assert_eq!(&collected[..], b"hello");
+		assert_eq!(meter.0.request_bytes.load(Ordering::Relaxed), 5);
+	}
+
+	/// Yields each chunk as its own data frame.
+	struct Chunks(std::collections::VecDeque<Bytes>);
+
+	impl HttpBody for Chunks {
+		type Data = Bytes;
+		type Error = Error;
+
+		fn poll_frame(
+			mut self: Pin<&mut Self>,
+			_cx: &mut Context<'_>,
+		) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+			Poll::Ready(self.0.pop_front().map(|b| Ok(Frame::data(b))))
+		}
+
+		fn is_end_stream(&self) -> bool {
+			self.0.is_empty()
+		}
+	}
+
+	/// A `rule`-labelled response carrying `chunks` through
+	/// [`RequestMeter::finish`], so its body is a [`CountingBody`].
+	fn metered_response(rule: &str, chunks: &[&'static [u8]]) -> Body {
+		crate::metrics::install_recorder().ok();
+		let (_req, meter) = RequestMeter::begin(http::Request::new(Body::Empty));
+		meter.enter_rule(Some(rule));
+		let chunks = Chunks(chunks.iter().map(|c| Bytes::from_static(c)).collect());
+		meter.finish(http::Response::new(Body::Stream(Box::pin(chunks)))).into_body()
+	}
+
+	/// The `vane.http.response_bytes_total` sample for `rule`, if any.
+	fn response_bytes(rule: &str) -> Option<u64> {
+		let text = crate::metrics::render_prometheus()?;
+		let prefix = format!("vane_http_response_bytes_total{{rule=\"{rule}\"}} ");
+		text.lines().find_map(|l| l.strip_prefix(&prefix)?.parse().ok())
+	}
+
+	#[tokio::test]
+	async fn response_body_bytes_land_at_end_of_stream() {
+		let mut body = metered_response("counting-end", &[b"hel", b"lo"]);
+		body.frame().await.unwrap().unwrap();
+		assert_eq!(response_bytes("counting-end"), None, "nothing lands mid-body");
+		body.frame().await.unwrap().unwrap();
+		assert_eq!(response_bytes("counting-end"), Some(5));
+		drop(body);
+		assert_eq!(response_bytes("counting-end"), Some(5), "drop after the end counts nothing more");
+	}
+
+	#[tokio::test]
+	async fn response_body_dropped_mid_stream_counts_bytes_sent() {
+		let mut body = metered_response("counting-drop", &[b"hel", b"lo"]);
+		body.frame().await.unwrap().unwrap();
+		drop(body);
+		assert_eq!(response_bytes("counting-drop"), Some(3));
+	}
+
+	#[test]
+	fn unnamed_requests_use_the_none_label() {
+		let (_req, meter) = RequestMeter::begin(http::Request::new(Body::Empty));
+		assert_eq!(meter.rule(), NO_RULE);
+		meter.enter_rule(Some("api"));
+		assert_eq!(meter.rule(), "api");
+	}
+}
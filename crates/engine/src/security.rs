@@ -1,28 +1,49 @@
 //! L1 security floor — daemon self-preservation: per-IP + global
-//! connection caps, header / body timeouts, floor-enforcement at
-//! startup.
+//! connection caps, handshake rate and pending-handshake caps, H2 / H3
+//! stream and request-rate caps, header / body timeouts,
+//! floor-enforcement at startup, and the ban table ([`ban`]) those
+//! limits feed.
 //!
 //! State is daemon-scoped (lives outside `FlowGraph`), so config reload
 //! does not reset counters. See `spec/crates/engine.md` § _Security floor (L1)_.
 
 use std::net::IpAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use dashmap::DashMap;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use parking_lot::Mutex;
+use tokio::time::Sleep;
 use tokio_util::sync::CancellationToken;
-use vane_core::{Error, config::Env};
+use vane_core::{Body, ConnContext, Error, TimeoutKind, config::Env};
 
 use crate::time::now_unix_ms;
 use crate::tls::CrlCache;
 
+pub mod ban;
+
+pub use ban::{BanConfig, BanRecord, BanRefused, BanSource, BanTable, Offense};
+
 // Spec-defined minimums from spec/crates/core.md.
 const FLOOR_HEADER_TIMEOUT_SECS: u32 = 5;
 const FLOOR_MAX_HEADER_BYTES: usize = 4_096;
 const FLOOR_MAX_HEADERS_COUNT: usize = 20;
 const FLOOR_MAX_CONN_PER_IP: usize = 10;
 const FLOOR_MAX_TOTAL_CONNS: usize = 1_024;
+const FLOOR_BODY_IDLE_TIMEOUT_SECS: u32 = 5;
+const FLOOR_MAX_HANDSHAKE_RATE_PER_IP: u32 = 1;
+const FLOOR_MAX_PENDING_HANDSHAKES: usize = 100;
+const FLOOR_MAX_STREAMS_PER_CONN: usize = 10;
+const FLOOR_MAX_REQUEST_RATE_PER_CONN: u32 = 100;
+
+/// Window of the `*_rate_*` limits.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
 
 /// Typed snapshot of `VANE_SEC_*` deployment constants. Values are
 /// validated against spec-defined minimums at construction time;
@@ -52,6 +73,29 @@ pub struct SecurityConfig {
 	/// `VANE_SEC_MAX_TOTAL_CONNS` — daemon-wide maximum concurrent
 	/// connections (default 65536, floor 1024).
 	pub max_total_conns: usize,
+	/// `VANE_SEC_BODY_IDLE_TIMEOUT` — longest gap between request-body
+	/// frames from the client (default 30 s, floor 5 s). On expiry the
+	/// body fails and the driver answers `408`, closing H1 connections.
+	pub body_idle_timeout: Duration,
+	/// `VANE_SEC_MAX_HANDSHAKE_RATE_PER_IP` — TLS / QUIC handshakes a
+	/// single source IP may start per second (default 10, floor 1).
+	/// Excess connections are dropped before the ClientHello is read.
+	pub max_handshake_rate_per_ip: u32,
+	/// `VANE_SEC_MAX_PENDING_HANDSHAKES` — daemon-wide cap on TLS /
+	/// QUIC handshakes in progress (default 1000, floor 100).
+	pub max_pending_handshakes: usize,
+	/// `VANE_SEC_MAX_STREAMS_PER_CONN` — in-flight H2 / H3 request
+	/// streams per connection (default 100, floor 10). Excess streams
+	/// are refused (`REFUSED_STREAM` / `H3_REQUEST_REJECTED`).
+	pub max_in_flight_streams_per_conn: usize,
+	/// `VANE_SEC_MAX_REQUEST_RATE_PER_CONN` — H2 / H3 requests per
+	/// second per connection (default 1000, floor 100). Excess streams
+	/// are refused; a connection reaching twice the rate is closed.
+	pub max_request_rate_per_conn: u32,
+	/// `VANE_SEC_BAN_*` — automatic-ban thresholds, durations and the
+	/// never-banned allow list. Thresholds default to `0`: the table
+	/// only holds bans set by request until an operator opts in.
+	pub ban: BanConfig,
 	/// Daemon-wide CRL cache shared by listener mTLS and upstream
 	/// verification. `None` for tests / default builds without CRL
 	/// support; populated by daemon main when at least one rule
@@ -67,6 +111,12 @@ impl std::fmt::Debug for SecurityConfig {
 			.field("max_headers_count", &self.max_headers_count)
 			.field("max_conn_per_ip", &self.max_conn_per_ip)
 			.field("max_total_conns", &self.max_total_conns)
+			.field("body_idle_timeout", &self.body_idle_timeout)
+			.field("max_handshake_rate_per_ip", &self.max_handshake_rate_per_ip)
+			.field("max_pending_handshakes", &self.max_pending_handshakes)
+			.field("max_in_flight_streams_per_conn", &self.max_in_flight_streams_per_conn)
+			.field("max_request_rate_per_conn", &self.max_request_rate_per_conn)
+			.field("ban", &self.ban)
 			.field("crl_cache", &self.crl_cache.is_some())
 			.finish()
 	}
@@ -80,6 +130,12 @@ impl Default for SecurityConfig {
 			max_headers_count: 100,
 			max_conn_per_ip: 100,
 			max_total_conns: 65_536,
+			body_idle_timeout: Duration::from_secs(30),
+			max_handshake_rate_per_ip: 10,
+			max_pending_handshakes: 1_000,
+			max_in_flight_streams_per_conn: 100,
+			max_request_rate_per_conn: 1_000,
+			ban: BanConfig::default(),
 			crl_cache: None,
 		}
 	}
@@ -114,12 +170,57 @@ impl SecurityConfig {
 			"VANE_SEC_MAX_TOTAL_CONNS",
 			FLOOR_MAX_TOTAL_CONNS,
 		)?;
+		floor_u32(
+			env.sec_body_idle_timeout_secs,
+			"VANE_SEC_BODY_IDLE_TIMEOUT",
+			FLOOR_BODY_IDLE_TIMEOUT_SECS,
+		)?;
+		floor_u32(
+			env.sec_max_handshake_rate_per_ip,
+			"VANE_SEC_MAX_HANDSHAKE_RATE_PER_IP",
+			FLOOR_MAX_HANDSHAKE_RATE_PER_IP,
+		)?;
+		floor_usize(
+			env.sec_max_pending_handshakes as usize,
+			"VANE_SEC_MAX_PENDING_HANDSHAKES",
+			FLOOR_MAX_PENDING_HANDSHAKES,
+		)?;
+		floor_usize(
+			env.sec_max_streams_per_conn as usize,
+			"VANE_SEC_MAX_STREAMS_PER_CONN",
+			FLOOR_MAX_STREAMS_PER_CONN,
+		)?;
+		floor_u32(
+			env.sec_max_request_rate_per_conn,
+			"VANE_SEC_MAX_REQUEST_RATE_PER_CONN",
+			FLOOR_MAX_REQUEST_RATE_PER_CONN,
+		)?;
+		floor_u32(env.sec_ban_window_secs, "VANE_SEC_BAN_WINDOW", 1)?;
+		floor_u32(env.sec_ban_duration_secs, "VANE_SEC_BAN_DURATION", 1)?;
+		floor_u32(
+			env.sec_ban_max_duration_secs,
+			"VANE_SEC_BAN_MAX_DURATION",
+			env.sec_ban_duration_secs,
+		)?;
 		Ok(Self {
 			header_timeout: Duration::from_secs(env.sec_header_timeout_secs.into()),
 			max_header_bytes: env.sec_max_header_bytes as usize,
 			max_headers_count: env.sec_max_headers_count as usize,
 			max_conn_per_ip: env.sec_max_conn_per_ip as usize,
 			max_total_conns: env.sec_max_total_conns as usize,
+			body_idle_timeout: Duration::from_secs(env.sec_body_idle_timeout_secs.into()),
+			max_handshake_rate_per_ip: env.sec_max_handshake_rate_per_ip,
+			max_pending_handshakes: env.sec_max_pending_handshakes as usize,
+			max_in_flight_streams_per_conn: env.sec_max_streams_per_conn as usize,
+			max_request_rate_per_conn: env.sec_max_request_rate_per_conn,
+			ban: BanConfig {
+				strikes: env.sec_ban_strikes,
+				client_errors: env.sec_ban_client_errors,
+				window: Duration::from_secs(env.sec_ban_window_secs.into()),
+				duration: Duration::from_secs(env.sec_ban_duration_secs.into()),
+				max_duration: Duration::from_secs(env.sec_ban_max_duration_secs.into()),
+				allow: env.sec_ban_allow.clone(),
+			},
 			crl_cache: None,
 		})
 	}
@@ -151,9 +252,32 @@ fn floor_usize(val: usize, var: &str, floor: usize) -> Result<(), Error> {
 /// `(limit kind, source IP)` so a flood from a single IP emits at
 /// most one warning per second per limit type.
 #[derive(Clone, Hash, Eq, PartialEq)]
-enum LimitLogKey {
-	TotalConns(IpAddr),
-	PerIpConns(IpAddr),
+struct LimitLogKey {
+	limit: &'static str,
+	ip: IpAddr,
+}
+
+/// Fixed one-second window counter behind the `*_rate_*` limits.
+struct RateWindow {
+	start: Instant,
+	count: u32,
+}
+
+impl RateWindow {
+	const fn new(now: Instant) -> Self {
+		Self { start: now, count: 0 }
+	}
+
+	/// Count one event at `now`; returns the events seen so far in the
+	/// current window, this one included.
+	fn hit(&mut self, now: Instant) -> u32 {
+		if now.checked_duration_since(self.start).is_none_or(|d| d >= RATE_WINDOW) {
+			self.start = now;
+			self.count = 0;
+		}
+		self.count = self.count.saturating_add(1);
+		self.count
+	}
 }
 
 /// Daemon-scoped L1 security state. Lives outside `FlowGraph` so
@@ -163,6 +287,10 @@ pub struct SecurityState {
 	pub cfg: SecurityConfig,
 	per_ip: DashMap<IpAddr, AtomicUsize>,
 	total: AtomicUsize,
+	/// Per-IP handshake starts in the current one-second window.
+	handshake_rate: DashMap<IpAddr, RateWindow>,
+	/// TLS / QUIC handshakes in progress, daemon-wide.
+	pending_handshakes: AtomicUsize,
 	/// Last warn timestamp per `(limit, ip)` for 1-second dedup.
 	/// Shared between the tracing path and the optional flow-log
 	/// emission so both stay coalesced on the same window.
@@ -176,15 +304,25 @@ pub struct SecurityState {
 	/// (boot phase creates `SecurityState` before the sink exists),
 	/// while every read after boot is lock-free.
 	log_sink: std::sync::OnceLock<Arc<dyn vane_core::FlowLogSink>>,
+	/// Banned sources, dropped at accept. Fed by [`Self::limit_hit`]
+	/// and the drivers; see [`ban`].
+	bans: BanTable,
+	/// `<state dir>/bans.json` when `VANE_SEC_BAN_PERSIST` is on; fixed
+	/// at boot like `log_sink`.
+	ban_store: std::sync::OnceLock<PathBuf>,
 }
 
 impl SecurityState {
 	#[must_use]
 	pub fn new(cfg: SecurityConfig) -> Self {
 		Self {
+			bans: BanTable::new(cfg.ban.clone()),
+			ban_store: std::sync::OnceLock::new(),
 			cfg,
 			per_ip: DashMap::new(),
 			total: AtomicUsize::new(0),
+			handshake_rate: DashMap::new(),
+			pending_handshakes: AtomicUsize::new(0),
 			last_warn: DashMap::new(),
 			log_sink: std::sync::OnceLock::new(),
 		}
@@ -200,6 +338,48 @@ impl SecurityState {
 		let _ = self.log_sink.set(sink);
 	}
 
+	/// The daemon's ban table, for the mgmt verbs and the plugin `ban`
+	/// decision.
+	#[must_use]
+	pub const fn bans(&self) -> &BanTable {
+		&self.bans
+	}
+
+	/// Keep the ban table in `path`: restores the bans saved there, then
+	/// lets [`Self::flush_bans`] and the cleanup task write changes
+	/// back. Returns the number of bans restored. Later calls only
+	/// reload (`OnceLock` semantics, as for the log sink).
+	///
+	/// # Errors
+	/// I/O failure reading the file, or a malformed file.
+	pub fn set_ban_store(&self, path: PathBuf) -> std::io::Result<usize> {
+		let restored = self.bans.load(&path)?;
+		let _ = self.ban_store.set(path);
+		Ok(restored)
+	}
+
+	/// Write pending ban-table changes to the store, if one is set.
+	/// Failures are logged; the next flush retries.
+	pub fn flush_bans(&self) {
+		if let Some(path) = self.ban_store.get()
+			&& let Err(e) = self.bans.save(path)
+		{
+			tracing::warn!(path = %path.display(), error = %e, "failed to persist ban table");
+		}
+	}
+
+	/// Accept-path ban check: `true` (and counted in
+	/// `vane.security.ban_drop_total`) when the caller must drop the
+	/// connection from `ip` unanswered.
+	#[must_use]
+	pub fn drop_banned(&self, ip: IpAddr) -> bool {
+		if !self.bans.is_banned(ip) {
+			return false;
+		}
+		metrics::counter!("vane.security.ban_drop_total").increment(1);
+		true
+	}
+
 	/// Attempt to register a new connection from `ip`.
 	///
 	/// Returns a [`ConnSecGuard`] on success — its `Drop` impl
@@ -213,7 +393,7 @@ impl SecurityState {
 		let prev_total = self.total.fetch_add(1, Ordering::AcqRel);
 		if prev_total >= self.cfg.max_total_conns {
 			self.total.fetch_sub(1, Ordering::Release);
-			self.maybe_warn(LimitLogKey::TotalConns(ip), ip, "max_total_conns");
+			self.limit_hit(ip, "max_total_conns");
 			return None;
 		}
 
@@ -226,11 +406,65 @@ impl SecurityState {
 				c.fetch_sub(1, Ordering::Release);
 			}
 			self.total.fetch_sub(1, Ordering::Release);
-			self.maybe_warn(LimitLogKey::PerIpConns(ip), ip, "max_conn_per_ip");
+			self.limit_hit(ip, "max_conn_per_ip");
+			return None;
+		}
+
+		Some(ConnSecGuard { state: Arc::clone(self), ip: Some(ip) })
+	}
+
+	/// Register a connection with no source IP (a Unix-socket peer).
+	/// Only the global cap applies: every local peer would otherwise
+	/// share one per-IP bucket. Same guard semantics as
+	/// [`Self::check_and_register`].
+	pub fn check_and_register_local(self: &Arc<Self>) -> Option<ConnSecGuard> {
+		let prev_total = self.total.fetch_add(1, Ordering::AcqRel);
+		if prev_total >= self.cfg.max_total_conns {
+			self.total.fetch_sub(1, Ordering::Release);
+			self.limit_hit(IpAddr::from([0, 0, 0, 0]), "max_total_conns");
 			return None;
 		}
+		Some(ConnSecGuard { state: Arc::clone(self), ip: None })
+	}
 
-		Some(ConnSecGuard { state: Arc::clone(self), ip })
+	/// Admit a TLS or QUIC handshake from `ip`: counts it against the
+	/// per-IP handshake rate, then takes a slot of the daemon-wide
+	/// pending-handshake cap. The returned guard frees the slot; hold
+	/// it until the handshake completes or fails.
+	///
+	/// Returns `None` when either limit is hit. The caller drops the
+	/// connection before reading the ClientHello.
+	pub fn begin_handshake(self: &Arc<Self>, ip: IpAddr) -> Option<HandshakeGuard> {
+		let now = Instant::now();
+		let seen = self.handshake_rate.entry(ip).or_insert_with(|| RateWindow::new(now)).hit(now);
+		if seen > self.cfg.max_handshake_rate_per_ip {
+			self.limit_hit(ip, "max_handshake_rate_per_ip");
+			return None;
+		}
+		let prev = self.pending_handshakes.fetch_add(1, Ordering::AcqRel);
+		if prev >= self.cfg.max_pending_handshakes {
+			self.pending_handshakes.fetch_sub(1, Ordering::Release);
+			self.limit_hit(ip, "max_pending_handshakes");
+			return None;
+		}
+		Some(HandshakeGuard { state: Arc::clone(self) })
+	}
+
+	/// Record one hit of `limit` by `ip`: the full-fidelity
+	/// `vane.security.limit_hit_total` counter, plus the deduplicated
+	/// warn and `SecurityLimit` flow-log event.
+	pub(crate) fn limit_hit(&self, ip: IpAddr, limit: &'static str) {
+		metrics::counter!(
+			"vane.security.limit_hit_total",
+			"limit" => limit,
+			"source" => ip.to_string(),
+		)
+		.increment(1);
+		self.maybe_warn(LimitLogKey { limit, ip }, ip, limit);
+		// The daemon-wide caps say nothing about this source.
+		if !matches!(limit, "max_total_conns" | "max_pending_handshakes") {
+			self.bans.offend(ip, Offense::Limit);
+		}
 	}
 
 	fn maybe_warn(&self, key: LimitLogKey, ip: IpAddr, limit: &'static str) {
@@ -243,7 +477,7 @@ impl SecurityState {
 			return;
 		}
 		self.last_warn.insert(key, now);
-		tracing::warn!(%ip, limit, "L1 security limit exceeded — new connection rejected");
+		tracing::warn!(%ip, limit, "L1 security limit exceeded");
 		// Mirror the warn into the structured flow log when a sink is
 		// installed. `FlowLogKind::SecurityLimit` is the spec slot for
 		// this event class but used to be dead code — every tracer-
@@ -265,9 +499,10 @@ impl SecurityState {
 		}
 	}
 
-	/// Spawn a background task that prunes zero-count per-IP entries
-	/// and stale log-dedup slots every 60 seconds. Cancelled via the
-	/// supplied token (typically the daemon's shutdown trigger).
+	/// Spawn a background task that prunes zero-count per-IP entries,
+	/// stale handshake-rate windows, stale log-dedup slots and expired
+	/// bans every 60 seconds, then flushes the ban store. Cancelled via the supplied token (typically the daemon's
+	/// shutdown trigger).
 	pub fn spawn_cleanup(self: Arc<Self>, cancel: CancellationToken) {
 		tokio::spawn(async move {
 			loop {
@@ -278,28 +513,352 @@ impl SecurityState {
 				}
 				self.per_ip.retain(|_, v| v.load(Ordering::Relaxed) > 0);
 				let now = Instant::now();
+				self.handshake_rate.retain(|_, w| {
+					now.checked_duration_since(w.start).is_none_or(|d| d < Duration::from_mins(1))
+				});
 				self
 					.last_warn
 					.retain(|_, v| now.checked_duration_since(*v).is_none_or(|d| d < Duration::from_mins(1)));
+				self.bans.prune();
+				self.flush_bans();
 			}
 		});
 	}
 }
 
-/// RAII guard: decrements global and per-IP connection counters on
-/// drop. Held for the duration of `handle_connection` so the
+/// RAII guard: decrements global and (for IP peers) per-IP connection
+/// counters on drop. Held for the duration of `handle_connection` so the
 /// decrement runs on every exit path including panics and
 /// cancellations.
 pub struct ConnSecGuard {
 	state: Arc<SecurityState>,
-	ip: IpAddr,
+	ip: Option<IpAddr>,
 }
 
 impl Drop for ConnSecGuard {
 	fn drop(&mut self) {
 		self.state.total.fetch_sub(1, Ordering::Relaxed);
-		if let Some(c) = self.state.per_ip.get(&self.ip) {
+		if let Some(c) = self.ip.and_then(|ip| self.state.per_ip.get(&ip)) {
 			c.fetch_sub(1, Ordering::Relaxed);
 		}
 	}
 }
+
+/// RAII guard from [`SecurityState::begin_handshake`]: frees the
+/// pending-handshake slot on drop.
+pub struct HandshakeGuard {
+	state: Arc<SecurityState>,
+}
+
+impl Drop for HandshakeGuard {
+	fn drop(&mut self) {
+		self.state.pending_handshakes.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// The daemon's [`SecurityState`] as one connection sees it. The
+/// listener stashes it in `ConnContext.user` at dispatch, so the H1 /
+/// H2 / H3 drivers — reached through the executor, not the listener —
+/// can enforce the handshake, stream and body limits against the
+/// connection's source. Connections driven outside a listener (unit
+/// tests) carry none, and those limits are skipped.
+#[derive(Clone)]
+pub(crate) struct ConnFloor {
+	state: Arc<SecurityState>,
+	ip: IpAddr,
+}
+
+impl ConnFloor {
+	pub(crate) fn install(conn: &ConnContext, state: &Arc<SecurityState>) {
+		conn.user.lock().insert(Self { state: Arc::clone(state), ip: conn.remote.ip() });
+	}
+
+	pub(crate) fn of(conn: &ConnContext) -> Option<Self> {
+		conn.user.lock().get::<Self>().cloned()
+	}
+
+	pub(crate) fn begin_handshake(&self) -> Option<HandshakeGuard> {
+		self.state.begin_handshake(self.ip)
+	}
+
+	/// Report an offence by the connection's source to the ban table.
+	pub(crate) fn offend(&self, offense: Offense) {
+		self.state.bans.offend(self.ip, offense);
+	}
+
+	/// Ban the connection's source on a plugin's say-so.
+	///
+	/// # Errors
+	/// [`BanRefused`] when the source is allow-listed.
+	pub(crate) fn ban(
+		&self,
+		duration: Option<Duration>,
+		reason: String,
+	) -> Result<BanRecord, BanRefused> {
+		self.state.bans.ban(ipnet::IpNet::from(self.ip), duration, reason, BanSource::Plugin)
+	}
+
+	/// Stream admission state for one multiplexed (H2 / H3) connection.
+	pub(crate) fn stream_limits(&self) -> Arc<StreamLimits> {
+		Arc::new(StreamLimits {
+			floor: self.clone(),
+			in_flight: AtomicUsize::new(0),
+			rate: Mutex::new(RateWindow::new(Instant::now())),
+		})
+	}
+
+	/// Bound a client request body by `body_idle_timeout`. The returned
+	/// flag reads `true` once the body has idled out, so the driver can
+	/// answer `408` whatever the executor made of the body error.
+	pub(crate) fn bound_request_body(&self, body: Body) -> (Body, BodyIdleFlag) {
+		let flag = BodyIdleFlag::default();
+		let body = match body {
+			Body::Stream(inner) => {
+				let timeout = self.state.cfg.body_idle_timeout;
+				Body::Stream(Box::pin(IdleBody {
+					inner,
+					timeout,
+					timer: Box::pin(tokio::time::sleep(timeout)),
+					floor: self.clone(),
+					flag: flag.clone(),
+				}))
+			}
+			other => other,
+		};
+		(body, flag)
+	}
+}
+
+/// Why [`StreamLimits::admit`] turned a stream away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StreamRefusal {
+	/// Refuse this stream; the connection stays up.
+	Stream,
+	/// Refuse this stream and close the connection: the client has
+	/// reached twice `max_request_rate_per_conn` this window.
+	Connection,
+}
+
+/// Per-connection H2 / H3 admission: the in-flight stream cap and the
+/// request-rate window. Shared by every stream handler of one
+/// connection.
+pub(crate) struct StreamLimits {
+	floor: ConnFloor,
+	in_flight: AtomicUsize,
+	rate: Mutex<RateWindow>,
+}
+
+impl StreamLimits {
+	/// Admit one request stream. The guard holds its in-flight slot;
+	/// drivers keep it until the response has been written.
+	///
+	/// # Errors
+	/// [`StreamRefusal`] when the request rate or the in-flight cap is
+	/// exceeded; the hit is recorded before returning.
+	pub(crate) fn admit(self: &Arc<Self>) -> Result<StreamGuard, StreamRefusal> {
+		let cfg = &self.floor.state.cfg;
+		let seen = self.rate.lock().hit(Instant::now());
+		if seen > cfg.max_request_rate_per_conn {
+			self.floor.state.limit_hit(self.floor.ip, "max_request_rate_per_conn");
+			return Err(if seen > cfg.max_request_rate_per_conn.saturating_mul(2) {
+				StreamRefusal::Connection
+			} else {
+				StreamRefusal::Stream
+			});
+		}
+		let prev = self.in_flight.fetch_add(1, Ordering::AcqRel);
+		if prev >= cfg.max_in_flight_streams_per_conn {
+			self.in_flight.fetch_sub(1, Ordering::Release);
+			self.floor.state.limit_hit(self.floor.ip, "max_in_flight_streams_per_conn");
+			return Err(StreamRefusal::Stream);
+		}
+		Ok(StreamGuard(Arc::clone(self)))
+	}
+}
+
+/// RAII guard from [`StreamLimits::admit`]: frees the in-flight slot on
+/// drop.
+pub(crate) struct StreamGuard(Arc<StreamLimits>);
+
+impl Drop for StreamGuard {
+	fn drop(&mut self) {
+		self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// Response extension: a middleware already reported the answer
+/// against the client's source (a `rate_limit` rejection), so the
+/// drivers' `4xx` tally skips it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OffenseRecorded;
+
+/// Set by an [`IdleBody`] when the client's request body idled out.
+#[derive(Clone, Default)]
+pub(crate) struct BodyIdleFlag(Arc<AtomicBool>);
+
+impl BodyIdleFlag {
+	pub(crate) fn expired(&self) -> bool {
+		self.0.load(Ordering::Acquire)
+	}
+}
+
+/// Client request body that fails with `Timeout(Idle)` when no frame
+/// arrives within `body_idle_timeout` (re-armed on every frame).
+struct IdleBody {
+	inner: Pin<Box<dyn HttpBody<Data = Bytes, Error = Error> + Send + 'static>>,
+	timeout: Duration,
+	timer: Pin<Box<Sleep>>,
+	floor: ConnFloor,
+	flag: BodyIdleFlag,
+}
+
+impl HttpBody for IdleBody {
+	type Data = Bytes;
+	type Error = Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let this = self.get_mut();
+		if this.flag.expired() {
+			return Poll::Ready(None);
+		}
+		match this.inner.as_mut().poll_frame(cx) {
+			Poll::Ready(frame) => {
+				this.timer.as_mut().reset(tokio::time::Instant::now() + this.timeout);
+				Poll::Ready(frame)
+			}
+			Poll::Pending => {
+				if this.timer.as_mut().poll(cx).is_pending() {
+					return Poll::Pending;
+				}
+				this.flag.0.store(true, Ordering::Release);
+				this.floor.state.limit_hit(this.floor.ip, "body_idle_timeout");
+				Poll::Ready(Some(Err(Error::timeout(TimeoutKind::Idle).with_ctx("client request body"))))
+			}
+		}
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.flag.expired() || self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::SocketAddr;
+
+	use vane_core::{ConnId, ErrorKind, Transport};
+
+	use super::*;
+
+	fn state(cfg: SecurityConfig) -> Arc<SecurityState> {
+		Arc::new(SecurityState::new(cfg))
+	}
+
+	fn conn() -> Arc<ConnContext> {
+		let remote: SocketAddr = "198.51.100.4:40000".parse().expect("addr");
+		let local: SocketAddr = "127.0.0.1:443".parse().expect("addr");
+		Arc::new(ConnContext::new(ConnId(0), remote, local, Transport::Tcp, Instant::now()))
+	}
+
+	/// Body whose client never sends another frame.
+	struct Stalled;
+
+	impl HttpBody for Stalled {
+		type Data = Bytes;
+		type Error = Error;
+
+		fn poll_frame(
+			self: Pin<&mut Self>,
+			_cx: &mut Context<'_>,
+		) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+			Poll::Pending
+		}
+	}
+
+	#[test]
+	fn rate_window_resets_after_one_second() {
+		let t0 = Instant::now();
+		let mut w = RateWindow::new(t0);
+		assert_eq!(w.hit(t0), 1);
+		assert_eq!(w.hit(t0 + Duration::from_millis(999)), 2);
+		assert_eq!(w.hit(t0 + RATE_WINDOW), 1, "new window");
+	}
+
+	#[test]
+	fn handshake_rate_is_per_ip() {
+		let s = state(SecurityConfig { max_handshake_rate_per_ip: 2, ..SecurityConfig::default() });
+		let a = IpAddr::from([198, 51, 100, 1]);
+		let b = IpAddr::from([198, 51, 100, 2]);
+		assert!(s.begin_handshake(a).is_some());
+		assert!(s.begin_handshake(a).is_some());
+		assert!(s.begin_handshake(a).is_none(), "third in the window");
+		assert!(s.begin_handshake(b).is_some(), "other IPs unaffected");
+	}
+
+	#[test]
+	fn pending_handshakes_free_on_guard_drop() {
+		let s = state(SecurityConfig {
+			max_pending_handshakes: 1,
+			max_handshake_rate_per_ip: 100,
+			..SecurityConfig::default()
+		});
+		let ip = IpAddr::from([198, 51, 100, 1]);
+		let g = s.begin_handshake(ip).expect("first");
+		assert!(s.begin_handshake(ip).is_none(), "cap reached");
+		drop(g);
+		assert!(s.begin_handshake(ip).is_some(), "slot freed");
+	}
+
+	#[test]
+	fn streams_refused_past_in_flight_cap() {
+		let s =
+			state(SecurityConfig { max_in_flight_streams_per_conn: 2, ..SecurityConfig::default() });
+		let c = conn();
+		ConnFloor::install(&c, &s);
+		let limits = ConnFloor::of(&c).expect("installed").stream_limits();
+		let g1 = limits.admit().expect("first");
+		let _g2 = limits.admit().expect("second");
+		assert_eq!(limits.admit().err(), Some(StreamRefusal::Stream));
+		drop(g1);
+		assert!(limits.admit().is_ok(), "slot freed");
+	}
+
+	#[test]
+	fn request_rate_refuses_then_closes_at_twice() {
+		let s = state(SecurityConfig { max_request_rate_per_conn: 2, ..SecurityConfig::default() });
+		let c = conn();
+		ConnFloor::install(&c, &s);
+		let limits = ConnFloor::of(&c).expect("installed").stream_limits();
+		for _ in 0..2 {
+			drop(limits.admit().expect("within rate"));
+		}
+		for _ in 0..2 {
+			assert_eq!(limits.admit().err(), Some(StreamRefusal::Stream));
+		}
+		assert_eq!(limits.admit().err(), Some(StreamRefusal::Connection));
+	}
+
+	#[tokio::test]
+	async fn idle_request_body_fails_and_sets_flag() {
+		let s = state(SecurityConfig {
+			body_idle_timeout: Duration::from_millis(20),
+			..SecurityConfig::default()
+		});
+		let c = conn();
+		ConnFloor::install(&c, &s);
+		let floor = ConnFloor::of(&c).expect("installed");
+		let (mut body, flag) = floor.bound_request_body(Body::Stream(Box::pin(Stalled)));
+		let frame = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await;
+		let err = frame.expect("frame").expect_err("idle timeout");
+		assert_eq!(err.kind, ErrorKind::Timeout(TimeoutKind::Idle));
+		assert!(flag.expired());
+		assert!(body.is_end_stream(), "body ends after the timeout");
+	}
+}
@@ -0,0 +1,624 @@
+//! Daemon-scoped ban table: source IPs and prefixes dropped at accept,
+//! before any TLS work.
+//!
+//! Fed by repeated L1 limit hits, `rate_limit` rejections and `4xx`
+//! bursts (automatic, escalating bans), by WASM plugin `ban` decisions
+//! and by the `ban` / `unban` mgmt verbs. Allow-listed CIDRs are never
+//! banned. Expiry is wall-clock (unix ms) so the optional on-disk copy
+//! stays meaningful across restarts. See `spec/crates/engine.md`
+//! § _Security floor (L1)_.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::time::now_unix_ms;
+
+/// Automatic-ban policy and the allow list. Built from the
+/// `VANE_SEC_BAN_*` env vars by [`super::SecurityConfig::new`].
+#[derive(Clone, Debug)]
+pub struct BanConfig {
+	/// L1 limit hits plus `rate_limit` rejections within `window` that
+	/// ban an IP. `0` disables strike bans.
+	pub strikes: u32,
+	/// `4xx` responses within `window` that ban an IP. `0` disables.
+	pub client_errors: u32,
+	/// Offence counting window.
+	pub window: Duration,
+	/// First automatic ban of an IP; doubled on each repeat ban.
+	pub duration: Duration,
+	/// Escalation cap, and the longest ban the `ban` verb accepts.
+	pub max_duration: Duration,
+	/// Never banned, automatically or by request.
+	pub allow: Vec<IpNet>,
+}
+
+impl Default for BanConfig {
+	fn default() -> Self {
+		Self {
+			strikes: 0,
+			client_errors: 0,
+			window: Duration::from_mins(1),
+			duration: Duration::from_mins(10),
+			max_duration: Duration::from_hours(24),
+			allow: vec![
+				IpNet::V4(Ipv4Net::new_assert(Ipv4Addr::new(127, 0, 0, 0), 8)),
+				IpNet::V6(Ipv6Net::new_assert(Ipv6Addr::LOCALHOST, 128)),
+			],
+		}
+	}
+}
+
+/// One kind of misbehaviour reported against a source IP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offense {
+	/// An L1 security limit hit (per-IP caps, rates, body idle).
+	Limit,
+	/// A `rate_limit` middleware rejection.
+	RateLimited,
+	/// A `4xx` response.
+	ClientError,
+}
+
+/// Who put a ban in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BanSource {
+	/// Offence thresholds in [`BanConfig`].
+	Auto,
+	/// The `ban` mgmt verb.
+	Manual,
+	/// A WASM plugin `ban` decision.
+	Plugin,
+}
+
+impl BanSource {
+	#[must_use]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Auto => "auto",
+			Self::Manual => "manual",
+			Self::Plugin => "plugin",
+		}
+	}
+}
+
+/// One active ban as reported by [`BanTable::snapshot`] and stored on
+/// disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanRecord {
+	/// Banned prefix, in CIDR notation (`/32` / `/128` for one host).
+	pub target: String,
+	/// Expiry, unix milliseconds.
+	pub until_ms: u64,
+	/// Escalation level: `0` for a first automatic ban, `n` for a ban
+	/// lasting `duration * 2^n`. Always `0` for manual and plugin bans.
+	pub level: u32,
+	pub reason: String,
+	pub source: BanSource,
+}
+
+/// [`BanTable::ban`] refused to ban a prefix overlapping the allow list.
+#[derive(Debug, thiserror::Error)]
+#[error("{target} overlaps allow-listed {allow}")]
+pub struct BanRefused {
+	pub target: IpNet,
+	pub allow: IpNet,
+}
+
+#[derive(Clone)]
+struct Ban {
+	until_ms: u64,
+	level: u32,
+	reason: String,
+	source: BanSource,
+}
+
+impl Ban {
+	fn record(&self, net: IpNet) -> BanRecord {
+		BanRecord {
+			target: net.to_string(),
+			until_ms: self.until_ms,
+			level: self.level,
+			reason: self.reason.clone(),
+			source: self.source,
+		}
+	}
+}
+
+/// Per-IP offence counters plus the number of automatic bans so far,
+/// which drives escalation.
+struct Offender {
+	window_start: Instant,
+	strikes: u32,
+	client_errors: u32,
+	bans: u32,
+	/// Expiry of the latest automatic ban; the record is kept for
+	/// `max_duration` past it so a quick repeat offender escalates.
+	last_until_ms: u64,
+}
+
+/// On-disk layout of `bans.json`.
+#[derive(Serialize, Deserialize)]
+struct BanFile {
+	bans: Vec<BanRecord>,
+}
+
+/// The ban table. Host bans (the automatic kind) live in a map keyed by
+/// address; wider prefixes (only ever set by request) in a short list
+/// that is scanned only when non-empty, so the accept-path check stays
+/// one map lookup.
+pub struct BanTable {
+	cfg: BanConfig,
+	hosts: DashMap<IpAddr, Ban>,
+	prefixes: RwLock<Vec<(IpNet, Ban)>>,
+	offenders: DashMap<IpAddr, Offender>,
+	/// Set on every change; cleared by a successful [`Self::save`].
+	dirty: AtomicBool,
+}
+
+impl BanTable {
+	#[must_use]
+	pub fn new(cfg: BanConfig) -> Self {
+		Self {
+			cfg,
+			hosts: DashMap::new(),
+			prefixes: RwLock::new(Vec::new()),
+			offenders: DashMap::new(),
+			dirty: AtomicBool::new(false),
+		}
+	}
+
+	#[must_use]
+	pub const fn config(&self) -> &BanConfig {
+		&self.cfg
+	}
+
+	/// Whether `ip` is under an unexpired ban.
+	#[must_use]
+	pub fn is_banned(&self, ip: IpAddr) -> bool {
+		let ip = ip.to_canonical();
+		let now = now_unix_ms();
+		if self.hosts.get(&ip).is_some_and(|b| b.until_ms > now) {
+			return true;
+		}
+		let prefixes = self.prefixes.read();
+		!prefixes.is_empty() && prefixes.iter().any(|(net, b)| b.until_ms > now && net.contains(&ip))
+	}
+
+	fn allowed(&self, net: &IpNet) -> Option<IpNet> {
+		self
+			.cfg
+			.allow
+			.iter()
+			.find(|a| a.contains(&net.network()) || net.contains(&a.network()))
+			.copied()
+	}
+
+	/// Count one `offense` by `ip`. Bans the IP once a non-zero
+	/// threshold of [`BanConfig`] is reached within the window; the
+	/// ban lasts `duration * 2^n` for the IP's `n`-th automatic ban,
+	/// capped at `max_duration`. Returns whether this call banned.
+	pub fn offend(&self, ip: IpAddr, offense: Offense) -> bool {
+		let threshold = match offense {
+			Offense::Limit | Offense::RateLimited => self.cfg.strikes,
+			Offense::ClientError => self.cfg.client_errors,
+		};
+		if threshold == 0 {
+			return false;
+		}
+		let ip = ip.to_canonical();
+		if ip.is_unspecified() || self.allowed(&IpNet::from(ip)).is_some() || self.is_banned(ip) {
+			return false;
+		}
+		let now = Instant::now();
+		let level = {
+			let mut o = self.offenders.entry(ip).or_insert_with(|| Offender {
+				window_start: now,
+				strikes: 0,
+				client_errors: 0,
+				bans: 0,
+				last_until_ms: 0,
+			});
+			if now.checked_duration_since(o.window_start).is_none_or(|d| d >= self.cfg.window) {
+				o.window_start = now;
+				o.strikes = 0;
+				o.client_errors = 0;
+			}
+			let count = match offense {
+				Offense::Limit | Offense::RateLimited => &mut o.strikes,
+				Offense::ClientError => &mut o.client_errors,
+			};
+			*count = count.saturating_add(1);
+			if *count < threshold {
+				return false;
+			}
+			o.strikes = 0;
+			o.client_errors = 0;
+			let level = o.bans;
+			o.bans = o.bans.saturating_add(1);
+			o.last_until_ms = now_unix_ms().saturating_add(duration_ms(self.escalated(level)));
+			level
+		};
+		let reason = match offense {
+			Offense::Limit | Offense::RateLimited => "strikes",
+			Offense::ClientError => "client_errors",
+		};
+		self.insert(IpNet::from(ip), self.escalated(level), level, reason.to_owned(), BanSource::Auto);
+		true
+	}
+
+	fn escalated(&self, level: u32) -> Duration {
+		let factor = 1u32.checked_shl(level).unwrap_or(u32::MAX);
+		self
+			.cfg
+			.duration
+			.checked_mul(factor)
+			.map_or(self.cfg.max_duration, |d| d.min(self.cfg.max_duration))
+	}
+
+	/// Ban `net` for `duration` (default: the configured first-ban
+	/// duration; capped at `max_duration`). Replaces any existing ban
+	/// of the same prefix.
+	///
+	/// # Errors
+	/// [`BanRefused`] when `net` overlaps the allow list.
+	pub fn ban(
+		&self,
+		net: IpNet,
+		duration: Option<Duration>,
+		reason: String,
+		source: BanSource,
+	) -> Result<BanRecord, BanRefused> {
+		let net = canonical_net(net);
+		if let Some(allow) = self.allowed(&net) {
+			return Err(BanRefused { target: net, allow });
+		}
+		let duration = duration.unwrap_or(self.cfg.duration).min(self.cfg.max_duration);
+		Ok(self.insert(net, duration, 0, reason, source))
+	}
+
+	fn insert(
+		&self,
+		net: IpNet,
+		duration: Duration,
+		level: u32,
+		reason: String,
+		source: BanSource,
+	) -> BanRecord {
+		let ban =
+			Ban { until_ms: now_unix_ms().saturating_add(duration_ms(duration)), level, reason, source };
+		let record = ban.record(net);
+		self.put(net, ban);
+		metrics::counter!("vane.security.bans_total", "source" => source.as_str()).increment(1);
+		tracing::warn!(
+			target = %net,
+			duration_secs = duration.as_secs(),
+			level,
+			reason = %record.reason,
+			source = source.as_str(),
+			"source banned"
+		);
+		record
+	}
+
+	fn put(&self, net: IpNet, ban: Ban) {
+		if is_host(&net) {
+			self.hosts.insert(net.addr(), ban);
+		} else {
+			let mut prefixes = self.prefixes.write();
+			prefixes.retain(|(n, _)| *n != net);
+			prefixes.push((net, ban));
+		}
+		self.dirty.store(true, Ordering::Release);
+	}
+
+	/// Lift the ban on exactly `net` (no prefix arithmetic: unbanning a
+	/// host inside a banned prefix is a no-op). A host also loses its
+	/// offence history, so its next automatic ban starts at level 0.
+	/// Returns whether a ban was removed.
+	pub fn unban(&self, net: IpNet) -> bool {
+		let net = canonical_net(net);
+		let removed = if is_host(&net) {
+			self.offenders.remove(&net.addr());
+			self.hosts.remove(&net.addr()).is_some()
+		} else {
+			let mut prefixes = self.prefixes.write();
+			let before = prefixes.len();
+			prefixes.retain(|(n, _)| *n != net);
+			prefixes.len() != before
+		};
+		if removed {
+			self.dirty.store(true, Ordering::Release);
+		}
+		removed
+	}
+
+	/// Unexpired bans, hosts first then prefixes, each sorted by target.
+	#[must_use]
+	pub fn snapshot(&self) -> Vec<BanRecord> {
+		let now = now_unix_ms();
+		let mut hosts: Vec<(IpAddr, BanRecord)> = self
+			.hosts
+			.iter()
+			.filter(|e| e.until_ms > now)
+			.map(|e| (*e.key(), e.value().record(IpNet::from(*e.key()))))
+			.collect();
+		hosts.sort_by_key(|(ip, _)| *ip);
+		let mut prefixes: Vec<(IpNet, BanRecord)> = self
+			.prefixes
+			.read()
+			.iter()
+			.filter(|(_, b)| b.until_ms > now)
+			.map(|(n, b)| (*n, b.record(*n)))
+			.collect();
+		prefixes.sort_by_key(|(n, _)| *n);
+		hosts.into_iter().map(|(_, r)| r).chain(prefixes.into_iter().map(|(_, r)| r)).collect()
+	}
+
+	/// Drop expired bans and offender records that no longer matter
+	/// for counting or escalation.
+	pub fn prune(&self) {
+		let now_ms = now_unix_ms();
+		let before = self.hosts.len();
+		self.hosts.retain(|_, b| b.until_ms > now_ms);
+		let mut changed = self.hosts.len() != before;
+		{
+			let mut prefixes = self.prefixes.write();
+			let before = prefixes.len();
+			prefixes.retain(|(_, b)| b.until_ms > now_ms);
+			changed |= prefixes.len() != before;
+		}
+		if changed {
+			self.dirty.store(true, Ordering::Release);
+		}
+		let now = Instant::now();
+		let keep_ms = duration_ms(self.cfg.max_duration);
+		self.offenders.retain(|_, o| {
+			now.checked_duration_since(o.window_start).is_none_or(|d| d < self.cfg.window)
+				|| o.last_until_ms.saturating_add(keep_ms) > now_ms
+		});
+	}
+
+	/// Write the unexpired bans to `path` (tmp file + `rename(2)`,
+	/// mode `0600`) if anything changed since the last save.
+	///
+	/// # Errors
+	/// I/O or serialisation failure; the table stays dirty so the next
+	/// call retries.
+	pub fn save(&self, path: &Path) -> std::io::Result<()> {
+		if !self.dirty.swap(false, Ordering::AcqRel) {
+			return Ok(());
+		}
+		let result = serde_json::to_vec_pretty(&BanFile { bans: self.snapshot() })
+			.map_err(std::io::Error::other)
+			.and_then(|bytes| atomic_write(path, &bytes));
+		if result.is_err() {
+			self.dirty.store(true, Ordering::Release);
+		}
+		result
+	}
+
+	/// Restore bans saved by [`Self::save`]. Expired, unparsable and
+	/// allow-listed entries are skipped; automatic bans keep their
+	/// escalation level. A missing file is an empty table. Returns the
+	/// number of bans restored.
+	///
+	/// # Errors
+	/// I/O failure other than `NotFound`, or a malformed file.
+	pub fn load(&self, path: &Path) -> std::io::Result<usize> {
+		let bytes = match std::fs::read(path) {
+			Ok(b) => b,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+			Err(e) => return Err(e),
+		};
+		let file: BanFile = serde_json::from_slice(&bytes).map_err(std::io::Error::other)?;
+		let now_ms = now_unix_ms();
+		let mut restored = 0;
+		for r in file.bans {
+			if r.until_ms <= now_ms {
+				continue;
+			}
+			let Some(net) = parse_target(&r.target) else {
+				tracing::warn!(target = %r.target, "skipping unparsable ban entry");
+				continue;
+			};
+			if self.allowed(&net).is_some() {
+				continue;
+			}
+			if r.source == BanSource::Auto && is_host(&net) {
+				self.offenders.insert(
+					net.addr(),
+					Offender {
+						window_start: Instant::now(),
+						strikes: 0,
+						client_errors: 0,
+						bans: r.level.saturating_add(1),
+						last_until_ms: r.until_ms,
+					},
+				);
+			}
+			self
+				.put(net, Ban { until_ms: r.until_ms, level: r.level, reason: r.reason, source: r.source });
+			restored += 1;
+		}
+		self.dirty.store(false, Ordering::Release);
+		Ok(restored)
+	}
+}
+
+/// Parse a ban target: CIDR notation, or a bare address as its host
+/// prefix.
+#[must_use]
+pub fn parse_target(s: &str) -> Option<IpNet> {
+	s.parse::<IpNet>().ok().or_else(|| s.parse::<IpAddr>().ok().map(IpNet::from)).map(canonical_net)
+}
+
+/// Truncate host bits and fold IPv4-mapped IPv6 hosts into IPv4, so a
+/// prefix has one spelling and dual-stack sockets match IPv4 bans.
+fn canonical_net(net: IpNet) -> IpNet {
+	let net = net.trunc();
+	if is_host(&net) { IpNet::from(net.addr().to_canonical()) } else { net }
+}
+
+fn is_host(net: &IpNet) -> bool {
+	net.prefix_len() == net.max_prefix_len()
+}
+
+fn duration_ms(d: Duration) -> u64 {
+	u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn atomic_write(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+	let parent = path.parent().ok_or_else(|| std::io::Error::other("path has no parent"))?;
+	let file_name = path.file_name().ok_or_else(|| std::io::Error::other("path has no file name"))?;
+	let tmp = parent.join(format!(".{}.tmp", file_name.to_string_lossy()));
+	{
+		let mut f =
+			OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&tmp)?;
+		f.write_all(bytes)?;
+		f.sync_all()?;
+	}
+	std::fs::rename(&tmp, path)?;
+	if let Ok(dir) = File::open(parent) {
+		let _ = dir.sync_all();
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn table(cfg: BanConfig) -> BanTable {
+		BanTable::new(cfg)
+	}
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().expect("ip")
+	}
+
+	fn net(s: &str) -> IpNet {
+		parse_target(s).expect("net")
+	}
+
+	#[test]
+	fn default_allow_list_is_loopback() {
+		let t = table(BanConfig::default());
+		assert!(t.ban(net("127.0.0.1"), None, String::new(), BanSource::Manual).is_err());
+		assert!(t.ban(net("::1"), None, String::new(), BanSource::Manual).is_err());
+		assert!(t.ban(net("198.51.100.1"), None, String::new(), BanSource::Manual).is_ok());
+	}
+
+	#[test]
+	fn strikes_threshold_bans_within_window() {
+		let t = table(BanConfig { strikes: 3, ..BanConfig::default() });
+		let a = ip("198.51.100.1");
+		assert!(!t.offend(a, Offense::Limit));
+		assert!(!t.offend(a, Offense::RateLimited));
+		assert!(!t.offend(a, Offense::ClientError), "client errors disabled");
+		assert!(!t.is_banned(a));
+		assert!(t.offend(a, Offense::Limit), "third strike");
+		assert!(t.is_banned(a));
+		assert!(!t.is_banned(ip("198.51.100.2")), "other IPs unaffected");
+		let snap = t.snapshot();
+		assert_eq!(snap.len(), 1);
+		assert_eq!(snap[0].target, "198.51.100.1/32");
+		assert_eq!(snap[0].source, BanSource::Auto);
+		assert_eq!(snap[0].reason, "strikes");
+	}
+
+	#[test]
+	fn repeat_bans_escalate_up_to_cap() {
+		let t = table(BanConfig {
+			client_errors: 1,
+			duration: Duration::from_secs(10),
+			max_duration: Duration::from_secs(25),
+			..BanConfig::default()
+		});
+		let a = ip("198.51.100.1");
+		let mut lengths = Vec::new();
+		for _ in 0..3 {
+			let start = now_unix_ms();
+			assert!(t.offend(a, Offense::ClientError));
+			let until = t.snapshot()[0].until_ms;
+			lengths.push((until - start + 500) / 1_000);
+			// Expire the ban by hand; escalation state stays.
+			t.hosts.alter(&a, |_, mut b| {
+				b.until_ms = 0;
+				b
+			});
+		}
+		assert_eq!(lengths, vec![10, 20, 25]);
+	}
+
+	#[test]
+	fn allow_listed_and_unspecified_sources_never_auto_ban() {
+		let t = table(BanConfig { strikes: 1, allow: vec![net("10.0.0.0/8")], ..BanConfig::default() });
+		assert!(!t.offend(ip("10.1.2.3"), Offense::Limit));
+		assert!(!t.offend(ip("0.0.0.0"), Offense::Limit));
+		assert!(t.snapshot().is_empty());
+		let err =
+			t.ban(net("10.0.0.0/16"), None, String::new(), BanSource::Manual).expect_err("inside");
+		assert_eq!(err.allow, net("10.0.0.0/8"));
+		assert!(t.ban(net("0.0.0.0/0"), None, String::new(), BanSource::Manual).is_err(), "covers");
+	}
+
+	#[test]
+	fn prefix_bans_match_hosts_and_mapped_addresses() {
+		let t = table(BanConfig::default());
+		t.ban(net("203.0.113.77/24"), None, "scan".into(), BanSource::Manual).expect("ban");
+		assert_eq!(t.snapshot()[0].target, "203.0.113.0/24", "host bits truncated");
+		assert!(t.is_banned(ip("203.0.113.9")));
+		assert!(t.is_banned(ip("::ffff:203.0.113.9")));
+		assert!(!t.is_banned(ip("203.0.114.9")));
+		assert!(!t.unban(net("203.0.113.9")), "exact match only");
+		assert!(t.unban(net("203.0.113.0/24")));
+		assert!(!t.is_banned(ip("203.0.113.9")));
+	}
+
+	#[test]
+	fn manual_duration_is_capped() {
+		let t = table(BanConfig { max_duration: Duration::from_mins(1), ..BanConfig::default() });
+		let start = now_unix_ms();
+		let r = t
+			.ban(net("198.51.100.1"), Some(Duration::from_hours(1)), String::new(), BanSource::Manual)
+			.expect("ban");
+		assert!(r.until_ms <= start + 61_000);
+	}
+
+	#[test]
+	fn save_and_load_round_trip() {
+		let dir = tempfile::tempdir().expect("tmp");
+		let path = dir.path().join("bans.json");
+		let t = table(BanConfig { strikes: 1, ..BanConfig::default() });
+		assert!(t.offend(ip("198.51.100.1"), Offense::Limit));
+		t.ban(net("2001:db8::/32"), None, "manual".into(), BanSource::Manual).expect("ban");
+		t.save(&path).expect("save");
+
+		let restored = table(BanConfig { strikes: 1, ..BanConfig::default() });
+		assert_eq!(restored.load(&path).expect("load"), 2);
+		assert_eq!(restored.snapshot(), t.snapshot());
+		assert!(restored.is_banned(ip("2001:db8::1")));
+		// Escalation survives the restart.
+		assert!(restored.unban(net("198.51.100.1")));
+		assert_eq!(restored.load(&path).expect("reload"), 2);
+		restored.hosts.alter(&ip("198.51.100.1"), |_, mut b| {
+			b.until_ms = 0;
+			b
+		});
+		assert!(restored.offend(ip("198.51.100.1"), Offense::Limit));
+		assert_eq!(restored.snapshot()[0].level, 1);
+
+		assert_eq!(table(BanConfig::default()).load(&dir.path().join("missing")).expect("ok"), 0);
+	}
+}
@@ -0,0 +1,201 @@
+//! Sockets inherited through systemd socket activation, keyed by the
+//! address (or Unix socket path) they are bound to. A listener whose
+//! compiled address matches an inherited socket serves on it instead of
+//! binding; every other listener falls back to `tokio-bind-retry`.
+//!
+//! The set keeps the original descriptors for the daemon's lifetime and
+//! hands each accept loop a `dup`. A listener torn down by reload drops
+//! only its duplicate, so the socket stays bound (TCP keeps queueing
+//! SYNs in its backlog) until a later reload brings the rule back.
+//!
+//! The same shape carries a binary upgrade: the replacement daemon
+//! receives [`HandoffSocket`]s from [`crate::ListenerSet::handoff_sockets`]
+//! over the mgmt socket and adds them here, so its listeners come up on
+//! the old daemon's sockets without a gap.
+//!
+//! See `spec/crates/engine.md` § _Socket activation_.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{self, SocketAddr};
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+
+use sd_listen_fds::ListenFd;
+use socket2::Type;
+use vane_core::Transport;
+
+/// A `dup` of one listening socket, offered to a replacement daemon.
+#[derive(Debug)]
+pub struct HandoffSocket {
+	pub transport: Transport,
+	/// `0.0.0.0:0` when `path` is set.
+	pub addr: SocketAddr,
+	/// Socket path of a Unix listener; `transport` is then `Tcp`.
+	pub path: Option<PathBuf>,
+	pub fd: OwnedFd,
+}
+
+/// Inherited listening sockets. Empty unless the daemon was started
+/// through a systemd `.socket` unit (or the set was filled by hand).
+#[derive(Debug, Default)]
+pub struct InheritedSockets {
+	tcp: HashMap<SocketAddr, net::TcpListener>,
+	udp: HashMap<SocketAddr, net::UdpSocket>,
+	unix: HashMap<PathBuf, UnixListener>,
+}
+
+impl InheritedSockets {
+	/// Take the sockets passed through `LISTEN_FDS`. Sockets that are
+	/// neither an IP stream, an IP datagram nor a Unix stream socket
+	/// bound to a path are logged and closed.
+	///
+	/// # Errors
+	///
+	/// A malformed `LISTEN_*` environment, or a passed descriptor that
+	/// is not open.
+	pub fn from_env() -> io::Result<Self> {
+		let mut set = Self::default();
+		for fd in sd_listen_fds::take()? {
+			set.adopt(fd);
+		}
+		Ok(set)
+	}
+
+	fn adopt(&mut self, ListenFd { socket, name }: ListenFd) {
+		let local = socket.local_addr().ok();
+		let addr = local.as_ref().and_then(socket2::SockAddr::as_socket);
+		let path = local.as_ref().and_then(|a| a.as_pathname().map(Path::to_path_buf));
+		let kind = socket.r#type().ok();
+		let added = match (addr, path, kind) {
+			(Some(_), _, Some(Type::STREAM)) => self.add_tcp(socket.into()).map(|a| a.to_string()),
+			(Some(_), _, Some(Type::DGRAM)) => self.add_udp(socket.into()).map(|a| a.to_string()),
+			(None, Some(_), Some(Type::STREAM)) => {
+				self.add_unix(socket.into()).map(|p| format!("unix:{}", p.display()))
+			}
+			_ => {
+				tracing::warn!(?name, ?addr, ?kind, "inherited fd is not a usable socket; closing it");
+				return;
+			}
+		};
+		match added {
+			Ok(addr) => tracing::info!(?name, %addr, "adopted inherited socket"),
+			Err(e) => tracing::warn!(?name, error = %e, "inherited socket rejected; closing it"),
+		}
+	}
+
+	/// Add a listening TCP socket. A second socket on the same address
+	/// replaces the first.
+	///
+	/// # Errors
+	///
+	/// `getsockname` failed.
+	pub fn add_tcp(&mut self, listener: net::TcpListener) -> io::Result<SocketAddr> {
+		let addr = listener.local_addr()?;
+		self.tcp.insert(addr, listener);
+		Ok(addr)
+	}
+
+	/// Add a bound UDP socket. A second socket on the same address
+	/// replaces the first.
+	///
+	/// # Errors
+	///
+	/// `getsockname` failed.
+	pub fn add_udp(&mut self, socket: net::UdpSocket) -> io::Result<SocketAddr> {
+		let addr = socket.local_addr()?;
+		self.udp.insert(addr, socket);
+		Ok(addr)
+	}
+
+	/// Add a listening Unix stream socket, keyed by its path. A second
+	/// socket on the same path replaces the first.
+	///
+	/// # Errors
+	///
+	/// `getsockname` failed, or the socket is not bound to a path.
+	pub fn add_unix(&mut self, listener: UnixListener) -> io::Result<PathBuf> {
+		let path = listener
+			.local_addr()?
+			.as_pathname()
+			.map(Path::to_path_buf)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unix socket has no path"))?;
+		self.unix.insert(path.clone(), listener);
+		Ok(path)
+	}
+
+	/// Every inherited Unix socket path, for boot-time logging.
+	pub fn unix_paths(&self) -> impl Iterator<Item = &Path> + '_ {
+		self.unix.keys().map(PathBuf::as_path)
+	}
+
+	/// Every inherited `(transport, addr)`, for boot-time logging.
+	pub fn iter(&self) -> impl Iterator<Item = (Transport, SocketAddr)> + '_ {
+		let tcp = self.tcp.keys().map(|a| (Transport::Tcp, *a));
+		let udp = self.udp.keys().map(|a| (Transport::Udp, *a));
+		tcp.chain(udp)
+	}
+
+	/// Whether nothing was inherited.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.tcp.is_empty() && self.udp.is_empty() && self.unix.is_empty()
+	}
+
+	/// A tokio listener on a `dup` of the inherited socket for `addr`.
+	/// `None` when nothing was inherited there. Must run inside a tokio
+	/// runtime.
+	pub(crate) fn tcp(&self, addr: &SocketAddr) -> Option<io::Result<tokio::net::TcpListener>> {
+		let listener = self.tcp.get(addr)?;
+		Some(listener.try_clone().and_then(|dup| {
+			dup.set_nonblocking(true)?;
+			tokio::net::TcpListener::from_std(dup)
+		}))
+	}
+
+	/// UDP counterpart of [`Self::tcp`].
+	pub(crate) fn udp(&self, addr: &SocketAddr) -> Option<io::Result<tokio::net::UdpSocket>> {
+		let socket = self.udp.get(addr)?;
+		Some(socket.try_clone().and_then(|dup| {
+			dup.set_nonblocking(true)?;
+			tokio::net::UdpSocket::from_std(dup)
+		}))
+	}
+
+	/// Unix counterpart of [`Self::tcp`], keyed by path.
+	pub(crate) fn unix(&self, path: &Path) -> Option<io::Result<tokio::net::UnixListener>> {
+		let listener = self.unix.get(path)?;
+		Some(listener.try_clone().and_then(|dup| {
+			dup.set_nonblocking(true)?;
+			tokio::net::UnixListener::from_std(dup)
+		}))
+	}
+
+	/// A `dup` of every held socket, for hand-off. Sockets whose `dup`
+	/// fails are logged and skipped.
+	pub(crate) fn handoff(&self) -> Vec<HandoffSocket> {
+		let tcp = self.tcp.iter().map(|(a, l)| (Transport::Tcp, *a, l.try_clone().map(OwnedFd::from)));
+		let udp = self.udp.iter().map(|(a, s)| (Transport::Udp, *a, s.try_clone().map(OwnedFd::from)));
+		let net = tcp.chain(udp).filter_map(|(transport, addr, fd)| match fd {
+			Ok(fd) => Some(HandoffSocket { transport, addr, path: None, fd }),
+			Err(e) => {
+				tracing::warn!(%addr, ?transport, error = %e, "dup of inherited socket failed");
+				None
+			}
+		});
+		let unix = self.unix.iter().filter_map(|(path, l)| match l.try_clone() {
+			Ok(dup) => Some(HandoffSocket {
+				transport: Transport::Tcp,
+				addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+				path: Some(path.clone()),
+				fd: OwnedFd::from(dup),
+			}),
+			Err(e) => {
+				tracing::warn!(?path, error = %e, "dup of inherited socket failed");
+				None
+			}
+		});
+		net.chain(unix).collect()
+	}
+}
@@ -0,0 +1,248 @@
+//! W3C Trace Context (`traceparent` / `tracestate`) glue between the
+//! engine's `tracing` spans and OpenTelemetry.
+//!
+//! The L7 drivers hand each request span to [`adopt_remote_parent`] so
+//! a trusted caller's trace continues through vane; `http_proxy` calls
+//! [`inject`] on every upstream attempt so the trace continues past
+//! it; the executor stamps [`trace_id`] onto the flow-log trajectory.
+//! Without the `otel` feature, or with no OTLP layer installed, every
+//! function is a no-op and inbound trace headers pass through
+//! untouched.
+//!
+//! See `spec/crates/engine.md` § _Distributed tracing_.
+
+use std::sync::Arc;
+
+/// Make the caller's `traceparent` in `headers` the OpenTelemetry
+/// parent of `span` when `peer` is a trusted propagation source
+/// ([`trust_sources`]). An untrusted caller's context is kept only as
+/// a link on a fresh root, so it can neither pick the trace id nor
+/// force sampling. A request without one (or with a malformed one)
+/// starts a fresh trace: the per-connection spans it nests under in
+/// `tracing` are not its OpenTelemetry parent, so each request on a
+/// kept-alive connection is its own trace.
+#[cfg(feature = "otel")]
+pub(crate) fn adopt_remote_parent(
+	span: &tracing::Span,
+	headers: &http::HeaderMap,
+	peer: std::net::IpAddr,
+) {
+	let peer = peer.to_canonical();
+	let trusted = otel::TRUSTED.get().is_some_and(|nets| nets.iter().any(|n| n.contains(&peer)));
+	adopt(span, headers, trusted);
+}
+
+#[cfg(not(feature = "otel"))]
+pub(crate) fn adopt_remote_parent(
+	_span: &tracing::Span,
+	_headers: &http::HeaderMap,
+	_peer: std::net::IpAddr,
+) {
+}
+
+#[cfg(feature = "otel")]
+fn adopt(span: &tracing::Span, headers: &http::HeaderMap, trusted: bool) {
+	use opentelemetry::propagation::TextMapPropagator as _;
+	use opentelemetry::trace::TraceContextExt as _;
+	use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+	if span.is_disabled() {
+		return;
+	}
+	let remote = otel::PROPAGATOR
+		.extract_with_context(&opentelemetry::Context::new(), &otel::HeaderExtractor(headers));
+	let parent = if trusted {
+		remote
+	} else {
+		let sc = remote.span().span_context().clone();
+		if sc.is_valid() {
+			span.add_link(sc);
+		}
+		opentelemetry::Context::new()
+	};
+	// Errs only when no OpenTelemetry layer is installed.
+	let _ = span.set_parent(parent);
+}
+
+/// Source CIDRs whose `traceparent` [`adopt_remote_parent`] continues
+/// (`VANE_OTEL_PROPAGATION_ALLOW`). Set once at boot; later calls are
+/// ignored. Until set, no source is trusted.
+#[cfg(feature = "otel")]
+pub(crate) fn trust_sources(nets: Vec<ipnet::IpNet>) {
+	let _ = otel::TRUSTED.set(nets);
+}
+
+/// Write `span`'s context into `headers` as `traceparent` /
+/// `tracestate`, replacing the caller's. Leaves `headers` alone when
+/// `span` has no exported context.
+#[cfg(feature = "otel")]
+pub(crate) fn inject(span: &tracing::Span, headers: &mut http::HeaderMap) {
+	use opentelemetry::propagation::TextMapPropagator as _;
+	use opentelemetry::trace::TraceContextExt as _;
+	use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+	if span.is_disabled() {
+		return;
+	}
+	let cx = span.context();
+	if !cx.span().span_context().is_valid() {
+		return;
+	}
+	otel::PROPAGATOR.inject_context(&cx, &mut otel::HeaderInjector(headers));
+}
+
+#[cfg(not(feature = "otel"))]
+pub(crate) fn inject(_span: &tracing::Span, _headers: &mut http::HeaderMap) {}
+
+/// Trace id of `span` as 32 lowercase hex digits, when the span is
+/// sampled for export. An unsampled trace never reaches the collector,
+/// so there is nothing to join a flow-log entry against.
+#[cfg(feature = "otel")]
+pub(crate) fn trace_id(span: &tracing::Span) -> Option<Arc<str>> {
+	use opentelemetry::trace::TraceContextExt as _;
+	use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+	if span.is_disabled() {
+		return None;
+	}
+	let cx = span.context();
+	let sc = cx.span().span_context().clone();
+	(sc.is_valid() && sc.is_sampled()).then(|| sc.trace_id().to_string().into())
+}
+
+#[cfg(not(feature = "otel"))]
+pub(crate) fn trace_id(_span: &tracing::Span) -> Option<Arc<str>> {
+	None
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+	use std::sync::{LazyLock, OnceLock};
+
+	use opentelemetry::propagation::{Extractor, Injector};
+	use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+	pub(super) static PROPAGATOR: LazyLock<TraceContextPropagator> =
+		LazyLock::new(TraceContextPropagator::new);
+
+	pub(super) static TRUSTED: OnceLock<Vec<ipnet::IpNet>> = OnceLock::new();
+
+	pub(super) struct HeaderExtractor<'a>(pub(super) &'a http::HeaderMap);
+
+	impl Extractor for HeaderExtractor<'_> {
+		fn get(&self, key: &str) -> Option<&str> {
+			self.0.get(key).and_then(|v| v.to_str().ok())
+		}
+
+		fn keys(&self) -> Vec<&str> {
+			self.0.keys().map(http::HeaderName::as_str).collect()
+		}
+	}
+
+	pub(super) struct HeaderInjector<'a>(pub(super) &'a mut http::HeaderMap);
+
+	impl Injector for HeaderInjector<'_> {
+		fn set(&mut self, key: &str, value: String) {
+			if let (Ok(name), Ok(value)) =
+				(http::HeaderName::from_bytes(key.as_bytes()), http::HeaderValue::from_str(&value))
+			{
+				self.0.insert(name, value);
+			}
+		}
+	}
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+	use opentelemetry::trace::TracerProvider as _;
+	use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+
+	const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+	fn subscriber(sampler: Sampler) -> impl tracing::Subscriber + Send + Sync {
+		let provider = SdkTracerProvider::builder().with_sampler(sampler).build();
+		tracing_subscriber::registry()
+			.with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")))
+	}
+
+	fn inbound(flags: &str) -> http::HeaderMap {
+		let mut headers = http::HeaderMap::new();
+		headers
+			.insert("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-{flags}").parse().unwrap());
+		headers
+	}
+
+	#[test]
+	fn remote_parent_continues_into_the_upstream_traceparent() {
+		tracing::subscriber::with_default(
+			subscriber(Sampler::ParentBased(Box::new(Sampler::AlwaysOff))),
+			|| {
+				let span = tracing::info_span!("request");
+				adopt(&span, &inbound("01"), true);
+				assert_eq!(
+					trace_id(&span).as_deref(),
+					Some(TRACE_ID),
+					"sampled parent wins over the ratio"
+				);
+
+				let attempt = tracing::debug_span!(parent: &span, "upstream_attempt");
+				let mut outbound = inbound("01");
+				inject(&attempt, &mut outbound);
+				let tp = outbound["traceparent"].to_str().unwrap();
+				assert!(tp.starts_with(&format!("00-{TRACE_ID}-")), "{tp}");
+				assert!(!tp.contains("00f067aa0ba902b7"), "attempt span is the new parent: {tp}");
+				assert!(tp.ends_with("-01"), "{tp}");
+			},
+		);
+	}
+
+	#[test]
+	fn missing_or_unsampled_traceparent() {
+		tracing::subscriber::with_default(
+			subscriber(Sampler::ParentBased(Box::new(Sampler::AlwaysOn))),
+			|| {
+				let fresh = tracing::info_span!("request");
+				adopt(&fresh, &http::HeaderMap::new(), true);
+				let id = trace_id(&fresh).expect("root span is sampled");
+				assert_eq!(id.len(), 32);
+				assert_ne!(&*id, TRACE_ID);
+
+				let unsampled = tracing::info_span!("request");
+				adopt(&unsampled, &inbound("00"), true);
+				assert_eq!(trace_id(&unsampled), None, "caller's sampled=0 is followed");
+			},
+		);
+	}
+
+	#[test]
+	fn untrusted_source_starts_a_new_root() {
+		tracing::subscriber::with_default(
+			subscriber(Sampler::ParentBased(Box::new(Sampler::AlwaysOff))),
+			|| {
+				let span = tracing::info_span!("request");
+				adopt_remote_parent(&span, &inbound("01"), "192.0.2.7".parse().unwrap());
+				assert_eq!(trace_id(&span), None, "caller's sampled=1 does not override the ratio");
+			},
+		);
+		tracing::subscriber::with_default(
+			subscriber(Sampler::ParentBased(Box::new(Sampler::AlwaysOn))),
+			|| {
+				let span = tracing::info_span!("request");
+				adopt(&span, &inbound("00"), false);
+				let id = trace_id(&span).expect("caller's sampled=0 is not followed either");
+				assert_ne!(&*id, TRACE_ID);
+			},
+		);
+	}
+
+	#[test]
+	fn no_layer_leaves_headers_alone() {
+		let span = tracing::info_span!("request");
+		let headers = inbound("01");
+		adopt_remote_parent(&span, &headers, "127.0.0.1".parse().unwrap());
+		assert_eq!(trace_id(&span), None);
+		let mut outbound = headers.clone();
+		inject(&span, &mut outbound);
+		assert_eq!(outbound, headers);
+	}
+}
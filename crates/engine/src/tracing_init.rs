@@ -2,5 +2,108 @@
 //! `tokio::sync::broadcast` fan-out consumed by the management API's
 //! streaming verbs (`tail_flow`, `tail_log`).
 //!
-//! See `spec/crates/core.md` § _Error type_ and
-//! `spec/crates/mgmt.md` § _Streaming verb lifecycle_.
+//! With the `otel` feature, [`otel_layer`] builds the OpenTelemetry
+//! layer the daemon stacks onto its subscriber: engine spans exported
+//! over OTLP to `VANE_OTEL_ENDPOINT`, head-sampled at
+//! `VANE_OTEL_SAMPLE_RATIO`.
+//!
+//! See `spec/crates/core.md` § _Error type_,
+//! `spec/crates/mgmt.md` § _Streaming verb lifecycle_ and
+//! `spec/crates/engine.md` § _Distributed tracing_.
+
+#[cfg(feature = "otel")]
+pub use otel::{OtelGuard, OtelLayer, otel_layer};
+
+#[cfg(feature = "otel")]
+mod otel {
+	use opentelemetry::trace::TracerProvider as _;
+	use opentelemetry_otlp::{SpanExporter, WithExportConfig as _};
+	use opentelemetry_sdk::Resource;
+	use opentelemetry_sdk::runtime::Tokio;
+	use opentelemetry_sdk::trace::span_processor_with_async_runtime::BatchSpanProcessor;
+	use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+	use tracing::Subscriber;
+	use tracing_subscriber::Layer;
+	use tracing_subscriber::filter::filter_fn;
+	use tracing_subscriber::registry::LookupSpan;
+	use vane_core::{Env, OtlpProtocol};
+
+	/// Instrumentation scope name on every exported span.
+	const TRACER_NAME: &str = "vane";
+
+	/// The export layer, boxed so the daemon can stack it without
+	/// naming the filtered tracer type.
+	pub type OtelLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+	/// Keeps the tracer provider alive for the daemon's lifetime.
+	/// Dropping it without [`OtelGuard::shutdown`] loses the spans still
+	/// queued in the batch processor.
+	#[derive(Debug)]
+	pub struct OtelGuard {
+		provider: SdkTracerProvider,
+	}
+
+	impl OtelGuard {
+		/// Flush queued spans to the collector and stop the exporter.
+		/// The SDK blocks on the export, so it runs off the async
+		/// workers.
+		pub async fn shutdown(self) {
+			let provider = self.provider;
+			let res = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+			match res {
+				Ok(Ok(())) => {}
+				Ok(Err(e)) => tracing::warn!(error = %e, "otel exporter shutdown failed"),
+				Err(e) => tracing::warn!(error = %e, "otel exporter shutdown task failed"),
+			}
+		}
+	}
+
+	/// OTLP export layer for `env`, or `None` when `VANE_OTEL_ENDPOINT`
+	/// is unset. Exports the engine's spans (`request`, `walk`,
+	/// `middleware`, `fetch`, `upstream_attempt`, `tls_handshake`, ...)
+	/// and attaches `WARN` / `ERROR` events to them; everything else
+	/// stays on the fmt and broadcast layers only.
+	///
+	/// Must run inside the tokio runtime: the batch processor and the
+	/// gRPC channel spawn onto it.
+	///
+	/// # Errors
+	/// The endpoint does not parse as a URI for the selected transport.
+	pub fn otel_layer<S>(
+		env: &Env,
+	) -> Result<Option<(OtelLayer<S>, OtelGuard)>, opentelemetry_otlp::ExporterBuildError>
+	where
+		S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+	{
+		let Some(endpoint) = env.otel_endpoint.as_deref() else {
+			return Ok(None);
+		};
+		crate::trace_propagation::trust_sources(env.otel_propagation_allow.clone());
+		let exporter = match env.otel_protocol {
+			// An explicit endpoint is used verbatim by the HTTP exporter;
+			// the operator names the collector, vane appends the signal path.
+			OtlpProtocol::HttpProtobuf => SpanExporter::builder()
+				.with_http()
+				.with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+				.build()?,
+			OtlpProtocol::Grpc => SpanExporter::builder().with_tonic().with_endpoint(endpoint).build()?,
+		};
+		let provider = SdkTracerProvider::builder()
+			.with_span_processor(BatchSpanProcessor::builder(exporter, Tokio).build())
+			.with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+				env.otel_sample_ratio,
+			))))
+			.with_resource(Resource::builder().with_service_name(env.otel_service_name.clone()).build())
+			.build();
+		let layer = tracing_opentelemetry::layer()
+			.with_tracer(provider.tracer(TRACER_NAME))
+			.with_filter(filter_fn(|meta| {
+				if meta.is_span() {
+					meta.target().starts_with("vane_engine") && *meta.level() <= tracing::Level::DEBUG
+				} else {
+					*meta.level() <= tracing::Level::WARN
+				}
+			}));
+		Ok(Some((Box::new(layer), OtelGuard { provider })))
+	}
+}
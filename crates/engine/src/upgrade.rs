@@ -20,13 +20,17 @@ use tokio::io::{AsyncRead, AsyncWrite, copy_bidirectional};
 use tokio_util::sync::CancellationToken;
 use vane_core::{
 	Body, ConnContext, Error, FlowCtx, FlowLogSink, FlowLogVerbosity, HttpVersion, NodeId, Request,
-	Response, TrajectoryBuilder,
+	Response, StreamGuards, TrajectoryBuilder,
 };
 
 use crate::body_adapter::IncomingAdapter;
 use crate::executor::{ExecutorInput, ExecutorOutput, execute};
 use crate::fetch::websocket_upgrade::StashedUpstreamUpgrade;
 use crate::flow_graph::FlowGraph;
+use crate::request_metrics::RequestMeter;
+use crate::security::{
+	BodyIdleFlag, ConnFloor, Offense, OffenseRecorded, StreamLimits, StreamRefusal,
+};
 use crate::time::now_unix_ms;
 
 /// Stash for the `JoinHandle` of a post-101 WebSocket byte-tunnel.
@@ -57,6 +61,41 @@ impl PendingUpgradeTask {
 	}
 }
 
+/// Bound the client request body by the L1 `body_idle_timeout` when
+/// the connection carries a [`ConnFloor`]. The flag, when present,
+/// tells the driver to answer `408` once the executor returns.
+fn bound_request_body(floor: Option<&ConnFloor>, req: Request) -> (Request, Option<BodyIdleFlag>) {
+	let Some(floor) = floor else {
+		return (req, None);
+	};
+	let (parts, body) = req.into_parts();
+	let (body, flag) = floor.bound_request_body(body);
+	(Request::from_parts(parts, body), Some(flag))
+}
+
+/// Count a `4xx` answer against the client's source in the ban table,
+/// unless a middleware already reported it ([`OffenseRecorded`]).
+fn note_client_error<B>(floor: Option<&ConnFloor>, response: &http::Response<B>) {
+	if response.status().is_client_error()
+		&& response.extensions().get::<OffenseRecorded>().is_none()
+		&& let Some(floor) = floor
+	{
+		floor.offend(Offense::ClientError);
+	}
+}
+
+/// `408 Request Timeout` for a request whose body idled out. Whatever
+/// the executor made of the truncated body (a `502` from a failed
+/// upstream write, a `500`) is replaced: the client is the party that
+/// stalled.
+fn body_idle_response(close: bool) -> Response {
+	let mut b = http::Response::builder().status(http::StatusCode::REQUEST_TIMEOUT);
+	if close {
+		b = b.header("connection", "close");
+	}
+	b.body(Body::Empty).expect("static 408")
+}
+
 /// Drive a byte stream as an H1 server. For each decoded request, build a
 /// fresh `FlowCtx` (sharing `log` / `cancel` / `verbosity` from the outer
 /// L4 ctx, with its own `TrajectoryBuilder`) and call the executor with
@@ -119,8 +158,10 @@ where
 	// move-into-closure capture. The service-fn itself clones from
 	// the inner shadow `conn` per request.
 	let conn_outer = Arc::clone(&conn);
+	let floor = ConnFloor::of(&conn);
 	let svc = service_fn(move |mut req: hyper::Request<Incoming>| {
 		let graph = Arc::clone(&graph);
+		let floor = floor.clone();
 		let conn = Arc::clone(&conn);
 		let log = Arc::clone(&log);
 		let cancel = cancel.clone();
@@ -153,8 +194,11 @@ where
 			// dropped-future no-op.
 			let client_on_upgrade = hyper::upgrade::on(&mut req);
 
-			let vane_req: Request =
-				req.map(|incoming| Body::Stream(Box::pin(IncomingAdapter::new(incoming))));
+			let (vane_req, body_idle) = bound_request_body(
+				floor.as_ref(),
+				req.map(|incoming| Body::Stream(Box::pin(IncomingAdapter::new(incoming)))),
+			);
+			let (vane_req, meter) = RequestMeter::begin(vane_req);
 
 			// URI path is intentionally absent from this INFO span:
 			// path commonly carries tokens (verify / reset / OAuth
@@ -171,6 +215,7 @@ where
 				conn = %conn.id,
 				method = %vane_req.method(),
 			);
+			crate::trace_propagation::adopt_remote_parent(&span, vane_req.headers(), conn.remote.ip());
 
 			// Keep a separate clone for the post-101 WS-tunnel spawn
 			// below — `FlowCtx::cancel` moves the original into the
@@ -187,8 +232,13 @@ where
 
 			let result =
 				execute(&graph, l7_entry, ExecutorInput::L7(Box::new(vane_req)), &conn, &mut ctx).await;
+			// The body stalled mid-request: the connection's framing is
+			// unrecoverable, so close it behind the 408.
+			if body_idle.as_ref().is_some_and(BodyIdleFlag::expired) {
+				return Ok::<Response, std::convert::Infallible>(meter.finish(body_idle_response(true)));
+			}
 
-			match result {
+			let response = match result {
 				Ok(ExecutorOutput::HttpResponse(r))
 					if r.status() == http::StatusCode::SWITCHING_PROTOCOLS =>
 				{
@@ -255,7 +305,10 @@ where
 					conn.user.lock().insert(PendingUpgradeTask::new(tunnel_handle));
 					Ok(r)
 				}
-				Ok(ExecutorOutput::HttpResponse(r)) => Ok::<Response, std::convert::Infallible>(r),
+				Ok(ExecutorOutput::HttpResponse(r)) => {
+					note_client_error(floor.as_ref(), &r);
+					Ok::<Response, std::convert::Infallible>(r)
+				}
 				Ok(ExecutorOutput::Closed) => {
 					// L7 path ended via Terminate(Close) without producing a
 					// Response. The L4 analogue is TCP RST; the L7 analogue
@@ -277,16 +330,16 @@ where
 					// keeps the choice future-proof when H2 / H3 driver
 					// siblings land.
 					let status = match conn.http_version.get() {
-						Some(HttpVersion::Http2 | HttpVersion::Http3) => 421,
-						_ => 404,
+						Some(HttpVersion::Http2 | HttpVersion::Http3) => http::StatusCode::MISDIRECTED_REQUEST,
+						_ => http::StatusCode::NOT_FOUND,
 					};
-					Ok(
-						http::Response::builder()
-							.status(status)
-							.header("connection", "close")
-							.body(Body::Empty)
-							.expect("static"),
-					)
+					let response = http::Response::builder()
+						.status(status)
+						.header("connection", "close")
+						.body(Body::Empty)
+						.expect("static");
+					note_client_error(floor.as_ref(), &response);
+					Ok(response)
 				}
 				Ok(ExecutorOutput::Tunneled) => {
 					// WS-101 lands here; not in MVP scope. Surface a 500 so
@@ -298,7 +351,8 @@ where
 					tracing::warn!(error = %e, "L7 execute returned Err — synthesising 500");
 					Ok(http::Response::builder().status(500).body(Body::Empty).expect("static"))
 				}
-			}
+			};
+			response.map(|r| meter.finish(r))
 		}
 	});
 
@@ -413,7 +467,8 @@ where
 /// inside the service-fn so the connection itself stays alive.
 #[allow(
 	clippy::too_many_arguments,
-	reason = "h2 driver wiring: same shape as h1; bag struct would just rename the noise"
+	clippy::too_many_lines,
+	reason = "h2 driver wiring: same shape as h1; bag struct would just rename the noise, and the length is the service_fn closure plus the L1 stream-floor admission"
 )]
 pub(crate) fn drive_h2_server<S>(
 	stream: S,
@@ -446,15 +501,48 @@ where
 		// `svc` keeps its own clones for per-request executor wiring.
 		let conn_cancel = cancel.clone();
 		let conn_accept_cancel = accept_cancel.clone();
+		// L1 stream floor. `overload` fires when a client reaches twice
+		// the per-connection request rate; the select below answers it
+		// with GOAWAY.
+		let floor = ConnFloor::of(&conn);
+		let stream_limits = floor.as_ref().map(ConnFloor::stream_limits);
+		let max_streams = graph.security_cfg().max_in_flight_streams_per_conn;
+		let overload = CancellationToken::new();
+		let conn_overload = overload.clone();
 		let svc = service_fn(move |req: hyper::Request<Incoming>| {
 			let graph = Arc::clone(&graph);
+			let floor = floor.clone();
+			let stream_limits = stream_limits.clone();
+			let overload = overload.clone();
 			let conn = Arc::clone(&conn);
 			let log = Arc::clone(&log);
 			let cancel = cancel.clone();
 			let accept_cancel = accept_cancel.clone();
 			async move {
-				let vane_req: Request =
-					req.map(|incoming| Body::Stream(Box::pin(IncomingAdapter::new(incoming))));
+				// A service error carrying an `h2::Reason` is how hyper
+				// is told to reset one stream: it answers with
+				// `RST_STREAM(REFUSED_STREAM)`, which clients may retry.
+				let stream_guard = match stream_limits.as_ref().map(StreamLimits::admit) {
+					None => None,
+					Some(Ok(guard)) => Some(guard),
+					Some(Err(refusal)) => {
+						if refusal == StreamRefusal::Connection {
+							overload.cancel();
+						}
+						return Err(h2::Error::from(h2::Reason::REFUSED_STREAM));
+					}
+				};
+
+				let (mut vane_req, body_idle) = bound_request_body(
+					floor.as_ref(),
+					req.map(|incoming| Body::Stream(Box::pin(IncomingAdapter::new(incoming)))),
+				);
+				// The in-flight slot rides the upstream response body, as
+				// `concurrency_limit` permits do.
+				if let Some(guard) = stream_guard {
+					StreamGuards::push(&mut vane_req, guard);
+				}
+				let (vane_req, meter) = RequestMeter::begin(vane_req);
 
 				// `path` intentionally absent — see the H1 driver's
 				// span comment above for the PII rationale.
@@ -464,6 +552,7 @@ where
 					version = "h2",
 					method = %vane_req.method(),
 				);
+				crate::trace_propagation::adopt_remote_parent(&span, vane_req.headers(), conn.remote.ip());
 
 				let mut ctx = FlowCtx {
 					span,
@@ -476,8 +565,11 @@ where
 
 				let result =
 					execute(&graph, l7_entry, ExecutorInput::L7(Box::new(vane_req)), &conn, &mut ctx).await;
+				if body_idle.as_ref().is_some_and(BodyIdleFlag::expired) {
+					return Ok::<Response, h2::Error>(meter.finish(body_idle_response(false)));
+				}
 
-				match result {
+				let response = match result {
 					Ok(ExecutorOutput::HttpResponse(r))
 						if r.status() == http::StatusCode::SWITCHING_PROTOCOLS =>
 					{
@@ -490,18 +582,23 @@ where
 							conn_id = %conn.id,
 							"h2 service-fn received 101 from executor; synthesising 500 (WS-over-h2 unsupported)",
 						);
-						Ok::<Response, std::convert::Infallible>(
+						Ok::<Response, h2::Error>(
 							http::Response::builder().status(500).body(Body::Empty).expect("static"),
 						)
 					}
-					Ok(ExecutorOutput::HttpResponse(r)) => Ok::<Response, std::convert::Infallible>(r),
+					Ok(ExecutorOutput::HttpResponse(r)) => {
+						note_client_error(floor.as_ref(), &r);
+						Ok::<Response, h2::Error>(r)
+					}
 					Ok(ExecutorOutput::Closed) => {
 						// L7 no-route in h2 land — 421 Misdirected Request
 						// is the semantically accurate match (RFC 9110
 						// § 15.5.20). Mirrors the H1 driver's 404, but
 						// uses h2-native semantics so clients can retry
 						// against a different authority.
-						Ok(http::Response::builder().status(421).body(Body::Empty).expect("static"))
+						let response = http::Response::builder().status(421).body(Body::Empty).expect("static");
+						note_client_error(floor.as_ref(), &response);
+						Ok(response)
 					}
 					Ok(ExecutorOutput::Tunneled) => {
 						tracing::warn!("L7 tunnel terminator over h2 unsupported — synthesising 500");
@@ -511,14 +608,21 @@ where
 						tracing::warn!(error = %e, "L7 execute returned Err — synthesising 500");
 						Ok(http::Response::builder().status(500).body(Body::Empty).expect("static"))
 					}
-				}
+				};
+				response.map(|r| meter.finish(r))
 			}
 		});
 
 		let io = TokioIo::new(stream);
-		let server_conn =
-			hyper::server::conn::http2::Builder::new(hyper_util::rt::TokioExecutor::new())
-				.serve_connection(io, svc);
+		// `max_concurrent_streams` at twice the L1 in-flight cap: the
+		// codec-level hard bound sits above the cap so streams past it
+		// still reach the admission check above and are refused (and
+		// counted) there.
+		let server_conn = {
+			let mut b = hyper::server::conn::http2::Builder::new(hyper_util::rt::TokioExecutor::new());
+			b.max_concurrent_streams(u32::try_from(max_streams).unwrap_or(u32::MAX).saturating_mul(2));
+			b.serve_connection(io, svc)
+		};
 		tokio::pin!(server_conn);
 
 		// H2 graceful_shutdown sends `GOAWAY` and waits for in-flight
@@ -533,6 +637,9 @@ where
 		// - `conn_cancel` (force_cancel): final hammer; re-await once
 		//   so any still-in-flight stream finishes against the
 		//   hard-cancelled FlowCtx.
+		// - `conn_overload` (L1 request-rate floor): the client hit
+		//   twice the per-connection rate; `GOAWAY` stops it opening
+		//   more streams while the admitted ones finish.
 		let outcome = tokio::select! {
 			biased;
 			result = server_conn.as_mut() => result,
@@ -544,6 +651,10 @@ where
 				server_conn.as_mut().graceful_shutdown();
 				server_conn.as_mut().await
 			}
+			() = conn_overload.cancelled() => {
+				server_conn.as_mut().graceful_shutdown();
+				server_conn.as_mut().await
+			}
 		};
 		outcome.map_err(|e| Error::protocol("h2 serve_connection").with_source(e))?;
 
@@ -562,6 +673,11 @@ where
 /// pre-populates `ConnContext.transport = Udp` and `http_version =
 /// Http3`.
 #[cfg(feature = "h3")]
+#[allow(
+	clippy::too_many_arguments,
+	clippy::too_many_lines,
+	reason = "h3 driver wiring: same shape as h1 / h2 plus the daemon's L1 state, which the TCP drivers find through the listener-installed `ConnFloor`; the length is the stream accept loop"
+)]
 pub(crate) async fn drive_h3_server(
 	listener_addr: std::net::SocketAddr,
 	quic_conn: quinn::Connection,
@@ -570,6 +686,7 @@ pub(crate) async fn drive_h3_server(
 	accept_cancel: CancellationToken,
 	force_cancel: CancellationToken,
 	verbosity: Arc<crate::verbosity::VerbosityState>,
+	security: &Arc<crate::security::SecurityState>,
 ) {
 	// Alias for the existing per-stream FlowCtx wiring below — every
 	// stream still binds its `FlowCtx::cancel` to `force_cancel`, so
@@ -591,7 +708,13 @@ pub(crate) async fn drive_h3_server(
 		..vane_core::TlsInfo::default()
 	});
 	let _ = conn.http_version.set(HttpVersion::Http3);
+	ConnFloor::install(&conn, security);
+	let floor = ConnFloor::of(&conn);
+	let stream_limits = floor.as_ref().map(ConnFloor::stream_limits);
 
+	// Kept for the L1 request-rate floor's connection close; the h3
+	// wrapper takes ownership of the original.
+	let overload_conn = quic_conn.clone();
 	let h3_quic_conn = h3_quinn::Connection::new(quic_conn);
 	let mut h3_conn = match h3::server::Connection::new(h3_quic_conn).await {
 		Ok(c) => c,
@@ -618,13 +741,29 @@ pub(crate) async fn drive_h3_server(
 			accepted = h3_conn.accept() => {
 				match accepted {
 					Ok(Some(resolver)) => {
-						let (req, stream) = match resolver.resolve_request().await {
+						let (req, mut stream) = match resolver.resolve_request().await {
 							Ok(t) => t,
 							Err(e) => {
 								tracing::debug!(error = %e, conn_id = %conn.id, "h3 resolve_request failed");
 								continue;
 							}
 						};
+						let stream_guard = match stream_limits.as_ref().map(StreamLimits::admit) {
+							None => None,
+							Some(Ok(guard)) => Some(guard),
+							Some(Err(StreamRefusal::Stream)) => {
+								let code = h3::error::Code::H3_REQUEST_REJECTED;
+								stream.stop_sending(code);
+								stream.stop_stream(code);
+								continue;
+							}
+							Some(Err(StreamRefusal::Connection)) => {
+								let code = h3::error::Code::H3_EXCESSIVE_LOAD;
+								let code = quinn::VarInt::from_u64(code.value()).unwrap_or_default();
+								overload_conn.close(code, b"request rate");
+								return;
+							}
+						};
 						let graph_snap = graph.load_full();
 						let Some(listener_entry) =
 							graph_snap.symbolic().entries.get(&listener_addr).copied()
@@ -665,6 +804,8 @@ pub(crate) async fn drive_h3_server(
 							cancel: cancel.clone(),
 							accept_cancel: accept_cancel.clone(),
 							verbosity: verbosity.current(),
+							stream_guard,
+							floor: floor.clone(),
 						};
 						tokio::spawn(handle_h3_request(req, stream, sctx));
 					}
@@ -693,6 +834,9 @@ pub(crate) struct H3StreamCtx {
 	pub cancel: CancellationToken,
 	pub accept_cancel: CancellationToken,
 	pub verbosity: vane_core::FlowLogVerbosity,
+	/// L1 in-flight slot, held until the response is written.
+	pub stream_guard: Option<crate::security::StreamGuard>,
+	pub floor: Option<ConnFloor>,
 }
 
 /// Per-stream handler — runs the executor then writes the response
@@ -715,7 +859,17 @@ async fn handle_h3_request(
 	sctx: H3StreamCtx,
 ) {
 	use http_body::Body as _;
-	let H3StreamCtx { graph, entry, conn, log, cancel, accept_cancel, verbosity } = sctx;
+	let H3StreamCtx {
+		graph,
+		entry,
+		conn,
+		log,
+		cancel,
+		accept_cancel,
+		verbosity,
+		stream_guard: _stream_guard,
+		floor,
+	} = sctx;
 	let (mut parts, _empty) = req.into_parts();
 
 	// `h3` sets `parts.version = HTTP/3.0`. The L7 executor + middleware
@@ -738,7 +892,9 @@ async fn handle_h3_request(
 	let (mut send_stream, recv_stream) = stream.split();
 	let body =
 		Body::from_producer(h3_body::H3Body::new(h3_body::ServerStreamSource::new(recv_stream)));
-	let vane_req: Request = http::Request::from_parts(parts, body);
+	let (vane_req, body_idle) =
+		bound_request_body(floor.as_ref(), http::Request::from_parts(parts, body));
+	let (vane_req, meter) = RequestMeter::begin(vane_req);
 
 	// `path` intentionally absent — see the H1 driver's span comment
 	// above for the PII rationale.
@@ -747,6 +903,7 @@ async fn handle_h3_request(
 		conn = %conn.id,
 		method = %vane_req.method(),
 	);
+	crate::trace_propagation::adopt_remote_parent(&span, vane_req.headers(), conn.remote.ip());
 	let mut ctx = FlowCtx {
 		span,
 		log,
@@ -760,6 +917,7 @@ async fn handle_h3_request(
 		execute(&graph, entry, ExecutorInput::L7(Box::new(vane_req)), &conn, &mut ctx).await;
 
 	let response = match exec_out {
+		_ if body_idle.as_ref().is_some_and(BodyIdleFlag::expired) => body_idle_response(false),
 		Ok(ExecutorOutput::HttpResponse(r)) => r,
 		Ok(ExecutorOutput::Closed) => {
 			http::Response::builder().status(421).body(Body::Empty).expect("static 421")
@@ -773,8 +931,9 @@ async fn handle_h3_request(
 			http::Response::builder().status(500).body(Body::Empty).expect("static 500")
 		}
 	};
+	note_client_error(floor.as_ref(), &response);
 
-	let (rparts, mut rbody) = response.into_parts();
+	let (rparts, mut rbody) = meter.finish(response).into_parts();
 	let resp_for_h3 = http::Response::from_parts(rparts, ());
 	if let Err(e) = send_stream.send_response(resp_for_h3).await {
 		tracing::debug!(error = %e, conn_id = %conn.id, "h3 send_response failed");
@@ -18,7 +18,9 @@ use std::collections::HashSet;
 use std::sync::{Mutex, OnceLock};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use vane_core::{ConnContext, ContextEntry, ContextValue, TlsInfo, TlsVersion, Transport};
+use vane_core::{
+	ConnContext, ContextEntry, ContextValue, ProxySslInfo, TlsInfo, TlsVersion, Transport,
+};
 
 /// Pack the inspects-declared paths into a `Vec<ContextEntry>`. Any
 /// declared connection-level path produces an entry — absent sources
@@ -80,6 +82,9 @@ fn pack_one(path: &str, conn: &ConnContext, tls: Option<&TlsInfo>) -> Option<Con
 		"conn.tls.sni" => ContextValue::Text(
 			tls.and_then(|t| t.sni.as_deref()).map(str::to_ascii_lowercase).unwrap_or_default(),
 		),
+		"conn.tls.ja4" => {
+			ContextValue::Text(tls.and_then(|t| t.ja4.as_deref()).unwrap_or_default().to_owned())
+		}
 
 		"conn.tls.peer_cert" => ContextValue::Bytes(
 			tls.and_then(|t| t.peer_cert.as_ref()).map(|c| c.leaf_der.to_vec()).unwrap_or_default(),
@@ -124,6 +129,27 @@ fn pack_one(path: &str, conn: &ConnContext, tls: Option<&TlsInfo>) -> Option<Con
 				.unwrap_or_default(),
 		),
 
+		"conn.proxy.present" => ContextValue::Boolean(conn.proxy.get().is_some()),
+		"conn.proxy.peer_ip" => {
+			ContextValue::Text(conn.proxy.get().map(|p| p.peer.ip().to_string()).unwrap_or_default())
+		}
+		"conn.proxy.authority" => ContextValue::Text(
+			conn.proxy.get().and_then(|p| p.authority.as_deref()).unwrap_or_default().to_owned(),
+		),
+		"conn.proxy.unique_id" => ContextValue::Bytes(
+			conn.proxy.get().and_then(|p| p.unique_id.as_ref()).map(|b| b.to_vec()).unwrap_or_default(),
+		),
+		"conn.proxy.ssl.version" => ContextValue::Text(
+			proxy_ssl(conn).and_then(|s| s.version.as_deref()).unwrap_or_default().to_owned(),
+		),
+		"conn.proxy.ssl.cn" => ContextValue::Text(
+			proxy_ssl(conn).and_then(|s| s.cn.as_deref()).unwrap_or_default().to_owned(),
+		),
+		"conn.proxy.ssl.cipher" => ContextValue::Text(
+			proxy_ssl(conn).and_then(|s| s.cipher.as_deref()).unwrap_or_default().to_owned(),
+		),
+		"conn.proxy.ssl.verified" => ContextValue::Boolean(proxy_ssl(conn).is_some_and(|s| s.verified)),
+
 		// Grammar-valid request / response path — load-time validation
 		// admitted it but the host pack path defers them.
 		_ => return None,
@@ -131,6 +157,10 @@ fn pack_one(path: &str, conn: &ConnContext, tls: Option<&TlsInfo>) -> Option<Con
 	Some(v)
 }
 
+fn proxy_ssl(conn: &ConnContext) -> Option<&ProxySslInfo> {
+	conn.proxy.get().and_then(|p| p.ssl.as_ref())
+}
+
 fn transport_str(t: Transport) -> &'static str {
 	match t {
 		Transport::Tcp => "tcp",
@@ -180,7 +210,9 @@ mod tests {
 	use std::sync::Arc;
 	use std::time::Instant as StdInstant;
 
-	use vane_core::{ConnContext, ConnId, PeerCertificate, TlsInfo, TlsVersion, Transport};
+	use vane_core::{
+		ConnContext, ConnId, PeerCertificate, ProxyInfo, ProxySslInfo, TlsInfo, TlsVersion, Transport,
+	};
 
 	use super::*;
 
@@ -298,6 +330,7 @@ mod tests {
 		assert_text(pack_single("conn.alpn", &conn).expect("present"), "");
 		assert_text(pack_single("conn.tls.version", &conn).expect("present"), "");
 		assert_text(pack_single("conn.tls.sni", &conn).expect("present"), "");
+		assert_text(pack_single("conn.tls.ja4", &conn).expect("present"), "");
 		assert_bytes(pack_single("conn.tls.peer_cert", &conn).expect("present"), &[]);
 		assert_boolean(pack_single("conn.tls.peer_cert.present", &conn).expect("present"), false);
 		assert_text(pack_single("conn.tls.peer_cert.subject_cn", &conn).expect("present"), "");
@@ -314,6 +347,8 @@ mod tests {
 			sni: Some(Arc::from("Example.COM")),
 			alpn: Some(Arc::from(&b"h2"[..])),
 			version: Some(TlsVersion::Tls13),
+			ja3: None,
+			ja4: Some(Arc::from("t13d1516h2_8daaf6152771_e5627efa2ab1")),
 			peer_cert: None,
 			zero_rtt_used: false,
 		};
@@ -322,6 +357,10 @@ mod tests {
 		assert_text(pack_single("conn.tls.version", &conn).expect("present"), "1.3");
 		// SNI is canonicalised to ASCII-lowercase per spec.
 		assert_text(pack_single("conn.tls.sni", &conn).expect("present"), "example.com");
+		assert_text(
+			pack_single("conn.tls.ja4", &conn).expect("present"),
+			"t13d1516h2_8daaf6152771_e5627efa2ab1",
+		);
 	}
 
 	#[test]
@@ -360,6 +399,38 @@ mod tests {
 		assert_text(pack_single("conn.tls.peer_cert.serial", &conn).expect("present"), "1234abcd");
 	}
 
+	#[test]
+	fn proxy_paths_pack_header_details_or_spec_empty_values() {
+		let conn = conn_with("203.0.113.7:51000", "192.0.2.10:443", Transport::Tcp, None);
+		assert_boolean(pack_single("conn.proxy.present", &conn).expect("present"), false);
+		assert_text(pack_single("conn.proxy.peer_ip", &conn).expect("present"), "");
+		assert_bytes(pack_single("conn.proxy.unique_id", &conn).expect("present"), &[]);
+		assert_boolean(pack_single("conn.proxy.ssl.verified", &conn).expect("present"), false);
+
+		let _ = conn.proxy.set(ProxyInfo {
+			peer: "10.0.0.2:40000".parse().expect("peer parse"),
+			authority: Some(Arc::from("example.com")),
+			unique_id: Some(bytes::Bytes::from_static(b"id-1")),
+			ssl: Some(ProxySslInfo {
+				version: Some(Arc::from("TLSv1.3")),
+				cn: Some(Arc::from("client")),
+				cipher: Some(Arc::from("TLS_AES_128_GCM_SHA256")),
+				verified: true,
+			}),
+		});
+		assert_boolean(pack_single("conn.proxy.present", &conn).expect("present"), true);
+		assert_text(pack_single("conn.proxy.peer_ip", &conn).expect("present"), "10.0.0.2");
+		assert_text(pack_single("conn.proxy.authority", &conn).expect("present"), "example.com");
+		assert_bytes(pack_single("conn.proxy.unique_id", &conn).expect("present"), b"id-1");
+		assert_text(pack_single("conn.proxy.ssl.version", &conn).expect("present"), "TLSv1.3");
+		assert_text(pack_single("conn.proxy.ssl.cn", &conn).expect("present"), "client");
+		assert_text(
+			pack_single("conn.proxy.ssl.cipher", &conn).expect("present"),
+			"TLS_AES_128_GCM_SHA256",
+		);
+		assert_boolean(pack_single("conn.proxy.ssl.verified", &conn).expect("present"), true);
+	}
+
 	#[test]
 	fn mixed_inspects_pack_every_known_path() {
 		let conn = conn_with("198.51.100.7:55001", "192.0.2.10:443", Transport::Tcp, None);
@@ -85,6 +85,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -696,6 +698,8 @@ async fn execute_l7_fetch_response_jumps_to_next_response() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(1)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -703,12 +707,7 @@ async fn execute_l7_fetch_response_jumps_to_next_response() {
 		],
 		vec![],
 		vec![],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 	let mw = MiddlewareFactories::new();
@@ -1111,6 +1110,8 @@ fn byte_tunnel_graph(tunnel: Tunnel) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -1118,12 +1119,7 @@ fn byte_tunnel_graph(tunnel: Tunnel) -> Arc<FlowGraph> {
 		],
 		vec![],
 		vec![],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::L4Forward, Value::Null)],
 		vec![Terminator::ByteTunnel],
 	);
 	let mw = MiddlewareFactories::new();
@@ -1168,6 +1164,8 @@ async fn execute_write_http_response_returns_response_output() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(1)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -1175,12 +1173,7 @@ async fn execute_write_http_response_returns_response_output() {
 		],
 		vec![],
 		vec![],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 	let mw = MiddlewareFactories::new();
@@ -1230,6 +1223,8 @@ async fn execute_write_http_response_preserves_body_payload() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(1)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -1237,12 +1232,7 @@ async fn execute_write_http_response_preserves_body_payload() {
 		],
 		vec![],
 		vec![],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 	let mw = MiddlewareFactories::new();
@@ -1551,6 +1541,8 @@ fn byte_tunnel_graph_with_notify(
 				id: FetchId::for_testing(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -1558,12 +1550,7 @@ fn byte_tunnel_graph_with_notify(
 		],
 		vec![],
 		vec![],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::L4Forward, Value::Null)],
 		vec![Terminator::ByteTunnel],
 	);
 	let mw = MiddlewareFactories::new();
@@ -2003,6 +1990,8 @@ async fn execute_collect_response_stream_body_becomes_static() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(1)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -2024,12 +2013,7 @@ async fn execute_collect_response_stream_body_becomes_static() {
 			needs_body: true,
 			on_error: None,
 		}],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 
@@ -2073,6 +2057,8 @@ async fn execute_collect_response_body_over_limit_returns_err() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(1)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -2087,12 +2073,7 @@ async fn execute_collect_response_body_over_limit_returns_err() {
 		],
 		vec![],
 		vec![l7_resp_ref("never_runs_resp")],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 
@@ -0,0 +1,316 @@
+//! End-to-end coverage for the `Node::Fetch` `on_error` fallback edge.
+//!
+//! Spec: `spec/flow-model.md` § _Fetch fallback_. Drives a real
+//! listener through `Upgrade → Fetch(http_proxy, on_error → fallback)
+//! → Terminate(WriteHttpResponse)`, checking that a fetch error, the
+//! 504 / 503 `http_proxy` answers for a timeout or an open breaker, or a
+//! listed upstream status diverts to the fallback with the buffered
+//! request body replayed, and that unlisted statuses pass through.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use vane_core::{
+	BodySide, FetchId, FetchKind, FlowLogKind, FlowLogSink, FlowTrajectory, Node, NodeId,
+	SymbolicFetchRef, SymbolicFlowGraph, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::fetch::http_synthesize::register as register_http_synth;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{RecordingSink, pick_port, sample_meta};
+
+fn fetch_ref(kind: FetchKind, args: serde_json::Value) -> SymbolicFetchRef {
+	SymbolicFetchRef::new(kind, args)
+}
+
+/// Primary `http_proxy` at node 3 falls back to `fallback` at node 2;
+/// both answer through the terminator at node 1.
+fn fallback_graph(
+	listen: SocketAddr,
+	primary: serde_json::Value,
+	on_error_status: Vec<u16>,
+	fallback: SymbolicFetchRef,
+) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(3) },
+			Node::Terminate(TerminatorId::for_testing(0)),
+			Node::Fetch {
+				id: FetchId::for_testing(1),
+				next_response: Some(NodeId::for_testing(1)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(1)),
+				next_tunnel: None,
+				on_error: Some(NodeId::for_testing(2)),
+				on_error_status,
+				collect_body_before: Some(BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![fetch_ref(FetchKind::HttpProxy, primary), fallback],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	register_http_synth(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link fallback graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr, Arc<RecordingSink>) {
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let sink = Arc::new(RecordingSink::default());
+	let verbosity = Arc::new(VerbosityState::new());
+	let set = ListenerSet::new();
+	set.start(
+		&Arc::new(ArcSwap::new(graph)),
+		&verbosity,
+		&(Arc::clone(&sink) as Arc<dyn FlowLogSink>),
+	);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr, sink)
+}
+
+async fn h1_post(proxy_addr: SocketAddr, body: &'static [u8]) -> (u16, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Full<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.method("POST")
+		.uri("/")
+		.header("host", "test.local")
+		.body(Full::new(Bytes::from_static(body)))
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect body").to_bytes())
+}
+
+/// Upstream answering every request with `status` and the request body
+/// echoed back (read up to the end of the client's first write).
+async fn spawn_upstream(status: u16) -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	tokio::spawn(async move {
+		loop {
+			let Ok((mut sock, _)) = listener.accept().await else { return };
+			tokio::spawn(async move {
+				let mut buf = vec![0u8; 8192];
+				let n = sock.read(&mut buf).await.unwrap_or(0);
+				let req = &buf[..n];
+				let body = req.windows(4).position(|w| w == b"\r\n\r\n").map_or(&[][..], |i| &req[i + 4..]);
+				let head = format!("HTTP/1.1 {status} X\r\ncontent-length: {}\r\n\r\n", body.len());
+				let _ = sock.write_all(head.as_bytes()).await;
+				let _ = sock.write_all(body).await;
+			});
+		}
+	});
+	addr
+}
+
+/// Upstream that reads each request and never answers.
+async fn spawn_stalling_upstream() -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	tokio::spawn(async move {
+		loop {
+			let Ok((mut sock, _)) = listener.accept().await else { return };
+			tokio::spawn(async move {
+				let mut buf = [0u8; 4096];
+				let _ = sock.read(&mut buf).await;
+				tokio::time::sleep(Duration::from_mins(1)).await;
+			});
+		}
+	});
+	addr
+}
+
+fn proxy_args(upstream: SocketAddr) -> serde_json::Value {
+	serde_json::json!({ "upstream": upstream.to_string(), "version": "h1" })
+}
+
+fn maintenance_page() -> SymbolicFetchRef {
+	// `bWFpbnRlbmFuY2U=` is base64 for `maintenance`.
+	fetch_ref(
+		FetchKind::HttpSynthesize,
+		serde_json::json!({ "status": 503, "body": "bWFpbnRlbmFuY2U=" }),
+	)
+}
+
+async fn trajectory(sink: &RecordingSink) -> FlowTrajectory {
+	for _ in 0..100 {
+		let data = sink
+			.events
+			.lock()
+			.iter()
+			.find(|e| e.kind == FlowLogKind::Trajectory)
+			.and_then(|e| e.data.clone());
+		if let Some(data) = data {
+			return serde_json::from_value(data).expect("trajectory data deserialises");
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	panic!("no Trajectory event in sink");
+}
+
+fn fetch_steps(t: &FlowTrajectory) -> Vec<u32> {
+	t.steps.iter().filter(|s| s.kind == FlowLogKind::Fetch).map(|s| s.node.get()).collect()
+}
+
+#[tokio::test]
+async fn unreachable_upstream_falls_back_to_static_page() {
+	vane_engine::crypto::install_default_provider();
+	let dead = pick_port();
+	let graph = fallback_graph(pick_port(), proxy_args(dead), Vec::new(), maintenance_page());
+	let (set, proxy_addr, sink) = start_listener(graph).await;
+
+	let (status, body) = h1_post(proxy_addr, b"payload").await;
+	assert_eq!(status, 503);
+	assert_eq!(body.as_ref(), b"maintenance");
+	assert_eq!(fetch_steps(&trajectory(&sink).await), vec![3, 2]);
+	assert!(
+		sink
+			.events
+			.lock()
+			.iter()
+			.any(|e| e.kind == FlowLogKind::Error && e.node.map(NodeId::get) == Some(3)),
+		"the primary's error is still logged",
+	);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn listed_status_falls_back_with_the_request_body_replayed() {
+	vane_engine::crypto::install_default_provider();
+	let primary = spawn_upstream(503).await;
+	let backup = spawn_upstream(200).await;
+	let graph = fallback_graph(
+		pick_port(),
+		proxy_args(primary),
+		vec![502, 503],
+		fetch_ref(FetchKind::HttpProxy, proxy_args(backup)),
+	);
+	let (set, proxy_addr, sink) = start_listener(graph).await;
+
+	let (status, body) = h1_post(proxy_addr, b"payload").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"payload", "the backup saw the buffered body");
+	assert_eq!(fetch_steps(&trajectory(&sink).await), vec![3, 2]);
+	let fallback_event = sink
+		.events
+		.lock()
+		.iter()
+		.find(|e| e.kind == FlowLogKind::Fetch)
+		.and_then(|e| e.data.clone())
+		.expect("fallback event");
+	assert_eq!(fallback_event, serde_json::json!({ "fallback": 2, "status": 503 }));
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn unlisted_status_is_passed_through() {
+	vane_engine::crypto::install_default_provider();
+	let primary = spawn_upstream(500).await;
+	let graph = fallback_graph(pick_port(), proxy_args(primary), vec![502, 503], maintenance_page());
+	let (set, proxy_addr, sink) = start_listener(graph).await;
+
+	let (status, body) = h1_post(proxy_addr, b"payload").await;
+	assert_eq!(status, 500);
+	assert_eq!(body.as_ref(), b"payload");
+	assert_eq!(fetch_steps(&trajectory(&sink).await), vec![3]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn upstream_timeout_falls_back_without_on_error_status() {
+	vane_engine::crypto::install_default_provider();
+	let primary = spawn_stalling_upstream().await;
+	let graph = fallback_graph(
+		pick_port(),
+		serde_json::json!({
+			"upstream": primary.to_string(),
+			"version": "h1",
+			"response_header_timeout": "200ms",
+		}),
+		Vec::new(),
+		maintenance_page(),
+	);
+	let (set, proxy_addr, sink) = start_listener(graph).await;
+
+	let (status, body) = h1_post(proxy_addr, b"payload").await;
+	assert_eq!(status, 503);
+	assert_eq!(body.as_ref(), b"maintenance", "the 504 is diverted, not answered");
+	assert_eq!(fetch_steps(&trajectory(&sink).await), vec![3, 2]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn open_circuit_breaker_falls_back_without_on_error_status() {
+	vane_engine::crypto::install_default_provider();
+	// Nothing listens on `dead`: the first request trips the breaker,
+	// the second meets it open.
+	let dead = pick_port();
+	let graph = fallback_graph(
+		pick_port(),
+		serde_json::json!({
+			"upstream": dead.to_string(),
+			"version": "h1",
+			"circuit_breaker": { "consecutive_failures": 1, "base_ejection": "30s" },
+		}),
+		Vec::new(),
+		maintenance_page(),
+	);
+	let (set, proxy_addr, sink) = start_listener(graph).await;
+
+	for _ in 0..2 {
+		let (status, body) = h1_post(proxy_addr, b"payload").await;
+		assert_eq!(status, 503);
+		assert_eq!(body.as_ref(), b"maintenance");
+	}
+	let diverted = sink
+		.events
+		.lock()
+		.iter()
+		.filter(|e| e.kind == FlowLogKind::Fetch)
+		.filter_map(|e| e.data.clone())
+		.collect::<Vec<_>>();
+	assert_eq!(
+		diverted,
+		[serde_json::json!({ "fallback": 2, "status": 503 })],
+		"the open breaker's 503 is diverted"
+	);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
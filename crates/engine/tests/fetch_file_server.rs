@@ -0,0 +1,133 @@
+//! Integration tests for `vane_engine::fetch::file_server`.
+//!
+//! Drives a real listener with `Upgrade -> Fetch(FileServer) ->
+//! Terminate(WriteHttpResponse)` and checks what reaches a hyper H1
+//! client: multi-chunk streamed bodies arrive intact with an exact
+//! `content-length`, and ranges survive the response writer. Resolution,
+//! precondition and negotiation details are covered by the in-file unit
+//! tests. See `spec/crates/engine.md` § _File server_.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::file_server::register as register_file_server;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn file_server_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::FileServer, args)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_file_server(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link file_server graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("graph has at least one entry").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+async fn get(addr: SocketAddr, path: &str, range: Option<&str>) -> http::Response<Bytes> {
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let mut req = hyper::Request::builder().uri(path).header("host", "test.local");
+	if let Some(r) = range {
+		req = req.header("range", r);
+	}
+	let resp = sender.send_request(req.body(Empty::new()).expect("request")).await.expect("send");
+	let (parts, body) = resp.into_parts();
+	http::Response::from_parts(parts, body.collect().await.expect("collect").to_bytes())
+}
+
+/// 300 KiB of a repeating pattern — several 64 KiB read chunks.
+fn payload() -> Vec<u8> {
+	(0..300 * 1024).map(|i| u8::try_from(i % 251).expect("fits")).collect()
+}
+
+#[tokio::test]
+async fn file_server_streams_large_file_over_h1() {
+	let root = tempfile::tempdir().expect("tempdir");
+	let data = payload();
+	std::fs::write(root.path().join("blob.bin"), &data).expect("write");
+	let addr = pick_port();
+	let (set, addr) =
+		start_listener(file_server_graph(addr, serde_json::json!({ "root": root.path() }))).await;
+
+	let resp = get(addr, "/blob.bin", None).await;
+	assert_eq!(resp.status(), 200);
+	assert_eq!(resp.headers()["content-length"], data.len().to_string().as_str());
+	assert_eq!(resp.headers()["content-type"], "application/octet-stream");
+	assert_eq!(resp.body().as_ref(), data.as_slice());
+
+	let resp = get(addr, "/nope.bin", None).await;
+	assert_eq!(resp.status(), 404);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn file_server_range_crosses_chunk_boundary() {
+	let root = tempfile::tempdir().expect("tempdir");
+	let data = payload();
+	std::fs::write(root.path().join("blob.bin"), &data).expect("write");
+	let addr = pick_port();
+	let (set, addr) =
+		start_listener(file_server_graph(addr, serde_json::json!({ "root": root.path() }))).await;
+
+	let (start, end) = (65_000_usize, 140_000_usize);
+	let resp = get(addr, "/blob.bin", Some(&format!("bytes={start}-{end}"))).await;
+	assert_eq!(resp.status(), 206);
+	assert_eq!(
+		resp.headers()["content-range"],
+		format!("bytes {start}-{end}/{}", data.len()).as_str()
+	);
+	assert_eq!(resp.body().as_ref(), &data[start..=end]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
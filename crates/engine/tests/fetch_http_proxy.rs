@@ -86,6 +86,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -109,6 +111,8 @@ fn proxy_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -116,12 +120,10 @@ fn proxy_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: serde_json::json!({ "upstream": upstream }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpProxy,
+			serde_json::json!({ "upstream": upstream }),
+		)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta: sample_meta(),
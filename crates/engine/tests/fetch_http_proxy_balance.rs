@@ -0,0 +1,199 @@
+//! End-to-end coverage for multi-member `http_proxy` rules.
+//!
+//! Spec: `spec/crates/engine.md` § _Load balancing_. Drives real
+//! listeners and upstreams to check that `args.upstreams` spreads
+//! requests per policy, that retries fail over to an untried member,
+//! and that `consistent_hash` keeps a key on one member.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			retry_buffer_required: true,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+/// Send one GET, optionally with an extra header, and return the
+/// status plus the upstream's self-reported name from the body.
+async fn h1_get(proxy_addr: SocketAddr, header: Option<(&str, &str)>) -> (u16, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let mut builder = hyper::Request::builder().method("GET").uri("/").header("host", "test.local");
+	if let Some((name, value)) = header {
+		builder = builder.header(name, value);
+	}
+	let resp = sender.send_request(builder.body(Empty::new()).expect("build")).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect").to_bytes())
+}
+
+/// Upstream that answers every request with `name` and counts hits.
+async fn spawn_named_upstream(name: &'static str) -> (SocketAddr, Arc<AtomicUsize>) {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	let hits = Arc::new(AtomicUsize::new(0));
+	let hits_clone = Arc::clone(&hits);
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			let hits = Arc::clone(&hits_clone);
+			tokio::spawn(async move {
+				let svc = service_fn(move |_req: hyper::Request<hyper::body::Incoming>| {
+					hits.fetch_add(1, Ordering::SeqCst);
+					async move {
+						Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from_static(
+							name.as_bytes(),
+						))))
+					}
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	(addr, hits)
+}
+
+#[tokio::test]
+async fn round_robin_spreads_requests_across_members() {
+	vane_engine::crypto::install_default_provider();
+	let (a, hits_a) = spawn_named_upstream("a").await;
+	let (b, hits_b) = spawn_named_upstream("b").await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(
+		proxy_addr,
+		serde_json::json!({
+			"upstreams": [a.to_string(), b.to_string()],
+			"version": "h1",
+		}),
+	);
+	let (set, proxy_addr) = start_listener(graph).await;
+
+	for _ in 0..4 {
+		let (status, _) = h1_get(proxy_addr, None).await;
+		assert_eq!(status, 200);
+	}
+	assert_eq!(hits_a.load(Ordering::SeqCst), 2);
+	assert_eq!(hits_b.load(Ordering::SeqCst), 2);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn retry_fails_over_to_untried_member() {
+	vane_engine::crypto::install_default_provider();
+	// Nothing listens on `dead` — every connect is refused.
+	let dead = pick_port();
+	let (live, hits) = spawn_named_upstream("live").await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(
+		proxy_addr,
+		serde_json::json!({
+			"upstreams": [dead.to_string(), live.to_string()],
+			"version": "h1",
+			"retry": { "max_attempts": 2, "backoff": "none", "buffering": "force" },
+		}),
+	);
+	let (set, proxy_addr) = start_listener(graph).await;
+
+	for _ in 0..4 {
+		let (status, body) = h1_get(proxy_addr, None).await;
+		assert_eq!(status, 200, "every request must land on the live member");
+		assert_eq!(body.as_ref(), b"live");
+	}
+	assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn consistent_hash_on_header_is_sticky() {
+	vane_engine::crypto::install_default_provider();
+	let (a, _) = spawn_named_upstream("a").await;
+	let (b, _) = spawn_named_upstream("b").await;
+	let (c, _) = spawn_named_upstream("c").await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(
+		proxy_addr,
+		serde_json::json!({
+			"upstreams": [a.to_string(), b.to_string(), c.to_string()],
+			"lb": { "policy": "consistent_hash", "hash_on": "header:x-user" },
+			"version": "h1",
+		}),
+	);
+	let (set, proxy_addr) = start_listener(graph).await;
+
+	let (_, first) = h1_get(proxy_addr, Some(("x-user", "alice"))).await;
+	for _ in 0..5 {
+		let (status, body) = h1_get(proxy_addr, Some(("x-user", "alice"))).await;
+		assert_eq!(status, 200);
+		assert_eq!(body, first, "same key must keep hitting the same member");
+	}
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,190 @@
+//! End-to-end coverage for the `http_proxy` circuit breaker.
+//!
+//! Spec: `spec/crates/engine.md` § _Circuit breaking_. Drives real
+//! listeners against a dead upstream to check that consecutive connect
+//! failures open the breaker, that further requests fail fast with 503,
+//! that retries steer to a healthy member, and that transitions land in
+//! the flow log.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph,
+	Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{RecordingSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			retry_buffer_required: true,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(
+	graph: Arc<FlowGraph>,
+	sink: Arc<dyn FlowLogSink>,
+) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+/// Send one GET and return the status plus the upstream's
+/// self-reported name from the body.
+async fn h1_get(proxy_addr: SocketAddr) -> (u16, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.method("GET")
+		.uri("/")
+		.header("host", "test.local")
+		.body(Empty::new())
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect").to_bytes())
+}
+
+/// Upstream that answers every request with `name`.
+async fn spawn_upstream(name: &'static str) -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			tokio::spawn(async move {
+				let svc = service_fn(move |_req: hyper::Request<hyper::body::Incoming>| async move {
+					Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from_static(name.as_bytes()))))
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	addr
+}
+
+fn breaker_events(sink: &RecordingSink) -> Vec<serde_json::Value> {
+	sink
+		.events
+		.lock()
+		.iter()
+		.filter(|e| e.kind == FlowLogKind::CircuitBreaker)
+		.filter_map(|e| e.data.clone())
+		.collect()
+}
+
+#[tokio::test]
+async fn consecutive_failures_open_breaker_and_fail_fast() {
+	vane_engine::crypto::install_default_provider();
+	// Nothing listens on `dead` — every connect is refused.
+	let dead = pick_port();
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(
+		proxy_addr,
+		serde_json::json!({
+			"upstream": dead.to_string(),
+			"version": "h1",
+			"circuit_breaker": { "consecutive_failures": 2, "base_ejection": "30s" },
+		}),
+	);
+	let sink = Arc::new(RecordingSink::default());
+	let (set, proxy_addr) = start_listener(graph, Arc::clone(&sink) as Arc<dyn FlowLogSink>).await;
+
+	// Fetch errors surface as the H1 driver's synthetic 500.
+	assert_eq!(h1_get(proxy_addr).await.0, 500);
+	assert_eq!(h1_get(proxy_addr).await.0, 500);
+	let started = Instant::now();
+	assert_eq!(h1_get(proxy_addr).await.0, 503, "open breaker must fail fast");
+	assert!(started.elapsed() < Duration::from_secs(1));
+
+	let events = breaker_events(&sink);
+	assert_eq!(events.len(), 1, "{events:?}");
+	assert_eq!(events[0]["upstream"], dead.to_string());
+	assert_eq!(events[0]["from"], "closed");
+	assert_eq!(events[0]["to"], "open");
+	assert_eq!(events[0]["ejection_ms"], 30_000);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn open_member_is_skipped_by_selection() {
+	vane_engine::crypto::install_default_provider();
+	let dead = pick_port();
+	let live = spawn_upstream("live").await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(
+		proxy_addr,
+		serde_json::json!({
+			"upstreams": [dead.to_string(), live.to_string()],
+			"version": "h1",
+			"circuit_breaker": { "consecutive_failures": 1, "base_ejection": "30s" },
+		}),
+	);
+	let sink = Arc::new(RecordingSink::default());
+	let (set, proxy_addr) = start_listener(graph, Arc::clone(&sink) as Arc<dyn FlowLogSink>).await;
+
+	// Round-robin lands on `dead` first and trips its breaker; every
+	// request after that goes to `live` without touching `dead` again.
+	assert_eq!(h1_get(proxy_addr).await.0, 500);
+	for _ in 0..4 {
+		let (status, body) = h1_get(proxy_addr).await;
+		assert_eq!(status, 200);
+		assert_eq!(body.as_ref(), b"live");
+	}
+	assert_eq!(breaker_events(&sink).len(), 1);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
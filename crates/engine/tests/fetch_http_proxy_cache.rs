@@ -0,0 +1,230 @@
+//! End-to-end coverage for the `http_proxy` response cache.
+//!
+//! Spec: `spec/crates/engine.md` § _Response cache_. Drives real
+//! listeners against a counting upstream to check the `X-Cache`
+//! outcomes a client sees: a miss fills and the next request hits, a
+//! purge forces a refetch, an expired entry revalidates with its
+//! `ETag`, concurrent misses share one upstream trip, and a failing
+//! upstream falls back to the stale entry. The store is daemon-wide,
+//! so every test keys its requests under its own `Host`.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, upstream: SocketAddr) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpProxy,
+			serde_json::json!({
+				"upstream": upstream.to_string(),
+				"version": "h1",
+				"cache": {},
+			}),
+		)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+/// Upstream whose answers the test steers: every response carries
+/// `cache_control` and `ETag: "v1"`, a matching `If-None-Match` gets a
+/// 304, and `failing` turns everything into a 503.
+struct Upstream {
+	hits: AtomicUsize,
+	failing: AtomicBool,
+	cache_control: &'static str,
+	delay: Duration,
+}
+
+async fn spawn_upstream(
+	cache_control: &'static str,
+	delay: Duration,
+) -> (Arc<Upstream>, SocketAddr) {
+	let state = Arc::new(Upstream {
+		hits: AtomicUsize::new(0),
+		failing: AtomicBool::new(false),
+		cache_control,
+		delay,
+	});
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	let shared = Arc::clone(&state);
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			let state = Arc::clone(&shared);
+			tokio::spawn(async move {
+				let svc = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+					let state = Arc::clone(&state);
+					async move {
+						let n = state.hits.fetch_add(1, Ordering::SeqCst) + 1;
+						tokio::time::sleep(state.delay).await;
+						let builder = hyper::Response::builder()
+							.header("cache-control", state.cache_control)
+							.header("etag", "\"v1\"");
+						let resp = if state.failing.load(Ordering::SeqCst) {
+							builder.status(503).body(Full::new(Bytes::from_static(b"down")))
+						} else if req.headers().get("if-none-match").is_some_and(|v| v == "\"v1\"") {
+							builder.status(304).body(Full::new(Bytes::new()))
+						} else {
+							builder.body(Full::new(Bytes::from(format!("body-{n}"))))
+						};
+						Ok::<_, Infallible>(resp.expect("response"))
+					}
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	(state, addr)
+}
+
+/// One GET; returns status, `X-Cache` and body.
+async fn get(proxy: SocketAddr, host: &str) -> (u16, String, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req =
+		hyper::Request::builder().uri("/page").header("host", host).body(Empty::new()).expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	let x_cache = resp
+		.headers()
+		.get("x-cache")
+		.map(|v| v.to_str().expect("ascii").to_owned())
+		.unwrap_or_default();
+	(status, x_cache, resp.into_body().collect().await.expect("collect").to_bytes())
+}
+
+#[tokio::test]
+async fn miss_fills_then_hits_until_purged() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, upstream_addr) = spawn_upstream("max-age=60", Duration::ZERO).await;
+	let (set, proxy) = start_listener(proxy_graph(pick_port(), upstream_addr)).await;
+
+	assert_eq!(get(proxy, "fill.test").await, (200, "MISS".into(), Bytes::from("body-1")));
+	assert_eq!(get(proxy, "fill.test").await, (200, "HIT".into(), Bytes::from("body-1")));
+	assert_eq!(upstream.hits.load(Ordering::SeqCst), 1);
+
+	let snap = vane_engine::fetch::cache::store().snapshot(Some("fill.test/"), 10);
+	assert_eq!(snap.entries.len(), 1);
+	assert_eq!(snap.entries[0].key, "fill.test/page");
+	assert_eq!(snap.entries[0].hits, 1);
+
+	assert_eq!(vane_engine::fetch::cache::store().purge_prefix("fill.test/"), 1);
+	assert_eq!(get(proxy, "fill.test").await, (200, "MISS".into(), Bytes::from("body-2")));
+	assert_eq!(upstream.hits.load(Ordering::SeqCst), 2);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn expired_entry_revalidates_with_etag() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, upstream_addr) = spawn_upstream("max-age=0", Duration::ZERO).await;
+	let (set, proxy) = start_listener(proxy_graph(pick_port(), upstream_addr)).await;
+
+	assert_eq!(get(proxy, "revalidate.test").await, (200, "MISS".into(), Bytes::from("body-1")));
+	// The upstream answers the conditional request with a 304; the
+	// client still gets the full stored body.
+	assert_eq!(
+		get(proxy, "revalidate.test").await,
+		(200, "REVALIDATED".into(), Bytes::from("body-1"))
+	);
+	assert_eq!(upstream.hits.load(Ordering::SeqCst), 2);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn concurrent_misses_share_one_upstream_fetch() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, upstream_addr) = spawn_upstream("max-age=60", Duration::from_millis(200)).await;
+	let (set, proxy) = start_listener(proxy_graph(pick_port(), upstream_addr)).await;
+
+	let requests: Vec<_> = (0..8).map(|_| tokio::spawn(get(proxy, "coalesce.test"))).collect();
+	let mut outcomes = Vec::new();
+	for r in requests {
+		let (status, x_cache, body) = r.await.expect("join");
+		assert_eq!((status, body), (200, Bytes::from("body-1")));
+		outcomes.push(x_cache);
+	}
+	assert_eq!(upstream.hits.load(Ordering::SeqCst), 1);
+	assert_eq!(outcomes.iter().filter(|o| *o == "MISS").count(), 1, "{outcomes:?}");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn failing_upstream_falls_back_to_stale_entry() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, upstream_addr) =
+		spawn_upstream("max-age=0, stale-if-error=60", Duration::ZERO).await;
+	let (set, proxy) = start_listener(proxy_graph(pick_port(), upstream_addr)).await;
+
+	assert_eq!(get(proxy, "stale.test").await, (200, "MISS".into(), Bytes::from("body-1")));
+	upstream.failing.store(true, Ordering::SeqCst);
+	assert_eq!(get(proxy, "stale.test").await, (200, "STALE".into(), Bytes::from("body-1")));
+	assert_eq!(upstream.hits.load(Ordering::SeqCst), 2);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -63,6 +63,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: BTreeMap::new(),
 
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -77,6 +79,8 @@ fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -84,12 +88,7 @@ fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpProxy, args)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta: sample_meta(),
@@ -29,8 +29,7 @@ fn make_ctx() -> (Arc<ConnContext>, FlowCtx) {
 		Transport::Tcp,
 		std::time::Instant::now(),
 	));
-	*conn.tls.lock() =
-		Some(TlsInfo { sni: None, alpn: None, version: None, peer_cert: None, zero_rtt_used: false });
+	*conn.tls.lock() = Some(TlsInfo::default());
 	let _ = conn.http_version.set(HttpVersion::Http1_1);
 	let span = tracing::info_span!("test");
 	let ctx = FlowCtx {
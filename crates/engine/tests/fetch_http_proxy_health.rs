@@ -0,0 +1,186 @@
+//! End-to-end coverage for `http_proxy` active health checks.
+//!
+//! Spec: `spec/crates/engine.md` § _Health checks_. Drives real
+//! listeners and upstreams to check that a member failing its probe
+//! stops receiving traffic, and that a set with every member down
+//! still routes (panic routing) instead of refusing outright.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			retry_buffer_required: true,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+/// Send one GET and return the status plus the upstream's
+/// self-reported name from the body.
+async fn h1_get(proxy_addr: SocketAddr) -> (u16, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.method("GET")
+		.uri("/")
+		.header("host", "test.local")
+		.body(Empty::new())
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect").to_bytes())
+}
+
+/// Upstream that answers `/healthz` with `health_status` and every
+/// other path with `name`, counting only the non-probe hits.
+async fn spawn_upstream(name: &'static str, health_status: u16) -> (SocketAddr, Arc<AtomicUsize>) {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	let hits = Arc::new(AtomicUsize::new(0));
+	let hits_clone = Arc::clone(&hits);
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			let hits = Arc::clone(&hits_clone);
+			tokio::spawn(async move {
+				let svc = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+					let probe = req.uri().path() == "/healthz";
+					if !probe {
+						hits.fetch_add(1, Ordering::SeqCst);
+					}
+					async move {
+						let mut resp = hyper::Response::new(Full::new(Bytes::from_static(name.as_bytes())));
+						if probe {
+							*resp.status_mut() = hyper::StatusCode::from_u16(health_status).expect("status");
+						}
+						Ok::<_, Infallible>(resp)
+					}
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	(addr, hits)
+}
+
+fn health_args(members: &[SocketAddr]) -> serde_json::Value {
+	serde_json::json!({
+		"upstreams": members.iter().map(ToString::to_string).collect::<Vec<_>>(),
+		"version": "h1",
+		"health_check": {
+			"type": "http",
+			"path": "/healthz",
+			"interval": "50ms",
+			"timeout": "50ms",
+			"rise": 1,
+			"fall": 1,
+		},
+	})
+}
+
+#[tokio::test]
+async fn unhealthy_member_is_skipped() {
+	vane_engine::crypto::install_default_provider();
+	let (good, good_hits) = spawn_upstream("good", 200).await;
+	let (bad, bad_hits) = spawn_upstream("bad", 503).await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(proxy_addr, health_args(&[good, bad]));
+	let (set, proxy_addr) = start_listener(graph).await;
+	// A few probe rounds so both members have a verdict.
+	tokio::time::sleep(Duration::from_millis(300)).await;
+
+	for _ in 0..6 {
+		let (status, body) = h1_get(proxy_addr).await;
+		assert_eq!(status, 200);
+		assert_eq!(body.as_ref(), b"good");
+	}
+	assert_eq!(good_hits.load(Ordering::SeqCst), 6);
+	assert_eq!(bad_hits.load(Ordering::SeqCst), 0, "unhealthy member must not take traffic");
+	let bad_status = vane_engine::fetch::health::status_for(&bad.to_string());
+	assert_eq!(bad_status, Some(vane_engine::fetch::health::HealthStatus::Unhealthy));
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn all_members_unhealthy_still_routes() {
+	vane_engine::crypto::install_default_provider();
+	let (a, hits_a) = spawn_upstream("a", 500).await;
+	let (b, hits_b) = spawn_upstream("b", 500).await;
+	let proxy_addr = pick_port();
+	let graph = proxy_graph(proxy_addr, health_args(&[a, b]));
+	let (set, proxy_addr) = start_listener(graph).await;
+	tokio::time::sleep(Duration::from_millis(300)).await;
+
+	for _ in 0..4 {
+		let (status, _) = h1_get(proxy_addr).await;
+		assert_eq!(status, 200, "panic routing must still reach a member");
+	}
+	assert_eq!(hits_a.load(Ordering::SeqCst) + hits_b.load(Ordering::SeqCst), 4);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,240 @@
+//! End-to-end coverage for `http_proxy`'s hedged requests.
+//!
+//! Spec: `spec/crates/engine.md` § _Hedging_. Drives a real listener
+//! against an upstream whose connections answer after per-connection
+//! delays, checking that a slow first attempt is raced by a hedge, that
+//! the first answer wins, that non-idempotent methods are never
+//! hedged, and that the trajectory records the winner.
+//!
+//! Every test fixes the daemon-wide budget at 100% so the shared
+//! budget never decides whether a hedge fires.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use vane_core::{
+	FetchId, FetchKind, FlowLogKind, FlowLogSink, FlowTrajectory, HedgeWinner, Node, NodeId,
+	SymbolicFetchRef, SymbolicFlowGraph, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::fetch::retry::init_hedge_budget;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{RecordingSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			retry_buffer_required: true,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(args: serde_json::Value) -> (ListenerSet, SocketAddr, Arc<RecordingSink>) {
+	init_hedge_budget(100);
+	let graph = proxy_graph(pick_port(), args);
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let sink = Arc::new(RecordingSink::default());
+	let verbosity = Arc::new(VerbosityState::new());
+	let set = ListenerSet::new();
+	set.start(
+		&Arc::new(ArcSwap::new(graph)),
+		&verbosity,
+		&(Arc::clone(&sink) as Arc<dyn FlowLogSink>),
+	);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr, sink)
+}
+
+async fn h1_send(proxy_addr: SocketAddr, method: &str) -> (u16, Bytes) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Full<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.method(method)
+		.uri("/")
+		.header("host", "test.local")
+		.body(Full::new(Bytes::from_static(b"payload")))
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect body").to_bytes())
+}
+
+/// Upstream whose `n`th accepted connection waits `delays_ms[n]` (the
+/// last entry repeats) after reading the request, then answers
+/// `conn-<n>`. Returns the address and an accept count.
+async fn spawn_upstream(delays_ms: &'static [u64]) -> (SocketAddr, Arc<AtomicUsize>) {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	let accepted = Arc::new(AtomicUsize::new(0));
+	let counter = Arc::clone(&accepted);
+	tokio::spawn(async move {
+		loop {
+			let Ok((mut sock, _)) = listener.accept().await else { return };
+			let n = counter.fetch_add(1, Ordering::SeqCst);
+			let delay = delays_ms[n.min(delays_ms.len() - 1)];
+			tokio::spawn(async move {
+				let mut buf = [0u8; 4096];
+				let _ = sock.read(&mut buf).await;
+				tokio::time::sleep(Duration::from_millis(delay)).await;
+				let body = format!("conn-{n}");
+				let reply = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{body}", body.len());
+				let _ = sock.write_all(reply.as_bytes()).await;
+				tokio::time::sleep(Duration::from_mins(1)).await;
+			});
+		}
+	});
+	(addr, accepted)
+}
+
+/// The request's trajectory; it is emitted once the response has been
+/// written, so poll briefly for it.
+async fn trajectory(sink: &RecordingSink) -> FlowTrajectory {
+	for _ in 0..100 {
+		let data = sink
+			.events
+			.lock()
+			.iter()
+			.find(|e| e.kind == FlowLogKind::Trajectory)
+			.and_then(|e| e.data.clone());
+		if let Some(data) = data {
+			return serde_json::from_value(data).expect("trajectory data deserialises");
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	panic!("no Trajectory event in sink");
+}
+
+fn hedged_args(upstream: SocketAddr) -> serde_json::Value {
+	serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"retry": { "buffering": "force", "hedge": { "delay": "50ms" } },
+	})
+}
+
+#[tokio::test]
+async fn slow_primary_is_beaten_by_the_hedge() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_upstream(&[2_000, 0]).await;
+	let (set, proxy_addr, sink) = start_listener(hedged_args(upstream)).await;
+
+	let started = Instant::now();
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"conn-1", "the hedge's connection answered");
+	assert!(started.elapsed() < Duration::from_secs(1), "{:?}", started.elapsed());
+	assert_eq!(accepted.load(Ordering::SeqCst), 2);
+	assert_eq!(trajectory(&sink).await.hedge, Some(HedgeWinner::Hedge));
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn primary_answering_first_wins_the_race() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_upstream(&[200, 2_000]).await;
+	let (set, proxy_addr, sink) = start_listener(hedged_args(upstream)).await;
+
+	let started = Instant::now();
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"conn-0");
+	assert!(started.elapsed() < Duration::from_secs(1), "loser cancelled, not awaited");
+	assert_eq!(accepted.load(Ordering::SeqCst), 2, "the hedge was sent");
+	assert_eq!(trajectory(&sink).await.hedge, Some(HedgeWinner::Primary));
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn prompt_upstream_is_not_hedged() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_upstream(&[0]).await;
+	let (set, proxy_addr, sink) = start_listener(hedged_args(upstream)).await;
+
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"conn-0");
+	assert_eq!(accepted.load(Ordering::SeqCst), 1);
+	assert_eq!(trajectory(&sink).await.hedge, None);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn non_idempotent_methods_are_never_hedged() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_upstream(&[300, 0]).await;
+	let (set, proxy_addr, sink) = start_listener(hedged_args(upstream)).await;
+
+	let (status, body) = h1_send(proxy_addr, "POST").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"conn-0", "the slow primary is waited out");
+	assert_eq!(accepted.load(Ordering::SeqCst), 1);
+	assert_eq!(trajectory(&sink).await.hedge, None);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn percentile_delay_waits_for_enough_samples() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_upstream(&[300, 0]).await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"retry": { "buffering": "force", "hedge": { "delay": "p95" } },
+	}))
+	.await;
+
+	// A fresh member has no latency history, so nothing is hedged yet.
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"conn-0");
+	assert_eq!(accepted.load(Ordering::SeqCst), 1);
+	assert_eq!(trajectory(&sink).await.hedge, None);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -51,6 +51,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: BTreeMap::new(),
 
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -72,6 +74,8 @@ fn proxy_graph(
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before,
 				body_limit,
 			},
@@ -80,10 +84,8 @@ fn proxy_graph(
 		predicates: vec![],
 		middlewares: vec![],
 		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args,
 			retry_buffer_required,
-			allow_zero_rtt: None,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
 		}],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
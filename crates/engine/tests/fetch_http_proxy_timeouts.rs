@@ -0,0 +1,288 @@
+//! End-to-end coverage for `http_proxy`'s per-rule time bounds.
+//!
+//! Spec: `spec/crates/engine.md` § _Upstream timeouts_. Drives real
+//! listeners against upstreams that accept and then stall, checking
+//! that each bound answers (or aborts) with its own `TimeoutKind`,
+//! lands in the flow log under that reason, and spends the retry
+//! budget the way `fetch/retry.rs` describes.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use vane_core::{
+	FetchId, FetchKind, FlowLogKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph,
+	Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{RecordingSink, pick_port, sample_meta};
+
+fn proxy_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 8 * 1024 * 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			retry_buffer_required: true,
+			..SymbolicFetchRef::new(FetchKind::HttpProxy, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start_listener(args: serde_json::Value) -> (ListenerSet, SocketAddr, Arc<RecordingSink>) {
+	let graph = proxy_graph(pick_port(), args);
+	let addr = *graph.symbolic().entries.iter().next().expect("entries").0;
+	let sink = Arc::new(RecordingSink::default());
+	let verbosity = Arc::new(VerbosityState::new());
+	let set = ListenerSet::new();
+	set.start(
+		&Arc::new(ArcSwap::new(graph)),
+		&verbosity,
+		&(Arc::clone(&sink) as Arc<dyn FlowLogSink>),
+	);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr, sink)
+}
+
+/// Send one request and return the status plus the collected body, or
+/// `None` for a body the proxy aborted mid-stream.
+async fn h1_send(proxy_addr: SocketAddr, method: &str) -> (u16, Option<Bytes>) {
+	let stream = tokio::net::TcpStream::connect(proxy_addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Full<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.method(method)
+		.uri("/")
+		.header("host", "test.local")
+		.body(Full::new(Bytes::from_static(b"payload")))
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.ok().map(http_body_util::Collected::to_bytes))
+}
+
+/// Upstream that reads each request, writes `reply` (possibly nothing,
+/// possibly a head with a short body) and then holds the connection
+/// open without another byte. Returns the address and an accept count.
+async fn spawn_stalling_upstream(reply: &'static [u8]) -> (SocketAddr, Arc<AtomicUsize>) {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	let accepted = Arc::new(AtomicUsize::new(0));
+	let counter = Arc::clone(&accepted);
+	tokio::spawn(async move {
+		loop {
+			let Ok((mut sock, _)) = listener.accept().await else { return };
+			counter.fetch_add(1, Ordering::SeqCst);
+			tokio::spawn(async move {
+				let mut buf = [0u8; 4096];
+				let _ = sock.read(&mut buf).await;
+				let _ = sock.write_all(reply).await;
+				tokio::time::sleep(Duration::from_mins(1)).await;
+			});
+		}
+	});
+	(addr, accepted)
+}
+
+/// `error.reason` of every flow-log `Error` event, in emit order.
+fn timeout_reasons(sink: &RecordingSink) -> Vec<String> {
+	sink
+		.events
+		.lock()
+		.iter()
+		.filter(|e| e.kind == FlowLogKind::Error)
+		.filter_map(|e| e.error.as_ref())
+		.filter(|e| e.kind == "timeout")
+		.filter_map(|e| e.reason.clone())
+		.collect()
+}
+
+const HEAD_THEN_STALL: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n0123456789";
+
+#[tokio::test]
+async fn response_header_timeout_answers_504_and_logs_read() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_stalling_upstream(b"").await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"response_header_timeout": "200ms",
+	}))
+	.await;
+
+	let started = Instant::now();
+	let (status, _) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 504);
+	assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+	assert_eq!(accepted.load(Ordering::SeqCst), 1, "no retry policy, one attempt");
+	assert_eq!(timeout_reasons(&sink), ["read"]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn response_header_timeout_is_retried_for_idempotent_methods_only() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_stalling_upstream(b"").await;
+	let (set, proxy_addr, _sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"response_header_timeout": "100ms",
+		"retry": { "max_attempts": 3, "methods": ["GET", "POST"], "backoff": "none", "buffering": "force" },
+	}))
+	.await;
+
+	assert_eq!(h1_send(proxy_addr, "GET").await.0, 504);
+	assert_eq!(accepted.load(Ordering::SeqCst), 3, "GET spends every attempt");
+
+	// The request reached the upstream, so even a whitelisted POST is
+	// not replayed.
+	assert_eq!(h1_send(proxy_addr, "POST").await.0, 504);
+	assert_eq!(accepted.load(Ordering::SeqCst), 4, "POST stops after one attempt");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn total_timeout_caps_the_retry_loop() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, accepted) = spawn_stalling_upstream(b"").await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"response_header_timeout": "200ms",
+		"total_timeout": "500ms",
+		"retry": { "max_attempts": 10, "backoff": "none", "buffering": "force" },
+	}))
+	.await;
+
+	let started = Instant::now();
+	assert_eq!(h1_send(proxy_addr, "GET").await.0, 504);
+	assert!(started.elapsed() < Duration::from_millis(1500), "{:?}", started.elapsed());
+	let n = accepted.load(Ordering::SeqCst);
+	assert!((2..=3).contains(&n), "the deadline ends the loop early: {n} attempts");
+	assert_eq!(timeout_reasons(&sink), ["total"]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn backoff_past_the_deadline_returns_the_attempt_error() {
+	vane_engine::crypto::install_default_provider();
+	// Nothing listens: every attempt fails at connect.
+	let dead = pick_port();
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": dead.to_string(),
+		"version": "h1",
+		"total_timeout": "300ms",
+		"retry": { "max_attempts": 3, "backoff": { "fixed": "1s" }, "buffering": "force" },
+	}))
+	.await;
+
+	let started = Instant::now();
+	// The connect failure itself, rendered by the H1 driver, not a 504.
+	assert_eq!(h1_send(proxy_addr, "GET").await.0, 500);
+	assert!(started.elapsed() < Duration::from_millis(300), "{:?}", started.elapsed());
+	assert!(timeout_reasons(&sink).is_empty());
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn body_read_idle_timeout_aborts_a_stalled_body() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, _) = spawn_stalling_upstream(HEAD_THEN_STALL).await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"body_read_idle_timeout": "200ms",
+	}))
+	.await;
+
+	let started = Instant::now();
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200, "the head was relayed before the stall");
+	assert!(body.is_none(), "the stalled body is aborted, not completed");
+	assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+	assert_eq!(timeout_reasons(&sink), ["idle"]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn total_timeout_covers_the_response_body() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, _) = spawn_stalling_upstream(HEAD_THEN_STALL).await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"total_timeout": "300ms",
+	}))
+	.await;
+
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert!(body.is_none());
+	assert_eq!(timeout_reasons(&sink), ["total"]);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn bounds_leave_a_prompt_upstream_alone() {
+	vane_engine::crypto::install_default_provider();
+	let (upstream, _) =
+		spawn_stalling_upstream(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok").await;
+	let (set, proxy_addr, sink) = start_listener(serde_json::json!({
+		"upstream": upstream.to_string(),
+		"version": "h1",
+		"response_header_timeout": "1s",
+		"body_read_idle_timeout": "1s",
+		"total_timeout": "2s",
+	}))
+	.await;
+
+	let (status, body) = h1_send(proxy_addr, "GET").await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_deref(), Some(&b"ok"[..]));
+	assert!(timeout_reasons(&sink).is_empty());
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -68,6 +68,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -91,6 +93,8 @@ fn synth_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -98,12 +102,7 @@ fn synth_graph(listen: SocketAddr, args: serde_json::Value) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, args)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta: sample_meta(),
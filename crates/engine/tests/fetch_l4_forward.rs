@@ -95,6 +95,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -120,6 +122,8 @@ fn make_proxy_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -127,12 +131,10 @@ fn make_proxy_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: serde_json::json!({ "upstream": upstream }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::L4Forward,
+			serde_json::json!({ "upstream": upstream }),
+		)],
 		terminators: vec![Terminator::ByteTunnel],
 		entries,
 		meta: sample_meta(),
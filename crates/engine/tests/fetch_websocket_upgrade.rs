@@ -66,6 +66,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_tls: std::collections::BTreeMap::new(),
 		listener_kinds: std::collections::BTreeMap::new(),
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -96,6 +98,8 @@ fn ws_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: Some(NodeId::for_testing(2)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -103,12 +107,10 @@ fn ws_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::WebSocketUpgrade,
-			args: serde_json::json!({ "upstream": upstream }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::WebSocketUpgrade,
+			serde_json::json!({ "upstream": upstream }),
+		)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta,
@@ -138,6 +140,8 @@ fn wss_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: Some(NodeId::for_testing(2)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -145,18 +149,16 @@ fn wss_graph(listen: SocketAddr, upstream: &str) -> Arc<FlowGraph> {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::WebSocketUpgrade,
-			args: serde_json::json!({
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::WebSocketUpgrade,
+			serde_json::json!({
 				"upstream": upstream,
 				"tls": {
 					"insecure_skip_verify": true,
 					"verify_hostname": "localhost",
 				},
 			}),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta,
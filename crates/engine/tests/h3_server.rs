@@ -132,6 +132,8 @@ fn h3_proxy_graph(listen: SocketAddr, upstream: &str, cert: &CertFixture) -> Arc
 		listener_tls,
 		listener_kinds,
 		listener_transports,
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	};
 
@@ -142,6 +144,8 @@ fn h3_proxy_graph(listen: SocketAddr, upstream: &str, cert: &CertFixture) -> Arc
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -149,12 +153,10 @@ fn h3_proxy_graph(listen: SocketAddr, upstream: &str, cert: &CertFixture) -> Arc
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args: serde_json::json!({ "upstream": upstream }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpProxy,
+			serde_json::json!({ "upstream": upstream }),
+		)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta,
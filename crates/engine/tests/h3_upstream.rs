@@ -112,6 +112,8 @@ fn h3_proxy_graph_with_connect_timeout(
 		listener_tls: std::collections::BTreeMap::new(),
 		listener_kinds: std::collections::BTreeMap::new(),
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	};
 
@@ -134,6 +136,8 @@ fn h3_proxy_graph_with_connect_timeout(
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -141,12 +145,7 @@ fn h3_proxy_graph_with_connect_timeout(
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpProxy,
-			args,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpProxy, args)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta,
@@ -89,6 +89,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -183,12 +185,7 @@ fn link_fails_on_unknown_middleware_name() {
 
 #[test]
 fn link_fails_on_unknown_fetch_kind() {
-	let sym = graph_with_fetch(SymbolicFetchRef {
-		kind: FetchKind::HttpProxy,
-		args: Value::Null,
-		retry_buffer_required: false,
-		allow_zero_rtt: None,
-	});
+	let sym = graph_with_fetch(SymbolicFetchRef::new(FetchKind::HttpProxy, Value::Null));
 	let mw = MiddlewareFactories::new();
 	let fetch = FetchFactories::new();
 	let Err(err) = FlowGraph::link(sym, &mw, &fetch) else {
@@ -293,6 +290,8 @@ fn link_preserves_version_hash_but_overrides_feature_set() {
 			listener_kinds: std::collections::BTreeMap::new(),
 
 			listener_transports: std::collections::BTreeMap::new(),
+			listener_proxy_protocol: std::collections::BTreeMap::new(),
+			unix_listeners: std::collections::BTreeMap::new(),
 			annotations: Vec::new(),
 		},
 	});
@@ -346,12 +345,7 @@ fn index_fetch_id_returns_inst() {
 			unreachable!("link tests never drive fetches")
 		}
 	}
-	let sym = graph_with_fetch(SymbolicFetchRef {
-		kind: FetchKind::HttpProxy,
-		args: Value::Null,
-		retry_buffer_required: false,
-		allow_zero_rtt: None,
-	});
+	let sym = graph_with_fetch(SymbolicFetchRef::new(FetchKind::HttpProxy, Value::Null));
 	let mw = MiddlewareFactories::new();
 	let mut fetch = FetchFactories::new();
 	fetch.register(FetchKind::HttpProxy, |_args| Ok(FetchInst::L7(Arc::new(NoopL7Fetch))));
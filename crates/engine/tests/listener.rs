@@ -89,6 +89,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_tls: std::collections::BTreeMap::new(),
 		listener_kinds: std::collections::BTreeMap::new(),
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -643,6 +645,8 @@ async fn shutdown_drains_idle_keep_alive_connections_within_drain_timeout() {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -650,12 +654,7 @@ async fn shutdown_drains_idle_keep_alive_connections_within_drain_timeout() {
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		terminators: vec![Terminator::Close],
 		entries,
 		meta: sample_meta(),
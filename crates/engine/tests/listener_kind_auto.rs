@@ -69,6 +69,8 @@ fn sample_meta(
 		listener_kinds: BTreeMap::new(),
 
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -194,6 +196,8 @@ fn auto_graph(
 			id: FetchId::for_testing(0),
 			next_response: None,
 			next_tunnel: Some(NodeId::for_testing(4)),
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -203,6 +207,8 @@ fn auto_graph(
 			id: FetchId::for_testing(1),
 			next_response: Some(NodeId::for_testing(6)),
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -225,18 +231,8 @@ fn auto_graph(
 
 	let upstream_arg = serde_json::json!({ "upstream": upstream.to_string() });
 	let fetches = vec![
-		SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: upstream_arg,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		},
-		SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: serde_json::Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		},
+		SymbolicFetchRef::new(FetchKind::L4Forward, upstream_arg),
+		SymbolicFetchRef::new(FetchKind::HttpSynthesize, serde_json::Value::Null),
 	];
 
 	let sym = Arc::new(SymbolicFlowGraph {
@@ -289,6 +285,8 @@ fn http_graph(addr: SocketAddr, tls_cfg: vane_core::rule::TlsConfig) -> Arc<Flow
 			id: FetchId::for_testing(0),
 			next_response: Some(NodeId::for_testing(3)),
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -308,12 +306,7 @@ fn http_graph(addr: SocketAddr, tls_cfg: vane_core::rule::TlsConfig) -> Arc<Flow
 		nodes,
 		predicates: vec![],
 		middlewares,
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: serde_json::Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, serde_json::Value::Null)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta: sample_meta(listener_tls),
@@ -336,6 +329,8 @@ fn raw_graph(addr: SocketAddr, upstream: SocketAddr) -> Arc<FlowGraph> {
 			id: FetchId::for_testing(0),
 			next_response: None,
 			next_tunnel: Some(NodeId::for_testing(1)),
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -347,12 +342,7 @@ fn raw_graph(addr: SocketAddr, upstream: SocketAddr) -> Arc<FlowGraph> {
 		nodes,
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args: upstream_arg,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::L4Forward, upstream_arg)],
 		terminators: vec![Terminator::ByteTunnel],
 		entries,
 		meta: sample_meta(BTreeMap::new()),
@@ -202,6 +202,8 @@ fn meta_with_client_auth(
 		listener_tls,
 		listener_kinds: BTreeMap::new(),
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -224,6 +226,8 @@ fn graph_single_route(
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -231,12 +235,10 @@ fn graph_single_route(
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: serde_json::json!({ "tag": tag }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpSynthesize,
+			serde_json::json!({ "tag": tag }),
+		)],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
 		meta,
@@ -276,6 +278,8 @@ fn graph_branching_on_predicate(
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(5)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -283,6 +287,8 @@ fn graph_branching_on_predicate(
 				id: FetchId::for_testing(1),
 				next_response: Some(NodeId::for_testing(5)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -291,18 +297,8 @@ fn graph_branching_on_predicate(
 		predicates: vec![predicate],
 		middlewares: vec![],
 		fetches: vec![
-			SymbolicFetchRef {
-				kind: FetchKind::HttpSynthesize,
-				args: serde_json::json!({ "tag": tag_match }),
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			},
-			SymbolicFetchRef {
-				kind: FetchKind::HttpSynthesize,
-				args: serde_json::json!({ "tag": tag_miss }),
-				retry_buffer_required: false,
-				allow_zero_rtt: None,
-			},
+			SymbolicFetchRef::new(FetchKind::HttpSynthesize, serde_json::json!({ "tag": tag_match })),
+			SymbolicFetchRef::new(FetchKind::HttpSynthesize, serde_json::json!({ "tag": tag_miss })),
 		],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
@@ -0,0 +1,259 @@
+//! Integration tests for `unix:/path` listeners.
+//!
+//! Covers `spec/crates/engine.md` § _Unix listeners_:
+//!
+//! * An `Http` Unix listener serves H1 through the same dispatch path
+//!   as TCP, and `remote.uid` matches the connecting process's
+//!   `SO_PEERCRED` uid.
+//! * `unix_socket.mode` is applied to the socket file.
+//! * A stale socket file is replaced at bind and the listener removes
+//!   its own file on shutdown.
+//! * A live socket at the path is never removed; the bind gives up.
+//! * A handed-off socket (binary upgrade) keeps its file when the old
+//!   listener exits, and the adopting set serves on it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use vane_core::rule::UnixSocketConfig;
+use vane_core::{
+	FetchId, FetchKind, FlowGraphMeta, FlowLogSink, ListenerKind, Node, NodeId, PredicateId,
+	PredicateInst, SymbolicFetchRef, SymbolicFlowGraph, Terminator, TerminatorId, UnixListenerSpec,
+	predicate::{CompiledOperator, CompiledValue, FieldPath},
+};
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_synthesize;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_engine::{BindConfig, InheritedSockets, ListenerSet};
+use vane_testutil::flow::{DropSink, sample_meta};
+
+fn synth(body: &[u8]) -> SymbolicFetchRef {
+	use base64::Engine as _;
+	SymbolicFetchRef::new(
+		FetchKind::HttpSynthesize,
+		serde_json::json!({
+			"status": 200,
+			"body": base64::engine::general_purpose::STANDARD.encode(body),
+		}),
+	)
+}
+
+/// `Check(remote.uid == uid)` → `Upgrade → Fetch("uid-match")` on match,
+/// `Upgrade → Fetch("uid-miss")` on miss; both answer through one
+/// `WriteHttpResponse` terminator. Served only at `path`.
+fn uid_graph(path: &Path, uid: u32, socket: UnixSocketConfig) -> Arc<FlowGraph> {
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Check {
+				predicate: PredicateId::for_testing(0),
+				on_match: NodeId::for_testing(1),
+				on_miss: NodeId::for_testing(4),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Upgrade { next: NodeId::for_testing(2) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(3)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+			Node::Upgrade { next: NodeId::for_testing(5) },
+			Node::Fetch {
+				id: FetchId::for_testing(1),
+				next_response: Some(NodeId::for_testing(3)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+		],
+		predicates: vec![PredicateInst {
+			path: FieldPath::RemoteUid,
+			op: CompiledOperator::Equals(CompiledValue::Int(i64::from(uid))),
+		}],
+		middlewares: vec![],
+		fetches: vec![synth(b"uid-match"), synth(b"uid-miss")],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries: HashMap::new(),
+		meta: FlowGraphMeta {
+			unix_listeners: BTreeMap::from([(
+				path.to_path_buf(),
+				UnixListenerSpec { entry: NodeId::for_testing(0), kind: ListenerKind::Http, socket },
+			)]),
+			..sample_meta()
+		},
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	http_synthesize::register(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link unix graph")
+}
+
+fn start(set: &ListenerSet, graph: Arc<FlowGraph>) {
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+}
+
+async fn wait_bound(set: &ListenerSet, path: &Path) {
+	for _ in 0..100 {
+		if set.is_unix_bound(path) {
+			return;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	panic!("unix listener at {} never bound", path.display());
+}
+
+async fn get(path: &Path) -> (u16, Bytes) {
+	let stream = tokio::net::UnixStream::connect(path).await.expect("connect unix listener");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.uri("/")
+		.header("host", "unix.local")
+		.body(Empty::<Bytes>::new())
+		.expect("build request");
+	let resp = sender.send_request(req).await.expect("send_request");
+	let status = resp.status().as_u16();
+	(status, resp.into_body().collect().await.expect("collect body").to_bytes())
+}
+
+fn socket_path(dir: &tempfile::TempDir) -> PathBuf {
+	dir.path().join("web.sock")
+}
+
+#[tokio::test]
+async fn unix_listener_serves_http_and_matches_peer_uid() {
+	let dir = tempfile::tempdir().expect("tempdir");
+	let path = socket_path(&dir);
+	let uid = std::fs::metadata(dir.path()).expect("stat tempdir").uid();
+	let socket = UnixSocketConfig { mode: Some(0o600), ..UnixSocketConfig::default() };
+
+	let set = ListenerSet::new();
+	start(&set, uid_graph(&path, uid, socket));
+	wait_bound(&set, &path).await;
+
+	let mode = std::fs::metadata(&path).expect("stat socket").permissions().mode();
+	assert_eq!(mode & 0o7777, 0o600, "unix_socket.mode applied to the socket file");
+
+	let (status, body) = get(&path).await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"uid-match", "remote.uid reads the peer's SO_PEERCRED uid");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn unix_listener_predicate_misses_for_other_uid() {
+	let dir = tempfile::tempdir().expect("tempdir");
+	let path = socket_path(&dir);
+	let uid = std::fs::metadata(dir.path()).expect("stat tempdir").uid();
+
+	let set = ListenerSet::new();
+	start(&set, uid_graph(&path, uid.wrapping_add(1), UnixSocketConfig::default()));
+	wait_bound(&set, &path).await;
+
+	let (_, body) = get(&path).await;
+	assert_eq!(body.as_ref(), b"uid-miss");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn stale_socket_is_replaced_and_removed_on_shutdown() {
+	let dir = tempfile::tempdir().expect("tempdir");
+	let path = socket_path(&dir);
+	// A listener that went away without unlinking its file.
+	drop(std::os::unix::net::UnixListener::bind(&path).expect("bind stale socket"));
+	assert!(path.exists(), "stale socket file left behind");
+	let uid = std::fs::metadata(dir.path()).expect("stat tempdir").uid();
+
+	let set = ListenerSet::new();
+	start(&set, uid_graph(&path, uid, UnixSocketConfig::default()));
+	wait_bound(&set, &path).await;
+	let (status, _) = get(&path).await;
+	assert_eq!(status, 200, "listener bound over the stale file");
+
+	set.shutdown(Duration::from_millis(500)).await;
+	assert!(!path.exists(), "listener removes its socket file on exit");
+}
+
+#[tokio::test]
+async fn live_socket_is_left_alone() {
+	let dir = tempfile::tempdir().expect("tempdir");
+	let path = socket_path(&dir);
+	let live = std::os::unix::net::UnixListener::bind(&path).expect("bind live socket");
+	let uid = std::fs::metadata(dir.path()).expect("stat tempdir").uid();
+
+	let set = ListenerSet::from_bind_config(BindConfig {
+		max_bind_attempts: 2,
+		bind_backoff_initial: Duration::from_millis(10),
+		bind_backoff_max: Duration::from_millis(10),
+		..BindConfig::default()
+	});
+	start(&set, uid_graph(&path, uid, UnixSocketConfig::default()));
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	assert!(!set.is_unix_bound(&path), "bind must give up on a live socket");
+	assert_eq!(set.expected_count(), 1);
+
+	// The original owner still accepts on its file.
+	let client = std::os::unix::net::UnixStream::connect(&path).expect("connect live socket");
+	let (_, _) = live.accept().expect("live listener still accepts");
+	drop(client);
+
+	set.shutdown(Duration::from_millis(500)).await;
+	assert!(path.exists(), "a live socket is never unlinked");
+}
+
+#[tokio::test]
+async fn handed_off_socket_keeps_serving_after_the_old_set_exits() {
+	let dir = tempfile::tempdir().expect("tempdir");
+	let path = socket_path(&dir);
+	let uid = std::fs::metadata(dir.path()).expect("stat tempdir").uid();
+
+	let old = ListenerSet::new();
+	start(&old, uid_graph(&path, uid, UnixSocketConfig::default()));
+	wait_bound(&old, &path).await;
+
+	let handoff = old.handoff_sockets();
+	assert_eq!(handoff.len(), 1);
+	let socket = handoff.into_iter().next().expect("one socket");
+	assert_eq!(socket.path.as_deref(), Some(path.as_path()));
+	let mut inherited = InheritedSockets::default();
+	let adopted = inherited
+		.add_unix(std::os::unix::net::UnixListener::from(socket.fd))
+		.expect("adopt handed-off socket");
+	assert_eq!(adopted, path);
+
+	let new = ListenerSet::new().with_inherited_sockets(inherited);
+	start(&new, uid_graph(&path, uid, UnixSocketConfig::default()));
+	wait_bound(&new, &path).await;
+
+	old.shutdown(Duration::from_millis(500)).await;
+	assert!(path.exists(), "a handed-off socket file outlives the old listener");
+	let (status, body) = get(&path).await;
+	assert_eq!(status, 200);
+	assert_eq!(body.as_ref(), b"uid-match");
+
+	new.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,142 @@
+//! Integration tests for `vane_engine::middleware::compress`.
+//!
+//! Drives a real listener with `Upgrade -> Fetch(HttpSynthesize) ->
+//! Middleware(compress) -> Terminate(WriteHttpResponse)` and checks what
+//! a hyper H1 client sees: the executor hands the request's
+//! `Accept-Encoding` to the response phase, the encoded body arrives
+//! chunked without a `content-length`, and a client that asks for
+//! nothing gets the identity body. Negotiation and skip rules are
+//! covered by the in-file unit tests. See `spec/crates/engine.md`
+//! § _Middleware_.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, MiddlewareId, MiddlewareKind, Node, NodeId, SymbolicFetchRef,
+	SymbolicFlowGraph, SymbolicMiddlewareRef, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_synthesize::register as register_synthesize;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::middleware::compress::register as register_compress;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn page() -> String {
+	"<p>hello from a cgi script that never learned to gzip</p>\n".repeat(200)
+}
+
+fn compress_graph(listen: SocketAddr) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Middleware {
+				id: MiddlewareId::for_testing(0),
+				next: NodeId::for_testing(3),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![SymbolicMiddlewareRef {
+			name: Arc::from("compress"),
+			args: serde_json::json!({ "algorithms": ["gzip"] }),
+			kind: MiddlewareKind::L7Response,
+			stateless: true,
+			needs_body: false,
+			on_error: None,
+		}],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpSynthesize,
+			serde_json::json!({
+				"status": 200,
+				"headers": { "content-type": "text/html; charset=utf-8", "etag": "\"page-1\"" },
+				"body": BASE64_STANDARD.encode(page()),
+			}),
+		)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mut mw = MiddlewareFactories::new();
+	register_compress(&mut mw);
+	let mut fetch = FetchFactories::new();
+	register_synthesize(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link compress graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("graph has at least one entry").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+async fn get(addr: SocketAddr, accept_encoding: Option<&str>) -> http::Response<Bytes> {
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let mut req = hyper::Request::builder().uri("/").header("host", "test.local");
+	if let Some(a) = accept_encoding {
+		req = req.header("accept-encoding", a);
+	}
+	let resp = sender.send_request(req.body(Empty::new()).expect("request")).await.expect("send");
+	let (parts, body) = resp.into_parts();
+	http::Response::from_parts(parts, body.collect().await.expect("collect").to_bytes())
+}
+
+#[tokio::test]
+async fn compress_gzips_synthesized_body_over_h1() {
+	let addr = pick_port();
+	let (set, addr) = start_listener(compress_graph(addr)).await;
+
+	let resp = get(addr, Some("gzip, deflate")).await;
+	assert_eq!(resp.status(), 200);
+	assert_eq!(resp.headers()["content-encoding"], "gzip");
+	assert_eq!(resp.headers()["vary"], "accept-encoding");
+	assert_eq!(resp.headers()["etag"], "W/\"page-1\"");
+	assert!(!resp.headers().contains_key("content-length"));
+	let mut decoded = String::new();
+	flate2::read::GzDecoder::new(resp.body().as_ref()).read_to_string(&mut decoded).expect("gunzip");
+	assert_eq!(decoded, page());
+
+	let resp = get(addr, None).await;
+	assert_eq!(resp.status(), 200);
+	assert!(!resp.headers().contains_key("content-encoding"));
+	assert_eq!(resp.headers()["vary"], "accept-encoding");
+	assert_eq!(resp.body().as_ref(), page().as_bytes());
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
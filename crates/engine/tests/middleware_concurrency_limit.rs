@@ -0,0 +1,174 @@
+//! Integration tests for `vane_engine::middleware::concurrency_limit`.
+//!
+//! Validates the contract in the doc-comment on
+//! `concurrency_limit::factory`:
+//!
+//! - A permit is held while the request's `StreamGuards` live, and is
+//!   released when they drop (the executor ties them to the response
+//!   body; here the test drops them directly).
+//! - Per-key isolation follows `rate_limit`'s key derivations.
+//! - The optional queue admits waiters in FIFO order up to `size`,
+//!   and times out to the `on_limit` response (default 503).
+//! - Factory validation: missing / zero `max`, malformed `queue`.
+//!
+//! Treats the middleware as a black box — calls it via the
+//! registered factory.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+use vane_core::{
+	ConnContext, ConnId, Decision, FlowCtx, FlowLogSink, FlowLogVerbosity, ShortCircuit,
+	StreamGuards, TrajectoryBuilder, Transport,
+};
+use vane_engine::factories::{MiddlewareFactories, MiddlewareFactoryEntry};
+use vane_engine::flow_graph::MiddlewareInst;
+use vane_engine::middleware::concurrency_limit;
+use vane_testutil::flow::DropSink;
+
+// fixtures
+fn make_conn(remote: &str) -> Arc<ConnContext> {
+	let remote: SocketAddr = remote.parse().expect("remote");
+	let local: SocketAddr = "127.0.0.1:0".parse().expect("local");
+	Arc::new(ConnContext::new(ConnId(1), remote, local, Transport::Tcp, Instant::now()))
+}
+
+fn make_ctx() -> FlowCtx {
+	FlowCtx {
+		span: tracing::Span::none(),
+		log: Arc::new(DropSink) as Arc<dyn FlowLogSink>,
+		cancel: CancellationToken::new(),
+		accept_cancel: CancellationToken::new(),
+		verbosity: FlowLogVerbosity::Trajectory,
+		trajectory: TrajectoryBuilder::new(ConnId(1), vane_core::NodeId::for_testing(0), 0),
+	}
+}
+
+fn request(headers: &[(&str, &str)]) -> http::Request<vane_core::Body> {
+	let mut b = http::Request::builder().method("GET").uri("/");
+	for (k, v) in headers {
+		b = b.header(*k, *v);
+	}
+	b.body(vane_core::Body::Empty).expect("req")
+}
+
+fn build_middleware(args: &serde_json::Value) -> Arc<dyn vane_core::L7RequestMiddleware> {
+	let mut mw = MiddlewareFactories::new();
+	concurrency_limit::register(&mut mw);
+	let MiddlewareFactoryEntry::Available { construct, .. } =
+		mw.get("concurrency_limit").expect("concurrency_limit registered")
+	else {
+		panic!("expected available factory");
+	};
+	match construct(args).expect("factory accepts args") {
+		MiddlewareInst::L7Request(m) => m,
+		other => panic!("expected L7Request, got {:?}", other.kind()),
+	}
+}
+
+fn factory_err(args: &serde_json::Value) -> String {
+	let mut mw = MiddlewareFactories::new();
+	concurrency_limit::register(&mut mw);
+	let MiddlewareFactoryEntry::Available { construct, .. } =
+		mw.get("concurrency_limit").expect("concurrency_limit registered")
+	else {
+		panic!("expected available factory");
+	};
+	match construct(args) {
+		Ok(_) => panic!("factory must reject these args"),
+		Err(e) => e.message().into_owned(),
+	}
+}
+
+/// Run the middleware; on `Continue` return the request's guards (the
+/// held permit), on a short-circuit return the response status.
+async fn call(
+	mw: &dyn vane_core::L7RequestMiddleware,
+	conn: &Arc<ConnContext>,
+	headers: &[(&str, &str)],
+) -> Result<StreamGuards, u16> {
+	let mut req = request(headers);
+	let mut ctx = make_ctx();
+	match mw.run(&mut req, conn, &mut ctx).await.expect("middleware run") {
+		Decision::Continue => Ok(req.extensions_mut().remove::<StreamGuards>().expect("permit guard")),
+		Decision::Short(ShortCircuit::Response(r)) => Err(r.status().as_u16()),
+		_ => panic!("unexpected decision"),
+	}
+}
+
+#[tokio::test]
+async fn concurrency_limit_caps_in_flight_until_guards_drop() {
+	let mw = build_middleware(&serde_json::json!({ "max": 2 }));
+	let conn = make_conn("1.2.3.4:1000");
+	let a = call(&*mw, &conn, &[]).await.expect("first admitted");
+	let _b = call(&*mw, &conn, &[]).await.expect("second admitted");
+	assert_eq!(call(&*mw, &conn, &[]).await.err(), Some(503), "third over the cap");
+
+	// Response body finished streaming → permit back.
+	drop(a);
+	assert!(call(&*mw, &conn, &[]).await.is_ok(), "slot freed");
+}
+
+#[tokio::test]
+async fn concurrency_limit_isolates_keys() {
+	let mw = build_middleware(&serde_json::json!({ "max": 1 }));
+	let _a = call(&*mw, &make_conn("1.1.1.1:1000"), &[]).await.expect("a");
+	assert!(call(&*mw, &make_conn("2.2.2.2:1000"), &[]).await.is_ok(), "other ip own slot");
+
+	let mw = build_middleware(&serde_json::json!({ "max": 1, "key": { "header": "x-api-key" } }));
+	let conn = make_conn("1.1.1.1:1000");
+	let _k1 = call(&*mw, &conn, &[("x-api-key", "k1")]).await.expect("k1");
+	assert_eq!(call(&*mw, &conn, &[("x-api-key", "k1")]).await.err(), Some(503));
+	assert!(call(&*mw, &conn, &[("x-api-key", "k2")]).await.is_ok(), "k2 own slot");
+
+	let mw = build_middleware(&serde_json::json!({ "max": 1, "key": "global" }));
+	let _g = call(&*mw, &make_conn("1.1.1.1:1000"), &[]).await.expect("global");
+	assert_eq!(call(&*mw, &make_conn("2.2.2.2:1000"), &[]).await.err(), Some(503));
+}
+
+#[tokio::test]
+async fn concurrency_limit_queue_admits_waiter_when_a_permit_frees() {
+	let mw =
+		build_middleware(&serde_json::json!({ "max": 1, "queue": { "size": 1, "timeout": "5s" } }));
+	let conn = make_conn("1.2.3.4:1000");
+	let held = call(&*mw, &conn, &[]).await.expect("holder");
+
+	let waiter = {
+		let mw = Arc::clone(&mw);
+		let conn = Arc::clone(&conn);
+		tokio::spawn(async move { call(&*mw, &conn, &[]).await.is_ok() })
+	};
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	// Queue is full (size 1): a third request is rejected straight away.
+	assert_eq!(call(&*mw, &conn, &[]).await.err(), Some(503), "queue full");
+
+	drop(held);
+	assert!(waiter.await.expect("join"), "waiter admitted once the permit freed");
+}
+
+#[tokio::test]
+async fn concurrency_limit_queue_timeout_returns_custom_on_limit() {
+	let mw = build_middleware(&serde_json::json!({
+		"max": 1,
+		"queue": { "size": 4, "timeout": "100ms" },
+		"on_limit": { "status": 429 }
+	}));
+	let conn = make_conn("1.2.3.4:1000");
+	let _held = call(&*mw, &conn, &[]).await.expect("holder");
+	assert_eq!(call(&*mw, &conn, &[]).await.err(), Some(429), "timed out in queue");
+}
+
+// factory validation
+#[test]
+fn concurrency_limit_factory_rejects_bad_args() {
+	assert!(factory_err(&serde_json::json!({})).contains("args.max"));
+	assert!(factory_err(&serde_json::json!({ "max": 0 })).contains("args.max"));
+	assert!(factory_err(&serde_json::json!({ "max": 1, "queue": {} })).contains("queue.size"));
+	let err =
+		factory_err(&serde_json::json!({ "max": 1, "queue": { "size": 1, "timeout": "soon" } }));
+	assert!(err.contains("queue.timeout"), "{err}");
+	let err = factory_err(&serde_json::json!({ "max": 1, "key": "bogus" }));
+	assert!(err.contains("\"bogus\""), "{err}");
+}
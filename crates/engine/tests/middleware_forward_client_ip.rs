@@ -69,6 +69,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_kinds: std::collections::BTreeMap::new(),
 
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -157,18 +159,15 @@ fn link_graph(fwd_args: Value) -> (Arc<FlowGraph>, Arc<Mutex<Option<http::Header
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
 			Node::Terminate(TerminatorId::for_testing(0)),
 		],
 		vec![l7_req_ref_with_args("forward_client_ip", fwd_args)],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 	let mut mw = MiddlewareFactories::new();
@@ -199,18 +198,15 @@ fn link_graph_expect_err(fwd_args: Value) -> String {
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
 			Node::Terminate(TerminatorId::for_testing(0)),
 		],
 		vec![l7_req_ref_with_args("forward_client_ip", fwd_args)],
-		vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
 		vec![Terminator::WriteHttpResponse],
 	);
 	let mut mw = MiddlewareFactories::new();
@@ -0,0 +1,197 @@
+//! Integration tests for `vane_engine::middleware::headers`.
+//!
+//! Drives a real listener with `Upgrade -> Middleware(request_headers)
+//! -> Fetch(HttpProxy) -> Middleware(response_headers) ->
+//! Terminate(WriteHttpResponse)` against an upstream that echoes the
+//! request headers it received. Checks that the request side reaches
+//! the upstream with templated values filled in and a forged identity
+//! header dropped, and that the response side strips and adds headers
+//! while reading the original request through the attached head.
+//! Operation order and args validation are covered by the in-file unit
+//! tests. See `spec/crates/engine.md` § _Middleware_.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, MiddlewareId, MiddlewareKind, Node, NodeId, SymbolicFetchRef,
+	SymbolicFlowGraph, SymbolicMiddlewareRef, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::middleware::headers::register as register_headers;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+/// Upstream that answers with `Server` / `X-Powered-By` and a body of
+/// `name: value` lines, one per request header it received.
+async fn spawn_echo_upstream() -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			tokio::spawn(async move {
+				let svc = service_fn(|req: hyper::Request<hyper::body::Incoming>| async move {
+					let mut body = String::new();
+					for (name, value) in req.headers() {
+						let _ = writeln!(body, "{name}: {}", value.to_str().unwrap_or("?"));
+					}
+					let resp = hyper::Response::builder()
+						.header("server", "upstream/1.0")
+						.header("x-powered-by", "php/8")
+						.body(Full::new(Bytes::from(body)))
+						.expect("response");
+					Ok::<_, Infallible>(resp)
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	addr
+}
+
+fn middleware(name: &str, kind: MiddlewareKind, args: serde_json::Value) -> SymbolicMiddlewareRef {
+	SymbolicMiddlewareRef {
+		name: Arc::from(name),
+		args,
+		kind,
+		stateless: true,
+		needs_body: false,
+		on_error: None,
+	}
+}
+
+fn headers_graph(listen: SocketAddr, upstream: SocketAddr) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Middleware {
+				id: MiddlewareId::for_testing(0),
+				next: NodeId::for_testing(2),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(3)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Middleware {
+				id: MiddlewareId::for_testing(1),
+				next: NodeId::for_testing(4),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![
+			middleware(
+				"request_headers",
+				MiddlewareKind::L7Request,
+				serde_json::json!({
+					"set": {
+						"x-client-ip": "${remote.ip}",
+						"x-client-cn": "${tls.peer_cert.subject_cn}",
+						"x-original-path": "${http.uri.path}",
+					},
+				}),
+			),
+			middleware(
+				"response_headers",
+				MiddlewareKind::L7Response,
+				serde_json::json!({
+					"remove": ["server", "x-powered-by"],
+					"set": {
+						"strict-transport-security": "max-age=63072000; includeSubDomains",
+						"x-trace": "${http.header.x-trace}",
+					},
+				}),
+			),
+		],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpProxy,
+			serde_json::json!({ "upstream": upstream.to_string(), "version": "h1" }),
+		)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mut mw = MiddlewareFactories::new();
+	register_headers(&mut mw);
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link headers graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("graph has at least one entry").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+#[tokio::test]
+async fn rewrites_both_sides_of_the_fetch() {
+	vane_engine::crypto::install_default_provider();
+	let upstream = spawn_echo_upstream().await;
+	let (set, addr) = start_listener(headers_graph(pick_port(), upstream)).await;
+
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.uri("/orders/7")
+		.header("host", "test.local")
+		.header("x-client-cn", "forged-admin")
+		.header("x-trace", "t-99")
+		.body(Empty::new())
+		.expect("build");
+	let resp = sender.send_request(req).await.expect("send");
+
+	assert_eq!(resp.status(), 200);
+	let h = resp.headers().clone();
+	assert!(!h.contains_key("server"), "server must be stripped: {h:?}");
+	assert!(!h.contains_key("x-powered-by"), "x-powered-by must be stripped: {h:?}");
+	assert_eq!(h["strict-transport-security"], "max-age=63072000; includeSubDomains");
+	assert_eq!(h["x-trace"], "t-99");
+
+	let body = resp.into_body().collect().await.expect("collect").to_bytes();
+	let seen = String::from_utf8(body.to_vec()).expect("utf8");
+	assert!(seen.contains("x-client-ip: 127.0.0.1\n"), "{seen}");
+	assert!(seen.contains("x-original-path: /orders/7\n"), "{seen}");
+	// Cleartext connection: no certificate, so the forged value is gone.
+	assert!(!seen.contains("x-client-cn"), "{seen}");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -8,7 +8,11 @@
 //!   yields `Decision::Short(ShortCircuit::Response(_))` with the
 //!   configured (default 429) status.
 //! - Per-key isolation: `key=remote_ip` keeps separate buckets per
-//!   client IP; `key=global` shares one bucket across all clients.
+//!   client IP (IPv6 per /64); header / cookie / query / composite
+//!   keys bucket per value; `key=global` shares one bucket across all
+//!   clients.
+//! - Rejection carries `Retry-After` and `RateLimit-*` headers.
+//! - `max_keys` bounds the bucket map.
 //! - Factory validation: `window` range, missing `rate`/`burst`,
 //!   `burst==0`, unsupported `key`, malformed `on_limit`.
 //!
@@ -53,7 +57,15 @@ fn make_ctx() -> FlowCtx {
 }
 
 fn empty_request() -> http::Request<vane_core::Body> {
-	http::Request::builder().method("GET").uri("/").body(vane_core::Body::Empty).expect("req")
+	request("/", &[])
+}
+
+fn request(uri: &str, headers: &[(&str, &str)]) -> http::Request<vane_core::Body> {
+	let mut b = http::Request::builder().method("GET").uri(uri);
+	for (k, v) in headers {
+		b = b.header(*k, *v);
+	}
+	b.body(vane_core::Body::Empty).expect("req")
 }
 
 /// Build a registered `RateLimitMiddleware` via the factory + return
@@ -79,7 +91,14 @@ fn build_middleware(args: &serde_json::Value) -> Arc<dyn vane_core::L7RequestMid
 }
 
 async fn try_call(mw: &dyn vane_core::L7RequestMiddleware, conn: &Arc<ConnContext>) -> Decision {
-	let mut req = empty_request();
+	try_call_with(mw, conn, empty_request()).await
+}
+
+async fn try_call_with(
+	mw: &dyn vane_core::L7RequestMiddleware,
+	conn: &Arc<ConnContext>,
+	mut req: http::Request<vane_core::Body>,
+) -> Decision {
 	let mut ctx = make_ctx();
 	mw.run(&mut req, conn, &mut ctx).await.expect("middleware run")
 }
@@ -161,6 +180,171 @@ async fn rate_limit_global_key_shared_across_ips() {
 	}
 }
 
+#[tokio::test]
+async fn rate_limit_ipv6_clients_share_a_slash_64_bucket() {
+	let mw = build_middleware(&serde_json::json!({ "rate": 1, "burst": 1, "window": "60s" }));
+	let a = make_conn("[2001:db8:1:2::1]:1000");
+	let same_64 = make_conn("[2001:db8:1:2:ffff::9]:1000");
+	let other_64 = make_conn("[2001:db8:1:3::1]:1000");
+
+	assert!(matches!(try_call(&*mw, &a).await, Decision::Continue));
+	assert!(
+		matches!(try_call(&*mw, &same_64).await, Decision::Short(_)),
+		"rotated address in same /64"
+	);
+	assert!(
+		matches!(try_call(&*mw, &other_64).await, Decision::Continue),
+		"next /64 has its own bucket"
+	);
+
+	// `ipv6_prefix: 128` restores per-address buckets.
+	let mw = build_middleware(
+		&serde_json::json!({ "rate": 1, "burst": 1, "window": "60s", "ipv6_prefix": 128 }),
+	);
+	assert!(matches!(try_call(&*mw, &a).await, Decision::Continue));
+	assert!(matches!(try_call(&*mw, &same_64).await, Decision::Continue));
+}
+
+#[tokio::test]
+async fn rate_limit_header_key_buckets_per_value_and_falls_back_to_ip() {
+	let mw = build_middleware(&serde_json::json!({
+		"key": { "header": "x-api-key" }, "rate": 1, "burst": 1, "window": "60s"
+	}));
+	let conn = make_conn("1.1.1.1:1000");
+	let key_a = || request("/", &[("x-api-key", "alpha")]);
+	let key_b = || request("/", &[("x-api-key", "beta")]);
+
+	assert!(matches!(try_call_with(&*mw, &conn, key_a()).await, Decision::Continue));
+	assert!(
+		matches!(try_call_with(&*mw, &conn, key_a()).await, Decision::Short(_)),
+		"alpha exhausted"
+	);
+	assert!(
+		matches!(try_call_with(&*mw, &conn, key_b()).await, Decision::Continue),
+		"beta has own bucket"
+	);
+
+	// No header: keyed by remote IP, so omitting the key is still limited.
+	assert!(matches!(try_call(&*mw, &conn).await, Decision::Continue));
+	assert!(matches!(try_call(&*mw, &conn).await, Decision::Short(_)), "keyless ip exhausted");
+	assert!(matches!(try_call(&*mw, &make_conn("2.2.2.2:1000")).await, Decision::Continue));
+}
+
+#[tokio::test]
+async fn rate_limit_cookie_and_query_keys_read_the_named_value() {
+	let mw = build_middleware(&serde_json::json!({
+		"key": { "cookie": "session" }, "rate": 1, "burst": 1, "window": "60s"
+	}));
+	let conn = make_conn("1.1.1.1:1000");
+	let s1 = || request("/", &[("cookie", "theme=dark; session=s1")]);
+	assert!(matches!(try_call_with(&*mw, &conn, s1()).await, Decision::Continue));
+	assert!(matches!(try_call_with(&*mw, &conn, s1()).await, Decision::Short(_)));
+	let s2 = request("/", &[("cookie", "session=s2")]);
+	assert!(matches!(try_call_with(&*mw, &conn, s2).await, Decision::Continue));
+
+	let mw = build_middleware(&serde_json::json!({
+		"key": { "query": "api_key" }, "rate": 1, "burst": 1, "window": "60s"
+	}));
+	assert!(matches!(
+		try_call_with(&*mw, &conn, request("/?api_key=abc", &[])).await,
+		Decision::Continue
+	));
+	// Percent-encoding the same value does not dodge the bucket.
+	assert!(matches!(
+		try_call_with(&*mw, &conn, request("/x?page=2&api_key=%61bc", &[])).await,
+		Decision::Short(_)
+	));
+	assert!(matches!(
+		try_call_with(&*mw, &conn, request("/?api_key=xyz", &[])).await,
+		Decision::Continue
+	));
+}
+
+#[tokio::test]
+async fn rate_limit_composite_key_combines_parts() {
+	let mw = build_middleware(&serde_json::json!({
+		"key": { "composite": ["remote_ip", { "header": "x-tenant" }] },
+		"rate": 1, "burst": 1, "window": "60s"
+	}));
+	let a = make_conn("1.1.1.1:1000");
+	let b = make_conn("2.2.2.2:1000");
+	let t1 = || request("/", &[("x-tenant", "t1")]);
+
+	assert!(matches!(try_call_with(&*mw, &a, t1()).await, Decision::Continue));
+	assert!(matches!(try_call_with(&*mw, &a, t1()).await, Decision::Short(_)));
+	assert!(
+		matches!(try_call_with(&*mw, &b, t1()).await, Decision::Continue),
+		"other ip, same tenant"
+	);
+	let t2 = request("/", &[("x-tenant", "t2")]);
+	assert!(matches!(try_call_with(&*mw, &a, t2).await, Decision::Continue), "same ip, other tenant");
+}
+
+#[tokio::test]
+async fn rate_limit_rejection_carries_retry_after_and_ratelimit_headers() {
+	let mw = build_middleware(&serde_json::json!({ "rate": 6, "burst": 1, "window": "60s" }));
+	let conn = make_conn("1.2.3.4:1000");
+	assert!(matches!(try_call(&*mw, &conn).await, Decision::Continue));
+	let Decision::Short(ShortCircuit::Response(r)) = try_call(&*mw, &conn).await else {
+		panic!("expected Short(Response(429))");
+	};
+	let header = |name: &str| r.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_owned);
+	// 6 per 60s refills one token every 10s.
+	assert_eq!(header("retry-after").as_deref(), Some("10"));
+	assert_eq!(header("ratelimit-limit").as_deref(), Some("6"));
+	assert_eq!(header("ratelimit-remaining").as_deref(), Some("0"));
+	assert_eq!(header("ratelimit-reset").as_deref(), Some("10"));
+	assert_eq!(header("ratelimit-policy").as_deref(), Some("6;w=60;burst=1"));
+
+	// Operator headers win; the rest can be switched off.
+	let mw = build_middleware(&serde_json::json!({
+		"rate": 1, "burst": 1, "window": "60s",
+		"on_limit": { "headers": { "retry-after": "120" } }
+	}));
+	assert!(matches!(try_call(&*mw, &conn).await, Decision::Continue));
+	let Decision::Short(ShortCircuit::Response(r)) = try_call(&*mw, &conn).await else {
+		panic!("expected Short(Response(429))");
+	};
+	let retry: Vec<_> = r.headers().get_all("retry-after").iter().collect();
+	assert_eq!(retry, ["120"]);
+
+	let mw = build_middleware(&serde_json::json!({
+		"rate": 1, "burst": 1, "window": "60s",
+		"on_limit": { "rate_limit_headers": false }
+	}));
+	assert!(matches!(try_call(&*mw, &conn).await, Decision::Continue));
+	let Decision::Short(ShortCircuit::Response(r)) = try_call(&*mw, &conn).await else {
+		panic!("expected Short(Response(429))");
+	};
+	assert!(r.headers().get("retry-after").is_none());
+	assert!(r.headers().get("ratelimit-limit").is_none());
+}
+
+#[tokio::test]
+async fn rate_limit_max_keys_sends_new_keys_to_the_overflow_bucket() {
+	// Two distinct keys fit; the third, fourth, ... share one overflow
+	// bucket while the first two keep their own state.
+	let mw = build_middleware(&serde_json::json!({
+		"key": { "header": "x-k" }, "rate": 1, "burst": 1, "window": "60s", "max_keys": 2
+	}));
+	let conn = make_conn("1.1.1.1:1000");
+	let k = |v: &'static str| request("/", &[("x-k", v)]);
+	assert!(matches!(try_call_with(&*mw, &conn, k("a")).await, Decision::Continue));
+	assert!(matches!(try_call_with(&*mw, &conn, k("b")).await, Decision::Continue));
+	assert!(
+		matches!(try_call_with(&*mw, &conn, k("c")).await, Decision::Continue),
+		"overflow bucket"
+	);
+	assert!(
+		matches!(try_call_with(&*mw, &conn, k("d")).await, Decision::Short(_)),
+		"overflow shared"
+	);
+	assert!(
+		matches!(try_call_with(&*mw, &conn, k("a")).await, Decision::Short(_)),
+		"a kept its bucket"
+	);
+}
+
 #[tokio::test]
 async fn rate_limit_custom_on_limit_status_and_body() {
 	use base64::Engine as _;
@@ -205,8 +389,27 @@ fn rate_limit_factory_validates_window_range() {
 fn rate_limit_factory_rejects_unsupported_key() {
 	let err =
 		factory_err(&serde_json::json!({ "key": "header", "rate": 1, "burst": 1, "window": "1s" }));
-	assert!(err.contains("post-MVP"), "error mentions post-MVP: {err}");
+	assert!(err.contains("supported:"), "error lists the supported keys: {err}");
 	assert!(err.contains("\"header\""), "error quotes the offending key: {err}");
+
+	let err = factory_err(
+		&serde_json::json!({ "key": { "header": "bad name" }, "rate": 1, "burst": 1, "window": "1s" }),
+	);
+	assert!(err.contains("args.key.header"), "{err}");
+	let err = factory_err(
+		&serde_json::json!({ "key": { "composite": [] }, "rate": 1, "burst": 1, "window": "1s" }),
+	);
+	assert!(err.contains("non-empty array"), "{err}");
+	let err = factory_err(
+		&serde_json::json!({ "key": { "composite": ["global"] }, "rate": 1, "burst": 1, "window": "1s" }),
+	);
+	assert!(err.contains("\"global\""), "global is not a composite part: {err}");
+	let err =
+		factory_err(&serde_json::json!({ "rate": 1, "burst": 1, "window": "1s", "ipv6_prefix": 129 }));
+	assert!(err.contains("0..=128"), "{err}");
+	let err =
+		factory_err(&serde_json::json!({ "rate": 1, "burst": 1, "window": "1s", "max_keys": 0 }));
+	assert!(err.contains("max_keys"), "{err}");
 }
 
 #[test]
@@ -0,0 +1,188 @@
+//! Integration tests for `vane_engine::middleware::rewrite`.
+//!
+//! Drives a real listener with `Upgrade -> Middleware(redirect) ->
+//! Middleware(rewrite) -> Fetch(HttpProxy) -> Terminate(WriteHttpResponse)`
+//! against an upstream that echoes the request target it received.
+//! Checks that a matching request is answered with the redirect before
+//! the fetch, and that everything else reaches the upstream with the
+//! rewritten path and query. Prefix boundaries, capture expansion and
+//! args validation are covered by the in-file unit tests. See
+//! `spec/crates/engine.md` § _Middleware_.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, MiddlewareId, MiddlewareKind, Node, NodeId, SymbolicFetchRef,
+	SymbolicFlowGraph, SymbolicMiddlewareRef, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_proxy::register as register_http_proxy;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::middleware::rewrite::register as register_rewrite;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+/// Upstream that answers with the request target it received.
+async fn spawn_echo_upstream() -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+	let addr = listener.local_addr().expect("local_addr");
+	tokio::spawn(async move {
+		loop {
+			let Ok((sock, _)) = listener.accept().await else { return };
+			tokio::spawn(async move {
+				let svc = service_fn(|req: hyper::Request<hyper::body::Incoming>| async move {
+					let target = req.uri().path_and_query().map_or("/", |pq| pq.as_str()).to_owned();
+					Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(target))))
+				});
+				let _ = hyper::server::conn::http1::Builder::new()
+					.serve_connection(TokioIo::new(sock), svc)
+					.await;
+			});
+		}
+	});
+	addr
+}
+
+fn middleware(name: &str, args: serde_json::Value) -> SymbolicMiddlewareRef {
+	SymbolicMiddlewareRef {
+		name: Arc::from(name),
+		args,
+		kind: MiddlewareKind::L7Request,
+		stateless: true,
+		needs_body: false,
+		on_error: None,
+	}
+}
+
+fn rewrite_graph(listen: SocketAddr, upstream: SocketAddr) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Middleware {
+				id: MiddlewareId::for_testing(0),
+				next: NodeId::for_testing(2),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Middleware {
+				id: MiddlewareId::for_testing(1),
+				next: NodeId::for_testing(3),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(4)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![
+			middleware(
+				"redirect",
+				serde_json::json!({
+					"pattern": "^/old/([^?]*)",
+					"location": "https://${http.header.host}/new/$1",
+					"status": 301,
+				}),
+			),
+			middleware(
+				"rewrite",
+				serde_json::json!({
+					"strip_prefix": "/api",
+					"replace": { "pattern": "^/users/(\\d+)$", "with": "/users?id=$1" },
+					"add_prefix": "/v2",
+				}),
+			),
+		],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::HttpProxy,
+			serde_json::json!({ "upstream": upstream.to_string(), "version": "h1" }),
+		)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: {
+			// `redirect` short-circuits from the post-upgrade entry; its
+			// response goes straight to the terminator.
+			let mut meta = sample_meta();
+			meta.short_circuit_response_entry.insert(NodeId::for_testing(1), NodeId::for_testing(4));
+			meta
+		},
+	});
+	let mut mw = MiddlewareFactories::new();
+	register_rewrite(&mut mw);
+	let mut fetch = FetchFactories::new();
+	register_http_proxy(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link rewrite graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> (ListenerSet, SocketAddr) {
+	let addr = *graph.symbolic().entries.iter().next().expect("graph has at least one entry").0;
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	(set, addr)
+}
+
+async fn get(addr: SocketAddr, target: &str) -> hyper::Response<hyper::body::Incoming> {
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let req = hyper::Request::builder()
+		.uri(target)
+		.header("host", "test.local")
+		.body(Empty::new())
+		.expect("build");
+	sender.send_request(req).await.expect("send")
+}
+
+async fn body(resp: hyper::Response<hyper::body::Incoming>) -> String {
+	let bytes = resp.into_body().collect().await.expect("collect").to_bytes();
+	String::from_utf8(bytes.to_vec()).expect("utf8")
+}
+
+#[tokio::test]
+async fn redirects_before_the_fetch_and_rewrites_the_rest() {
+	vane_engine::crypto::install_default_provider();
+	let upstream = spawn_echo_upstream().await;
+	let (set, addr) = start_listener(rewrite_graph(pick_port(), upstream)).await;
+
+	let resp = get(addr, "/old/docs/intro").await;
+	assert_eq!(resp.status(), 301);
+	assert_eq!(resp.headers()["location"], "https://test.local/new/docs/intro");
+
+	let resp = get(addr, "/api/users/42").await;
+	assert_eq!(resp.status(), 200);
+	assert_eq!(body(resp).await, "/v2/users?id=42");
+
+	let resp = get(addr, "/api/orders?page=2").await;
+	assert_eq!(body(resp).await, "/v2/orders?page=2");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
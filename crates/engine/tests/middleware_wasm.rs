@@ -4,8 +4,8 @@
 //! cover the decision-translation table and error-routing rules specified in
 //! `spec/crates/engine.md`.
 //!
-//! Test cases: (a) Continue, (b) Short synth response, (c) Close,
-//! (d) plugin error with no hint routes via `on_error`,
+//! Test cases: (a) Continue, (b) Short synth response, (c) Close and
+//! Ban, (d) plugin error with no hint routes via `on_error`,
 //! (e) plugin error with force-close hint bypasses `on_error`,
 //! (f) `PluginError::Trap` propagates as Err, (g) stateless dedup via Arc.
 
@@ -20,11 +20,12 @@ use async_trait::async_trait;
 use parking_lot::Mutex;
 use tokio_util::sync::CancellationToken;
 use vane_core::{
-	Body, ConnContext, ConnId, Error, FlowCtx, FlowGraphMeta, FlowLogEvent, FlowLogKind, FlowLogSink,
-	Header, L4BytesDecision, L4BytesInput, L4Conn, L4PeekDecision, L4PeekInput, L7RequestDecision,
-	L7RequestInput, L7ResponseDecision, L7ResponseInput, MiddlewareId, MiddlewareKind, ModuleId,
-	Node, NodeId, PeekResult, PluginError, PluginExport, PluginMetadata, Request, SymbolicFlowGraph,
-	SymbolicMiddlewareRef, SynthResponse, Terminator, TerminatorId, Transport, WasmRuntime,
+	BanRequest, Body, ConnContext, ConnId, Error, FlowCtx, FlowGraphMeta, FlowLogEvent, FlowLogKind,
+	FlowLogSink, Header, L4BytesDecision, L4BytesInput, L4Conn, L4PeekDecision, L4PeekInput,
+	L7RequestDecision, L7RequestInput, L7ResponseDecision, L7ResponseInput, MiddlewareId,
+	MiddlewareKind, ModuleId, Node, NodeId, PeekResult, PluginError, PluginExport, PluginMetadata,
+	Request, SymbolicFlowGraph, SymbolicMiddlewareRef, SynthResponse, Terminator, TerminatorId,
+	Transport, WasmRuntime,
 };
 use vane_engine::executor::{ExecutorInput, ExecutorOutput, execute};
 use vane_engine::factories::{FetchFactories, MiddlewareFactories};
@@ -68,6 +69,8 @@ fn sample_meta() -> FlowGraphMeta {
 		listener_tls: std::collections::BTreeMap::new(),
 		listener_kinds: std::collections::BTreeMap::new(),
 		listener_transports: std::collections::BTreeMap::new(),
+		listener_proxy_protocol: std::collections::BTreeMap::new(),
+		unix_listeners: std::collections::BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -403,6 +406,45 @@ async fn wasm_l7request_close_returns_closed() {
 	);
 }
 
+#[tokio::test]
+async fn wasm_l7request_ban_closes_without_listener_floor() {
+	let ban = BanRequest { duration_secs: Some(60), reason: "probe".to_owned() };
+	let runtime = Arc::new(MockWasmRuntime::with_l7_request(vec![Ok(L7RequestDecision::Ban(ban))]));
+
+	let sym = build_graph(
+		vec![
+			Node::Middleware {
+				id: MiddlewareId::for_testing(0),
+				next: NodeId::for_testing(1),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		vec![wasm_symref("my-plugin:probe", MiddlewareKind::L7Request)],
+		vec![Terminator::Close],
+	);
+	let reg = make_registry("my-plugin:probe", "probe", MiddlewareKind::L7Request, runtime);
+	let graph = link_with_plugins(sym, &reg);
+	let conn = make_conn();
+	let sink = Arc::new(NullSink::new());
+
+	let result = run_execute(
+		&graph,
+		NodeId::for_testing(0),
+		ExecutorInput::L7(Box::new(empty_request())),
+		&conn,
+		&sink,
+	)
+	.await;
+
+	assert!(
+		matches!(result, Ok(ExecutorOutput::Closed)),
+		"plugin Ban must close the connection: {result:?}",
+	);
+}
+
 // (d) PluginError with on_error_hint:None + configured on_error → fires node
 
 #[tokio::test]
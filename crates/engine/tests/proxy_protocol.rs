@@ -0,0 +1,384 @@
+//! Integration tests for PROXY protocol on both edges of the proxy.
+//!
+//! Covers `spec/crates/engine.md` § _PROXY protocol_:
+//!
+//! * A listener with `listener_proxy_protocol` consumes a v1 / v2 header
+//!   from a trusted peer and serves the connection as the header's
+//!   source — observed here through the header `tcp_forward` emits
+//!   upstream, which names `ConnContext.remote`.
+//! * An untrusted peer's bytes are not parsed: they reach the upstream
+//!   verbatim.
+//! * A malformed header from a trusted peer drops the connection.
+//! * `http_proxy` with `send_proxy_protocol` opens each upstream
+//!   connection with a header naming the client, and reuses it only
+//!   for that client connection's keep-alive requests.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use proxy_protocol::{Header, Version};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use vane_core::rule::ProxyProtocolConfig;
+use vane_core::{
+	FetchId, FetchKind, FlowGraphMeta, FlowLogSink, Node, NodeId, SymbolicFetchRef,
+	SymbolicFlowGraph, Terminator, TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::{http_proxy, l4_forward};
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+fn meta(listen: SocketAddr, trusted: &str) -> FlowGraphMeta {
+	let cfg = ProxyProtocolConfig { trusted: vec![trusted.parse().expect("parse trusted cidr")] };
+	FlowGraphMeta { listener_proxy_protocol: BTreeMap::from([(listen, cfg)]), ..sample_meta() }
+}
+
+/// `Fetch(L4Forward) -> Terminate(ByteTunnel)`, accepting headers from
+/// `trusted`.
+fn l4_graph(listen: SocketAddr, trusted: &str, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: None,
+				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::L4Forward, args)],
+		terminators: vec![Terminator::ByteTunnel],
+		entries,
+		meta: meta(listen, trusted),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	l4_forward::register(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link l4_forward graph")
+}
+
+/// `Upgrade -> Fetch(HttpProxy) -> Terminate(WriteHttpResponse)`.
+fn l7_graph(listen: SocketAddr, trusted: &str, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpProxy, args)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: meta(listen, trusted),
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	http_proxy::register(&mut fetch, None);
+	FlowGraph::link(sym, &mw, &fetch).expect("link http_proxy graph")
+}
+
+async fn start(graph: Arc<FlowGraph>) -> ListenerSet {
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	set
+}
+
+/// Upstream that records everything one connection sends until EOF.
+async fn spawn_recording_upstream() -> (SocketAddr, oneshot::Receiver<Vec<u8>>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind upstream");
+	let addr = listener.local_addr().expect("upstream local_addr");
+	let (tx, rx) = oneshot::channel();
+	tokio::spawn(async move {
+		let Ok((mut sock, _)) = listener.accept().await else { return };
+		let mut received = Vec::new();
+		let _ = sock.read_to_end(&mut received).await;
+		let _ = tx.send(received);
+	});
+	(addr, rx)
+}
+
+/// Send `bytes` through the proxy, half-close, and return what the
+/// upstream recorded.
+async fn relay(
+	proxy_addr: SocketAddr,
+	bytes: &[u8],
+	upstream: oneshot::Receiver<Vec<u8>>,
+) -> Vec<u8> {
+	let mut client = TcpStream::connect(proxy_addr).await.expect("connect proxy");
+	client.write_all(bytes).await.expect("write payload");
+	client.shutdown().await.expect("client write shutdown");
+	let mut sink = Vec::new();
+	let _ = client.read_to_end(&mut sink).await;
+	tokio::time::timeout(Duration::from_secs(5), upstream)
+		.await
+		.expect("upstream recorded in time")
+		.expect("upstream sent its recording")
+}
+
+#[tokio::test]
+async fn trusted_v1_header_replaces_remote_for_the_outbound_header() {
+	let (upstream_addr, recorded) = spawn_recording_upstream().await;
+	let proxy_addr = pick_port();
+	let graph = l4_graph(
+		proxy_addr,
+		"127.0.0.0/8",
+		serde_json::json!({ "upstream": upstream_addr.to_string(), "send_proxy_protocol": "v1" }),
+	);
+	let set = start(graph).await;
+
+	let received =
+		relay(proxy_addr, b"PROXY TCP4 203.0.113.7 192.0.2.1 51000 443\r\nping", recorded).await;
+	let expected = format!("PROXY TCP4 203.0.113.7 127.0.0.1 51000 {}\r\nping", proxy_addr.port());
+	assert_eq!(String::from_utf8_lossy(&received), expected);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn trusted_v2_header_is_consumed_and_v2_is_sent_upstream() {
+	let (upstream_addr, recorded) = spawn_recording_upstream().await;
+	let proxy_addr = pick_port();
+	let graph = l4_graph(
+		proxy_addr,
+		"127.0.0.1/32",
+		serde_json::json!({ "upstream": upstream_addr.to_string(), "send_proxy_protocol": "v2" }),
+	);
+	let set = start(graph).await;
+
+	let client_addr: SocketAddr = "[2001:db8::7]:51000".parse().expect("client addr");
+	let inbound = Header::proxy(Version::V2, client_addr, "[2001:db8::1]:443".parse().expect("dst"))
+		.with_tlv(proxy_protocol::tlv::UNIQUE_ID, b"conn-1".to_vec())
+		.encode();
+	let mut payload = inbound;
+	payload.extend_from_slice(b"ping");
+
+	let received = relay(proxy_addr, &payload, recorded).await;
+	let (header, consumed) =
+		Header::parse(&received).expect("valid header").expect("complete header");
+	assert_eq!(header.version, Version::V2);
+	assert_eq!(header.addresses.expect("addresses").source, client_addr);
+	assert_eq!(&received[consumed..], b"ping");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn untrusted_peer_header_is_forwarded_verbatim() {
+	let (upstream_addr, recorded) = spawn_recording_upstream().await;
+	let proxy_addr = pick_port();
+	let graph = l4_graph(
+		proxy_addr,
+		"10.0.0.0/8",
+		serde_json::json!({ "upstream": upstream_addr.to_string() }),
+	);
+	let set = start(graph).await;
+
+	let spoofed = b"PROXY TCP4 203.0.113.7 192.0.2.1 51000 443\r\nping";
+	let received = relay(proxy_addr, spoofed, recorded).await;
+	assert_eq!(received, spoofed, "an untrusted peer's header is application data");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn malformed_header_from_trusted_peer_drops_the_connection() {
+	let upstream = TcpListener::bind("127.0.0.1:0").await.expect("bind upstream");
+	let upstream_addr = upstream.local_addr().expect("upstream local_addr");
+	let proxy_addr = pick_port();
+	let graph = l4_graph(
+		proxy_addr,
+		"127.0.0.0/8",
+		serde_json::json!({ "upstream": upstream_addr.to_string() }),
+	);
+	let set = start(graph).await;
+
+	let mut client = TcpStream::connect(proxy_addr).await.expect("connect proxy");
+	client.write_all(b"GET / HTTP/1.1\r\nhost: a\r\n\r\n").await.expect("write payload");
+	let mut buf = Vec::new();
+	let read = tokio::time::timeout(Duration::from_secs(5), client.read_to_end(&mut buf))
+		.await
+		.expect("proxy closes the connection");
+	assert!(read.is_err() || buf.is_empty(), "no bytes may come back: {buf:?}");
+	let dialed = tokio::time::timeout(Duration::from_millis(200), upstream.accept()).await;
+	assert!(dialed.is_err(), "a rejected header must not reach the upstream");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn http_proxy_sends_header_on_each_upstream_connection() {
+	// Raw upstream: record the PROXY header, then answer one HTTP/1.1
+	// request and close.
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind upstream");
+	let upstream_addr = listener.local_addr().expect("upstream local_addr");
+	let (tx, rx) = oneshot::channel();
+	tokio::spawn(async move {
+		let Ok((mut sock, _)) = listener.accept().await else { return };
+		let mut buf = Vec::new();
+		let mut chunk = [0u8; 1024];
+		let header = loop {
+			let n = sock.read(&mut chunk).await.expect("upstream read");
+			assert!(n > 0, "upstream saw EOF before a full header");
+			buf.extend_from_slice(&chunk[..n]);
+			if let Some((header, consumed)) = Header::parse(&buf).expect("valid header") {
+				buf.drain(..consumed);
+				break header;
+			}
+		};
+		while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+			let n = sock.read(&mut chunk).await.expect("upstream read");
+			assert!(n > 0, "upstream saw EOF before the request head");
+			buf.extend_from_slice(&chunk[..n]);
+		}
+		sock
+			.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+			.await
+			.expect("upstream write");
+		let _ = tx.send(header);
+	});
+
+	let proxy_addr = pick_port();
+	let graph = l7_graph(
+		proxy_addr,
+		"127.0.0.0/8",
+		serde_json::json!({
+			"upstream": upstream_addr.to_string(),
+			"version": "h1",
+			"send_proxy_protocol": "v2",
+		}),
+	);
+	let set = start(graph).await;
+
+	let mut client = TcpStream::connect(proxy_addr).await.expect("connect proxy");
+	client
+		.write_all(b"PROXY TCP4 198.51.100.9 192.0.2.1 40000 80\r\n")
+		.await
+		.expect("write PROXY header");
+	client
+		.write_all(b"GET / HTTP/1.1\r\nhost: test.local\r\nconnection: close\r\n\r\n")
+		.await
+		.expect("write request");
+	let mut response = Vec::new();
+	client.read_to_end(&mut response).await.expect("read response");
+	let response = String::from_utf8_lossy(&response);
+	assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+	assert!(response.ends_with("ok"), "{response}");
+
+	let header = tokio::time::timeout(Duration::from_secs(5), rx)
+		.await
+		.expect("upstream recorded in time")
+		.expect("upstream sent the header");
+	assert_eq!(header.version, Version::V2);
+	let addresses = header.addresses.expect("addresses");
+	assert_eq!(addresses.source, "198.51.100.9:40000".parse::<SocketAddr>().expect("src"));
+	assert_eq!(addresses.destination, proxy_addr);
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn http_proxy_pools_upstream_connections_per_client_connection() {
+	// Keep-alive upstream: per connection, record the PROXY header's
+	// source, then answer HTTP/1.1 requests until EOF.
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind upstream");
+	let upstream_addr = listener.local_addr().expect("upstream local_addr");
+	let sources = Arc::new(parking_lot::Mutex::new(Vec::new()));
+	let recorded = Arc::clone(&sources);
+	tokio::spawn(async move {
+		while let Ok((mut sock, _)) = listener.accept().await {
+			let recorded = Arc::clone(&recorded);
+			tokio::spawn(async move {
+				let mut buf = Vec::new();
+				let mut chunk = [0u8; 1024];
+				loop {
+					let n = sock.read(&mut chunk).await.unwrap_or(0);
+					if n == 0 {
+						return;
+					}
+					buf.extend_from_slice(&chunk[..n]);
+					if let Some((header, consumed)) = Header::parse(&buf).expect("valid header") {
+						buf.drain(..consumed);
+						recorded.lock().push(header.addresses.expect("addresses").source);
+						break;
+					}
+				}
+				loop {
+					while let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+						buf.drain(..end + 4);
+						sock
+							.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+							.await
+							.expect("upstream write");
+					}
+					let n = sock.read(&mut chunk).await.unwrap_or(0);
+					if n == 0 {
+						return;
+					}
+					buf.extend_from_slice(&chunk[..n]);
+				}
+			});
+		}
+	});
+
+	let proxy_addr = pick_port();
+	// Nothing is trusted: the client's own address is the header source.
+	let graph = l7_graph(
+		proxy_addr,
+		"192.0.2.0/24",
+		serde_json::json!({
+			"upstream": upstream_addr.to_string(),
+			"version": "h1",
+			"send_proxy_protocol": "v1",
+		}),
+	);
+	let set = start(graph).await;
+
+	let mut clients = Vec::new();
+	for _ in 0..2 {
+		let mut client = TcpStream::connect(proxy_addr).await.expect("connect proxy");
+		for _ in 0..2 {
+			client.write_all(b"GET / HTTP/1.1\r\nhost: test.local\r\n\r\n").await.expect("write request");
+			let mut response = Vec::new();
+			let mut chunk = [0u8; 1024];
+			while !response.ends_with(b"ok") {
+				let n = client.read(&mut chunk).await.expect("read response");
+				assert!(n > 0, "proxy closed mid-response");
+				response.extend_from_slice(&chunk[..n]);
+			}
+			assert!(response.starts_with(b"HTTP/1.1 200"), "{}", String::from_utf8_lossy(&response));
+		}
+		clients.push(client.local_addr().expect("client local_addr"));
+	}
+
+	assert_eq!(*sources.lock(), clients, "one upstream connection per client connection");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,138 @@
+//! Integration tests for per-rule HTTP request metrics.
+//!
+//! Covers `spec/crates/engine.md` § _Request metrics_: a request that
+//! reaches a rule's fetch is counted under the rule's name, with its
+//! request / response bytes, duration, time to first byte and upstream
+//! latency. The Prometheus recorder is process-global, so the test
+//! reads the rendered exposition text rather than a local recorder.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_synthesize::register as register_http_synth;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+// entry(Upgrade) -> Fetch(HttpSynthesize, rule = `rule`) -> Terminate(WriteHttpResponse)
+fn synth_graph(listen: SocketAddr, rule: &str, args: serde_json::Value) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	entries.insert(listen, NodeId::for_testing(0));
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				// Buffer the request so its bytes are read (and counted)
+				// even though the synthesised response ignores them.
+				collect_body_before: Some(vane_core::BodySide::Request),
+				body_limit: 1024,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			rule: Some(rule.to_owned()),
+			..SymbolicFetchRef::new(FetchKind::HttpSynthesize, args)
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries,
+		meta: sample_meta(),
+	});
+	let mut fetch = FetchFactories::new();
+	register_http_synth(&mut fetch);
+	FlowGraph::link(sym, &MiddlewareFactories::new(), &fetch).expect("link http_synthesize graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>) -> ListenerSet {
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(graph)), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	set
+}
+
+/// The first exposition line for `metric` whose labels include every
+/// `key="value"` pair in `labels`.
+fn sample<'a>(text: &'a str, metric: &str, labels: &[&str]) -> Option<&'a str> {
+	text.lines().find(|l| {
+		l.strip_prefix(metric).is_some_and(|rest| rest.starts_with('{'))
+			&& labels.iter().all(|kv| l.contains(kv))
+	})
+}
+
+#[tokio::test]
+async fn request_is_counted_under_its_rule() {
+	vane_engine::metrics::install_recorder().expect("install recorder");
+	let addr = pick_port();
+	let body = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, b"hello");
+	let graph = synth_graph(addr, "metrics-api", serde_json::json!({ "status": 201, "body": body }));
+	let set = start_listener(graph).await;
+
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Full<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(conn);
+	let req = hyper::Request::builder()
+		.method("POST")
+		.uri("/")
+		.header("host", "test.local")
+		.body(Full::new(Bytes::from_static(b"ping")))
+		.expect("build POST request");
+	let resp = sender.send_request(req).await.expect("send_request");
+	assert_eq!(resp.status().as_u16(), 201);
+	let got = resp.into_body().collect().await.expect("collect body").to_bytes();
+	assert_eq!(got.as_ref(), b"hello");
+
+	// Duration and bytes land when the response body is released,
+	// which may trail the client's read by a scheduler tick.
+	let rule = r#"rule="metrics-api""#;
+	let mut text = String::new();
+	for _ in 0..50 {
+		text = vane_engine::metrics::render_prometheus().expect("recorder installed");
+		if sample(&text, "vane_http_response_bytes_total", &[rule]).is_some() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(20)).await;
+	}
+
+	let total =
+		sample(&text, "vane_http_requests_total", &[rule, r#"method="POST""#, r#"status_class="2xx""#])
+			.expect("requests_total series");
+	assert!(total.ends_with(" 1"), "one request counted: {total}");
+	let resp_bytes =
+		sample(&text, "vane_http_response_bytes_total", &[rule]).expect("response bytes series");
+	assert!(resp_bytes.ends_with(" 5"), "synthesised body length: {resp_bytes}");
+	let req_bytes =
+		sample(&text, "vane_http_request_bytes_total", &[rule]).expect("request bytes series");
+	assert!(req_bytes.ends_with(" 4"), "client body length: {req_bytes}");
+	for histogram in [
+		"vane_http_request_duration_ms_count",
+		"vane_http_request_ttfb_ms_count",
+		"vane_http_upstream_duration_ms_count",
+	] {
+		assert!(sample(&text, histogram, &[rule]).is_some(), "{histogram} missing:\n{text}");
+	}
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,123 @@
+//! Rule-label cap for per-rule HTTP request metrics, isolated to its
+//! own integration-test binary so the process-wide rule registry it
+//! fills doesn't bleed into `request_metrics.rs`.
+//!
+//! `spec/crates/engine.md` § _Request metrics_: once
+//! `VANE_HTTP_METRIC_RULE_CAP` distinct rules are labelled, further
+//! rules are counted under `_other`. We set the cap to 1 via env, send
+//! one request to each of two rules, and assert the second lands under
+//! `_other`.
+
+// The only test in this binary sets `VANE_HTTP_METRIC_RULE_CAP` before
+// the first request initialises the registry that reads it. There is
+// no concurrent reader, so the `set_var` race that motivates the
+// `unsafe` annotation in Rust 2024 doesn't apply here.
+
+#![allow(unsafe_code)] // std::env::set_var is unsafe in 2024 edition; single-test binary isolates the race.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use vane_core::{
+	FetchId, FetchKind, FlowLogSink, Node, NodeId, SymbolicFetchRef, SymbolicFlowGraph, Terminator,
+	TerminatorId,
+};
+use vane_engine::ListenerSet;
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::http_synthesize::register as register_http_synth;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+// entry(Upgrade) -> Fetch(HttpSynthesize, rule = `rule`) -> Terminate(WriteHttpResponse)
+fn synth_graph(listen: SocketAddr, rule: &str) -> Arc<FlowGraph> {
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef {
+			rule: Some(rule.to_owned()),
+			..SymbolicFetchRef::new(FetchKind::HttpSynthesize, serde_json::json!({ "status": 204 }))
+		}],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries: HashMap::from([(listen, NodeId::for_testing(0))]),
+		meta: sample_meta(),
+	});
+	let mut fetch = FetchFactories::new();
+	register_http_synth(&mut fetch);
+	FlowGraph::link(sym, &MiddlewareFactories::new(), &fetch).expect("link http_synthesize graph")
+}
+
+/// Serve `rule` on a fresh listener and send it one GET.
+async fn request_rule(rule: &str) -> ListenerSet {
+	let addr = pick_port();
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new();
+	set.start(&Arc::new(ArcSwap::new(synth_graph(addr, rule))), &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(conn);
+	let req = hyper::Request::builder()
+		.uri("/")
+		.header("host", "test.local")
+		.body(Empty::new())
+		.expect("build GET request");
+	let resp = sender.send_request(req).await.expect("send_request");
+	assert_eq!(resp.status().as_u16(), 204);
+	resp.into_body().collect().await.expect("collect body");
+	set
+}
+
+/// The `vane_http_requests_total` sample value for `rule`, if any.
+fn requests_total(text: &str, rule: &str) -> Option<u64> {
+	let label = format!(r#"rule="{rule}""#);
+	text
+		.lines()
+		.find(|l| l.starts_with("vane_http_requests_total{") && l.contains(&label))
+		.and_then(|l| l.rsplit(' ').next()?.parse().ok())
+}
+
+#[tokio::test]
+async fn rules_past_the_cap_are_counted_under_other() {
+	// SAFETY: the only test in this binary; nothing reads
+	// VANE_HTTP_METRIC_RULE_CAP concurrently.
+	unsafe {
+		std::env::set_var("VANE_HTTP_METRIC_RULE_CAP", "1");
+	}
+	vane_engine::metrics::install_recorder().expect("install recorder");
+
+	let first = request_rule("cap-first").await;
+	let second = request_rule("cap-second").await;
+
+	let text = vane_engine::metrics::render_prometheus().expect("recorder installed");
+	assert_eq!(requests_total(&text, "cap-first"), Some(1), "the first rule keeps its label");
+	assert_eq!(requests_total(&text, "cap-second"), None, "no series past the cap:\n{text}");
+	assert_eq!(requests_total(&text, "_other"), Some(1), "the second rule folds into _other");
+
+	first.shutdown(Duration::from_millis(500)).await;
+	second.shutdown(Duration::from_millis(500)).await;
+}
@@ -0,0 +1,425 @@
+//! End-to-end coverage for the L1 handshake and stream floors.
+//!
+//! Covers `spec/crates/engine.md` § _Security floor (L1)_ on a real TLS
+//! listener, with a `SecurityState` built from a lowered
+//! `SecurityConfig`:
+//!
+//! * `run_tls` drops a connection over `max_handshake_rate_per_ip`
+//!   before the ClientHello is read.
+//! * H2: a stream past `max_in_flight_streams_per_conn` is reset with
+//!   `REFUSED_STREAM`; a connection at twice `max_request_rate_per_conn`
+//!   gets `GOAWAY`.
+//! * H3: a stream past the in-flight cap is rejected with
+//!   `H3_REQUEST_REJECTED`.
+//! * A `rate_limit` 429 counts one offense against the source in the
+//!   ban table, not a strike plus a `4xx`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::Empty;
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use tempfile::NamedTempFile;
+use vane_core::{
+	Body, ConnContext, Error, FetchId, FetchKind, FlowCtx, FlowGraphMeta, FlowLogSink, L7Fetch,
+	L7FetchOutput, MiddlewareId, MiddlewareKind, Node, NodeId, Request, SymbolicFetchRef,
+	SymbolicFlowGraph, SymbolicMiddlewareRef, Terminator, TerminatorId, Transport,
+};
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::flow_graph::{FetchInst, FlowGraph};
+use vane_engine::middleware::rate_limit;
+use vane_engine::security::BanConfig;
+use vane_engine::verbosity::VerbosityState;
+use vane_engine::{BindConfig, ListenerSet, SecurityConfig, SecurityState};
+use vane_testutil::flow::{DropSink, pick_port, sample_meta};
+
+struct CertFixture {
+	_cert_file: NamedTempFile,
+	_key_file: NamedTempFile,
+	cert_pem: String,
+	tls_cfg: vane_core::rule::TlsConfig,
+}
+
+fn make_cert() -> CertFixture {
+	let issued =
+		rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).expect("self-signed cert");
+	let cert_pem = issued.cert.pem();
+	let mut cert_file = NamedTempFile::new().expect("cert tmp");
+	cert_file.write_all(cert_pem.as_bytes()).expect("write cert pem");
+	let mut key_file = NamedTempFile::new().expect("key tmp");
+	key_file.write_all(issued.signing_key.serialize_pem().as_bytes()).expect("write key pem");
+	let tls_cfg = vane_core::rule::TlsConfig {
+		sni: None,
+		cert_file: Some(cert_file.path().to_path_buf()),
+		key_file: Some(key_file.path().to_path_buf()),
+		managed: None,
+		client_auth: None,
+		enable_zero_rtt: false,
+		ocsp_path: None,
+		ocsp_fetch: false,
+	};
+	CertFixture { _cert_file: cert_file, _key_file: key_file, cert_pem, tls_cfg }
+}
+
+/// Answers `200` after `delay`, so concurrent streams stay in flight.
+struct DelayedOk(Duration);
+
+#[async_trait]
+impl L7Fetch for DelayedOk {
+	async fn fetch(
+		&self,
+		_req: Request,
+		_conn: &Arc<ConnContext>,
+		_ctx: &mut FlowCtx,
+	) -> Result<L7FetchOutput, Error> {
+		tokio::time::sleep(self.0).await;
+		Ok(L7FetchOutput::Response(
+			http::Response::builder().status(200).body(Body::Empty).expect("build response"),
+		))
+	}
+}
+
+/// `Upgrade -> Fetch(DelayedOk) -> Terminate(WriteHttpResponse)` on a
+/// TLS listener at `addr`, linked against `cfg`.
+fn graph(
+	addr: SocketAddr,
+	transport: Transport,
+	cert: &CertFixture,
+	delay: Duration,
+	cfg: &SecurityConfig,
+) -> Arc<FlowGraph> {
+	let tls = vane_core::rule::ListenerTlsSpec {
+		default: Some(cert.tls_cfg.clone()),
+		sni_certs: BTreeMap::new(),
+		managed_snis: BTreeMap::new(),
+		client_auth: vane_core::rule::ClientAuthSpec::None,
+		enable_zero_rtt: false,
+	};
+	let mut meta = FlowGraphMeta { listener_tls: BTreeMap::from([(addr, tls)]), ..sample_meta() };
+	if transport == Transport::Udp {
+		meta.listener_kinds.insert(addr, vane_core::ListenerKind::Http);
+		meta.listener_transports.insert(addr, Transport::Udp);
+	}
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(2)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries: HashMap::from([(addr, NodeId::for_testing(0))]),
+		meta,
+	});
+	let mut fetch = FetchFactories::new();
+	fetch.register(FetchKind::HttpSynthesize, move |_args| {
+		Ok(FetchInst::L7(Arc::new(DelayedOk(delay))))
+	});
+	FlowGraph::link_with_security(sym, &MiddlewareFactories::new(), &fetch, Arc::new(cfg.clone()))
+		.expect("link floor graph")
+}
+
+/// `Upgrade -> Middleware(rate_limit) -> Fetch(DelayedOk) ->
+/// Terminate(WriteHttpResponse)` on a cleartext listener at `addr`;
+/// the bucket holds one request per minute.
+fn rate_limited_graph(addr: SocketAddr, cfg: &SecurityConfig) -> Arc<FlowGraph> {
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Upgrade { next: NodeId::for_testing(1) },
+			Node::Middleware {
+				id: MiddlewareId::for_testing(0),
+				next: NodeId::for_testing(2),
+				on_error: None,
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: Some(NodeId::for_testing(3)),
+				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![SymbolicMiddlewareRef {
+			name: Arc::from("rate_limit"),
+			args: serde_json::json!({ "rate": 1, "burst": 1, "window": "60s" }),
+			kind: MiddlewareKind::L7Request,
+			stateless: true,
+			needs_body: false,
+			on_error: None,
+		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)],
+		terminators: vec![Terminator::WriteHttpResponse],
+		entries: HashMap::from([(addr, NodeId::for_testing(0))]),
+		meta: FlowGraphMeta {
+			// The 429 lands on the chain's own `WriteHttpResponse`.
+			short_circuit_response_entry: BTreeMap::from([(
+				NodeId::for_testing(1),
+				NodeId::for_testing(3),
+			)]),
+			..sample_meta()
+		},
+	});
+	let mut mw = MiddlewareFactories::new();
+	rate_limit::register(&mut mw);
+	let mut fetch = FetchFactories::new();
+	fetch.register(FetchKind::HttpSynthesize, |_args| {
+		Ok(FetchInst::L7(Arc::new(DelayedOk(Duration::ZERO))))
+	});
+	FlowGraph::link_with_security(sym, &mw, &fetch, Arc::new(cfg.clone()))
+		.expect("link rate_limit graph")
+}
+
+async fn start_listener(graph: Arc<FlowGraph>, cfg: SecurityConfig) -> ListenerSet {
+	start_listener_with(graph, Arc::new(SecurityState::new(cfg))).await
+}
+
+async fn start_listener_with(graph: Arc<FlowGraph>, security: Arc<SecurityState>) -> ListenerSet {
+	let set = ListenerSet::from_security_and_bind_config(security, BindConfig::default());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	set.start(&Arc::new(ArcSwap::new(graph)), &Arc::new(VerbosityState::new()), &sink);
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	set
+}
+
+async fn tls_connect(
+	addr: SocketAddr,
+	cert_pem: &str,
+	alpn: &[u8],
+) -> std::io::Result<tokio_rustls::client::TlsStream<tokio::net::TcpStream>> {
+	let mut roots = rustls::RootCertStore::empty();
+	for cert in rustls_pemfile::certs(&mut cert_pem.as_bytes()) {
+		roots.add(cert.expect("parse cert")).expect("add cert");
+	}
+	let mut cfg = rustls::ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+	cfg.alpn_protocols = vec![alpn.to_vec()];
+	let tcp = tokio::net::TcpStream::connect(addr).await?;
+	let server_name = rustls::pki_types::ServerName::try_from("localhost").expect("server name");
+	tokio_rustls::TlsConnector::from(Arc::new(cfg)).connect(server_name, tcp).await
+}
+
+type H2Sender = hyper::client::conn::http2::SendRequest<Empty<Bytes>>;
+
+/// H2 client over TLS; the handle resolves when the connection closes.
+async fn h2_connect(addr: SocketAddr, cert_pem: &str) -> (H2Sender, tokio::task::JoinHandle<()>) {
+	let tls = tls_connect(addr, cert_pem, b"h2").await.expect("tls handshake");
+	let (sender, conn) = hyper::client::conn::http2::handshake::<_, _, Empty<Bytes>>(
+		hyper_util::rt::TokioExecutor::new(),
+		TokioIo::new(tls),
+	)
+	.await
+	.expect("h2 handshake");
+	let closed = tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	(sender, closed)
+}
+
+/// Status of one GET, or the `RST_STREAM` / `GOAWAY` reason it failed
+/// with (`None` for a failure without one).
+async fn h2_get(mut sender: H2Sender) -> Result<u16, Option<h2::Reason>> {
+	let req = hyper::Request::builder()
+		.uri("https://localhost/")
+		.body(Empty::<Bytes>::new())
+		.expect("build GET");
+	match sender.send_request(req).await {
+		Ok(resp) => Ok(resp.status().as_u16()),
+		Err(e) => {
+			let mut source = std::error::Error::source(&e);
+			while let Some(s) = source {
+				if let Some(h2) = s.downcast_ref::<h2::Error>() {
+					return Err(h2.reason());
+				}
+				source = s.source();
+			}
+			Err(None)
+		}
+	}
+}
+
+#[tokio::test]
+async fn tls_handshake_over_the_per_ip_rate_is_dropped() {
+	vane_engine::crypto::install_default_provider();
+	let cert = make_cert();
+	let cfg = SecurityConfig { max_handshake_rate_per_ip: 1, ..SecurityConfig::default() };
+	let addr = pick_port();
+	let set =
+		start_listener(graph(addr, Transport::Tcp, &cert, Duration::ZERO, &cfg), cfg.clone()).await;
+
+	let first = tls_connect(addr, &cert.cert_pem, b"http/1.1").await;
+	assert!(first.is_ok(), "first handshake is admitted: {:?}", first.err());
+	let second = tls_connect(addr, &cert.cert_pem, b"http/1.1").await;
+	assert!(second.is_err(), "second handshake in the same second is dropped");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn h2_stream_past_the_in_flight_cap_is_refused() {
+	vane_engine::crypto::install_default_provider();
+	let cert = make_cert();
+	let cfg = SecurityConfig { max_in_flight_streams_per_conn: 10, ..SecurityConfig::default() };
+	let addr = pick_port();
+	let set = start_listener(
+		graph(addr, Transport::Tcp, &cert, Duration::from_millis(500), &cfg),
+		cfg.clone(),
+	)
+	.await;
+
+	let (sender, _closed) = h2_connect(addr, &cert.cert_pem).await;
+	let streams: Vec<_> = (0..11).map(|_| tokio::spawn(h2_get(sender.clone()))).collect();
+	let mut ok = 0;
+	let mut refused = 0;
+	for stream in streams {
+		match stream.await.expect("join") {
+			Ok(200) => ok += 1,
+			Err(Some(h2::Reason::REFUSED_STREAM)) => refused += 1,
+			other => panic!("unexpected outcome {other:?}"),
+		}
+	}
+	assert_eq!((ok, refused), (10, 1));
+
+	assert_eq!(h2_get(sender).await, Ok(200), "the connection stays up");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn h2_connection_at_twice_the_request_rate_gets_goaway() {
+	vane_engine::crypto::install_default_provider();
+	let cert = make_cert();
+	let cfg = SecurityConfig { max_request_rate_per_conn: 100, ..SecurityConfig::default() };
+	let addr = pick_port();
+	let set =
+		start_listener(graph(addr, Transport::Tcp, &cert, Duration::ZERO, &cfg), cfg.clone()).await;
+
+	let (sender, closed) = h2_connect(addr, &cert.cert_pem).await;
+	let streams: Vec<_> = (0..250).map(|_| tokio::spawn(h2_get(sender.clone()))).collect();
+	let mut refused = 0;
+	for stream in streams {
+		if stream.await.expect("join") == Err(Some(h2::Reason::REFUSED_STREAM)) {
+			refused += 1;
+		}
+	}
+	assert!(refused > 0, "streams over the rate are refused");
+	tokio::time::timeout(Duration::from_secs(5), closed)
+		.await
+		.expect("GOAWAY closes the connection")
+		.expect("join");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[cfg(feature = "h3")]
+#[tokio::test]
+async fn h3_stream_past_the_in_flight_cap_is_rejected() {
+	use h3::error::{Code, StreamError};
+
+	vane_engine::crypto::install_default_provider();
+	let cert = make_cert();
+	let cfg = SecurityConfig { max_in_flight_streams_per_conn: 10, ..SecurityConfig::default() };
+	let udp = tokio::net::UdpSocket::bind("127.0.0.1:0").await.expect("bind ephemeral udp");
+	let addr = udp.local_addr().expect("local_addr");
+	drop(udp);
+	let set = start_listener(
+		graph(addr, Transport::Udp, &cert, Duration::from_millis(500), &cfg),
+		cfg.clone(),
+	)
+	.await;
+
+	let handle =
+		vane_testutil::h3::connect_h3(addr, &cert.cert_pem, "localhost").await.expect("h3 connect");
+	let streams: Vec<_> = (0..11)
+		.map(|_| {
+			let mut send_request = handle.send_request.clone();
+			tokio::spawn(async move {
+				let req =
+					http::Request::builder().uri("https://localhost/").body(()).expect("build h3 GET");
+				let mut stream = send_request.send_request(req).await?;
+				stream.finish().await?;
+				stream.recv_response().await.map(|r| r.status().as_u16())
+			})
+		})
+		.collect();
+	let mut ok = 0;
+	let mut rejected = 0;
+	for stream in streams {
+		match stream.await.expect("join") {
+			Ok(200) => ok += 1,
+			Err(StreamError::RemoteTerminate { code, .. }) if code == Code::H3_REQUEST_REJECTED => {
+				rejected += 1;
+			}
+			Ok(status) => panic!("unexpected status {status}"),
+			Err(e) => panic!("unexpected stream error {e:?}"),
+		}
+	}
+	assert_eq!((ok, rejected), (10, 1));
+
+	handle.shutdown().await;
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn rate_limited_request_counts_one_offense() {
+	vane_engine::crypto::install_default_provider();
+	// Two strikes ban; so would a single 4xx, were the 429 also
+	// counted as a client error.
+	let cfg = SecurityConfig {
+		ban: BanConfig { strikes: 2, client_errors: 1, allow: Vec::new(), ..BanConfig::default() },
+		..SecurityConfig::default()
+	};
+	let addr = pick_port();
+	let security = Arc::new(SecurityState::new(cfg.clone()));
+	let set = start_listener_with(rate_limited_graph(addr, &cfg), Arc::clone(&security)).await;
+
+	let stream = tokio::net::TcpStream::connect(addr).await.expect("client connect");
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake::<_, Empty<Bytes>>(TokioIo::new(stream))
+			.await
+			.expect("h1 handshake");
+	tokio::spawn(async move {
+		let _ = conn.await;
+	});
+	let mut get = async || {
+		let req = hyper::Request::builder()
+			.uri("/")
+			.header("host", "localhost")
+			.body(Empty::<Bytes>::new())
+			.expect("build GET");
+		sender.send_request(req).await.expect("send").status().as_u16()
+	};
+	let source = "127.0.0.1".parse().expect("ip");
+
+	assert_eq!(get().await, 200);
+	assert_eq!(get().await, 429);
+	assert!(!security.bans().is_banned(source), "one 429 is one strike, not a client error");
+	assert_eq!(get().await, 429);
+	assert!(security.bans().is_banned(source), "the second strike bans");
+	let bans = security.bans().snapshot();
+	assert_eq!(bans.len(), 1);
+	assert_eq!(bans[0].reason, "strikes");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
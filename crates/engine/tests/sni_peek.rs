@@ -15,7 +15,9 @@
 //!   the rule before any traffic flows.
 //! * The listener's peek prelude pre-populates `ConnContext.tls.sni`
 //!   from the parsed `ClientHello` (lowercase, per the SNI invariant)
-//!   so the predicate evaluating `tls.sni == ...` sees a value.
+//!   so the predicate evaluating `tls.sni == ...` sees a value. The
+//!   same prelude stamps the hello's JA3 / JA4 fingerprints, exercised
+//!   through a `tls.ja4` predicate.
 //!
 //! Spec anchors:
 //!
@@ -131,7 +133,7 @@ fn tagged_fetch_factory(args: &Value) -> Result<FetchInst, FactoryError> {
 ///
 /// ```text
 ///  0: Middleware(sni_peek) -> 1
-///  1: Check(tls.sni == "match.example.com") on_match=2 on_miss=3
+///  1: Check(predicate) on_match=2 on_miss=3
 ///  2: Upgrade -> 4
 ///  3: Upgrade -> 5
 ///  4: Fetch(match) -> 6
@@ -139,6 +141,25 @@ fn tagged_fetch_factory(args: &Value) -> Result<FetchInst, FactoryError> {
 ///  6: Terminate(WriteHttpResponse)
 /// ```
 fn sni_peek_branching_graph(addr: SocketAddr, tls_cfg: rule::TlsConfig) -> Arc<FlowGraph> {
+	peek_branching_graph(
+		addr,
+		tls_cfg,
+		PredicateInst {
+			path: FieldPath::TlsSni,
+			op: CompiledOperator::Equals(CompiledValue::Str(Arc::from("match.example.com"))),
+		},
+	)
+}
+
+fn synth_fetch(tag: &str) -> SymbolicFetchRef {
+	SymbolicFetchRef::new(FetchKind::HttpSynthesize, json!({ "tag": tag }))
+}
+
+fn peek_branching_graph(
+	addr: SocketAddr,
+	tls_cfg: rule::TlsConfig,
+	predicate: PredicateInst,
+) -> Arc<FlowGraph> {
 	let mut entries = HashMap::new();
 	entries.insert(addr, NodeId::for_testing(0));
 
@@ -164,6 +185,8 @@ fn sni_peek_branching_graph(addr: SocketAddr, tls_cfg: rule::TlsConfig) -> Arc<F
 		listener_kinds: BTreeMap::new(),
 
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	};
 
@@ -188,6 +211,8 @@ fn sni_peek_branching_graph(addr: SocketAddr, tls_cfg: rule::TlsConfig) -> Arc<F
 			id: FetchId::for_testing(0),
 			next_response: Some(NodeId::for_testing(6)),
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -195,6 +220,8 @@ fn sni_peek_branching_graph(addr: SocketAddr, tls_cfg: rule::TlsConfig) -> Arc<F
 			id: FetchId::for_testing(1),
 			next_response: Some(NodeId::for_testing(6)),
 			next_tunnel: None,
+			on_error: None,
+			on_error_status: Vec::new(),
 			collect_body_before: None,
 			body_limit: 0,
 		},
@@ -210,25 +237,9 @@ fn sni_peek_branching_graph(addr: SocketAddr, tls_cfg: rule::TlsConfig) -> Arc<F
 		on_error: None,
 	}];
 
-	let predicates = vec![PredicateInst {
-		path: FieldPath::TlsSni,
-		op: CompiledOperator::Equals(CompiledValue::Str(Arc::from("match.example.com"))),
-	}];
+	let predicates = vec![predicate];
 
-	let fetches = vec![
-		SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: json!({ "tag": "match" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		},
-		SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: json!({ "tag": "miss" }),
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		},
-	];
+	let fetches = vec![synth_fetch("match"), synth_fetch("miss")];
 
 	let sym = Arc::new(SymbolicFlowGraph {
 		nodes,
@@ -357,3 +368,30 @@ async fn sni_peek_routes_miss_branch_when_sni_differs() {
 
 	set.shutdown(Duration::from_millis(500)).await;
 }
+
+/// `tls.ja4` is stamped from the same peeked `ClientHello`: a rustls
+/// client offering TLS 1.3 with `http/1.1` first reads as
+/// `t13d…h1_<ciphers>_<extensions>`, and a QUIC-only pattern misses.
+#[tokio::test]
+async fn ja4_predicate_matches_the_peeked_client_hello() {
+	vane_engine::crypto::install_default_provider();
+	let tls = rcgen_default_cert();
+	for (pattern, want_status) in [(r"^t13d\d{4}h1_[0-9a-f]{12}_[0-9a-f]{12}$", 200), (r"^q13d", 404)]
+	{
+		let addr = pick_port().await;
+		let graph = peek_branching_graph(
+			addr,
+			tls.tls_cfg.clone(),
+			PredicateInst {
+				path: FieldPath::TlsJa4,
+				op: CompiledOperator::Matches(fancy_regex::Regex::new(pattern).expect("regex")),
+			},
+		);
+		let (set, addr) = start_listener(graph).await;
+
+		let (status, _) = drive_one_request(addr, "match.example.com").await;
+		assert_eq!(status, want_status, "pattern {pattern}");
+
+		set.shutdown(Duration::from_millis(500)).await;
+	}
+}
@@ -0,0 +1,240 @@
+//! Integration tests for listeners served on inherited sockets.
+//!
+//! Covers `spec/crates/engine.md` § _Socket activation_:
+//!
+//! * A TCP / UDP listener whose address matches an inherited socket
+//!   serves on it rather than binding.
+//! * A reload that drops the listener keeps the inherited socket bound:
+//!   a client that connects meanwhile waits in the backlog and is served
+//!   once a later reload brings the rule back.
+//! * `handoff_sockets` hands a bound listener's socket to a second
+//!   `ListenerSet`, which keeps serving after the first shuts down.
+//!
+//! `InheritedSockets` is filled by hand with sockets the test bound, so
+//! no socket-activated process is needed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use vane_core::{
+	FetchId, FetchKind, FlowGraphMeta, FlowLogSink, Node, NodeId, SymbolicFetchRef,
+	SymbolicFlowGraph, Terminator, TerminatorId, Transport,
+};
+use vane_engine::factories::{FetchFactories, MiddlewareFactories};
+use vane_engine::fetch::l4_forward;
+use vane_engine::flow_graph::FlowGraph;
+use vane_engine::verbosity::VerbosityState;
+use vane_engine::{HandoffSocket, InheritedSockets, ListenerSet};
+use vane_testutil::flow::{DropSink, sample_meta};
+
+/// `Fetch(L4Forward) -> Terminate(ByteTunnel)` on `listen`, or a graph
+/// with no entries when `listen` is `None`.
+fn forward_graph(
+	listen: Option<SocketAddr>,
+	transport: Transport,
+	upstream: SocketAddr,
+) -> Arc<FlowGraph> {
+	let mut entries = HashMap::new();
+	let mut listener_transports = BTreeMap::new();
+	if let Some(addr) = listen {
+		entries.insert(addr, NodeId::for_testing(0));
+		listener_transports.insert(addr, transport);
+	}
+	let transport_arg = match transport {
+		Transport::Tcp => "tcp",
+		Transport::Udp => "udp",
+	};
+	let sym = Arc::new(SymbolicFlowGraph {
+		nodes: vec![
+			Node::Fetch {
+				id: FetchId::for_testing(0),
+				next_response: None,
+				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
+				collect_body_before: None,
+				body_limit: 0,
+			},
+			Node::Terminate(TerminatorId::for_testing(0)),
+		],
+		predicates: vec![],
+		middlewares: vec![],
+		fetches: vec![SymbolicFetchRef::new(
+			FetchKind::L4Forward,
+			serde_json::json!({
+				"upstream": upstream.to_string(),
+				"transport": transport_arg,
+			}),
+		)],
+		terminators: vec![Terminator::ByteTunnel],
+		entries,
+		meta: FlowGraphMeta { listener_transports, ..sample_meta() },
+	});
+	let mw = MiddlewareFactories::new();
+	let mut fetch = FetchFactories::new();
+	l4_forward::register(&mut fetch);
+	FlowGraph::link(sym, &mw, &fetch).expect("link l4_forward graph")
+}
+
+/// Echo upstream: every accepted connection gets its bytes back.
+async fn spawn_tcp_echo() -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind echo");
+	let addr = listener.local_addr().expect("echo local_addr");
+	tokio::spawn(async move {
+		while let Ok((mut sock, _)) = listener.accept().await {
+			tokio::spawn(async move {
+				let (mut r, mut w) = sock.split();
+				let _ = tokio::io::copy(&mut r, &mut w).await;
+			});
+		}
+	});
+	addr
+}
+
+/// Send `payload` and read the same number of bytes back.
+async fn echo_round_trip(client: &mut TcpStream, payload: &[u8]) -> Vec<u8> {
+	client.write_all(payload).await.expect("write payload");
+	let mut back = vec![0u8; payload.len()];
+	tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut back))
+		.await
+		.expect("echo in time")
+		.expect("read echo");
+	back
+}
+
+fn inherited_tcp() -> (SocketAddr, InheritedSockets) {
+	let std_listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind inherited tcp");
+	let mut inherited = InheritedSockets::default();
+	let addr = inherited.add_tcp(std_listener).expect("add inherited tcp");
+	(addr, inherited)
+}
+
+#[tokio::test]
+async fn tcp_listener_serves_on_inherited_socket() {
+	let upstream = spawn_tcp_echo().await;
+	let (listen, inherited) = inherited_tcp();
+	let swap = Arc::new(ArcSwap::new(forward_graph(Some(listen), Transport::Tcp, upstream)));
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+
+	let set = ListenerSet::new().with_inherited_sockets(inherited);
+	set.start(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(set.is_bound(&listen), "inherited socket counts as bound");
+
+	let mut client = TcpStream::connect(listen).await.expect("connect inherited listener");
+	assert_eq!(echo_round_trip(&mut client, b"ping").await, b"ping");
+
+	drop(client);
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn reload_that_drops_the_rule_keeps_the_inherited_socket_bound() {
+	let upstream = spawn_tcp_echo().await;
+	let (listen, inherited) = inherited_tcp();
+	let swap = Arc::new(ArcSwap::new(forward_graph(Some(listen), Transport::Tcp, upstream)));
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+
+	let set = ListenerSet::new().with_inherited_sockets(inherited);
+	set.start(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	// Reload without the rule: the accept loop goes away...
+	swap.store(forward_graph(None, Transport::Tcp, upstream));
+	set.reconcile(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert!(!set.is_running(&listen));
+
+	// ...but the socket is still listening, so the kernel completes the
+	// handshake and the connection waits in the backlog.
+	let mut client = TcpStream::connect(listen).await.expect("inherited socket still bound");
+	client.write_all(b"queued").await.expect("write while unserved");
+
+	// Bring the rule back: the new accept loop picks the waiting
+	// connection up from the same socket.
+	swap.store(forward_graph(Some(listen), Transport::Tcp, upstream));
+	set.reconcile(&swap, &verbosity, &sink);
+	let mut back = vec![0u8; 6];
+	tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut back))
+		.await
+		.expect("served after the rule returns")
+		.expect("read echo");
+	assert_eq!(back, b"queued");
+
+	drop(client);
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn udp_listener_serves_on_inherited_socket() {
+	let upstream = UdpSocket::bind("127.0.0.1:0").await.expect("bind udp upstream");
+	let upstream_addr = upstream.local_addr().expect("upstream local_addr");
+	let std_socket = std::net::UdpSocket::bind("127.0.0.1:0").expect("bind inherited udp");
+	let mut inherited = InheritedSockets::default();
+	let listen = inherited.add_udp(std_socket).expect("add inherited udp");
+
+	let swap = Arc::new(ArcSwap::new(forward_graph(Some(listen), Transport::Udp, upstream_addr)));
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+	let set = ListenerSet::new().with_inherited_sockets(inherited);
+	set.start(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(set.is_bound(&listen));
+
+	let client = UdpSocket::bind("127.0.0.1:0").await.expect("bind client");
+	client.send_to(b"dgram", listen).await.expect("send datagram");
+	let mut buf = [0u8; 64];
+	let (n, _) = tokio::time::timeout(Duration::from_secs(5), upstream.recv_from(&mut buf))
+		.await
+		.expect("datagram forwarded in time")
+		.expect("upstream recv");
+	assert_eq!(&buf[..n], b"dgram");
+
+	set.shutdown(Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn handoff_sockets_let_a_second_listener_set_serve_the_same_port() {
+	let upstream = spawn_tcp_echo().await;
+	let listen = {
+		let probe = std::net::TcpListener::bind("127.0.0.1:0").expect("probe bind");
+		probe.local_addr().expect("probe local_addr")
+	};
+	let swap = Arc::new(ArcSwap::new(forward_graph(Some(listen), Transport::Tcp, upstream)));
+	let verbosity = Arc::new(VerbosityState::new());
+	let sink: Arc<dyn FlowLogSink> = Arc::new(DropSink);
+
+	let old = ListenerSet::new();
+	old.start(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(old.is_bound(&listen));
+
+	let handoff = old.handoff_sockets();
+	assert_eq!(handoff.len(), 1);
+	let HandoffSocket { transport, addr, fd, .. } = handoff.into_iter().next().expect("one socket");
+	assert_eq!((transport, addr), (Transport::Tcp, listen));
+
+	let mut inherited = InheritedSockets::default();
+	inherited.add_tcp(std::net::TcpListener::from(fd)).expect("adopt handed-over socket");
+	let new = ListenerSet::new().with_inherited_sockets(inherited);
+	new.start(&swap, &verbosity, &sink);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(new.is_bound(&listen));
+
+	// The old set lets go; the socket stays open in the new one.
+	old.shutdown(Duration::from_millis(500)).await;
+	assert!(old.handoff_sockets().is_empty(), "a stopped listener offers nothing");
+
+	let mut client = TcpStream::connect(listen).await.expect("connect after hand-off");
+	assert_eq!(echo_round_trip(&mut client, b"still here").await, b"still here");
+
+	drop(client);
+	new.shutdown(Duration::from_millis(500)).await;
+}
@@ -77,6 +77,8 @@ fn meta_with_udp(addr: SocketAddr) -> FlowGraphMeta {
 		listener_tls: BTreeMap::new(),
 		listener_kinds: BTreeMap::new(),
 		listener_transports,
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	}
 }
@@ -103,6 +105,8 @@ fn make_udp_proxy_graph(
 				id: FetchId::for_testing(0),
 				next_response: None,
 				next_tunnel: Some(NodeId::for_testing(1)),
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -110,12 +114,7 @@ fn make_udp_proxy_graph(
 		],
 		predicates: vec![],
 		middlewares: vec![],
-		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::L4Forward,
-			args,
-			retry_buffer_required: false,
-			allow_zero_rtt: None,
-		}],
+		fetches: vec![SymbolicFetchRef::new(FetchKind::L4Forward, args)],
 		terminators: vec![Terminator::ByteTunnel],
 		entries,
 		meta: meta_with_udp(listen),
@@ -162,6 +162,8 @@ fn tls_zero_rtt_graph(
 		listener_tls,
 		listener_kinds: BTreeMap::new(),
 		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
 		annotations: Vec::new(),
 	};
 
@@ -172,6 +174,8 @@ fn tls_zero_rtt_graph(
 				id: FetchId::for_testing(0),
 				next_response: Some(NodeId::for_testing(2)),
 				next_tunnel: None,
+				on_error: None,
+				on_error_status: Vec::new(),
 				collect_body_before: None,
 				body_limit: 0,
 			},
@@ -180,10 +184,8 @@ fn tls_zero_rtt_graph(
 		predicates: vec![],
 		middlewares: vec![],
 		fetches: vec![SymbolicFetchRef {
-			kind: FetchKind::HttpSynthesize,
-			args: Value::Null,
-			retry_buffer_required: false,
 			allow_zero_rtt,
+			..SymbolicFetchRef::new(FetchKind::HttpSynthesize, Value::Null)
 		}],
 		terminators: vec![Terminator::WriteHttpResponse],
 		entries,
@@ -0,0 +1,239 @@
+//! JA3 and JA4 TLS client fingerprints of a parsed [`ClientHello`].
+//!
+//! JA3 (Salesforce) is the MD5 of five comma-separated fields, each a
+//! `-`-joined list of decimal values in wire order:
+//!
+//!   SSLVersion,Ciphers,Extensions,EllipticCurves,EllipticCurvePointFormats
+//!
+//! JA4 (FoxIO, TLS client) is `a_b_c`:
+//!
+//!   a  protocol (t = TCP, q = QUIC), TLS version (highest
+//!      supported_versions entry, else legacy_version), d/i for SNI
+//!      present/absent, two-digit cipher and extension counts, first
+//!      and last character of the first ALPN value
+//!   b  first 12 hex chars of SHA-256 over the sorted cipher list
+//!   c  first 12 hex chars of SHA-256 over the sorted extension list
+//!      minus server_name and ALPN, `_`, then the signature
+//!      algorithms in wire order
+//!
+//! Both drop GREASE values (RFC 8701). Sorting makes JA4 stable under
+//! the extension-order randomisation modern browsers do; JA3 is not.
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::tls::{ClientHello, EXT_ALPN, EXT_SERVER_NAME};
+
+/// Transport the hello arrived on — JA4's leading character.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ja4Transport {
+	Tcp,
+	Quic,
+}
+
+/// Truncated hash JA4 substitutes for an empty list.
+const EMPTY_JA4_HASH: &str = "000000000000";
+
+impl ClientHello {
+	/// JA3 fingerprint: 32 lowercase hex characters.
+	#[must_use]
+	pub fn ja3(&self) -> String {
+		hex(&Md5::digest(self.ja3_text().as_bytes()))
+	}
+
+	fn ja3_text(&self) -> String {
+		fn join<T: Copy + Into<u16>>(values: &[T]) -> String {
+			values
+				.iter()
+				.map(|v| (*v).into())
+				.filter(|v| !is_grease(*v))
+				.map(|v| v.to_string())
+				.collect::<Vec<_>>()
+				.join("-")
+		}
+		format!(
+			"{},{},{},{},{}",
+			self.legacy_version,
+			join(&self.cipher_suites),
+			join(&self.extensions),
+			join(&self.supported_groups),
+			join(&self.ec_point_formats),
+		)
+	}
+
+	/// JA4 fingerprint, e.g. `t13d1516h2_8daaf6152771_e5627efa2ab1`.
+	#[must_use]
+	pub fn ja4(&self, transport: Ja4Transport) -> String {
+		let ciphers = sorted_hex(self.cipher_suites.iter().copied());
+		let extensions = sorted_hex(
+			self.extensions.iter().copied().filter(|e| *e != EXT_SERVER_NAME && *e != EXT_ALPN),
+		);
+		let sig_algs = self
+			.signature_algorithms
+			.iter()
+			.filter(|v| !is_grease(**v))
+			.map(|v| format!("{v:04x}"))
+			.collect::<Vec<_>>()
+			.join(",");
+		let c = if sig_algs.is_empty() { extensions } else { format!("{extensions}_{sig_algs}") };
+		format!(
+			"{}{}{}{:02}{:02}{}_{}_{}",
+			match transport {
+				Ja4Transport::Tcp => 't',
+				Ja4Transport::Quic => 'q',
+			},
+			self.ja4_version(),
+			if self.extensions.contains(&EXT_SERVER_NAME) { 'd' } else { 'i' },
+			count(&self.cipher_suites),
+			count(&self.extensions),
+			self.ja4_alpn(),
+			truncated_sha256(&ciphers),
+			truncated_sha256(&c),
+		)
+	}
+
+	fn ja4_version(&self) -> &'static str {
+		let version = self
+			.supported_versions
+			.iter()
+			.copied()
+			.filter(|v| !is_grease(*v))
+			.max()
+			.unwrap_or(self.legacy_version);
+		match version {
+			0x0304 => "13",
+			0x0303 => "12",
+			0x0302 => "11",
+			0x0301 => "10",
+			0x0300 => "s3",
+			0x0002 => "s2",
+			_ => "00",
+		}
+	}
+
+	/// First and last character of the first ALPN value, or of its hex
+	/// form when either end is not ASCII alphanumeric; `00` without ALPN.
+	fn ja4_alpn(&self) -> String {
+		let Some(first) = self.alpn.first().filter(|p| !p.is_empty()) else {
+			return "00".to_string();
+		};
+		let (head, tail) = (first[0], first[first.len() - 1]);
+		if head.is_ascii_alphanumeric() && tail.is_ascii_alphanumeric() {
+			[char::from(head), char::from(tail)].into_iter().collect()
+		} else {
+			let hex = hex(first);
+			[hex.as_bytes()[0], hex.as_bytes()[hex.len() - 1]].into_iter().map(char::from).collect()
+		}
+	}
+}
+
+/// GREASE values are `0x?a?a` with both bytes equal (RFC 8701 §2).
+fn is_grease(v: u16) -> bool {
+	v & 0x0f0f == 0x0a0a && v >> 8 == v & 0xff
+}
+
+fn count(values: &[u16]) -> usize {
+	values.iter().filter(|v| !is_grease(**v)).count().min(99)
+}
+
+fn sorted_hex(values: impl Iterator<Item = u16>) -> String {
+	let mut values: Vec<u16> = values.filter(|v| !is_grease(*v)).collect();
+	values.sort_unstable();
+	values.iter().map(|v| format!("{v:04x}")).collect::<Vec<_>>().join(",")
+}
+
+fn truncated_sha256(text: &str) -> String {
+	if text.is_empty() {
+		return EMPTY_JA4_HASH.to_string();
+	}
+	let mut out = hex(&Sha256::digest(text.as_bytes()));
+	out.truncate(12);
+	out
+}
+
+fn hex(bytes: &[u8]) -> String {
+	use std::fmt::Write as _;
+	bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+		let _ = write!(s, "{b:02x}");
+		s
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tls::test_support::*;
+
+	const CHROME_CIPHERS: [u16; 16] = [
+		0x3a3a, 0x1301, 0x1302, 0x1303, 0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc013, 0xc014,
+		0x009c, 0x009d, 0x002f, 0x0035,
+	];
+	const CHROME_SIG_ALGS: [u16; 8] =
+		[0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601];
+
+	/// A Chrome-shaped hello matching the worked example in the JA4
+	/// reference documentation: 15 ciphers, 16 extensions, h2 first,
+	/// GREASE at both ends of the extension list.
+	fn chrome_hello(extension_order: &[u16]) -> Vec<u8> {
+		let extensions: Vec<(u16, Vec<u8>)> = extension_order
+			.iter()
+			.map(|ty| match *ty {
+				0x0000 => sni_ext("example.com"),
+				0x000a => u16_list_ext(0x000a, &[0x4a4a, 0x001d, 0x0017, 0x0018]),
+				0x000b => (0x000b, vec![1, 0]),
+				0x000d => u16_list_ext(0x000d, &CHROME_SIG_ALGS),
+				0x0010 => alpn_ext(&[b"h2", b"http/1.1"]),
+				0x002b => supported_versions_ext(&[0x5a5a, 0x0304, 0x0303]),
+				other => (other, Vec::new()),
+			})
+			.collect();
+		handshake_message(&CHROME_CIPHERS, &extensions)
+	}
+
+	const CHROME_EXTENSIONS: [u16; 18] = [
+		0x2a2a, 0x0000, 0x0017, 0xff01, 0x000a, 0x000b, 0x0023, 0x0010, 0x0005, 0x000d, 0x0012, 0x0033,
+		0x002d, 0x002b, 0x001b, 0x0015, 0x4469, 0x1a1a,
+	];
+
+	#[test]
+	fn ja4_matches_the_reference_example() {
+		let hello = ClientHello::parse_handshake(&chrome_hello(&CHROME_EXTENSIONS)).unwrap().unwrap();
+		assert_eq!(hello.ja4(Ja4Transport::Tcp), "t13d1516h2_8daaf6152771_e5627efa2ab1");
+		assert_eq!(hello.ja4(Ja4Transport::Quic), "q13d1516h2_8daaf6152771_e5627efa2ab1");
+	}
+
+	#[test]
+	fn ja4_ignores_extension_order_but_ja3_does_not() {
+		let mut shuffled = CHROME_EXTENSIONS;
+		shuffled[1..17].reverse();
+		let a = ClientHello::parse_handshake(&chrome_hello(&CHROME_EXTENSIONS)).unwrap().unwrap();
+		let b = ClientHello::parse_handshake(&chrome_hello(&shuffled)).unwrap().unwrap();
+		assert_eq!(a.ja4(Ja4Transport::Tcp), b.ja4(Ja4Transport::Tcp));
+		assert_ne!(a.ja3(), b.ja3());
+	}
+
+	#[test]
+	fn ja3_drops_grease_and_keeps_wire_order() {
+		let hello = ClientHello::parse_handshake(&chrome_hello(&CHROME_EXTENSIONS)).unwrap().unwrap();
+		assert_eq!(
+			hello.ja3_text(),
+			"771,4865-4866-4867-49195-49199-49196-49200-52393-52392-49171-49172-156-157-47-53,\
+			 0-23-65281-10-11-35-16-5-13-18-51-45-43-27-21-17513,29-23-24,0",
+		);
+		assert_eq!(hello.ja3(), "50a0e1f8c13ee9e5521e3f374a63a021");
+	}
+
+	#[test]
+	fn bare_hello_uses_the_empty_markers() {
+		let hello = ClientHello::parse_handshake(&handshake_message(&[0x0a0a], &[])).unwrap().unwrap();
+		assert_eq!(hello.ja4(Ja4Transport::Tcp), "t12i000000_000000000000_000000000000");
+		assert_eq!(hello.ja3_text(), "771,,,,");
+	}
+
+	#[test]
+	fn non_alphanumeric_alpn_falls_back_to_hex() {
+		let msg = handshake_message(&[0x1301], &[alpn_ext(&[&[0xab, b'x', 0x0d]])]);
+		let hello = ClientHello::parse_handshake(&msg).unwrap().unwrap();
+		assert!(hello.ja4(Ja4Transport::Quic).starts_with("q12i0101ad_"));
+	}
+}
@@ -8,7 +8,8 @@
 // for the pedantic `similar_names` lint would obscure the crypto.
 
 //! Extract the TLS Server Name Indication (SNI) from a QUIC client's
-//! Initial datagrams without performing a full QUIC handshake.
+//! Initial datagrams without performing a full QUIC handshake, and
+//! fingerprint the client (JA3 / JA4) from the same `ClientHello`.
 //!
 //! QUIC Initial packets carry the TLS `ClientHello` in CRYPTO frames,
 //! AEAD-encrypted with keys derived from the client's chosen
@@ -22,6 +23,12 @@
 //! probes, and any system that needs to route QUIC connections by
 //! server name without terminating them.
 //!
+//! The parsed hello is available as a [`ClientHello`] — from
+//! [`Extractor::client_hello`] for QUIC, or
+//! [`ClientHello::parse_records`] for TLS over TCP — with the cipher,
+//! extension, group and ALPN lists and the [`ClientHello::ja3`] /
+//! [`ClientHello::ja4`] fingerprints.
+//!
 //! # Versions
 //!
 //! Supports **QUIC v1** (transport version `0x00000001`, RFC 9000).
@@ -47,12 +54,16 @@
 //! ```
 
 mod aead;
+mod fingerprint;
 mod frame;
 mod header;
 mod keys;
 mod reassemble;
 mod tls;
 
+pub use crate::fingerprint::Ja4Transport;
+pub use crate::tls::ClientHello;
+
 use crate::header::InitialHeader;
 use crate::keys::{InitialKeys, derive_client_initial_keys};
 use crate::reassemble::CryptoStream;
@@ -72,9 +83,9 @@ pub struct Extractor {
 	keys: Option<InitialKeys>,
 	stream: CryptoStream,
 	datagrams_seen: usize,
-	/// Cached parsed SNI so subsequent pushes return the same answer
-	/// without re-running the parser.
-	cached_sni: Option<String>,
+	/// Cached parsed hello (always carrying an SNI) so subsequent
+	/// pushes return the same answer without re-running the parser.
+	hello: Option<ClientHello>,
 }
 
 impl Extractor {
@@ -82,7 +93,7 @@ impl Extractor {
 	/// growth is bounded by the bytes you feed via [`Self::push`].
 	#[must_use]
 	pub fn new() -> Self {
-		Self { keys: None, stream: CryptoStream::new(), datagrams_seen: 0, cached_sni: None }
+		Self { keys: None, stream: CryptoStream::new(), datagrams_seen: 0, hello: None }
 	}
 
 	/// Feed one UDP datagram into the extractor.
@@ -98,8 +109,8 @@ impl Extractor {
 	pub fn push(&mut self, datagram: &[u8]) -> Result<PushOutcome, Error> {
 		self.datagrams_seen += 1;
 
-		if let Some(sni) = &self.cached_sni {
-			return Ok(PushOutcome::Sni(sni.clone()));
+		if let Some(sni) = self.hello.as_ref().and_then(|h| h.server_name.clone()) {
+			return Ok(PushOutcome::Sni(sni));
 		}
 
 		let header = InitialHeader::parse(datagram)?;
@@ -122,9 +133,10 @@ impl Extractor {
 		}
 
 		match self.stream.contiguous_prefix() {
-			Some(prefix) => match tls::try_extract_sni(prefix)? {
-				Some(sni) => {
-					self.cached_sni = Some(sni.clone());
+			Some(prefix) => match ClientHello::parse_handshake(prefix)? {
+				Some(hello) => {
+					let sni = hello.server_name.clone().ok_or(Error::TlsParse)?;
+					self.hello = Some(hello);
 					Ok(PushOutcome::Sni(sni))
 				}
 				None => Ok(PushOutcome::NeedMore),
@@ -133,6 +145,14 @@ impl Extractor {
 		}
 	}
 
+	/// The parsed `ClientHello` once [`Self::push`] has returned
+	/// [`PushOutcome::Sni`] — ALPN, cipher and extension lists, and the
+	/// [`ClientHello::ja4`] fingerprint with [`Ja4Transport::Quic`].
+	#[must_use]
+	pub fn client_hello(&self) -> Option<&ClientHello> {
+		self.hello.as_ref()
+	}
+
 	/// Number of bytes buffered in the CRYPTO reassembly stream.
 	/// Useful for callers that want to enforce their own per-session
 	/// budget alongside the parser.
@@ -202,8 +222,10 @@ pub enum Error {
 	/// conflicting bytes is treated as adversarial.
 	#[error("CRYPTO frames overlap with conflicting bytes")]
 	ConflictingOverlap,
-	/// TLS `ClientHello` structure malformed, or the `ServerName`
-	/// extension is present but contains no `host_name` entry.
+	/// TLS `ClientHello` structure malformed, or it carries no
+	/// `host_name` (an Initial push needs one to report
+	/// [`PushOutcome::Sni`]). Also returned by
+	/// [`ClientHello::parse_records`] for a non-handshake record.
 	#[error("malformed TLS ClientHello or missing SNI")]
 	TlsParse,
 }
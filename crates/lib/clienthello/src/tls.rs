@@ -1,7 +1,8 @@
-//! TLS 1.3 ClientHello parser, scoped to the SNI extension.
+//! TLS ClientHello parser: SNI for routing, plus the cipher, extension,
+//! group and ALPN lists the JA3 / JA4 fingerprints are built from.
 //!
 //! QUIC carries the ClientHello directly inside CRYPTO frames — no
-//! TLS record-layer header. So the parser starts at:
+//! TLS record-layer header. So the QUIC parser starts at:
 //!
 //!   HandshakeMessage:
 //!     msg_type:   u8   == 0x01 (ClientHello)
@@ -18,6 +19,19 @@
 //!       extensions_length       u16
 //!       extensions              [u8; k]
 //!
+//! Over TCP the same HandshakeMessage is wrapped in one or more TLS
+//! records (RFC 8446 §5.1):
+//!
+//!   TLSPlaintext:
+//!     content_type    u8   == 0x16 (handshake)
+//!     legacy_version  u16
+//!     length          u16  (<= 2^14)
+//!     fragment        [u8; length]
+//!
+//! and a large ClientHello (post-quantum key shares) may span several
+//! records, so [`ClientHello::parse_records`] reassembles fragments
+//! before parsing.
+//!
 //! The server_name extension (type 0x0000, RFC 6066 §3) wraps a list
 //! of `ServerName` entries; only `name_type = 0` (host_name) is
 //! defined. The host_name is a length-prefixed UTF-8 string.
@@ -25,42 +39,105 @@
 use crate::Error;
 
 const HANDSHAKE_TYPE_CLIENT_HELLO: u8 = 0x01;
-const EXT_SERVER_NAME: u16 = 0x0000;
+const CONTENT_TYPE_HANDSHAKE: u8 = 0x16;
+const MAX_RECORD_FRAGMENT: usize = 1 << 14;
+pub(crate) const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+pub(crate) const EXT_ALPN: u16 = 0x0010;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
 const NAME_TYPE_HOST_NAME: u8 = 0x00;
 
-/// Try to extract the SNI host name from a buffered ClientHello prefix.
-///
-/// Returns:
-///   * `Ok(Some(sni))` when a complete ClientHello is parsed and the
-///     SNI extension carries a host_name. The returned string is
-///     **guaranteed ASCII-lowercased**: SNI is case-insensitive per
-///     RFC 6066 §3 and the `tls.sni` predicate compiles its operand to
-///     ASCII-lowercase, so this parser normalizes once on the wire side
-///     to keep the equality semantics consistent across TCP and QUIC.
-///   * `Ok(None)` when the buffer is shorter than the declared
-///     ClientHello body — more bytes needed.
-///   * `Err(_)` when the structure is malformed or the SNI extension
-///     is present but contains no host_name.
-pub(crate) fn try_extract_sni(buf: &[u8]) -> Result<Option<String>, Error> {
-	if buf.len() < 4 {
-		return Ok(None);
-	}
-	if buf[0] != HANDSHAKE_TYPE_CLIENT_HELLO {
-		return Err(Error::TlsParse);
+/// The parts of a TLS `ClientHello` this crate reads. Every list is
+/// kept in wire order with GREASE values (RFC 8701) left in; the
+/// fingerprint methods filter them.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ClientHello {
+	/// `legacy_version` from the hello body — `0x0303` for every
+	/// TLS 1.2 / 1.3 client.
+	pub legacy_version: u16,
+	pub cipher_suites: Vec<u16>,
+	/// Extension types, in the order the client sent them.
+	pub extensions: Vec<u16>,
+	/// `host_name` from the `server_name` extension, ASCII-lowercased
+	/// (see [`crate::PushOutcome::Sni`] for the invariant).
+	pub server_name: Option<String>,
+	pub supported_groups: Vec<u16>,
+	pub ec_point_formats: Vec<u8>,
+	/// ALPN protocol names, in client preference order.
+	pub alpn: Vec<Vec<u8>>,
+	pub signature_algorithms: Vec<u16>,
+	pub supported_versions: Vec<u16>,
+}
+
+impl ClientHello {
+	/// Parse a ClientHello handshake message with no record header, as
+	/// QUIC carries it in the CRYPTO stream.
+	///
+	/// Returns `Ok(None)` while `buf` is shorter than the declared
+	/// message; trailing bytes past the message are ignored.
+	///
+	/// # Errors
+	///
+	/// [`Error::TlsParse`] when the message is not a ClientHello or is
+	/// structurally malformed, including a `server_name` extension
+	/// without a `host_name` entry.
+	pub fn parse_handshake(buf: &[u8]) -> Result<Option<Self>, Error> {
+		if buf.len() < 4 {
+			return Ok(None);
+		}
+		if buf[0] != HANDSHAKE_TYPE_CLIENT_HELLO {
+			return Err(Error::TlsParse);
+		}
+		let body_len = (usize::from(buf[1]) << 16) | (usize::from(buf[2]) << 8) | usize::from(buf[3]);
+		let total_needed = 4 + body_len;
+		if buf.len() < total_needed {
+			return Ok(None);
+		}
+		parse_client_hello_body(&buf[4..total_needed]).map(Some)
 	}
-	let body_len = (usize::from(buf[1]) << 16) | (usize::from(buf[2]) << 8) | usize::from(buf[3]);
-	let total_needed = 4 + body_len;
-	if buf.len() < total_needed {
-		return Ok(None);
+
+	/// Parse a ClientHello from the start of a TLS-over-TCP byte
+	/// stream, reassembling the handshake message across records.
+	///
+	/// Returns `Ok(None)` while the hello is still incomplete.
+	///
+	/// # Errors
+	///
+	/// [`Error::TlsParse`] when a record before the end of the hello
+	/// is not a handshake record or exceeds the 2^14-byte fragment
+	/// limit, or on any error [`Self::parse_handshake`] reports.
+	pub fn parse_records(buf: &[u8]) -> Result<Option<Self>, Error> {
+		let mut handshake = Vec::new();
+		let mut idx = 0;
+		while buf.len() >= idx + 5 {
+			if buf[idx] != CONTENT_TYPE_HANDSHAKE {
+				return Err(Error::TlsParse);
+			}
+			let len = usize::from(read_u16(buf, idx + 3)?);
+			if len > MAX_RECORD_FRAGMENT {
+				return Err(Error::TlsParse);
+			}
+			let end = idx + 5 + len;
+			let Some(fragment) = buf.get(idx + 5..end) else {
+				break;
+			};
+			handshake.extend_from_slice(fragment);
+			if let Some(hello) = Self::parse_handshake(&handshake)? {
+				return Ok(Some(hello));
+			}
+			idx = end;
+		}
+		Ok(None)
 	}
-	let body = &buf[4..total_needed];
-	parse_client_hello_body(body).map(Some)
 }
 
-fn parse_client_hello_body(body: &[u8]) -> Result<String, Error> {
-	let mut idx: usize = 0;
+fn parse_client_hello_body(body: &[u8]) -> Result<ClientHello, Error> {
+	let mut hello = ClientHello { legacy_version: read_u16(body, 0)?, ..ClientHello::default() };
 	// legacy_version (2) + random (32)
-	idx = idx.checked_add(34).ok_or(Error::TlsParse)?;
+	let mut idx: usize = 34;
 	if body.len() < idx {
 		return Err(Error::TlsParse);
 	}
@@ -72,29 +149,37 @@ fn parse_client_hello_body(body: &[u8]) -> Result<String, Error> {
 	}
 	idx = idx.checked_add(sid_len).ok_or(Error::TlsParse)?;
 	// cipher_suites
-	let cs_len = read_u16(body, idx)?;
+	let cs_len = usize::from(read_u16(body, idx)?);
 	idx += 2;
-	if cs_len % 2 != 0 {
+	if !cs_len.is_multiple_of(2) {
 		return Err(Error::TlsParse);
 	}
-	idx = idx.checked_add(usize::from(cs_len)).ok_or(Error::TlsParse)?;
+	let cs_end = idx.checked_add(cs_len).ok_or(Error::TlsParse)?;
+	hello.cipher_suites = read_u16_list(body.get(idx..cs_end).ok_or(Error::TlsParse)?)?;
+	idx = cs_end;
 	// compression_methods
 	let comp_len = usize::from(*body.get(idx).ok_or(Error::TlsParse)?);
 	idx += 1;
 	idx = idx.checked_add(comp_len).ok_or(Error::TlsParse)?;
-	// extensions
+	if idx > body.len() {
+		return Err(Error::TlsParse);
+	}
+	// extensions — the whole block may be absent in a pre-TLS-1.3
+	// hello (RFC 5246 §7.4.1.2).
+	if idx == body.len() {
+		return Ok(hello);
+	}
 	let ext_total = usize::from(read_u16(body, idx)?);
 	idx += 2;
 	let ext_end = idx.checked_add(ext_total).ok_or(Error::TlsParse)?;
 	if ext_end > body.len() {
 		return Err(Error::TlsParse);
 	}
-	let extensions = &body[idx..ext_end];
-
-	parse_extensions_for_sni(extensions)
+	parse_extensions(&body[idx..ext_end], &mut hello)?;
+	Ok(hello)
 }
 
-fn parse_extensions_for_sni(extensions: &[u8]) -> Result<String, Error> {
+fn parse_extensions(extensions: &[u8], hello: &mut ClientHello) -> Result<(), Error> {
 	let mut idx = 0;
 	while idx < extensions.len() {
 		let ext_type = read_u16(extensions, idx)?;
@@ -105,22 +190,29 @@ fn parse_extensions_for_sni(extensions: &[u8]) -> Result<String, Error> {
 		if ext_end > extensions.len() {
 			return Err(Error::TlsParse);
 		}
-		if ext_type == EXT_SERVER_NAME {
-			return parse_server_name_extension(&extensions[idx..ext_end]);
+		let payload = &extensions[idx..ext_end];
+		hello.extensions.push(ext_type);
+		match ext_type {
+			EXT_SERVER_NAME => hello.server_name = Some(parse_server_name_extension(payload)?),
+			EXT_SUPPORTED_GROUPS => hello.supported_groups = read_u16_list(u16_prefixed(payload)?)?,
+			EXT_EC_POINT_FORMATS => hello.ec_point_formats = u8_prefixed(payload)?.to_vec(),
+			EXT_SIGNATURE_ALGORITHMS => {
+				hello.signature_algorithms = read_u16_list(u16_prefixed(payload)?)?;
+			}
+			EXT_ALPN => hello.alpn = parse_alpn_extension(payload)?,
+			EXT_SUPPORTED_VERSIONS => {
+				hello.supported_versions = read_u16_list(u8_prefixed(payload)?)?;
+			}
+			_ => {}
 		}
 		idx = ext_end;
 	}
-	Err(Error::TlsParse)
+	Ok(())
 }
 
 fn parse_server_name_extension(payload: &[u8]) -> Result<String, Error> {
 	// ServerNameList: list_length (u16), then entries.
-	let list_len = usize::from(read_u16(payload, 0)?);
-	let list_end = 2usize.checked_add(list_len).ok_or(Error::TlsParse)?;
-	if list_end > payload.len() {
-		return Err(Error::TlsParse);
-	}
-	let list = &payload[2..list_end];
+	let list = u16_prefixed(payload)?;
 	let mut idx = 0;
 	while idx < list.len() {
 		let name_type = *list.get(idx).ok_or(Error::TlsParse)?;
@@ -133,7 +225,7 @@ fn parse_server_name_extension(payload: &[u8]) -> Result<String, Error> {
 		}
 		let name_bytes = &list[idx..name_end];
 		if name_type == NAME_TYPE_HOST_NAME {
-			// Lowercase invariant — see `try_extract_sni` docs and
+			// Lowercase invariant — see `ClientHello::server_name` and
 			// the `tls.sni` operand contract in `core::predicate`.
 			return std::str::from_utf8(name_bytes)
 				.map(str::to_ascii_lowercase)
@@ -144,64 +236,146 @@ fn parse_server_name_extension(payload: &[u8]) -> Result<String, Error> {
 	Err(Error::TlsParse)
 }
 
+fn parse_alpn_extension(payload: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+	// ProtocolNameList: list_length (u16), then u8-prefixed names.
+	let mut list = u16_prefixed(payload)?;
+	let mut names = Vec::new();
+	while let Some((&len, rest)) = list.split_first() {
+		let name = rest.get(..usize::from(len)).ok_or(Error::TlsParse)?;
+		names.push(name.to_vec());
+		list = &rest[name.len()..];
+	}
+	Ok(names)
+}
+
+/// The body of a `u16`-length-prefixed vector; bytes past it are ignored.
+fn u16_prefixed(payload: &[u8]) -> Result<&[u8], Error> {
+	let len = usize::from(read_u16(payload, 0)?);
+	payload.get(2..2 + len).ok_or(Error::TlsParse)
+}
+
+fn u8_prefixed(payload: &[u8]) -> Result<&[u8], Error> {
+	let len = usize::from(*payload.first().ok_or(Error::TlsParse)?);
+	payload.get(1..1 + len).ok_or(Error::TlsParse)
+}
+
+fn read_u16_list(bytes: &[u8]) -> Result<Vec<u16>, Error> {
+	if !bytes.len().is_multiple_of(2) {
+		return Err(Error::TlsParse);
+	}
+	Ok(bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect())
+}
+
 fn read_u16(buf: &[u8], offset: usize) -> Result<u16, Error> {
 	let bytes: [u8; 2] =
 		buf.get(offset..offset + 2).ok_or(Error::TlsParse)?.try_into().map_err(|_| Error::TlsParse)?;
 	Ok(u16::from_be_bytes(bytes))
 }
 
+/// Hand-rolled ClientHello builders shared by this module's and
+/// `fingerprint`'s tests.
 #[cfg(test)]
-mod tests {
+pub(crate) mod test_support {
 	use super::*;
 
-	/// Build a minimal ClientHello with the given SNI host_name.
-	/// Cipher suite list is `[0x1301]` (TLS_AES_128_GCM_SHA256), one
-	/// compression method (null). `host_name` is the only extension.
-	fn build_client_hello(sni: &str) -> Vec<u8> {
+	/// Wrap `ciphers` and `extensions` (type, payload) into a
+	/// ClientHello handshake message: legacy_version 0x0303, zero
+	/// random, empty session id, null compression.
+	pub(crate) fn handshake_message(ciphers: &[u16], extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
 		let mut body: Vec<u8> = Vec::new();
-		// legacy_version
 		body.extend_from_slice(&[0x03, 0x03]);
-		// random
 		body.extend_from_slice(&[0u8; 32]);
-		// session_id
-		body.push(0);
-		// cipher_suites
-		body.extend_from_slice(&2u16.to_be_bytes());
-		body.extend_from_slice(&[0x13, 0x01]);
-		// compression
-		body.push(1);
 		body.push(0);
-		// extensions
+		body.extend_from_slice(&len16(ciphers.len() * 2));
+		for c in ciphers {
+			body.extend_from_slice(&c.to_be_bytes());
+		}
+		body.extend_from_slice(&[1, 0]);
 		let mut ext_body: Vec<u8> = Vec::new();
-		// server_name extension
-		let sni_bytes = sni.as_bytes();
-		let host_name_len = u16::try_from(sni_bytes.len()).expect("sni fits u16");
-		let server_name_entry_len = 1 + 2 + host_name_len; // name_type + len + bytes
-		let list_len = server_name_entry_len; // single entry
-		let ext_payload_len = 2 + list_len; // list_length prefix + list
-		ext_body.extend_from_slice(&EXT_SERVER_NAME.to_be_bytes());
-		ext_body.extend_from_slice(&ext_payload_len.to_be_bytes());
-		ext_body.extend_from_slice(&list_len.to_be_bytes());
-		ext_body.push(NAME_TYPE_HOST_NAME);
-		ext_body.extend_from_slice(&host_name_len.to_be_bytes());
-		ext_body.extend_from_slice(sni_bytes);
-		body.extend_from_slice(&u16::try_from(ext_body.len()).expect("ext fits u16").to_be_bytes());
+		for (ty, payload) in extensions {
+			ext_body.extend_from_slice(&ty.to_be_bytes());
+			ext_body.extend_from_slice(&len16(payload.len()));
+			ext_body.extend_from_slice(payload);
+		}
+		body.extend_from_slice(&len16(ext_body.len()));
 		body.extend_from_slice(&ext_body);
 
-		// Wrap in HandshakeMessage envelope. `body_len` is bounded by
-		// our test fixtures (well under 2 KB) so `try_from` for the
-		// 24-bit big-endian length encoding never trips.
-		let body_len = body.len();
-		let body_len_u32 = u32::try_from(body_len).expect("test fixture body fits u24");
-		let mut msg = Vec::with_capacity(4 + body_len);
-		msg.push(HANDSHAKE_TYPE_CLIENT_HELLO);
-		msg.push(u8::try_from((body_len_u32 >> 16) & 0xff).expect("byte"));
-		msg.push(u8::try_from((body_len_u32 >> 8) & 0xff).expect("byte"));
-		msg.push(u8::try_from(body_len_u32 & 0xff).expect("byte"));
+		// Test fixtures stay well under 2^24, so the u24 length is
+		// the low three bytes of a u32.
+		let body_len = u32::try_from(body.len()).expect("test fixture body fits u24");
+		let mut msg = vec![HANDSHAKE_TYPE_CLIENT_HELLO];
+		msg.extend_from_slice(&body_len.to_be_bytes()[1..]);
 		msg.extend_from_slice(&body);
 		msg
 	}
 
+	pub(crate) fn sni_ext(host: &str) -> (u16, Vec<u8>) {
+		let mut entry = vec![NAME_TYPE_HOST_NAME];
+		entry.extend_from_slice(&len16(host.len()));
+		entry.extend_from_slice(host.as_bytes());
+		(EXT_SERVER_NAME, prefixed16(&entry))
+	}
+
+	pub(crate) fn alpn_ext(protocols: &[&[u8]]) -> (u16, Vec<u8>) {
+		let mut list = Vec::new();
+		for p in protocols {
+			list.push(u8::try_from(p.len()).expect("alpn name fits u8"));
+			list.extend_from_slice(p);
+		}
+		(EXT_ALPN, prefixed16(&list))
+	}
+
+	/// A `u16`-list extension body (`supported_groups`,
+	/// `signature_algorithms`).
+	pub(crate) fn u16_list_ext(ty: u16, values: &[u16]) -> (u16, Vec<u8>) {
+		let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+		(ty, prefixed16(&bytes))
+	}
+
+	pub(crate) fn supported_versions_ext(versions: &[u16]) -> (u16, Vec<u8>) {
+		let mut body = vec![u8::try_from(versions.len() * 2).expect("fits u8")];
+		body.extend(versions.iter().flat_map(|v| v.to_be_bytes()));
+		(EXT_SUPPORTED_VERSIONS, body)
+	}
+
+	/// Split `message` into TLS handshake records of at most
+	/// `fragment` bytes each.
+	pub(crate) fn records(message: &[u8], fragment: usize) -> Vec<u8> {
+		let mut out = Vec::new();
+		for chunk in message.chunks(fragment) {
+			out.extend_from_slice(&[CONTENT_TYPE_HANDSHAKE, 0x03, 0x01]);
+			out.extend_from_slice(&len16(chunk.len()));
+			out.extend_from_slice(chunk);
+		}
+		out
+	}
+
+	fn prefixed16(bytes: &[u8]) -> Vec<u8> {
+		let mut out = len16(bytes.len()).to_vec();
+		out.extend_from_slice(bytes);
+		out
+	}
+
+	fn len16(len: usize) -> [u8; 2] {
+		u16::try_from(len).expect("test fixture length fits u16").to_be_bytes()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::test_support::*;
+	use super::*;
+
+	/// Minimal ClientHello: one cipher suite (TLS_AES_128_GCM_SHA256),
+	/// `host_name` the only extension.
+	fn build_client_hello(sni: &str) -> Vec<u8> {
+		handshake_message(&[0x1301], &[sni_ext(sni)])
+	}
+
+	fn try_extract_sni(buf: &[u8]) -> Result<Option<String>, Error> {
+		ClientHello::parse_handshake(buf)?.map(|h| h.server_name.ok_or(Error::TlsParse)).transpose()
+	}
+
 	#[test]
 	fn extracts_sni_from_minimal_client_hello() {
 		let hello = build_client_hello("example.com");
@@ -240,4 +414,66 @@ mod tests {
 		let sni = try_extract_sni(&hello).expect("parse").expect("present");
 		assert_eq!(sni, "example.com");
 	}
+
+	#[test]
+	fn parses_fingerprint_fields_in_wire_order() {
+		let msg = handshake_message(
+			&[0x0a0a, 0x1301, 0xc02b],
+			&[
+				(0x1a1a, Vec::new()),
+				sni_ext("example.com"),
+				u16_list_ext(EXT_SUPPORTED_GROUPS, &[0x001d, 0x0017]),
+				(EXT_EC_POINT_FORMATS, vec![1, 0]),
+				alpn_ext(&[b"h2", b"http/1.1"]),
+				u16_list_ext(EXT_SIGNATURE_ALGORITHMS, &[0x0403, 0x0804]),
+				supported_versions_ext(&[0x0304, 0x0303]),
+			],
+		);
+		let hello = ClientHello::parse_handshake(&msg).unwrap().unwrap();
+		assert_eq!(hello.legacy_version, 0x0303);
+		assert_eq!(hello.cipher_suites, [0x0a0a, 0x1301, 0xc02b]);
+		assert_eq!(hello.extensions, [0x1a1a, 0x0000, 0x000a, 0x000b, 0x0010, 0x000d, 0x002b]);
+		assert_eq!(hello.server_name.as_deref(), Some("example.com"));
+		assert_eq!(hello.supported_groups, [0x001d, 0x0017]);
+		assert_eq!(hello.ec_point_formats, [0]);
+		assert_eq!(hello.alpn, [b"h2".to_vec(), b"http/1.1".to_vec()]);
+		assert_eq!(hello.signature_algorithms, [0x0403, 0x0804]);
+		assert_eq!(hello.supported_versions, [0x0304, 0x0303]);
+	}
+
+	#[test]
+	fn hello_without_sni_parses_but_has_no_server_name() {
+		let msg = handshake_message(&[0x1301], &[alpn_ext(&[b"h2"])]);
+		let hello = ClientHello::parse_handshake(&msg).unwrap().unwrap();
+		assert_eq!(hello.server_name, None);
+		assert!(matches!(try_extract_sni(&msg), Err(Error::TlsParse)));
+	}
+
+	#[test]
+	fn malformed_alpn_list_is_rejected() {
+		// Name length 5 overruns the 3-byte list.
+		let msg = handshake_message(&[0x1301], &[(EXT_ALPN, vec![0, 3, 5, b'h', b'2'])]);
+		assert!(matches!(ClientHello::parse_handshake(&msg), Err(Error::TlsParse)));
+	}
+
+	#[test]
+	fn parse_records_reassembles_a_hello_split_across_records() {
+		let msg = handshake_message(&[0x1301], &[sni_ext("split.example"), alpn_ext(&[b"h2"])]);
+		let stream = records(&msg, 16);
+		let hello = ClientHello::parse_records(&stream).unwrap().unwrap();
+		assert_eq!(hello.server_name.as_deref(), Some("split.example"));
+		assert_eq!(ClientHello::parse_handshake(&msg).unwrap().unwrap(), hello);
+
+		// Cut mid-record and on a record boundary: both need more.
+		assert_eq!(ClientHello::parse_records(&stream[..stream.len() - 3]).unwrap(), None);
+		assert_eq!(ClientHello::parse_records(&stream[..21]).unwrap(), None);
+	}
+
+	#[test]
+	fn parse_records_rejects_non_handshake_records() {
+		let mut stream = records(&build_client_hello("x.example"), 4096);
+		stream[0] = 0x17; // application_data
+		assert!(matches!(ClientHello::parse_records(&stream), Err(Error::TlsParse)));
+		assert!(matches!(ClientHello::parse_records(b"GET / HTTP/1.1\r\n"), Err(Error::TlsParse)));
+	}
 }
@@ -0,0 +1,250 @@
+//! Hedging: when an attempt has not answered within a delay, race a
+//! second copy of the request against it and keep whichever answers
+//! first.
+//!
+//! Three pieces, all transport-agnostic:
+//!
+//! - [`HedgePolicy`] / [`HedgeDelay`] — when to fire the hedge: a fixed
+//!   delay, or a percentile of the upstream's observed latency.
+//! - [`LatencyWindow`] — the ring of recent latencies a percentile
+//!   delay is read from.
+//! - [`HedgeBudget`] — a shared cap on hedges as a percentage of the
+//!   hedge-eligible traffic, so a slow upstream cannot double the load
+//!   sent to it.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use http::Method;
+
+/// Hedging configuration for one request path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HedgePolicy {
+	pub delay: HedgeDelay,
+}
+
+/// How long an attempt may go without a response before the hedge
+/// fires.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HedgeDelay {
+	Fixed(Duration),
+	/// The given percentile (1–99) of the upstream's recent latencies.
+	/// No hedge fires until the window holds
+	/// [`LatencyWindow::MIN_SAMPLES`] samples.
+	Percentile(u8),
+}
+
+impl HedgeDelay {
+	/// The delay to wait before hedging, or `None` when a percentile
+	/// delay has too few samples to be meaningful.
+	#[must_use]
+	pub fn resolve(&self, window: Option<&LatencyWindow>) -> Option<Duration> {
+		match *self {
+			Self::Fixed(d) => Some(d),
+			Self::Percentile(p) => window?.percentile(p),
+		}
+	}
+}
+
+/// Methods a hedge may duplicate: the RFC 9110 idempotent set (GET,
+/// HEAD, PUT, DELETE, OPTIONS). Sending one of these twice has the
+/// same effect as sending it once.
+#[must_use]
+pub fn is_hedgeable(method: &Method) -> bool {
+	matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+/// Fixed-size ring of the most recent latency samples.
+#[derive(Debug)]
+pub struct LatencyWindow {
+	ring: Mutex<Ring>,
+}
+
+#[derive(Debug)]
+struct Ring {
+	samples: Vec<Duration>,
+	next: usize,
+}
+
+impl LatencyWindow {
+	/// Samples kept; older ones are overwritten.
+	pub const CAPACITY: usize = 128;
+	/// Samples required before [`Self::percentile`] answers.
+	pub const MIN_SAMPLES: usize = 20;
+
+	#[must_use]
+	pub fn new() -> Self {
+		Self { ring: Mutex::new(Ring { samples: Vec::with_capacity(Self::CAPACITY), next: 0 }) }
+	}
+
+	pub fn record(&self, latency: Duration) {
+		let mut ring = self.ring.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+		if ring.samples.len() < Self::CAPACITY {
+			ring.samples.push(latency);
+		} else {
+			let i = ring.next;
+			ring.samples[i] = latency;
+		}
+		ring.next = (ring.next + 1) % Self::CAPACITY;
+	}
+
+	/// The `p`th percentile (clamped to 1–99) of the recorded samples,
+	/// or `None` below [`Self::MIN_SAMPLES`].
+	#[must_use]
+	pub fn percentile(&self, p: u8) -> Option<Duration> {
+		let mut samples = {
+			let ring = self.ring.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+			if ring.samples.len() < Self::MIN_SAMPLES {
+				return None;
+			}
+			ring.samples.clone()
+		};
+		let rank = (samples.len() * usize::from(p.clamp(1, 99))).div_ceil(100) - 1;
+		let (_, nth, _) = samples.select_nth_unstable(rank);
+		Some(*nth)
+	}
+}
+
+impl Default for LatencyWindow {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Shared cap on hedges, as a percentage of hedge-eligible requests.
+///
+/// Every eligible request [`deposit`](Self::deposit)s `percent`
+/// hundredths of a hedge; firing one [`withdraw`](Self::try_withdraw)s
+/// a whole hedge. The balance is capped at [`Self::BURST`] hedges so a
+/// quiet period cannot bank an unbounded burst.
+#[derive(Debug)]
+pub struct HedgeBudget {
+	percent: u64,
+	/// Balance in hundredths of a hedge.
+	credits: AtomicU64,
+}
+
+impl HedgeBudget {
+	/// Most hedges the balance can hold.
+	pub const BURST: u64 = 10;
+	const UNIT: u64 = 100;
+
+	/// A budget of `percent` (clamped to 100) hedges per 100 eligible
+	/// requests, starting empty.
+	#[must_use]
+	pub const fn new(percent: u8) -> Self {
+		let percent = if percent > 100 { 100 } else { percent };
+		Self { percent: percent as u64, credits: AtomicU64::new(0) }
+	}
+
+	#[must_use]
+	pub const fn percent(&self) -> u8 {
+		#[expect(clippy::cast_possible_truncation, reason = "clamped to 100 in `new`")]
+		let p = self.percent as u8;
+		p
+	}
+
+	/// Count one hedge-eligible request toward the budget.
+	pub fn deposit(&self) {
+		let cap = Self::BURST * Self::UNIT;
+		let _ = self.credits.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| {
+			(c < cap).then(|| (c + self.percent).min(cap))
+		});
+	}
+
+	/// Spend one hedge; `false` when the budget is exhausted.
+	pub fn try_withdraw(&self) -> bool {
+		self
+			.credits
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| c.checked_sub(Self::UNIT))
+			.is_ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_idempotent_methods_are_hedgeable() {
+		for m in [Method::GET, Method::HEAD, Method::PUT, Method::DELETE, Method::OPTIONS] {
+			assert!(is_hedgeable(&m), "{m}");
+		}
+		for m in [Method::POST, Method::PATCH, Method::TRACE, Method::CONNECT] {
+			assert!(!is_hedgeable(&m), "{m}");
+		}
+	}
+
+	#[test]
+	fn percentile_needs_min_samples() {
+		let w = LatencyWindow::new();
+		for ms in 1..LatencyWindow::MIN_SAMPLES as u64 {
+			w.record(Duration::from_millis(ms));
+		}
+		assert_eq!(w.percentile(95), None);
+		w.record(Duration::from_millis(20));
+		assert!(w.percentile(95).is_some());
+	}
+
+	#[test]
+	fn percentile_reads_the_nearest_rank() {
+		let w = LatencyWindow::new();
+		for ms in 1..=100 {
+			w.record(Duration::from_millis(ms));
+		}
+		assert_eq!(w.percentile(95), Some(Duration::from_millis(95)));
+		assert_eq!(w.percentile(50), Some(Duration::from_millis(50)));
+	}
+
+	#[test]
+	fn window_keeps_only_the_latest_samples() {
+		let w = LatencyWindow::new();
+		for _ in 0..LatencyWindow::CAPACITY {
+			w.record(Duration::from_secs(10));
+		}
+		for _ in 0..LatencyWindow::CAPACITY {
+			w.record(Duration::from_millis(5));
+		}
+		assert_eq!(w.percentile(99), Some(Duration::from_millis(5)));
+	}
+
+	#[test]
+	fn fixed_delay_ignores_the_window() {
+		let d = HedgeDelay::Fixed(Duration::from_millis(30));
+		assert_eq!(d.resolve(None), Some(Duration::from_millis(30)));
+		assert_eq!(HedgeDelay::Percentile(95).resolve(None), None);
+	}
+
+	#[test]
+	fn budget_allows_its_percentage_of_traffic() {
+		let b = HedgeBudget::new(10);
+		assert!(!b.try_withdraw(), "starts empty");
+		for _ in 0..9 {
+			b.deposit();
+		}
+		assert!(!b.try_withdraw(), "nine requests earn 0.9 hedges");
+		b.deposit();
+		assert!(b.try_withdraw());
+		assert!(!b.try_withdraw());
+	}
+
+	#[test]
+	fn budget_balance_is_capped_at_burst() {
+		let b = HedgeBudget::new(100);
+		for _ in 0..1000 {
+			b.deposit();
+		}
+		let spent = std::iter::from_fn(|| b.try_withdraw().then_some(())).count();
+		assert_eq!(spent as u64, HedgeBudget::BURST);
+	}
+
+	#[test]
+	fn zero_percent_never_hedges() {
+		let b = HedgeBudget::new(0);
+		for _ in 0..1000 {
+			b.deposit();
+		}
+		assert!(!b.try_withdraw());
+	}
+}
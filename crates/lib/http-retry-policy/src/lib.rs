@@ -12,6 +12,10 @@ use std::time::Duration;
 use http::Method;
 use rand::RngExt;
 
+mod hedge;
+
+pub use hedge::{HedgeBudget, HedgeDelay, HedgePolicy, LatencyWindow, is_hedgeable};
+
 /// HTTP retry policy. Pair with a per-attempt loop that consults
 /// [`Backoff::delay_for_attempt`] and the configured method allow-list.
 #[derive(Clone, Debug)]
@@ -24,6 +28,10 @@ pub struct RetryPolicy {
 	pub methods: HashSet<Method>,
 	pub backoff: Backoff,
 	pub buffering: BufferingPolicy,
+	/// Race a second attempt against a slow first one. `None` (the
+	/// default) disables hedging. Like retry, hedging needs a
+	/// replayable body.
+	pub hedge: Option<HedgePolicy>,
 }
 
 /// Body-buffering posture for retry. Names the trade-off between
@@ -54,6 +62,7 @@ impl Default for RetryPolicy {
 			methods: Self::idempotent_methods(),
 			backoff: Backoff::exponential_default(),
 			buffering: BufferingPolicy::Opportunistic,
+			hedge: None,
 		}
 	}
 }
@@ -176,6 +185,11 @@ mod tests {
 		assert_eq!(p.buffering, BufferingPolicy::Opportunistic);
 	}
 
+	#[test]
+	fn default_policy_does_not_hedge() {
+		assert_eq!(RetryPolicy::default().hedge, None);
+	}
+
 	#[test]
 	fn backoff_none_returns_zero() {
 		assert_eq!(Backoff::None.delay_for_attempt(2), Duration::ZERO);
@@ -3,13 +3,17 @@
 //! `call(verb, args) -> result`, and a future multiplexed transport
 //! can be slotted in without changing the call shape.
 
+use std::io::IoSliceMut;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use nix::sys::socket::{ControlMessageOwned, MsgFlags, recvmsg};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Interest};
 use tokio::net::UnixStream;
 
 use crate::protocol::{Request, Response, ResponseOutcome, WireError, WireErrorKind, encode_line};
+use crate::server::{MAX_NDJSON_LINE_BYTES, MAX_PASSED_FDS};
 
 /// Maximum time to wait for `UnixStream::connect` to return. The mgmt
 /// daemon is on the same host as the CLI; if the kernel doesn't grant
@@ -75,22 +79,49 @@ impl UnixClient {
 			.await
 			.map_err(|_| ClientError::Timeout("read"))??
 			.ok_or(ClientError::EmptyResponse)?;
-		let response: Response = serde_json::from_str(&line).map_err(ClientError::Decode)?;
-		match response.outcome {
-			ResponseOutcome::Result { result } => {
-				serde_json::from_value(result).map_err(ClientError::Decode)
-			}
-			ResponseOutcome::Error { error } => Err(ClientError::Server(error)),
-			ResponseOutcome::Event { .. } | ResponseOutcome::End { .. } => {
-				// Streaming frame on a one-shot `call`. Either the verb is
-				// a streaming verb (caller should use `call_stream`) or
-				// the server is buggy.
-				Err(ClientError::Server(WireError::new(
-					WireErrorKind::Internal,
-					"received streaming frame on one-shot call",
-				)))
-			}
+		decode_one_shot(&line)
+	}
+
+	/// [`Self::call`] for verbs that answer with
+	/// [`crate::DispatchOutcome::OneShotWithFds`]: returns the typed
+	/// result plus the descriptors passed alongside it, in the order the
+	/// server listed them. Each arrives close-on-exec where the platform
+	/// supports `MSG_CMSG_CLOEXEC`.
+	///
+	/// # Errors
+	/// As [`Self::call`]; a reply longer than
+	/// [`crate::server::MAX_NDJSON_LINE_BYTES`] or with truncated
+	/// ancillary data is an [`ClientError::Io`].
+	pub async fn call_with_fds<A, R>(
+		&self,
+		verb: &str,
+		args: &A,
+	) -> Result<(R, Vec<OwnedFd>), ClientError>
+	where
+		A: serde::Serialize,
+		R: for<'de> serde::Deserialize<'de>,
+	{
+		let mut stream = tokio::time::timeout(CONNECT_TIMEOUT, UnixStream::connect(&self.socket_path))
+			.await
+			.map_err(|_| ClientError::Timeout("connect"))??;
+		let req = Request {
+			id: 1,
+			verb: verb.to_string(),
+			args: serde_json::to_value(args).map_err(ClientError::Encode)?,
+		};
+		let bytes = encode_line(&req).map_err(ClientError::Encode)?;
+		stream.write_all(&bytes).await?;
+		stream.shutdown().await.ok();
+
+		let (line, fds) = tokio::time::timeout(ONESHOT_TIMEOUT, recv_line_with_fds(&stream))
+			.await
+			.map_err(|_| ClientError::Timeout("read"))??;
+		if line.is_empty() {
+			return Err(ClientError::EmptyResponse);
 		}
+		let line = String::from_utf8(line)
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+		Ok((decode_one_shot(&line)?, fds))
 	}
 
 	/// Send a streaming verb and consume each `Event` frame via
@@ -153,6 +184,85 @@ impl UnixClient {
 	}
 }
 
+/// Decode one response line of a one-shot call.
+fn decode_one_shot<R>(line: &str) -> Result<R, ClientError>
+where
+	R: for<'de> serde::Deserialize<'de>,
+{
+	let response: Response = serde_json::from_str(line).map_err(ClientError::Decode)?;
+	match response.outcome {
+		ResponseOutcome::Result { result } => {
+			serde_json::from_value(result).map_err(ClientError::Decode)
+		}
+		ResponseOutcome::Error { error } => Err(ClientError::Server(error)),
+		ResponseOutcome::Event { .. } | ResponseOutcome::End { .. } => {
+			// Streaming frame on a one-shot `call`. Either the verb is
+			// a streaming verb (caller should use `call_stream`) or
+			// the server is buggy.
+			Err(ClientError::Server(WireError::new(
+				WireErrorKind::Internal,
+				"received streaming frame on one-shot call",
+			)))
+		}
+	}
+}
+
+/// Read one line off `stream` with `recvmsg`, keeping any descriptors
+/// passed alongside it. The trailing newline is stripped.
+async fn recv_line_with_fds(stream: &UnixStream) -> std::io::Result<(Vec<u8>, Vec<OwnedFd>)> {
+	let mut line = Vec::new();
+	let mut fds = Vec::new();
+	let mut chunk = [0u8; 4096];
+	loop {
+		let n = stream
+			.async_io(Interest::READABLE, || recv_chunk(stream.as_raw_fd(), &mut chunk, &mut fds))
+			.await?;
+		if n == 0 {
+			break;
+		}
+		line.extend_from_slice(&chunk[..n]);
+		if let Some(end) = line.iter().position(|b| *b == b'\n') {
+			line.truncate(end);
+			break;
+		}
+		if line.len() > MAX_NDJSON_LINE_BYTES {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("ndjson line exceeded {MAX_NDJSON_LINE_BYTES}-byte cap"),
+			));
+		}
+	}
+	Ok((line, fds))
+}
+
+#[cfg(any(target_os = "android", target_os = "linux", target_os = "freebsd"))]
+const RECV_FLAGS: MsgFlags = MsgFlags::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "android", target_os = "linux", target_os = "freebsd")))]
+const RECV_FLAGS: MsgFlags = MsgFlags::empty();
+
+/// One non-blocking `recvmsg` into `buf`; passed descriptors are
+/// appended to `fds`.
+fn recv_chunk(fd: RawFd, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> std::io::Result<usize> {
+	let mut cmsg_buf = nix::cmsg_space!([RawFd; MAX_PASSED_FDS]);
+	let mut iov = [IoSliceMut::new(buf)];
+	let msg = recvmsg::<()>(fd, &mut iov, Some(&mut cmsg_buf), RECV_FLAGS)?;
+	for cmsg in msg.cmsgs()? {
+		if let ControlMessageOwned::ScmRights(raw) = cmsg {
+			for raw_fd in raw {
+				#[allow(
+					unsafe_code,
+					reason = "adopting a descriptor the kernel just installed for this process"
+				)]
+				// SAFETY: `SCM_RIGHTS` hands us fresh descriptors that
+				// nothing else in the process refers to yet.
+				let owned = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+				fds.push(owned);
+			}
+		}
+	}
+	Ok(msg.bytes)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
 	#[error("io: {0}")]
@@ -351,4 +461,44 @@ mod tests {
 			.expect_err("must fail without a server");
 		assert!(matches!(err, ClientError::Io(_)), "unexpected variant: {err:?}");
 	}
+
+	/// Hands out a `dup` of one listening TCP socket per call.
+	struct FdHandler {
+		listener: std::net::TcpListener,
+	}
+
+	#[async_trait]
+	impl Handler for FdHandler {
+		async fn dispatch(&self, _req: Request) -> DispatchOutcome {
+			let dup = self.listener.try_clone().expect("dup listener");
+			let addr = self.listener.local_addr().expect("local_addr");
+			DispatchOutcome::OneShotWithFds(
+				serde_json::json!({ "addr": addr.to_string() }),
+				vec![OwnedFd::from(dup)],
+			)
+		}
+	}
+
+	#[tokio::test]
+	async fn call_with_fds_receives_passed_descriptors() {
+		let tmp = tempfile::tempdir().expect("tempdir");
+		let path = tmp.path().join("fds.sock");
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+		let addr = listener.local_addr().expect("local_addr");
+		let cancel = tokio_util::sync::CancellationToken::new();
+		let server = crate::spawn_unix_server(&path, Arc::new(FdHandler { listener }), cancel.clone())
+			.await
+			.expect("spawn server");
+
+		let (result, fds): (serde_json::Value, _) =
+			UnixClient::new(&path).call_with_fds("take", &NoArgs {}).await.expect("call");
+		assert_eq!(result["addr"], addr.to_string());
+		assert_eq!(fds.len(), 1);
+		// The received descriptor is the same listening socket.
+		let received = std::net::TcpListener::from(fds.into_iter().next().expect("one fd"));
+		assert_eq!(received.local_addr().expect("received local_addr"), addr);
+
+		cancel.cancel();
+		let _ = server.await;
+	}
 }
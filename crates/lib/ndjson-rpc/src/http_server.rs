@@ -35,7 +35,9 @@ use tokio::sync::{Semaphore, mpsc};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
-use crate::protocol::{EndMarker, Request, Response, ResponseOutcome, encode_line};
+use crate::protocol::{
+	EndMarker, Request, Response, ResponseOutcome, WireError, WireErrorKind, encode_line,
+};
 use crate::server::{DispatchOutcome, Handler};
 
 /// Hard cap on request body size. Mgmt requests are tiny (a verb +
@@ -230,6 +232,15 @@ async fn handle_request<H: Handler>(
 		DispatchOutcome::OneShot(Err(error)) => {
 			Ok(oneshot_response(&Response { id, outcome: ResponseOutcome::Error { error } }))
 		}
+		DispatchOutcome::OneShotWithFds(..) => {
+			// Dropping the descriptors closes them; HTTP has no channel
+			// to carry them.
+			let error = WireError::new(
+				WireErrorKind::NotImplemented,
+				"descriptor passing needs the unix transport",
+			);
+			Ok(oneshot_response(&Response { id, outcome: ResponseOutcome::Error { error } }))
+		}
 		DispatchOutcome::Stream(stream) => Ok(streaming_response(id, stream)),
 	}
 }
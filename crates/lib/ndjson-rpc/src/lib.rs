@@ -19,4 +19,4 @@ pub use http_server::{HttpServerConfig, HttpServerError, spawn_http_server};
 pub use protocol::{
 	EndMarker, Request, Response, ResponseOutcome, WireError, WireErrorKind, encode_line,
 };
-pub use server::{DispatchOutcome, EventStream, Handler, spawn_unix_server};
+pub use server::{DispatchOutcome, EventStream, Handler, MAX_PASSED_FDS, spawn_unix_server};
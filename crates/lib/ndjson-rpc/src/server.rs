@@ -3,12 +3,16 @@
 //! ([`crate::http_server`]) speaks the same frame shapes, so dispatch
 //! logic is shared.
 
+use std::io::IoSlice;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use nix::sys::socket::{ControlMessage, MsgFlags, sendmsg};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Interest};
+use tokio::net::unix::OwnedWriteHalf;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
@@ -24,6 +28,10 @@ use crate::protocol::{
 /// malformed framing or an adversarial slowloris-by-line attack.
 pub const MAX_NDJSON_LINE_BYTES: usize = 1024 * 1024;
 
+/// Most descriptors one [`DispatchOutcome::OneShotWithFds`] reply may
+/// carry — Linux's `SCM_MAX_FD`, the per-message `SCM_RIGHTS` limit.
+pub const MAX_PASSED_FDS: usize = 253;
+
 /// Server-side dispatcher. Callers implement this against their own
 /// application state and pass an `Arc<H>` to [`spawn_unix_server`] or
 /// [`crate::spawn_http_server`].
@@ -42,6 +50,12 @@ pub trait Handler: Send + Sync + 'static {
 pub enum DispatchOutcome {
 	/// One-shot reply: a single JSON value or a structured error.
 	OneShot(Result<serde_json::Value, WireError>),
+	/// One-shot result that also hands the client open descriptors,
+	/// attached to the result line as `SCM_RIGHTS` ancillary data and
+	/// read back with [`crate::UnixClient::call_with_fds`]. Unix
+	/// transport only: the HTTP transport answers `NotImplemented` and
+	/// closes them. At most [`MAX_PASSED_FDS`] per reply.
+	OneShotWithFds(serde_json::Value, Vec<OwnedFd>),
 	/// Streaming reply: each call to `next_event` yields the next
 	/// `Event` payload, or `None` to terminate with an `End` frame.
 	Stream(Box<dyn EventStream + Send>),
@@ -245,7 +259,7 @@ pub(crate) async fn handle_conn<R, W, H>(
 	cancel: CancellationToken,
 ) where
 	R: AsyncRead + Unpin,
-	W: AsyncWrite + Unpin,
+	W: FdWrite,
 	H: Handler,
 {
 	let mut reader = BufReader::new(read);
@@ -299,6 +313,11 @@ pub(crate) async fn handle_conn<R, W, H>(
 							return;
 						}
 					}
+					DispatchOutcome::OneShotWithFds(value, fds) => {
+						if write_reply_with_fds(&mut write, id, value, fds).await.is_err() {
+							return;
+						}
+					}
 					DispatchOutcome::Stream(mut stream) => {
 						// Streaming verbs consume the connection — once we
 						// start streaming we don't read more requests on
@@ -369,6 +388,63 @@ async fn write_frame<W: AsyncWrite + Unpin>(
 	write.write_all(&bytes).await
 }
 
+/// Write a [`DispatchOutcome::OneShotWithFds`] reply: the result line
+/// with `fds` attached as `SCM_RIGHTS`, or an error frame when there
+/// are more than one message can carry. The kernel holds its own
+/// references once `sendmsg` returns; ours close on return either way.
+async fn write_reply_with_fds<W: FdWrite>(
+	write: &mut W,
+	id: u64,
+	value: serde_json::Value,
+	fds: Vec<OwnedFd>,
+) -> Result<(), std::io::Error> {
+	if fds.len() > MAX_PASSED_FDS {
+		let error = WireError::new(
+			WireErrorKind::Internal,
+			format!("reply carries {} descriptors; at most {MAX_PASSED_FDS}", fds.len()),
+		);
+		return write_frame(write, &Response { id, outcome: ResponseOutcome::Error { error } }).await;
+	}
+	let frame = Response { id, outcome: ResponseOutcome::Result { result: value } };
+	let bytes = encode_line(&frame).map_err(std::io::Error::other)?;
+	let raw: Vec<RawFd> = fds.iter().map(AsRawFd::as_raw_fd).collect();
+	write.write_with_fds(&bytes, &raw).await
+}
+
+/// Write half that can pass descriptors alongside a frame. The Unix
+/// transport's write half implements it; the in-memory pipes the unit
+/// tests drive [`handle_conn`] with refuse.
+pub(crate) trait FdWrite: AsyncWrite + Unpin {
+	async fn write_with_fds(&mut self, bytes: &[u8], fds: &[RawFd]) -> std::io::Result<()>;
+}
+
+impl FdWrite for OwnedWriteHalf {
+	async fn write_with_fds(&mut self, bytes: &[u8], fds: &[RawFd]) -> std::io::Result<()> {
+		let stream: &UnixStream = self.as_ref();
+		let sent = stream
+			.async_io(Interest::WRITABLE, || {
+				let iov = [IoSlice::new(bytes)];
+				let cmsgs = [ControlMessage::ScmRights(fds)];
+				sendmsg::<()>(stream.as_raw_fd(), &iov, &cmsgs, MsgFlags::empty(), None)
+					.map_err(std::io::Error::from)
+			})
+			.await?;
+		// The descriptors travel with the first byte; whatever a short
+		// write left over goes out as plain bytes.
+		self.write_all(&bytes[sent..]).await
+	}
+}
+
+#[cfg(test)]
+impl FdWrite for tokio::io::DuplexStream {
+	async fn write_with_fds(&mut self, _bytes: &[u8], _fds: &[RawFd]) -> std::io::Result<()> {
+		Err(std::io::Error::new(
+			std::io::ErrorKind::Unsupported,
+			"descriptor passing needs a unix socket",
+		))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
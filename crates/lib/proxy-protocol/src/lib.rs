@@ -0,0 +1,400 @@
+//! HAProxy PROXY protocol header parsing and encoding.
+//!
+//! A TCP load balancer that terminates the client's connection and
+//! opens its own to the server hides the client's address: the server
+//! sees the balancer's. The PROXY protocol fixes that by having the
+//! balancer write a short header at the very start of the upstream
+//! connection naming the original source and destination. Two wire
+//! forms exist, and a receiver tells them apart by their first bytes:
+//!
+//! - **v1** — one ASCII line, at most [`V1_MAX_LEN`] bytes:
+//!   `PROXY TCP4 203.0.113.7 192.0.2.1 51000 443\r\n`.
+//! - **v2** — a 16-byte binary prefix (12-byte signature, version /
+//!   command, family / protocol, length) followed by the address block
+//!   and optional TLVs: authority (SNI), a unique connection ID, and
+//!   the TLS details of the client's session when the balancer
+//!   terminated TLS.
+//!
+//! [`Header::parse`] is sans-I/O: hand it whatever has been read so
+//! far and it returns the header plus the number of bytes it spans, or
+//! `Ok(None)` when the buffer is a valid but incomplete prefix. The
+//! caller discards exactly that many bytes and treats the rest as the
+//! application stream. [`Header::encode`] goes the other way for a
+//! proxy that wants to tell its own upstream who the client is.
+//!
+//! The trust decision is the caller's: a header is only meaningful
+//! from a peer known to be a balancer, since anyone can write one.
+//!
+//! Specification: <https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt>.
+
+use std::net::SocketAddr;
+
+mod v1;
+mod v2;
+
+/// Longest legal v1 header, CRLF included.
+pub const V1_MAX_LEN: usize = 107;
+
+/// Longest legal v2 header: the 16-byte prefix plus the largest
+/// length field. Also the most [`Header::parse`] can ever ask for.
+pub const MAX_LEN: usize = 16 + u16::MAX as usize;
+
+/// First 12 bytes of every v2 header.
+pub const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Registered v2 TLV types.
+pub mod tlv {
+	pub const ALPN: u8 = 0x01;
+	pub const AUTHORITY: u8 = 0x02;
+	pub const CRC32C: u8 = 0x03;
+	pub const NOOP: u8 = 0x04;
+	pub const UNIQUE_ID: u8 = 0x05;
+	pub const SSL: u8 = 0x20;
+	pub const SSL_VERSION: u8 = 0x21;
+	pub const SSL_CN: u8 = 0x22;
+	pub const SSL_CIPHER: u8 = 0x23;
+	pub const SSL_SIG_ALG: u8 = 0x24;
+	pub const SSL_KEY_ALG: u8 = 0x25;
+	pub const NETNS: u8 = 0x30;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Version {
+	V1,
+	V2,
+}
+
+/// Transport of the proxied connection. v1 only describes streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+	Stream,
+	Datagram,
+}
+
+/// Original endpoints of the proxied connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Addresses {
+	/// The client, as the balancer saw it.
+	pub source: SocketAddr,
+	/// The address the client connected to on the balancer.
+	pub destination: SocketAddr,
+	pub transport: Transport,
+}
+
+/// One v2 type-length-value entry, value as sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlv {
+	pub kind: u8,
+	pub value: Vec<u8>,
+}
+
+/// A parsed or to-be-encoded PROXY header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+	pub version: Version,
+	/// `None` when the header carries no client: a v2 `LOCAL` command
+	/// (the balancer's own health check), a v1 `UNKNOWN` line, or a v2
+	/// `AF_UNSPEC` / `AF_UNIX` block. The socket addresses stand.
+	pub addresses: Option<Addresses>,
+	/// v2 TLVs in wire order; always empty for v1.
+	pub tlvs: Vec<Tlv>,
+}
+
+/// TLS details the balancer observed on the client's connection, read
+/// from the v2 `SSL` TLV and its sub-TLVs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ssl<'a> {
+	/// `PP2_CLIENT_*` bit field.
+	pub client: u8,
+	/// `0` when the client presented a certificate that verified.
+	pub verify: u32,
+	pub version: Option<&'a str>,
+	pub common_name: Option<&'a str>,
+	pub cipher: Option<&'a str>,
+	pub sig_alg: Option<&'a str>,
+	pub key_alg: Option<&'a str>,
+}
+
+impl Ssl<'_> {
+	/// The client connected over TLS.
+	#[must_use]
+	pub const fn is_tls(&self) -> bool {
+		self.client & 0x01 != 0
+	}
+
+	/// The client presented a certificate (on this connection or an
+	/// earlier one of the resumed session).
+	#[must_use]
+	pub const fn client_cert(&self) -> bool {
+		self.client & 0x06 != 0
+	}
+
+	/// The client presented a certificate and it verified.
+	#[must_use]
+	pub const fn verified(&self) -> bool {
+		self.client_cert() && self.verify == 0
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+	/// The bytes start with neither the v1 `PROXY ` prefix nor the v2
+	/// signature.
+	#[error("not a PROXY protocol header")]
+	NotProxy,
+	#[error("malformed PROXY header: {0}")]
+	Malformed(&'static str),
+	/// Well-formed, but a version or command this crate does not know.
+	#[error("unsupported PROXY header: {0}")]
+	Unsupported(&'static str),
+}
+
+impl Header {
+	/// A header naming `source` → `destination` over a stream.
+	#[must_use]
+	pub const fn proxy(version: Version, source: SocketAddr, destination: SocketAddr) -> Self {
+		Self {
+			version,
+			addresses: Some(Addresses { source, destination, transport: Transport::Stream }),
+			tlvs: Vec::new(),
+		}
+	}
+
+	/// An address-less header (v2 `LOCAL`, v1 `UNKNOWN`).
+	#[must_use]
+	pub const fn local(version: Version) -> Self {
+		Self { version, addresses: None, tlvs: Vec::new() }
+	}
+
+	/// Append a TLV. Ignored by [`Self::encode`] for v1.
+	#[must_use]
+	pub fn with_tlv(mut self, kind: u8, value: impl Into<Vec<u8>>) -> Self {
+		self.tlvs.push(Tlv { kind, value: value.into() });
+		self
+	}
+
+	/// Parse a header from the start of `buf`.
+	///
+	/// Returns the header and the number of bytes it occupies, or
+	/// `Ok(None)` when `buf` is a valid prefix that needs more bytes
+	/// (never more than [`MAX_LEN`] in total).
+	///
+	/// # Errors
+	/// [`Error::NotProxy`] when `buf` cannot start a header,
+	/// [`Error::Malformed`] for a broken one, and
+	/// [`Error::Unsupported`] for a v2 version or command other than
+	/// 2 / `LOCAL` / `PROXY`.
+	pub fn parse(buf: &[u8]) -> Result<Option<(Self, usize)>, Error> {
+		let sig = buf.len().min(V2_SIGNATURE.len());
+		if buf[..sig] == V2_SIGNATURE[..sig] {
+			return if sig < V2_SIGNATURE.len() { Ok(None) } else { v2::parse(buf) };
+		}
+		let prefix = buf.len().min(v1::PREFIX.len());
+		if buf[..prefix] == v1::PREFIX[..prefix] {
+			return if prefix < v1::PREFIX.len() { Ok(None) } else { v1::parse(buf) };
+		}
+		Err(Error::NotProxy)
+	}
+
+	/// Serialise the header. v1 carries neither TLVs nor datagram
+	/// transport; a v1 header with mixed IPv4 / IPv6 endpoints maps
+	/// the IPv4 side into IPv6 (`TCP6`), as does v2. v2 drops any TLV
+	/// that would overflow the 16-bit header length.
+	#[must_use]
+	pub fn encode(&self) -> Vec<u8> {
+		match self.version {
+			Version::V1 => v1::encode(self.addresses.as_ref()),
+			Version::V2 => v2::encode(self.addresses.as_ref(), &self.tlvs),
+		}
+	}
+
+	/// Value of the first TLV of type `kind`.
+	#[must_use]
+	pub fn tlv(&self, kind: u8) -> Option<&[u8]> {
+		self.tlvs.iter().find(|t| t.kind == kind).map(|t| t.value.as_slice())
+	}
+
+	/// `AUTHORITY` TLV: the host name the client asked for (usually
+	/// its TLS SNI). `None` when absent or not UTF-8.
+	#[must_use]
+	pub fn authority(&self) -> Option<&str> {
+		self.tlv(tlv::AUTHORITY).and_then(|v| std::str::from_utf8(v).ok())
+	}
+
+	/// `UNIQUE_ID` TLV: an opaque connection ID of at most 128 bytes.
+	#[must_use]
+	pub fn unique_id(&self) -> Option<&[u8]> {
+		self.tlv(tlv::UNIQUE_ID)
+	}
+
+	/// `SSL` TLV. `None` when absent or when its sub-TLVs are
+	/// malformed; sub-TLV strings that are not UTF-8 read as `None`.
+	#[must_use]
+	pub fn ssl(&self) -> Option<Ssl<'_>> {
+		let value = self.tlv(tlv::SSL)?;
+		let (&client, rest) = value.split_first()?;
+		let verify = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?);
+		let mut ssl = Ssl { client, verify, ..Ssl::default() };
+		for sub in v2::tlvs(&rest[4..]) {
+			let (kind, value) = sub.ok()?;
+			let text = std::str::from_utf8(value).ok();
+			match kind {
+				tlv::SSL_VERSION => ssl.version = text,
+				tlv::SSL_CN => ssl.common_name = text,
+				tlv::SSL_CIPHER => ssl.cipher = text,
+				tlv::SSL_SIG_ALG => ssl.sig_alg = text,
+				tlv::SSL_KEY_ALG => ssl.key_alg = text,
+				_ => {}
+			}
+		}
+		Some(ssl)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn addr(s: &str) -> SocketAddr {
+		s.parse().expect("socket addr")
+	}
+
+	#[test]
+	fn v1_round_trips_ipv4_and_ipv6() {
+		for (src, dst, line) in [
+			("203.0.113.7:51000", "192.0.2.1:443", "PROXY TCP4 203.0.113.7 192.0.2.1 51000 443\r\n"),
+			(
+				"[2001:db8::7]:51000",
+				"[2001:db8::1]:443",
+				"PROXY TCP6 2001:db8::7 2001:db8::1 51000 443\r\n",
+			),
+		] {
+			let header = Header::proxy(Version::V1, addr(src), addr(dst));
+			assert_eq!(header.encode(), line.as_bytes());
+			let mut wire = header.encode();
+			wire.extend_from_slice(b"GET / HTTP/1.1\r\n");
+			assert_eq!(Header::parse(&wire), Ok(Some((header, line.len()))));
+		}
+	}
+
+	#[test]
+	fn v1_unknown_has_no_addresses() {
+		let (header, used) = Header::parse(b"PROXY UNKNOWN ffff::1 ffff::2 1 2\r\nrest")
+			.expect("parse")
+			.expect("complete");
+		assert_eq!(header.addresses, None);
+		assert_eq!(used, 35);
+		assert_eq!(Header::local(Version::V1).encode(), b"PROXY UNKNOWN\r\n");
+	}
+
+	#[test]
+	fn v1_rejects_malformed_lines() {
+		for bad in [
+			&b"PROXY TCP4 203.0.113.7 192.0.2.1 51000\r\n"[..],
+			b"PROXY TCP4 2001:db8::7 192.0.2.1 51000 443\r\n",
+			b"PROXY TCP4 203.0.113.7 192.0.2.1 +1 443\r\n",
+			b"PROXY TCP4 203.0.113.7 192.0.2.1 70000 443\r\n",
+			b"PROXY UDP4 203.0.113.7 192.0.2.1 1 2\r\n",
+		] {
+			assert!(matches!(Header::parse(bad), Err(Error::Malformed(_))), "{bad:?}");
+		}
+		let long = [b"PROXY ".as_slice(), &[b'x'; V1_MAX_LEN]].concat();
+		assert!(matches!(Header::parse(&long), Err(Error::Malformed(_))));
+	}
+
+	#[test]
+	fn partial_input_asks_for_more() {
+		let v1 = Header::proxy(Version::V1, addr("203.0.113.7:1"), addr("192.0.2.1:2")).encode();
+		let v2 = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("192.0.2.1:2"))
+			.with_tlv(tlv::AUTHORITY, "example.com")
+			.encode();
+		for wire in [v1, v2] {
+			for cut in 0..wire.len() {
+				assert_eq!(Header::parse(&wire[..cut]), Ok(None), "cut at {cut}");
+			}
+			assert!(Header::parse(&wire).expect("parse").is_some());
+		}
+	}
+
+	#[test]
+	fn rejects_non_proxy_bytes() {
+		assert_eq!(Header::parse(b"GET / HTTP/1.1\r\n"), Err(Error::NotProxy));
+		assert_eq!(Header::parse(b"\x16\x03\x01"), Err(Error::NotProxy));
+	}
+
+	#[test]
+	fn v2_round_trips_addresses_and_tlvs() {
+		let header = Header::proxy(Version::V2, addr("[2001:db8::7]:51000"), addr("[2001:db8::1]:443"))
+			.with_tlv(tlv::AUTHORITY, "api.example.com")
+			.with_tlv(tlv::UNIQUE_ID, [1, 2, 3]);
+		let wire = header.encode();
+		assert_eq!(&wire[..12], &V2_SIGNATURE);
+		assert_eq!(wire[12], 0x21);
+		assert_eq!(wire[13], 0x21);
+		assert_eq!(Header::parse(&wire), Ok(Some((header.clone(), wire.len()))));
+		assert_eq!(header.authority(), Some("api.example.com"));
+		assert_eq!(header.unique_id(), Some(&[1, 2, 3][..]));
+	}
+
+	#[test]
+	fn mixed_families_map_to_ipv6() {
+		let header = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("[2001:db8::1]:2"));
+		let (parsed, _) = Header::parse(&header.encode()).expect("parse").expect("complete");
+		let addrs = parsed.addresses.expect("addresses");
+		assert_eq!(addrs.source, addr("[::ffff:203.0.113.7]:1"));
+		let v1 = Header { version: Version::V1, ..header }.encode();
+		assert!(v1.starts_with(b"PROXY TCP6 ::ffff:203.0.113.7 2001:db8::1 "), "{v1:?}");
+	}
+
+	#[test]
+	fn v2_local_ignores_addresses_but_keeps_tlvs() {
+		let mut wire = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("192.0.2.1:2"))
+			.with_tlv(tlv::NOOP, [])
+			.encode();
+		wire[12] = 0x20;
+		let (header, _) = Header::parse(&wire).expect("parse").expect("complete");
+		assert_eq!(header.addresses, None);
+		assert_eq!(header.tlvs.len(), 1);
+		assert_eq!(Header::local(Version::V2).encode()[12..], [0x20, 0x00, 0x00, 0x00]);
+	}
+
+	#[test]
+	fn v2_rejects_bad_version_command_and_lengths() {
+		let good = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("192.0.2.1:2")).encode();
+		let mut bad_version = good.clone();
+		bad_version[12] = 0x11;
+		assert_eq!(Header::parse(&bad_version), Err(Error::Unsupported("version")));
+		let mut bad_command = good.clone();
+		bad_command[12] = 0x2f;
+		assert_eq!(Header::parse(&bad_command), Err(Error::Unsupported("command")));
+		// Length field too short for an AF_INET block.
+		let mut short = good.clone();
+		short[15] = 4;
+		assert!(matches!(Header::parse(&short), Err(Error::Malformed(_))));
+		// TLV claims more bytes than the header holds.
+		let mut overrun = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("192.0.2.1:2"))
+			.with_tlv(tlv::AUTHORITY, "a")
+			.encode();
+		let len = overrun.len();
+		overrun[len - 2] = 9;
+		assert!(matches!(Header::parse(&overrun), Err(Error::Malformed(_))));
+	}
+
+	#[test]
+	fn v2_reads_ssl_sub_tlvs() {
+		let mut ssl = vec![0x07, 0, 0, 0, 0];
+		for (kind, value) in [(tlv::SSL_VERSION, "TLSv1.3"), (tlv::SSL_CN, "client.example")] {
+			ssl.push(kind);
+			ssl.extend_from_slice(&u16::try_from(value.len()).expect("len").to_be_bytes());
+			ssl.extend_from_slice(value.as_bytes());
+		}
+		let header = Header::proxy(Version::V2, addr("203.0.113.7:1"), addr("192.0.2.1:2"))
+			.with_tlv(tlv::SSL, ssl);
+		let (parsed, _) = Header::parse(&header.encode()).expect("parse").expect("complete");
+		let ssl = parsed.ssl().expect("ssl tlv");
+		assert!(ssl.is_tls() && ssl.client_cert() && ssl.verified());
+		assert_eq!(ssl.version, Some("TLSv1.3"));
+		assert_eq!(ssl.common_name, Some("client.example"));
+		assert_eq!(ssl.cipher, None);
+	}
+}
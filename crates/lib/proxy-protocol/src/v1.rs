@@ -0,0 +1,79 @@
+//! v1: `PROXY <TCP4|TCP6|UNKNOWN> <src> <dst> <sport> <dport>\r\n`.
+
+use std::fmt::Write as _;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::{Addresses, Error, Header, Transport, V1_MAX_LEN, Version};
+
+pub(crate) const PREFIX: &[u8] = b"PROXY ";
+
+pub(crate) fn parse(buf: &[u8]) -> Result<Option<(Header, usize)>, Error> {
+	let window = &buf[..buf.len().min(V1_MAX_LEN)];
+	let Some(end) = window.windows(2).position(|w| w == b"\r\n") else {
+		return if window.len() == V1_MAX_LEN {
+			Err(Error::Malformed("v1 line exceeds 107 bytes"))
+		} else {
+			Ok(None)
+		};
+	};
+	let line = std::str::from_utf8(&buf[PREFIX.len()..end])
+		.map_err(|_| Error::Malformed("v1 line is not ASCII"))?;
+	let mut fields = line.split(' ');
+	let addresses = match fields.next() {
+		Some("UNKNOWN") => None,
+		Some("TCP4") => Some(parse_endpoints::<Ipv4Addr>(&mut fields)?),
+		Some("TCP6") => Some(parse_endpoints::<Ipv6Addr>(&mut fields)?),
+		_ => return Err(Error::Malformed("v1 protocol must be TCP4, TCP6 or UNKNOWN")),
+	};
+	Ok(Some((Header { version: Version::V1, addresses, tlvs: Vec::new() }, end + 2)))
+}
+
+fn parse_endpoints<'a, A>(fields: &mut impl Iterator<Item = &'a str>) -> Result<Addresses, Error>
+where
+	A: std::str::FromStr + Into<IpAddr>,
+{
+	let mut next = || fields.next().ok_or(Error::Malformed("v1 line is missing fields"));
+	let ip = |s: &str| s.parse::<A>().map(Into::into).map_err(|_| Error::Malformed("v1 address"));
+	let (src, dst) = (ip(next()?)?, ip(next()?)?);
+	let (sport, dport) = (port(next()?)?, port(next()?)?);
+	if fields.next().is_some() {
+		return Err(Error::Malformed("v1 line has trailing fields"));
+	}
+	Ok(Addresses {
+		source: SocketAddr::new(src, sport),
+		destination: SocketAddr::new(dst, dport),
+		transport: Transport::Stream,
+	})
+}
+
+/// Decimal port with no sign; `u16::from_str` alone would take `+80`.
+fn port(s: &str) -> Result<u16, Error> {
+	if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+		return Err(Error::Malformed("v1 port"));
+	}
+	s.parse().map_err(|_| Error::Malformed("v1 port"))
+}
+
+pub(crate) fn encode(addresses: Option<&Addresses>) -> Vec<u8> {
+	let Some(a) = addresses else {
+		return b"PROXY UNKNOWN\r\n".to_vec();
+	};
+	let mut line = String::with_capacity(V1_MAX_LEN);
+	match (a.source.ip(), a.destination.ip()) {
+		(IpAddr::V4(src), IpAddr::V4(dst)) => {
+			let _ = write!(line, "PROXY TCP4 {src} {dst}");
+		}
+		(src, dst) => {
+			let _ = write!(line, "PROXY TCP6 {} {}", to_v6(src), to_v6(dst));
+		}
+	}
+	let _ = write!(line, " {} {}\r\n", a.source.port(), a.destination.port());
+	line.into_bytes()
+}
+
+pub(crate) fn to_v6(ip: IpAddr) -> Ipv6Addr {
+	match ip {
+		IpAddr::V4(v4) => v4.to_ipv6_mapped(),
+		IpAddr::V6(v6) => v6,
+	}
+}
@@ -0,0 +1,146 @@
+//! v2: 12-byte signature, version / command, family / protocol,
+//! big-endian length, address block, TLVs.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::v1::to_v6;
+use crate::{Addresses, Error, Header, Tlv, Transport, V2_SIGNATURE, Version};
+
+const PREFIX_LEN: usize = 16;
+const INET_LEN: usize = 12;
+const INET6_LEN: usize = 36;
+const UNIX_LEN: usize = 216;
+
+pub(crate) fn parse(buf: &[u8]) -> Result<Option<(Header, usize)>, Error> {
+	let Some(prefix) = buf.get(..PREFIX_LEN) else {
+		return Ok(None);
+	};
+	if prefix[12] >> 4 != 2 {
+		return Err(Error::Unsupported("version"));
+	}
+	let local = match prefix[12] & 0x0f {
+		0 => true,
+		1 => false,
+		_ => return Err(Error::Unsupported("command")),
+	};
+	let len = usize::from(u16::from_be_bytes([prefix[14], prefix[15]]));
+	let Some(body) = buf.get(PREFIX_LEN..PREFIX_LEN + len) else {
+		return Ok(None);
+	};
+
+	let family = prefix[13] >> 4;
+	let transport = match prefix[13] & 0x0f {
+		1 => Some(Transport::Stream),
+		2 => Some(Transport::Datagram),
+		_ => None,
+	};
+	let block_len = match family {
+		0 => 0,
+		1 => INET_LEN,
+		2 => INET6_LEN,
+		3 => UNIX_LEN,
+		_ => return Err(Error::Malformed("v2 address family")),
+	};
+	let (block, rest) =
+		body.split_at_checked(block_len).ok_or(Error::Malformed("v2 address block is truncated"))?;
+	// LOCAL and unspecified protocols carry nothing to act on; the
+	// receiver keeps the socket's own addresses.
+	let addresses = match (local, transport, family) {
+		(false, Some(transport), 1) => {
+			let ip = |at: usize| {
+				IpAddr::V4(Ipv4Addr::new(block[at], block[at + 1], block[at + 2], block[at + 3]))
+			};
+			Some(endpoints(ip(0), ip(4), &block[8..], transport))
+		}
+		(false, Some(transport), 2) => {
+			let ip = |at: usize| {
+				let octets: [u8; 16] = block[at..at + 16].try_into().expect("16-byte slice");
+				IpAddr::V6(Ipv6Addr::from(octets))
+			};
+			Some(endpoints(ip(0), ip(16), &block[32..], transport))
+		}
+		_ => None,
+	};
+
+	let tlvs = tlvs(rest)
+		.map(|t| t.map(|(kind, value)| Tlv { kind, value: value.to_vec() }))
+		.collect::<Result<_, _>>()?;
+	Ok(Some((Header { version: Version::V2, addresses, tlvs }, PREFIX_LEN + len)))
+}
+
+fn endpoints(src: IpAddr, dst: IpAddr, ports: &[u8], transport: Transport) -> Addresses {
+	Addresses {
+		source: SocketAddr::new(src, u16::from_be_bytes([ports[0], ports[1]])),
+		destination: SocketAddr::new(dst, u16::from_be_bytes([ports[2], ports[3]])),
+		transport,
+	}
+}
+
+/// Walk a TLV sequence (top-level or the `SSL` TLV's sub-TLVs).
+pub(crate) fn tlvs(mut buf: &[u8]) -> impl Iterator<Item = Result<(u8, &[u8]), Error>> {
+	std::iter::from_fn(move || {
+		if buf.is_empty() {
+			return None;
+		}
+		let Some(&[kind, hi, lo]) = buf.get(..3) else {
+			buf = &[];
+			return Some(Err(Error::Malformed("v2 TLV header is truncated")));
+		};
+		let end = 3 + usize::from(u16::from_be_bytes([hi, lo]));
+		let Some(value) = buf.get(3..end) else {
+			buf = &[];
+			return Some(Err(Error::Malformed("v2 TLV overruns the header")));
+		};
+		buf = &buf[end..];
+		Some(Ok((kind, value)))
+	})
+}
+
+pub(crate) fn encode(addresses: Option<&Addresses>, tlvs: &[Tlv]) -> Vec<u8> {
+	let mut body = Vec::with_capacity(INET6_LEN);
+	let (command, family_protocol) = match addresses {
+		None => (0x20, 0x00),
+		Some(a) => {
+			let protocol = match a.transport {
+				Transport::Stream => 0x01,
+				Transport::Datagram => 0x02,
+			};
+			match (a.source.ip(), a.destination.ip()) {
+				(IpAddr::V4(src), IpAddr::V4(dst)) => {
+					body.extend_from_slice(&src.octets());
+					body.extend_from_slice(&dst.octets());
+					(0x21, 0x10 | protocol)
+				}
+				(src, dst) => {
+					body.extend_from_slice(&to_v6(src).octets());
+					body.extend_from_slice(&to_v6(dst).octets());
+					(0x21, 0x20 | protocol)
+				}
+			}
+		}
+	};
+	if let Some(a) = addresses {
+		body.extend_from_slice(&a.source.port().to_be_bytes());
+		body.extend_from_slice(&a.destination.port().to_be_bytes());
+	}
+	for t in tlvs {
+		// A TLV that would push the header past its 16-bit length is
+		// dropped rather than corrupting the framing.
+		let Ok(len) = u16::try_from(t.value.len()) else { continue };
+		if body.len() + 3 + t.value.len() > usize::from(u16::MAX) {
+			continue;
+		}
+		body.push(t.kind);
+		body.extend_from_slice(&len.to_be_bytes());
+		body.extend_from_slice(&t.value);
+	}
+
+	let len = u16::try_from(body.len()).expect("body bounded by the TLV loop");
+	let mut out = Vec::with_capacity(PREFIX_LEN + body.len());
+	out.extend_from_slice(&V2_SIGNATURE);
+	out.push(command);
+	out.push(family_protocol);
+	out.extend_from_slice(&len.to_be_bytes());
+	out.extend_from_slice(&body);
+	out
+}
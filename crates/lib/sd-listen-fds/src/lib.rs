@@ -0,0 +1,161 @@
+//! See `README.md` for the operator-facing pitch.
+//!
+//! [`take`] reads `LISTEN_PID` / `LISTEN_FDS` / `LISTEN_FDNAMES` and
+//! wraps fds `3..3 + LISTEN_FDS` as owned sockets. The environment
+//! parse is a pure function ([`parse`]) so its edge cases are testable
+//! without a socket-activated process; the only `unsafe` is the
+//! `from_raw_fd` adoption, guarded by a process-wide once-flag.
+
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use socket2::Socket;
+
+/// First passed descriptor (`SD_LISTEN_FDS_START`); stdin / stdout /
+/// stderr precede it.
+pub const LISTEN_FDS_START: RawFd = 3;
+
+/// One passed socket and its `FileDescriptorName=` (systemd's default
+/// name for an unnamed socket is the socket unit's name).
+#[derive(Debug)]
+pub struct ListenFd {
+	pub socket: Socket,
+	pub name: Option<String>,
+}
+
+/// Set by the first [`take`]; the descriptors belong to that caller.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Adopt the sockets passed to this process.
+///
+/// Returns an empty list when the process was not socket-activated
+/// (`LISTEN_PID` / `LISTEN_FDS` unset), when the variables name another
+/// process (they leaked through an `exec`), or on any call after the
+/// first. Each socket gets `FD_CLOEXEC`.
+///
+/// # Errors
+///
+/// `InvalidInput` for a malformed `LISTEN_*` variable, including a
+/// `LISTEN_FDNAMES` whose entry count differs from `LISTEN_FDS`. Any
+/// error from setting `FD_CLOEXEC` (`EBADF` when the advertised
+/// descriptor is not open).
+pub fn take() -> io::Result<Vec<ListenFd>> {
+	if TAKEN.swap(true, Ordering::AcqRel) {
+		return Ok(Vec::new());
+	}
+	let var = |key: &str| std::env::var(key).ok();
+	let Some(names) = parse(
+		var("LISTEN_PID").as_deref(),
+		var("LISTEN_FDS").as_deref(),
+		var("LISTEN_FDNAMES").as_deref(),
+		std::process::id(),
+	)?
+	else {
+		return Ok(Vec::new());
+	};
+	(LISTEN_FDS_START..)
+		.zip(names)
+		.map(|(fd, name)| {
+			#[allow(
+				unsafe_code,
+				reason = "adopting an fd systemd passed us; `TAKEN` guarantees it is adopted once"
+			)]
+			// SAFETY: `parse` bounded the range to `LISTEN_FDS`, which the
+			// service manager keeps open for this pid, and the once-flag
+			// means no other `OwnedFd` for it exists in this process.
+			let owned = unsafe { OwnedFd::from_raw_fd(fd) };
+			let socket = Socket::from(owned);
+			socket.set_cloexec(true)?;
+			Ok(ListenFd { socket, name })
+		})
+		.collect()
+}
+
+/// Interpret the `LISTEN_*` values for process `own_pid`. `Ok(None)`
+/// means "not activated, or not for us"; otherwise one name slot per
+/// passed fd.
+///
+/// # Errors
+///
+/// `InvalidInput` when a variable does not parse, the count overflows
+/// the fd range, or the name list has the wrong length.
+pub fn parse(
+	listen_pid: Option<&str>,
+	listen_fds: Option<&str>,
+	listen_fdnames: Option<&str>,
+	own_pid: u32,
+) -> io::Result<Option<Vec<Option<String>>>> {
+	let (Some(pid), Some(fds)) = (listen_pid, listen_fds) else {
+		return Ok(None);
+	};
+	let pid: u32 =
+		pid.parse().map_err(|_| invalid(format!("LISTEN_PID is not a process id: {pid:?}")))?;
+	if pid != own_pid {
+		return Ok(None);
+	}
+	let count: usize =
+		fds.parse().map_err(|_| invalid(format!("LISTEN_FDS is not a count: {fds:?}")))?;
+	if RawFd::try_from(count).map_or(true, |n| n > RawFd::MAX - LISTEN_FDS_START) {
+		return Err(invalid(format!("LISTEN_FDS out of range: {count}")));
+	}
+	let names = match listen_fdnames {
+		None => vec![None; count],
+		Some(_) if count == 0 => Vec::new(),
+		Some(list) => {
+			let names: Vec<Option<String>> =
+				list.split(':').map(|n| (!n.is_empty()).then(|| n.to_owned())).collect();
+			if names.len() != count {
+				return Err(invalid(format!(
+					"LISTEN_FDNAMES lists {} names for {count} descriptors",
+					names.len()
+				)));
+			}
+			names
+		}
+	};
+	Ok(Some(names))
+}
+
+fn invalid(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn unset_or_foreign_pid_is_not_activated() {
+		assert!(parse(None, None, None, 42).unwrap().is_none());
+		assert!(parse(Some("42"), None, None, 42).unwrap().is_none());
+		assert!(parse(None, Some("2"), None, 42).unwrap().is_none());
+		assert!(parse(Some("41"), Some("2"), None, 42).unwrap().is_none());
+	}
+
+	#[test]
+	fn count_without_names_yields_unnamed_slots() {
+		assert_eq!(parse(Some("42"), Some("2"), None, 42).unwrap(), Some(vec![None, None]));
+		assert_eq!(parse(Some("42"), Some("0"), Some(""), 42).unwrap(), Some(vec![]));
+	}
+
+	#[test]
+	fn names_pair_with_descriptors_in_order() {
+		let names = parse(Some("42"), Some("3"), Some("http:quic:"), 42).unwrap();
+		assert_eq!(names, Some(vec![Some("http".to_owned()), Some("quic".to_owned()), None]));
+	}
+
+	#[test]
+	fn malformed_values_are_rejected() {
+		for (pid, fds, names) in [
+			("x", "1", None),
+			("42", "-1", None),
+			("42", "one", None),
+			("42", "2147483647", None),
+			("42", "2", Some("only-one")),
+		] {
+			let err = parse(Some(pid), Some(fds), names, 42).unwrap_err();
+			assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{pid} {fds} {names:?}");
+		}
+	}
+}
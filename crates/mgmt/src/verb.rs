@@ -5,6 +5,7 @@
 //!
 //! Verbs: `compile_dry_run`, `reload`, `get_config`, `stats`,
 //! `shutdown`, `get_connections`, plus `ping` for cheap liveness checks.
+//! `upgrade` and its two internal follow-ups are at the bottom.
 //!
 //! See [`spec/crates/mgmt.md` § _Verbs_](../../../spec/crates/mgmt.md#verbs).
 
@@ -22,6 +23,8 @@ pub const VERB_TAIL_LOG: &str = "tail_log";
 pub const VERB_GET_METRICS: &str = "get_metrics";
 pub const VERB_GET_POOLS: &str = "get_pools";
 pub const VERB_GET_UPSTREAMS: &str = "get_upstreams";
+pub const VERB_GET_HEALTH: &str = "get_health";
+pub const VERB_GET_CACHE: &str = "get_cache";
 pub const VERB_RELOAD_NATIVE_ROOTS: &str = "reload_native_roots";
 
 /// Placeholder for verbs that accept no arguments. Round-trips as `{}`.
@@ -204,7 +207,8 @@ pub struct CgiPoolEntry {
 }
 
 /// Snapshot of cached upstream connection objects: the TCP / TLS
-/// `hyper-util` client cache and (when `h3` is built) the QUIC pool.
+/// `hyper-util` client cache and (when `h3` is built) the QUIC pool,
+/// plus the `http_proxy` member sets and their selection state.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GetUpstreamsResult {
 	#[serde(default)]
@@ -212,6 +216,10 @@ pub struct GetUpstreamsResult {
 	/// Empty when the `h3` feature is disabled.
 	#[serde(default)]
 	pub quic: Vec<QuicUpstreamEntry>,
+	/// One entry per live upstream set. Single-`upstream` rules appear
+	/// as one-member sets.
+	#[serde(default)]
+	pub sets: Vec<UpstreamSetEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -246,6 +254,65 @@ pub struct QuicUpstreamEntry {
 	pub fingerprint_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpstreamSetEntry {
+	/// 16-char hex hash of the set's `(policy, members)`. Stable across
+	/// reloads that keep the member list unchanged.
+	pub set_id: String,
+	/// `"round_robin"`, `"weighted_random"`, `"least_in_flight"`,
+	/// `"p2c"`, or `"consistent_hash(<key>)"`.
+	pub policy: String,
+	pub members: Vec<UpstreamMemberEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpstreamMemberEntry {
+	/// `host:port` as written in `args.upstreams`.
+	pub authority: String,
+	pub weight: u32,
+	/// Requests currently dispatched to this authority, summed across
+	/// every set that lists it.
+	pub in_flight: u64,
+	/// Times this set picked the member since the set was built.
+	pub selected: u64,
+	/// Worst active health-check verdict for the authority
+	/// (`"unknown"` / `"healthy"` / `"unhealthy"`); absent when no rule
+	/// probes it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub health: Option<String>,
+}
+
+/// Snapshot of every active health-check target. Targets live at
+/// daemon scope, so the list survives reloads that keep a check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GetHealthResult {
+	#[serde(default)]
+	pub targets: Vec<HealthTargetEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthTargetEntry {
+	/// 16-char hex hash of `(authority, check spec, tls)`.
+	pub target_id: String,
+	pub authority: String,
+	/// Probe type: `"http"`, `"tcp"`, or `"tls"`.
+	pub kind: String,
+	/// `"unknown"` until the first threshold is crossed, then
+	/// `"healthy"` / `"unhealthy"`.
+	pub status: String,
+	pub consecutive_successes: u32,
+	pub consecutive_failures: u32,
+	/// Milliseconds since the last completed probe.
+	#[serde(default)]
+	pub last_check_age_ms: Option<u64>,
+	/// Milliseconds since `status` last changed.
+	#[serde(default)]
+	pub last_change_age_ms: Option<u64>,
+	/// Failure reason of the most recent probe; cleared on success.
+	#[serde(default)]
+	pub last_error: Option<String>,
+}
+
 /// Verb name for the manual pool eviction RPC. Operators look up a
 /// `fingerprint_id` from `get_upstreams` and pass it back here to
 /// remove the matching cache entry. Live `Arc<Client>` /
@@ -268,6 +335,127 @@ pub struct PoolDrainResult {
 	pub quic_drained: usize,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GetCacheArgs {
+	/// Only list keys starting with this string.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub prefix: Option<String>,
+	/// At most this many entries, by key order. Defaults to 100.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub limit: Option<usize>,
+}
+
+/// Snapshot of the shared response cache. The store lives at daemon
+/// scope, so its contents survive reloads.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GetCacheResult {
+	/// `VANE_CACHE_MAX_BYTES`, or its default.
+	pub capacity_bytes: usize,
+	pub used_bytes: usize,
+	/// Keys in the store, whatever the filter.
+	pub keys: usize,
+	/// Keys under `prefix`, before `limit` is applied.
+	pub matched_keys: usize,
+	#[serde(default)]
+	pub entries: Vec<CacheEntryInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheEntryInfo {
+	pub key: String,
+	/// Status of the most recently stored variant.
+	pub status: u16,
+	/// Stored `Vary` variants under this key.
+	pub variants: usize,
+	pub bytes: usize,
+	pub age_secs: u64,
+	/// Freshness left on the newest variant; negative once stale.
+	pub ttl_secs: i64,
+	pub hits: u64,
+}
+
+/// Verb name for dropping cached responses, by exact key or by key
+/// prefix. Requests already streaming a cached body finish normally.
+pub const VERB_CACHE_PURGE: &str = "cache_purge";
+
+/// Exactly one of `key` / `prefix`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CachePurgeArgs {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CachePurgeResult {
+	/// Stored variants removed.
+	pub purged: usize,
+}
+
+/// Verb name for the L1 ban table snapshot. The table lives at daemon
+/// scope, so bans survive reloads.
+pub const VERB_GET_BANS: &str = "get_bans";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GetBansResult {
+	/// `VANE_SEC_BAN_ALLOW`: prefixes that are never banned.
+	#[serde(default)]
+	pub allow: Vec<String>,
+	/// Unexpired bans, hosts first, then wider prefixes.
+	#[serde(default)]
+	pub bans: Vec<BanInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BanInfo {
+	/// CIDR; `/32` / `/128` for a single host.
+	pub target: String,
+	pub remaining_secs: u64,
+	/// Escalation level of an automatic ban: it lasts
+	/// `VANE_SEC_BAN_DURATION * 2^level`.
+	pub level: u32,
+	pub reason: String,
+	/// `"auto"`, `"manual"` or `"plugin"`.
+	pub source: String,
+}
+
+/// Verb name for banning an IP or CIDR by hand. New connections from
+/// it are dropped at accept; established ones run to completion.
+pub const VERB_BAN: &str = "ban";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BanArgs {
+	/// IP address or CIDR.
+	pub target: String,
+	/// Defaults to `VANE_SEC_BAN_DURATION`; capped at
+	/// `VANE_SEC_BAN_MAX_DURATION`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub duration_secs: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BanResult {
+	pub ban: BanInfo,
+}
+
+/// Verb name for lifting a ban. `target` must name the banned prefix
+/// exactly, as `get_bans` lists it (a bare IP for a host ban).
+pub const VERB_UNBAN: &str = "unban";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnbanArgs {
+	pub target: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnbanResult {
+	/// `false` when no ban matched `target`.
+	pub removed: bool,
+}
+
 /// Verb name for the operator-driven "renew this cert NOW" RPC per
 /// `spec/crates/engine-acme.md` § _mgmt verbs_. Bypasses the
 /// `renew_before` timer and any active backoff; useful for
@@ -372,6 +560,55 @@ pub struct AriWindowWire {
 	pub end: String,
 }
 
+/// Verb name for the zero-downtime binary upgrade per
+/// `spec/crates/daemon.md` § _Binary upgrade_. The daemon starts a new
+/// `vaned` from the same path and arguments and returns at once; the
+/// new process takes over the listening sockets and the old one drains
+/// once the new one is healthy. The outcome (hand-over or rollback) is
+/// in the daemon log. `SIGUSR2` does the same.
+pub const VERB_UPGRADE: &str = "upgrade";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpgradeResult {
+	/// Pid of the new `vaned`.
+	pub child_pid: u32,
+}
+
+/// Internal: the new `vaned` asks the old one for its listening
+/// sockets. The reply carries one descriptor per `sockets` entry, in
+/// order, as `SCM_RIGHTS` — Unix transport only.
+pub const VERB_UPGRADE_TAKE_LISTENERS: &str = "upgrade_take_listeners";
+
+/// Internal: the new `vaned` passed its boot health watchdog; the old
+/// one starts its soft drain.
+pub const VERB_UPGRADE_READY: &str = "upgrade_ready";
+
+/// Args of both internal upgrade verbs. Only the child the upgrade
+/// started is answered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpgradeChildArgs {
+	pub pid: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpgradeTakeListenersResult {
+	pub sockets: Vec<HandoffSocketInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HandoffSocketInfo {
+	/// `"tcp"` | `"udp"` | `"unix"`.
+	pub transport: String,
+	/// Socket address, or the socket path for `"unix"`.
+	pub addr: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpgradeReadyResult {
+	/// Always `true`: the old daemon has fired its shutdown trigger.
+	pub draining: bool,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -448,6 +685,17 @@ mod tests {
 		assert_eq!(round_trip(&r), r);
 	}
 
+	#[test]
+	fn upgrade_take_listeners_result_round_trips() {
+		let r = UpgradeTakeListenersResult {
+			sockets: vec![
+				HandoffSocketInfo { transport: "tcp".to_string(), addr: "0.0.0.0:443".to_string() },
+				HandoffSocketInfo { transport: "udp".to_string(), addr: "[::]:443".to_string() },
+			],
+		};
+		assert_eq!(round_trip(&r), r);
+	}
+
 	#[test]
 	fn get_connections_result_round_trips() {
 		let r = GetConnectionsResult {
@@ -536,8 +784,84 @@ mod tests {
 				alpn: vec!["h3".to_string()],
 				fingerprint_id: "fedcba9876543210".to_string(),
 			}],
+			sets: vec![UpstreamSetEntry {
+				set_id: "0123456789abcdef".to_string(),
+				policy: "least_in_flight".to_string(),
+				members: vec![UpstreamMemberEntry {
+					authority: "10.0.0.1:8080".to_string(),
+					weight: 2,
+					in_flight: 3,
+					selected: 40,
+					health: Some("healthy".to_string()),
+				}],
+			}],
+		};
+		assert_eq!(round_trip(&r), r);
+	}
+
+	#[test]
+	fn get_health_result_round_trips() {
+		let r = GetHealthResult {
+			targets: vec![HealthTargetEntry {
+				target_id: "0123456789abcdef".to_string(),
+				authority: "10.0.0.1:8080".to_string(),
+				kind: "http".to_string(),
+				status: "unhealthy".to_string(),
+				consecutive_successes: 0,
+				consecutive_failures: 4,
+				last_check_age_ms: Some(120),
+				last_change_age_ms: Some(9_000),
+				last_error: Some("unexpected status 503".to_string()),
+			}],
+		};
+		assert_eq!(round_trip(&r), r);
+	}
+
+	#[test]
+	fn cache_verbs_round_trip() {
+		let r = GetCacheResult {
+			capacity_bytes: 1 << 28,
+			used_bytes: 4096,
+			keys: 3,
+			matched_keys: 1,
+			entries: vec![CacheEntryInfo {
+				key: "example.com/static/app.js".to_string(),
+				status: 200,
+				variants: 2,
+				bytes: 2048,
+				age_secs: 30,
+				ttl_secs: -5,
+				hits: 12,
+			}],
 		};
 		assert_eq!(round_trip(&r), r);
+		let a = CachePurgeArgs { key: None, prefix: Some("example.com/static/".to_string()) };
+		assert_eq!(round_trip(&a), a);
+		assert_eq!(serde_json::to_string(&a).expect("encode"), r#"{"prefix":"example.com/static/"}"#);
+		let a: GetCacheArgs = serde_json::from_str("{}").expect("decode empty args");
+		assert_eq!(a, GetCacheArgs::default());
+	}
+
+	#[test]
+	fn ban_verbs_round_trip() {
+		let info = BanInfo {
+			target: "198.51.100.7/32".to_string(),
+			remaining_secs: 540,
+			level: 1,
+			reason: "strikes".to_string(),
+			source: "auto".to_string(),
+		};
+		let r = GetBansResult { allow: vec!["127.0.0.0/8".to_string()], bans: vec![info.clone()] };
+		assert_eq!(round_trip(&r), r);
+		let r = BanResult { ban: info };
+		assert_eq!(round_trip(&r), r);
+		let a = BanArgs { target: "203.0.113.0/24".to_string(), duration_secs: None, reason: None };
+		assert_eq!(round_trip(&a), a);
+		assert_eq!(serde_json::to_string(&a).expect("encode"), r#"{"target":"203.0.113.0/24"}"#);
+		let a = UnbanArgs { target: "198.51.100.7".to_string() };
+		assert_eq!(round_trip(&a), a);
+		let r = UnbanResult { removed: true };
+		assert_eq!(round_trip(&r), r);
 	}
 
 	#[test]
@@ -548,5 +872,6 @@ mod tests {
 		let r: GetUpstreamsResult = serde_json::from_str(raw).expect("decode");
 		assert!(r.tcp.is_empty());
 		assert!(r.quic.is_empty());
+		assert!(r.sets.is_empty());
 	}
 }
@@ -1,9 +1,55 @@
-//! `build_flow(rules)` helper — constructs a linked `FlowGraph` from
-//! raw rule bytes without touching disk or spawning a daemon. Used by
-//! unit tests that need an executor target.
+//! Flow-graph fixtures shared by the engine integration tests: an empty
+//! [`FlowGraphMeta`], the two [`FlowLogSink`]s tests hand the executor,
+//! and a loopback listen address.
 //!
 //! See [`spec/crates/core.md` § _Compile pipeline_](../../../spec/crates/core.md#compile-pipeline).
-//
-// TODO(testutil-build-flow): module is documentation-only; integration
-// tests currently inline equivalents. Land the shared helper here when
-// more than one test needs the same shape.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use vane_core::{FlowGraphMeta, FlowLogEvent, FlowLogSink};
+
+/// Graph metadata with every table empty: no TLS, no PROXY, no unix
+/// listeners, no annotations. Tests fill in the fields they exercise.
+pub fn sample_meta() -> FlowGraphMeta {
+	FlowGraphMeta {
+		version_hash: [0; 32],
+		compiled_at: SystemTime::UNIX_EPOCH,
+		source_files: vec![],
+		feature_set: &[],
+		short_circuit_response_entry: BTreeMap::new(),
+		listener_tls: BTreeMap::new(),
+		listener_kinds: BTreeMap::new(),
+		listener_transports: BTreeMap::new(),
+		listener_proxy_protocol: BTreeMap::new(),
+		unix_listeners: BTreeMap::new(),
+		annotations: Vec::new(),
+	}
+}
+
+/// Sink that discards every flow-log event.
+pub struct DropSink;
+
+impl FlowLogSink for DropSink {
+	fn emit(&self, _event: FlowLogEvent) {}
+}
+
+/// Sink that keeps every flow-log event for the test to inspect.
+#[derive(Default)]
+pub struct RecordingSink {
+	pub events: Mutex<Vec<FlowLogEvent>>,
+}
+
+impl FlowLogSink for RecordingSink {
+	fn emit(&self, event: FlowLogEvent) {
+		self.events.lock().push(event);
+	}
+}
+
+/// Loopback address with an OS-assigned free port; see
+/// [`crate::port::free_port`] for the accepted re-bind race.
+pub fn pick_port() -> SocketAddr {
+	SocketAddr::from(([127, 0, 0, 1], crate::port::free_port()))
+}
@@ -23,6 +23,7 @@ pub mod flow;
 pub mod h3;
 #[cfg(feature = "ocsp")]
 pub use ocsp_mock_responder as ocsp;
+pub mod mmdb;
 pub mod port;
 pub mod tracing;
 pub mod vaned_fixture;
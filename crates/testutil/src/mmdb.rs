@@ -0,0 +1,144 @@
+//! Tiny MaxMind DB writer for GeoIP tests. Produces an IPv4-only,
+//! 24-bit-record database whose data records carry just the fields
+//! vane reads (`country.iso_code`, `continent.code`,
+//! `autonomous_system_number`), so tests never need a real GeoLite2
+//! download.
+//!
+//! Prefixes must not nest; an address outside every prefix is "not
+//! found".
+
+use std::net::Ipv4Addr;
+
+/// One data record. `None` fields are left out of the record map.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GeoRecord<'a> {
+	pub country: Option<&'a str>,
+	pub continent: Option<&'a str>,
+	pub asn: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+enum Slot {
+	Empty,
+	Node(usize),
+	Data(usize),
+}
+
+/// Serialise `prefixes` (network, prefix length, record) into MMDB
+/// bytes.
+pub fn build(prefixes: &[(Ipv4Addr, u8, GeoRecord<'_>)]) -> Vec<u8> {
+	let mut tree: Vec<[Slot; 2]> = vec![[Slot::Empty; 2]];
+	let mut data = Vec::new();
+	for (net, len, record) in prefixes {
+		assert!((1..=32).contains(len), "prefix length {len} out of range");
+		let offset = data.len();
+		encode_record(&mut data, record);
+		let bits = u32::from(*net);
+		let mut node = 0;
+		for i in 0..*len {
+			let bit = usize::from(bits >> (31 - i) & 1 == 1);
+			if i + 1 == *len {
+				tree[node][bit] = Slot::Data(offset);
+			} else {
+				node = match tree[node][bit] {
+					Slot::Node(next) => next,
+					Slot::Empty => {
+						tree.push([Slot::Empty; 2]);
+						tree[node][bit] = Slot::Node(tree.len() - 1);
+						tree.len() - 1
+					}
+					Slot::Data(_) => panic!("prefix {net}/{len} nests inside another"),
+				};
+			}
+		}
+	}
+
+	let node_count = tree.len();
+	let mut out = Vec::with_capacity(node_count * 6 + 16 + data.len() + 256);
+	for slots in &tree {
+		for slot in slots {
+			let value = match *slot {
+				Slot::Empty => node_count,
+				Slot::Node(n) => n,
+				Slot::Data(offset) => node_count + 16 + offset,
+			};
+			let value = u32::try_from(value).expect("tree fits 24-bit records");
+			out.extend_from_slice(&value.to_be_bytes()[1..]);
+		}
+	}
+	out.extend_from_slice(&[0; 16]);
+	out.extend_from_slice(&data);
+	out.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+	encode_metadata(&mut out, u32::try_from(node_count).expect("node count fits u32"));
+	out
+}
+
+fn encode_record(out: &mut Vec<u8>, record: &GeoRecord<'_>) {
+	let entries = [record.country.is_some(), record.continent.is_some(), record.asn.is_some()];
+	map(out, entries.iter().filter(|present| **present).count());
+	if let Some(code) = record.country {
+		string(out, "country");
+		map(out, 1);
+		string(out, "iso_code");
+		string(out, code);
+	}
+	if let Some(code) = record.continent {
+		string(out, "continent");
+		map(out, 1);
+		string(out, "code");
+		string(out, code);
+	}
+	if let Some(asn) = record.asn {
+		string(out, "autonomous_system_number");
+		uint32(out, asn);
+	}
+}
+
+fn encode_metadata(out: &mut Vec<u8>, node_count: u32) {
+	map(out, 9);
+	string(out, "binary_format_major_version");
+	uint16(out, 2);
+	string(out, "binary_format_minor_version");
+	uint16(out, 0);
+	string(out, "build_epoch");
+	// uint64 is an extended type: type bits 0, then `9 - 7`.
+	out.extend_from_slice(&[8, 2]);
+	out.extend_from_slice(&0u64.to_be_bytes());
+	string(out, "database_type");
+	string(out, "vane-test");
+	string(out, "description");
+	map(out, 0);
+	string(out, "ip_version");
+	uint16(out, 4);
+	string(out, "languages");
+	// Empty array: extended type `11 - 7`.
+	out.extend_from_slice(&[0, 4]);
+	string(out, "node_count");
+	uint32(out, node_count);
+	string(out, "record_size");
+	uint16(out, 24);
+}
+
+fn control(out: &mut Vec<u8>, ty: u8, size: usize) {
+	let size = u8::try_from(size).ok().filter(|s| *s < 29).expect("test fixture sizes stay < 29");
+	out.push(ty << 5 | size);
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+	control(out, 2, s.len());
+	out.extend_from_slice(s.as_bytes());
+}
+
+fn map(out: &mut Vec<u8>, entries: usize) {
+	control(out, 7, entries);
+}
+
+fn uint16(out: &mut Vec<u8>, v: u16) {
+	control(out, 5, 2);
+	out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn uint32(out: &mut Vec<u8>, v: u32) {
+	control(out, 6, 4);
+	out.extend_from_slice(&v.to_be_bytes());
+}
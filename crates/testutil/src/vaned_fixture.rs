@@ -137,6 +137,22 @@ impl VanedFixture {
 		]);
 	}
 
+	/// Author a document-root rule via `vane add file-server`.
+	pub fn add_file_server(&self, name: &str, listen: &str, root: &Path) {
+		self.run_vane(&[
+			"add",
+			"file-server",
+			"--dir",
+			self.dir_str(),
+			"--name",
+			name,
+			"--listen",
+			listen,
+			"--root",
+			&root.to_string_lossy(),
+		]);
+	}
+
 	/// Spawn `vaned -c <dir>` with an isolated mgmt socket and the HTTP
 	/// mgmt transport disabled (so parallel daemons don't fight over the
 	/// fixed mgmt port). Returns a guard that SIGKILL-tears-down on Drop.
@@ -20,6 +20,7 @@ const CONN_PATHS: &[&str] = &[
 	"conn.accept_unix_ms",
 	"conn.tls.version",
 	"conn.tls.sni",
+	"conn.tls.ja4",
 	"conn.tls.peer_cert",
 	"conn.tls.peer_cert.present",
 	"conn.tls.peer_cert.subject_cn",
@@ -28,6 +29,14 @@ const CONN_PATHS: &[&str] = &[
 	"conn.tls.peer_cert.spki_sha256",
 	"conn.tls.peer_cert.issuer_cn",
 	"conn.tls.peer_cert.serial",
+	"conn.proxy.present",
+	"conn.proxy.peer_ip",
+	"conn.proxy.authority",
+	"conn.proxy.unique_id",
+	"conn.proxy.ssl.version",
+	"conn.proxy.ssl.cn",
+	"conn.proxy.ssl.cipher",
+	"conn.proxy.ssl.verified",
 ];
 
 /// Request / response-level paths mirrored from
@@ -36,13 +45,18 @@ const CONN_PATHS: &[&str] = &[
 /// them, and dispatch logs a warn-once per `(module_id, path)` when
 /// one is declared.
 const REQ_RESP_STATIC_PATHS: &[&str] =
-	&["http.method", "http.uri.path", "http.uri.query", "http.body"];
+	&["http.method", "http.version", "http.uri.host", "http.uri.path", "http.uri.query", "http.body"];
+
+/// Dynamic request paths, `<prefix><name>`.
+const REQ_DYNAMIC_PREFIXES: &[&str] = &["http.header.", "http.cookie.", "http.query."];
 
 /// Validate a single `inspects` path string.
 ///
 /// Static membership for fixed paths above; the dynamic
-/// `http.header.<name>` form is accepted with a syntactic check on
-/// the suffix (RFC 7230 `token`). Unknown paths return `false` so the
+/// `http.header.<name>` and `http.cookie.<name>` forms are accepted
+/// with a syntactic check on the suffix (RFC 7230 `token`), and
+/// `http.query.<name>` with any non-empty name free of whitespace and
+/// query delimiters. Unknown paths return `false` so the
 /// caller can reject the plugin at load time — the alternative is a
 /// silently-empty `context` entry the plugin author expected
 /// populated.
@@ -54,6 +68,12 @@ pub fn validate_inspects_path(path: &str) -> bool {
 	if let Some(rest) = path.strip_prefix("http.header.") {
 		return is_token(rest);
 	}
+	if let Some(rest) = path.strip_prefix("http.cookie.") {
+		return is_token(rest);
+	}
+	if let Some(rest) = path.strip_prefix("http.query.") {
+		return is_query_name(rest);
+	}
 	false
 }
 
@@ -62,7 +82,8 @@ pub fn validate_inspects_path(path: &str) -> bool {
 /// only) and emit a warn-once.
 #[must_use]
 pub fn is_request_or_response_path(path: &str) -> bool {
-	REQ_RESP_STATIC_PATHS.contains(&path) || path.starts_with("http.header.")
+	REQ_RESP_STATIC_PATHS.contains(&path)
+		|| REQ_DYNAMIC_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
 }
 
 /// RFC 7230 `token`: one or more `tchar`, where `tchar` is alphanumeric
@@ -78,6 +99,13 @@ fn is_token(s: &str) -> bool {
 		})
 }
 
+/// Query parameter names are matched after percent-decoding, so only
+/// the characters that would split the query string are excluded.
+fn is_query_name(s: &str) -> bool {
+	!s.is_empty()
+		&& !s.bytes().any(|b| b.is_ascii_control() || matches!(b, b' ' | b'&' | b'=' | b'#'))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -109,6 +137,19 @@ mod tests {
 		assert!(!validate_inspects_path("http.header."));
 	}
 
+	#[test]
+	fn cookie_and_query_paths_validate_by_name() {
+		for path in ["http.cookie.session", "http.cookie.JSESSIONID", "http.query.version"] {
+			assert!(validate_inspects_path(path), "path rejected: {path}");
+			assert!(is_request_or_response_path(path), "not a request path: {path}");
+		}
+		for bad in
+			["http.cookie.", "http.cookie.a;b", "http.query.", "http.query.a&b", "http.query.a b"]
+		{
+			assert!(!validate_inspects_path(bad), "path wrongly accepted: {bad}");
+		}
+	}
+
 	#[test]
 	fn dynamic_header_path_rejects_non_token_chars() {
 		// Space, comma, slash, colon, semicolon, control chars are not
@@ -127,7 +168,7 @@ mod tests {
 			"conn.unknown",
 			"conn.tls.peer_cert.unknown",
 			"conn..peer_ip",
-			"http.cookie.session_id",
+			"http.cookies",
 			"http.host",
 			"http.scheme",
 			"http.path",
@@ -32,9 +32,9 @@ use wasmtime::{Config, Engine, PoolingAllocationConfig, Store};
 
 use vane_core::middleware::MiddlewareKind;
 use vane_core::{
-	BytesView, ContextEntry, ContextValue, Error, Header, HttpFetchBackend, HttpFetchError,
-	HttpFetchLimits, HttpFetchRequest, HttpFetchResponse, L4BytesDecision, L4BytesInput,
-	L4PeekDecision, L4PeekInput, L7RequestDecision, L7RequestInput, L7ResponseDecision,
+	BanRequest, BytesView, ContextEntry, ContextValue, Error, Header, HttpFetchBackend,
+	HttpFetchError, HttpFetchLimits, HttpFetchRequest, HttpFetchResponse, L4BytesDecision,
+	L4BytesInput, L4PeekDecision, L4PeekInput, L7RequestDecision, L7RequestInput, L7ResponseDecision,
 	L7ResponseInput, ModifiedResponse, ModuleId, PluginError, PluginExport, PluginHttpPolicy,
 	PluginMetadata, SynthResponse, WasmPoolStats, WasmPoolSummary, WasmRuntime,
 };
@@ -115,6 +115,10 @@ struct HostState {
 	/// `Arc`.
 	#[allow(dead_code, reason = "consumed by metric host fn in the next commit")]
 	cardinality: Arc<CardinalityRegistry>,
+	/// Last `vane:host/security.ban` call of this invocation. Read back
+	/// by `invoke_l7_request` once `handle` returns; every other kind
+	/// drops it with the store.
+	ban: Option<BanRequest>,
 	/// Captures the last value returned by `get_args` during an invocation.
 	/// Used by `StatefulPoolHandle::last_args_received` in tests to verify
 	/// that the fixture plugin actually called host.get-args and received the
@@ -141,15 +145,27 @@ impl HostState {
 			export_name,
 			policy,
 			cardinality,
+			ban: None,
 			#[cfg(test)]
 			args_received: None,
 		}
 	}
+
+	fn record_ban(&mut self, duration_secs: Option<u32>, reason: String) {
+		self.ban = Some(BanRequest { duration_secs, reason });
+	}
 }
 
 // vane:plugin/types has no functions; the generated Host trait is empty.
 impl vane::plugin::types::Host for HostState {}
 
+impl vane::host::security::Host for HostState {
+	async fn ban(&mut self, duration_secs: Option<u32>, reason: String) -> wasmtime::Result<()> {
+		self.record_ban(duration_secs, reason);
+		Ok(())
+	}
+}
+
 // Implement the host import trait generated by `bindgen!`.
 // Native async fn is used (RPITIT, no #[async_trait]).
 impl vane::host::host::Host for HostState {
@@ -241,6 +257,13 @@ impl vane::host::host::Host for HostState {
 
 impl invoke_l4peek::vane::plugin::types::Host for HostState {}
 
+impl invoke_l4peek::vane::host::security::Host for HostState {
+	async fn ban(&mut self, duration_secs: Option<u32>, reason: String) -> wasmtime::Result<()> {
+		self.record_ban(duration_secs, reason);
+		Ok(())
+	}
+}
+
 impl invoke_l4peek::vane::host::host::Host for HostState {
 	async fn get_args(&mut self) -> wasmtime::Result<String> {
 		let result = self.args.clone();
@@ -335,6 +358,13 @@ impl invoke_l4peek::vane::host::host::Host for HostState {
 
 impl invoke_l4bytes::vane::plugin::types::Host for HostState {}
 
+impl invoke_l4bytes::vane::host::security::Host for HostState {
+	async fn ban(&mut self, duration_secs: Option<u32>, reason: String) -> wasmtime::Result<()> {
+		self.record_ban(duration_secs, reason);
+		Ok(())
+	}
+}
+
 impl invoke_l4bytes::vane::host::host::Host for HostState {
 	async fn get_args(&mut self) -> wasmtime::Result<String> {
 		let result = self.args.clone();
@@ -429,6 +459,13 @@ impl invoke_l4bytes::vane::host::host::Host for HostState {
 
 impl invoke_l7request::vane::plugin::types::Host for HostState {}
 
+impl invoke_l7request::vane::host::security::Host for HostState {
+	async fn ban(&mut self, duration_secs: Option<u32>, reason: String) -> wasmtime::Result<()> {
+		self.record_ban(duration_secs, reason);
+		Ok(())
+	}
+}
+
 impl invoke_l7request::vane::host::host::Host for HostState {
 	async fn get_args(&mut self) -> wasmtime::Result<String> {
 		let result = self.args.clone();
@@ -523,6 +560,13 @@ impl invoke_l7request::vane::host::host::Host for HostState {
 
 impl invoke_l7response::vane::plugin::types::Host for HostState {}
 
+impl invoke_l7response::vane::host::security::Host for HostState {
+	async fn ban(&mut self, duration_secs: Option<u32>, reason: String) -> wasmtime::Result<()> {
+		self.record_ban(duration_secs, reason);
+		Ok(())
+	}
+}
+
 impl invoke_l7response::vane::host::host::Host for HostState {
 	async fn get_args(&mut self) -> wasmtime::Result<String> {
 		let result = self.args.clone();
@@ -2256,7 +2300,11 @@ impl WasmRuntime for WasmtimeRuntime {
 			plugin.vane_plugin_handler_l7_request().call_handle(&mut store, export_name, &wit_input);
 
 		match result {
-			Ok(Ok(d)) => lift_l7request_decision(d),
+			// A `security.ban` call overrides the returned decision.
+			Ok(Ok(d)) => match store.data_mut().ban.take() {
+				Some(ban) => Ok(L7RequestDecision::Ban(ban)),
+				None => lift_l7request_decision(d),
+			},
 			Ok(Err(pe)) => Err(lift_plugin_error_l7request(pe)?),
 			Err(e) => Err(PluginError::trap(e.to_string())),
 		}